@@ -0,0 +1,80 @@
+// Cell material (see src/cell_material.rs).
+//
+// Draws one cell on a unit quad: the organic outline of a live cell, or the shape of a
+// multi-state cell, with soft edges, a per-cell living pulse and a newborn tint that
+// settles as the cell ages. Each cell's variation is hashed from its grid position, and
+// time comes from the view globals, so nothing here changes on the CPU from frame to frame.
+
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+#import bevy_sprite::mesh2d_view_bindings::globals
+
+@group(2) @binding(0) var<uniform> color: vec4<f32>;
+@group(2) @binding(1) var<uniform> glow: vec4<f32>;
+// x: maturity (0 newborn .. 1 mature), y: shape, z: cell size, w: animation speed
+@group(2) @binding(2) var<uniform> params: vec4<f32>;
+
+const TAU: f32 = 6.28318530718;
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+// Smooth value noise
+fn noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    let a = hash(i);
+    let b = hash(i + vec2<f32>(1.0, 0.0));
+    let c = hash(i + vec2<f32>(0.0, 1.0));
+    let d = hash(i + vec2<f32>(1.0, 1.0));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}
+
+// Soft edge so shapes don't alias when scaled down
+fn edge(distance: f32, limit: f32) -> f32 {
+    return clamp((limit - distance) / 0.08, 0.0, 1.0);
+}
+
+// Coverage of the point `d` (-1..1 across the quad); shapes match `MaterialShape`
+fn coverage(shape: u32, d: vec2<f32>, variation: f32, time: f32) -> f32 {
+    let radius = length(d);
+    switch shape {
+        case 1u: { return edge(max(abs(d.x), abs(d.y)), 0.85) * 0.85; }
+        case 2u: { return edge(radius, 0.9) * max(1.0 - radius * 0.3, 0.0); }
+        case 3u: { return edge(radius, 0.85) * edge(0.5, radius); }
+        case 4u: { return edge(radius, 0.55) * 0.7; }
+        case 5u: { return edge(radius, 0.85); }
+        case 6u: { return edge(abs(d.x) + abs(d.y), 0.95); }
+        default: {
+            // Organic blob: the rim wobbles around the cell and drifts with slow noise
+            let angle = atan2(d.y, d.x);
+            let lobes = 2.0 + floor(variation * 3.0);
+            let wobble = sin(angle * lobes + time * (1.0 + variation)) * 0.08;
+            let drift = (noise(d * 2.5 + vec2<f32>(variation * 17.0, time * 0.3)) - 0.5) * 0.3 * (0.5 + variation);
+            let rim = 0.9 * (1.0 + wobble + drift);
+            let inner_glow = 1.0 - sqrt(min(radius / rim, 1.0));
+            return clamp((rim - radius) / (rim * 0.2), 0.0, 1.0) * (0.8 + inner_glow * 0.2);
+        }
+    }
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let d = mesh.uv * 2.0 - 1.0;
+    let cell = floor(mesh.world_position.xy / params.z + 0.5);
+    let variation = hash(cell);
+    let time = globals.time * params.w;
+
+    let alpha = coverage(u32(params.y + 0.5), d, variation, time) * color.a;
+    if alpha <= 0.0 {
+        discard;
+    }
+    // Living pulse, out of step between neighbours, and a little darker towards the rim
+    let pulse = 1.0 + 0.1 * sin(time * 3.0 + variation * TAU);
+    let shade = 1.0 - min(length(d), 1.0) * 0.3;
+    // Newborns start in the glow tint and settle into their own colour as they age
+    let fresh = 1.0 - smoothstep(0.0, 0.35, params.x);
+    let rgb = mix(color.rgb, glow.rgb, fresh * 0.6) * pulse * shade;
+    return vec4<f32>(rgb, alpha);
+}
@@ -0,0 +1,266 @@
+//! Historical activity heat.
+//!
+//! While enabled, every birth adds heat to its cell and the heat halves every
+//! `ActivityHeat::half_life` generations, so the layer keeps a fading memory of where the
+//! pattern has been busy: the wake of a spaceship, the churn of a soup, the track of a
+//! WireWorld signal. Births come from the grid's change log (see `GridChanges`). The heat is
+//! drawn as a texture behind everything else in the theme's `glow` colour, one texel per
+//! block of cells like the density heatmap. E toggles it with the default bindings.
+
+use std::collections::HashMap;
+use bevy::color::ColorToPacked;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::window::PrimaryWindow;
+use crate::camera::GameCamera;
+use crate::cell_renderer::{collect_grid_changes, CellRenderConfig, GridChanges};
+use crate::heatmap::{view_region, BlockRegion};
+use crate::infinite_grid::InfiniteGrid;
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::{GameState, InGame};
+use crate::theme::Theme;
+use crate::ui::toast;
+use crate::tr;
+use crate::CellState;
+
+/// Heat below which a cell is forgotten
+const MIN_HEAT: f32 = 0.05;
+
+/// Mean heat per cell at which a texel is about two thirds of `MAX_ALPHA`
+const HEAT_SCALE: f32 = 4.0;
+
+/// Opacity of the hottest texels, low enough to stay a background
+const MAX_ALPHA: f32 = 0.55;
+
+/// Seconds between texture rebuilds while the heat is changing
+const REFRESH_SECS: f32 = 0.1;
+
+/// Behind the density heatmap (z = -1) and extra layers
+const ACTIVITY_Z: f32 = -2.0;
+
+#[derive(Resource)]
+pub struct ActivityHeat {
+    pub enabled: bool,
+    /// Generations for a cell's heat to halve
+    pub half_life: f32,
+    heat: HashMap<(i32, i32), f32>,
+    generation: u64,
+    image: Option<Handle<Image>>,
+    region: Option<BlockRegion>,
+    refresh: Timer,
+    /// Heat changed since the texture was last built
+    dirty: bool,
+}
+
+impl Default for ActivityHeat {
+    fn default() -> Self {
+        Self::new(false, 200.0)
+    }
+}
+
+impl ActivityHeat {
+    pub fn new(enabled: bool, half_life: f32) -> Self {
+        Self {
+            enabled,
+            half_life: half_life.max(1.0),
+            heat: HashMap::new(),
+            generation: 0,
+            image: None,
+            region: None,
+            refresh: Timer::from_seconds(REFRESH_SECS, TimerMode::Repeating),
+            dirty: true,
+        }
+    }
+
+    /// Add one unit of heat per birth
+    pub fn record_births(&mut self, births: impl IntoIterator<Item = (i32, i32)>) {
+        for cell in births {
+            *self.heat.entry(cell).or_insert(0.0) += 1.0;
+            self.dirty = true;
+        }
+    }
+
+    /// Cool every cell by `generations` worth of decay, dropping those gone cold
+    pub fn decay(&mut self, generations: u64) {
+        if generations == 0 || self.heat.is_empty() {
+            return;
+        }
+        let factor = 0.5f32.powf(generations as f32 / self.half_life);
+        self.heat.retain(|_, heat| {
+            *heat *= factor;
+            *heat >= MIN_HEAT
+        });
+        self.dirty = true;
+    }
+
+    pub fn reset(&mut self) {
+        self.heat.clear();
+        self.dirty = true;
+    }
+
+    pub fn heat(&self, cell: (i32, i32)) -> f32 {
+        self.heat.get(&cell).copied().unwrap_or(0.0)
+    }
+
+    /// Total heat per block, row-major from the top-left block like `BlockRegion::count`
+    pub fn block_heat(&self, region: &BlockRegion) -> Vec<f32> {
+        let mut totals = vec![0.0; (region.width * region.height) as usize];
+        let (min_x, min_y) = (region.min_bx * region.block, region.min_by * region.block);
+        let (width, height) = (region.width as i32 * region.block, region.height as i32 * region.block);
+        for (&(x, y), &heat) in &self.heat {
+            let (dx, dy) = (x - min_x, y - min_y);
+            if dx < 0 || dy < 0 || dx >= width || dy >= height {
+                continue;
+            }
+            let column = (dx / region.block) as u32;
+            let row = region.height - 1 - (dy / region.block) as u32;
+            totals[(row * region.width + column) as usize] += heat;
+        }
+        totals
+    }
+}
+
+/// Texels in `colour`, more opaque the hotter the block; cold blocks are transparent
+fn heat_pixels(totals: &[f32], block: i32, colour: Color) -> Vec<u8> {
+    let [r, g, b, _] = colour.to_srgba().to_u8_array();
+    let cells = (block as f32).powi(2);
+    let mut pixels = Vec::with_capacity(totals.len() * 4);
+    for &total in totals {
+        let alpha = MAX_ALPHA * (1.0 - (-total / cells / HEAT_SCALE).exp());
+        pixels.extend_from_slice(&[r, g, b, (alpha * 255.0) as u8]);
+    }
+    pixels
+}
+
+#[derive(Component)]
+pub struct ActivityLayer;
+
+pub struct ActivityPlugin;
+
+impl Plugin for ActivityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActivityHeat>()
+            .add_systems(
+                Update,
+                (activity_controls, track_activity.after(collect_grid_changes), render_activity)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(InGame), despawn_activity);
+    }
+}
+
+fn activity_controls(keyboard_input: Res<ButtonInput<KeyCode>>, keys: Res<Keybindings>, grid: Res<InfiniteGrid>, mut activity: ResMut<ActivityHeat>) {
+    if keys.just_pressed(&keyboard_input, Action::ActivityHeat) {
+        activity.enabled = !activity.enabled;
+        activity.reset();
+        activity.generation = grid.generation();
+        toast(tr!("🔥 Activity heat {}", if activity.enabled { "on" } else { "off" }));
+    }
+}
+
+fn track_activity(grid: Res<InfiniteGrid>, changes: Res<GridChanges>, mut activity: ResMut<ActivityHeat>) {
+    if !activity.enabled {
+        return;
+    }
+    if grid.generation() < activity.generation {
+        // Cleared or a session was loaded: start the history over
+        activity.reset();
+    } else {
+        let generations = grid.generation() - activity.generation;
+        activity.decay(generations);
+    }
+    activity.generation = grid.generation();
+    if let Some(cells) = &changes.cells {
+        activity.record_births(cells.iter().filter(|&&(_, _, state)| state != CellState::Dead).map(|&(x, y, _)| (x, y)));
+    }
+}
+
+/// Keep the heat texture covering the view, rebuilt at most every `REFRESH_SECS`
+fn render_activity(
+    mut commands: Commands,
+    (config, theme, time): (Res<CellRenderConfig>, Res<Theme>, Res<Time>),
+    camera_query: Query<(&Transform, &GameCamera), Without<ActivityLayer>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut layer_query: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<ActivityLayer>>,
+    mut activity: ResMut<ActivityHeat>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !activity.enabled {
+        for (_, _, mut visibility) in layer_query.iter_mut() {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+        return;
+    }
+    let (Ok((camera_transform, camera)), Ok(window)) = (camera_query.get_single(), windows.get_single()) else { return };
+    let region = view_region(camera_transform, camera, window, config.cell_size);
+
+    let due = activity.refresh.tick(time.delta()).finished() && activity.dirty;
+    if due || activity.region != Some(region) || activity.image.is_none() || theme.is_changed() {
+        let pixels = heat_pixels(&activity.block_heat(&region), region.block, theme.glow.0);
+        let size = Extent3d { width: region.width, height: region.height, depth_or_array_layers: 1 };
+        let existing = activity.image.as_ref().and_then(|handle| images.get_mut(handle));
+        match existing {
+            Some(image) if image.texture_descriptor.size == size => image.data = pixels,
+            _ => {
+                let mut image = Image::new(size, TextureDimension::D2, pixels, TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::default());
+                image.sampler = ImageSampler::linear();
+                activity.image = Some(images.add(image));
+            }
+        }
+        activity.region = Some(region);
+        activity.dirty = false;
+    }
+    let Some(handle) = activity.image.clone() else { return };
+    let (centre, span) = region.world_rect(config.cell_size);
+    let translation = centre.extend(ACTIVITY_Z);
+
+    if let Ok((mut transform, mut sprite, mut visibility)) = layer_query.get_single_mut() {
+        transform.translation = translation;
+        if sprite.image != handle || sprite.custom_size != Some(span) {
+            sprite.image = handle;
+            sprite.custom_size = Some(span);
+        }
+        visibility.set_if_neq(Visibility::Visible);
+    } else {
+        commands.spawn((
+            Sprite { image: handle, custom_size: Some(span), ..default() },
+            Transform::from_translation(translation),
+            ActivityLayer,
+        ));
+    }
+}
+
+fn despawn_activity(mut commands: Commands, layer_query: Query<Entity, With<ActivityLayer>>, mut activity: ResMut<ActivityHeat>) {
+    for entity in layer_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    activity.reset();
+    activity.region = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn births_heat_cells_that_cool_by_half_lives() {
+        let mut activity = ActivityHeat::new(true, 10.0);
+        activity.record_births([(0, 0), (0, 0), (5, 1)]);
+        assert_eq!(activity.heat((0, 0)), 2.0);
+
+        activity.decay(10);
+        assert!((activity.heat((0, 0)) - 1.0).abs() < 1e-5);
+        assert!((activity.heat((5, 1)) - 0.5).abs() < 1e-5);
+
+        let region = BlockRegion::covering(0, 7, 0, 3, 4);
+        let totals = activity.block_heat(&region);
+        assert!((totals[0] - 1.0).abs() < 1e-5 && (totals[1] - 0.5).abs() < 1e-5);
+
+        // Long enough and everything goes cold
+        activity.decay(1_000);
+        assert_eq!(activity.heat((0, 0)), 0.0);
+    }
+}
@@ -0,0 +1,179 @@
+//! Training data for the DDSP engine.
+//!
+//! While an export runs, the audio callback mixes what it plays down to mono and sends it in
+//! fixed-size frames of `FRAME_SAMPLES` over a bounded lock-free channel to a writer thread,
+//! so it never allocates; frames the writer has no room for are dropped. The writer pairs each
+//! frame with the game's features at the time it arrives (`latest_game_features`, a buffer or
+//! so behind the sound at most). Stopping sends `None`, on which the writer saves every pair
+//! as a safetensors file:
+//!
+//! * `features` `[frames, 10]`: `GameStateFeatures::to_vector`, the decoders' inputs
+//! * `audio` `[frames, FRAME_SAMPLES]`: the sound that played meanwhile, in -1..1
+//!
+//! with the sample rate, frame size and feature names in the header's metadata. Any engine's
+//! output can be collected this way, the hybrid one included, to teach decoders to sound like
+//! it: train them offline (with DDSP's spectral loss on `audio`, say), save them under the
+//! tensor names `ddsp_engine` reads and point `ddsp_model` at the file. The synth panel's
+//! DATASET button starts and stops an export.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::thread::JoinHandle;
+use crossbeam_channel::{bounded, Receiver, Sender};
+
+use super::ddsp_engine::GameStateFeatures;
+use super::ddsp_game_analysis::latest_game_features;
+use super::hybrid_dungeon_synth::output_sample_rate;
+use super::output::PREFERRED_SAMPLE_RATE;
+use super::safetensors::{write_tensors, Tensor, TensorFileError};
+
+/// Samples of audio paired with each feature vector (about 23 ms at 44.1 kHz)
+pub const FRAME_SAMPLES: usize = 1024;
+/// Frames an export keeps, about 12 minutes at 44.1 kHz; later ones are dropped
+pub const MAX_FRAMES: usize = 30_000;
+/// Frames waiting for the writer before the callback drops new ones
+const QUEUED_FRAMES: usize = 64;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DatasetError {
+    #[error("a dataset export is already running")]
+    AlreadyExporting,
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Tensors(#[from] TensorFileError),
+}
+
+/// A finished export
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub path: PathBuf,
+    pub frames: usize,
+}
+
+/// The writer of the running export
+static SESSION: Mutex<Option<JoinHandle<Result<Dataset, DatasetError>>>> = Mutex::new(None);
+type Channel<T> = (Sender<T>, Receiver<T>);
+
+/// Frames on their way from the audio callback to the writer, copied by value so sending one
+/// doesn't allocate; `None` ends the export
+static FRAMES: LazyLock<Channel<Option<[f32; FRAME_SAMPLES]>>> = LazyLock::new(|| bounded(QUEUED_FRAMES));
+static EXPORTING: AtomicBool = AtomicBool::new(false);
+static EXPORTED_FRAMES: AtomicUsize = AtomicUsize::new(0);
+
+/// Start collecting (features, audio) pairs for the safetensors file at `path`
+pub fn start_dataset(path: &Path) -> Result<(), DatasetError> {
+    let mut session = SESSION.lock().unwrap();
+    if session.is_some() {
+        return Err(DatasetError::AlreadyExporting);
+    }
+    let file = BufWriter::new(File::create(path)?);
+    let sample_rate = output_sample_rate().unwrap_or(PREFERRED_SAMPLE_RATE);
+    // Frames the callback sent just after the last export stopped
+    while FRAMES.1.try_recv().is_ok() {}
+    let received = FRAMES.1.clone();
+    let path = path.to_path_buf();
+    let writer = std::thread::spawn(move || {
+        let (mut features, mut audio) = (Vec::new(), Vec::new());
+        let mut frames = 0;
+        for frame in received.iter().map_while(|frame| frame) {
+            if frames == MAX_FRAMES {
+                continue;
+            }
+            features.extend(latest_game_features().unwrap_or_default().to_vector());
+            audio.extend_from_slice(&frame);
+            frames += 1;
+            EXPORTED_FRAMES.store(frames, Ordering::Relaxed);
+        }
+        let tensors = [
+            ("features", Tensor { shape: vec![frames, GameStateFeatures::INPUTS], data: features }),
+            ("audio", Tensor { shape: vec![frames, FRAME_SAMPLES], data: audio }),
+        ];
+        let (sample_rate, frame_samples) = (sample_rate.to_string(), FRAME_SAMPLES.to_string());
+        let names = GameStateFeatures::NAMES.join(",");
+        let metadata = [("sample_rate", sample_rate.as_str()), ("frame_samples", frame_samples.as_str()), ("features", names.as_str())];
+        write_tensors(file, &tensors, &metadata)?;
+        Ok(Dataset { path, frames })
+    });
+    EXPORTED_FRAMES.store(0, Ordering::Relaxed);
+    *session = Some(writer);
+    EXPORTING.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stop the export, if one is running; the returned writer saves the file
+pub fn stop_dataset() -> Option<JoinHandle<Result<Dataset, DatasetError>>> {
+    EXPORTING.store(false, Ordering::Relaxed);
+    let writer = SESSION.lock().unwrap().take()?;
+    // Ends the writer's loop, once it has taken the frames queued before it
+    let _ = FRAMES.0.send(None);
+    Some(writer)
+}
+
+pub fn is_exporting_dataset() -> bool {
+    EXPORTING.load(Ordering::Relaxed)
+}
+
+/// Pairs the running export holds so far
+pub fn exported_frames() -> usize {
+    EXPORTED_FRAMES.load(Ordering::Relaxed)
+}
+
+/// The audio thread's end of an export: collects the output into mono frames
+pub struct Tap {
+    frame: [f32; FRAME_SAMPLES],
+    len: usize,
+}
+
+impl Default for Tap {
+    fn default() -> Self {
+        Self { frame: [0.0; FRAME_SAMPLES], len: 0 }
+    }
+}
+
+impl Tap {
+    /// Add one stereo output frame to the export, if one is running
+    pub fn push(&mut self, left: f32, right: f32) {
+        if !is_exporting_dataset() {
+            self.len = 0;
+            return;
+        }
+        self.frame[self.len] = (left + right) * 0.5;
+        self.len += 1;
+        if self.len == FRAME_SAMPLES {
+            let _ = FRAMES.0.try_send(Some(self.frame));
+            self.len = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::safetensors::read_tensors;
+
+    #[test]
+    fn exported_frames_are_saved_with_their_features() {
+        let path = std::env::temp_dir().join(format!("gameofdeath-dataset-{}.safetensors", std::process::id()));
+        let mut tap = Tap::default();
+        tap.push(1.0, 1.0);
+        start_dataset(&path).unwrap();
+        assert!(matches!(start_dataset(&path), Err(DatasetError::AlreadyExporting)));
+        // Two whole frames, then part of one, which is left out
+        for i in 0..FRAME_SAMPLES * 2 + 100 {
+            tap.push(0.25, if i % 2 == 0 { 0.75 } else { 0.25 });
+        }
+        let dataset = stop_dataset().unwrap().join().unwrap().unwrap();
+        assert!(!is_exporting_dataset());
+        assert_eq!(dataset.frames, 2);
+
+        let tensors = read_tensors(&path).unwrap();
+        assert_eq!(tensors["features"].shape, vec![2, GameStateFeatures::INPUTS]);
+        assert_eq!(tensors["audio"].shape, vec![2, FRAME_SAMPLES]);
+        assert_eq!(&tensors["audio"].data[..2], &[0.5, 0.25]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
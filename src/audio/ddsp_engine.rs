@@ -1,6 +1,35 @@
+//! DDSP engine.
+//!
+//! Two small neural decoders turn the game's features (`GameStateFeatures`) into the
+//! amplitudes of a bank of harmonics and the level of a noise band, the way DDSP does, and
+//! classic DSP (oscillators, noise, a convolution reverb) turns those into sound. The
+//! decoders run once every `CONTROL_PERIOD` samples rather than for each one.
+//!
+//! Pretrained weights come from the safetensors file named by `ddsp_model` in the config,
+//! loaded once at startup with `load_ddsp_model`. Each decoder is a two-layer MLP, a tanh
+//! hidden layer and a sigmoid output, stored as four tensors in PyTorch's `nn.Linear` layout:
+//!
+//! * `harmonic.w1` `[hidden, 10]`, `harmonic.b1` `[hidden]`
+//! * `harmonic.w2` `[harmonics, hidden]`, `harmonic.b2` `[harmonics]`
+//! * `noise.w1` `[hidden, 10]`, `noise.b1` `[hidden]`
+//! * `noise.w2` `[outputs, hidden]`, `noise.b2` `[outputs]`
+//!
+//! The 10 inputs are `GameStateFeatures::to_vector`; the number of harmonics and the hidden
+//! sizes are up to the model. Without a model the engine plays untrained decoders with random
+//! weights, nudged so they always make some sound. The engine plays through the hybrid
+//! engine's output in its place (see `hybrid_dungeon_synth::init_ddsp_engine`).
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
 use std::f32::consts::PI;
 
+use super::safetensors::{read_tensors, Tensor, TensorFileError};
+
+/// Samples between runs of the decoders
+pub const CONTROL_PERIOD: usize = 64;
+
 // Extension trait for f32 to add sigmoid function
 trait SigmoidExt {
     fn sigmoid(self) -> Self;
@@ -12,8 +41,31 @@ impl SigmoidExt for f32 {
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum DdspModelError {
+    #[error("{0}")]
+    File(#[from] TensorFileError),
+    #[error("no tensor {0}")]
+    Missing(String),
+    #[error("tensor {name} is {found:?}, expected {expected}")]
+    Shape { name: String, found: Vec<usize>, expected: String },
+}
+
+/// The tensor `name`, if its shape is `expected` (`None` standing for any size)
+fn shaped_tensor<'a>(tensors: &'a HashMap<String, Tensor>, name: &str, expected: &[Option<usize>]) -> Result<&'a Tensor, DdspModelError> {
+    let tensor = tensors.get(name).ok_or_else(|| DdspModelError::Missing(name.to_string()))?;
+    let fits = tensor.shape.len() == expected.len()
+        && tensor.shape.iter().zip(expected).all(|(&size, want)| size > 0 && want.is_none_or(|want| want == size));
+    if !fits {
+        let dims: Vec<String> = expected.iter().map(|want| want.map_or("n".to_string(), |want| want.to_string())).collect();
+        return Err(DdspModelError::Shape { name: name.to_string(), found: tensor.shape.clone(), expected: format!("[{}]", dims.join(", ")) });
+    }
+    Ok(tensor)
+}
+
 /// Lightweight neural network for harmonic generation
 /// This is a simple 2-layer MLP that can be loaded from pretrained weights
+#[derive(Clone)]
 pub struct HarmonicDecoder {
     input_size: usize,
     hidden_size: usize,
@@ -57,35 +109,65 @@ impl HarmonicDecoder {
         }
     }
     
+    /// The decoder stored under `prefix` (`harmonic` or `noise`) in a model's tensors
+    pub fn from_tensors(tensors: &HashMap<String, Tensor>, prefix: &str, input_size: usize) -> Result<Self, DdspModelError> {
+        let get = |suffix: &str, expected: &[Option<usize>]| shaped_tensor(tensors, &format!("{}.{}", prefix, suffix), expected);
+        let w1 = get("w1", &[None, Some(input_size)])?;
+        let hidden_size = w1.shape[0];
+        let b1 = get("b1", &[Some(hidden_size)])?;
+        let w2 = get("w2", &[None, Some(hidden_size)])?;
+        let output_size = w2.shape[0];
+        let b2 = get("b2", &[Some(output_size)])?;
+        Ok(Self {
+            input_size,
+            hidden_size,
+            output_size,
+            weights1: w1.data.chunks(input_size).map(<[f32]>::to_vec).collect(),
+            bias1: b1.data.clone(),
+            weights2: w2.data.chunks(hidden_size).map(<[f32]>::to_vec).collect(),
+            bias2: b2.data.clone(),
+        })
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.output_size
+    }
+
+    pub fn hidden_size(&self) -> usize {
+        self.hidden_size
+    }
+
     /// Forward pass through the network
     pub fn forward(&self, input: &[f32]) -> Vec<f32> {
+        let mut hidden = vec![0.0; self.hidden_size];
+        let mut output = vec![0.0; self.output_size];
+        self.forward_into(input, &mut hidden, &mut output);
+        output
+    }
+
+    /// Forward pass into buffers the caller keeps, so the audio thread doesn't allocate;
+    /// `hidden` needs at least `hidden_size` values and `output` `output_size`
+    pub fn forward_into(&self, input: &[f32], hidden: &mut [f32], output: &mut [f32]) {
         assert_eq!(input.len(), self.input_size);
-        
+        let hidden = &mut hidden[..self.hidden_size];
+        let output = &mut output[..self.output_size];
+
         // Hidden layer
-        let mut hidden = vec![0.0; self.hidden_size];
-        for i in 0..self.hidden_size {
-            for j in 0..self.input_size {
-                hidden[i] += self.weights1[i][j] * input[j];
-            }
-            hidden[i] += self.bias1[i];
-            hidden[i] = hidden[i].tanh(); // Activation function
+        for ((value, weights), bias) in hidden.iter_mut().zip(&self.weights1).zip(&self.bias1) {
+            let sum: f32 = weights.iter().zip(input).map(|(w, x)| w * x).sum();
+            *value = (sum + bias).tanh(); // Activation function
         }
-        
+
         // Output layer
-        let mut output = vec![0.0; self.output_size];
-        for i in 0..self.output_size {
-            for j in 0..self.hidden_size {
-                output[i] += self.weights2[i][j] * hidden[j];
-            }
-            output[i] += self.bias2[i];
-            output[i] = output[i].sigmoid(); // Normalize to [0,1]
+        for ((value, weights), bias) in output.iter_mut().zip(&self.weights2).zip(&self.bias2) {
+            let sum: f32 = weights.iter().zip(hidden.iter()).map(|(w, h)| w * h).sum();
+            *value = (sum + bias).sigmoid(); // Normalize to [0,1]
         }
-        
-        output
     }
 }
 
 /// Similar lightweight network for noise generation
+#[derive(Clone)]
 pub struct NoiseDecoder {
     decoder: HarmonicDecoder,
 }
@@ -97,9 +179,57 @@ impl NoiseDecoder {
         }
     }
     
+    pub fn from_tensors(tensors: &HashMap<String, Tensor>, input_size: usize) -> Result<Self, DdspModelError> {
+        Ok(Self { decoder: HarmonicDecoder::from_tensors(tensors, "noise", input_size)? })
+    }
+
+    pub fn output_size(&self) -> usize {
+        self.decoder.output_size()
+    }
+
+    pub fn hidden_size(&self) -> usize {
+        self.decoder.hidden_size()
+    }
+
     pub fn forward(&self, input: &[f32]) -> Vec<f32> {
         self.decoder.forward(input)
     }
+
+    pub fn forward_into(&self, input: &[f32], hidden: &mut [f32], output: &mut [f32]) {
+        self.decoder.forward_into(input, hidden, output)
+    }
+}
+
+/// The two decoders of the engine
+#[derive(Clone)]
+pub struct DdspModel {
+    pub harmonic: HarmonicDecoder,
+    pub noise: NoiseDecoder,
+    /// Whether the weights were loaded from a file rather than made up
+    pub pretrained: bool,
+}
+
+impl DdspModel {
+    /// Decoders with random weights
+    pub fn untrained() -> Self {
+        let inputs = GameStateFeatures::INPUTS;
+        Self { harmonic: HarmonicDecoder::new(inputs, 64, 32), noise: NoiseDecoder::new(inputs), pretrained: false }
+    }
+
+    /// Decoders from a model's tensors (see the module documentation for their names)
+    pub fn from_tensors(tensors: &HashMap<String, Tensor>) -> Result<Self, DdspModelError> {
+        let inputs = GameStateFeatures::INPUTS;
+        Ok(Self {
+            harmonic: HarmonicDecoder::from_tensors(tensors, "harmonic", inputs)?,
+            noise: NoiseDecoder::from_tensors(tensors, inputs)?,
+            pretrained: true,
+        })
+    }
+
+    /// Decoders from the safetensors file at `path`
+    pub fn load(path: &Path) -> Result<Self, DdspModelError> {
+        Self::from_tensors(&read_tensors(path)?)
+    }
 }
 
 /// Game state features extracted from the grid
@@ -135,9 +265,17 @@ impl Default for GameStateFeatures {
 }
 
 impl GameStateFeatures {
+    /// Length of `to_vector`, the inputs of the DDSP decoders
+    pub const INPUTS: usize = 10;
+    /// Names of the values in `to_vector`, in order
+    pub const NAMES: [&'static str; Self::INPUTS] = [
+        "population", "density", "activity", "cluster_count", "avg_cluster_size",
+        "symmetry", "chaos", "generation", "centroid_x", "centroid_y",
+    ];
+
     /// Convert to input vector for neural networks
-    pub fn to_vector(&self) -> Vec<f32> {
-        vec![
+    pub fn to_vector(&self) -> [f32; Self::INPUTS] {
+        [
             self.population,
             self.density,
             self.activity,
@@ -177,6 +315,10 @@ impl HarmonicOscillator {
         for (i, &amp) in harmonic_amps.iter().enumerate().take(self.num_harmonics) {
             let harmonic = (i + 1) as f32;
             let freq = fundamental_freq * harmonic;
+            // Harmonics above Nyquist would fold back down as noise
+            if freq >= self.sample_rate * 0.5 {
+                break;
+            }
             let phase_increment = 2.0 * PI * freq / self.sample_rate;
             
             // Generate harmonic
@@ -278,8 +420,7 @@ impl ConvolutionReverb {
 
 /// Main DDSP audio engine
 pub struct DDSPAudioEngine {
-    harmonic_decoder: HarmonicDecoder,
-    noise_decoder: NoiseDecoder,
+    model: DdspModel,
     harmonic_osc: HarmonicOscillator,
     noise_gen: NoiseGenerator,
     reverb_l: ConvolutionReverb,
@@ -287,20 +428,31 @@ pub struct DDSPAudioEngine {
     _sample_rate: f32,
     enabled: bool,
     current_features: GameStateFeatures,
+    // What the decoders made of the features, refreshed every `CONTROL_PERIOD` samples into
+    // buffers sized here once, as decoding runs on the audio thread
+    harmonic_amps: Vec<f32>,
+    noise_params: Vec<f32>,
+    hidden: Vec<f32>,
+    fundamental: f32,
+    until_decode: usize,
 }
 
 impl DDSPAudioEngine {
-    pub fn new(sample_rate: f32) -> Self {
+    pub fn new(sample_rate: f32, model: DdspModel) -> Self {
         Self {
-            harmonic_decoder: HarmonicDecoder::new(GameStateFeatures::default().to_vector().len(), 64, 32),
-            noise_decoder: NoiseDecoder::new(GameStateFeatures::default().to_vector().len()),
-            harmonic_osc: HarmonicOscillator::new(sample_rate, 32),
+            harmonic_osc: HarmonicOscillator::new(sample_rate, model.harmonic.output_size()),
             noise_gen: NoiseGenerator::new(sample_rate),
             reverb_l: ConvolutionReverb::new(sample_rate),
             reverb_r: ConvolutionReverb::new(sample_rate),
             _sample_rate: sample_rate,
             enabled: true, // Start enabled by default
             current_features: GameStateFeatures::default(),
+            harmonic_amps: vec![0.0; model.harmonic.output_size()],
+            noise_params: vec![0.0; model.noise.output_size()],
+            hidden: vec![0.0; model.harmonic.hidden_size().max(model.noise.hidden_size())],
+            fundamental: 220.0,
+            until_decode: 0,
+            model,
         }
     }
     
@@ -308,42 +460,61 @@ impl DDSPAudioEngine {
     pub fn update_features(&mut self, features: GameStateFeatures) {
         self.current_features = features;
     }
+
+    /// Run the decoders on the current features
+    fn decode(&mut self) {
+        let mut input = self.current_features.to_vector();
+        let untrained = !self.model.pretrained;
+
+        // Ensure minimum activity for audible sound even with empty grid; a trained model
+        // knows what to make of silence
+        if untrained {
+            input[0] = input[0].max(0.05); // Minimum population
+            input[1] = input[1].max(0.02); // Minimum density
+            input[2] = input[2].max(0.01); // Minimum activity
+        }
+
+        // Get harmonic amplitudes from neural network
+        self.model.harmonic.forward_into(&input, &mut self.hidden, &mut self.harmonic_amps);
+        if untrained {
+            // Boost first few harmonics to ensure audible output
+            for amp in self.harmonic_amps.iter_mut().take(4) {
+                *amp = amp.max(0.3);
+            }
+        }
+        // Shared out like DDSP's harmonic distribution, so more harmonics aren't louder
+        let total: f32 = self.harmonic_amps.iter().sum();
+        if total > 1.0 {
+            for amp in &mut self.harmonic_amps {
+                *amp /= total;
+            }
+        }
+
+        // Get noise parameters from neural network
+        self.model.noise.forward_into(&input, &mut self.hidden, &mut self.noise_params);
+
+        // Calculate fundamental frequency based on game state (always audible)
+        self.fundamental = 220.0 + self.current_features.density * 330.0 +
+                           self.current_features.activity * 220.0 +
+                           self.current_features.population * 110.0;
+    }
     
     /// Generate stereo audio sample
     pub fn generate_stereo_sample(&mut self) -> (f32, f32) {
         if !self.enabled {
             return (0.0, 0.0);
         }
-        
-        // Convert game features to neural network input (with minimum values for audible output)
-        let mut input = self.current_features.to_vector();
-        
-        // Ensure minimum activity for audible sound even with empty grid
-        input[0] = input[0].max(0.05); // Minimum population
-        input[1] = input[1].max(0.02); // Minimum density
-        input[2] = input[2].max(0.01); // Minimum activity
-        
-        // Get harmonic amplitudes from neural network
-        let mut harmonic_amps = self.harmonic_decoder.forward(&input);
-        
-        // Boost first few harmonics to ensure audible output
-        for i in 0..4.min(harmonic_amps.len()) {
-            harmonic_amps[i] = harmonic_amps[i].max(0.3);
+        if self.until_decode == 0 {
+            self.decode();
+            self.until_decode = CONTROL_PERIOD;
         }
-        
-        // Get noise parameters from neural network
-        let noise_params = self.noise_decoder.forward(&input);
-        
-        // Calculate fundamental frequency based on game state (always audible)
-        let fundamental = 220.0 + self.current_features.density * 330.0 + 
-                         self.current_features.activity * 220.0 + 
-                         self.current_features.population * 110.0;
+        self.until_decode -= 1;
         
         // Generate harmonic component
-        let harmonic_sample = self.harmonic_osc.generate_sample(&harmonic_amps, fundamental);
+        let harmonic_sample = self.harmonic_osc.generate_sample(&self.harmonic_amps, self.fundamental);
         
         // Generate noise component
-        let noise_sample = self.noise_gen.generate_sample(&noise_params);
+        let noise_sample = self.noise_gen.generate_sample(&self.noise_params);
         
         // Mix components (boost overall volume)
         let dry_l = (harmonic_sample * 0.8 + noise_sample * 0.2) * 3.0; // Further boost volume
@@ -356,9 +527,8 @@ impl DDSPAudioEngine {
         (wet_l * 0.5, wet_r * 0.5) // Final volume control
     }
     
-    pub fn toggle(&mut self) -> bool {
-        self.enabled = !self.enabled;
-        self.enabled
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
     }
     
     pub fn is_enabled(&self) -> bool {
@@ -366,56 +536,111 @@ impl DDSPAudioEngine {
     }
 }
 
-// Global DDSP engine instance
-static DDSP_ENGINE: Mutex<Option<DDSPAudioEngine>> = Mutex::new(None);
+/// The model loaded at startup; engines play untrained decoders without one
+static MODEL: Mutex<Option<DdspModel>> = Mutex::new(None);
+/// Whether the DDSP engine is toggled on
+static DDSP_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Load the pretrained decoders in the safetensors file at `path`, for every engine started
+/// after this
+pub fn load_ddsp_model(path: &Path) -> Result<(), DdspModelError> {
+    let model = DdspModel::load(path)?;
+    *MODEL.lock().unwrap() = Some(model);
+    Ok(())
+}
+
+/// Whether engines play a pretrained model
+pub fn ddsp_model_loaded() -> bool {
+    MODEL.lock().unwrap().is_some()
+}
 
-/// Initialize DDSP audio system
+/// Start a fresh DDSP engine in the hybrid engine's place, opening the audio output the first
+/// time
 pub fn init_ddsp_audio() {
-    let sample_rate = 44100.0;
-    let engine = DDSPAudioEngine::new(sample_rate);
+    let model = MODEL.lock().unwrap().clone().unwrap_or_else(DdspModel::untrained);
+    let pretrained = model.pretrained;
+    super::hybrid_dungeon_synth::init_ddsp_engine(move |sample_rate| DDSPAudioEngine::new(sample_rate, model));
+    DDSP_ENABLED.store(true, Ordering::Relaxed);
     
-    let mut global_engine = DDSP_ENGINE.lock().unwrap();
-    *global_engine = Some(engine);
-    
-    println!("🎵 DDSP Neural Audio Engine initialized");
+    if pretrained {
+        println!("🎵 DDSP Neural Audio Engine initialized with the pretrained model");
+    } else {
+        println!("🎵 DDSP Neural Audio Engine initialized (untrained decoders, set ddsp_model to load one)");
+    }
 }
 
 /// Update DDSP system with current game state
 pub fn update_ddsp_audio(features: GameStateFeatures) {
-    if let Ok(mut engine_guard) = DDSP_ENGINE.lock() {
-        if let Some(ref mut engine) = *engine_guard {
-            engine.update_features(features);
-        }
-    }
-}
-
-/// Generate stereo audio samples (called from audio callback)
-pub fn generate_ddsp_samples(buffer: &mut [(f32, f32)]) {
-    if let Ok(mut engine_guard) = DDSP_ENGINE.lock() {
-        if let Some(ref mut engine) = *engine_guard {
-            for sample in buffer.iter_mut() {
-                *sample = engine.generate_stereo_sample();
-            }
-        }
-    }
+    super::hybrid_dungeon_synth::update_ddsp_engine(features);
 }
 
 /// Toggle DDSP audio
 pub fn toggle_ddsp_audio() -> bool {
-    if let Ok(mut engine_guard) = DDSP_ENGINE.lock() {
-        if let Some(ref mut engine) = *engine_guard {
-            return engine.toggle();
-        }
-    }
-    false
+    let enabled = !DDSP_ENABLED.fetch_xor(true, Ordering::Relaxed);
+    super::hybrid_dungeon_synth::set_ddsp_engine_enabled(enabled);
+    enabled
+}
+
+/// Whether `init_ddsp_audio` has run since the hybrid engine last took over
+pub fn ddsp_audio_running() -> bool {
+    super::hybrid_dungeon_synth::ddsp_engine_running()
 }
 
 /// Check if DDSP audio is enabled
 pub fn has_ddsp_audio() -> bool {
-    if let Ok(engine_guard) = DDSP_ENGINE.lock() {
-        if let Some(ref engine) = *engine_guard {
-            return engine.is_enabled();
-        }
+    ddsp_audio_running() && DDSP_ENABLED.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tensor(shape: &[usize], value: f32) -> Tensor {
+        Tensor { shape: shape.to_vec(), data: vec![value; shape.iter().product()] }
     }
-    false
-} 
\ No newline at end of file
+
+    fn model_tensors(harmonics: usize) -> HashMap<String, Tensor> {
+        let inputs = GameStateFeatures::INPUTS;
+        [
+            ("harmonic.w1", tensor(&[4, inputs], 0.0)),
+            ("harmonic.b1", tensor(&[4], 0.0)),
+            ("harmonic.w2", tensor(&[harmonics, 4], 0.0)),
+            ("harmonic.b2", tensor(&[harmonics], 2.0)),
+            ("noise.w1", tensor(&[3, inputs], 0.0)),
+            ("noise.b1", tensor(&[3], 0.0)),
+            ("noise.w2", tensor(&[2, 3], 0.0)),
+            ("noise.b2", tensor(&[2], -20.0)),
+        ]
+        .into_iter()
+        .map(|(name, tensor)| (name.to_string(), tensor))
+        .collect()
+    }
+
+    #[test]
+    fn a_model_is_built_from_its_tensors_and_plays() {
+        let model = DdspModel::from_tensors(&model_tensors(8)).unwrap();
+        assert!(model.pretrained);
+        assert_eq!(model.harmonic.output_size(), 8);
+        let amps = model.harmonic.forward(&GameStateFeatures::default().to_vector());
+        assert!(amps.iter().all(|amp| (amp - 2.0f32.sigmoid()).abs() < 1e-6));
+
+        let mut engine = DDSPAudioEngine::new(44100.0, model);
+        let frames: Vec<(f32, f32)> = (0..CONTROL_PERIOD * 8).map(|_| engine.generate_stereo_sample()).collect();
+        let peak = frames.iter().fold(0.0f32, |peak, (l, r)| peak.max(l.abs()).max(r.abs()));
+        assert!(peak > 0.01 && peak < 2.0, "{}", peak);
+        engine.set_enabled(false);
+        assert_eq!(engine.generate_stereo_sample(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn missing_or_misshapen_tensors_are_reported() {
+        let mut tensors = model_tensors(8);
+        tensors.remove("noise.b2");
+        assert!(matches!(DdspModel::from_tensors(&tensors), Err(DdspModelError::Missing(name)) if name == "noise.b2"));
+
+        let mut tensors = model_tensors(8);
+        tensors.insert("harmonic.w2".to_string(), tensor(&[8, 5], 0.0));
+        let error = DdspModel::from_tensors(&tensors).err().unwrap();
+        assert_eq!(error.to_string(), "tensor harmonic.w2 is [8, 5], expected [n, 4]");
+    }
+}
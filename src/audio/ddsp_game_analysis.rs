@@ -5,22 +5,16 @@ use std::collections::HashSet;
 
 /// Analyze the game grid and extract neural network features
 pub struct GameStateAnalyzer {
-    previous_population: usize,
     previous_generation: u64,
     previous_features: Option<GameStateFeatures>, // Track previous features for better change detection
-    _birth_count: usize,
-    _death_count: usize,
     activity_history: Vec<f32>, // Track recent activity for smoothing
 }
 
 impl Default for GameStateAnalyzer {
     fn default() -> Self {
         Self {
-            previous_population: 0,
             previous_generation: 0,
             previous_features: None,
-            _birth_count: 0,
-            _death_count: 0,
             activity_history: Vec::with_capacity(10), // Keep last 10 frames
         }
     }
@@ -61,10 +55,11 @@ impl GameStateAnalyzer {
         let viewport_population = viewport_cells.len();
         let viewport_area = (viewport_width * viewport_height) as usize;
         
-        // Calculate activity (births/deaths since last frame) - more sensitive
+        // Calculate activity from the births and deaths of the last step - an oscillator
+        // keeps its population but is still busy
         let activity = if generation > self.previous_generation {
-            let pop_change = current_population as i32 - self.previous_population as i32;
-            let raw_activity = (pop_change.abs() as f32) / (current_population.max(1) as f32);
+            let step = grid.last_step();
+            let raw_activity = (step.births + step.deaths) as f32 / (current_population.max(1) as f32);
             
             // Add to activity history for smoothing
             self.activity_history.push(raw_activity);
@@ -114,7 +109,6 @@ impl GameStateAnalyzer {
         } else { (0.0, 0.0) };
         
         // Update state for next frame
-        self.previous_population = current_population;
         self.previous_generation = generation;
         
         let features = GameStateFeatures {
@@ -291,4 +285,8 @@ pub fn extract_game_features(
     let mut analyzer = analyzer_mutex.lock().unwrap();
     
     analyzer.extract_features(grid, camera_state, generation)
-} 
\ No newline at end of file
+} 
+/// Features from the most recent `extract_game_features`, without analysing again
+pub fn latest_game_features() -> Option<GameStateFeatures> {
+    get_analyzer().lock().ok()?.previous_features.clone()
+}
@@ -1,10 +1,27 @@
 use std::f32::consts::PI;
 
-use rodio::{Source, OutputStream, Sink};
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
 use std::collections::HashMap;
+use crossbeam_channel::{bounded, Receiver, Sender};
+use kira::clock::clock_info::ClockInfoProvider;
+use kira::dsp::Frame;
+use kira::manager::AudioManager;
+use kira::modulator::value_provider::ModulatorValueProvider;
+use kira::sound::{Sound, SoundData};
+use kira::track::{TrackHandle, TrackId};
+use kira::OutputDestination;
+use super::ddsp_engine::{DDSPAudioEngine, GameStateFeatures};
+use super::key::{self, KeySelection, Mode};
+use super::layer_mix::{AudioLayer, LayerMix};
+use super::spatial_audio::{ClusterVoice, SpatialVoices, MAX_VOICES};
+use super::output::{start_output, write_frame, OutputBackend, OutputFormat, OutputStream, PREFERRED_SAMPLE_RATE};
+use super::user_samples::{user_samples, SampleRole, BANK_SAMPLE_RATE};
 // use std::time::{Duration, Instant}; // Not needed
 
+/// Generations between the milestone bells
+pub const BELL_INTERVAL: u64 = 100;
+
 /// Optimized cell matrix for efficient processing of large populations
 #[derive(Clone)]
 struct CellMatrix {
@@ -194,6 +211,8 @@ pub struct HybridDungeonSynthEngine {
     
     // Hybrid control parameters
     synthesis_mix: f32,      // 0.0 = all samples, 1.0 = all synthesis
+    layer_mix: LayerMix,     // Per-layer gain, mute and solo
+    key: KeySelection,       // Root and mode fixed by the player, overriding the automatic choice
     _neural_influence: f32,   // How much neural modulation affects parameters
     _medieval_authenticity: f32, // Controls how "authentic" vs "atmospheric" the sound is
     
@@ -203,6 +222,7 @@ pub struct HybridDungeonSynthEngine {
     compression_ratio: f32,  // Dynamic compression
     // --- Phase-2 additions ---
     scale_notes: [f32; 7],      // Current diatonic scale (Hz)
+    scale_mode: Mode,           // Mode of `scale_notes`
     last_milestone_generation: u64, // For 100-generation bell trigger
 }
 
@@ -218,6 +238,7 @@ struct CathedralDroneLayer {
     pattern_transition_timer: f32,
     _sub_bass_phase: f32,              // Deep sub-bass oscillator
     rhythm_trigger: f32,              // Rhythmic bass variations
+    sample_rate: f32,
 }
 
 #[derive(Copy, Clone)]
@@ -235,7 +256,7 @@ struct ResonanceFilter {
 }
 
 impl CathedralDroneLayer {
-    fn new(_sample_rate: f32) -> Self {
+    fn new(sample_rate: f32) -> Self {
         let base_frequencies = [32.7, 65.4, 82.4, 98.0]; // C1, C2, E2, G2 - extended minor triad with sub-bass
         let mut oscillators = [DroneOscillator {
             phase: 0.0,
@@ -270,10 +291,12 @@ impl CathedralDroneLayer {
             _sub_bass_phase: 0.0,
             rhythm_trigger: 0.0,
             base_frequencies,
+            sample_rate,
         }
     }
     
-    fn process(&mut self, sample_rate: f32) -> f32 {
+    fn process(&mut self) -> f32 {
+        let sample_rate = self.sample_rate;
         let mut output = 0.0;
         
         // Update pattern transition
@@ -349,7 +372,7 @@ impl CathedralDroneLayer {
         }
         
         // Reduced overall gain and better filtering to prevent clipping
-        let filtered_output = self.resonance_filter.process(output * 0.15); // Reduced from 0.3
+        let filtered_output = self.resonance_filter.process(output * 0.15, sample_rate); // Reduced from 0.3
         
         // Apply soft limiting to final output
         let threshold = 0.7;
@@ -383,7 +406,7 @@ impl CathedralDroneLayer {
         // Update bass pattern selection based on cell activity
         if regional_mod > 0.5 {
             // High activity - faster pattern changes
-            self.pattern_transition_timer += 0.5 / 44100.0; // Accelerate pattern changes
+            self.pattern_transition_timer += 0.5 / self.sample_rate; // Accelerate pattern changes
         }
         
         // Modulate bass patterns based on regional complexity
@@ -441,8 +464,8 @@ impl CathedralDroneLayer {
 }
 
 impl ResonanceFilter {
-    fn process(&mut self, input: f32) -> f32 {
-        let f = (self.cutoff * 2.0 * PI / 44100.0).min(0.99);
+    fn process(&mut self, input: f32, sample_rate: f32) -> f32 {
+        let f = (self.cutoff * 2.0 * PI / sample_rate).min(0.99);
         let fb = self.resonance + self.resonance / (1.0 - f);
         
         self.state[0] += f * (input - self.state[0] + fb * (self.state[0] - self.state[1]));
@@ -454,11 +477,16 @@ impl ResonanceFilter {
     }
 }
 
-/// Medieval Sample Bank - Procedurally generated medieval instrument samples
+/// Medieval Sample Bank - Procedurally generated medieval instrument samples, joined or
+/// replaced by the player's own (see `user_samples`)
 struct MedievalSampleBank {
     lute_samples: Vec<SampleData>,
     bell_samples: Vec<SampleData>,
+    /// Only ever user samples; nothing is generated for pads
+    pad_samples: Vec<SampleData>,
     current_voices: Vec<PlayingVoice>,
+    /// Bank samples per output sample, as samples are kept at `BANK_SAMPLE_RATE`
+    playback_step: f32,
 }
 
 #[derive(Clone)]
@@ -470,13 +498,14 @@ struct SampleData {
 struct PlayingVoice {
     sample_data: Vec<f32>,
     position: f32,
-    pitch_ratio: f32,
+    pitch_ratio: f32, // Bank samples to move on per output sample
     amplitude: f32,
-    decay_rate: f32,
+    decay_rate: f32,  // Amplitude kept per output sample
+    is_pad: bool,
 }
 
 impl MedievalSampleBank {
-    fn new() -> Self {
+    fn new(sample_rate: f32) -> Self {
         let mut lute_samples = Vec::new();
         let mut bell_samples = Vec::new();
         
@@ -491,14 +520,27 @@ impl MedievalSampleBank {
         }
         
         Self {
-            lute_samples,
-            bell_samples,
+            lute_samples: Self::with_user_samples(lute_samples, SampleRole::Pluck),
+            bell_samples: Self::with_user_samples(bell_samples, SampleRole::Bell),
+            pad_samples: Self::with_user_samples(Vec::new(), SampleRole::Pad),
             current_voices: Vec::new(),
+            playback_step: BANK_SAMPLE_RATE as f32 / sample_rate,
+        }
+    }
+
+    /// Generated samples of a role with the player's samples of that role added or swapped in
+    fn with_user_samples(generated: Vec<SampleData>, role: SampleRole) -> Vec<SampleData> {
+        let (user, replace) = user_samples(role);
+        let user = user.into_iter().map(|sample| SampleData { data: sample.data, base_frequency: sample.base_frequency });
+        if replace {
+            user.collect()
+        } else {
+            generated.into_iter().chain(user).collect()
         }
     }
     
     fn generate_lute_sample(frequency: f32, duration: f32) -> SampleData {
-        let sample_rate = 44100.0;
+        let sample_rate = BANK_SAMPLE_RATE as f32;
         let length = (sample_rate * duration) as usize;
         let mut data = Vec::with_capacity(length);
         
@@ -525,7 +567,7 @@ impl MedievalSampleBank {
     }
     
     fn generate_bell_sample(frequency: f32, duration: f32) -> SampleData {
-        let sample_rate = 44100.0;
+        let sample_rate = BANK_SAMPLE_RATE as f32;
         let length = (sample_rate * duration) as usize;
         let mut data = Vec::with_capacity(length);
         
@@ -573,9 +615,10 @@ impl MedievalSampleBank {
             let voice = PlayingVoice {
                 sample_data: sample.data.clone(),
                 position: 0.0,
-                pitch_ratio: (note / sample.base_frequency) * pitch_variation,
+                pitch_ratio: (note / sample.base_frequency) * pitch_variation * self.playback_step,
                 amplitude: velocity_variation * 0.7, // Reduced amplitude
-                decay_rate: decay_variation,
+                decay_rate: decay_variation.powf(self.playback_step),
+                is_pad: false,
             };
             self.current_voices.push(voice);
         }
@@ -596,25 +639,44 @@ impl MedievalSampleBank {
             let voice = PlayingVoice {
                 sample_data: sample.data.clone(),
                 position: 0.0,
-                pitch_ratio: (note / sample.base_frequency) * pitch_variation,
+                pitch_ratio: (note / sample.base_frequency) * pitch_variation * self.playback_step,
                 amplitude: velocity_variation * 0.6, // Reduced amplitude
-                decay_rate: decay_variation,
+                decay_rate: decay_variation.powf(self.playback_step),
+                is_pad: false,
+            };
+            self.current_voices.push(voice);
+        }
+    }
+    
+    fn trigger_pad(&mut self, note: f32, velocity: f32) {
+        // Pads hold on, so only one sounds at a time
+        if self.current_voices.len() >= 6 || self.current_voices.iter().any(|voice| voice.is_pad) {
+            return;
+        }
+        
+        if let Some(sample) = Self::find_closest_sample(&self.pad_samples, note) {
+            let voice = PlayingVoice {
+                sample_data: sample.data.clone(),
+                position: 0.0,
+                pitch_ratio: note / sample.base_frequency * self.playback_step,
+                amplitude: velocity * 0.5,
+                decay_rate: 0.99999f32.powf(self.playback_step),
+                is_pad: true,
             };
             self.current_voices.push(voice);
         }
     }
     
     fn find_closest_lute_sample(&self, target_freq: f32) -> Option<&SampleData> {
-        self.lute_samples.iter()
-            .min_by(|a, b| {
-                let dist_a = (a.base_frequency - target_freq).abs();
-                let dist_b = (b.base_frequency - target_freq).abs();
-                dist_a.partial_cmp(&dist_b).unwrap()
-            })
+        Self::find_closest_sample(&self.lute_samples, target_freq)
     }
     
     fn find_closest_bell_sample(&self, target_freq: f32) -> Option<&SampleData> {
-        self.bell_samples.iter()
+        Self::find_closest_sample(&self.bell_samples, target_freq)
+    }
+    
+    fn find_closest_sample(samples: &[SampleData], target_freq: f32) -> Option<&SampleData> {
+        samples.iter()
             .min_by(|a, b| {
                 let dist_a = (a.base_frequency - target_freq).abs();
                 let dist_b = (b.base_frequency - target_freq).abs();
@@ -860,7 +922,7 @@ impl HybridDungeonSynthEngine {
     pub fn new(sample_rate: f32) -> Self {
         Self {
             cathedral_drone: CathedralDroneLayer::new(sample_rate),
-            medieval_samples: MedievalSampleBank::new(),
+            medieval_samples: MedievalSampleBank::new(sample_rate),
             neural_modulator: SimpleNeuralModulator::new(),
             crypt_reverb: CryptReverb::new(sample_rate),
             tape_saturation: TapeSaturation::new(),
@@ -886,6 +948,8 @@ impl HybridDungeonSynthEngine {
             _spatial_modulation: [0.0; 16],
             
             synthesis_mix: 0.7,
+            layer_mix: LayerMix::default(),
+            key: KeySelection::default(),
             _neural_influence: 0.5,
             _medieval_authenticity: 0.8,
             
@@ -895,6 +959,7 @@ impl HybridDungeonSynthEngine {
             compression_ratio: 1.0,
             // Phase-2 init
             scale_notes: [220.0, 246.94, 261.63, 293.66, 329.63, 369.99, 415.30], // A minor by default
+            scale_mode: Mode::Aeolian,
             last_milestone_generation: 0,
         }
     }
@@ -911,20 +976,21 @@ impl HybridDungeonSynthEngine {
         self.update_counter += 1;
         
         // Generate synthesis layers
-        let drone = self.cathedral_drone.process(self.sample_rate);
+        let drone = self.cathedral_drone.process() * self.layer_mix.gain(AudioLayer::Drone);
         
         // Generate sample-based layers
-        let samples = self.medieval_samples.process();
+        let samples = self.medieval_samples.process() * self.layer_mix.gain(AudioLayer::Samples);
         
         // Mix synthesis and samples
         let mixed = drone * self.synthesis_mix + samples * (1.0 - self.synthesis_mix);
         
-        // Apply effects
+        // Apply effects, blended in by the effects layer's gain
         let reverbed = self.crypt_reverb.process(mixed);
         let saturated = self.tape_saturation.process(reverbed);
+        let effected = mixed + (saturated - mixed) * self.layer_mix.gain(AudioLayer::Effects);
         
         // Master limiting to prevent clipping
-        let limited = self.master_limiter(saturated);
+        let limited = self.master_limiter(effected);
         
         // Create stereo image with subtle differences
         let left = limited;
@@ -948,10 +1014,12 @@ impl HybridDungeonSynthEngine {
         // -----------------------------
         // 1. Compute musical scale (root & mode)
         // -----------------------------
-        let scale_choice = ((symmetry + chaos) * 4.0) as usize % 4; // 0-3
+        // A root or mode chosen by the player wins over the automatic one
+        let scale_choice = self.key.mode.map_or(((symmetry + chaos) * 4.0) as usize % 4, Mode::rotation); // 0-3 when automatic
 
         // Root note shifts with symmetry (smooth) – between ~55 Hz and 110 Hz
-        let root_hz = 55.0 * 2f32.powf((symmetry - 0.5) * 1.0);
+        let root_hz = self.key.root.map_or(55.0 * 2f32.powf((symmetry - 0.5) * 1.0), key::root_hz);
+        self.scale_mode = Mode::from_rotation(scale_choice);
 
         let new_scale = Self::build_scale(root_hz, scale_choice);
         if new_scale != self.scale_notes {
@@ -968,10 +1036,11 @@ impl HybridDungeonSynthEngine {
         ];
         self.cathedral_drone.set_base_frequencies(drone_bases);
 
-        // Milestone bell every 100 generations
+        // Milestone bell every BELL_INTERVAL generations
         let gen_u64 = generation as u64;
-        if gen_u64 / 100 > self.last_milestone_generation / 100 {
+        if gen_u64 / BELL_INTERVAL > self.last_milestone_generation / BELL_INTERVAL {
             self.medieval_samples.trigger_bell(self.scale_notes[0], 1.0);
+            self.medieval_samples.trigger_pad(self.scale_notes[0], 0.8);
             self.last_milestone_generation = gen_u64;
         }
 
@@ -1278,8 +1347,9 @@ impl HybridDungeonSynthEngine {
         }
     }
 
-    /// Build a 7-note diatonic scale (Ionian, Aeolian, Dorian, Mixolydian) starting from `root_hz`.
-    fn build_scale(root_hz: f32, mode: usize) -> [f32; 7] {
+    /// Build a 7-note diatonic scale starting from `root_hz`, in the mode the major scale
+    /// rotated by `mode` steps gives (0 Ionian, 1 Dorian, ... 6 Locrian; see `key::Mode`).
+    pub(super) fn build_scale(root_hz: f32, mode: usize) -> [f32; 7] {
         // Semitone intervals for major scale
         const MAJOR: [i32; 7] = [0, 2, 4, 5, 7, 9, 11];
         // Rotate intervals for modes
@@ -1298,111 +1368,298 @@ impl HybridDungeonSynthEngine {
     }
 }
 
-// Global instance and volume control
-static HYBRID_ENGINE: Mutex<Option<HybridDungeonSynthEngine>> = Mutex::new(None);
-static MASTER_VOLUME: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+// The audio callback (Kira's, or a cpal stream's when Kira can't start) owns the engine, or
+// the DDSP engine in its place when that one is picked, with the spatial engine's cluster voices
+// mixed in over either. The game changes it through `EngineCommand`s on a bounded, lock-free channel, which the
+// callback drains at the start of every buffer, and
+// reads what it needs back from the atomics below, so neither side ever waits on the other.
 
-// Audio source for rodio
-struct HybridAudioSource {
-    sample_rate: u32,
-    channels: u16,
-    sample_counter: usize,
+/// A change for the engine, applied by the audio callback
+enum EngineCommand {
+    /// Play a new engine, handing the old one back over `RETIRED`
+    Start(Box<HybridDungeonSynthEngine>),
+    /// Play a DDSP engine instead, handing back whichever engine played before
+    StartDdsp(Box<DDSPAudioEngine>),
+    DdspFeatures(GameStateFeatures),
+    DdspEnabled(bool),
+    /// Where the cluster voices should head, at the output's sample rate
+    ClusterVoices { voices: [ClusterVoice; MAX_VOICES], sample_rate: f32 },
+    Features([f32; 8]),
+    Regions([(f32, f32, f32); 16]),
+    SynthesisMix(f32),
+    Key(KeySelection),
+    LayerMix(LayerMix),
+    Note { degree: i32, velocity: f32 },
 }
 
-impl HybridAudioSource {
+/// Commands the game can get ahead of the callback by; more are dropped
+const COMMAND_CAPACITY: usize = 1024;
+/// Level of the engine's output at full master volume
+const OUTPUT_GAIN: f32 = 0.6;
+/// Further gain on the way to the device; recordings are taken before it
+const DEVICE_GAIN: f32 = 0.7;
+
+type Channel<T> = (Sender<T>, Receiver<T>);
+
+static COMMANDS: LazyLock<Channel<EngineCommand>> = LazyLock::new(|| bounded(COMMAND_CAPACITY));
+/// Engines the callback has replaced, freed by the game rather than on the audio thread
+static RETIRED: LazyLock<Channel<Box<dyn Send>>> = LazyLock::new(|| bounded(4));
+/// Format of the output stream, or `None` if it couldn't be opened
+static OUTPUT: OnceLock<Option<OutputFormat>> = OnceLock::new();
+/// The engine's own stream, when it doesn't play through Kira
+static FALLBACK_STREAM: OnceLock<OutputStream> = OnceLock::new();
+/// The game's copy of the cell matrix; only its regions go to the engine
+static CELL_MATRIX: LazyLock<Mutex<CellMatrix>> = LazyLock::new(|| Mutex::new(CellMatrix::new()));
+
+static ENGINE_STARTED: AtomicBool = AtomicBool::new(false);
+static DDSP_STARTED: AtomicBool = AtomicBool::new(false);
+static MASTER_VOLUME: AtomicU32 = AtomicU32::new(0);
+// Scale the engine is playing, published after every buffer; a root of 0 means none yet
+static SCALE_ROOT: AtomicU32 = AtomicU32::new(0);
+static SCALE_MODE: AtomicU8 = AtomicU8::new(0);
+
+// Time spent making samples, for the performance overlay; only measured while it is shown
+static LOAD_METERING: AtomicBool = AtomicBool::new(false);
+static BUSY_NANOS: AtomicU64 = AtomicU64::new(0);
+static METERED_FRAMES: AtomicU64 = AtomicU64::new(0);
+
+/// Queue a command, if there is an output stream to play it
+fn send_command(command: EngineCommand) {
+    if OUTPUT.get().is_some_and(Option::is_some) {
+        let _ = COMMANDS.0.try_send(command);
+    }
+}
+
+/// Hand an engine the callback no longer plays back to the game to free
+fn retire(old: Option<Box<impl Send + 'static>>) {
+    if let Some(old) = old {
+        // Should the game not have collected the last ones yet, it is freed here after all
+        let _ = RETIRED.0.try_send(old);
+    }
+}
+
+/// The audio callback's side: the engine it plays, the cluster voices, and the recording and
+/// dataset taps
+struct HybridRenderer {
+    engine: Option<Box<HybridDungeonSynthEngine>>,
+    /// Plays instead of `engine` when set
+    ddsp: Option<Box<DDSPAudioEngine>>,
+    spatial: SpatialVoices,
+    commands: Receiver<EngineCommand>,
+    tap: super::recorder::Tap,
+    dataset: super::dataset::Tap,
+}
+
+impl HybridRenderer {
     fn new() -> Self {
         Self {
-            sample_rate: 44100,
-            channels: 2,
-            sample_counter: 0,
+            engine: None,
+            ddsp: None,
+            spatial: SpatialVoices::default(),
+            commands: COMMANDS.1.clone(),
+            tap: super::recorder::Tap::default(),
+            dataset: super::dataset::Tap::default(),
+        }
+    }
+
+    fn apply(&mut self, command: EngineCommand) {
+        match command {
+            EngineCommand::Start(engine) => {
+                retire(self.ddsp.take());
+                retire(self.engine.replace(engine));
+                return;
+            }
+            EngineCommand::StartDdsp(engine) => {
+                retire(self.engine.take());
+                retire(self.ddsp.replace(engine));
+                return;
+            }
+            EngineCommand::DdspFeatures(features) => {
+                if let Some(ddsp) = self.ddsp.as_mut() {
+                    ddsp.update_features(features);
+                }
+                return;
+            }
+            EngineCommand::DdspEnabled(enabled) => {
+                if let Some(ddsp) = self.ddsp.as_mut() {
+                    ddsp.set_enabled(enabled);
+                }
+                return;
+            }
+            EngineCommand::ClusterVoices { voices, sample_rate } => {
+                self.spatial.set_targets(voices, sample_rate);
+                return;
+            }
+            _ => {}
+        }
+        let Some(engine) = self.engine.as_mut() else { return };
+        match command {
+            // Handled above
+            EngineCommand::Start(_) | EngineCommand::StartDdsp(_) | EngineCommand::DdspFeatures(_) | EngineCommand::DdspEnabled(_)
+            | EngineCommand::ClusterVoices { .. } => {}
+            EngineCommand::Features(features) => engine.current_features = features,
+            EngineCommand::Regions(regions) => engine.cell_matrix.regions = regions,
+            EngineCommand::SynthesisMix(mix) => engine.synthesis_mix = mix,
+            EngineCommand::Key(key) => engine.key = key,
+            EngineCommand::LayerMix(mix) => engine.layer_mix = mix,
+            EngineCommand::Note { degree, velocity } => {
+                let octave = 2f32.powi(degree.div_euclid(7) + 2);
+                let note = engine.scale_notes[degree.rem_euclid(7) as usize] * octave;
+                engine.medieval_samples.trigger_lute(note, velocity);
+            }
         }
     }
-}
 
-impl Iterator for HybridAudioSource {
-    type Item = f32;
+    /// Apply the commands sent since the last buffer
+    fn start_buffer(&mut self) {
+        while let Ok(command) = self.commands.try_recv() {
+            self.apply(command);
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        // Use the actual global engine that receives real game features
-        if let Ok(mut engine_guard) = HYBRID_ENGINE.try_lock() {
-            if let Some(ref mut engine) = engine_guard.as_mut() {
+    /// The next stereo frame at the device's level, recording it if a recording or a dataset
+    /// export runs
+    fn next_frame(&mut self, gain: f32) -> (f32, f32) {
+        let (left, right) = match (self.ddsp.as_mut(), self.engine.as_mut()) {
+            (Some(ddsp), _) => ddsp.generate_stereo_sample(),
+            (None, Some(engine)) => {
                 let game_features = engine.current_features;
-                let (left, right) = engine.process_sample(game_features);
-                
-                // Alternate between left and right channels
-                let output = if self.sample_counter % 2 == 0 {
-                    left
-                } else {
-                    right
-                };
-                
-                // Apply master volume control
-                let master_volume = get_hybrid_volume();
-                
-                self.sample_counter += 1;
-                return Some(output * 0.6 * master_volume); // Doubled base volume for more presence
+                engine.process_sample(game_features)
             }
+            (None, None) => (0.0, 0.0),
+        };
+        let (spatial_left, spatial_right) = self.spatial.next_frame();
+        let (left, right) = ((left + spatial_left) * gain, (right + spatial_right) * gain);
+        self.tap.push(left);
+        self.tap.push(right);
+        self.dataset.push(left, right);
+        (left * DEVICE_GAIN, right * DEVICE_GAIN)
+    }
+
+    /// Publish the scale for `get_scale_key`
+    fn end_buffer(&self) {
+        if let Some(engine) = &self.engine {
+            SCALE_ROOT.store(engine.scale_notes[0].to_bits(), Ordering::Relaxed);
+            SCALE_MODE.store(engine.scale_mode.rotation() as u8, Ordering::Relaxed);
+        }
+    }
+
+    /// Fill an interleaved device buffer
+    fn render(&mut self, buffer: &mut [f32], channels: usize) {
+        let started = LOAD_METERING.load(Ordering::Relaxed).then(std::time::Instant::now);
+        self.start_buffer();
+        let gain = OUTPUT_GAIN * get_hybrid_volume();
+        for frame in buffer.chunks_mut(channels) {
+            let (left, right) = self.next_frame(gain);
+            write_frame(frame, left, right);
+        }
+        self.end_buffer();
+        if let Some(started) = started {
+            BUSY_NANOS.fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+            METERED_FRAMES.fetch_add((buffer.len() / channels.max(1)) as u64, Ordering::Relaxed);
         }
-        
-        // Return silence if engine is locked or unavailable
-        Some(0.0)
     }
 }
 
-impl Source for HybridAudioSource {
-    fn current_frame_len(&self) -> Option<usize> {
-        None
+/// The engine as a Kira sound, playing on the synth track until its handle is dropped
+pub struct HybridSound {
+    renderer: HybridRenderer,
+    track: TrackId,
+    gain: f32,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Sound for HybridSound {
+    fn output_destination(&mut self) -> OutputDestination {
+        OutputDestination::Track(self.track)
     }
 
-    fn channels(&self) -> u16 {
-        self.channels
+    fn on_start_processing(&mut self) {
+        self.renderer.end_buffer();
+        self.renderer.start_buffer();
+        self.gain = OUTPUT_GAIN * get_hybrid_volume();
     }
 
-    fn sample_rate(&self) -> u32 {
-        self.sample_rate
+    fn process(&mut self, _dt: f64, _clock_info: &ClockInfoProvider, _modulators: &ModulatorValueProvider) -> Frame {
+        let started = LOAD_METERING.load(Ordering::Relaxed).then(std::time::Instant::now);
+        let (left, right) = self.renderer.next_frame(self.gain);
+        if let Some(started) = started {
+            BUSY_NANOS.fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+            METERED_FRAMES.fetch_add(1, Ordering::Relaxed);
+        }
+        Frame::new(left, right)
     }
 
-    fn total_duration(&self) -> Option<std::time::Duration> {
-        None // Infinite stream
+    fn finished(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
     }
 }
 
-pub fn init_hybrid_dungeon_synth() {
-    let engine = HybridDungeonSynthEngine::new(44100.0);
-    *HYBRID_ENGINE.lock().unwrap() = Some(engine);
-    
-    // Initialize volume from config
-    let default_volume = 0.7; // Default volume if config not available
-    set_hybrid_volume(default_volume);
-    
-    // Initialize audio output with better error handling and persistence
-    std::thread::spawn(|| {
-        println!("🎵 Starting hybrid audio thread...");
-        
-        match OutputStream::try_default() {
-            Ok((_stream, stream_handle)) => {
-                println!("✅ Audio output stream created successfully");
-                
-                match Sink::try_new(&stream_handle) {
-                    Ok(sink) => {
-                        println!("✅ Audio sink created successfully");
-                        
-                        let audio_source = HybridAudioSource::new();
-                        sink.append(audio_source);
-                        sink.set_volume(0.7);
-                        
-                        println!("🏰🔊 Hybrid Dungeon Synth Engine initialized with AUDIO OUTPUT!");
-                        println!("🎵 Audio thread running - you should now hear medieval dungeon synth audio!");
-                        
-                        // Keep the thread alive indefinitely
-                        loop {
-                            std::thread::sleep(std::time::Duration::from_secs(1));
-                        }
-                    }
-                    Err(e) => {
-                        println!("❌ Failed to create audio sink: {}", e);
-                    }
-                }
+/// Keeps the engine playing in Kira; dropping it lets Kira unload the sound
+pub struct HybridSoundHandle {
+    stopped: Arc<AtomicBool>,
+}
+
+impl Drop for HybridSoundHandle {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+struct HybridSoundData {
+    track: TrackId,
+}
+
+impl SoundData for HybridSoundData {
+    type Error = ();
+    type Handle = HybridSoundHandle;
+
+    fn into_sound(self) -> Result<(Box<dyn Sound>, Self::Handle), Self::Error> {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let sound = HybridSound {
+            renderer: HybridRenderer::new(),
+            track: self.track,
+            gain: 0.0,
+            stopped: stopped.clone(),
+        };
+        Ok((Box::new(sound), HybridSoundHandle { stopped }))
+    }
+}
+
+/// Play the engine on `track` of Kira's mixer instead of a stream of its own. Only the first
+/// output to open is used, so this has to run before `init_hybrid_dungeon_synth`.
+pub fn play_hybrid_in_kira(manager: &mut AudioManager<OutputBackend>, track: &TrackHandle) -> Option<HybridSoundHandle> {
+    // The engine has to be built for the rate Kira mixes at
+    let sample_rate = manager.backend_mut().format().sample_rate;
+    let mut handle = None;
+    OUTPUT.get_or_init(|| match manager.play(HybridSoundData { track: track.id() }) {
+        Ok(playing) => {
+            println!("✅ Hybrid engine playing through Kira at {} Hz", sample_rate);
+            super::recorder::set_sample_rate(sample_rate);
+            handle = Some(playing);
+            Some(OutputFormat { sample_rate, channels: 2 })
+        }
+        Err(e) => {
+            println!("❌ Could not play the hybrid engine through Kira: {}", e);
+            None
+        }
+    });
+    handle
+}
+
+/// Open the output stream the first time round, unless Kira plays the engine; its format,
+/// if it could be opened
+fn output_format() -> Option<OutputFormat> {
+    *OUTPUT.get_or_init(|| {
+        let mut renderer = HybridRenderer::new();
+        match start_output(move |buffer, channels| renderer.render(buffer, channels)) {
+            Ok(stream) => {
+                let format = stream.format();
+                println!("✅ Audio output stream created: {} Hz, {} channels", format.sample_rate, format.channels);
+                super::recorder::set_sample_rate(format.sample_rate);
+                // Plays for the rest of the run
+                let _ = FALLBACK_STREAM.set(stream);
+                Some(format)
             }
             Err(e) => {
                 println!("❌ Failed to initialize audio output: {}", e);
@@ -1410,34 +1667,80 @@ pub fn init_hybrid_dungeon_synth() {
                 println!("   - No audio device available");
                 println!("   - Audio permissions not granted");
                 println!("   - Another application is using the audio device");
+                None
             }
         }
-    });
+    })
+}
+
+/// Start a fresh engine, opening the audio output the first time
+pub fn init_hybrid_dungeon_synth() {
+    // Free the engines the callback has swapped out
+    while RETIRED.1.try_recv().is_ok() {}
+    ENGINE_STARTED.store(true, Ordering::Relaxed);
+    DDSP_STARTED.store(false, Ordering::Relaxed);
+    let sample_rate = output_format().map_or(PREFERRED_SAMPLE_RATE, |format| format.sample_rate);
+    send_command(EngineCommand::Start(Box::new(HybridDungeonSynthEngine::new(sample_rate as f32))));
+    
+    // Initialize volume from config
+    let default_volume = 0.7; // Default volume if config not available
+    set_hybrid_volume(default_volume);
     
     println!("🏰 Hybrid Dungeon Synth Engine initialized!");
 }
 
+/// Whether `init_hybrid_dungeon_synth` has run since the DDSP engine last took over
+pub fn hybrid_dungeon_synth_running() -> bool {
+    ENGINE_STARTED.load(Ordering::Relaxed)
+}
+
+/// Play the DDSP engine `build` makes for the output's sample rate in the hybrid engine's
+/// place, opening the audio output the first time
+pub(super) fn init_ddsp_engine(build: impl FnOnce(f32) -> DDSPAudioEngine) {
+    while RETIRED.1.try_recv().is_ok() {}
+    ENGINE_STARTED.store(false, Ordering::Relaxed);
+    DDSP_STARTED.store(true, Ordering::Relaxed);
+    let sample_rate = output_format().map_or(PREFERRED_SAMPLE_RATE, |format| format.sample_rate);
+    send_command(EngineCommand::StartDdsp(Box::new(build(sample_rate as f32))));
+}
+
+/// Whether the DDSP engine plays in the hybrid engine's place
+pub(super) fn ddsp_engine_running() -> bool {
+    DDSP_STARTED.load(Ordering::Relaxed)
+}
+
+pub(super) fn update_ddsp_engine(features: GameStateFeatures) {
+    send_command(EngineCommand::DdspFeatures(features));
+}
+
+pub(super) fn set_ddsp_engine_enabled(enabled: bool) {
+    send_command(EngineCommand::DdspEnabled(enabled));
+}
+
+/// Send the cluster voices where to head, if there is an output to play them
+pub(super) fn set_cluster_voices(voices: [ClusterVoice; MAX_VOICES]) {
+    if let Some(sample_rate) = output_sample_rate() {
+        send_command(EngineCommand::ClusterVoices { voices, sample_rate: sample_rate as f32 });
+    }
+}
+
 pub fn update_hybrid_dungeon_synth(features: [f32; 8]) {
     // Update the engine with real game state features
-    if let Some(ref mut engine) = HYBRID_ENGINE.lock().unwrap().as_mut() {
-        engine.current_features = features;
-    }
+    send_command(EngineCommand::Features(features));
 }
 
 /// Update the cell matrix for optimized processing of large populations
 pub fn update_hybrid_cell_data(cells: &[(i32, i32)], camera_x: f32, camera_y: f32, viewport_size: f32) {
-    if let Ok(mut engine_guard) = HYBRID_ENGINE.try_lock() {
-        if let Some(ref mut engine) = engine_guard.as_mut() {
-            engine.cell_matrix.update_from_cells(cells, camera_x, camera_y, viewport_size);
-        }
-    }
+    let mut matrix = CELL_MATRIX.lock().unwrap();
+    matrix.update_from_cells(cells, camera_x, camera_y, viewport_size);
+    send_command(EngineCommand::Regions(matrix.regions));
 }
 
 /// Set the master volume for the hybrid audio engine (0.0 to 2.0 for overdrive)
 pub fn set_hybrid_volume(volume: f32) {
     let volume_clamped = volume.clamp(0.0, 2.0); // Allow up to 200% for overdrive
     let volume_bits = volume_clamped.to_bits();
-    MASTER_VOLUME.store(volume_bits, std::sync::atomic::Ordering::Relaxed);
+    MASTER_VOLUME.store(volume_bits, Ordering::Relaxed);
     if volume_clamped > 1.0 {
         println!("🔊🔥 OVERDRIVE! Hybrid audio volume: {:.0}%", volume_clamped * 100.0);
     } else {
@@ -1445,38 +1748,67 @@ pub fn set_hybrid_volume(volume: f32) {
     }
 }
 
+/// Start or stop measuring how busy the audio thread is (see `take_audio_load`)
+pub fn set_audio_load_metering(enabled: bool) {
+    LOAD_METERING.store(enabled, Ordering::Relaxed);
+}
+
+/// Sample rate of the output the engine plays through, once it is open
+pub fn output_sample_rate() -> Option<u32> {
+    OUTPUT.get().copied().flatten().map(|format| format.sample_rate)
+}
+
+/// Share of real time the audio thread spent making the samples played since the last call
+/// (1.0 = it only just keeps up), or `None` if none were measured
+pub fn take_audio_load() -> Option<f32> {
+    let frames = METERED_FRAMES.swap(0, Ordering::Relaxed);
+    let busy = BUSY_NANOS.swap(0, Ordering::Relaxed);
+    let sample_rate = OUTPUT.get().copied().flatten()?.sample_rate;
+    if frames == 0 {
+        return None;
+    }
+    let played_nanos = frames as f64 / sample_rate as f64 * 1e9;
+    Some((busy as f64 / played_nanos) as f32)
+}
+
 /// Get the current master volume
 pub fn get_hybrid_volume() -> f32 {
-    let volume_bits = MASTER_VOLUME.load(std::sync::atomic::Ordering::Relaxed);
+    let volume_bits = MASTER_VOLUME.load(Ordering::Relaxed);
     f32::from_bits(volume_bits)
 }
 
 /// Returns the current scale root frequency (degree 0) if the engine is active.
 pub fn get_scale_root() -> Option<f32> {
-    if let Ok(engine_guard) = HYBRID_ENGINE.try_lock() {
-        if let Some(ref engine) = *engine_guard {
-            return Some(engine.scale_notes[0]);
-        }
-    }
-    None
-}
-
-pub fn generate_hybrid_samples(buffer: &mut [(f32, f32)]) {
-    if let Some(ref mut engine) = HYBRID_ENGINE.lock().unwrap().as_mut() {
-        for sample in buffer.iter_mut() {
-            let game_features = engine.current_features;
-            *sample = engine.process_sample(game_features);
-        }
-    }
+    let root = f32::from_bits(SCALE_ROOT.load(Ordering::Relaxed));
+    (root > 0.0).then_some(root)
 }
 
 /// Set the synthesis/sample mix (0.0 = all samples, 1.0 = all synth).
 pub fn set_hybrid_synthesis_mix(mix: f32) {
     let mix_clamped = mix.clamp(0.0, 1.0);
-    if let Ok(mut engine_guard) = HYBRID_ENGINE.try_lock() {
-        if let Some(ref mut engine) = *engine_guard {
-            engine.synthesis_mix = mix_clamped;
-            println!("🎛️ Hybrid synthesis mix set to: {:.0}% synth / {:.0}% samples", mix_clamped * 100.0, (1.0 - mix_clamped) * 100.0);
-        }
-    }
-} 
\ No newline at end of file
+    send_command(EngineCommand::SynthesisMix(mix_clamped));
+    println!("🎛️ Hybrid synthesis mix set to: {:.0}% synth / {:.0}% samples", mix_clamped * 100.0, (1.0 - mix_clamped) * 100.0);
+}
+
+/// Fix the root and/or mode of the engine's scale, or hand them back to the pattern
+pub fn set_hybrid_key(key: KeySelection) {
+    send_command(EngineCommand::Key(key));
+}
+
+/// Pluck a lute note `degree` steps up the current scale from its root, two octaves above
+/// the drone; degrees past the seventh carry on into higher octaves
+pub fn play_hybrid_note(degree: i32, velocity: f32) {
+    send_command(EngineCommand::Note { degree, velocity });
+}
+
+/// Root frequency and mode of the current scale, if the engine is active
+pub fn get_scale_key() -> Option<(f32, Mode)> {
+    let mode = Mode::from_rotation(SCALE_MODE.load(Ordering::Relaxed) as usize);
+    get_scale_root().map(|root| (root, mode))
+}
+
+/// Set the gain, mute and solo of the drone, samples and effects (the groove plays through
+/// the groove track's volume, `KiraManager::set_volume`)
+pub fn set_hybrid_layer_mix(mix: LayerMix) {
+    send_command(EngineCommand::LayerMix(mix));
+}
@@ -1,8 +1,11 @@
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
+use bevy::prelude::Resource;
+use kira::dsp::Frame;
+use kira::sound::static_sound::{StaticSoundData, StaticSoundSettings};
 use rand;
 use crate::audio::ddsp_engine::GameStateFeatures;
-// (No Bevy types needed in this module; accessed via NonSendMut from outside)
+use crate::audio::kira_manager::{KiraManager, MixTrack};
 
 /// Simple kick drum oscillator: decaying sine with pitch drop.
 struct KickOsc {
@@ -41,12 +44,6 @@ impl Iterator for KickOsc {
     }
 }
 
-impl Source for KickOsc {
-    fn current_frame_len(&self) -> Option<usize> { Some(self.total_samples - self.generated) }
-    fn channels(&self) -> u16 { 1 }
-    fn sample_rate(&self) -> u32 { self.sample_rate }
-    fn total_duration(&self) -> Option<Duration> { Some(Duration::from_secs_f32(self.total_samples as f32 / self.sample_rate as f32)) }
-}
 
 /// Hi-hat: burst of white noise with fast decay & band-pass (approximated by simple envelope).
 struct HatOsc {
@@ -75,7 +72,6 @@ impl Iterator for HatOsc {
     }
 }
 
-impl Source for HatOsc { fn current_frame_len(&self)->Option<usize>{Some(self.total_samples-self.generated)} fn channels(&self)->u16{1} fn sample_rate(&self)->u32{self.sample_rate} fn total_duration(&self)->Option<Duration>{Some(Duration::from_secs_f32(self.total_samples as f32/self.sample_rate as f32))} }
 
 /// Deep sine sub-bass
 struct BassOsc {
@@ -113,17 +109,11 @@ impl Iterator for BassOsc {
     }
 }
 
-impl Source for BassOsc {
-    fn current_frame_len(&self) -> Option<usize> { Some(self.total_samples - self.generated) }
-    fn channels(&self) -> u16 { 1 }
-    fn sample_rate(&self) -> u32 { self.sample_rate }
-    fn total_duration(&self) -> Option<Duration> { Some(Duration::from_secs_f32(self.total_samples as f32 / self.sample_rate as f32)) }
-}
 
-/// Runtime state managed as a Bevy resource.
+/// Runtime state managed as a Bevy resource. The hits play on `KiraManager`'s groove
+/// track, whose volume is the groove's `AudioLayer` gain.
+#[derive(Resource)]
 pub struct IllbientGroove {
-    stream: OutputStream,
-    handle: OutputStreamHandle,
     bpm: f32,
     next_beat: Instant,
     step: u8,
@@ -131,53 +121,56 @@ pub struct IllbientGroove {
 
 impl IllbientGroove {
     pub fn new(bpm: f32) -> Self {
-        let (stream, handle) = OutputStream::try_default().expect("audio device");
         let now = Instant::now();
-        Self { stream, handle, bpm, next_beat: now, step: 0 }
+        Self { bpm, next_beat: now, step: 0 }
     }
 
     fn beat_duration(&self) -> Duration { Duration::from_secs_f32(60.0 / self.bpm as f32) }
 
-    pub fn update(&mut self, features: &GameStateFeatures, root_hz: Option<f32>) {
+    pub fn update(&mut self, kira: &mut KiraManager, features: &GameStateFeatures, root_hz: Option<f32>) {
         let now = Instant::now();
         while now >= self.next_beat {
-            self.trigger_step(features, root_hz);
+            self.trigger_step(kira, features, root_hz);
             self.next_beat += self.beat_duration();
             self.step = self.step.wrapping_add(1);
         }
     }
 
-    fn trigger_step(&mut self, features: &GameStateFeatures, root_hz: Option<f32>) {
+    fn trigger_step(&mut self, kira: &mut KiraManager, features: &GameStateFeatures, root_hz: Option<f32>) {
         // Feature-driven pattern: activity controls hat density; chaos adds syncopation.
         let activity = features.activity;
         let chaos = features.chaos;
         // Kick on steps 0 & 8 always
-        if self.step % 8 == 0 { self.play_kick(); }
+        if self.step % 8 == 0 { Self::play_kick(kira); }
         // Extra kick when activity high (>0.2) on off-beat
-        if activity > 0.2 && self.step % 8 == 4 { self.play_kick(); }
+        if activity > 0.2 && self.step % 8 == 4 { Self::play_kick(kira); }
         // Hi-hat probabilistic
         // Density and centroid add subtle swing (more hats on right side of board)
         let density = features.density;
         let centroid_x = features.centroid_x; // -1..1
         let hat_prob = 0.25 + activity*0.4 + chaos*0.2 + density*0.15 + centroid_x.abs()*0.1; // 0.25-1.1
-        if rand::random::<f32>() < hat_prob { self.play_hat(); }
+        if rand::random::<f32>() < hat_prob { Self::play_hat(kira); }
 
         // Sub-bass follows kicks and scale root
         if self.step % 8 == 0 {
             if let Some(root) = root_hz {
-                self.play_bass(root * 0.5); // sub-octave of root
+                Self::play_bass(kira, root * 0.5); // sub-octave of root
             }
         }
     }
 
-    fn play_kick(&self) {
-        if let Ok(sink) = Sink::try_new(&self.handle) { sink.append(KickOsc::new()); sink.detach(); }
+    /// Render a mono hit at 44.1 kHz and play it on the groove track
+    fn play(kira: &mut KiraManager, source: impl Iterator<Item = f32>) {
+        let settings = StaticSoundSettings::new().output_destination(kira.track(MixTrack::Groove));
+        let frames: Arc<[Frame]> = source.map(Frame::from_mono).collect();
+        let _ = kira.manager.play(StaticSoundData { sample_rate: 44100, frames, settings });
     }
-    fn play_hat(&self) { if let Ok(sink)=Sink::try_new(&self.handle){ sink.append(HatOsc::new()); sink.detach(); } }
-    fn play_bass(&self, freq: f32) { if let Ok(sink)=Sink::try_new(&self.handle){ sink.append(BassOsc::new(freq)); sink.detach(); } }
+    fn play_kick(kira: &mut KiraManager) { Self::play(kira, KickOsc::new()); }
+    fn play_hat(kira: &mut KiraManager) { Self::play(kira, HatOsc::new()); }
+    fn play_bass(kira: &mut KiraManager, freq: f32) { Self::play(kira, BassOsc::new(freq)); }
 }
 
-// (Groove resource is inserted in `main.rs` via `insert_non_send_resource`; update is called from the audio system.)
+// (Groove resource is inserted in `main.rs` via `insert_resource`; update is called from the audio system.)
 
 // pub fn update_illbient_groove(
 //     mut groove: ResMut<IllbientGroove>,
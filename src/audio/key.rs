@@ -0,0 +1,164 @@
+//! Musical key of the hybrid engine.
+//!
+//! Left alone, the engine picks its mode from the pattern's symmetry and chaos and slides its
+//! root between A1 and A2 with the symmetry. A `KeySelection` pins the root, the mode or both
+//! instead; whatever is left unset stays automatic.
+
+use serde::{Deserialize, Serialize};
+
+/// Note names of the twelve pitch classes, C first
+pub const PITCH_NAMES: [&str; 12] = ["C", "C♯", "D", "E♭", "E", "F", "F♯", "G", "A♭", "A", "B♭", "B"];
+
+/// Lowest root the engine plays, A1; roots lie in the octave above it
+const LOWEST_ROOT_HZ: f32 = 55.0;
+/// Pitch class of `LOWEST_ROOT_HZ`
+const A: i32 = 9;
+
+/// The seven modes of the major scale, in the order `build_scale` rotates through them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Ionian,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Aeolian,
+    Locrian,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 7] = [Mode::Ionian, Mode::Dorian, Mode::Phrygian, Mode::Lydian, Mode::Mixolydian, Mode::Aeolian, Mode::Locrian];
+
+    /// Steps the major scale is rotated by
+    pub fn rotation(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_rotation(rotation: usize) -> Self {
+        Mode::ALL[rotation % Mode::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Ionian => "Ionian",
+            Mode::Dorian => "Dorian",
+            Mode::Phrygian => "Phrygian",
+            Mode::Lydian => "Lydian",
+            Mode::Mixolydian => "Mixolydian",
+            Mode::Aeolian => "Aeolian",
+            Mode::Locrian => "Locrian",
+        }
+    }
+}
+
+/// Pitch class (C = 0) of a note name such as "D", "F#", "Bb" or "E♭"
+pub fn parse_pitch_class(name: &str) -> Option<u8> {
+    let mut chars = name.trim().chars();
+    let natural: i32 = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let accidental = match chars.as_str() {
+        "" => 0,
+        "#" | "♯" => 1,
+        "b" | "♭" => -1,
+        _ => return None,
+    };
+    Some((natural + accidental).rem_euclid(12) as u8)
+}
+
+/// Frequency of a pitch class in the engine's root octave
+pub fn root_hz(pitch_class: u8) -> f32 {
+    LOWEST_ROOT_HZ * 2f32.powf((pitch_class as i32 - A).rem_euclid(12) as f32 / 12.0)
+}
+
+/// Pitch class nearest to a frequency
+pub fn nearest_pitch_class(hz: f32) -> u8 {
+    let semitones = (12.0 * (hz / LOWEST_ROOT_HZ).log2()).round() as i32;
+    (semitones + A).rem_euclid(12) as u8
+}
+
+/// Which parts of the key the player has fixed; `None` leaves that part automatic
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeySelection {
+    /// Pitch class of the root, C = 0
+    pub root: Option<u8>,
+    pub mode: Option<Mode>,
+}
+
+impl KeySelection {
+    /// From the config's note name and mode; unknown note names leave the root automatic
+    pub fn from_config(root: Option<&str>, mode: Option<Mode>) -> Self {
+        Self { root: root.and_then(parse_pitch_class), mode }
+    }
+
+    pub fn is_auto(&self) -> bool {
+        self.root.is_none() && self.mode.is_none()
+    }
+
+    /// Next root: automatic, then C up to B, then automatic again
+    pub fn cycle_root(&mut self) {
+        self.root = match self.root {
+            None => Some(0),
+            Some(11) => None,
+            Some(pitch_class) => Some(pitch_class + 1),
+        };
+    }
+
+    /// Next mode: automatic, then Ionian through Locrian, then automatic again
+    pub fn cycle_mode(&mut self) {
+        self.mode = match self.mode {
+            None => Some(Mode::Ionian),
+            Some(Mode::Locrian) => None,
+            Some(mode) => Some(Mode::from_rotation(mode.rotation() + 1)),
+        };
+    }
+
+    pub fn root_label(&self) -> &'static str {
+        self.root.map_or("Auto", |pitch_class| PITCH_NAMES[pitch_class as usize])
+    }
+
+    pub fn mode_label(&self) -> &'static str {
+        self.mode.map_or("Auto", Mode::name)
+    }
+}
+
+/// Name of a key, such as "D Dorian"
+pub fn key_name(root_hz: f32, mode: Mode) -> String {
+    format!("{} {}", PITCH_NAMES[nearest_pitch_class(root_hz) as usize], mode.name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_names_round_trip_through_the_root_octave() {
+        assert_eq!(parse_pitch_class("C"), Some(0));
+        assert_eq!(parse_pitch_class("f#"), Some(6));
+        assert_eq!(parse_pitch_class("Bb"), Some(10));
+        assert_eq!(parse_pitch_class("C♭"), Some(11));
+        assert_eq!(parse_pitch_class("H"), None);
+
+        assert_eq!(root_hz(9), 55.0);
+        for pitch_class in 0..12 {
+            let hz = root_hz(pitch_class);
+            assert!((55.0..110.0).contains(&hz));
+            assert_eq!(nearest_pitch_class(hz), pitch_class);
+        }
+        assert_eq!(key_name(73.4, Mode::Dorian), "D Dorian");
+
+        let mut key = KeySelection::from_config(Some("B"), None);
+        key.cycle_root();
+        key.cycle_mode();
+        assert_eq!(key, KeySelection { root: None, mode: Some(Mode::Ionian) });
+        assert_eq!((key.root_label(), key.mode_label()), ("Auto", "Ionian"));
+    }
+}
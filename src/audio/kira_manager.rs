@@ -1,24 +1,90 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 use kira::manager::{AudioManager, AudioManagerSettings};
+use kira::track::{TrackBuilder, TrackHandle};
+use kira::tween::Tween;
+use kira::Volume;
+
+use super::hybrid_dungeon_synth::{play_hybrid_in_kira, HybridSoundHandle};
+use super::output::OutputBackend;
+
+/// Fade on leaving the game, so the sound doesn't end in a click
+const EXIT_FADE: Duration = Duration::from_millis(80);
+
+/// A mixer track of `KiraManager`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MixTrack {
+    /// The hybrid dungeon synth
+    Synth,
+    /// The illbient groove's hits
+    Groove,
+}
 
 /// Bevy resource that stores the global Kira `AudioManager`.
 /// Other systems can fetch this via `Res<KiraManager>` to play sounds, create sub-mixes, etc.
+/// The hybrid engine and the groove each play on a track of their own.
 #[derive(Resource)]
-pub struct KiraManager(pub AudioManager);
+pub struct KiraManager {
+    pub manager: AudioManager<OutputBackend>,
+    synth: TrackHandle,
+    groove: TrackHandle,
+    /// Dropping it unloads the hybrid engine's sound
+    _hybrid: Option<HybridSoundHandle>,
+}
+
+impl KiraManager {
+    pub fn track(&self, track: MixTrack) -> &TrackHandle {
+        match track {
+            MixTrack::Synth => &self.synth,
+            MixTrack::Groove => &self.groove,
+        }
+    }
+
+    /// Set the volume of a track as an amplitude (1.0 = unchanged)
+    pub fn set_volume(&self, track: MixTrack, volume: f32) {
+        let volume = Volume::Amplitude(volume.max(0.0) as f64);
+        if let Err(e) = self.track(track).set_volume(volume, Tween::default()) {
+            eprintln!("❌ Could not set the {:?} track's volume: {e}", track);
+        }
+    }
+}
 
 /// Initialise the Kira audio backend and store it as a Bevy resource.
-/// Call this in a Startup schedule once at app launch.
+/// Call this in a Startup schedule once at app launch, before any system that starts the
+/// hybrid engine, which otherwise opens an output stream of its own.
 pub fn setup_kira(mut commands: Commands) {
-    match AudioManager::new(AudioManagerSettings::default()) {
-        Ok(mut manager) => {
-            println!("🎧 Kira audio engine initialised");
-            // Future: spawn ambient pad once custom generator is implemented.
-            commands.insert_resource(KiraManager(manager));
-        }
+    let mut manager = match AudioManager::<OutputBackend>::new(AudioManagerSettings::default()) {
+        Ok(manager) => manager,
         Err(err) => {
             eprintln!("❌ Failed to initialise Kira audio engine: {err}");
+            return;
         }
-    }
+    };
+    let tracks = manager
+        .add_sub_track(TrackBuilder::new())
+        .and_then(|synth| Ok((synth, manager.add_sub_track(TrackBuilder::new())?)));
+    let (synth, groove) = match tracks {
+        Ok(tracks) => tracks,
+        Err(err) => {
+            eprintln!("❌ Failed to create Kira mixer tracks: {err}");
+            return;
+        }
+    };
+    let hybrid = play_hybrid_in_kira(&mut manager, &synth);
+    println!("🎧 Kira audio engine initialised");
+    commands.insert_resource(KiraManager { manager, synth, groove, _hybrid: hybrid });
 }
 
-// (placeholder for future ambient generator implementation) 
\ No newline at end of file
+/// Fade everything out and shut Kira down when the app exits
+pub fn shutdown_kira(mut exits: EventReader<AppExit>, kira: Option<Res<KiraManager>>, mut commands: Commands) {
+    if exits.read().next().is_none() {
+        return;
+    }
+    let Some(kira) = kira else { return };
+    if kira.manager.pause(Tween { duration: EXIT_FADE, ..default() }).is_ok() {
+        std::thread::sleep(EXIT_FADE);
+    }
+    // Dropping the manager stops its stream
+    commands.remove_resource::<KiraManager>();
+}
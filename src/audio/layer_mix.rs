@@ -0,0 +1,117 @@
+//! Per-layer levels of the hybrid engine.
+//!
+//! The drone, the lute and bell samples, the illbient groove and the effects chain each have
+//! their own gain and can be muted on their own, or one sound layer soloed. The effects are
+//! not a sound of their own: their gain blends from the dry mix (0) to the full reverb and
+//! tape chain (1), and they stay on while another layer is soloed.
+
+/// A part of the hybrid engine's sound with its own level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudioLayer {
+    /// The cathedral drone oscillators
+    Drone,
+    /// The lute and bell samples
+    Samples,
+    /// The illbient groove's kick, hats and sub-bass
+    Groove,
+    /// The crypt reverb and tape saturation
+    Effects,
+}
+
+impl AudioLayer {
+    pub const ALL: [AudioLayer; 4] = [AudioLayer::Drone, AudioLayer::Samples, AudioLayer::Groove, AudioLayer::Effects];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AudioLayer::Drone => "Drone",
+            AudioLayer::Samples => "Samples",
+            AudioLayer::Groove => "Groove",
+            AudioLayer::Effects => "Effects",
+        }
+    }
+
+    /// Whether the layer makes sound of its own, and so can be soloed
+    pub fn is_source(self) -> bool {
+        self != AudioLayer::Effects
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Gain, mute and solo of every `AudioLayer`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerMix {
+    /// 0.0 .. 1.0 each, in `AudioLayer::ALL` order
+    pub gains: [f32; 4],
+    pub muted: [bool; 4],
+    /// Sound layer heard on its own, if any
+    pub solo: Option<AudioLayer>,
+}
+
+impl Default for LayerMix {
+    fn default() -> Self {
+        Self { gains: [1.0; 4], muted: [false; 4], solo: None }
+    }
+}
+
+impl LayerMix {
+    /// The layer's gain as heard, after mute and solo
+    pub fn gain(&self, layer: AudioLayer) -> f32 {
+        let silenced = self.muted[layer.index()] || self.solo.is_some_and(|solo| layer.is_source() && solo != layer);
+        if silenced { 0.0 } else { self.gains[layer.index()] }
+    }
+
+    /// The layer's own gain, whether or not it is heard
+    pub fn level(&self, layer: AudioLayer) -> f32 {
+        self.gains[layer.index()]
+    }
+
+    pub fn set_level(&mut self, layer: AudioLayer, gain: f32) {
+        self.gains[layer.index()] = gain.clamp(0.0, 1.0);
+    }
+
+    pub fn is_muted(&self, layer: AudioLayer) -> bool {
+        self.muted[layer.index()]
+    }
+
+    /// Mute or unmute `layer`; true if it is muted now
+    pub fn toggle_mute(&mut self, layer: AudioLayer) -> bool {
+        let muted = &mut self.muted[layer.index()];
+        *muted = !*muted;
+        *muted
+    }
+
+    /// Solo `layer`, or end its solo; true if it is soloed now. The effects can't be soloed
+    pub fn toggle_solo(&mut self, layer: AudioLayer) -> bool {
+        self.solo = if self.solo == Some(layer) || !layer.is_source() { None } else { Some(layer) };
+        self.solo == Some(layer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mute_and_solo_silence_the_right_layers() {
+        let mut mix = LayerMix::default();
+        mix.set_level(AudioLayer::Samples, 0.5);
+        assert_eq!(mix.gain(AudioLayer::Samples), 0.5);
+
+        assert!(mix.toggle_mute(AudioLayer::Drone));
+        assert_eq!(mix.gain(AudioLayer::Drone), 0.0);
+        assert_eq!(mix.level(AudioLayer::Drone), 1.0);
+
+        // Soloing the groove leaves the effects on and the muted drone muted
+        assert!(mix.toggle_solo(AudioLayer::Groove));
+        assert_eq!(AudioLayer::ALL.map(|layer| mix.gain(layer)), [0.0, 0.0, 1.0, 1.0]);
+        assert!(!mix.toggle_solo(AudioLayer::Effects));
+        assert_eq!(mix.solo, None);
+        assert!(mix.toggle_solo(AudioLayer::Samples));
+        assert!(!mix.toggle_solo(AudioLayer::Samples));
+        assert!(!mix.toggle_mute(AudioLayer::Drone));
+        assert_eq!(AudioLayer::ALL.map(|layer| mix.gain(layer)), [1.0, 0.5, 1.0, 1.0]);
+    }
+}
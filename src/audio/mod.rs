@@ -7,16 +7,22 @@ pub mod scales;
 pub mod hybrid_dungeon_synth;
 pub mod kira_manager;
 pub mod illbient_groove;
+pub mod layer_mix;
+pub mod key;
+pub mod recorder;
+pub mod dataset;
+pub mod user_samples;
+pub mod output;
+pub mod safetensors;
+pub mod spectrum;
 
-// Re-export spatial audio functions (the advanced system)
+// Re-export spatial audio functions (cluster voices over the hybrid engine)
 pub use spatial_audio::{
     init_spatial_audio,
-    update_camera_position,
-    process_spatial_audio,
-    update_spatial_population,
-    play_spatial_cell_birth,
-    play_spatial_cell_death,
+    update_spatial_audio,
+    set_spatial_audio,
     toggle_spatial_audio,
+    spatial_audio_enabled,
     has_spatial_audio,
     get_active_voice_count,
 };
@@ -24,18 +30,29 @@ pub use spatial_audio::{
 // Re-export DDSP neural audio functions
 pub use ddsp_engine::{
     GameStateFeatures,
+    init_ddsp_audio,
+    update_ddsp_audio,
+    toggle_ddsp_audio,
+    has_ddsp_audio,
+    ddsp_audio_running,
+    ddsp_model_loaded,
+    load_ddsp_model,
 };
 
 pub use ddsp_game_analysis::{
     extract_game_features,
+    latest_game_features,
 };
 
 // Re-export hybrid dungeon synth functions
 pub use hybrid_dungeon_synth::*;
 pub use hybrid_dungeon_synth::get_scale_root;
 pub use hybrid_dungeon_synth::set_hybrid_synthesis_mix;
-pub use kira_manager::{KiraManager, setup_kira};
+pub use kira_manager::{KiraManager, MixTrack, setup_kira, shutdown_kira};
 pub use illbient_groove::IllbientGroove;
+pub use key::{key_name, KeySelection, Mode};
+pub use layer_mix::{AudioLayer, LayerMix};
+pub use spectrum::{latest_spectrum, set_spectrum_analysis, Spectrum};
 
 // Re-export spatial mapping
 pub use spatial_mapping::{SpatialMapper, DroneMapper, PatternMapper};
@@ -0,0 +1,238 @@
+//! Audio device output.
+//!
+//! Everything plays through a cpal output stream on the default device, whose callback asks
+//! a render function for every buffer: normally Kira's mixer (see `kira_manager`), through
+//! `OutputBackend`, and the hybrid engine alone when Kira can't start. A cpal stream can't
+//! move between threads, so a thread of its own opens it, reports the format it got and
+//! keeps it playing until its `OutputStream` is dropped. Whatever the render function needs
+//! has to reach it without locks (see `hybrid_dungeon_synth`), or the game thread holding
+//! one would cut the sound out. The spectrum meter taps every buffer played (see `spectrum`).
+//!
+//! The sample rate and buffer size come from the config (`audio_sample_rate`,
+//! `audio_buffer_size`): smaller buffers answer the grid sooner, larger ones survive a busy
+//! machine without crackling.
+
+use std::sync::OnceLock;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{BufferSize, FromSample, SampleFormat, SizedSample, StreamConfig, SupportedBufferSize};
+use crossbeam_channel::{bounded, Sender};
+use kira::manager::backend::{Backend, Renderer};
+
+/// Sample rate asked of the device unless configured; its default rate is used if it can't
+/// do this one
+pub const PREFERRED_SAMPLE_RATE: u32 = 44100;
+/// Samples the conversion buffer starts with, enough for the usual device buffers
+const SCRATCH_SAMPLES: usize = 8192;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AudioOutputError {
+    #[error("no audio output device")]
+    NoDevice,
+    #[error("{0}")]
+    Config(#[from] cpal::DefaultStreamConfigError),
+    #[error("{0}")]
+    Build(#[from] cpal::BuildStreamError),
+    #[error("{0}")]
+    Play(#[from] cpal::PlayStreamError),
+    #[error("unsupported sample format {0}")]
+    Format(SampleFormat),
+    #[error("audio thread failed: {0}")]
+    Thread(#[from] std::io::Error),
+}
+
+/// What to ask of the device; `None` leaves a choice to it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OutputSettings {
+    pub sample_rate: Option<u32>,
+    /// Frames per buffer, clamped to what the device allows
+    pub buffer_size: Option<u32>,
+}
+
+static SETTINGS: OnceLock<OutputSettings> = OnceLock::new();
+
+/// Set what every output stream asks of the device; only the first call counts, so make it
+/// before any audio starts
+pub fn configure_output(settings: OutputSettings) {
+    let _ = SETTINGS.set(settings);
+}
+
+fn settings() -> OutputSettings {
+    SETTINGS.get().copied().unwrap_or_default()
+}
+
+/// Format of the running stream
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutputFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// A playing output stream; dropping it stops the stream
+pub struct OutputStream {
+    format: OutputFormat,
+    /// Its thread lets the stream go once this is dropped
+    _stop: Sender<()>,
+}
+
+impl OutputStream {
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+}
+
+/// Open the default output device and fill its buffers with `render(buffer, channels)`,
+/// interleaved samples in -1..1, until the returned stream is dropped
+pub fn start_output<R>(render: R) -> Result<OutputStream, AudioOutputError>
+where
+    R: FnMut(&mut [f32], usize) + Send + 'static,
+{
+    let (started, result) = bounded(1);
+    let (stop, stopped) = bounded::<()>(0);
+    std::thread::Builder::new().name("audio-output".into()).spawn(move || match open_stream(render) {
+        Ok((_stream, format)) => {
+            let _ = started.send(Ok(format));
+            // The stream plays for as long as it isn't dropped
+            let _ = stopped.recv();
+        }
+        Err(e) => {
+            let _ = started.send(Err(e));
+        }
+    })?;
+    let format = result.recv().unwrap_or_else(|_| Err(std::io::Error::other("audio thread exited").into()))?;
+    Ok(OutputStream { format, _stop: stop })
+}
+
+/// The format `start_output` would open the default device with
+pub fn probe_output() -> Result<OutputFormat, AudioOutputError> {
+    let device = cpal::default_host().default_output_device().ok_or(AudioOutputError::NoDevice)?;
+    let (config, _) = stream_config(&device)?;
+    Ok(OutputFormat { sample_rate: config.sample_rate.0, channels: config.channels })
+}
+
+/// The configured rate and buffer size, or what the device offers nearest them
+fn stream_config(device: &cpal::Device) -> Result<(StreamConfig, SampleFormat), AudioOutputError> {
+    let settings = settings();
+    let sample_rate = settings.sample_rate.unwrap_or(PREFERRED_SAMPLE_RATE);
+    let supported = device
+        .supported_output_configs()
+        .ok()
+        .and_then(|configs| {
+            configs
+                .filter(|range| {
+                    range.channels() >= 2
+                        && range.min_sample_rate().0 <= sample_rate
+                        && range.max_sample_rate().0 >= sample_rate
+                })
+                // Float output and plain stereo where the device offers them
+                .min_by_key(|range| (range.sample_format() != SampleFormat::F32, range.channels()))
+        })
+        .map(|range| range.with_sample_rate(cpal::SampleRate(sample_rate)));
+    let supported = match supported {
+        Some(supported) => supported,
+        None => {
+            if settings.sample_rate.is_some() {
+                println!("⚠️ The audio device can't play at {} Hz, using its default rate", sample_rate);
+            }
+            device.default_output_config()?
+        }
+    };
+    let mut config = supported.config();
+    if let Some(frames) = settings.buffer_size {
+        config.buffer_size = match *supported.buffer_size() {
+            SupportedBufferSize::Range { min, max } => BufferSize::Fixed(frames.clamp(min, max)),
+            SupportedBufferSize::Unknown => BufferSize::Fixed(frames),
+        };
+    }
+    Ok((config, supported.sample_format()))
+}
+
+fn open_stream<R>(render: R) -> Result<(cpal::Stream, OutputFormat), AudioOutputError>
+where
+    R: FnMut(&mut [f32], usize) + Send + 'static,
+{
+    let device = cpal::default_host().default_output_device().ok_or(AudioOutputError::NoDevice)?;
+    let (config, sample_format) = stream_config(&device)?;
+    let format = OutputFormat { sample_rate: config.sample_rate.0, channels: config.channels };
+    super::spectrum::set_sample_rate(format.sample_rate);
+    let stream = match sample_format {
+        SampleFormat::F32 => build_stream::<f32, R>(&device, &config, render)?,
+        SampleFormat::I16 => build_stream::<i16, R>(&device, &config, render)?,
+        SampleFormat::U16 => build_stream::<u16, R>(&device, &config, render)?,
+        SampleFormat::I32 => build_stream::<i32, R>(&device, &config, render)?,
+        other => return Err(AudioOutputError::Format(other)),
+    };
+    stream.play()?;
+    Ok((stream, format))
+}
+
+fn build_stream<T, R>(device: &cpal::Device, config: &StreamConfig, mut render: R) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+    R: FnMut(&mut [f32], usize) + Send + 'static,
+{
+    let channels = config.channels as usize;
+    let mut scratch = Vec::with_capacity(SCRATCH_SAMPLES);
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _| {
+            scratch.resize(data.len(), 0.0);
+            render(&mut scratch, channels);
+            super::spectrum::tap(&scratch, channels);
+            for (out, sample) in data.iter_mut().zip(&scratch) {
+                *out = T::from_sample(*sample);
+            }
+        },
+        |e| eprintln!("❌ Audio output error: {}", e),
+        None,
+    )
+}
+
+/// Write a stereo frame into an interleaved frame of any width
+pub fn write_frame(frame: &mut [f32], left: f32, right: f32) {
+    match frame {
+        [mono] => *mono = (left + right) * 0.5,
+        [l, r, rest @ ..] => {
+            *l = left;
+            *r = right;
+            rest.fill(0.0);
+        }
+        [] => {}
+    }
+}
+
+/// Kira backend on `start_output`, so Kira's mixer plays at the configured rate and buffer
+/// size (its own cpal backend always takes the device's defaults)
+pub struct OutputBackend {
+    format: OutputFormat,
+    stream: Option<OutputStream>,
+}
+
+impl Backend for OutputBackend {
+    type Settings = ();
+    type Error = AudioOutputError;
+
+    fn setup(_settings: Self::Settings) -> Result<(Self, u32), Self::Error> {
+        let format = probe_output()?;
+        Ok((Self { format, stream: None }, format.sample_rate))
+    }
+
+    fn start(&mut self, mut renderer: Renderer) -> Result<(), Self::Error> {
+        let stream = start_output(move |buffer, channels| {
+            renderer.on_start_processing();
+            for frame in buffer.chunks_mut(channels) {
+                let out = renderer.process();
+                write_frame(frame, out.left, out.right);
+            }
+        })?;
+        self.format = stream.format();
+        self.stream = Some(stream);
+        Ok(())
+    }
+}
+
+impl OutputBackend {
+    /// Format of the stream Kira plays through
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+}
@@ -0,0 +1,178 @@
+//! Recording the hybrid engine's output to WAV.
+//!
+//! While a recording runs, the audio callback copies every sample it plays into fixed-size
+//! blocks and sends them over a bounded lock-free channel to a writer thread, which encodes
+//! them as 16-bit stereo WAV, so the callback never allocates or waits on the disk or a lock;
+//! should the writer fall that far behind, new blocks are dropped. Stopping sends `None`, on
+//! which the writer finalizes the file. The synth panel's REC button starts and stops it.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::thread::JoinHandle;
+use crossbeam_channel::{bounded, Receiver, Sender};
+
+pub const RECORD_SAMPLE_RATE: u32 = 44100;
+pub const RECORD_CHANNELS: u16 = 2;
+/// Samples the audio thread collects before handing a block to the writer (even, so blocks
+/// always start on the left channel)
+const BLOCK_SAMPLES: usize = 4096;
+/// Blocks waiting for the writer before the callback drops new ones (about 12 seconds)
+const QUEUED_BLOCKS: usize = 256;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AudioRecordError {
+    #[error("a recording is already running")]
+    AlreadyRecording,
+    #[error("WAV encoding failed: {0}")]
+    Wav(#[from] hound::Error),
+}
+
+/// A finished recording
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub path: PathBuf,
+    pub seconds: f32,
+}
+
+/// The writer of the running recording
+static SESSION: Mutex<Option<JoinHandle<Result<Recording, AudioRecordError>>>> = Mutex::new(None);
+type Channel<T> = (Sender<T>, Receiver<T>);
+
+/// Blocks on their way from the audio callback to the writer, copied by value so sending one
+/// doesn't allocate; `None` ends the recording
+static BLOCKS: LazyLock<Channel<Option<[f32; BLOCK_SAMPLES]>>> = LazyLock::new(|| bounded(QUEUED_BLOCKS));
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static RECORDED_SAMPLES: AtomicU64 = AtomicU64::new(0);
+/// Rate the engine renders at, which is the output device's
+static SAMPLE_RATE: AtomicU32 = AtomicU32::new(RECORD_SAMPLE_RATE);
+
+/// Record at `sample_rate` from now on, when the device plays at something other than
+/// `RECORD_SAMPLE_RATE`
+pub fn set_sample_rate(sample_rate: u32) {
+    SAMPLE_RATE.store(sample_rate, Ordering::Relaxed);
+}
+
+fn seconds(samples: u64) -> f32 {
+    samples as f32 / (SAMPLE_RATE.load(Ordering::Relaxed) * RECORD_CHANNELS as u32) as f32
+}
+
+/// Start writing the engine's output to the WAV file at `path`
+pub fn start_recording(path: &Path) -> Result<(), AudioRecordError> {
+    let mut session = SESSION.lock().unwrap();
+    if session.is_some() {
+        return Err(AudioRecordError::AlreadyRecording);
+    }
+    let spec = hound::WavSpec {
+        channels: RECORD_CHANNELS,
+        sample_rate: SAMPLE_RATE.load(Ordering::Relaxed),
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut wav = hound::WavWriter::create(path, spec)?;
+    // Blocks the callback sent just after the last recording stopped
+    while BLOCKS.1.try_recv().is_ok() {}
+    let received = BLOCKS.1.clone();
+    let path = path.to_path_buf();
+    let writer = std::thread::spawn(move || {
+        let mut samples = 0u64;
+        for block in received.iter().map_while(|block| block) {
+            for sample in &block {
+                wav.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+            }
+            samples += block.len() as u64;
+        }
+        wav.finalize()?;
+        Ok(Recording { path, seconds: seconds(samples) })
+    });
+    RECORDED_SAMPLES.store(0, Ordering::Relaxed);
+    *session = Some(writer);
+    RECORDING.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stop the recording, if one is running; the returned writer finishes the file
+pub fn stop_recording() -> Option<JoinHandle<Result<Recording, AudioRecordError>>> {
+    RECORDING.store(false, Ordering::Relaxed);
+    let writer = SESSION.lock().unwrap().take()?;
+    // Ends the writer's loop, once it has taken the blocks queued before it
+    let _ = BLOCKS.0.send(None);
+    Some(writer)
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.load(Ordering::Relaxed)
+}
+
+/// Length of the running recording so far
+pub fn recorded_seconds() -> f32 {
+    seconds(RECORDED_SAMPLES.load(Ordering::Relaxed))
+}
+
+/// The audio thread's end of a recording: collects interleaved output samples into blocks
+pub struct Tap {
+    block: [f32; BLOCK_SAMPLES],
+    len: usize,
+    /// Samples seen since the stream started, to tell left from right
+    position: u64,
+}
+
+impl Default for Tap {
+    fn default() -> Self {
+        Self { block: [0.0; BLOCK_SAMPLES], len: 0, position: 0 }
+    }
+}
+
+impl Tap {
+    /// Copy one interleaved output sample into the recording, if one is running
+    pub fn push(&mut self, sample: f32) {
+        let left = self.position.is_multiple_of(2);
+        self.position += 1;
+        if !is_recording() {
+            self.len = 0;
+            return;
+        }
+        // Start on a left sample so the channels don't swap
+        if self.len == 0 && !left {
+            return;
+        }
+        self.block[self.len] = sample;
+        self.len += 1;
+        if self.len == BLOCK_SAMPLES {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if BLOCKS.0.try_send(Some(self.block)).is_ok() {
+            RECORDED_SAMPLES.fetch_add(BLOCK_SAMPLES as u64, Ordering::Relaxed);
+        }
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tapped_blocks_end_up_in_the_wav_starting_on_the_left_channel() {
+        let path = std::env::temp_dir().join(format!("gameofdeath-recorder-{}.wav", std::process::id()));
+        let mut tap = Tap::default();
+        tap.push(0.9);
+        start_recording(&path).unwrap();
+        assert!(matches!(start_recording(&path), Err(AudioRecordError::AlreadyRecording)));
+        // A right sample first, which is skipped, then a full block and a partial one
+        for i in 0..BLOCK_SAMPLES + 11 {
+            tap.push(if i % 2 == 0 { -0.5 } else { 0.5 });
+        }
+        let recording = stop_recording().unwrap().join().unwrap().unwrap();
+        assert!(!is_recording());
+
+        let samples: Vec<i16> = hound::WavReader::open(&path).unwrap().into_samples().map(Result::unwrap).collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(samples.len(), BLOCK_SAMPLES);
+        assert_eq!(&samples[..2], &[16383, -16383]);
+        assert_eq!(recording.seconds, seconds(BLOCK_SAMPLES as u64));
+    }
+}
@@ -0,0 +1,146 @@
+//! Tensors in the safetensors format.
+//!
+//! The format PyTorch, JAX and TensorFlow users save weights in (`safetensors.torch.save_file`
+//! and the like): an 8-byte little-endian header length, a JSON header giving each tensor's
+//! dtype, shape and byte range, then the raw little-endian data. Float tensors are read as
+//! f32; the header's `__metadata__` is skipped. Tensors are written as F32, with string
+//! metadata if given.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use serde::Deserialize;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TensorFileError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("bad header: {0}")]
+    Header(#[from] serde_json::Error),
+    #[error("file ends before its header says it does")]
+    Truncated,
+    #[error("tensor {name} is {dtype}; only F32 and F64 tensors can be read")]
+    Dtype { name: String, dtype: String },
+    #[error("tensor {0} doesn't hold as many values as its shape")]
+    Size(String),
+}
+
+/// A float tensor, its values in row-major order
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tensor {
+    pub shape: Vec<usize>,
+    pub data: Vec<f32>,
+}
+
+#[derive(Deserialize)]
+struct HeaderEntry {
+    dtype: String,
+    shape: Vec<usize>,
+    data_offsets: [usize; 2],
+}
+
+/// Every tensor in the safetensors file at `path`, by name
+pub fn read_tensors(path: &Path) -> Result<HashMap<String, Tensor>, TensorFileError> {
+    parse_tensors(&std::fs::read(path)?)
+}
+
+/// Every tensor in a safetensors file's bytes, by name
+pub fn parse_tensors(bytes: &[u8]) -> Result<HashMap<String, Tensor>, TensorFileError> {
+    let length = bytes.get(..8).ok_or(TensorFileError::Truncated)?;
+    let length = u64::from_le_bytes(length.try_into().unwrap()) as usize;
+    let header = bytes.get(8..8usize.saturating_add(length)).ok_or(TensorFileError::Truncated)?;
+    let data = &bytes[8 + length..];
+    let entries: HashMap<String, serde_json::Value> = serde_json::from_slice(header)?;
+    let mut tensors = HashMap::new();
+    for (name, entry) in entries {
+        if name == "__metadata__" {
+            continue;
+        }
+        let entry: HeaderEntry = serde_json::from_value(entry)?;
+        let [begin, end] = entry.data_offsets;
+        let raw = data.get(begin..end).ok_or(TensorFileError::Truncated)?;
+        let values: Vec<f32> = match entry.dtype.as_str() {
+            "F32" => raw.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect(),
+            "F64" => raw.chunks_exact(8).map(|b| f64::from_le_bytes(b.try_into().unwrap()) as f32).collect(),
+            _ => return Err(TensorFileError::Dtype { name, dtype: entry.dtype }),
+        };
+        if values.len() != entry.shape.iter().product::<usize>() {
+            return Err(TensorFileError::Size(name));
+        }
+        tensors.insert(name, Tensor { shape: entry.shape, data: values });
+    }
+    Ok(tensors)
+}
+
+/// Write `tensors` in the safetensors format, with `metadata` in the header
+pub fn write_tensors(mut out: impl Write, tensors: &[(&str, Tensor)], metadata: &[(&str, &str)]) -> Result<(), TensorFileError> {
+    let mut header = serde_json::Map::new();
+    if !metadata.is_empty() {
+        let entries = metadata.iter().map(|(key, value)| (key.to_string(), serde_json::Value::from(*value)));
+        header.insert("__metadata__".to_string(), serde_json::Value::Object(entries.collect()));
+    }
+    let mut offset = 0;
+    for (name, tensor) in tensors {
+        if tensor.data.len() != tensor.shape.iter().product::<usize>() {
+            return Err(TensorFileError::Size(name.to_string()));
+        }
+        let end = offset + tensor.data.len() * 4;
+        let entry = serde_json::json!({ "dtype": "F32", "shape": tensor.shape, "data_offsets": [offset, end] });
+        header.insert(name.to_string(), entry);
+        offset = end;
+    }
+    let mut header = serde_json::to_vec(&header)?;
+    // Padded with spaces so the data starts 8-byte aligned
+    header.resize(header.len().next_multiple_of(8), b' ');
+    out.write_all(&(header.len() as u64).to_le_bytes())?;
+    out.write_all(&header)?;
+    for (_, tensor) in tensors {
+        for value in &tensor.data {
+            out.write_all(&value.to_le_bytes())?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tensors_are_read_from_safetensors_bytes() {
+        let header = r#"{"__metadata__":{"format":"pt"},"w":{"dtype":"F32","shape":[2,2],"data_offsets":[0,16]},"b":{"dtype":"F64","shape":[1],"data_offsets":[16,24]}}"#;
+        let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(header.as_bytes());
+        for value in [1.0f32, 2.0, 3.0, 4.0] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&0.5f64.to_le_bytes());
+        let tensors = parse_tensors(&bytes).unwrap();
+        assert_eq!(tensors.len(), 2);
+        assert_eq!(tensors["w"], Tensor { shape: vec![2, 2], data: vec![1.0, 2.0, 3.0, 4.0] });
+        assert_eq!(tensors["b"].data, vec![0.5]);
+
+        assert!(matches!(parse_tensors(&bytes[..bytes.len() - 4]), Err(TensorFileError::Truncated)));
+        assert!(matches!(parse_tensors(&bytes[..6]), Err(TensorFileError::Truncated)));
+    }
+
+    #[test]
+    fn written_tensors_read_back() {
+        let tensors = [
+            ("features", Tensor { shape: vec![2, 3], data: vec![0.1, 0.2, 0.3, 0.4, 0.5, 0.6] }),
+            ("audio", Tensor { shape: vec![0, 4], data: Vec::new() }),
+        ];
+        let mut bytes = Vec::new();
+        write_tensors(&mut bytes, &tensors, &[("sample_rate", "48000")]).unwrap();
+        let header_length = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        assert_eq!(header_length % 8, 0);
+        assert!(String::from_utf8_lossy(&bytes).contains(r#""sample_rate":"48000""#));
+        let read = parse_tensors(&bytes).unwrap();
+        assert_eq!(read["features"], tensors[0].1);
+        assert_eq!(read["audio"], tensors[1].1);
+
+        let misshapen = [("w", Tensor { shape: vec![2], data: vec![1.0] })];
+        assert!(matches!(write_tensors(Vec::new(), &misshapen, &[]), Err(TensorFileError::Size(name)) if name == "w"));
+    }
+}
@@ -0,0 +1,210 @@
+//! Spectrum of the master output.
+//!
+//! While the spectrum meter is shown, the output callback (see `output`) mixes each buffer it
+//! plays down to mono in fixed-size blocks on the stack, so it never allocates, and sends
+//! them over a lock-free channel to an analyser thread. That
+//! thread keeps the last `FFT_SIZE` samples in a ring buffer and, a few dozen times a second,
+//! turns them into levels for `BANDS` log-spaced bands with an FFT, plus the RMS and peak
+//! level. The game reads the newest result with `latest_spectrum`. The callback never waits:
+//! blocks the analyser has no room for are dropped, and it only wants the newest ones.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, Once};
+use std::time::{Duration, Instant};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use dasp_ring_buffer::Fixed;
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+use super::output::PREFERRED_SAMPLE_RATE;
+
+/// Samples in each analysis (about 46 ms at 44.1 kHz)
+pub const FFT_SIZE: usize = 2048;
+/// Bands the spectrum is shown in
+pub const BANDS: usize = 24;
+/// Edges of the lowest and highest band
+const LOW_HZ: f32 = 40.0;
+const HIGH_HZ: f32 = 16000.0;
+/// Level shown for silence, and the lowest any level goes
+pub const FLOOR_DB: f32 = -80.0;
+/// Time between analyses
+const ANALYSIS_INTERVAL: Duration = Duration::from_millis(33);
+/// Mono samples in each block the callback sends
+const BLOCK_SIZE: usize = 256;
+/// Blocks waiting for the analyser before the callback drops new ones
+const QUEUED_BLOCKS: usize = 64;
+
+/// Levels of the output, in dB relative to full scale
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spectrum {
+    /// From the lowest band up
+    pub bands: [f32; BANDS],
+    pub rms_db: f32,
+    pub peak_db: f32,
+}
+
+impl Spectrum {
+    pub const SILENT: Spectrum = Spectrum { bands: [FLOOR_DB; BANDS], rms_db: FLOOR_DB, peak_db: FLOOR_DB };
+}
+
+impl Default for Spectrum {
+    fn default() -> Self {
+        Self::SILENT
+    }
+}
+
+type Channel<T> = (Sender<T>, Receiver<T>);
+
+/// Up to `BLOCK_SIZE` mono samples, copied by value so sending one doesn't allocate
+#[derive(Clone, Copy)]
+struct Block {
+    samples: [f32; BLOCK_SIZE],
+    len: usize,
+}
+
+/// Mono blocks on their way from the audio callback to the analyser
+static BLOCKS: LazyLock<Channel<Block>> = LazyLock::new(|| bounded(QUEUED_BLOCKS));
+static ANALYSING: AtomicBool = AtomicBool::new(false);
+static SAMPLE_RATE: AtomicU32 = AtomicU32::new(PREFERRED_SAMPLE_RATE);
+static LATEST: Mutex<Spectrum> = Mutex::new(Spectrum::SILENT);
+static ANALYSER: Once = Once::new();
+
+/// Analyse at `sample_rate` from now on; the output stream sets it once it is open
+pub fn set_sample_rate(sample_rate: u32) {
+    SAMPLE_RATE.store(sample_rate, Ordering::Relaxed);
+}
+
+/// Start or stop analysing the output; the analyser thread starts the first time
+pub fn set_spectrum_analysis(enabled: bool) {
+    if enabled {
+        ANALYSER.call_once(|| {
+            let received = BLOCKS.1.clone();
+            if let Err(e) = std::thread::Builder::new().name("spectrum".into()).spawn(move || analyse_blocks(received)) {
+                eprintln!("❌ Could not start the spectrum analyser: {}", e);
+            }
+        });
+    } else {
+        *LATEST.lock().unwrap() = Spectrum::SILENT;
+    }
+    ANALYSING.store(enabled, Ordering::Relaxed);
+}
+
+/// The last analysis, or silence if none ran
+pub fn latest_spectrum() -> Spectrum {
+    *LATEST.lock().unwrap()
+}
+
+/// Hand `buffer`, interleaved with `channels` channels, to the analyser if it is running.
+/// Called from the audio callback.
+pub fn tap(buffer: &[f32], channels: usize) {
+    if channels == 0 || !ANALYSING.load(Ordering::Relaxed) {
+        return;
+    }
+    for frames in buffer.chunks(channels * BLOCK_SIZE) {
+        let mut block = Block { samples: [0.0; BLOCK_SIZE], len: 0 };
+        for (sample, frame) in block.samples.iter_mut().zip(frames.chunks(channels)) {
+            *sample = frame.iter().sum::<f32>() / channels as f32;
+            block.len += 1;
+        }
+        let _ = BLOCKS.0.try_send(block);
+    }
+}
+
+fn analyse_blocks(received: Receiver<Block>) {
+    let mut ring = Fixed::from(vec![0.0f32; FFT_SIZE]);
+    let mut analyser = Analyser::new();
+    let mut last = Instant::now();
+    for block in received.iter() {
+        for &sample in &block.samples[..block.len] {
+            ring.push(sample);
+        }
+        if last.elapsed() < ANALYSIS_INTERVAL || !ANALYSING.load(Ordering::Relaxed) {
+            continue;
+        }
+        last = Instant::now();
+        let spectrum = analyser.analyse(ring.iter().copied(), SAMPLE_RATE.load(Ordering::Relaxed));
+        *LATEST.lock().unwrap() = spectrum;
+    }
+}
+
+/// Lower and upper edge of `band` in Hz
+fn band_edges(band: usize) -> (f32, f32) {
+    let edge = |band: usize| LOW_HZ * (HIGH_HZ / LOW_HZ).powf(band as f32 / BANDS as f32);
+    (edge(band), edge(band + 1))
+}
+
+fn to_db(amplitude: f32) -> f32 {
+    if amplitude > 0.0 {
+        (20.0 * amplitude.log10()).max(FLOOR_DB)
+    } else {
+        FLOOR_DB
+    }
+}
+
+struct Analyser {
+    fft: Arc<dyn Fft<f32>>,
+    /// Hann window, so a tone between two bins doesn't smear over the whole spectrum
+    window: Vec<f32>,
+    window_sum: f32,
+    buffer: Vec<Complex<f32>>,
+}
+
+impl Analyser {
+    fn new() -> Self {
+        let window: Vec<f32> = (0..FFT_SIZE)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FFT_SIZE as f32).cos())
+            .collect();
+        Self {
+            fft: FftPlanner::new().plan_fft_forward(FFT_SIZE),
+            window_sum: window.iter().sum(),
+            window,
+            buffer: vec![Complex::default(); FFT_SIZE],
+        }
+    }
+
+    /// Levels of the last `FFT_SIZE` samples, oldest first
+    fn analyse(&mut self, samples: impl Iterator<Item = f32>, sample_rate: u32) -> Spectrum {
+        let (mut squares, mut peak) = (0.0f32, 0.0f32);
+        for ((slot, sample), weight) in self.buffer.iter_mut().zip(samples).zip(&self.window) {
+            squares += sample * sample;
+            peak = peak.max(sample.abs());
+            *slot = Complex::new(sample * weight, 0.0);
+        }
+        self.fft.process(&mut self.buffer);
+
+        let bin_hz = sample_rate as f32 / FFT_SIZE as f32;
+        let bins = &self.buffer[..FFT_SIZE / 2];
+        let mut bands = [FLOOR_DB; BANDS];
+        for (band, level) in bands.iter_mut().enumerate() {
+            let (low, high) = band_edges(band);
+            let first = ((low / bin_hz).round() as usize).min(bins.len());
+            // Low bands are narrower than a bin; they take the nearest one
+            let last = ((high / bin_hz).round() as usize).clamp(first + 1, bins.len());
+            let strongest = bins.get(first..last).into_iter().flatten().map(|bin| bin.norm()).fold(0.0, f32::max);
+            // Scaled so a full-scale sine reads 0 dB
+            *level = to_db(strongest * 2.0 / self.window_sum);
+        }
+        Spectrum { bands, rms_db: to_db((squares / FFT_SIZE as f32).sqrt()), peak_db: to_db(peak) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_sine_lights_up_its_band_and_silence_reads_the_floor() {
+        let sample_rate = 44100;
+        let sine = (0..FFT_SIZE).map(|i| (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / sample_rate as f32).sin());
+        let spectrum = Analyser::new().analyse(sine, sample_rate);
+        let band = (0..BANDS).find(|&band| band_edges(band).1 > 1000.0).unwrap();
+        let loudest = (0..BANDS).max_by(|&a, &b| spectrum.bands[a].total_cmp(&spectrum.bands[b])).unwrap();
+        assert_eq!(loudest, band);
+        assert!(spectrum.bands[band] > -2.0 && spectrum.bands[band] < 1.0, "{:?}", spectrum);
+        assert!((spectrum.rms_db + 3.0).abs() < 0.5, "{}", spectrum.rms_db);
+        assert!(spectrum.peak_db > -0.1);
+
+        let silence = Analyser::new().analyse(std::iter::repeat_n(0.0, FFT_SIZE), sample_rate);
+        assert_eq!(silence, Spectrum::SILENT);
+    }
+}
@@ -0,0 +1,225 @@
+//! User samples for the hybrid engine's sample bank.
+//!
+//! Besides the lute and bell samples it generates, the engine plays recordings found in the
+//! `sample_dir` folder, one subfolder per role:
+//!
+//! * `pluck/` — played wherever the engine plucks a lute
+//! * `bell/` — played wherever it strikes a bell
+//! * `pad/` — held under the milestone bell every `BELL_INTERVAL` generations, one at a time
+//!
+//! Files may be WAV or OGG, at any sample rate and channel count; they are mixed down to mono
+//! at 44.1 kHz, normalized and cut to their role's `max_seconds`. A note name or frequency at
+//! the end of the file name (`lute_A3.wav`, `bell-440hz.ogg`) gives the pitch the engine
+//! transposes from; without one the role's `default_frequency` is assumed. With
+//! `SampleMode::Layer` the recordings join the generated samples of their role, with
+//! `SampleMode::Replace` they take their place. Samples are read once at startup and used by
+//! every engine created after that.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use super::key::parse_pitch_class;
+
+/// Sample rate the sample bank plays at
+pub const BANK_SAMPLE_RATE: u32 = 44100;
+/// Peak level samples are normalized to, about that of the generated lute
+const PEAK: f32 = 0.5;
+
+/// What a sample is played as
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleRole {
+    Pluck,
+    Bell,
+    Pad,
+}
+
+impl SampleRole {
+    pub const ALL: [SampleRole; 3] = [SampleRole::Pluck, SampleRole::Bell, SampleRole::Pad];
+
+    /// Subfolder of `sample_dir` the role's files are read from
+    pub fn folder(self) -> &'static str {
+        match self {
+            SampleRole::Pluck => "pluck",
+            SampleRole::Bell => "bell",
+            SampleRole::Pad => "pad",
+        }
+    }
+
+    /// Pitch assumed for files whose name doesn't give one
+    pub fn default_frequency(self) -> f32 {
+        match self {
+            SampleRole::Pluck => 220.0,
+            SampleRole::Bell => 130.81,
+            SampleRole::Pad => 110.0,
+        }
+    }
+
+    /// Longest stretch of a file that is kept
+    pub fn max_seconds(self) -> f32 {
+        match self {
+            SampleRole::Pluck => 3.0,
+            SampleRole::Bell => 5.0,
+            SampleRole::Pad => 8.0,
+        }
+    }
+}
+
+/// Whether user samples join or replace the generated ones of their role
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SampleMode {
+    #[default]
+    Layer,
+    Replace,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SampleLoadError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Decode(#[from] rodio::decoder::DecoderError),
+    #[error("no audio in file")]
+    Empty,
+}
+
+/// A decoded user sample
+#[derive(Clone, Debug)]
+pub struct UserSample {
+    pub role: SampleRole,
+    /// Mono samples at `BANK_SAMPLE_RATE`
+    pub data: Vec<f32>,
+    pub base_frequency: f32,
+}
+
+/// Samples read at startup, and how they combine with the generated ones
+static LOADED: Mutex<(Vec<UserSample>, SampleMode)> = Mutex::new((Vec::new(), SampleMode::Layer));
+
+/// Pitch named at the end of a file stem: a note with octave ("A3", "F#2", "Bb4") or a
+/// frequency ("440hz"), after the last `_`, `-` or space
+pub fn base_frequency_from_name(stem: &str) -> Option<f32> {
+    let tag = stem.rsplit(['_', '-', ' ']).next()?;
+    if let Some(hz) = tag.strip_suffix("hz").or_else(|| tag.strip_suffix("Hz")) {
+        return hz.parse::<f32>().ok().filter(|hz| *hz > 0.0);
+    }
+    let octave_at = tag.find(|c: char| c.is_ascii_digit())?;
+    let pitch_class = parse_pitch_class(&tag[..octave_at])? as i32;
+    let octave: i32 = tag[octave_at..].parse().ok()?;
+    let midi_note = (octave + 1) * 12 + pitch_class;
+    Some(440.0 * 2f32.powf((midi_note - 69) as f32 / 12.0))
+}
+
+/// Interleaved samples mixed down to mono and resampled to `BANK_SAMPLE_RATE` by linear
+/// interpolation, keeping at most `max_seconds`
+pub fn to_bank_format(samples: &[i16], channels: u16, sample_rate: u32, max_seconds: f32) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    let mono: Vec<f32> = samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().map(|&s| s as f32 / i16::MAX as f32).sum::<f32>() / channels as f32)
+        .collect();
+    let step = sample_rate.max(1) as f32 / BANK_SAMPLE_RATE as f32;
+    let length = ((mono.len() as f32 / step) as usize).min((max_seconds * BANK_SAMPLE_RATE as f32) as usize);
+    (0..length)
+        .map(|i| {
+            let position = i as f32 * step;
+            let index = position as usize;
+            let next = mono.get(index + 1).copied().unwrap_or(0.0);
+            let fraction = position - index as f32;
+            mono[index] * (1.0 - fraction) + next * fraction
+        })
+        .collect()
+}
+
+/// Decode one WAV or OGG file for `role`
+pub fn load_sample(path: &Path, role: SampleRole) -> Result<UserSample, SampleLoadError> {
+    use rodio::Source;
+    let decoder = rodio::Decoder::new(BufReader::new(File::open(path)?))?;
+    let (channels, sample_rate) = (decoder.channels(), decoder.sample_rate());
+    let max_frames = (role.max_seconds() * sample_rate as f32) as usize + 1;
+    let samples: Vec<i16> = decoder.take(max_frames * channels as usize).collect();
+    let mut data = to_bank_format(&samples, channels, sample_rate, role.max_seconds());
+    let peak = data.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    if peak <= f32::EPSILON {
+        return Err(SampleLoadError::Empty);
+    }
+    for sample in &mut data {
+        *sample *= PEAK / peak;
+    }
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+    let base_frequency = base_frequency_from_name(stem).unwrap_or(role.default_frequency());
+    Ok(UserSample { role, data, base_frequency })
+}
+
+/// Read every WAV and OGG file in the role folders of `dir` and hand them to the engine.
+/// Returns how many were loaded and the files that couldn't be; a missing folder just
+/// loads nothing.
+pub fn load_user_samples(dir: &Path, mode: SampleMode) -> (usize, Vec<(PathBuf, SampleLoadError)>) {
+    let mut samples = Vec::new();
+    let mut failed = Vec::new();
+    for role in SampleRole::ALL {
+        let Ok(entries) = std::fs::read_dir(dir.join(role.folder())) else { continue };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+                matches!(extension.as_str(), "wav" | "ogg")
+            })
+            .collect();
+        paths.sort();
+        for path in paths {
+            match load_sample(&path, role) {
+                Ok(sample) => samples.push(sample),
+                Err(e) => failed.push((path, e)),
+            }
+        }
+    }
+    let count = samples.len();
+    *LOADED.lock().unwrap() = (samples, mode);
+    (count, failed)
+}
+
+/// The user samples of `role`, and whether they replace the generated ones (only when there
+/// are any)
+pub(super) fn user_samples(role: SampleRole) -> (Vec<UserSample>, bool) {
+    let loaded = LOADED.lock().unwrap();
+    let samples: Vec<UserSample> = loaded.0.iter().filter(|sample| sample.role == role).cloned().collect();
+    let replace = loaded.1 == SampleMode::Replace && !samples.is_empty();
+    (samples, replace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_give_the_pitch_and_audio_is_brought_to_the_bank_format() {
+        assert_eq!(base_frequency_from_name("lute_A4"), Some(440.0));
+        assert!((base_frequency_from_name("my bell C4").unwrap() - 261.63).abs() < 0.01);
+        assert!((base_frequency_from_name("harp-F#2").unwrap() - 92.5).abs() < 0.01);
+        assert_eq!(base_frequency_from_name("pad_110hz"), Some(110.0));
+        assert_eq!(base_frequency_from_name("pluck"), None);
+        assert_eq!(base_frequency_from_name("take_2"), None);
+
+        // Stereo frames are averaged, 22.05 kHz is interpolated up to twice the length
+        let stereo = [i16::MAX, 0, i16::MAX, i16::MAX];
+        let data = to_bank_format(&stereo, 2, 22050, 1.0);
+        assert_eq!(data.len(), 4);
+        assert_eq!(&data[..3], &[0.5, 0.75, 1.0]);
+        assert_eq!(to_bank_format(&[0; 88200], 1, 44100, 1.5).len(), 66150);
+
+        let path = std::env::temp_dir().join(format!("gameofdeath-sample-{}_A4.wav", std::process::id()));
+        let spec = hound::WavSpec { channels: 1, sample_rate: 22050, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut wav = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..22050 * 10 {
+            wav.write_sample(if i % 50 < 25 { 8000i16 } else { -8000 }).unwrap();
+        }
+        wav.finalize().unwrap();
+        let sample = load_sample(&path, SampleRole::Pluck).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(sample.base_frequency, 440.0);
+        assert_eq!(sample.data.len(), (SampleRole::Pluck.max_seconds() * BANK_SAMPLE_RATE as f32) as usize);
+        assert!((sample.data.iter().fold(0.0f32, |peak, s| peak.max(s.abs())) - PEAK).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,143 @@
+//! Audio section of the start screen.
+//!
+//! A small panel in the top-right corner of the start screen picks the audio engine, sets
+//! the volume and mutes, so none of it needs the config file. Changes go to `GameConfig`
+//! (and the saved preferences) and take effect at once, on the start screen's own ambient
+//! music. M mutes and unmutes; changing the volume unmutes, as the in-game keys do.
+
+use bevy::prelude::*;
+use crate::preferences::Preferences;
+use crate::settings_menu::{apply_audio_engine, step_engine, step_volume};
+use crate::start_screen::GameState;
+use crate::theme::StartScreenColors;
+use crate::tr;
+use crate::GameConfig;
+
+#[derive(Component, Clone, Copy)]
+enum AudioButton {
+    Engine(i32),
+    Volume(i32),
+    Mute,
+}
+
+/// Text showing one of the settings
+#[derive(Component, Clone, Copy)]
+enum AudioValue {
+    Engine,
+    Volume,
+    Mute,
+}
+
+/// The engine, volume and mute controls, in their corner of the start screen
+pub fn spawn_audio_picker(parent: &mut ChildBuilder, font: &Handle<Font>, palette: &StartScreenColors) {
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+    // A button with `label` on it, or the current value of the setting `value` shows
+    let button = |parent: &mut ChildBuilder, action: AudioButton, width: f32, label: &str, value: Option<AudioValue>| {
+        parent
+            .spawn((
+                Button,
+                Node {
+                    width: Val::Px(width),
+                    height: Val::Px(26.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..default()
+                },
+                BackgroundColor(palette.button.0),
+                BorderColor(palette.button_border.0),
+                action,
+            ))
+            .with_children(|button| {
+                let mut label = button.spawn((Text::new(label), text(16.0), TextColor(palette.accent.0)));
+                if let Some(value) = value {
+                    label.insert(value);
+                }
+            });
+    };
+    let row = || Node { flex_direction: FlexDirection::Row, align_items: AlignItems::Center, column_gap: Val::Px(8.0), ..default() };
+    let value = |value: AudioValue| (Text::new(""), text(16.0), TextColor(palette.accent.0), value);
+    parent
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                right: Val::Px(20.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            BackgroundColor(palette.panel.0),
+            BorderColor(palette.panel_border.0),
+        ))
+        .with_children(|panel| {
+            panel.spawn((Text::new(tr!("AUDIO")), text(18.0), TextColor(palette.heading.0)));
+            panel.spawn(row()).with_children(|row| {
+                button(row, AudioButton::Engine(-1), 26.0, "<", None);
+                row.spawn((Node { width: Val::Px(110.0), justify_content: JustifyContent::Center, ..default() },))
+                    .with_child(value(AudioValue::Engine));
+                button(row, AudioButton::Engine(1), 26.0, ">", None);
+            });
+            panel.spawn(row()).with_children(|row| {
+                button(row, AudioButton::Volume(-1), 26.0, "-", None);
+                row.spawn((Node { width: Val::Px(110.0), justify_content: JustifyContent::Center, ..default() },))
+                    .with_child(value(AudioValue::Volume));
+                button(row, AudioButton::Volume(1), 26.0, "+", None);
+            });
+            button(panel, AudioButton::Mute, 110.0, "", Some(AudioValue::Mute));
+        });
+}
+
+pub struct AudioPickerPlugin;
+
+impl Plugin for AudioPickerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (audio_picker_input, update_audio_picker).chain().run_if(in_state(GameState::StartScreen)));
+    }
+}
+
+fn audio_picker_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    buttons: Query<(&Interaction, &AudioButton), Changed<Interaction>>,
+    mut game_config: ResMut<GameConfig>,
+    mut preferences: ResMut<Preferences>,
+) {
+    let mut pressed: Vec<AudioButton> =
+        buttons.iter().filter(|(interaction, _)| **interaction == Interaction::Pressed).map(|(_, &button)| button).collect();
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        pressed.push(AudioButton::Mute);
+    }
+    for button in pressed {
+        match button {
+            AudioButton::Engine(steps) => {
+                game_config.audio_engine = step_engine(game_config.audio_engine, steps);
+                preferences.config.audio_engine = game_config.audio_engine;
+            }
+            AudioButton::Volume(steps) => {
+                game_config.audio_volume = step_volume(game_config.audio_volume, steps);
+                game_config.audio_muted = false;
+            }
+            AudioButton::Mute => game_config.audio_muted = !game_config.audio_muted,
+        }
+        preferences.config.audio_volume = game_config.audio_volume;
+        preferences.config.audio_muted = game_config.audio_muted;
+        apply_audio_engine(game_config.audio_engine, game_config.output_volume());
+    }
+}
+
+fn update_audio_picker(game_config: Res<GameConfig>, mut values: Query<(&AudioValue, &mut Text)>) {
+    for (value, mut text) in &mut values {
+        let shown = match value {
+            AudioValue::Engine => game_config.audio_engine.name().to_string(),
+            AudioValue::Volume => format!("{:.0}%", game_config.audio_volume * 100.0),
+            AudioValue::Mute if game_config.audio_muted => tr!("Unmute"),
+            AudioValue::Mute => tr!("Mute"),
+        };
+        if text.0 != shown {
+            text.0 = shown;
+        }
+    }
+}
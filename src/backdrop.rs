@@ -0,0 +1,295 @@
+//! Parallax backdrop.
+//!
+//! A theme can put a starfield or drifting fog behind the grid with a `[backdrop]` section.
+//! The backdrop is drawn in two layers at screen scale, a near one and a dimmer far one, that
+//! follow the camera at a fraction (`parallax`) of the grid's movement, so panning the
+//! infinite plane gives it some depth. The layers also drift slowly on their own, except in
+//! reduced-motion mode. Each
+//! layer is a small seamless texture generated once per theme and tiled across the window;
+//! keep `color` faint so cells stay readable over it.
+
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+use crate::camera::GameCamera;
+use crate::cell_renderer::CellRenderConfig;
+use crate::start_screen::{GameState, InGame};
+use crate::theme::{Theme, ThemeColor};
+
+/// Side of a backdrop texture tile, in screen pixels
+pub const TILE: u32 = 256;
+
+/// Stars in one tile of the near layer at density 1
+const STARS_PER_TILE: f32 = 90.0;
+
+/// Fog noise lattice cells per tile side, coarse octave first
+const FOG_OCTAVES: [u32; 2] = [4, 8];
+
+/// Depth of each layer: the fraction of `parallax` and `drift` it moves by, and its opacity
+const LAYERS: [f32; 2] = [1.0, 0.5];
+
+/// Behind the activity heat (z = -2) and everything else
+const BACKDROP_Z: f32 = -10.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackdropStyle {
+    #[default]
+    None,
+    Stars,
+    Fog,
+}
+
+/// The `[backdrop]` section of a theme
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Backdrop {
+    pub style: BackdropStyle,
+    /// Tint of the stars or fog, alpha included
+    pub color: ThemeColor,
+    /// How much the near layer follows the grid: 0 stays put on screen, 1 moves with the cells
+    pub parallax: f32,
+    /// Star count or fog thickness, 1 being the usual amount
+    pub density: f32,
+    /// Screen pixels per second the near layer drifts by itself
+    pub drift: [f32; 2],
+}
+
+impl Default for Backdrop {
+    fn default() -> Self {
+        Self {
+            style: BackdropStyle::None,
+            color: ThemeColor::srgba(1.0, 1.0, 1.0, 0.5),
+            parallax: 0.2,
+            density: 1.0,
+            drift: [3.0, 1.0],
+        }
+    }
+}
+
+/// Small xorshift generator, so a theme's backdrop looks the same on every run
+struct Noise(u32);
+
+impl Noise {
+    fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    /// Uniform in 0..1
+    fn unit(&mut self) -> f32 {
+        (self.next() >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+/// A `TILE`² tile of white stars on transparent, alpha carrying their brightness. A few
+/// bright stars get a faint cross; stars near an edge wrap round so the tile is seamless.
+pub fn star_pixels(seed: u32, density: f32) -> Vec<u8> {
+    let mut noise = Noise(seed.max(1));
+    let mut pixels = vec![0u8; (TILE * TILE * 4) as usize];
+    let mut plot = |x: i32, y: i32, brightness: f32| {
+        let (x, y) = (x.rem_euclid(TILE as i32) as u32, y.rem_euclid(TILE as i32) as u32);
+        let at = ((y * TILE + x) * 4) as usize;
+        let alpha = pixels[at + 3].max((brightness * 255.0) as u8);
+        pixels[at..at + 4].copy_from_slice(&[255, 255, 255, alpha]);
+    };
+    let count = (STARS_PER_TILE * density.max(0.0)) as u32;
+    for _ in 0..count {
+        let (x, y) = ((noise.unit() * TILE as f32) as i32, (noise.unit() * TILE as f32) as i32);
+        // Mostly dim stars, a few bright ones
+        let brightness = 0.25 + 0.75 * noise.unit().powi(3);
+        plot(x, y, brightness);
+        if brightness > 0.8 {
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+                plot(x + dx, y + dy, brightness * 0.35);
+            }
+        }
+    }
+    pixels
+}
+
+/// A seamless `TILE`² tile of soft white fog: value noise on lattices that wrap round the
+/// tile, alpha carrying its thickness
+pub fn fog_pixels(seed: u32, density: f32) -> Vec<u8> {
+    let mut noise = Noise(seed.max(1));
+    let lattices: Vec<(u32, Vec<f32>)> = FOG_OCTAVES
+        .iter()
+        .map(|&cells| (cells, (0..cells * cells).map(|_| noise.unit()).collect()))
+        .collect();
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+
+    let mut pixels = Vec::with_capacity((TILE * TILE * 4) as usize);
+    for y in 0..TILE {
+        for x in 0..TILE {
+            let mut value = 0.0;
+            let mut weight = 1.0;
+            for (cells, lattice) in &lattices {
+                let (fx, fy) = (x as f32 * *cells as f32 / TILE as f32, y as f32 * *cells as f32 / TILE as f32);
+                let (x0, y0) = (fx as u32, fy as u32);
+                let (x1, y1) = ((x0 + 1) % cells, (y0 + 1) % cells);
+                let at = |x: u32, y: u32| lattice[(y * cells + x) as usize];
+                let (tx, ty) = (smooth(fx.fract()), smooth(fy.fract()));
+                let top = at(x0, y0) + (at(x1, y0) - at(x0, y0)) * tx;
+                let bottom = at(x0, y1) + (at(x1, y1) - at(x0, y1)) * tx;
+                value += (top + (bottom - top) * ty) * weight;
+                weight *= 0.5;
+            }
+            // Thin out the wisps so patches of clear sky show between them
+            let thickness = ((value / 1.5 - 0.35) / 0.65).clamp(0.0, 1.0);
+            let alpha = (thickness * thickness * density.clamp(0.0, 2.0) * 255.0).min(255.0) as u8;
+            pixels.extend_from_slice(&[255, 255, 255, alpha]);
+        }
+    }
+    pixels
+}
+
+#[derive(Component)]
+pub struct BackdropLayer {
+    /// One of `LAYERS`
+    depth: f32,
+}
+
+/// The game camera, kept apart from the layers' transforms
+type CameraView<'w, 's> = Query<'w, 's, (&'static Transform, &'static OrthographicProjection), (With<GameCamera>, Without<BackdropLayer>)>;
+
+/// What the backdrop layers were built from, and how far the camera has travelled
+#[derive(Resource, Default)]
+pub struct BackdropState {
+    built: Option<Backdrop>,
+    /// Camera position and scale last frame
+    last_camera: Option<(Vec2, f32)>,
+    /// Total camera travel in screen pixels
+    travel: Vec2,
+    /// How far the near layer has drifted, in screen pixels
+    drifted: Vec2,
+}
+
+pub struct BackdropPlugin;
+
+impl Plugin for BackdropPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BackdropState>()
+            .add_systems(Update, (build_backdrop, scroll_backdrop).chain().run_if(in_state(GameState::Playing)))
+            .add_systems(OnExit(InGame), despawn_backdrop);
+    }
+}
+
+/// Generate the layer textures when the theme's backdrop changes
+fn build_backdrop(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    layers: Query<Entity, With<BackdropLayer>>,
+    mut state: ResMut<BackdropState>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if state.built.as_ref() == Some(&theme.backdrop) {
+        return;
+    }
+    for entity in &layers {
+        commands.entity(entity).despawn();
+    }
+    let backdrop = theme.backdrop.clone();
+    for (index, depth) in LAYERS.into_iter().enumerate() {
+        let seed = 0x9e37_79b9 ^ (index as u32 + 1);
+        let (pixels, sampler) = match backdrop.style {
+            BackdropStyle::None => break,
+            // The far layer has more, smaller-looking stars
+            BackdropStyle::Stars => (star_pixels(seed, backdrop.density / depth), ImageSampler::nearest()),
+            BackdropStyle::Fog => (fog_pixels(seed, backdrop.density), ImageSampler::linear()),
+        };
+        let size = Extent3d { width: TILE, height: TILE, depth_or_array_layers: 1 };
+        let mut image = Image::new(size, TextureDimension::D2, pixels, TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::default());
+        image.sampler = sampler;
+        let colour = backdrop.color.0;
+        commands.spawn((
+            Sprite {
+                image: images.add(image),
+                color: colour.with_alpha(colour.alpha() * depth),
+                image_mode: SpriteImageMode::Tiled { tile_x: true, tile_y: true, stretch_value: 1.0 },
+                ..default()
+            },
+            Transform::from_xyz(0.0, 0.0, BACKDROP_Z - index as f32),
+            BackdropLayer { depth },
+        ));
+    }
+    state.built = Some(backdrop);
+}
+
+/// Keep the layers over the window, shifted by their share of the camera's travel and drift
+fn scroll_backdrop(
+    (theme, config, time): (Res<Theme>, Res<CellRenderConfig>, Res<Time>),
+    camera_query: CameraView,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut layers: Query<(&BackdropLayer, &mut Transform, &mut Sprite)>,
+    mut state: ResMut<BackdropState>,
+) {
+    let (Ok((camera_transform, projection)), Ok(window)) = (camera_query.get_single(), windows.get_single()) else { return };
+    let (position, scale) = (camera_transform.translation.truncate(), projection.scale);
+    if let Some((last, last_scale)) = state.last_camera {
+        // Zooming doesn't move the backdrop; panning moves it by the distance the cells moved
+        state.travel += (position - last) / last_scale.max(f32::EPSILON);
+    }
+    state.last_camera = Some((position, scale));
+    if layers.is_empty() {
+        return;
+    }
+
+    let tile = TILE as f32;
+    let tiles = (Vec2::new(window.width(), window.height()) / tile).ceil() + 2.0;
+    let size = tiles * tile;
+    let backdrop = &theme.backdrop;
+    // Reduced motion holds the drift; following the camera is the player's own doing
+    if !config.reduced_motion {
+        state.drifted += Vec2::from(backdrop.drift) * time.delta_secs();
+    }
+    for (layer, mut transform, mut sprite) in &mut layers {
+        let shift = (state.drifted - state.travel * backdrop.parallax) * layer.depth;
+        // Whole tiles of shift look the same, so only the remainder matters
+        let wrapped = Vec2::new(shift.x.rem_euclid(tile), shift.y.rem_euclid(tile)) - tile / 2.0;
+        transform.translation.x = position.x + wrapped.x * scale;
+        transform.translation.y = position.y + wrapped.y * scale;
+        transform.scale = Vec3::new(scale, scale, 1.0);
+        if sprite.custom_size != Some(size) {
+            sprite.custom_size = Some(size);
+        }
+    }
+}
+
+fn despawn_backdrop(mut commands: Commands, layers: Query<Entity, With<BackdropLayer>>, mut state: ResMut<BackdropState>) {
+    for entity in &layers {
+        commands.entity(entity).despawn();
+    }
+    state.built = None;
+    state.last_camera = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backdrops_parse_from_themes_and_tile_seamlessly() {
+        let theme: Theme = toml::from_str("[backdrop]\nstyle = \"fog\"\nparallax = 0.5\n").unwrap();
+        assert_eq!(theme.backdrop.style, BackdropStyle::Fog);
+        assert_eq!(theme.backdrop.parallax, 0.5);
+        assert_eq!(theme.backdrop.drift, Backdrop::default().drift);
+        assert_eq!(Theme::default().backdrop.style, BackdropStyle::None);
+
+        let stars = star_pixels(7, 1.0);
+        assert_eq!(stars, star_pixels(7, 1.0));
+        let lit = stars.chunks(4).filter(|texel| texel[3] > 0).count();
+        assert!(lit >= STARS_PER_TILE as usize / 2, "{}", lit);
+
+        // The first and last columns of the fog meet without a seam
+        let fog = fog_pixels(7, 1.0);
+        let alpha = |x: u32, y: u32| fog[((y * TILE + x) * 4 + 3) as usize] as i32;
+        for y in 0..TILE {
+            assert!((alpha(0, y) - alpha(TILE - 1, y)).abs() <= 24);
+        }
+    }
+}
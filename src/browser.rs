@@ -0,0 +1,259 @@
+//! In-game pattern file browser.
+//!
+//! O (by default) opens a panel listing every pattern file (`.rle`, `.cells`, `.lif`, `.mc`) in the
+//! configured folder with its name, rule and size. Clicking an entry closes the panel and
+//! arms the stamp tool with that pattern; the folder is rescanned each time the panel opens
+//! so newly downloaded files show up without restarting.
+
+use std::path::{Path, PathBuf};
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use crate::io::{load_pattern_file, Pattern, PATTERN_EXTENSIONS};
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::{GameState, InGame};
+use crate::tools::StampState;
+use crate::ui::toast;
+use crate::tr;
+
+/// Pixels scrolled per mouse-wheel line
+const SCROLL_LINE: f32 = 24.0;
+
+pub struct BrowserEntry {
+    pub path: PathBuf,
+    pub pattern: Pattern,
+}
+
+impl BrowserEntry {
+    /// The pattern's own name, falling back to the file name
+    pub fn title(&self) -> String {
+        self.pattern.name.clone().unwrap_or_else(|| {
+            self.path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()
+        })
+    }
+
+    pub fn summary(&self) -> String {
+        let rule = match (self.pattern.rule(), &self.pattern.rulestring) {
+            (Some(rule), _) => rule.name().to_string(),
+            (None, Some(raw)) => format!("{} (unsupported)", raw),
+            (None, None) => "any rule".to_string(),
+        };
+        format!("{} · {}×{} · {} cells", rule, self.pattern.width(), self.pattern.height(), self.pattern.cells.len())
+    }
+}
+
+#[derive(Resource)]
+pub struct PatternBrowser {
+    pub dir: PathBuf,
+    pub entries: Vec<BrowserEntry>,
+}
+
+impl PatternBrowser {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), entries: Vec::new() }
+    }
+}
+
+/// Load every readable pattern file in `dir`, sorted by file name
+pub fn scan_patterns(dir: &Path) -> Vec<BrowserEntry> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut paths: Vec<PathBuf> = read_dir
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| PATTERN_EXTENSIONS.iter().any(|known| ext.eq_ignore_ascii_case(known)))
+        })
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| match load_pattern_file(&path) {
+            Ok(pattern) if !pattern.cells.is_empty() => Some(BrowserEntry { path, pattern }),
+            Ok(_) => None,
+            Err(e) => {
+                println!("📂 Skipping {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+#[derive(Component)]
+struct BrowserPanel;
+
+/// Scrollable list inside the panel
+#[derive(Component)]
+struct BrowserList;
+
+/// Button for `PatternBrowser::entries[index]`
+#[derive(Component)]
+struct BrowserItem(usize);
+
+pub struct PatternBrowserPlugin;
+
+impl Plugin for PatternBrowserPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (toggle_browser, browser_clicks, scroll_browser).run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(OnExit(InGame), close_browser);
+    }
+}
+
+fn toggle_browser(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
+    asset_server: Res<AssetServer>,
+    mut browser: ResMut<PatternBrowser>,
+    panels: Query<Entity, With<BrowserPanel>>,
+) {
+    if !keys.just_pressed(&keyboard_input, Action::Browser) {
+        return;
+    }
+    if !panels.is_empty() {
+        for entity in &panels {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    browser.entries = scan_patterns(&browser.dir);
+    println!("📂 {} patterns in {}", browser.entries.len(), browser.dir.display());
+    spawn_panel(&mut commands, &asset_server.load("fonts/Geo-Regular.ttf"), &browser, &keys);
+}
+
+fn spawn_panel(commands: &mut Commands, font: &Handle<Font>, browser: &PatternBrowser, keys: &Keybindings) {
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                top: Val::Px(60.0),
+                width: Val::Px(340.0),
+                max_height: Val::Percent(80.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.92)),
+            BrowserPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(format!("Patterns — {}", browser.dir.display())),
+                text(20.0),
+                TextColor(Color::srgb(1.0, 0.9, 0.3)),
+            ));
+            parent.spawn((
+                Text::new(format!("Click to pick up, {} to close", keys.label(Action::Browser))),
+                text(13.0),
+                TextColor(Color::srgb(0.6, 0.6, 0.6)),
+                Node { margin: UiRect::bottom(Val::Px(6.0)), ..default() },
+            ));
+            if browser.entries.is_empty() {
+                parent.spawn((Text::new(tr!("No pattern files found")), text(15.0), TextColor(Color::srgb(0.8, 0.5, 0.5))));
+                return;
+            }
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        overflow: Overflow::scroll_y(),
+                        ..default()
+                    },
+                    ScrollPosition::default(),
+                    Interaction::default(),
+                    BrowserList,
+                ))
+                .with_children(|list| {
+                    for (index, entry) in browser.entries.iter().enumerate() {
+                        list.spawn((
+                            Button,
+                            Node {
+                                flex_direction: FlexDirection::Column,
+                                padding: UiRect::axes(Val::Px(6.0), Val::Px(4.0)),
+                                margin: UiRect::bottom(Val::Px(2.0)),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgba(0.15, 0.15, 0.2, 0.9)),
+                            BrowserItem(index),
+                        ))
+                        .with_children(|item| {
+                            item.spawn((Text::new(entry.title()), text(16.0), TextColor(Color::WHITE)));
+                            item.spawn((Text::new(entry.summary()), text(12.0), TextColor(Color::srgb(0.6, 0.8, 1.0))));
+                        });
+                    }
+                });
+        });
+}
+
+fn browser_clicks(
+    mut commands: Commands,
+    mut mouse_button_input: ResMut<ButtonInput<MouseButton>>,
+    browser: Res<PatternBrowser>,
+    mut stamp: ResMut<StampState>,
+    mut items: Query<(&Interaction, &BrowserItem, &mut BackgroundColor), Changed<Interaction>>,
+    panels: Query<Entity, With<BrowserPanel>>,
+) {
+    for (interaction, item, mut colour) in &mut items {
+        match interaction {
+            Interaction::Pressed => {
+                let Some(entry) = browser.entries.get(item.0) else { continue };
+                stamp.hold(entry.title(), entry.pattern.grid_cells());
+                toast(tr!("📂 Picked up {}", entry.title()));
+                // Don't let the same click place the stamp or paint under the panel
+                mouse_button_input.reset(MouseButton::Left);
+                for entity in &panels {
+                    commands.entity(entity).despawn_recursive();
+                }
+                return;
+            }
+            Interaction::Hovered => *colour = BackgroundColor(Color::srgba(0.25, 0.25, 0.35, 0.95)),
+            Interaction::None => *colour = BackgroundColor(Color::srgba(0.15, 0.15, 0.2, 0.9)),
+        }
+    }
+}
+
+fn scroll_browser(
+    mut wheel_events: EventReader<MouseWheel>,
+    mut lists: Query<(&Interaction, &mut ScrollPosition), With<BrowserList>>,
+) {
+    let Ok((interaction, mut scroll)) = lists.get_single_mut() else {
+        wheel_events.clear();
+        return;
+    };
+    for event in wheel_events.read() {
+        if *interaction == Interaction::None {
+            continue;
+        }
+        let lines = match event.unit {
+            MouseScrollUnit::Line => event.y * SCROLL_LINE,
+            MouseScrollUnit::Pixel => event.y,
+        };
+        scroll.offset_y = (scroll.offset_y - lines).max(0.0);
+    }
+}
+
+fn close_browser(mut commands: Commands, panels: Query<Entity, With<BrowserPanel>>) {
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scans_shipped_patterns() {
+        let entries = scan_patterns(Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/patterns")));
+        let titles: Vec<String> = entries.iter().map(BrowserEntry::title).collect();
+        assert!(titles.contains(&"Acorn".to_string()));
+        assert!(titles.contains(&"Gosper glider gun".to_string()));
+        let gun = entries.iter().find(|e| e.title() == "Gosper glider gun").unwrap();
+        assert!(gun.summary().starts_with("Conway's Game of Life · 36×9"));
+    }
+}
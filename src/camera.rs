@@ -1,14 +1,28 @@
+use bevy::input::gestures::{PanGesture, PinchGesture};
+use bevy::input::mouse::MouseScrollUnit;
+use bevy::input::touch::Touches;
 use bevy::prelude::*;
+use crate::infinite_grid::{GridBounds, InfiniteGrid};
+use crate::keybindings::{Action, Keybindings};
+use crate::ui::toast;
+use crate::tr;
 
 /// Camera component for handling zoom and pan
 #[derive(Component)]
 pub struct GameCamera {
     pub zoom: f32,
     pub target_zoom: f32,
+    /// Screen pixels per second the pan keys move the view
     pub pan_speed: f32,
+    /// Multiplier on wheel, keyboard and pinch zoom rates
     pub zoom_speed: f32,
     pub min_zoom: f32,
     pub max_zoom: f32,
+    /// World point held under the cursor while a wheel zoom settles, with the cursor's offset
+    /// from the window centre in screen pixels (y up)
+    pub zoom_anchor: Option<(Vec2, Vec2)>,
+    /// World point the camera glides to, as set by fit view
+    pub pan_target: Option<Vec2>,
 }
 
 impl Default for GameCamera {
@@ -17,9 +31,53 @@ impl Default for GameCamera {
             zoom: 1.0,        // Start at 1x zoom (reasonable default)
             target_zoom: 1.0, // Start at 1x zoom (reasonable default)  
             pan_speed: 500.0,
-            zoom_speed: 0.1,  // Increase zoom speed for better responsiveness
+            zoom_speed: 1.0,
             min_zoom: 0.05,   // Allow much more zoom out
             max_zoom: 20.0,   // Allow much more zoom in
+            zoom_anchor: None,
+            pan_target: None,
+        }
+    }
+}
+
+/// Zoom limits and navigation speeds, from the config and the F3 panel
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct CameraSettings {
+    pub min_zoom: f32,
+    pub max_zoom: f32,
+    pub zoom_speed: f32,
+    pub pan_speed: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        let camera = GameCamera::default();
+        Self { min_zoom: camera.min_zoom, max_zoom: camera.max_zoom, zoom_speed: camera.zoom_speed, pan_speed: camera.pan_speed }
+    }
+}
+
+impl CameraSettings {
+    /// Settings from config values, with the limits put in order and kept positive
+    pub fn new(min_zoom: f32, max_zoom: f32, zoom_speed: f32, pan_speed: f32) -> Self {
+        let min_zoom = min_zoom.max(1e-4);
+        Self { min_zoom, max_zoom: max_zoom.max(min_zoom), zoom_speed: zoom_speed.max(0.0), pan_speed: pan_speed.max(0.0) }
+    }
+
+    /// Give the camera these settings, pulling its zoom back within the limits
+    pub fn apply(&self, camera: &mut GameCamera) {
+        camera.min_zoom = self.min_zoom;
+        camera.max_zoom = self.max_zoom;
+        camera.zoom_speed = self.zoom_speed;
+        camera.pan_speed = self.pan_speed;
+        camera.target_zoom = camera.target_zoom.clamp(self.min_zoom, self.max_zoom);
+    }
+}
+
+/// Follow changes to the camera settings
+pub fn apply_camera_settings(settings: Res<CameraSettings>, mut camera_query: Query<&mut GameCamera>) {
+    if settings.is_changed() {
+        for mut camera in camera_query.iter_mut() {
+            settings.apply(&mut camera);
         }
     }
 }
@@ -41,37 +99,88 @@ impl Default for CameraState {
 }
 
 /// Setup the game camera
-pub fn setup_camera(mut commands: Commands) {
+pub fn setup_camera(mut commands: Commands, settings: Res<CameraSettings>) {
+    let mut camera = GameCamera::default();
+    settings.apply(&mut camera);
     commands.spawn((
         Camera2d::default(),
-        GameCamera::default(),
+        camera,
+        // The HUD stays on this camera when the split screen adds another (see `split_screen`)
+        IsDefaultUiCamera,
     ));
 }
 
+/// Camera position that puts `world` at `screen_offset` pixels from the window centre
+pub fn anchored_translation(world: Vec2, screen_offset: Vec2, scale: f32) -> Vec2 {
+    world - screen_offset * scale
+}
+
+/// Hold the world point under the cursor in place while the zoom settles
+fn anchor_zoom_at_cursor(camera: &mut GameCamera, transform: &Transform, projection: &OrthographicProjection, window: &Window) {
+    let Some(cursor) = window.cursor_position() else { return };
+    let size = Vec2::new(window.width(), window.height());
+    let offset = (cursor - size / 2.0) * Vec2::new(1.0, -1.0);
+    camera.zoom_anchor = Some((screen_to_world(cursor, transform, projection, size), offset));
+    camera.pan_target = None;
+}
+
+/// Scroll the view by a trackpad's pan delta in screen pixels, the content following the fingers
+fn pan_by_screen_delta(transform: &mut Transform, camera: &mut GameCamera, delta: Vec2, scale: f32) {
+    transform.translation.x -= delta.x * scale;
+    transform.translation.y += delta.y * scale;
+    camera.zoom_anchor = None;
+    camera.pan_target = None;
+}
+
 /// Handle camera controls (zoom and pan)
 pub fn handle_camera_controls(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    (keyboard_input, keys): (Res<ButtonInput<KeyCode>>, Res<Keybindings>),
     mut mouse_wheel_events: EventReader<bevy::input::mouse::MouseWheel>,
     mut camera_query: Query<(&mut Transform, &mut OrthographicProjection, &mut GameCamera)>,
-    _camera_state: Res<CameraState>,
+    windows: Query<&Window, With<bevy::window::PrimaryWindow>>,
     time: Res<Time>,
+    capture: Res<crate::ui::PointerCapture>,
 ) {
     if let Ok((mut transform, mut projection, mut camera)) = camera_query.get_single_mut() {
         let dt = time.delta_secs();
 
-        // Handle zoom with mouse wheel
+        // Something else moved the camera (the minimap, a loaded session): let go of the anchor
+        if let Some((world, offset)) = camera.zoom_anchor {
+            let expected = anchored_translation(world, offset, projection.scale);
+            if transform.translation.truncate().distance(expected) > projection.scale {
+                camera.zoom_anchor = None;
+            }
+        }
+
+        // Handle zoom with mouse wheel, unless a panel under the pointer scrolls instead.
+        // The point under the cursor stays there while the zoom settles. Trackpads scroll in
+        // pixels: two fingers pan then, and zoom with Ctrl held like a pinch in a browser.
+        let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
         for event in mouse_wheel_events.read() {
-            camera.target_zoom *= 1.0 + event.y * 0.001; // Restore original wheel zoom speed
+            if capture.captured {
+                continue;
+            }
+            if event.unit == MouseScrollUnit::Pixel && !ctrl {
+                pan_by_screen_delta(&mut transform, &mut camera, Vec2::new(event.x, event.y), projection.scale);
+                continue;
+            }
+            camera.target_zoom *= 1.0 + event.y * 0.001 * camera.zoom_speed; // Restore original wheel zoom speed
             camera.target_zoom = camera.target_zoom.clamp(camera.min_zoom, camera.max_zoom);
+            if let Ok(window) = windows.get_single() {
+                anchor_zoom_at_cursor(&mut camera, &transform, &projection, window);
+            }
         }
 
         // Handle zoom with keyboard
-        if keyboard_input.pressed(KeyCode::PageUp) {
-            camera.target_zoom *= 1.0 + 2.0 * dt; // Restore original keyboard zoom speed
+        if keys.pressed(&keyboard_input, Action::ZoomIn) || keys.pressed(&keyboard_input, Action::ZoomOut) {
+            camera.zoom_anchor = None;
+        }
+        if keys.pressed(&keyboard_input, Action::ZoomIn) {
+            camera.target_zoom *= 1.0 + 2.0 * camera.zoom_speed * dt; // Restore original keyboard zoom speed
             camera.target_zoom = camera.target_zoom.clamp(camera.min_zoom, camera.max_zoom);
         }
-        if keyboard_input.pressed(KeyCode::PageDown) {
-            camera.target_zoom *= 1.0 - 2.0 * dt; // Restore original keyboard zoom speed
+        if keys.pressed(&keyboard_input, Action::ZoomOut) {
+            camera.target_zoom *= (1.0 - 2.0 * camera.zoom_speed * dt).max(0.5); // Restore original keyboard zoom speed
             camera.target_zoom = camera.target_zoom.clamp(camera.min_zoom, camera.max_zoom);
         }
 
@@ -80,37 +189,144 @@ pub fn handle_camera_controls(
         
         // Use orthographic projection scale instead of transform scale
         projection.scale = 1.0 / camera.zoom;
+        if let Some((world, offset)) = camera.zoom_anchor {
+            let translation = anchored_translation(world, offset, projection.scale);
+            transform.translation.x = translation.x;
+            transform.translation.y = translation.y;
+            if (camera.target_zoom - camera.zoom).abs() <= camera.target_zoom * 1e-3 {
+                camera.zoom_anchor = None;
+            }
+        }
 
-        // Handle panning with WASD only
+        // Handle panning with the pan keys (WASD by default)
         let mut pan_direction = Vec2::ZERO;
         
-        if keyboard_input.pressed(KeyCode::KeyW) {
+        if keys.pressed(&keyboard_input, Action::PanUp) {
             pan_direction.y += 1.0;
         }
-        if keyboard_input.pressed(KeyCode::KeyS) {
+        if keys.pressed(&keyboard_input, Action::PanDown) {
             pan_direction.y -= 1.0;
         }
-        if keyboard_input.pressed(KeyCode::KeyA) {
+        if keys.pressed(&keyboard_input, Action::PanLeft) {
             pan_direction.x -= 1.0;
         }
-        if keyboard_input.pressed(KeyCode::KeyD) {
+        if keys.pressed(&keyboard_input, Action::PanRight) {
             pan_direction.x += 1.0;
         }
 
         // Apply panning (adjust for zoom level)
         if pan_direction != Vec2::ZERO {
+            camera.zoom_anchor = None;
+            camera.pan_target = None;
             let pan_amount = pan_direction.normalize() * camera.pan_speed * dt * projection.scale;
             transform.translation += pan_amount.extend(0.0);
         }
 
         // Reset camera position
-        if keyboard_input.just_pressed(KeyCode::Home) {
+        if keys.just_pressed(&keyboard_input, Action::ResetCamera) {
             transform.translation = Vec3::ZERO;
-            camera.target_zoom = 1.0;
+            camera.target_zoom = 1.0_f32.clamp(camera.min_zoom, camera.max_zoom);
+            camera.zoom_anchor = None;
+            camera.pan_target = None;
+        }
+
+        // Glide towards the pan target at the same rate the zoom eases in
+        if let Some(target) = camera.pan_target {
+            let position = transform.translation.truncate();
+            let next = if position.distance(target) <= projection.scale { target } else { position.lerp(target, (5.0 * dt).min(1.0)) };
+            transform.translation.x = next.x;
+            transform.translation.y = next.y;
+            if next == target {
+                camera.pan_target = None;
+            }
         }
     }
 }
 
+/// Trackpad and touchscreen navigation: pinch to zoom about the fingers, two fingers to pan.
+/// Trackpad gestures arrive as events (macOS); touchscreens report raw touches.
+pub fn handle_gestures(
+    (mut pinches, mut pans): (EventReader<PinchGesture>, EventReader<PanGesture>),
+    touches: Res<Touches>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection, &mut GameCamera)>,
+    windows: Query<&Window, With<bevy::window::PrimaryWindow>>,
+    capture: Res<crate::ui::PointerCapture>,
+) {
+    let (Ok((mut transform, mut projection, mut camera)), Ok(window)) = (camera_query.get_single_mut(), windows.get_single()) else { return };
+    for PinchGesture(delta) in pinches.read() {
+        if capture.captured {
+            continue;
+        }
+        camera.target_zoom = (camera.target_zoom * (1.0 + delta * camera.zoom_speed)).clamp(camera.min_zoom, camera.max_zoom);
+        anchor_zoom_at_cursor(&mut camera, &transform, &projection, window);
+    }
+    for PanGesture(delta) in pans.read() {
+        if !capture.captured {
+            pan_by_screen_delta(&mut transform, &mut camera, *delta, projection.scale);
+        }
+    }
+
+    // Two fingers on a touchscreen: the world point between them follows their midpoint,
+    // scaled by how far they spread
+    let fingers: Vec<_> = touches.iter().collect();
+    let [first, second] = fingers.as_slice() else { return };
+    let (before, after) = ((first.previous_position(), second.previous_position()), (first.position(), second.position()));
+    let spread = |(a, b): (Vec2, Vec2)| a.distance(b);
+    if spread(before) < 1.0 {
+        return;
+    }
+    let size = Vec2::new(window.width(), window.height());
+    let midpoint = |(a, b): (Vec2, Vec2)| (a + b) / 2.0;
+    let world = screen_to_world(midpoint(before), &transform, &projection, size);
+
+    let zoom = (camera.zoom * spread(after) / spread(before)).clamp(camera.min_zoom, camera.max_zoom);
+    camera.zoom = zoom;
+    camera.target_zoom = zoom;
+    projection.scale = 1.0 / zoom;
+    let offset = (midpoint(after) - size / 2.0) * Vec2::new(1.0, -1.0);
+    let translation = anchored_translation(world, offset, projection.scale);
+    transform.translation.x = translation.x;
+    transform.translation.y = translation.y;
+    camera.zoom_anchor = None;
+    camera.pan_target = None;
+}
+
+/// Fraction of the view left empty round the pattern on each side by fit view
+pub const FIT_PADDING: f32 = 0.1;
+
+/// Camera centre and zoom that show the cells in `bounds` whole within `window_size`,
+/// with `FIT_PADDING` to spare on each side
+pub fn fit_view(bounds: &GridBounds, camera_state: &CameraState, window_size: Vec2) -> (Vec2, f32) {
+    let min = Vec2::new(bounds.min_x as f32, bounds.min_y as f32);
+    let max = Vec2::new(bounds.max_x as f32, bounds.max_y as f32);
+    let centre = (min + max) / 2.0 * camera_state.cell_size + camera_state.grid_offset;
+    let span = (max - min + 1.0) * camera_state.cell_size * (1.0 + 2.0 * FIT_PADDING);
+    let zoom = (window_size / span).min_element();
+    (centre, zoom)
+}
+
+/// Frame the whole live pattern (F by default), animating there like a zoom
+pub fn fit_view_to_pattern(
+    (keyboard_input, keys): (Res<ButtonInput<KeyCode>>, Res<Keybindings>),
+    (grid, camera_state, stamp): (Res<InfiniteGrid>, Res<CameraState>, Res<crate::tools::StampState>),
+    mut camera_query: Query<&mut GameCamera>,
+    windows: Query<&Window, With<bevy::window::PrimaryWindow>>,
+) {
+    // The stamp's controls may share the key
+    if !keys.just_pressed(&keyboard_input, Action::FitView) || stamp.is_armed() {
+        return;
+    }
+    let (Ok(mut camera), Ok(window)) = (camera_query.get_single_mut(), windows.get_single()) else { return };
+    let Some(bounds) = grid.bounds() else {
+        toast(tr!("🔍 Nothing to fit: the grid is empty"));
+        return;
+    };
+    let (centre, zoom) = fit_view(bounds, &camera_state, Vec2::new(window.width(), window.height()));
+    camera.target_zoom = zoom.clamp(camera.min_zoom, camera.max_zoom);
+    camera.pan_target = Some(centre);
+    camera.zoom_anchor = None;
+}
+
 /// Convert screen coordinates to world coordinates
 pub fn screen_to_world(
     screen_pos: Vec2,
@@ -143,8 +359,33 @@ pub fn world_to_grid(world_pos: Vec2, camera_state: &CameraState) -> (i32, i32)
     // Convert world position to grid coordinates
     let grid_pos = (world_pos - camera_state.grid_offset) / camera_state.cell_size;
     
-    // Use floor to get the cell the position is actually in (not nearest center)
-    (grid_pos.x.floor() as i32, grid_pos.y.floor() as i32)
+    // Cells are drawn centred on their grid position, so each one covers ±half a cell around it
+    ((grid_pos.x + 0.5).floor() as i32, (grid_pos.y + 0.5).floor() as i32)
+}
+
+/// Grid cell under the mouse cursor, if the cursor is inside the window
+pub fn cursor_grid_position(
+    window: &Window,
+    camera_transform: &Transform,
+    projection: &OrthographicProjection,
+    camera_state: &CameraState,
+) -> Option<(i32, i32)> {
+    let cursor_position = window.cursor_position()?;
+    let window_size = Vec2::new(window.width(), window.height());
+    let world_pos = screen_to_world(cursor_position, camera_transform, projection, window_size);
+    Some(world_to_grid(world_pos, camera_state))
+}
+
+/// Grid cells covered by the camera's current view
+pub fn visible_grid_bounds(
+    camera_transform: &Transform,
+    projection: &OrthographicProjection,
+    camera_state: &CameraState,
+) -> GridBounds {
+    let centre = camera_transform.translation.truncate();
+    let (min_x, min_y) = world_to_grid(centre + projection.area.min, camera_state);
+    let (max_x, max_y) = world_to_grid(centre + projection.area.max, camera_state);
+    GridBounds { min_x, max_x, min_y, max_y }
 }
 
 /// Convert grid coordinates to world coordinates
@@ -213,4 +454,46 @@ pub fn screen_to_world_2d(
         camera_transform.translation.x + local_pos.x,
         camera_transform.translation.y + local_pos.y,
     )
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anchored_zoom_keeps_the_point_under_the_cursor() {
+        let window = Vec2::new(800.0, 600.0);
+        let cursor = Vec2::new(700.0, 100.0);
+        let offset = (cursor - window / 2.0) * Vec2::new(1.0, -1.0);
+        let mut transform = Transform::from_xyz(50.0, -20.0, 0.0);
+        let mut projection = OrthographicProjection::default_2d();
+        let world = screen_to_world(cursor, &transform, &projection, window);
+
+        projection.scale = 0.25;
+        transform.translation = anchored_translation(world, offset, projection.scale).extend(0.0);
+        assert!(screen_to_world(cursor, &transform, &projection, window).distance(world) < 1e-3);
+    }
+
+    #[test]
+    fn two_finger_pan_moves_the_content_with_the_fingers() {
+        let window = Vec2::new(800.0, 600.0);
+        let mut transform = Transform::default();
+        let projection = OrthographicProjection { scale: 2.0, ..OrthographicProjection::default_2d() };
+        let mut camera = GameCamera { zoom_anchor: Some((Vec2::ZERO, Vec2::ZERO)), ..default() };
+        let world = screen_to_world(Vec2::new(400.0, 300.0), &transform, &projection, window);
+
+        pan_by_screen_delta(&mut transform, &mut camera, Vec2::new(30.0, -10.0), projection.scale);
+        assert!(screen_to_world(Vec2::new(430.0, 290.0), &transform, &projection, window).distance(world) < 1e-3);
+        assert!(camera.zoom_anchor.is_none());
+    }
+
+    #[test]
+    fn fit_view_frames_the_pattern_with_padding() {
+        let state = CameraState { cell_size: 10.0, grid_offset: Vec2::ZERO };
+        let bounds = GridBounds { min_x: -10, max_x: 9, min_y: 0, max_y: 4 };
+        let (centre, zoom) = fit_view(&bounds, &state, Vec2::new(480.0, 480.0));
+        assert_eq!(centre, Vec2::new(-5.0, 20.0));
+        // 20 cells wide is the tight side: 200 world units plus 10% each side fill 480 pixels
+        assert!((zoom - 2.0).abs() < 1e-5);
+    }
+}
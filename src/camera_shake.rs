@@ -0,0 +1,182 @@
+//! Event-driven camera shake.
+//!
+//! A brief, subtle shake of the view when something happens in the simulation: a mass
+//! extinction (half or more of a sizeable population gone in one step), the population
+//! passing a power of ten, or the hybrid synth's milestone bell every `BELL_INTERVAL`
+//! generations. Each event adds "trauma" that decays over a second or so; the offset grows
+//! with its square, so small events barely nudge the view. `CameraShake::amplitude` is the
+//! largest offset in screen pixels and defaults to 0, which turns the shake off, as does
+//! reduced motion.
+//!
+//! The offset is added to the camera just before transforms propagate and taken off again
+//! at the start of the next frame, so the camera controls, picking and the minimap never
+//! see it.
+
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use crate::audio::hybrid_dungeon_synth::BELL_INTERVAL;
+use crate::camera::GameCamera;
+use crate::cell_renderer::CellRenderConfig;
+use crate::config::AudioEngine;
+use crate::game_config::GameConfig;
+use crate::infinite_grid::InfiniteGrid;
+use crate::start_screen::GameState;
+
+/// Smallest population whose halving counts as a mass extinction
+pub const EXTINCTION_MIN: usize = 32;
+
+/// Smallest population milestone; the next ones are each ten times larger
+pub const FIRST_MILESTONE: usize = 100;
+
+/// Trauma lost per second
+const DECAY: f32 = 1.5;
+
+/// What shook the camera
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShakeCause {
+    Extinction,
+    Milestone,
+    Bell,
+}
+
+impl ShakeCause {
+    /// Trauma added, out of 1
+    pub fn trauma(self) -> f32 {
+        match self {
+            ShakeCause::Extinction => 1.0,
+            ShakeCause::Bell => 0.6,
+            ShakeCause::Milestone => 0.5,
+        }
+    }
+}
+
+/// Milestones passed by `population`: 0 below `FIRST_MILESTONE`, 1 from there, 2 from ten
+/// times that and so on
+fn milestone(population: usize) -> u32 {
+    if population < FIRST_MILESTONE {
+        0
+    } else {
+        (population / FIRST_MILESTONE).ilog10() + 1
+    }
+}
+
+/// Events between two `(generation, population)` samples of a running game. Going back a
+/// generation (a clear, load or reset) is not an event.
+pub fn shake_causes(previous: (u64, usize), current: (u64, usize), bells: bool) -> Vec<ShakeCause> {
+    let ((last_generation, last_population), (generation, population)) = (previous, current);
+    let mut causes = Vec::new();
+    if generation <= last_generation {
+        return causes;
+    }
+    if last_population >= EXTINCTION_MIN && population <= last_population / 2 {
+        causes.push(ShakeCause::Extinction);
+    }
+    if milestone(population) > milestone(last_population) {
+        causes.push(ShakeCause::Milestone);
+    }
+    if bells && generation / BELL_INTERVAL > last_generation / BELL_INTERVAL {
+        causes.push(ShakeCause::Bell);
+    }
+    causes
+}
+
+#[derive(Resource, Default)]
+pub struct CameraShake {
+    /// Largest offset in screen pixels; 0 turns the shake off
+    pub amplitude: f32,
+    trauma: f32,
+    /// Offset currently added to the camera, in world units
+    applied: Vec2,
+    /// Generation and population when last looked at
+    last: Option<(u64, usize)>,
+}
+
+impl CameraShake {
+    pub fn new(amplitude: f32) -> Self {
+        Self { amplitude: amplitude.max(0.0), ..default() }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.amplitude > 0.0
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+}
+
+pub struct CameraShakePlugin;
+
+impl Plugin for CameraShakePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraShake>()
+            .add_systems(PreUpdate, remove_shake)
+            .add_systems(Update, detect_shake_events.run_if(in_state(GameState::Playing)))
+            .add_systems(PostUpdate, apply_shake.before(TransformSystem::TransformPropagate));
+    }
+}
+
+/// Take last frame's offset back off, leaving the camera where the controls put it
+fn remove_shake(mut shake: ResMut<CameraShake>, mut camera_query: Query<&mut Transform, With<GameCamera>>) {
+    if shake.applied == Vec2::ZERO {
+        return;
+    }
+    if let Ok(mut transform) = camera_query.get_single_mut() {
+        transform.translation -= shake.applied.extend(0.0);
+    }
+    shake.applied = Vec2::ZERO;
+}
+
+fn detect_shake_events(grid: Res<InfiniteGrid>, game_config: Res<GameConfig>, mut shake: ResMut<CameraShake>) {
+    let current = (grid.generation(), grid.population());
+    let previous = shake.last.replace(current);
+    if !shake.enabled() {
+        return;
+    }
+    let Some(previous) = previous else { return };
+    let bells = matches!(game_config.audio_engine, AudioEngine::Spatial | AudioEngine::Hybrid);
+    for cause in shake_causes(previous, current, bells) {
+        shake.add_trauma(cause.trauma());
+    }
+}
+
+fn apply_shake(
+    time: Res<Time>,
+    config: Res<CellRenderConfig>,
+    mut shake: ResMut<CameraShake>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<GameCamera>>,
+) {
+    if shake.trauma <= 0.0 {
+        return;
+    }
+    shake.trauma = (shake.trauma - DECAY * time.delta_secs()).max(0.0);
+    if !shake.enabled() || config.reduced_motion {
+        return;
+    }
+    let Ok((mut transform, projection)) = camera_query.get_single_mut() else { return };
+    // A few incommensurate sines make a jitter that doesn't visibly repeat
+    let t = time.elapsed_secs();
+    let jitter = Vec2::new((t * 41.0).sin() + (t * 67.0).sin() * 0.5, (t * 53.0).cos() + (t * 29.0).cos() * 0.5) / 1.5;
+    let offset = jitter * shake.amplitude * shake.trauma * shake.trauma * projection.scale;
+    transform.translation += offset.extend(0.0);
+    shake.applied = offset;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_between_generations() {
+        assert_eq!(shake_causes((10, 200), (11, 90), false), vec![ShakeCause::Extinction]);
+        // Too few cells to call it a mass extinction
+        assert!(shake_causes((10, 20), (11, 0), false).is_empty());
+        assert_eq!(shake_causes((10, 950), (11, 1004), false), vec![ShakeCause::Milestone]);
+        assert!(shake_causes((10, 1004), (11, 1100), false).is_empty());
+        assert_eq!(shake_causes((199, 50), (200, 50), true), vec![ShakeCause::Bell]);
+        assert!(shake_causes((199, 50), (200, 50), false).is_empty());
+        // A reset or a pause is nothing
+        assert!(shake_causes((500, 4000), (0, 0), true).is_empty());
+        assert!(shake_causes((500, 4000), (500, 10), true).is_empty());
+    }
+}
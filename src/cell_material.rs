@@ -0,0 +1,206 @@
+//! Cells drawn with a WGSL material.
+//!
+//! Instead of regenerating RGBA textures on the CPU as the cell sprites do, cells become
+//! quads drawn by `assets/shaders/cell.wgsl`, which computes the organic outline, soft
+//! edges and pulsing per fragment from the frame time, the cell's age and a variation
+//! hashed from its position. Materials are shared: one per cell state and age bucket
+//! (`AGE_BUCKETS`), so a cell only swaps handles as it ages and a whole screen of cells
+//! batches into a handful of draws. Switched on with `cell_shader` in the config.
+
+use std::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+use bevy::sprite::{AlphaMode2d, Material2d, Material2dPlugin};
+use crate::cell_renderer::{render_optimized_cells, CellAnimation, CellRenderConfig, CellSprite};
+use crate::glow::{emissive_boost, NEWBORN_BOOST};
+use crate::infinite_grid::InfiniteGrid;
+use crate::start_screen::GameState;
+use crate::theme::Theme;
+use crate::CellState;
+
+const SHADER_PATH: &str = "shaders/cell.wgsl";
+
+/// Steps a cell's age is quantised to, from newborn to `CellRenderConfig::age_span`
+pub const AGE_BUCKETS: u8 = 8;
+
+/// Side of the cell quad, the same as the sprite textures so cell scaling works unchanged
+pub const QUAD_SIZE: f32 = 32.0;
+
+/// Uniforms of `cell.wgsl`
+#[derive(Asset, TypePath, AsBindGroup, Clone, Copy, Debug, Default)]
+pub struct CellMaterial {
+    /// Linear colour, may exceed 1 for bloom
+    #[uniform(0)]
+    pub color: Vec4,
+    /// Tint newborn cells start in
+    #[uniform(1)]
+    pub glow: Vec4,
+    /// Maturity (0 for a newborn, 1 once the cell reaches the age span), shape (see
+    /// `material_shape`), cell size and animation speed
+    #[uniform(2)]
+    pub params: Vec4,
+}
+
+impl Material2d for CellMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+/// Shape the shader draws for `state`: an organic blob for live cells, and the same shapes
+/// as the sprite renderer's state textures for multi-state cells
+pub fn material_shape(state: CellState) -> u32 {
+    match state {
+        CellState::Alive | CellState::Dead => 0,
+        CellState::Wire => 1,
+        CellState::ElectronHead => 2,
+        CellState::ElectronTail => 3,
+        CellState::Dying => 4,
+        CellState::SpeciesA => 5,
+        CellState::SpeciesB => 6,
+    }
+}
+
+/// Age bucket of a cell `age` generations old, on the same log scale as the theme's age
+/// gradient. Cells of unknown age count as mature.
+pub fn age_bucket(age: Option<u64>, span: u64) -> u8 {
+    let Some(age) = age else { return AGE_BUCKETS - 1 };
+    let t = ((age as f32).ln_1p() / (span.max(1) as f32).ln_1p()).min(1.0);
+    (t * (AGE_BUCKETS - 1) as f32).round() as u8
+}
+
+fn bucket_maturity(bucket: u8) -> f32 {
+    bucket as f32 / (AGE_BUCKETS - 1) as f32
+}
+
+/// Age at the middle of `bucket`, for picking its colour off the age gradient
+fn bucket_age(bucket: u8, span: u64) -> u64 {
+    (bucket_maturity(bucket) * (span.max(1) as f32).ln_1p()).exp_m1().round() as u64
+}
+
+/// Linear colour scaled by `boost`, alpha untouched
+fn boosted(color: Color, boost: f32) -> Vec4 {
+    let linear = color.to_linear();
+    Vec4::new(linear.red * boost, linear.green * boost, linear.blue * boost, linear.alpha)
+}
+
+/// Material for cells in `state` and age `bucket`
+pub fn cell_material(state: CellState, bucket: u8, theme: &Theme, config: &CellRenderConfig) -> CellMaterial {
+    let color = if config.generation_colors && state == CellState::Alive {
+        theme.age_color(bucket_age(bucket, config.age_span), config.age_span)
+    } else {
+        theme.cells.color(state)
+    };
+    let bloom = config.bloom_intensity > 0.0;
+    let boost = if bloom { 1.0 + emissive_boost(state, None) } else { 1.0 };
+    // Only live cells flash on birth; multi-state cells keep their own colour
+    let (glow, glow_boost) = match state {
+        CellState::Alive => (theme.glow.0, if bloom { 1.0 + NEWBORN_BOOST } else { 1.0 }),
+        _ => (color, boost),
+    };
+    CellMaterial {
+        color: boosted(color, boost),
+        glow: boosted(glow, glow_boost),
+        params: Vec4::new(bucket_maturity(bucket), material_shape(state) as f32, config.cell_size, config.motion_speed()),
+    }
+}
+
+/// The cell quad and the shared materials, created on first use
+#[derive(Resource, Default)]
+pub struct CellMaterials {
+    pub quad: Handle<Mesh>,
+    handles: HashMap<(CellState, u8), Handle<CellMaterial>>,
+    /// Settings the materials were last built with: age colours, age span, bloom, cell size
+    /// and animation speed
+    applied: Option<(bool, u64, f32, f32, f32)>,
+}
+
+impl CellMaterials {
+    pub fn get(&mut self, state: CellState, bucket: u8, theme: &Theme, config: &CellRenderConfig, materials: &mut Assets<CellMaterial>) -> Handle<CellMaterial> {
+        self.handles
+            .entry((state, bucket))
+            .or_insert_with(|| materials.add(cell_material(state, bucket, theme, config)))
+            .clone()
+    }
+
+    /// Rebuild every material's parameters if the theme or the settings they depend on changed
+    fn refresh(&mut self, theme: &Theme, theme_changed: bool, config: &CellRenderConfig, materials: &mut Assets<CellMaterial>) {
+        let settings = (config.generation_colors, config.age_span, config.bloom_intensity, config.cell_size, config.motion_speed());
+        if !theme_changed && self.applied == Some(settings) {
+            return;
+        }
+        self.applied = Some(settings);
+        for (&(state, bucket), handle) in &self.handles {
+            if let Some(material) = materials.get_mut(handle) {
+                *material = cell_material(state, bucket, theme, config);
+            }
+        }
+    }
+}
+
+pub struct CellMaterialPlugin;
+
+impl Plugin for CellMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<CellMaterial>::default())
+            .init_resource::<CellMaterials>()
+            .add_systems(Startup, setup_cell_quad)
+            .add_systems(Update, update_cell_materials.after(render_optimized_cells).run_if(in_state(GameState::Playing)));
+    }
+}
+
+fn setup_cell_quad(mut cell_materials: ResMut<CellMaterials>, mut meshes: ResMut<Assets<Mesh>>) {
+    cell_materials.quad = meshes.add(Rectangle::new(QUAD_SIZE, QUAD_SIZE));
+}
+
+/// Keep each shader cell on the material for its current state and age, and remove cells
+/// that died without a death animation (the sprite path does this in `animate_cell_textures`)
+fn update_cell_materials(
+    mut commands: Commands,
+    mut cells: Query<(Entity, &mut CellSprite, &mut MeshMaterial2d<CellMaterial>, Option<&CellAnimation>)>,
+    (grid, theme, config): (Res<InfiniteGrid>, Res<Theme>, Res<CellRenderConfig>),
+    mut cell_materials: ResMut<CellMaterials>,
+    mut materials: ResMut<Assets<CellMaterial>>,
+) {
+    cell_materials.refresh(&theme, theme.is_changed(), &config, &mut materials);
+    for (entity, mut cell, mut material, animation) in cells.iter_mut() {
+        let state = grid.get(cell.x, cell.y);
+        if state == CellState::Dead {
+            if animation.is_none() {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        }
+        if cell.cell_type != state {
+            cell.cell_type = state;
+        }
+        let bucket = age_bucket(grid.meta(cell.x, cell.y).map(|meta| meta.age(grid.generation())), config.age_span);
+        let handle = cell_materials.get(state, bucket, &theme, &config, &mut materials);
+        if material.0 != handle {
+            material.0 = handle;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn age_buckets_run_from_newborn_to_mature() {
+        assert_eq!(age_bucket(Some(0), 100), 0);
+        assert_eq!(age_bucket(Some(100), 100), AGE_BUCKETS - 1);
+        assert_eq!(age_bucket(Some(5_000), 100), AGE_BUCKETS - 1);
+        assert_eq!(age_bucket(None, 100), AGE_BUCKETS - 1);
+        let buckets: Vec<_> = (0..=100).map(|age| age_bucket(Some(age), 100)).collect();
+        assert!(buckets.windows(2).all(|pair| pair[0] <= pair[1]));
+        // A bucket's representative age falls back into the same bucket
+        for bucket in 0..AGE_BUCKETS {
+            assert_eq!(age_bucket(Some(bucket_age(bucket, 100)), 100), bucket);
+        }
+    }
+}
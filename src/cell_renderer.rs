@@ -4,6 +4,8 @@ use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
 use bevy::render::render_asset::RenderAssetUsages;
 use std::collections::HashMap;
 use crate::{CellState, RuleType};
+use crate::ui::toast;
+use crate::tr;
 use bevy::time::{Timer, TimerMode};
 
 /// Cell rendering component to track sprites
@@ -57,6 +59,9 @@ pub struct CellTexturePool {
     pub alive_textures: Vec<Handle<Image>>,
     pub dying_textures: Vec<Handle<Image>>,
     pub newborn_textures: Vec<Handle<Image>>,
+    /// One texture per multi-state cell state, so wires, electrons and species differ in
+    /// shape as well as colour
+    pub state_textures: HashMap<CellState, Handle<Image>>,
     pub is_initialized: bool,
     pub generation_seed: u64,
     pub evolution_timer: Timer,  // Timer for texture evolution
@@ -70,6 +75,7 @@ impl Default for CellTexturePool {
             alive_textures: Vec::new(),
             dying_textures: Vec::new(),
             newborn_textures: Vec::new(),
+            state_textures: HashMap::new(),
             is_initialized: false,
             generation_seed: 1,
             evolution_timer: Timer::from_seconds(30.0, TimerMode::Repeating), // Evolve texture sets every 30 seconds
@@ -84,11 +90,32 @@ impl Default for CellTexturePool {
 pub struct CellRenderConfig {
     pub cell_size: f32,
     pub base_color: Color,
+    /// Theme names U cycles through (see `theme::cycle_theme`)
+    pub themes: Vec<String>,
+    /// Palette applied over every theme for colour vision deficiencies
+    pub color_vision: crate::theme::ColorVision,
+    /// Colour live cells by age along the theme's `age_gradient` instead of by state
     pub generation_colors: bool,
+    /// Generations until a cell reaches the end of the age gradient
+    pub age_span: u64,
     pub max_visible_cells: usize,
+    /// Switch to the density heatmap (see `heatmap`) when zoomed out past `lod_zoom`
     pub lod_enabled: bool,
+    pub lod_zoom: f32,
+    /// Set while the heatmap is drawn instead of cell sprites
+    pub heatmap_active: bool,
+    /// Strength of the HDR bloom pass (see `glow`); 0 renders without HDR
+    pub bloom_intensity: f32,
+    /// Draw cells with the WGSL material (see `cell_material`) instead of CPU-built textures
+    pub shader_cells: bool,
+    /// Draw cells as flat, unanimated squares in one nearest-sampled texture (see `pixel_mode`)
+    pub pixel_mode: bool,
+    /// Draw cells as isometric cubes that grow taller with age (see `isometric`)
+    pub isometric: bool,
     /// Global multiplier for texture animation speed (1.0 = normal)
     pub animation_speed: f32,
+    /// No pulsing, texture cycling or birth/death scaling: cells simply appear and disappear
+    pub reduced_motion: bool,
     /// Frames-per-second for cycling between texture variations (visual refresh rate)
     pub texture_fps: f32,
     /// Base uniform scale applied to every cell sprite (controls visual density)
@@ -102,10 +129,20 @@ impl Default for CellRenderConfig {
         Self {
             cell_size: 20.0,
             base_color: Color::WHITE,
+            themes: Vec::new(),
+            color_vision: crate::theme::ColorVision::Normal,
             generation_colors: false,
+            age_span: 100,
             max_visible_cells: 10000,
             lod_enabled: true,
+            lod_zoom: 0.2,
+            heatmap_active: false,
+            bloom_intensity: 0.0,
+            shader_cells: false,
+            pixel_mode: false,
+            isometric: false,
             animation_speed: 2.0, // 2× faster animations by default
+            reduced_motion: false,
             texture_fps: 24.0,    // swap textures ~24 FPS
             base_scale: 0.65,     // Roughly 65% of the texture -> leaves some empty space
             overlay_scale: 0.10,  // Extra 10% stretch towards neighbouring connections
@@ -113,8 +150,20 @@ impl Default for CellRenderConfig {
     }
 }
 
-/// Return a distinct base colour for each cell state for better visual distinction between rules.
-fn base_color_for_state(state: CellState) -> Color {
+impl CellRenderConfig {
+    /// Animation speed in effect: nothing moves with reduced motion on
+    pub fn motion_speed(&self) -> f32 {
+        if self.reduced_motion { 0.0 } else { self.animation_speed }
+    }
+
+    /// Scale of a settled cell sprite; textures and the material quad are 32 px across
+    pub fn rest_scale(&self) -> f32 {
+        self.base_scale * self.cell_size / 32.0
+    }
+}
+
+/// Built-in colour for each cell state, used by the default theme (see `theme::CellColors`).
+pub fn base_color_for_state(state: CellState) -> Color {
     match state {
         CellState::Alive => Color::WHITE,
         CellState::Dying => Color::rgb_linear(0.6, 0.3, 0.8),
@@ -127,10 +176,33 @@ fn base_color_for_state(state: CellState) -> Color {
     }
 }
 
-/// Optimized cell rendering using procedural textures with object pooling
+/// Cell entities the renderer has spawned, and the view they were spawned for
+#[derive(Default)]
+pub struct RenderedCells {
+    entities: HashMap<(i32, i32), Entity>,
+    view: Option<(i32, i32, i32, i32)>,
+}
+
+/// Cells changed since the previous frame, drained from the grid by `collect_grid_changes`
+#[derive(Resource, Default)]
+pub struct GridChanges {
+    /// Changed cells and their new state, or `None` when they weren't logged and the
+    /// renderer has to rescan the view
+    pub cells: Option<Vec<(i32, i32, CellState)>>,
+}
+
+/// Hand the grid's birth/death deltas for this frame to the renderer
+pub fn collect_grid_changes(mut grid: ResMut<crate::InfiniteGrid>, mut changes: ResMut<GridChanges>) {
+    changes.cells = grid.take_changes();
+}
+
+/// Optimized cell rendering using procedural textures with object pooling.
+///
+/// While the view stays put only the cells in `GridChanges` are looked at; the view is
+/// rescanned after the camera moves, the heatmap hands back, or the changes weren't logged.
 pub fn render_optimized_cells(
     mut commands: Commands,
-    mut grid: ResMut<crate::InfiniteGrid>,
+    grid: Res<crate::InfiniteGrid>,
     camera_query: Query<(&Transform, &OrthographicProjection, &crate::camera::GameCamera), With<crate::camera::GameCamera>>,
     _camera_state: Res<crate::camera::CameraState>,
     existing_cells: Query<(Entity, &CellSprite, Option<&CellAnimation>)>,
@@ -140,12 +212,26 @@ pub fn render_optimized_cells(
     mut texture_pool: ResMut<CellTexturePool>,
     mut images: ResMut<Assets<Image>>,
     game_config: Res<crate::GameConfig>,
+    theme: Res<crate::theme::Theme>,
+    (mut cell_materials, mut materials): (ResMut<crate::cell_material::CellMaterials>, ResMut<Assets<crate::cell_material::CellMaterial>>),
+    (changes, mut rendered): (Res<GridChanges>, Local<RenderedCells>),
 ) {
+    if config.heatmap_active || config.pixel_mode || config.isometric {
+        // The density heatmap, pixel texture or isometric cubes draw the cells; no per-cell sprites
+        for (entity, _, _) in existing_cells.iter() {
+            commands.entity(entity).despawn();
+        }
+        rendered.entities.clear();
+        rendered.view = None;
+        return;
+    }
     if let Ok((camera_transform, _projection, game_camera)) = camera_query.get_single() {
         let window = windows.single();
         
         // Initialize texture pool if needed
-        initialize_texture_pool(&mut texture_pool, &mut images, &config);
+        if !config.shader_cells {
+            initialize_texture_pool(&mut texture_pool, &mut images, &config);
+        }
         
         // Calculate visible area bounds with some padding
         let camera_pos = camera_transform.translation.truncate();
@@ -158,81 +244,149 @@ pub fn render_optimized_cells(
         let max_x = ((camera_pos.x + world_size.x / 2.0 + padding.x) / config.cell_size).ceil() as i32;
         let min_y = ((camera_pos.y - world_size.y / 2.0 - padding.y) / config.cell_size).floor() as i32;
         let max_y = ((camera_pos.y + world_size.y / 2.0 + padding.y) / config.cell_size).ceil() as i32;
-        
-        // Get current alive cells first
-        let alive_cells = grid.get_alive_cells();
-        
-        // Create a set of currently existing cell entities for efficient lookup
-        let mut existing_positions = std::collections::HashSet::new();
-        for (entity, cell_sprite, animation) in existing_cells.iter() {
-            let pos = (cell_sprite.x, cell_sprite.y);
-            
-            // Check if cell position is within view bounds first
-            if cell_sprite.x < min_x || cell_sprite.x > max_x ||
-               cell_sprite.y < min_y || cell_sprite.y > max_y {
+        let view = (min_x, max_x, min_y, max_y);
+        let in_view = |x: i32, y: i32| x >= min_x && x <= max_x && y >= min_y && y <= max_y;
+
+        let death_dur = animation_duration(game_config.current_rule, AnimationType::Death);
+        let start_death = |commands: &mut Commands, entity: Entity| {
+            if config.reduced_motion {
                 commands.entity(entity).despawn();
+                return;
+            }
+            commands.entity(entity).insert(CellAnimation {
+                animation_type: AnimationType::Death,
+                timer: Timer::from_seconds(death_dur, TimerMode::Once),
+                progress: 0.0,
+            });
+        };
+        let is_dying = |animation: Option<&CellAnimation>| animation.is_some_and(|anim| matches!(anim.animation_type, AnimationType::Death));
+
+        let birth_dur = animation_duration(game_config.current_rule, AnimationType::Birth);
+        let mut spawn_cell = |commands: &mut Commands, x: i32, y: i32, state: CellState| -> Entity {
+            let world_x = x as f32 * config.cell_size;
+            let world_y = y as f32 * config.cell_size;
+            // Cells grow in from a tenth of their size, or start settled with reduced motion
+            let (scale, birth) = if config.reduced_motion {
+                (config.rest_scale(), None)
             } else {
-                existing_positions.insert(pos);
-                
-                // Check if this position is still alive and add death animation if needed
-                let still_alive = alive_cells.iter().any(|&(x, y)| x == cell_sprite.x && y == cell_sprite.y);
-                if !still_alive && animation.is_none() {
-                    let death_dur = animation_duration(game_config.current_rule, AnimationType::Death);
-                    commands.entity(entity).insert(CellAnimation {
-                        animation_type: AnimationType::Death,
-                        timer: Timer::from_seconds(death_dur, TimerMode::Once),
-                        progress: 0.0,
-                    });
+                let birth = CellAnimation {
+                    animation_type: AnimationType::Birth,
+                    timer: Timer::from_seconds(birth_dur, TimerMode::Once),
+                    progress: 0.0,
+                };
+                (0.1, Some(birth))
+            };
+
+            if config.shader_cells {
+                let age = grid.meta(x, y).map(|meta| meta.age(grid.generation()));
+                let bucket = crate::cell_material::age_bucket(age, config.age_span);
+                let material = cell_materials.get(state, bucket, &theme, &config, &mut materials);
+                let mut entity = commands.spawn((
+                    Mesh2d(cell_materials.quad.clone()),
+                    MeshMaterial2d(material),
+                    Transform::from_translation(Vec3::new(world_x, world_y, 0.0)).with_scale(Vec3::splat(scale)),
+                    CellSprite { x, y, cell_type: state },
+                ));
+                if let Some(birth) = birth {
+                    entity.insert(birth);
                 }
+                return entity.id();
             }
-        }
-        
-        // Spawn new cell entities for visible alive cells
-        for &(x, y) in alive_cells {
-            if x >= min_x && x <= max_x && y >= min_y && y <= max_y {
-                if !existing_positions.contains(&(x, y)) {
-                    let world_x = x as f32 * config.cell_size;
-                    let world_y = y as f32 * config.cell_size;
-                    
-                    // Get procedural texture for this cell
-                    let cell_texture = if let Some(texture) = get_cell_texture(
-                        &texture_pool,
-                        CellState::Alive,
-                        None, // No animation for new cells initially
-                        (x, y),
-                    ) {
-                        texture
+
+            // Get procedural texture for this cell
+            let cell_texture = if let Some(texture) = get_cell_texture(
+                &texture_pool,
+                state,
+                None, // No animation for new cells initially
+                (x, y),
+            ) {
+                texture
+            } else {
+                // Fallback to simple texture if procedural textures aren't ready
+                if texture_cache.simple_texture.is_none() {
+                    texture_cache.simple_texture = Some(create_simple_cell_texture(&mut images, 32, config.base_color));
+                }
+                texture_cache.simple_texture.as_ref().unwrap().clone()
+            };
+
+            let mut entity = commands.spawn((
+                Sprite {
+                    image: cell_texture,
+                    color: theme.cells.color(state),
+                    ..default()
+                },
+                Transform::from_translation(Vec3::new(world_x, world_y, 0.0)).with_scale(Vec3::splat(scale)),
+                CellSprite {
+                    x,
+                    y,
+                    cell_type: state,
+                },
+            ));
+            if let Some(birth) = birth {
+                entity.insert(birth);
+            }
+            entity.id()
+        };
+
+        match changes.cells.as_ref().filter(|_| rendered.view == Some(view)) {
+            Some(cells) => {
+                // Same view as last frame: only cells that were born or died need attention.
+                // State changes between live states are picked up by the animation systems.
+                let changed: std::collections::HashSet<(i32, i32)> =
+                    cells.iter().map(|&(x, y, _)| (x, y)).filter(|&(x, y)| in_view(x, y)).collect();
+                for (x, y) in changed {
+                    let state = grid.get(x, y);
+                    let current = rendered.entities.get(&(x, y)).and_then(|&entity| existing_cells.get(entity).ok());
+                    match current {
+                        Some((entity, _, animation)) if state == CellState::Dead => {
+                            if animation.is_none() {
+                                start_death(&mut commands, entity);
+                            }
+                        }
+                        Some((_, _, animation)) if !is_dying(animation) => {}
+                        _ if state == CellState::Dead => {}
+                        current => {
+                            // Born, or reborn while its old sprite is still fading out
+                            if let Some((entity, _, _)) = current {
+                                commands.entity(entity).despawn();
+                            }
+                            let entity = spawn_cell(&mut commands, x, y, state);
+                            rendered.entities.insert((x, y), entity);
+                        }
+                    }
+                }
+            }
+            None => {
+                rendered.entities.clear();
+                let mut existing_positions = std::collections::HashSet::new();
+                for (entity, cell_sprite, animation) in existing_cells.iter() {
+                    let pos = (cell_sprite.x, cell_sprite.y);
+                    let still_alive = grid.get(cell_sprite.x, cell_sprite.y) != CellState::Dead;
+
+                    if !in_view(cell_sprite.x, cell_sprite.y) || (still_alive && is_dying(animation)) {
+                        // Out of view, or a fading sprite whose cell has come back to life
+                        commands.entity(entity).despawn();
                     } else {
-                        // Fallback to simple texture if procedural textures aren't ready
-                        if texture_cache.simple_texture.is_none() {
-                            texture_cache.simple_texture = Some(create_simple_cell_texture(&mut images, 32, config.base_color));
+                        existing_positions.insert(pos);
+                        rendered.entities.insert(pos, entity);
+
+                        // Add a death animation to sprites whose cell has died
+                        if !still_alive && animation.is_none() {
+                            start_death(&mut commands, entity);
                         }
-                        texture_cache.simple_texture.as_ref().unwrap().clone()
-                    };
-
-                    let birth_dur = animation_duration(game_config.current_rule, AnimationType::Birth);
-                    commands.spawn((
-                        Sprite {
-                            image: cell_texture,
-                            color: base_color_for_state(CellState::Alive),
-                            ..default()
-                        },
-                        Transform::from_translation(Vec3::new(world_x, world_y, 0.0))
-                            .with_scale(Vec3::splat(0.1)), // Start small for birth animation
-                        CellSprite {
-                            x,
-                            y,
-                            cell_type: CellState::Alive,
-                        },
-                        CellAnimation {
-                            animation_type: AnimationType::Birth,
-                            timer: Timer::from_seconds(birth_dur, TimerMode::Once),
-                            progress: 0.0,
-                        },
-                    ));
+                    }
+                }
+
+                // Spawn new cell entities for visible alive cells (only chunks overlapping the view are visited)
+                for (x, y, state) in grid.cell_states_in_region(min_x, max_x, min_y, max_y) {
+                    if !existing_positions.contains(&(x, y)) {
+                        let entity = spawn_cell(&mut commands, x, y, state);
+                        rendered.entities.insert((x, y), entity);
+                    }
                 }
             }
         }
+        rendered.view = Some(view);
     }
 }
 
@@ -292,6 +446,13 @@ pub fn update_cell_render_config(
         } else {
             10000 // Default
         };
+
+        // Leave the heatmap a little further in than it was entered, so it doesn't flicker at the threshold
+        let threshold = if config.heatmap_active { config.lod_zoom * 1.1 } else { config.lod_zoom };
+        let active = config.lod_enabled && camera.zoom < threshold;
+        if active != config.heatmap_active {
+            config.heatmap_active = active;
+        }
     }
 }
 
@@ -305,7 +466,13 @@ pub fn animate_cell_textures(
     mut images: ResMut<Assets<Image>>,
     grid: Res<crate::InfiniteGrid>,
     game_config: Res<crate::GameConfig>,
+    theme: Res<crate::theme::Theme>,
 ) {
+    // The cell material animates in its shader; there are no textures to keep up
+    if config.shader_cells {
+        return;
+    }
+
     // Initialize texture pool if needed
     if !texture_pool.is_initialized {
         initialize_texture_pool(&mut texture_pool, &mut images, &config);
@@ -316,10 +483,10 @@ pub fn animate_cell_textures(
     texture_pool.texture_update_timer.tick(time.delta());
 
     // Advance our own time tracker with speed multiplier
-    texture_pool.last_update_time += time.delta().as_secs_f32() * config.animation_speed;
+    texture_pool.last_update_time += time.delta().as_secs_f32() * config.motion_speed();
     
     // Evolve texture sets when evolution timer finishes (less frequent, major changes)
-    if texture_pool.evolution_timer.just_finished() {
+    if texture_pool.evolution_timer.just_finished() && !config.reduced_motion {
         texture_pool.generation_seed = texture_pool.generation_seed.wrapping_add(1);
         
         println!("🧬 Evolving texture sets... generation {}", texture_pool.generation_seed);
@@ -392,17 +559,23 @@ pub fn animate_cell_textures(
         }
 
         // Determine the base colour for this cell
-        let base_lin = base_color_for_state(current_state).to_linear();
+        let age = grid.meta(cell_sprite.x, cell_sprite.y).filter(|_| config.generation_colors && current_state == CellState::Alive);
+        let base_lin = match age {
+            Some(meta) => theme.age_color(meta.age(grid.generation()), config.age_span),
+            None => theme.cells.color(current_state),
+        }
+        .to_linear();
 
         // Apply colour effects based on animation, modulating the base colour rather than overriding with greyscale.
         if let Some(anim) = animation {
             let progress = anim.timer.fraction();
             match anim.animation_type {
                 AnimationType::Birth => {
-                    // Bright flash effect during birth
+                    // Bright flash in the theme's glow tint during birth
                     let pulse = (texture_pool.last_update_time * 12.0).sin() * 0.3 + 1.0;
                     let intensity = (1.0 + (1.0 - progress) * 0.8) * pulse;
-                    sprite.color = Color::linear_rgb(base_lin.red * intensity, base_lin.green * intensity, base_lin.blue * intensity);
+                    let flash = base_lin.mix(&theme.glow.0.to_linear(), (1.0 - progress) * 0.6);
+                    sprite.color = Color::linear_rgb(flash.red * intensity, flash.green * intensity, flash.blue * intensity);
                 }
                 AnimationType::Death => {
                     // Fade with flicker, keep hue
@@ -423,6 +596,32 @@ pub fn animate_cell_textures(
             let living_pulse = (texture_pool.last_update_time * 3.0 + (cell_sprite.x + cell_sprite.y) as f32 * 0.2).sin() * 0.1 + 1.0;
             sprite.color = Color::linear_rgb(base_lin.red * living_pulse, base_lin.green * living_pulse, base_lin.blue * living_pulse);
         }
+
+        // Push newborns and electron heads past full brightness so the bloom pass picks them up
+        if config.bloom_intensity > 0.0 {
+            let boost = 1.0 + crate::glow::emissive_boost(current_state, animation);
+            let lin = sprite.color.to_linear();
+            sprite.color = Color::linear_rgba(lin.red * boost, lin.green * boost, lin.blue * boost, lin.alpha);
+        }
+    }
+}
+
+/// Toggle age colouring, keeping the grid's per-cell metadata (which records birth
+/// generations) switched on while it's in use
+pub fn toggle_age_colors(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<crate::keybindings::Keybindings>,
+    mut config: ResMut<CellRenderConfig>,
+    mut grid: ResMut<crate::InfiniteGrid>,
+) {
+    if keys.just_pressed(&keyboard_input, crate::keybindings::Action::AgeColors) {
+        config.generation_colors = !config.generation_colors;
+        toast(tr!("🎨 Age colours {}", if config.generation_colors { "on" } else { "off" }));
+    }
+    // Loading a session replaces the grid, so check every frame rather than only on toggle.
+    // The cell material shades by age too, and isometric cubes grow with it.
+    if (config.generation_colors || config.shader_cells || config.isometric) && !grid.metadata_enabled() {
+        grid.enable_metadata();
     }
 }
 
@@ -610,23 +809,83 @@ fn initialize_texture_pool(
         texture_pool.generation_seed.wrapping_add(200),
     );
     
+    // Multi-state rules get a distinct shape per state
+    texture_pool.state_textures = MULTI_STATES
+        .into_iter()
+        .map(|state| (state, images.add(create_state_cell_texture(64, state))))
+        .collect();
+    
     texture_pool.is_initialized = true;
-    println!("🎨 Procedural cell textures initialized! {} alive, {} newborn, {} dying variants", 
+    println!("🎨 Procedural cell textures initialized! {} alive, {} newborn, {} dying variants, {} state shapes", 
              texture_pool.alive_textures.len(),
              texture_pool.newborn_textures.len(),
-             texture_pool.dying_textures.len());
+             texture_pool.dying_textures.len(),
+             texture_pool.state_textures.len());
+}
+
+/// States of multi-state rules that get their own texture instead of the organic alive set
+const MULTI_STATES: [CellState; 6] = [
+    CellState::Dying,
+    CellState::Wire,
+    CellState::ElectronHead,
+    CellState::ElectronTail,
+    CellState::SpeciesA,
+    CellState::SpeciesB,
+];
+
+/// Coverage (0..=1) of a state's shape at offset `(dx, dy)` from the texture centre, in
+/// units of the half-size: wires are near-square traces, electron heads bright discs,
+/// tails rings, Brian's refractory cells small dots and the two Immigration species a
+/// disc and a diamond, so the states stay apart even where their colours are close.
+fn state_shape_coverage(state: CellState, dx: f32, dy: f32) -> f32 {
+    let radius = (dx * dx + dy * dy).sqrt();
+    // Soft edge so shapes don't alias when scaled down
+    let edge = |distance: f32, limit: f32| ((limit - distance) / 0.08).clamp(0.0, 1.0);
+    match state {
+        CellState::Wire => edge(dx.abs().max(dy.abs()), 0.85) * 0.85,
+        CellState::ElectronHead => edge(radius, 0.9) * (1.0 - radius * 0.3).max(0.0),
+        CellState::ElectronTail => edge(radius, 0.85) * edge(0.5, radius),
+        CellState::Dying => edge(radius, 0.55) * 0.7,
+        CellState::SpeciesA => edge(radius, 0.85),
+        CellState::SpeciesB => edge(dx.abs() + dy.abs(), 0.95),
+        CellState::Alive | CellState::Dead => 0.0,
+    }
+}
+
+/// White texture for a multi-state cell, tinted by the sprite colour
+fn create_state_cell_texture(size: u32, state: CellState) -> Image {
+    let half = size as f32 / 2.0;
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let dx = (x as f32 + 0.5 - half) / half;
+            let dy = (y as f32 + 0.5 - half) / half;
+            let coverage = state_shape_coverage(state, dx, dy);
+            data.extend_from_slice(&[255, 255, 255, (coverage * 255.0) as u8]);
+        }
+    }
+    Image::new(
+        Extent3d { width: size, height: size, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    )
 }
 
 /// Get a texture for a cell based on its state and variation
 fn get_cell_texture(
     texture_pool: &CellTexturePool,
-    _cell_state: CellState,
+    cell_state: CellState,
     animation: Option<&CellAnimation>,
     position: (i32, i32),
 ) -> Option<Handle<Image>> {
     if !texture_pool.is_initialized {
         return None;
     }
+    if let Some(texture) = texture_pool.state_textures.get(&cell_state) {
+        return Some(texture.clone());
+    }
     
     // Choose texture set based on animation state
     let texture_set = if let Some(anim) = animation {
@@ -732,7 +991,7 @@ fn create_dynamic_cell_texture(
 /// Get a dynamic texture for a cell based on its state and variation
 fn get_dynamic_cell_texture(
     texture_pool: &CellTexturePool,
-    _cell_state: CellState,
+    cell_state: CellState,
     animation: Option<&CellAnimation>,
     position: (i32, i32),
     last_update_time: f32,
@@ -741,6 +1000,9 @@ fn get_dynamic_cell_texture(
     if !texture_pool.is_initialized {
         return None;
     }
+    if let Some(texture) = texture_pool.state_textures.get(&cell_state) {
+        return Some(texture.clone());
+    }
     
     // Choose texture set based on animation state
     let texture_set = if let Some(anim) = animation {
@@ -791,4 +1053,25 @@ fn animation_duration(rule: RuleType, anim: AnimationType) -> f32 {
             _ => 0.25,
         },
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_state_shapes_differ() {
+        let shapes: Vec<Vec<u8>> = MULTI_STATES
+            .into_iter()
+            .map(|state| create_state_cell_texture(16, state).data)
+            .collect();
+        for (i, shape) in shapes.iter().enumerate() {
+            assert!(shape.chunks_exact(4).any(|pixel| pixel[3] > 0), "{:?} is empty", MULTI_STATES[i]);
+            for other in &shapes[i + 1..] {
+                assert_ne!(shape, other);
+            }
+        }
+        // Electron tails are rings: hollow in the middle
+        assert_eq!(state_shape_coverage(CellState::ElectronTail, 0.0, 0.0), 0.0);
+        assert!(state_shape_coverage(CellState::ElectronHead, 0.0, 0.0) > 0.9);
+    }
+}
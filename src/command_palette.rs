@@ -0,0 +1,409 @@
+//! Command palette.
+//!
+//! Ctrl+P (by default) pauses the game under a search box listing every command: each
+//! keybinding action, a few Shift variants, the library patterns for the current rule, every
+//! built-in rule, and commands with no key of their own such as exporting the grid as an RLE
+//! file. Typing filters the list by fuzzy match (the letters in order, with runs and word
+//! starts ranked first), Up/Down pick, Enter or a click runs the command and Esc closes.
+//!
+//! Key commands are run by pressing their key for the game on the next frame, after input is
+//! read, so they do exactly what the key does (and follow rebinding) without every hotkey
+//! system needing a second way in.
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::{ButtonState, InputSystem};
+use bevy::prelude::*;
+use crate::controls_screen::action_label;
+use crate::export::ExportSettings;
+use crate::game_config::GameConfig;
+use crate::infinite_grid::patterns::{for_rule, LibraryPattern};
+use crate::infinite_grid::InfiniteGrid;
+use crate::io::{write_rle, Pattern};
+use crate::keybindings::{Action, Keybindings};
+use crate::settings_menu::{button_color, BUTTON_IDLE};
+use crate::start_screen::{GameState, RuleType};
+use crate::tools::StampState;
+use crate::ui::toast;
+use crate::tr;
+
+/// Commands listed at once
+const MAX_SHOWN: usize = 12;
+
+/// Actions whose Shift variant does something else, with what it does
+pub const SHIFTED: [(Action, &str); 11] = [
+    (Action::NextTheme, "previous theme"),
+    (Action::AddLayer, "remove layer"),
+    (Action::Symmetry, "set symmetry origin"),
+    (Action::Screenshot, "save png of the whole pattern"),
+    (Action::ExportSvg, "save svg of the whole pattern"),
+    (Action::RecordGif, "record the next n generations"),
+    (Action::Bloom, "stronger bloom"),
+    (Action::SplitScreen, "next split-screen rule"),
+    (Action::MuteDrone, "solo drone"),
+    (Action::MuteSamples, "solo samples"),
+    (Action::MuteGroove, "solo groove"),
+];
+
+const SELECTED_COLOR: Color = Color::srgba(0.45, 0.4, 0.2, 0.95);
+
+/// Something the palette can do
+#[derive(Clone, Copy, Debug)]
+pub enum Command {
+    /// Press the action's key
+    Key(Action),
+    /// Press the action's key with Shift
+    Shifted(Action, &'static str),
+    /// Pick up a library pattern to place
+    Pattern(&'static LibraryPattern),
+    Rule(RuleType),
+    ExportRle,
+}
+
+impl Command {
+    pub fn label(&self, keys: &Keybindings) -> String {
+        match self {
+            Command::Key(action) if uses_ctrl(*action) => format!("{} (Ctrl+{})", action_label(*action), keys.label(*action)),
+            Command::Key(action) => format!("{} ({})", action_label(*action), keys.label(*action)),
+            Command::Shifted(action, label) => format!("{} (Shift+{})", label, keys.label(*action)),
+            Command::Pattern(pattern) => format!("insert pattern: {}", pattern.name),
+            Command::Rule(rule) => format!("switch rule: {}", rule.name()),
+            Command::ExportRle => "export rle file".to_string(),
+        }
+    }
+}
+
+/// Copy and paste are Ctrl+key; their plain key belongs to other actions
+pub fn uses_ctrl(action: Action) -> bool {
+    matches!(action, Action::Copy | Action::Paste)
+}
+
+/// Every command available under `rule`
+pub fn commands(rule: RuleType) -> Vec<Command> {
+    Action::ALL
+        .into_iter()
+        .filter(|&action| action != Action::CommandPalette)
+        .map(Command::Key)
+        .chain(SHIFTED.into_iter().map(|(action, label)| Command::Shifted(action, label)))
+        .chain(for_rule(rule).map(Command::Pattern))
+        .chain(RuleType::all().into_iter().map(Command::Rule))
+        .chain([Command::ExportRle])
+        .collect()
+}
+
+/// How well `query` matches `label`, higher being better; `None` unless the query's letters
+/// all appear in order. Consecutive letters and letters starting a word score extra.
+pub fn fuzzy_score(query: &str, label: &str) -> Option<i32> {
+    let label: Vec<char> = label.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.chars().flat_map(char::to_lowercase).filter(|c| !c.is_whitespace()) {
+        let found = next + label[next..].iter().position(|&c| c == wanted)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 3;
+        }
+        if found == 0 || !label[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        score -= (found - next).min(5) as i32 / 2;
+        previous = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
+
+/// Indices of the labels matching `query`, best first (list order among equals)
+pub fn rank(query: &str, labels: &[String]) -> Vec<usize> {
+    let mut scored: Vec<(i32, usize)> =
+        labels.iter().enumerate().filter_map(|(i, label)| fuzzy_score(query, label).map(|score| (score, i))).collect();
+    scored.sort_by_key(|&(score, i)| (-score, i));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+#[derive(Resource, Default)]
+struct Palette {
+    query: String,
+    commands: Vec<Command>,
+    labels: Vec<String>,
+    /// Indices into `commands`, best match first
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+impl Palette {
+    fn refilter(&mut self) {
+        self.matches = rank(&self.query, &self.labels);
+        self.selected = 0;
+    }
+}
+
+/// Keys to press for the game on the next frame, and those pressed on this one
+#[derive(Resource, Default)]
+struct InjectedKeys {
+    queued: Vec<KeyCode>,
+    held: Vec<KeyCode>,
+}
+
+#[derive(Component)]
+struct PaletteScreen;
+
+#[derive(Component)]
+struct QueryText;
+
+/// One of the `MAX_SHOWN` result rows, top to bottom
+#[derive(Component)]
+struct ResultRow(usize);
+
+pub struct CommandPalettePlugin;
+
+impl Plugin for CommandPalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Palette>()
+            .init_resource::<InjectedKeys>()
+            .add_systems(PreUpdate, inject_keys.after(InputSystem))
+            .add_systems(Update, open_palette.run_if(in_state(GameState::Playing)))
+            .add_systems(OnEnter(GameState::Palette), spawn_palette)
+            .add_systems(OnExit(GameState::Palette), despawn_palette)
+            .add_systems(Update, (type_query, click_results, refresh_palette).chain().run_if(in_state(GameState::Palette)));
+    }
+}
+
+/// Release the keys pressed last frame and press the queued ones
+fn inject_keys(mut injected: ResMut<InjectedKeys>, mut keyboard_input: ResMut<ButtonInput<KeyCode>>) {
+    if injected.queued.is_empty() && injected.held.is_empty() {
+        return;
+    }
+    for key in std::mem::take(&mut injected.held) {
+        keyboard_input.release(key);
+    }
+    let queued = std::mem::take(&mut injected.queued);
+    for &key in &queued {
+        keyboard_input.press(key);
+    }
+    injected.held = queued;
+}
+
+fn open_palette(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight, KeyCode::SuperLeft, KeyCode::SuperRight]);
+    if ctrl && keys.just_pressed(&keyboard_input, Action::CommandPalette) {
+        next_state.set(GameState::Palette);
+    }
+}
+
+fn spawn_palette(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    (keys, game_config): (Res<Keybindings>, Res<GameConfig>),
+    mut palette: ResMut<Palette>,
+) {
+    palette.query.clear();
+    palette.commands = self::commands(game_config.current_rule);
+    palette.labels = palette.commands.iter().map(|command| command.label(&keys)).collect();
+    palette.refilter();
+
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                padding: UiRect::top(Val::Px(80.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            GlobalZIndex(20),
+            PaletteScreen,
+        ))
+        .with_children(|screen| {
+            screen
+                .spawn((
+                    Node {
+                        width: Val::Px(520.0),
+                        height: Val::Auto,
+                        align_self: AlignSelf::FlexStart,
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::all(Val::Px(8.0)),
+                        row_gap: Val::Px(2.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.95)),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((Text::new("> "), text(20.0), TextColor(Color::srgb(1.0, 0.9, 0.3)), QueryText));
+                    for row in 0..MAX_SHOWN {
+                        panel
+                            .spawn((
+                                Button,
+                                Node { padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)), ..default() },
+                                BackgroundColor(BUTTON_IDLE),
+                                ResultRow(row),
+                            ))
+                            .with_child((Text::new(""), text(15.0), TextColor(Color::WHITE)));
+                    }
+                    panel.spawn((
+                        Text::new(tr!("Type to search, Up/Down to pick, Enter to run, Esc to close")),
+                        text(13.0),
+                        TextColor(Color::srgb(0.6, 0.6, 0.6)),
+                    ));
+                });
+        });
+}
+
+fn despawn_palette(mut commands: Commands, screens: Query<Entity, With<PaletteScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn type_query(
+    mut typed: EventReader<KeyboardInput>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut palette: ResMut<Palette>,
+    mut run: RunCommand,
+) {
+    // Ctrl+letters aren't typing (the Ctrl+P that opened the palette, for one)
+    let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight, KeyCode::SuperLeft, KeyCode::SuperRight]);
+    for event in typed.read() {
+        if event.state != ButtonState::Pressed || ctrl {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(typed) if !typed.chars().any(char::is_control) => palette.query.push_str(typed),
+            Key::Space => palette.query.push(' '),
+            Key::Backspace => {
+                palette.query.pop();
+            }
+            _ => continue,
+        }
+        palette.refilter();
+    }
+    let shown = palette.matches.len().min(MAX_SHOWN);
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) && shown > 0 {
+        palette.selected = (palette.selected + 1) % shown;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) && shown > 0 {
+        palette.selected = (palette.selected + shown - 1) % shown;
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        run.next_state.set(GameState::Playing);
+    } else if keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) {
+        if let Some(&index) = palette.matches.get(palette.selected) {
+            run.run(palette.commands[index]);
+        }
+    }
+}
+
+fn click_results(rows: Query<(&Interaction, &ResultRow), Changed<Interaction>>, palette: Res<Palette>, mut run: RunCommand) {
+    for (interaction, row) in &rows {
+        if *interaction == Interaction::Pressed {
+            if let Some(&index) = palette.matches.get(row.0) {
+                run.run(palette.commands[index]);
+            }
+        }
+    }
+}
+
+/// Everything running a command touches
+#[derive(bevy::ecs::system::SystemParam)]
+struct RunCommand<'w> {
+    next_state: ResMut<'w, NextState<GameState>>,
+    injected: ResMut<'w, InjectedKeys>,
+    keys: Res<'w, Keybindings>,
+    game_config: ResMut<'w, GameConfig>,
+    stamp: ResMut<'w, StampState>,
+    grid: Res<'w, InfiniteGrid>,
+    export: Res<'w, ExportSettings>,
+}
+
+impl RunCommand<'_> {
+    /// Run `command` and go back to the game
+    fn run(&mut self, command: Command) {
+        self.next_state.set(GameState::Playing);
+        match command {
+            Command::Key(action) => {
+                if uses_ctrl(action) {
+                    self.injected.queued.push(KeyCode::ControlLeft);
+                }
+                self.injected.queued.push(self.keys.key(action));
+            }
+            Command::Shifted(action, _) => {
+                self.injected.queued.extend([KeyCode::ShiftLeft, self.keys.key(action)]);
+            }
+            Command::Pattern(pattern) => {
+                self.stamp.arm(pattern);
+                toast(tr!("📂 Picked up {} - click to place", pattern.name));
+            }
+            Command::Rule(rule) => {
+                self.game_config.current_rule = rule;
+                toast(tr!("🧬 Rule: {} ({})", rule.rulestring(), rule.name()));
+            }
+            Command::ExportRle => {
+                let pattern = Pattern::from_grid(&self.grid, self.game_config.current_rule);
+                let saved = self.export.next_path("pattern", "rle").and_then(|path| std::fs::write(&path, write_rle(&pattern)).map(|_| path));
+                match saved {
+                    Ok(path) => toast(tr!("📋 Saved {} cells as RLE to {}", pattern.cells.len(), path.display())),
+                    Err(e) => toast(tr!("📋 RLE export failed: {}", e)),
+                }
+            }
+        }
+    }
+}
+
+fn refresh_palette(
+    palette: Res<Palette>,
+    mut query_texts: Query<&mut Text, With<QueryText>>,
+    mut rows: Query<(&ResultRow, &Interaction, &Children, &mut Visibility, &mut BackgroundColor)>,
+    mut texts: Query<&mut Text, Without<QueryText>>,
+) {
+    for mut text in &mut query_texts {
+        let shown = format!("> {}_", palette.query);
+        if text.0 != shown {
+            text.0 = shown;
+        }
+    }
+    for (row, interaction, children, mut visibility, mut background) in &mut rows {
+        let Some(&index) = palette.matches.get(row.0) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        background.0 = if row.0 == palette.selected { SELECTED_COLOR } else { button_color(*interaction) };
+        for &child in children {
+            if let Ok(mut text) = texts.get_mut(child) {
+                if text.0 != palette.labels[index] {
+                    text.0.clone_from(&palette.labels[index]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_search_ranks_word_starts_and_runs_first() {
+        assert_eq!(fuzzy_score("xyz", "toggle hud"), None);
+        assert!(fuzzy_score("hud", "toggle hud").is_some());
+        let labels: Vec<String> = ["quick save (F5)", "toggle hud (H)", "export svg (F8)", "switch rule: HighLife"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(rank("sv", &labels), vec![2, 0]);
+        assert_eq!(rank("", &labels), vec![0, 1, 2, 3]);
+        assert_eq!(rank("HIGH", &labels)[0], 3);
+
+        let conway = commands(RuleType::Conway);
+        assert!(conway.iter().any(|c| matches!(c, Command::Pattern(p) if p.name == "Glider")));
+        assert!(!conway.iter().any(|c| matches!(c, Command::Key(Action::CommandPalette))));
+    }
+}
@@ -2,15 +2,40 @@
 //!
 //! * Looks for `oraclelife.toml` in the cwd unless overridden by `--config`.
 //! * Provides defaults so the file is optional.
+//! * Written back by `Config::save` on exit, so window and session preferences (window
+//!   geometry, rule, zoom, brush size, HUD visibility and layout) carry over to the next launch.
+//!   Comments and keys this struct doesn't know about are left as they are.
 //!
 //! Extend this struct whenever you add new tunables.
 
-use serde::Deserialize;
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use toml_edit::{DocumentMut, Item};
+use crate::audio::Mode;
+use crate::audio::user_samples::SampleMode;
+use crate::export::{RecordFormat, VideoFormat};
+use crate::pattern_markers::MarkerMode;
+use crate::population_graph::GraphMode;
+use crate::theme::ColorVision;
+use crate::ui::HudLayout;
 use std::fs;
 // use std::path::Path;
 
+/// Config file read when no other path is given
+pub const DEFAULT_CONFIG_PATH: &str = "oraclelife.toml";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("could not write config: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    #[error("existing config is not valid TOML: {0}")]
+    Parse(#[from] toml_edit::TomlError),
+}
+
 /// Audio engine options
-#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub enum AudioEngine {
     Spatial,  // Current spatial audio system
     DDSP,     // Neural DDSP synthesis
@@ -25,6 +50,17 @@ impl Default for AudioEngine {
 }
 
 impl AudioEngine {
+    pub const ALL: [AudioEngine; 4] = [AudioEngine::Spatial, AudioEngine::Hybrid, AudioEngine::DDSP, AudioEngine::DungeonSynth];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AudioEngine::Spatial => "Spatial",
+            AudioEngine::DDSP => "DDSP",
+            AudioEngine::DungeonSynth => "Dungeon synth",
+            AudioEngine::Hybrid => "Hybrid",
+        }
+    }
+
     pub fn from_string(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "spatial" => AudioEngine::Spatial,
@@ -36,7 +72,7 @@ impl AudioEngine {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
     /// Desired frame-rate cap.
     pub fps:            u32,
@@ -51,9 +87,260 @@ pub struct Config {
     /// Master audio volume (0.0 to 1.0)
     #[serde(default = "default_volume")]
     pub audio_volume:   f32,
+    /// Start with the audio muted (the volume is kept for unmuting)
+    #[serde(default)]
+    pub audio_muted:    bool,
+    /// Largest region the fill tool will paint
+    #[serde(default = "default_fill_max_cells")]
+    pub fill_max_cells: usize,
+    /// Cells further than this from the camera are discarded (unset = keep everything)
+    #[serde(default)]
+    pub prune_distance: Option<i32>,
+    /// Furthest cells are discarded once the population passes this (unset = no cap)
+    #[serde(default = "default_prune_max_population")]
+    pub prune_max_population: Option<usize>,
+    /// Folder listed by the pattern browser
+    #[serde(default = "default_pattern_dir")]
+    pub pattern_dir: String,
+    /// Pixels per cell in exported images and recordings
+    #[serde(default = "default_export_cell_pixels")]
+    pub export_cell_pixels: u32,
+    /// Folder screenshots and recordings are written to
+    #[serde(default = "default_export_dir")]
+    pub export_dir: String,
+    /// Write runs of same-state cells as one rectangle in SVG exports
+    #[serde(default = "default_svg_merge_runs")]
+    pub svg_merge_runs: bool,
+    /// Recording format: "gif" or "apng"
+    #[serde(default)]
+    pub record_format: RecordFormat,
+    /// Recording playback speed in generations per second
+    #[serde(default = "default_record_fps")]
+    pub record_fps: u32,
+    /// Generations captured by Shift+F10
+    #[serde(default = "default_record_generations")]
+    pub record_generations: usize,
+    /// Video format: "mp4" or "webm"
+    #[serde(default)]
+    pub video_format: VideoFormat,
+    /// Frames per second captured into videos
+    #[serde(default = "default_video_fps")]
+    pub video_fps: u32,
+    /// ffmpeg executable used for video recording
+    #[serde(default = "default_ffmpeg_path")]
+    pub ffmpeg_path: String,
+    /// Key overrides, `action = "Key"` (see `keybindings`)
+    #[serde(default)]
+    pub keybindings: HashMap<String, String>,
+    /// Colour theme, loaded from `themes/<name>.toml`
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    /// Interface language, translated from `locales/<locale>.toml` (see `locale`)
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    /// Size of the whole interface, panels and text alike, for high-DPI screens
+    #[serde(default = "default_scale")]
+    pub ui_scale: f32,
+    /// Size of the interface text on top of `ui_scale`
+    #[serde(default = "default_scale")]
+    pub font_scale: f32,
+    /// Zoom below which a density heatmap replaces individual cells (0 = never)
+    #[serde(default = "default_heatmap_zoom")]
+    pub heatmap_zoom: f32,
+    /// Colour live cells by age (toggle in game with G)
+    #[serde(default)]
+    pub age_colors: bool,
+    /// Generations until a cell reaches the end of the theme's age gradient
+    #[serde(default = "default_age_span")]
+    pub age_span: u64,
+    /// Leave fading ghosts where cells died (toggle in game with K)
+    #[serde(default)]
+    pub trails: bool,
+    /// Generations a death-trail ghost takes to fade out
+    #[serde(default = "default_trail_length")]
+    pub trail_length: u32,
+    /// Show cell boundaries when zoomed in (toggle in game with X)
+    #[serde(default)]
+    pub grid_lines: bool,
+    /// Zoom above which grid lines are drawn
+    #[serde(default = "default_grid_lines_zoom")]
+    pub grid_lines_zoom: f32,
+    /// Strength of the bloom glow around bright cells (0 = off; toggle in game with Q)
+    #[serde(default)]
+    pub bloom_intensity: f32,
+    /// Draw cells with the WGSL cell shader rather than CPU-generated textures
+    #[serde(default = "default_cell_shader")]
+    pub cell_shader: bool,
+    /// Cells appear and disappear without pulsing, texture cycling or birth/death scaling
+    #[serde(default)]
+    pub reduced_motion: bool,
+    /// Draw cells as crisp, unanimated squares instead of organic textures (see `pixel_mode`)
+    #[serde(default)]
+    pub pixel_mode: bool,
+    /// Draw cells as isometric cubes, taller the older they are (see `isometric`)
+    #[serde(default)]
+    pub isometric: bool,
+    /// Show the corner minimap (toggle in game with I)
+    #[serde(default = "default_minimap")]
+    pub minimap: bool,
+    /// Show where births have happened over the run (toggle in game with E)
+    #[serde(default)]
+    pub activity_heat: bool,
+    /// Generations for a cell's activity heat to halve
+    #[serde(default = "default_activity_half_life")]
+    pub activity_half_life: f32,
+    /// Cell and accent colours safe for a colour vision deficiency, applied over the theme
+    #[serde(default)]
+    pub color_vision: ColorVision,
+    /// Pattern bounds and centroid overlays: "off", "bounds", "centroid" or "both" (F4 cycles)
+    #[serde(default)]
+    pub pattern_markers: MarkerMode,
+    /// Population graph above the minimap: "off", "population" or "full" with births and
+    /// deaths too (F2 cycles)
+    #[serde(default)]
+    pub population_graph: GraphMode,
+    /// Generations the population graph spans
+    #[serde(default = "default_graph_generations")]
+    pub graph_generations: usize,
+    /// Generations the run control (Enter) advances before pausing
+    #[serde(default = "default_run_generations")]
+    pub run_generations: u64,
+    /// Generations kept for stepping backwards (Backspace); 0 turns rewinding off
+    #[serde(default = "default_rewind_generations")]
+    pub rewind_generations: usize,
+    /// Ask before clearing the grid or resetting to the start screen
+    #[serde(default = "default_confirm_destructive")]
+    pub confirm_destructive: bool,
+    /// Screen shake on extinctions, population milestones and bells, in pixels (0 = off)
+    #[serde(default)]
+    pub camera_shake: f32,
+    /// Give rules such as WireWorld and Seeds their own look (see `presets`)
+    #[serde(default = "default_rule_presets")]
+    pub rule_presets: bool,
+    /// Seconds between refreshes of the HUD text; raise it on slow machines, 0 refreshes
+    /// every frame
+    #[serde(default = "default_hud_refresh")]
+    pub hud_refresh: f32,
+    /// Root note of the hybrid engine's scale, such as "D" or "F#" (unset = follows the pattern)
+    #[serde(default)]
+    pub key_root: Option<String>,
+    /// Mode of the hybrid engine's scale, "ionian" to "locrian" (unset = follows the pattern)
+    #[serde(default)]
+    pub key_mode: Option<Mode>,
+    /// Folder of the player's own samples, with `pluck`, `bell` and `pad` subfolders of WAV
+    /// and OGG files (see `audio::user_samples`)
+    #[serde(default = "default_sample_dir")]
+    pub sample_dir: String,
+    /// Whether the player's samples join ("layer") or replace ("replace") the generated ones
+    #[serde(default)]
+    pub sample_mode: SampleMode,
+    /// Tempo of the step sequencer's playhead, a column per sixteenth note
+    #[serde(default = "default_sequencer_bpm")]
+    pub sequencer_bpm: f32,
+    /// Part of the name of the MIDI input port to play cells from (unset = the first port)
+    #[serde(default)]
+    pub midi_port: Option<String>,
+    /// MIDI notes per grid row; 12 puts an octave on each row
+    #[serde(default = "default_midi_columns")]
+    pub midi_columns: u32,
+    /// `host:port` to stream the game's features to over OSC (unset = off; see `osc`)
+    #[serde(default)]
+    pub osc_target: Option<String>,
+    /// OSC messages per second
+    #[serde(default = "default_osc_rate")]
+    pub osc_rate: f32,
+    /// Regions per side of the view whose densities are sent over OSC
+    #[serde(default = "default_osc_regions")]
+    pub osc_regions: u32,
+    /// Sample rate to run the audio at, if the device can (unset = 44100 Hz, or the device's
+    /// own rate if it can't)
+    #[serde(default)]
+    pub audio_sample_rate: Option<u32>,
+    /// Frames per audio buffer; lower for less delay, raise if the sound crackles (unset =
+    /// the device's choice)
+    #[serde(default)]
+    pub audio_buffer_size: Option<u32>,
+    /// Safetensors file of pretrained decoders for the DDSP engine (unset = untrained ones;
+    /// see `audio::ddsp_engine` for the tensors it holds)
+    #[serde(default)]
+    pub ddsp_model: Option<String>,
+    /// Window size in logical pixels, saved on exit
+    #[serde(default = "default_window_width")]
+    pub window_width: f32,
+    #[serde(default = "default_window_height")]
+    pub window_height: f32,
+    /// Window position on the desktop, saved on exit (unset = let the OS place it)
+    #[serde(default)]
+    pub window_position: Option<[i32; 2]>,
+    /// Rulestring of the rule last played, preselected on the start screen
+    #[serde(default)]
+    pub last_rule: Option<String>,
+    /// Furthest out and in the camera zooms; lower min_zoom for very large patterns
+    #[serde(default = "default_min_zoom")]
+    pub min_zoom: f32,
+    #[serde(default = "default_max_zoom")]
+    pub max_zoom: f32,
+    /// Multiplier on wheel, keyboard and pinch zoom rates
+    #[serde(default = "default_zoom_speed")]
+    pub zoom_speed: f32,
+    /// Screen pixels per second the pan keys move the view
+    #[serde(default = "default_pan_speed")]
+    pub pan_speed: f32,
+    /// Camera zoom, saved on exit
+    #[serde(default = "default_camera_zoom")]
+    pub camera_zoom: f32,
+    /// Brush diameter, saved on exit
+    #[serde(default = "default_brush_size")]
+    pub brush_size: u32,
+    /// Whether the in-game HUD is shown, saved on exit
+    #[serde(default = "default_hud_visible")]
+    pub hud_visible: bool,
+    /// Corner and folding of each HUD panel, saved on exit
+    #[serde(default)]
+    pub hud_layout: HudLayout,
 }
 
 fn default_volume() -> f32 { 0.7 }
+fn default_fill_max_cells() -> usize { crate::tools::fill::DEFAULT_FILL_CAP }
+fn default_prune_max_population() -> Option<usize> { Some(2_000_000) }
+fn default_pattern_dir() -> String { "patterns".to_string() }
+fn default_export_cell_pixels() -> u32 { 4 }
+fn default_export_dir() -> String { "exports".to_string() }
+fn default_svg_merge_runs() -> bool { true }
+fn default_record_fps() -> u32 { 10 }
+fn default_record_generations() -> usize { 100 }
+fn default_video_fps() -> u32 { 30 }
+fn default_ffmpeg_path() -> String { "ffmpeg".to_string() }
+fn default_theme() -> String { "blood".to_string() }
+fn default_locale() -> String { crate::locale::DEFAULT_LOCALE.to_string() }
+fn default_scale() -> f32 { 1.0 }
+fn default_heatmap_zoom() -> f32 { 0.2 }
+fn default_age_span() -> u64 { 100 }
+fn default_trail_length() -> u32 { 20 }
+fn default_grid_lines_zoom() -> f32 { 0.5 }
+fn default_cell_shader() -> bool { true }
+fn default_minimap() -> bool { true }
+fn default_activity_half_life() -> f32 { 200.0 }
+fn default_graph_generations() -> usize { crate::stats::history::DEFAULT_HISTORY }
+fn default_run_generations() -> u64 { crate::game_config::DEFAULT_RUN_LENGTH }
+fn default_rewind_generations() -> usize { crate::infinite_grid::rewind::DEFAULT_REWIND }
+fn default_confirm_destructive() -> bool { true }
+fn default_rule_presets() -> bool { true }
+fn default_hud_refresh() -> f32 { crate::ui::DEFAULT_HUD_REFRESH }
+fn default_sample_dir() -> String { "assets/samples".to_string() }
+fn default_sequencer_bpm() -> f32 { crate::sequencer::DEFAULT_BPM }
+fn default_midi_columns() -> u32 { 12 }
+fn default_osc_rate() -> f32 { crate::osc::DEFAULT_OSC_RATE }
+fn default_osc_regions() -> u32 { crate::osc::DEFAULT_OSC_REGIONS }
+fn default_window_width() -> f32 { 1200.0 }
+fn default_window_height() -> f32 { 800.0 }
+fn default_min_zoom() -> f32 { 0.05 }
+fn default_max_zoom() -> f32 { 20.0 }
+fn default_zoom_speed() -> f32 { 1.0 }
+fn default_pan_speed() -> f32 { 500.0 }
+fn default_camera_zoom() -> f32 { 1.0 }
+fn default_brush_size() -> u32 { 1 }
+fn default_hud_visible() -> bool { true }
 
 impl Default for Config {
     fn default() -> Self {
@@ -64,6 +351,75 @@ impl Default for Config {
             seed: None,
             audio_engine: AudioEngine::default(),
             audio_volume: default_volume(),
+            audio_muted: false,
+            fill_max_cells: default_fill_max_cells(),
+            prune_distance: None,
+            prune_max_population: default_prune_max_population(),
+            pattern_dir: default_pattern_dir(),
+            export_cell_pixels: default_export_cell_pixels(),
+            export_dir: default_export_dir(),
+            svg_merge_runs: default_svg_merge_runs(),
+            record_format: RecordFormat::default(),
+            record_fps: default_record_fps(),
+            record_generations: default_record_generations(),
+            video_format: VideoFormat::default(),
+            video_fps: default_video_fps(),
+            ffmpeg_path: default_ffmpeg_path(),
+            keybindings: HashMap::new(),
+            theme: default_theme(),
+            locale: default_locale(),
+            ui_scale: default_scale(),
+            font_scale: default_scale(),
+            heatmap_zoom: default_heatmap_zoom(),
+            age_colors: false,
+            age_span: default_age_span(),
+            trails: false,
+            trail_length: default_trail_length(),
+            grid_lines: false,
+            grid_lines_zoom: default_grid_lines_zoom(),
+            bloom_intensity: 0.0,
+            cell_shader: default_cell_shader(),
+            reduced_motion: false,
+            pixel_mode: false,
+            isometric: false,
+            minimap: default_minimap(),
+            activity_heat: false,
+            activity_half_life: default_activity_half_life(),
+            color_vision: ColorVision::Normal,
+            pattern_markers: MarkerMode::Off,
+            population_graph: GraphMode::Off,
+            graph_generations: default_graph_generations(),
+            run_generations: default_run_generations(),
+            rewind_generations: default_rewind_generations(),
+            confirm_destructive: default_confirm_destructive(),
+            camera_shake: 0.0,
+            rule_presets: default_rule_presets(),
+            hud_refresh: default_hud_refresh(),
+            key_root: None,
+            key_mode: None,
+            sample_dir: default_sample_dir(),
+            sample_mode: SampleMode::default(),
+            sequencer_bpm: default_sequencer_bpm(),
+            midi_port: None,
+            midi_columns: default_midi_columns(),
+            osc_target: None,
+            osc_rate: default_osc_rate(),
+            osc_regions: default_osc_regions(),
+            audio_sample_rate: None,
+            audio_buffer_size: None,
+            ddsp_model: None,
+            window_width: default_window_width(),
+            window_height: default_window_height(),
+            window_position: None,
+            last_rule: None,
+            min_zoom: default_min_zoom(),
+            max_zoom: default_max_zoom(),
+            zoom_speed: default_zoom_speed(),
+            pan_speed: default_pan_speed(),
+            camera_zoom: default_camera_zoom(),
+            brush_size: default_brush_size(),
+            hud_visible: default_hud_visible(),
+            hud_layout: HudLayout::default(),
         }
     }
 }
@@ -71,10 +427,87 @@ impl Default for Config {
 impl Config {
     /// Load from a TOML file; fall back to defaults on any error.
     pub fn load(path: Option<&str>) -> Self {
-        let p = path.unwrap_or("oraclelife.toml");
+        let p = path.unwrap_or(DEFAULT_CONFIG_PATH);
         match fs::read_to_string(p) {
             Ok(text) => toml::from_str(&text).unwrap_or_default(),
             Err(_)   => Self::default(),
         }
     }
+
+    /// Write to a TOML file, updating the existing one in place.
+    ///
+    /// Every field is written, but comments, formatting and unknown keys already in the
+    /// file survive. A file that isn't valid TOML is left untouched rather than replaced.
+    pub fn save(&self, path: Option<&str>) -> Result<(), ConfigError> {
+        let p = path.unwrap_or(DEFAULT_CONFIG_PATH);
+        let existing = match fs::read_to_string(p) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        fs::write(p, self.merge_into(&existing)?)?;
+        Ok(())
+    }
+
+    /// `existing` with this config's values written over it
+    fn merge_into(&self, existing: &str) -> Result<String, ConfigError> {
+        let mut doc: DocumentMut = existing.parse()?;
+        let fresh: DocumentMut = toml::to_string(self)?.parse()?;
+        for (key, item) in fresh.iter() {
+            match doc.get_mut(key) {
+                Some(old) => merge_item(old, item),
+                None => {
+                    doc.insert(key, item.clone());
+                }
+            }
+        }
+        Ok(doc.to_string())
+    }
+}
+
+/// Overwrite `old` with `new`, keeping the comments around values and recursing into tables
+fn merge_item(old: &mut Item, new: &Item) {
+    match (old, new) {
+        (Item::Value(old), Item::Value(new)) => {
+            let decor = old.decor().clone();
+            *old = new.clone();
+            *old.decor_mut() = decor;
+        }
+        (Item::Table(old), Item::Table(new)) => {
+            for (key, item) in new.iter() {
+                match old.get_mut(key) {
+                    Some(existing) => merge_item(existing, item),
+                    None => {
+                        old.insert(key, item.clone());
+                    }
+                }
+            }
+        }
+        (old, new) => *old = new.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_keeps_comments_and_unknown_keys() {
+        let existing = "# Frame rate\nfps = 60 # capped\nrule_type = \"Conway\"\n\n[keybindings]\n# pausing\npause = \"P\"\n";
+        let mut config = Config { fps: 144, ..Config::default() };
+        config.camera_zoom = 2.5;
+        config.last_rule = Some("B36/S23".to_string());
+        config.keybindings.insert("pause".to_string(), "Space".to_string());
+
+        let saved = config.merge_into(existing).unwrap();
+        assert!(saved.contains("# Frame rate\nfps = 144 # capped"), "{}", saved);
+        assert!(saved.contains("rule_type = \"Conway\""));
+        assert!(saved.contains("# pausing\npause = \"Space\""), "{}", saved);
+
+        let reloaded: Config = toml::from_str(&saved).unwrap();
+        assert_eq!(reloaded.fps, 144);
+        assert_eq!(reloaded.camera_zoom, 2.5);
+        assert_eq!(reloaded.last_rule.as_deref(), Some("B36/S23"));
+        assert_eq!(reloaded.window_position, None);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,218 @@
+//! Confirmation before destructive actions.
+//!
+//! Clearing the grid (C) and resetting to the start screen (R) throw away everything drawn,
+//! so with `confirm_destructive` on (the default) they first pause the game under a small
+//! dialog saying how many cells would go. Enter or Y goes ahead, Esc or N backs out, and the
+//! dialog's buttons do the same. An empty grid has nothing to lose and is never asked about.
+
+use bevy::prelude::*;
+use crate::infinite_grid::InfiniteGrid;
+use crate::settings_menu::button_color;
+use crate::start_screen::GameState;
+use crate::ui::toast;
+use crate::GameStats;
+use crate::tr;
+
+/// An action that can't be taken back
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Destructive {
+    ClearGrid,
+    Reset,
+}
+
+impl Destructive {
+    fn question(self, cells: usize) -> String {
+        match self {
+            Destructive::ClearGrid => tr!("Clear the grid? {} cells will be lost.", cells),
+            Destructive::Reset => tr!("Reset and return to the start screen? {} cells will be lost.", cells),
+        }
+    }
+
+    fn verb(self) -> String {
+        match self {
+            Destructive::ClearGrid => tr!("Clear"),
+            Destructive::Reset => tr!("Reset"),
+        }
+    }
+
+    /// Do it, without asking
+    pub fn carry_out(self, grid: &mut InfiniteGrid, game_stats: &mut GameStats, game_state: &mut NextState<GameState>) {
+        grid.clear();
+        game_stats.generation = 0;
+        game_stats.stop();
+        if self == Destructive::Reset {
+            game_state.set(GameState::StartScreen);
+            toast(tr!("🔄 Game reset - returning to start screen"));
+        }
+    }
+}
+
+/// Whether destructive actions are confirmed, and the one waiting on the dialog
+#[derive(Resource)]
+pub struct Confirmation {
+    pub enabled: bool,
+    pending: Option<Destructive>,
+}
+
+impl Default for Confirmation {
+    fn default() -> Self {
+        Self { enabled: true, pending: None }
+    }
+}
+
+impl Confirmation {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled, ..default() }
+    }
+
+    /// Whether `action` on `grid` needs confirming first, holding it for the dialog if so
+    pub fn needs_confirming(&mut self, action: Destructive, grid: &InfiniteGrid) -> bool {
+        if !self.enabled || grid.population() == 0 {
+            return false;
+        }
+        self.pending = Some(action);
+        true
+    }
+
+    /// Carry out `action` now, or open the dialog for it
+    pub fn request(
+        &mut self,
+        action: Destructive,
+        grid: &mut InfiniteGrid,
+        game_stats: &mut GameStats,
+        game_state: &mut NextState<GameState>,
+    ) {
+        if self.needs_confirming(action, grid) {
+            game_state.set(GameState::Confirm);
+        } else {
+            action.carry_out(grid, game_stats, game_state);
+        }
+    }
+}
+
+#[derive(Component)]
+struct ConfirmScreen;
+
+#[derive(Component, Clone, Copy)]
+enum ConfirmButton {
+    Yes,
+    No,
+}
+
+pub struct ConfirmPlugin;
+
+impl Plugin for ConfirmPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Confirmation>()
+            .add_systems(OnEnter(GameState::Confirm), spawn_dialog)
+            .add_systems(OnExit(GameState::Confirm), despawn_dialog)
+            .add_systems(Update, answer_dialog.run_if(in_state(GameState::Confirm)));
+    }
+}
+
+fn spawn_dialog(mut commands: Commands, asset_server: Res<AssetServer>, confirmation: Res<Confirmation>, grid: Res<InfiniteGrid>) {
+    let Some(action) = confirmation.pending else { return };
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+            GlobalZIndex(20),
+            ConfirmScreen,
+        ))
+        .with_children(|screen| {
+            screen
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(12.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        max_width: Val::Px(480.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.95)),
+                ))
+                .with_children(|dialog| {
+                    dialog.spawn((Text::new(action.question(grid.population())), text(20.0), TextColor(Color::WHITE)));
+                    dialog.spawn((
+                        Text::new(tr!("Enter or Y to go ahead, Esc or N to cancel")),
+                        text(14.0),
+                        TextColor(Color::srgb(0.7, 0.7, 0.7)),
+                    ));
+                    dialog.spawn(Node { flex_direction: FlexDirection::Row, column_gap: Val::Px(8.0), ..default() }).with_children(|row| {
+                        for (button, label) in [(ConfirmButton::Yes, action.verb()), (ConfirmButton::No, tr!("Cancel"))] {
+                            row.spawn((
+                                Button,
+                                Node { padding: UiRect::axes(Val::Px(16.0), Val::Px(6.0)), ..default() },
+                                BackgroundColor(button_color(Interaction::None)),
+                                button,
+                            ))
+                            .with_child((Text::new(label), text(16.0), TextColor(Color::WHITE)));
+                        }
+                    });
+                });
+        });
+}
+
+fn despawn_dialog(mut commands: Commands, screens: Query<Entity, With<ConfirmScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn answer_dialog(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut buttons: Query<(&Interaction, &ConfirmButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut confirmation: ResMut<Confirmation>,
+    (mut grid, mut game_stats): (ResMut<InfiniteGrid>, ResMut<GameStats>),
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let mut answer = if keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::KeyY]) {
+        Some(ConfirmButton::Yes)
+    } else if keyboard_input.any_just_pressed([KeyCode::Escape, KeyCode::KeyN]) {
+        Some(ConfirmButton::No)
+    } else {
+        None
+    };
+    for (interaction, button, mut colour) in &mut buttons {
+        colour.0 = button_color(*interaction);
+        if *interaction == Interaction::Pressed {
+            answer = Some(*button);
+        }
+    }
+    let Some(answer) = answer else { return };
+    game_state.set(GameState::Playing);
+    if let (ConfirmButton::Yes, Some(action)) = (answer, confirmation.pending.take()) {
+        action.carry_out(&mut grid, &mut game_stats, &mut game_state);
+    }
+    confirmation.pending = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_populated_grid_is_asked_about() {
+        let mut grid = InfiniteGrid::new();
+        let mut confirmation = Confirmation::default();
+        assert!(!confirmation.needs_confirming(Destructive::ClearGrid, &grid));
+        assert_eq!(confirmation.pending, None);
+
+        grid.set_alive(0, 0);
+        assert!(confirmation.needs_confirming(Destructive::Reset, &grid));
+        assert_eq!(confirmation.pending, Some(Destructive::Reset));
+
+        let mut confirmation = Confirmation::new(false);
+        assert!(!confirmation.needs_confirming(Destructive::ClearGrid, &grid));
+    }
+}
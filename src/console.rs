@@ -0,0 +1,406 @@
+//! Drop-down console.
+//!
+//! The backquote key (by default) pauses the game under a console that takes typed commands,
+//! for setting things up more precisely than the mouse allows, or from a script of lines to
+//! paste in a demo:
+//!
+//! ```text
+//! rule B36/S23            switch rule, by rulestring or name
+//! fill 0 0 50 50 0.3      randomly fill a rectangle (corners inclusive, density 0-1)
+//! load glidergun.rle 5 5  read a pattern file onto the grid, top-left at (5, 5)
+//! pattern glider 0 0      place a library pattern
+//! speed 20                generations per second
+//! run 100                 play 100 generations and pause
+//! ```
+//!
+//! plus `clear`, `play`, `pause`, `step`, `back` and `help`. Relative paths are tried as given
+//! and then in the pattern browser's folder. Up/Down recall earlier lines; Enter runs one,
+//! and Esc or the console key closes the console, picking up where the game was left.
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use rand::Rng;
+use crate::browser::PatternBrowser;
+use crate::game_config::GameStats;
+use crate::infinite_grid::patterns::{find, LibraryPattern};
+use crate::infinite_grid::{InfiniteGrid, RewindBuffer};
+use crate::io::load_pattern_file;
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::{GameState, RuleType};
+use crate::{CellState, GameConfig};
+
+/// Output lines kept on screen
+const SHOWN_LINES: usize = 14;
+/// Largest rectangle `fill` will touch
+const MAX_FILL_CELLS: i64 = 4_000_000;
+
+const HELP: &str = "commands: rule <B3/S23 | name>, fill x0 y0 x1 y1 [density], load <file> [x y], \
+                    pattern <name> [x y], speed <gen/s>, run <n>, play, pause, step, back, clear";
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum ConsoleError {
+    #[error("unknown command '{0}' (try help)")]
+    UnknownCommand(String),
+    #[error("usage: {0}")]
+    Usage(&'static str),
+    #[error("'{0}' is not a number")]
+    BadNumber(String),
+    #[error("unknown rule '{0}'")]
+    UnknownRule(String),
+    #[error("no library pattern called '{0}'")]
+    UnknownPattern(String),
+}
+
+/// A parsed console line
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConsoleCommand {
+    Help,
+    Clear,
+    Rule(RuleType),
+    Fill { from: (i32, i32), to: (i32, i32), density: f64 },
+    Load { path: String, at: (i32, i32) },
+    Pattern { name: &'static str, at: (i32, i32) },
+    Speed(f64),
+    Run(u64),
+    Play,
+    Pause,
+    Step,
+    Back,
+}
+
+fn number<T: std::str::FromStr>(word: &str) -> Result<T, ConsoleError> {
+    word.parse().map_err(|_| ConsoleError::BadNumber(word.to_string()))
+}
+
+/// Split a trailing `x y` off `words`, defaulting to the origin
+fn position<'a>(words: &'a [&'a str]) -> Result<(&'a [&'a str], (i32, i32)), ConsoleError> {
+    match words {
+        [rest @ .., x, y] if !rest.is_empty() && x.parse::<i32>().is_ok() => Ok((rest, (number(x)?, number(y)?))),
+        _ => Ok((words, (0, 0))),
+    }
+}
+
+/// A rule by rulestring or by name, ignoring case, spaces and punctuation
+pub fn parse_rule(text: &str) -> Option<RuleType> {
+    let key: String = text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect();
+    RuleType::from_rulestring(text).or_else(|| {
+        RuleType::all().into_iter().find(|rule| {
+            let name: String = rule.name().chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect();
+            name == key || format!("{:?}", rule).to_lowercase() == key
+        })
+    })
+}
+
+pub fn parse_command(line: &str) -> Result<ConsoleCommand, ConsoleError> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let Some((&command, args)) = words.split_first() else {
+        return Err(ConsoleError::Usage(HELP));
+    };
+    let parsed = match (command.to_ascii_lowercase().as_str(), args) {
+        ("help" | "?", _) => ConsoleCommand::Help,
+        ("clear", []) => ConsoleCommand::Clear,
+        ("play", []) => ConsoleCommand::Play,
+        ("pause", []) => ConsoleCommand::Pause,
+        ("step", []) => ConsoleCommand::Step,
+        ("back", []) => ConsoleCommand::Back,
+        ("rule", [_, ..]) => {
+            let text = args.join(" ");
+            ConsoleCommand::Rule(parse_rule(&text).ok_or(ConsoleError::UnknownRule(text))?)
+        }
+        ("rule", []) => return Err(ConsoleError::Usage("rule <B3/S23 | name>")),
+        ("fill", [x0, y0, x1, y1, density @ ..]) if density.len() <= 1 => {
+            let density = density.first().map(|d| number::<f64>(d)).transpose()?.unwrap_or(1.0);
+            if !(0.0..=1.0).contains(&density) {
+                return Err(ConsoleError::Usage("fill x0 y0 x1 y1 [density 0-1]"));
+            }
+            ConsoleCommand::Fill { from: (number(x0)?, number(y0)?), to: (number(x1)?, number(y1)?), density }
+        }
+        ("fill", _) => return Err(ConsoleError::Usage("fill x0 y0 x1 y1 [density 0-1]")),
+        ("load", [_, ..]) => {
+            let (path, at) = position(args)?;
+            ConsoleCommand::Load { path: path.join(" "), at }
+        }
+        ("load", []) => return Err(ConsoleError::Usage("load <file> [x y]")),
+        ("pattern", [_, ..]) => {
+            let (name, at) = position(args)?;
+            let name = name.join(" ");
+            let pattern: &LibraryPattern = find(&name).ok_or(ConsoleError::UnknownPattern(name))?;
+            ConsoleCommand::Pattern { name: pattern.name, at }
+        }
+        ("pattern", []) => return Err(ConsoleError::Usage("pattern <name> [x y]")),
+        ("speed", [speed]) => {
+            let speed: f64 = number(speed)?;
+            if !(speed > 0.0 && speed.is_finite()) {
+                return Err(ConsoleError::Usage("speed <generations per second, above 0>"));
+            }
+            ConsoleCommand::Speed(speed)
+        }
+        ("speed", _) => return Err(ConsoleError::Usage("speed <generations per second>")),
+        ("run", [n]) => ConsoleCommand::Run(number(n)?),
+        ("run", _) => return Err(ConsoleError::Usage("run <generations>")),
+        ("clear" | "play" | "pause" | "step" | "back", _) => return Err(ConsoleError::Usage("clear, play, pause, step and back take no arguments")),
+        (other, _) => return Err(ConsoleError::UnknownCommand(other.to_string())),
+    };
+    Ok(parsed)
+}
+
+/// Lines shown and typed
+#[derive(Resource, Default)]
+struct Console {
+    input: String,
+    output: Vec<String>,
+    history: Vec<String>,
+    /// Position while recalling `history` with Up/Down
+    recalled: Option<usize>,
+}
+
+impl Console {
+    fn print(&mut self, line: impl Into<String>) {
+        self.output.push(line.into());
+        let excess = self.output.len().saturating_sub(SHOWN_LINES);
+        self.output.drain(..excess);
+    }
+}
+
+#[derive(Component)]
+struct ConsoleScreen;
+
+#[derive(Component)]
+struct ConsoleText;
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .add_systems(Update, open_console.run_if(in_state(GameState::Playing)))
+            .add_systems(OnEnter(GameState::Console), spawn_console)
+            .add_systems(OnExit(GameState::Console), despawn_console)
+            .add_systems(Update, (type_line, refresh_console).chain().run_if(in_state(GameState::Console)));
+    }
+}
+
+fn open_console(keyboard_input: Res<ButtonInput<KeyCode>>, keys: Res<Keybindings>, mut next_state: ResMut<NextState<GameState>>) {
+    if keys.just_pressed(&keyboard_input, Action::Console) {
+        next_state.set(GameState::Console);
+    }
+}
+
+fn spawn_console(mut commands: Commands, asset_server: Res<AssetServer>, mut console: ResMut<Console>) {
+    if console.output.is_empty() {
+        console.print(HELP);
+    }
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::FlexEnd,
+                padding: UiRect::all(Val::Px(8.0)),
+                min_height: Val::Percent(35.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.02, 0.02, 0.04, 0.9)),
+            GlobalZIndex(25),
+            ConsoleScreen,
+        ))
+        .with_child((
+            Text::new(""),
+            TextFont { font: asset_server.load("fonts/Geo-Regular.ttf"), font_size: 15.0, ..default() },
+            TextColor(Color::srgb(0.7, 1.0, 0.7)),
+            ConsoleText,
+        ));
+}
+
+fn despawn_console(mut commands: Commands, screens: Query<Entity, With<ConsoleScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// What commands act on
+#[derive(bevy::ecs::system::SystemParam)]
+struct Engine<'w> {
+    grid: ResMut<'w, InfiniteGrid>,
+    game_config: ResMut<'w, GameConfig>,
+    game_stats: ResMut<'w, GameStats>,
+    rewind: ResMut<'w, RewindBuffer>,
+    browser: Res<'w, PatternBrowser>,
+}
+
+fn type_line(
+    mut typed: EventReader<KeyboardInput>,
+    (keyboard_input, keys): (Res<ButtonInput<KeyCode>>, Res<Keybindings>),
+    mut console: ResMut<Console>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut engine: Engine,
+) {
+    let console_key = keys.key(Action::Console);
+    for event in typed.read() {
+        // The console key itself only opens and closes the console
+        if event.state != ButtonState::Pressed || event.key_code == console_key {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(typed) if !typed.chars().any(char::is_control) => console.input.push_str(typed),
+            Key::Space => console.input.push(' '),
+            Key::Backspace => {
+                console.input.pop();
+            }
+            _ => {}
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) || keys.just_pressed(&keyboard_input, Action::Console) {
+        next_state.set(GameState::Playing);
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) && !console.history.is_empty() {
+        let index = console.recalled.map_or(console.history.len() - 1, |i| i.saturating_sub(1));
+        console.recalled = Some(index);
+        console.input = console.history[index].clone();
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        if let Some(index) = console.recalled {
+            console.recalled = (index + 1 < console.history.len()).then_some(index + 1);
+            console.input = console.recalled.map(|i| console.history[i].clone()).unwrap_or_default();
+        }
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) {
+        let line = std::mem::take(&mut console.input);
+        console.recalled = None;
+        if line.trim().is_empty() {
+            return;
+        }
+        console.print(format!("> {}", line));
+        if console.history.last() != Some(&line) {
+            console.history.push(line.clone());
+        }
+        let reply = match parse_command(&line) {
+            Ok(command) => execute(command, &mut engine),
+            Err(e) => e.to_string(),
+        };
+        console.print(reply);
+    }
+}
+
+/// Carry out `command`, returning what to print
+fn execute(command: ConsoleCommand, engine: &mut Engine) -> String {
+    match command {
+        ConsoleCommand::Help => HELP.to_string(),
+        ConsoleCommand::Clear => {
+            engine.grid.clear();
+            engine.game_stats.generation = 0;
+            engine.game_stats.stop();
+            "cleared".to_string()
+        }
+        ConsoleCommand::Rule(rule) => {
+            engine.game_config.current_rule = rule;
+            format!("rule {} ({})", rule.rulestring(), rule.name())
+        }
+        ConsoleCommand::Fill { from, to, density } => {
+            let (x0, x1) = (from.0.min(to.0), from.0.max(to.0));
+            let (y0, y1) = (from.1.min(to.1), from.1.max(to.1));
+            let area = (x1 as i64 - x0 as i64 + 1) * (y1 as i64 - y0 as i64 + 1);
+            if area > MAX_FILL_CELLS {
+                return format!("{} cells is more than fill does at once ({})", area, MAX_FILL_CELLS);
+            }
+            let mut rng = rand::thread_rng();
+            let mut alive = 0;
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    let state = if rng.gen_bool(density) { CellState::Alive } else { CellState::Dead };
+                    alive += (state == CellState::Alive) as usize;
+                    engine.grid.set(x, y, state);
+                }
+            }
+            format!("filled {} of {} cells", alive, area)
+        }
+        ConsoleCommand::Load { path, at } => {
+            let given = std::path::PathBuf::from(&path);
+            let path = if given.is_relative() && !given.exists() { engine.browser.dir.join(&given) } else { given };
+            match load_pattern_file(&path) {
+                Ok(pattern) => {
+                    for (x, y, state) in pattern.grid_cells() {
+                        engine.grid.set(at.0 + x, at.1 + y, state);
+                    }
+                    format!("loaded {} cells from {}", pattern.cells.len(), path.display())
+                }
+                Err(e) => format!("could not load {}: {}", path.display(), e),
+            }
+        }
+        ConsoleCommand::Pattern { name, at } => {
+            let Some(pattern) = find(name) else { return format!("no library pattern called '{}'", name) };
+            for (x, y, state) in pattern.cells() {
+                engine.grid.set(at.0 + x, at.1 + y, state);
+            }
+            format!("placed {} at ({}, {})", pattern.name, at.0, at.1)
+        }
+        ConsoleCommand::Speed(speed) => {
+            let stats = &mut engine.game_stats;
+            stats.update_interval = (1.0 / speed).clamp(stats.min_update_interval, stats.max_update_interval);
+            format!("speed {:.1} gen/s", 1.0 / stats.update_interval)
+        }
+        ConsoleCommand::Run(generations) => {
+            engine.game_stats.start_run(generations);
+            format!("running {} generations (close the console to watch)", generations)
+        }
+        ConsoleCommand::Play => {
+            engine.game_stats.stop();
+            engine.game_stats.is_running = true;
+            "playing (close the console to watch)".to_string()
+        }
+        ConsoleCommand::Pause => {
+            engine.game_stats.stop();
+            "paused".to_string()
+        }
+        ConsoleCommand::Step => {
+            engine.game_stats.step();
+            "stepping one generation when the console closes".to_string()
+        }
+        ConsoleCommand::Back => {
+            let Engine { grid, game_stats, rewind, .. } = engine;
+            if game_stats.step_back(grid, rewind) {
+                format!("back to generation {}", game_stats.generation)
+            } else {
+                "no earlier generation to step back to (pause first)".to_string()
+            }
+        }
+    }
+}
+
+fn refresh_console(console: Res<Console>, mut texts: Query<&mut Text, With<ConsoleText>>) {
+    if !console.is_changed() {
+        return;
+    }
+    for mut text in &mut texts {
+        let mut shown = console.output.join("\n");
+        shown.push_str(&format!("\n> {}_", console.input));
+        text.0 = shown;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_reports_mistakes() {
+        assert_eq!(parse_command("rule B36/S23"), Ok(ConsoleCommand::Rule(RuleType::HighLife)));
+        assert_eq!(parse_command("RULE highlife"), Ok(ConsoleCommand::Rule(RuleType::HighLife)));
+        assert_eq!(parse_command("fill 0 0 50 50 0.3"), Ok(ConsoleCommand::Fill { from: (0, 0), to: (50, 50), density: 0.3 }));
+        assert_eq!(parse_command("fill 0 0 5 5"), Ok(ConsoleCommand::Fill { from: (0, 0), to: (5, 5), density: 1.0 }));
+        assert_eq!(parse_command("load glidergun.rle"), Ok(ConsoleCommand::Load { path: "glidergun.rle".to_string(), at: (0, 0) }));
+        assert_eq!(parse_command("load my gun.rle -4 8"), Ok(ConsoleCommand::Load { path: "my gun.rle".to_string(), at: (-4, 8) }));
+        assert_eq!(parse_command("pattern Glider 3 4"), Ok(ConsoleCommand::Pattern { name: "Glider", at: (3, 4) }));
+        assert_eq!(parse_command("speed 20"), Ok(ConsoleCommand::Speed(20.0)));
+        assert_eq!(parse_command("run 100"), Ok(ConsoleCommand::Run(100)));
+
+        assert_eq!(parse_command("teleport"), Err(ConsoleError::UnknownCommand("teleport".to_string())));
+        assert_eq!(parse_command("speed fast"), Err(ConsoleError::BadNumber("fast".to_string())));
+        assert!(matches!(parse_command("fill 0 0 5 5 2"), Err(ConsoleError::Usage(_))));
+        assert!(matches!(parse_command("speed 0"), Err(ConsoleError::Usage(_))));
+        assert_eq!(parse_command("rule nope"), Err(ConsoleError::UnknownRule("nope".to_string())));
+    }
+}
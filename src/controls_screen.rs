@@ -0,0 +1,261 @@
+//! Key rebinding screen.
+//!
+//! Opened from the settings screen, it lists every action with the key bound to it. Clicking a
+//! key and pressing another rebinds the action on the spot (Esc cancels), and the bindings are
+//! written back to the `[keybindings]` table of the config, one entry per action moved off its
+//! default, to be saved on exit. Keys shared by more than one action are shown in orange;
+//! sharing is allowed, as several defaults do it (`R` resets the game, and rotates a stamp
+//! being placed). Esc goes back to the settings screen.
+
+use bevy::prelude::*;
+use crate::keybindings::{bindable, key_name, Action, Keybindings};
+use crate::preferences::Preferences;
+use crate::settings_menu::{button_color, BUTTON_IDLE};
+use crate::start_screen::GameState;
+use crate::tr;
+
+/// Columns the actions are split into
+const COLUMNS: usize = 4;
+
+const KEY_COLOR: Color = Color::srgb(0.9, 0.9, 0.4);
+const SHARED_KEY_COLOR: Color = Color::srgb(1.0, 0.6, 0.2);
+const WAITING_COLOR: Color = Color::srgb(0.4, 1.0, 0.6);
+
+/// Readable name of an action ("quick save" for `quick_save`)
+pub fn action_label(action: Action) -> String {
+    action.config_name().replace('_', " ")
+}
+
+/// The action waiting for a key press, if any
+#[derive(Resource, Default)]
+struct Rebinding {
+    action: Option<Action>,
+}
+
+#[derive(Component)]
+struct ControlsScreen;
+
+/// Button showing the key bound to an action
+#[derive(Component)]
+struct KeyButton(Action);
+
+#[derive(Component)]
+struct KeyLabel(Action);
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum ScreenButton {
+    Defaults,
+    Back,
+}
+
+/// Outcome of the last rebinding
+#[derive(Component)]
+struct RebindStatus;
+
+pub struct ControlsScreenPlugin;
+
+impl Plugin for ControlsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Rebinding>()
+            .add_systems(OnEnter(GameState::Controls), spawn_controls_screen)
+            .add_systems(OnExit(GameState::Controls), despawn_controls_screen)
+            .add_systems(
+                Update,
+                (key_buttons, screen_buttons, capture_key, leave_controls, refresh_keys).chain().run_if(in_state(GameState::Controls)),
+            );
+    }
+}
+
+fn spawn_controls_screen(mut commands: Commands, asset_server: Res<AssetServer>, mut rebinding: ResMut<Rebinding>) {
+    rebinding.action = None;
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            GlobalZIndex(20),
+            ControlsScreen,
+        ))
+        .with_children(|screen| {
+            screen
+                .spawn((
+                    Node { flex_direction: FlexDirection::Column, padding: UiRect::all(Val::Px(12.0)), row_gap: Val::Px(6.0), ..default() },
+                    BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.95)),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((Text::new(tr!("Controls")), text(24.0), TextColor(Color::srgb(1.0, 0.9, 0.3))));
+                    panel.spawn((
+                        Text::new(tr!("Click a key, then press the new one (Esc cancels). Shared keys are orange. Esc to go back")),
+                        text(13.0),
+                        TextColor(Color::srgb(0.6, 0.6, 0.6)),
+                    ));
+                    panel.spawn(Node { flex_direction: FlexDirection::Row, column_gap: Val::Px(16.0), ..default() }).with_children(|columns| {
+                        for chunk in Action::ALL.chunks(Action::ALL.len().div_ceil(COLUMNS)) {
+                            columns
+                                .spawn(Node { width: Val::Px(215.0), flex_direction: FlexDirection::Column, row_gap: Val::Px(2.0), ..default() })
+                                .with_children(|column| {
+                                    for &action in chunk {
+                                        spawn_action_row(column, &text, action);
+                                    }
+                                });
+                        }
+                    });
+                    panel.spawn((Text::new(""), text(14.0), TextColor(KEY_COLOR), RebindStatus));
+                    panel.spawn(Node { flex_direction: FlexDirection::Row, column_gap: Val::Px(8.0), ..default() }).with_children(|footer| {
+                        spawn_screen_button(footer, text(16.0), "Back", ScreenButton::Back);
+                        spawn_screen_button(footer, text(16.0), "Restore defaults", ScreenButton::Defaults);
+                    });
+                });
+        });
+}
+
+fn spawn_action_row(parent: &mut ChildBuilder, text: &impl Fn(f32) -> TextFont, action: Action) {
+    parent
+        .spawn(Node { height: Val::Px(22.0), flex_direction: FlexDirection::Row, align_items: AlignItems::Center, ..default() })
+        .with_children(|row| {
+            row.spawn((Text::new(action_label(action)), text(14.0), TextColor(Color::WHITE), Node { flex_grow: 1.0, ..default() }));
+            row.spawn((
+                Button,
+                Node { width: Val::Px(90.0), height: Val::Px(20.0), align_items: AlignItems::Center, justify_content: JustifyContent::Center, ..default() },
+                BackgroundColor(BUTTON_IDLE),
+                KeyButton(action),
+            ))
+            .with_child((Text::new(""), text(14.0), TextColor(KEY_COLOR), KeyLabel(action)));
+        });
+}
+
+fn spawn_screen_button(parent: &mut ChildBuilder, font: TextFont, label: &str, button: ScreenButton) {
+    parent
+        .spawn((
+            Button,
+            Node { padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)), justify_content: JustifyContent::Center, ..default() },
+            BackgroundColor(BUTTON_IDLE),
+            button,
+        ))
+        .with_child((Text::new(label), font, TextColor(Color::WHITE)));
+}
+
+fn despawn_controls_screen(mut commands: Commands, screens: Query<Entity, With<ControlsScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn key_buttons(
+    mut buttons: Query<(&Interaction, &KeyButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut statuses: Query<&mut Text, With<RebindStatus>>,
+) {
+    for (interaction, button, mut colour) in &mut buttons {
+        colour.0 = button_color(*interaction);
+        if *interaction == Interaction::Pressed {
+            rebinding.action = Some(button.0);
+            for mut text in &mut statuses {
+                text.0 = format!("Press a key for {} (Esc cancels)", action_label(button.0));
+            }
+        }
+    }
+}
+
+fn screen_buttons(
+    mut buttons: Query<(&Interaction, &ScreenButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut keys: ResMut<Keybindings>,
+    mut preferences: ResMut<Preferences>,
+    mut rebinding: ResMut<Rebinding>,
+    mut statuses: Query<&mut Text, With<RebindStatus>>,
+) {
+    for (interaction, button, mut colour) in &mut buttons {
+        colour.0 = button_color(*interaction);
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            ScreenButton::Back => next_state.set(GameState::Settings),
+            ScreenButton::Defaults => {
+                *keys = Keybindings::default();
+                preferences.config.keybindings.clear();
+                rebinding.action = None;
+                for mut text in &mut statuses {
+                    text.0 = "All keys restored to their defaults".to_string();
+                }
+            }
+        }
+    }
+}
+
+/// Bind the next key pressed to the action waiting for one
+fn capture_key(
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut keys: ResMut<Keybindings>,
+    mut preferences: ResMut<Preferences>,
+    mut statuses: Query<&mut Text, With<RebindStatus>>,
+) {
+    let Some(action) = rebinding.action else { return };
+    let Some(&key) = keyboard_input.get_just_pressed().next() else { return };
+    // The press is used up here, so Esc doesn't also leave the screen
+    keyboard_input.clear_just_pressed(key);
+    let status = if key == KeyCode::Escape {
+        "Cancelled".to_string()
+    } else if !bindable(key) {
+        format!("{:?} can't be bound; press another key (Esc cancels)", key)
+    } else {
+        keys.set(action, key);
+        preferences.config.keybindings = keys.overrides();
+        let others: Vec<_> = keys.actions_on(key).into_iter().filter(|&other| other != action).map(action_label).collect();
+        if others.is_empty() {
+            format!("{}: {}", action_label(action), key_name(key))
+        } else {
+            format!("{}: {} (also {})", action_label(action), key_name(key), others.join(", "))
+        }
+    };
+    if key == KeyCode::Escape || bindable(key) {
+        rebinding.action = None;
+    }
+    for mut text in &mut statuses {
+        text.0 = status.clone();
+    }
+}
+
+fn leave_controls(keyboard_input: Res<ButtonInput<KeyCode>>, rebinding: Res<Rebinding>, mut next_state: ResMut<NextState<GameState>>) {
+    if rebinding.action.is_none() && keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Settings);
+    }
+}
+
+fn refresh_keys(keys: Res<Keybindings>, rebinding: Res<Rebinding>, mut labels: Query<(&KeyLabel, &mut Text, &mut TextColor)>) {
+    if !keys.is_changed() && !rebinding.is_changed() {
+        return;
+    }
+    for (label, mut text, mut colour) in &mut labels {
+        let (shown, shown_colour) = if rebinding.action == Some(label.0) {
+            ("press a key".to_string(), WAITING_COLOR)
+        } else {
+            let key = keys.key(label.0);
+            let shared = keys.actions_on(key).len() > 1;
+            (key_name(key), if shared { SHARED_KEY_COLOR } else { KEY_COLOR })
+        };
+        text.0 = shown;
+        colour.0 = shown_colour;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_have_readable_labels() {
+        assert_eq!(action_label(Action::QuickSave), "quick save");
+    }
+}
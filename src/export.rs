@@ -0,0 +1,345 @@
+//! Exporting the grid as images.
+//!
+//! * `raster` — draw a grid region to RGBA pixels and save it as PNG.
+//! * `svg` — write a grid region as vector rectangles.
+//! * `recording` — capture one frame per generation into an animated GIF or APNG.
+//! * `gif` — the small GIF encoder recordings use.
+//! * `video` — stream rendered frames to ffmpeg for MP4/WebM.
+//!
+//! With the default bindings, F12 saves the current viewport and Shift+F12 the whole pattern to `ExportSettings::dir`,
+//! at `ExportSettings::cell_pixels` pixels per cell regardless of the window size; F8 and
+//! Shift+F8 do the same as SVG. F10
+//! records the viewport until pressed again; Shift+F10 records the next
+//! `ExportSettings::record_generations` generations. F11 starts and stops a video of the
+//! window. A red indicator shows while either kind of recording is running.
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+use crate::camera::{visible_grid_bounds, CameraState, GameCamera};
+use crate::infinite_grid::{GridBounds, InfiniteGrid};
+use crate::keybindings::{Action, Keybindings};
+use crate::theme::Theme;
+use crate::start_screen::{GameState, InGame};
+use crate::ui::toast;
+use crate::tr;
+
+pub mod gif;
+pub mod raster;
+pub mod recording;
+pub mod svg;
+pub mod video;
+
+pub use raster::{rasterize, state_rgba, Raster};
+pub use recording::{save_animation, RecordFormat, Recorder, Recording};
+pub use svg::{save_svg, write_svg};
+pub use video::{VideoFormat, VideoRecorder};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error("image would be {width}×{height} pixels; pick a smaller region or scale")]
+    TooLarge { width: u64, height: u64 },
+    #[error("nothing to export")]
+    Empty,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("PNG encoding failed: {0}")]
+    Png(#[from] png::EncodingError),
+    #[error("{0}")]
+    Ffmpeg(String),
+}
+
+#[derive(Resource, Debug, Clone)]
+pub struct ExportSettings {
+    /// Pixels per cell side in exported images
+    pub cell_pixels: u32,
+    /// Folder exports are written to
+    pub dir: PathBuf,
+    /// Merge horizontal runs of cells into single rectangles in SVG exports
+    pub svg_merge_runs: bool,
+    pub record_format: RecordFormat,
+    /// Playback speed of recordings, in generations per second
+    pub record_fps: u32,
+    /// Generations captured by a fixed-length recording
+    pub record_generations: usize,
+    pub video_format: VideoFormat,
+    /// Frames per second captured into videos
+    pub video_fps: u32,
+    /// ffmpeg executable, looked up on PATH unless absolute
+    pub ffmpeg_path: String,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            cell_pixels: 4,
+            dir: PathBuf::from("exports"),
+            svg_merge_runs: true,
+            record_format: RecordFormat::Gif,
+            record_fps: 10,
+            record_generations: 100,
+            video_format: VideoFormat::Mp4,
+            video_fps: 30,
+            ffmpeg_path: "ffmpeg".to_string(),
+        }
+    }
+}
+
+impl ExportSettings {
+    /// Fresh file path in the export folder, creating the folder if needed
+    pub fn next_path(&self, prefix: &str, extension: &str) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis());
+        Ok(self.dir.join(format!("{}-{}.{}", prefix, stamp, extension)))
+    }
+}
+
+pub struct ExportPlugin;
+
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ExportSettings>()
+            .init_resource::<Recorder>()
+            .init_resource::<VideoRecorder>()
+            .add_systems(
+                Update,
+                (
+                    screenshot_hotkey,
+                    svg_hotkey,
+                    record_hotkey,
+                    capture_frames.after(record_hotkey),
+                    video_hotkey,
+                    capture_video.after(video_hotkey),
+                    update_recording_indicator,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnEnter(InGame), spawn_recording_indicator)
+            .add_systems(OnExit(InGame), (stop_recording, despawn_recording_indicator));
+    }
+}
+
+fn screenshot_hotkey(
+    (keyboard_input, keys): (Res<ButtonInput<KeyCode>>, Res<Keybindings>),
+    grid: Res<InfiniteGrid>,
+    theme: Res<Theme>,
+    settings: Res<ExportSettings>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    camera_state: Res<CameraState>,
+) {
+    if !keys.just_pressed(&keyboard_input, Action::Screenshot) {
+        return;
+    }
+    let result = export_region(&keyboard_input, &grid, &camera_query, &camera_state)
+        .ok_or(ExportError::Empty)
+        .and_then(|region| rasterize(&grid, &region, settings.cell_pixels, &theme.cells))
+        .and_then(|raster| {
+            let path = settings.next_path("grid", "png")?;
+            raster.save_png(&path)?;
+            Ok((path, raster))
+        });
+    match result {
+        Ok((path, raster)) => toast(tr!("📸 Saved {}×{} image to {}", raster.width, raster.height, path.display())),
+        Err(e) => toast(tr!("📸 Screenshot failed: {}", e)),
+    }
+}
+
+fn svg_hotkey(
+    (keyboard_input, keys): (Res<ButtonInput<KeyCode>>, Res<Keybindings>),
+    grid: Res<InfiniteGrid>,
+    theme: Res<Theme>,
+    settings: Res<ExportSettings>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    camera_state: Res<CameraState>,
+) {
+    if !keys.just_pressed(&keyboard_input, Action::ExportSvg) {
+        return;
+    }
+    let result = export_region(&keyboard_input, &grid, &camera_query, &camera_state)
+        .ok_or(ExportError::Empty)
+        .and_then(|region| {
+            let path = settings.next_path("grid", "svg")?;
+            save_svg(&grid, &region, settings.cell_pixels, &theme.cells, settings.svg_merge_runs, &path)?;
+            Ok(path)
+        });
+    match result {
+        Ok(path) => toast(tr!("📐 Saved SVG to {}", path.display())),
+        Err(e) => toast(tr!("📐 SVG export failed: {}", e)),
+    }
+}
+
+/// The whole pattern while Shift is held, otherwise the viewport
+fn export_region(
+    keyboard_input: &ButtonInput<KeyCode>,
+    grid: &InfiniteGrid,
+    camera_query: &Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    camera_state: &CameraState,
+) -> Option<GridBounds> {
+    if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        grid.bounds().cloned()
+    } else {
+        camera_query.get_single().ok().map(|(transform, projection)| visible_grid_bounds(transform, projection, camera_state))
+    }
+}
+
+fn record_hotkey(
+    (keyboard_input, keys): (Res<ButtonInput<KeyCode>>, Res<Keybindings>),
+    (grid, theme): (Res<InfiniteGrid>, Res<Theme>),
+    settings: Res<ExportSettings>,
+    mut recorder: ResMut<Recorder>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    camera_state: Res<CameraState>,
+) {
+    if !keys.just_pressed(&keyboard_input, Action::RecordGif) {
+        return;
+    }
+    if recorder.is_recording() {
+        write_recording(&mut recorder, &settings);
+        return;
+    }
+    let Ok((transform, projection)) = camera_query.get_single() else { return };
+    let region = visible_grid_bounds(transform, projection, &camera_state);
+    let fixed_length = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let generations = fixed_length.then_some(settings.record_generations);
+    match recorder.start(&grid, region, settings.cell_pixels, &theme.cells, generations) {
+        Ok(()) => match generations {
+            Some(n) => toast(tr!("🎬 Recording the next {} generations", n)),
+            None => toast(tr!("🎬 Recording, press {} to stop", keys.label(Action::RecordGif))),
+        },
+        Err(e) => toast(tr!("🎬 Recording failed: {}", e)),
+    }
+}
+
+fn capture_frames(grid: Res<InfiniteGrid>, settings: Res<ExportSettings>, mut recorder: ResMut<Recorder>) {
+    if !recorder.is_recording() {
+        return;
+    }
+    if recorder.capture(&grid) {
+        write_recording(&mut recorder, &settings);
+    }
+}
+
+fn stop_recording(settings: Res<ExportSettings>, mut recorder: ResMut<Recorder>, mut video: ResMut<VideoRecorder>) {
+    if recorder.is_recording() {
+        write_recording(&mut recorder, &settings);
+    }
+    if video.is_recording() {
+        finish_video(&mut video);
+    }
+}
+
+/// Stop the recording and encode it off the main thread
+fn write_recording(recorder: &mut Recorder, settings: &ExportSettings) {
+    let Some(recording) = recorder.finish() else { return };
+    let path = match settings.next_path("recording", settings.record_format.extension()) {
+        Ok(path) => path,
+        Err(e) => {
+            toast(tr!("🎬 Saving recording failed: {}", e));
+            return;
+        }
+    };
+    let (format, fps) = (settings.record_format, settings.record_fps);
+    std::thread::spawn(move || match save_animation(&recording, format, fps, &path) {
+        Ok(()) => toast(tr!("🎬 Saved {} frames to {}", recording.len(), path.display())),
+        Err(e) => toast(tr!("🎬 Saving recording failed: {}", e)),
+    });
+}
+
+fn video_hotkey(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
+    settings: Res<ExportSettings>,
+    mut video: ResMut<VideoRecorder>,
+) {
+    if !keys.just_pressed(&keyboard_input, Action::RecordVideo) {
+        return;
+    }
+    if video.is_recording() {
+        finish_video(&mut video);
+        return;
+    }
+    match settings.next_path("video", settings.video_format.extension()) {
+        Ok(path) => {
+            toast(tr!("🎥 Recording video to {}, press {} to stop", path.display(), keys.label(Action::RecordVideo)));
+            video.start(path);
+        }
+        Err(e) => toast(tr!("🎥 Video recording failed: {}", e)),
+    }
+}
+
+/// Request a window capture whenever a video frame is due; frames arrive a few frames later
+/// through the observer
+fn capture_video(mut commands: Commands, time: Res<Time>, settings: Res<ExportSettings>, mut video: ResMut<VideoRecorder>) {
+    if !video.is_recording() || !video.frame_due(time.delta_secs(), settings.video_fps) {
+        return;
+    }
+    commands.spawn(Screenshot::primary_window()).observe(
+        |trigger: Trigger<ScreenshotCaptured>, settings: Res<ExportSettings>, mut video: ResMut<VideoRecorder>| {
+            let result = video.push_frame(&trigger.event().0, &settings.ffmpeg_path, settings.video_fps, settings.video_format);
+            if let Err(e) = result {
+                toast(tr!("🎥 Video recording failed: {}", e));
+                // Still wait for ffmpeg, and say whether the frames so far were saved
+                finish_video(&mut video);
+            }
+        },
+    );
+}
+
+/// Close ffmpeg's input and let it finish encoding off the main thread
+fn finish_video(video: &mut VideoRecorder) {
+    let frames = video.frames;
+    let Some(pipe) = video.stop() else {
+        toast(tr!("🎥 Video stopped before any frames were captured"));
+        return;
+    };
+    std::thread::spawn(move || match pipe.finish() {
+        Ok(path) => toast(tr!("🎥 Saved {} frames to {}", frames, path.display())),
+        Err(e) => toast(tr!("🎥 Saving video failed: {}", e)),
+    });
+}
+
+#[derive(Component)]
+struct RecordingIndicator;
+
+fn spawn_recording_indicator(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        Text::new(""),
+        TextFont { font: asset_server.load("fonts/Geo-Regular.ttf"), font_size: 18.0, ..default() },
+        TextColor(Color::srgb(1.0, 0.25, 0.25)),
+        TextLayout::new_with_justify(JustifyText::Center),
+        // Top centre, clear of the HUD on the left and the synth panel on the right
+        Node { position_type: PositionType::Absolute, width: Val::Percent(100.0), top: Val::Px(10.0), ..default() },
+        Visibility::Hidden,
+        RecordingIndicator,
+    ));
+}
+
+fn update_recording_indicator(
+    recorder: Res<Recorder>,
+    video: Res<VideoRecorder>,
+    mut indicators: Query<(&mut Text, &mut Visibility), With<RecordingIndicator>>,
+) {
+    let Ok((mut text, mut visibility)) = indicators.get_single_mut() else { return };
+    let mut parts = Vec::new();
+    if recorder.is_recording() {
+        parts.push(format!("● REC GIF {} frames", recorder.frame_count()));
+    }
+    if video.is_recording() {
+        parts.push(format!("● REC VIDEO {} frames", video.frames));
+    }
+    if crate::audio::recorder::is_recording() {
+        parts.push(format!("● REC AUDIO {:.0}s", crate::audio::recorder::recorded_seconds()));
+    }
+    let label = parts.join("   ");
+    if **text != label {
+        **text = label;
+    }
+    visibility.set_if_neq(if parts.is_empty() { Visibility::Hidden } else { Visibility::Visible });
+}
+
+fn despawn_recording_indicator(mut commands: Commands, indicators: Query<Entity, With<RecordingIndicator>>) {
+    for entity in &indicators {
+        commands.entity(entity).despawn_recursive();
+    }
+}
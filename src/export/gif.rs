@@ -0,0 +1,202 @@
+//! Minimal animated GIF encoder.
+//!
+//! Grid frames only ever contain a handful of colours (one per cell state plus the
+//! background), known before the first frame is drawn, so a single global palette and plain
+//! LZW compression are enough. Frames are encoded one at a time as they come, written
+//! full-size with a fixed delay, and the animation loops forever.
+
+use std::collections::HashMap;
+use std::io::Write;
+use super::{ExportError, Raster};
+
+/// Largest LZW code width GIF allows
+const MAX_CODE_BITS: u32 = 12;
+
+/// Encode `frames` (all the same size) as a looping GIF with `delay_cs` hundredths of a
+/// second between frames. Pixels are matched against `palette` (at most 256 colours), and
+/// any colour missing from it is drawn in the first one.
+pub fn write_gif<W: Write>(
+    out: &mut W,
+    palette: &[[u8; 3]],
+    frames: impl IntoIterator<Item = Result<Raster, ExportError>>,
+    delay_cs: u16,
+) -> Result<(), ExportError> {
+    let mut frames = frames.into_iter();
+    let Some(first) = frames.next() else { return Err(ExportError::Empty) };
+    let first = first?;
+    let (width, height) = (first.width, first.height);
+    if width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(ExportError::TooLarge { width: width as u64, height: height as u64 });
+    }
+
+    let mut palette: Vec<[u8; 3]> = palette.iter().copied().take(256).collect();
+    // Inserted last to first so the first of any duplicate colours wins
+    let lookup: HashMap<[u8; 3], u8> = palette.iter().enumerate().rev().map(|(index, &rgb)| (rgb, index as u8)).collect();
+    let table_bits = (palette.len().max(2) as f32).log2().ceil().max(1.0) as u32;
+    palette.resize(1 << table_bits, [0, 0, 0]);
+
+    out.write_all(b"GIF89a")?;
+    out.write_all(&(width as u16).to_le_bytes())?;
+    out.write_all(&(height as u16).to_le_bytes())?;
+    out.write_all(&[0x80 | 0x70 | (table_bits - 1) as u8, 0, 0])?;
+    for rgb in &palette {
+        out.write_all(rgb)?;
+    }
+    // Loop forever
+    out.write_all(&[0x21, 0xFF, 0x0B])?;
+    out.write_all(b"NETSCAPE2.0")?;
+    out.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+    let min_code_size = table_bits.max(2);
+    for frame in std::iter::once(Ok(first)).chain(frames) {
+        let frame = frame?;
+        let indices: Vec<u8> = frame
+            .pixels
+            .chunks_exact(4)
+            .map(|pixel| lookup.get(&[pixel[0], pixel[1], pixel[2]]).copied().unwrap_or(0))
+            .collect();
+        out.write_all(&[0x21, 0xF9, 0x04, 0x00])?;
+        out.write_all(&delay_cs.to_le_bytes())?;
+        out.write_all(&[0x00, 0x00])?;
+        out.write_all(&[0x2C, 0, 0, 0, 0])?;
+        out.write_all(&(width as u16).to_le_bytes())?;
+        out.write_all(&(height as u16).to_le_bytes())?;
+        out.write_all(&[0x00, min_code_size as u8])?;
+        for block in lzw_encode(&indices, min_code_size).chunks(255) {
+            out.write_all(&[block.len() as u8])?;
+            out.write_all(block)?;
+        }
+        out.write_all(&[0x00])?;
+    }
+    out.write_all(&[0x3B])?;
+    Ok(())
+}
+
+/// Packs variable-width codes least-significant bit first
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u32) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+fn lzw_encode(indices: &[u8], min_code_size: u32) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let mut writer = BitWriter { bytes: Vec::new(), buffer: 0, bits: 0 };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end + 1;
+    let mut width = min_code_size + 1;
+
+    writer.write(clear, width);
+    let mut iter = indices.iter();
+    let Some(&first) = iter.next() else {
+        writer.write(end, width);
+        return writer.finish();
+    };
+    let mut prefix = first as u16;
+    for &index in iter {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        writer.write(prefix, width);
+        if next_code < (1 << MAX_CODE_BITS) {
+            table.insert((prefix, index), next_code);
+            // The decoder widens one code later than the encoder adds the entry
+            if next_code == (1 << width) && width < MAX_CODE_BITS {
+                width += 1;
+            }
+            next_code += 1;
+        } else {
+            writer.write(clear, width);
+            table.clear();
+            next_code = end + 1;
+            width = min_code_size + 1;
+        }
+        prefix = index as u16;
+    }
+    writer.write(prefix, width);
+    writer.write(end, width);
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference decoder, following the GIF specification directly
+    fn lzw_decode(data: &[u8], min_code_size: u32) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let mut width = min_code_size + 1;
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let reset = |table: &mut Vec<Vec<u8>>| {
+            table.clear();
+            table.extend((0..clear).map(|i| vec![i as u8]));
+            table.push(Vec::new());
+            table.push(Vec::new());
+        };
+        reset(&mut table);
+        let (mut bit, mut out, mut previous): (usize, Vec<u8>, Option<Vec<u8>>) = (0, Vec::new(), None);
+        loop {
+            let mut code = 0usize;
+            for i in 0..width as usize {
+                code |= (((data[(bit + i) / 8] >> ((bit + i) % 8)) & 1) as usize) << i;
+            }
+            bit += width as usize;
+            if code == clear {
+                reset(&mut table);
+                width = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (table.get(code), &previous) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(prev)) => [prev.clone(), vec![prev[0]]].concat(),
+                (None, None) => panic!("bad code"),
+            };
+            if let Some(prev) = previous {
+                table.push([prev, vec![entry[0]]].concat());
+            }
+            if table.len() == 1 << width && width < MAX_CODE_BITS {
+                width += 1;
+            }
+            out.extend_from_slice(&entry);
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trips_including_table_resets() {
+        let mut noise: Vec<u8> = (0..20_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 29) as u8 & 3).collect();
+        noise.extend(std::iter::repeat_n(1, 5_000));
+        assert_eq!(lzw_decode(&lzw_encode(&noise, 2), 2), noise);
+
+        let frame = Raster { width: 2, height: 1, pixels: vec![0, 0, 0, 255, 255, 255, 255, 255] };
+        let mut gif = Vec::new();
+        write_gif(&mut gif, &[[0, 0, 0], [255, 255, 255]], [Ok(frame.clone()), Ok(frame)], 10).unwrap();
+        assert!(gif.starts_with(b"GIF89a") && gif.ends_with(&[0x3B]));
+    }
+}
@@ -0,0 +1,112 @@
+//! Rasterising a region of the grid to an RGBA image.
+//!
+//! Works straight from `InfiniteGrid`, so exports don't depend on the window size, the
+//! camera or what the sprite renderer currently has spawned. Each cell becomes a
+//! `cell_pixels`-wide square in the theme's colour for its state; higher grid rows are drawn
+//! nearer the top of the image, matching the on-screen orientation.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use bevy::color::ColorToPacked;
+use crate::infinite_grid::{GridBounds, InfiniteGrid};
+use crate::theme::CellColors;
+use crate::CellState;
+use super::ExportError;
+
+/// Longest image side an export may produce, in pixels
+pub const MAX_IMAGE_SIDE: u32 = 16_384;
+
+/// Background colour of exported images
+pub const BACKGROUND: [u8; 4] = [0, 0, 0, 255];
+
+/// 8-bit RGBA pixels, row-major from the top-left
+#[derive(Clone, Debug, PartialEq)]
+pub struct Raster {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// sRGB bytes for a cell state in the given colours
+pub fn state_rgba(colors: &CellColors, state: CellState) -> [u8; 4] {
+    colors.color(state).to_srgba().to_u8_array()
+}
+
+/// Draw `region` (inclusive) of the grid at `cell_pixels` pixels per cell
+pub fn rasterize(grid: &InfiniteGrid, region: &GridBounds, cell_pixels: u32, colors: &CellColors) -> Result<Raster, ExportError> {
+    let cells = grid.cell_states_in_region(region.min_x, region.max_x, region.min_y, region.max_y);
+    rasterize_cells(cells, region, cell_pixels, colors)
+}
+
+/// Draw `cells`, all inside `region`, as `rasterize` would draw them from a grid
+pub fn rasterize_cells(
+    cells: impl IntoIterator<Item = (i32, i32, CellState)>,
+    region: &GridBounds,
+    cell_pixels: u32,
+    colors: &CellColors,
+) -> Result<Raster, ExportError> {
+    let (width, height) = image_size(region, cell_pixels)?;
+    let cell_pixels = cell_pixels.max(1);
+    let mut pixels = BACKGROUND.repeat((width * height) as usize);
+    for (x, y, state) in cells {
+        let colour = state_rgba(colors, state);
+        let px = (x - region.min_x) as u32 * cell_pixels;
+        let py = (region.max_y - y) as u32 * cell_pixels;
+        for row in py..py + cell_pixels {
+            let start = ((row * width + px) * 4) as usize;
+            for pixel in pixels[start..start + cell_pixels as usize * 4].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&colour);
+            }
+        }
+    }
+    Ok(Raster { width, height, pixels })
+}
+
+/// Pixel size of `region` at `cell_pixels` pixels per cell, or `TooLarge` past `MAX_IMAGE_SIDE`
+pub fn image_size(region: &GridBounds, cell_pixels: u32) -> Result<(u32, u32), ExportError> {
+    let cell_pixels = cell_pixels.max(1);
+    let cols = (region.max_x as i64 - region.min_x as i64 + 1).max(1) as u64;
+    let rows = (region.max_y as i64 - region.min_y as i64 + 1).max(1) as u64;
+    let (width, height) = (cols * cell_pixels as u64, rows * cell_pixels as u64);
+    if width > MAX_IMAGE_SIDE as u64 || height > MAX_IMAGE_SIDE as u64 {
+        return Err(ExportError::TooLarge { width, height });
+    }
+    Ok((width as u32, height as u32))
+}
+
+impl Raster {
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), ExportError> {
+        let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_land_on_the_right_pixels() {
+        let mut grid = InfiniteGrid::new();
+        grid.set(0, 1, CellState::Alive);
+        grid.set(1, 0, CellState::Wire);
+        let region = GridBounds { min_x: 0, max_x: 2, min_y: 0, max_y: 1 };
+        let colors = CellColors::default();
+        let raster = rasterize(&grid, &region, 2, &colors).unwrap();
+        assert_eq!((raster.width, raster.height), (6, 4));
+        let at = |x: u32, y: u32| &raster.pixels[((y * raster.width + x) * 4) as usize..][..4];
+        // Higher rows are drawn at the top
+        assert_eq!(at(1, 1), state_rgba(&colors, CellState::Alive));
+        assert_eq!(at(3, 3), state_rgba(&colors, CellState::Wire));
+        assert_eq!(at(5, 0), BACKGROUND);
+
+        let huge = GridBounds { min_x: 0, max_x: 100_000, min_y: 0, max_y: 0 };
+        assert!(matches!(rasterize(&grid, &huge, 1, &colors), Err(ExportError::TooLarge { .. })));
+    }
+}
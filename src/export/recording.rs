@@ -0,0 +1,230 @@
+//! Recording generations into an animated GIF or APNG.
+//!
+//! The viewport is frozen when recording starts so every frame covers the same cells, then
+//! one frame is captured per new generation. Only the cells of each frame are kept, which
+//! costs far less than their pixels; frames are drawn one at a time while the recording is
+//! encoded, off the main thread, once it stops, either after the requested number of
+//! generations, on demand, or when one of the caps below is reached.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::infinite_grid::{GridBounds, InfiniteGrid};
+use crate::theme::CellColors;
+use crate::CellState;
+use super::gif::write_gif;
+use super::raster::{image_size, rasterize_cells, state_rgba, BACKGROUND};
+use super::{ExportError, Raster};
+
+/// Hard stop for open-ended recordings so a forgotten recorder can't eat all memory
+pub const MAX_RECORDED_FRAMES: usize = 2_000;
+/// Cells kept over all frames before the recording stops by itself (about 600 MB)
+pub const MAX_RECORDED_CELLS: usize = 50_000_000;
+
+/// Every state a frame can draw, for the GIF palette
+const STATES: [CellState; 7] = [
+    CellState::Alive,
+    CellState::Dying,
+    CellState::Wire,
+    CellState::ElectronHead,
+    CellState::ElectronTail,
+    CellState::SpeciesA,
+    CellState::SpeciesB,
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordFormat {
+    #[default]
+    Gif,
+    Apng,
+}
+
+impl RecordFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            RecordFormat::Gif => "gif",
+            RecordFormat::Apng => "png",
+        }
+    }
+}
+
+/// The frames of a stopped recording, drawn as they are encoded
+#[derive(Clone, Debug)]
+pub struct Recording {
+    region: GridBounds,
+    cell_pixels: u32,
+    colors: CellColors,
+    frames: Vec<Vec<(i32, i32, CellState)>>,
+}
+
+impl Recording {
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Every frame as pixels, drawn one at a time
+    pub fn rasters(&self) -> impl Iterator<Item = Result<Raster, ExportError>> + '_ {
+        self.frames.iter().map(|cells| rasterize_cells(cells.iter().copied(), &self.region, self.cell_pixels, &self.colors))
+    }
+}
+
+/// An in-progress recording
+#[derive(Resource, Default)]
+pub struct Recorder {
+    region: Option<GridBounds>,
+    cell_pixels: u32,
+    /// Colours frozen at the start so every frame matches
+    colors: CellColors,
+    frames: Vec<Vec<(i32, i32, CellState)>>,
+    /// Cells over all of `frames`
+    cells: usize,
+    /// Frames still to capture, or `None` to record until stopped
+    remaining: Option<usize>,
+    last_generation: u64,
+}
+
+impl Recorder {
+    pub fn is_recording(&self) -> bool {
+        self.region.is_some()
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Start recording `region`, capturing the current grid as the first frame
+    pub fn start(
+        &mut self,
+        grid: &InfiniteGrid,
+        region: GridBounds,
+        cell_pixels: u32,
+        colors: &CellColors,
+        generations: Option<usize>,
+    ) -> Result<(), ExportError> {
+        image_size(&region, cell_pixels)?;
+        self.frames.clear();
+        self.cells = 0;
+        self.cell_pixels = cell_pixels;
+        self.colors = colors.clone();
+        self.region = Some(region);
+        self.remaining = generations;
+        self.last_generation = grid.generation();
+        self.keep_frame(grid);
+        Ok(())
+    }
+
+    /// Capture a frame if the grid has moved on since the last one. Returns true once the
+    /// recording has all the frames it asked for, or as many as it may hold.
+    pub fn capture(&mut self, grid: &InfiniteGrid) -> bool {
+        if self.region.is_none() {
+            return false;
+        }
+        if grid.generation() != self.last_generation {
+            self.last_generation = grid.generation();
+            self.keep_frame(grid);
+            if let Some(remaining) = &mut self.remaining {
+                *remaining = remaining.saturating_sub(1);
+            }
+        }
+        self.remaining == Some(0) || self.frames.len() >= MAX_RECORDED_FRAMES || self.cells >= MAX_RECORDED_CELLS
+    }
+
+    fn keep_frame(&mut self, grid: &InfiniteGrid) {
+        let Some(region) = &self.region else { return };
+        let cells: Vec<_> = grid.cell_states_in_region(region.min_x, region.max_x, region.min_y, region.max_y).collect();
+        self.cells += cells.len();
+        self.frames.push(cells);
+    }
+
+    /// Stop recording and hand back the captured frames, if it was running
+    pub fn finish(&mut self) -> Option<Recording> {
+        let region = self.region.take()?;
+        self.remaining = None;
+        self.cells = 0;
+        Some(Recording {
+            region,
+            cell_pixels: self.cell_pixels,
+            colors: self.colors.clone(),
+            frames: std::mem::take(&mut self.frames),
+        })
+    }
+}
+
+/// Encode `recording` at `fps` frames per second
+pub fn save_animation(
+    recording: &Recording,
+    format: RecordFormat,
+    fps: u32,
+    path: impl AsRef<Path>,
+) -> Result<(), ExportError> {
+    if recording.is_empty() {
+        return Err(ExportError::Empty);
+    }
+    let (width, height) = image_size(&recording.region, recording.cell_pixels)?;
+    let fps = fps.clamp(1, 100);
+    let mut out = BufWriter::new(File::create(path)?);
+    match format {
+        // GIF delays are in hundredths of a second, and most viewers treat anything under
+        // two as "as fast as possible"
+        RecordFormat::Gif => {
+            let rgb = |rgba: [u8; 4]| [rgba[0], rgba[1], rgba[2]];
+            let mut palette = vec![rgb(BACKGROUND)];
+            palette.extend(STATES.iter().map(|&state| rgb(state_rgba(&recording.colors, state))));
+            write_gif(&mut out, &palette, recording.rasters(), (100 / fps).max(2) as u16)
+        }
+        RecordFormat::Apng => {
+            let mut encoder = png::Encoder::new(out, width, height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_animated(recording.len() as u32, 0)?;
+            encoder.set_frame_delay(1, fps as u16)?;
+            let mut writer = encoder.write_header()?;
+            for frame in recording.rasters() {
+                writer.write_image_data(&frame?.pixels)?;
+            }
+            writer.finish()?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::rasterize;
+    use super::*;
+
+    #[test]
+    fn records_one_frame_per_generation() {
+        let mut grid = InfiniteGrid::new();
+        for x in 0..3 {
+            grid.set(x, 0, CellState::Alive);
+        }
+        let region = GridBounds { min_x: -1, max_x: 3, min_y: -2, max_y: 2 };
+        let mut recorder = Recorder::default();
+        recorder.start(&grid, region.clone(), 1, &CellColors::default(), Some(2)).unwrap();
+        // No new generation, no new frame
+        assert!(!recorder.capture(&grid));
+        assert_eq!(recorder.frame_count(), 1);
+
+        grid.step_conway();
+        assert!(!recorder.capture(&grid));
+        grid.step_conway();
+        assert!(recorder.capture(&grid));
+
+        let recording = recorder.finish().unwrap();
+        assert!(!recorder.is_recording());
+        assert_eq!(recording.len(), 3);
+        let frames: Vec<_> = recording.rasters().map(Result::unwrap).collect();
+        // The blinker returns to its first phase
+        assert_eq!(frames[0], frames[2]);
+        assert_ne!(frames[0], frames[1]);
+        assert_eq!(frames[0], rasterize(&grid, &region, 1, &CellColors::default()).unwrap());
+    }
+}
@@ -0,0 +1,98 @@
+//! Vector export of a grid region as SVG.
+//!
+//! Every live cell becomes a unit square in a `viewBox` measured in cells, so the drawing
+//! scales to any size without resampling. Cells are grouped by state under one `<g fill>`
+//! each, and with `merge_runs` horizontal runs of same-state cells are written as a single
+//! wider rectangle, which keeps files for dense patterns several times smaller.
+
+use std::cmp::Reverse;
+use std::fmt::Write as _;
+use std::path::Path;
+use crate::infinite_grid::{GridBounds, InfiniteGrid};
+use crate::theme::CellColors;
+use super::raster::BACKGROUND;
+use super::{state_rgba, ExportError};
+
+/// Render `region` (inclusive) as an SVG document, `cell_pixels` user units per cell
+pub fn write_svg(grid: &InfiniteGrid, region: &GridBounds, cell_pixels: u32, colors: &CellColors, merge_runs: bool) -> String {
+    let cols = (region.max_x as i64 - region.min_x as i64 + 1).max(1);
+    let rows = (region.max_y as i64 - region.min_y as i64 + 1).max(1);
+    let cell_pixels = cell_pixels.max(1) as i64;
+
+    // Grouped by state, then top row first and left to right, so runs are adjacent
+    let mut cells: Vec<_> = grid.cell_states_in_region(region.min_x, region.max_x, region.min_y, region.max_y).collect();
+    cells.sort_unstable_by_key(|&(x, y, state)| (state as u8, Reverse(y), x));
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" shape-rendering="crispEdges">"#,
+        cols * cell_pixels, rows * cell_pixels, cols, rows
+    );
+    let _ = writeln!(svg, r#"<rect width="{}" height="{}"{}/>"#, cols, rows, fill_attributes(BACKGROUND));
+
+    let mut index = 0;
+    while index < cells.len() {
+        let state = cells[index].2;
+        let _ = writeln!(svg, "<g{}>", fill_attributes(state_rgba(colors, state)));
+        while index < cells.len() && cells[index].2 == state {
+            let (x, y, _) = cells[index];
+            let mut width = 1;
+            if merge_runs {
+                while cells.get(index + width).is_some_and(|&(nx, ny, ns)| ns == state && ny == y && nx as i64 == x as i64 + width as i64) {
+                    width += 1;
+                }
+            }
+            let _ = writeln!(svg, r#"<rect x="{}" y="{}" width="{}" height="1"/>"#, x - region.min_x, region.max_y - y, width);
+            index += width;
+        }
+        svg.push_str("</g>\n");
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Render and write `region` to `path`
+pub fn save_svg(grid: &InfiniteGrid, region: &GridBounds, cell_pixels: u32, colors: &CellColors, merge_runs: bool, path: impl AsRef<Path>) -> Result<(), ExportError> {
+    if grid.cell_states_in_region(region.min_x, region.max_x, region.min_y, region.max_y).next().is_none() {
+        return Err(ExportError::Empty);
+    }
+    std::fs::write(path, write_svg(grid, region, cell_pixels, colors, merge_runs))?;
+    Ok(())
+}
+
+fn fill_attributes([r, g, b, a]: [u8; 4]) -> String {
+    let mut attributes = format!(r##" fill="#{:02x}{:02x}{:02x}""##, r, g, b);
+    if a < 255 {
+        let _ = write!(attributes, r#" fill-opacity="{:.3}""#, a as f32 / 255.0);
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CellState;
+
+    #[test]
+    fn runs_merge_per_row_and_state() {
+        let mut grid = InfiniteGrid::new();
+        for x in 0..3 {
+            grid.set(x, 5, CellState::Alive);
+        }
+        grid.set(4, 5, CellState::Alive);
+        grid.set(1, 4, CellState::Wire);
+        let region = GridBounds { min_x: 0, max_x: 4, min_y: 4, max_y: 5 };
+        let colors = CellColors::default();
+
+        let merged = write_svg(&grid, &region, 10, &colors, true);
+        assert!(merged.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="50" height="20" viewBox="0 0 5 2""#));
+        assert!(merged.contains(r#"<rect x="0" y="0" width="3" height="1"/>"#), "{}", merged);
+        assert!(merged.contains(r#"<rect x="4" y="0" width="1" height="1"/>"#));
+        assert!(merged.contains(r#"<rect x="1" y="1" width="1" height="1"/>"#));
+        assert_eq!(merged.matches("<g ").count(), 2);
+
+        let separate = write_svg(&grid, &region, 10, &colors, false);
+        assert_eq!(separate.matches("<rect ").count(), 1 + 5);
+    }
+}
@@ -0,0 +1,193 @@
+//! Streaming rendered frames to ffmpeg.
+//!
+//! While a video recording is running the primary window is captured at the configured
+//! frame rate and each frame's raw pixels are written to the stdin of an `ffmpeg` child
+//! process, which encodes MP4 (H.264) or WebM (VP9). ffmpeg is spawned on the first captured
+//! frame, once the window size and pixel layout are known; frames of any other size (after a
+//! window resize) are dropped rather than corrupting the stream.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use serde::{Deserialize, Serialize};
+use super::ExportError;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoFormat {
+    #[default]
+    Mp4,
+    Webm,
+}
+
+impl VideoFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "mp4",
+            VideoFormat::Webm => "webm",
+        }
+    }
+
+    fn codec_args(self) -> &'static [&'static str] {
+        match self {
+            VideoFormat::Mp4 => &["-c:v", "libx264", "-preset", "veryfast", "-crf", "20", "-pix_fmt", "yuv420p"],
+            VideoFormat::Webm => &["-c:v", "libvpx-vp9", "-b:v", "0", "-crf", "32", "-pix_fmt", "yuv420p"],
+        }
+    }
+}
+
+/// ffmpeg's name for a screenshot's pixel layout, if it's one we can stream
+pub fn ffmpeg_pixel_format(format: TextureFormat) -> Option<&'static str> {
+    match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => Some("rgba"),
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => Some("bgra"),
+        _ => None,
+    }
+}
+
+/// Command-line arguments for encoding raw `width`×`height` frames from stdin to `path`
+pub fn ffmpeg_args(width: u32, height: u32, pixel_format: &str, fps: u32, format: VideoFormat, path: &Path) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "-hide_banner", "-loglevel", "error", "-y",
+        "-f", "rawvideo", "-pix_fmt", pixel_format,
+        "-s", &format!("{}x{}", width, height),
+        "-framerate", &fps.to_string(),
+        "-i", "-",
+        // yuv420p needs even dimensions
+        "-vf", "scale=trunc(iw/2)*2:trunc(ih/2)*2",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    args.extend(format.codec_args().iter().map(|s| s.to_string()));
+    args.push(path.to_string_lossy().into_owned());
+    args
+}
+
+/// A running ffmpeg process fed one frame at a time
+pub struct FfmpegPipe {
+    child: Child,
+    stdin: ChildStdin,
+    pub width: u32,
+    pub height: u32,
+    pub path: PathBuf,
+}
+
+impl FfmpegPipe {
+    pub fn spawn(ffmpeg: &str, args: Vec<String>, width: u32, height: u32, path: PathBuf) -> Result<Self, ExportError> {
+        let mut child = Command::new(ffmpeg)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            // Its progress report would otherwise scroll through the game's terminal
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => ExportError::Ffmpeg(format!("'{}' was not found; install ffmpeg or set ffmpeg_path", ffmpeg)),
+                _ => ExportError::Io(e),
+            })?;
+        let stdin = child.stdin.take().ok_or_else(|| ExportError::Ffmpeg("no stdin pipe".to_string()))?;
+        Ok(Self { child, stdin, width, height, path })
+    }
+
+    pub fn write_frame(&mut self, pixels: &[u8]) -> Result<(), ExportError> {
+        self.stdin.write_all(pixels).map_err(|e| ExportError::Ffmpeg(format!("ffmpeg stopped accepting frames: {}", e)))
+    }
+
+    /// Close the stream and wait for ffmpeg to finish writing the file
+    pub fn finish(self) -> Result<PathBuf, ExportError> {
+        let Self { mut child, stdin, path, .. } = self;
+        drop(stdin);
+        let status = child.wait()?;
+        if status.success() {
+            Ok(path)
+        } else {
+            Err(ExportError::Ffmpeg(format!("ffmpeg exited with {}", status)))
+        }
+    }
+}
+
+/// Video recording state
+#[derive(Resource, Default)]
+pub struct VideoRecorder {
+    /// Output file while recording; ffmpeg starts on the first frame
+    target: Option<PathBuf>,
+    pipe: Option<FfmpegPipe>,
+    pub frames: u64,
+    /// Seconds since the last frame was requested
+    since_capture: f32,
+}
+
+impl VideoRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.target.is_some()
+    }
+
+    pub fn start(&mut self, path: PathBuf) {
+        self.target = Some(path);
+        self.pipe = None;
+        self.frames = 0;
+        self.since_capture = f32::INFINITY;
+    }
+
+    /// Whether a frame is due after `delta` seconds at `fps` frames per second
+    pub fn frame_due(&mut self, delta: f32, fps: u32) -> bool {
+        self.since_capture += delta;
+        if self.since_capture < 1.0 / fps.max(1) as f32 {
+            return false;
+        }
+        self.since_capture = 0.0;
+        true
+    }
+
+    /// Feed one captured frame, starting ffmpeg if this is the first
+    pub fn push_frame(&mut self, image: &Image, ffmpeg: &str, fps: u32, format: VideoFormat) -> Result<(), ExportError> {
+        let Some(target) = &self.target else { return Ok(()) };
+        let (width, height) = (image.width(), image.height());
+        if self.pipe.is_none() {
+            let pixel_format = ffmpeg_pixel_format(image.texture_descriptor.format)
+                .ok_or_else(|| ExportError::Ffmpeg(format!("unsupported frame format {:?}", image.texture_descriptor.format)))?;
+            let args = ffmpeg_args(width, height, pixel_format, fps, format, target);
+            self.pipe = Some(FfmpegPipe::spawn(ffmpeg, args, width, height, target.clone())?);
+        }
+        let Some(pipe) = &mut self.pipe else { return Ok(()) };
+        if (pipe.width, pipe.height) != (width, height) {
+            return Ok(());
+        }
+        pipe.write_frame(&image.data)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Stop recording, returning the pipe to finish if any frames were written
+    pub fn stop(&mut self) -> Option<FfmpegPipe> {
+        self.target = None;
+        self.pipe.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ffmpeg_reads_raw_frames_from_stdin() {
+        let args = ffmpeg_args(641, 480, "bgra", 30, VideoFormat::Webm, Path::new("out/clip.webm"));
+        let after = |flag: &str| args[args.iter().position(|a| a == flag).unwrap() + 1].as_str();
+        assert_eq!(after("-f"), "rawvideo");
+        assert_eq!(after("-pix_fmt"), "bgra");
+        assert_eq!(after("-s"), "641x480");
+        assert_eq!(after("-framerate"), "30");
+        assert_eq!(after("-i"), "-");
+        assert_eq!(after("-c:v"), "libvpx-vp9");
+        assert_eq!(args.last().unwrap(), "out/clip.webm");
+
+        let mut recorder = VideoRecorder::default();
+        recorder.start(PathBuf::from("unused.mp4"));
+        assert!(recorder.frame_due(0.0, 30));
+        assert!(!recorder.frame_due(0.01, 30));
+        assert!(recorder.frame_due(0.03, 30));
+    }
+}
@@ -1,10 +1,20 @@
 use bevy::prelude::Resource;
+use crate::infinite_grid::{InfiniteGrid, RewindBuffer};
 
 #[derive(Resource)]
 pub struct GameConfig {
     pub current_rule: crate::start_screen::RuleType,
     pub audio_engine: crate::config::AudioEngine,
     pub audio_volume: f32,
+    /// Silences the audio without forgetting `audio_volume`
+    pub audio_muted: bool,
+}
+
+impl GameConfig {
+    /// Volume to hand the audio engine: `audio_volume`, or nothing while muted
+    pub fn output_volume(&self) -> f32 {
+        if self.audio_muted { 0.0 } else { self.audio_volume }
+    }
 }
 
 impl Default for GameConfig {
@@ -13,6 +23,141 @@ impl Default for GameConfig {
             current_rule: crate::start_screen::RuleType::Conway,
             audio_engine: crate::config::AudioEngine::Spatial,
             audio_volume: 0.7,
+            audio_muted: false,
+        }
+    }
+}
+
+/// Generations the run control goes for unless configured otherwise
+pub const DEFAULT_RUN_LENGTH: u64 = 100;
+
+/// Game statistics
+#[derive(Resource)]
+pub struct GameStats {
+    pub is_running: bool,
+    pub generation: u64,
+    pub last_update: f64,
+    pub update_interval: f64,
+    pub min_update_interval: f64,
+    pub max_update_interval: f64,
+    /// Generations a run started with the run control goes for before pausing
+    pub run_length: u64,
+    /// Generations left in the current run, if one is under way
+    pub run_remaining: Option<u64>,
+}
+
+impl Default for GameStats {
+    fn default() -> Self {
+        Self {
+            is_running: false,
+            generation: 0,
+            last_update: 0.0,
+            update_interval: 0.5,
+            min_update_interval: 0.01,
+            max_update_interval: 2.0,
+            run_length: DEFAULT_RUN_LENGTH,
+            run_remaining: None,
+        }
+    }
+}
+
+impl GameStats {
+    /// Run exactly `generations` more generations, then pause
+    pub fn start_run(&mut self, generations: u64) {
+        if generations > 0 {
+            self.run_remaining = Some(generations);
+            self.is_running = true;
+        }
+    }
+
+    /// Advance a single generation if paused
+    pub fn step(&mut self) {
+        if !self.is_running {
+            self.start_run(1);
         }
     }
-} 
\ No newline at end of file
+
+    /// Go back a generation through `rewind` if paused. False when there's nothing to go
+    /// back to.
+    pub fn step_back(&mut self, grid: &mut InfiniteGrid, rewind: &mut RewindBuffer) -> bool {
+        if self.is_running || !rewind.step_back(grid) {
+            return false;
+        }
+        self.generation = self.generation.saturating_sub(1);
+        true
+    }
+
+    /// Whether the simulation is paused, counting a single step as still paused
+    pub fn is_paused(&self) -> bool {
+        !self.is_running || self.run_remaining == Some(1)
+    }
+
+    /// Pause, ending any run
+    pub fn stop(&mut self) {
+        self.is_running = false;
+        self.run_remaining = None;
+    }
+
+    /// Count a generation towards the current run, pausing once it is over. True if this
+    /// generation finished a run.
+    pub fn count_generation(&mut self) -> bool {
+        match self.run_remaining {
+            Some(left) if left > 1 => {
+                self.run_remaining = Some(left - 1);
+                false
+            }
+            Some(_) => {
+                self.stop();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_pause_after_exactly_n_generations() {
+        let mut stats = GameStats::default();
+        stats.start_run(3);
+        assert!(stats.is_running);
+        assert!(!stats.count_generation());
+        assert!(!stats.count_generation());
+        assert!(stats.count_generation());
+        assert!(!stats.is_running);
+        assert_eq!(stats.run_remaining, None);
+
+        // Outside a run generations go uncounted
+        stats.is_running = true;
+        assert!(!stats.count_generation());
+        assert!(stats.is_running);
+        stats.start_run(0);
+        assert_eq!(stats.run_remaining, None);
+    }
+
+    #[test]
+    fn a_single_step_stays_paused() {
+        let mut stats = GameStats::default();
+        assert!(stats.is_paused());
+        stats.step();
+        assert!(stats.is_running && stats.is_paused());
+        stats.count_generation();
+        stats.start_run(10);
+        assert!(!stats.is_paused());
+        stats.stop();
+        stats.is_running = true;
+        assert!(!stats.is_paused());
+    }
+
+    #[test]
+    fn muting_keeps_the_volume() {
+        let mut config = GameConfig { audio_volume: 0.4, ..Default::default() };
+        assert_eq!(config.output_volume(), 0.4);
+        config.audio_muted = true;
+        assert_eq!(config.output_volume(), 0.0);
+        assert_eq!(config.audio_volume, 0.4);
+    }
+}
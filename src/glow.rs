@@ -0,0 +1,122 @@
+//! Optional HDR bloom.
+//!
+//! With `CellRenderConfig::bloom_intensity` above zero the game camera renders in HDR with
+//! Bevy's bloom pass, and the cell renderer pushes newborn cells and WireWorld electron
+//! heads past full brightness (see `emissive_boost`) so they spill light onto their
+//! neighbours. At zero the camera goes back to plain LDR rendering. Q toggles bloom with
+//! the default bindings and Shift+Q steps through `BLOOM_LEVELS`.
+
+use bevy::core_pipeline::bloom::Bloom;
+use bevy::prelude::*;
+use crate::camera::GameCamera;
+use crate::cell_renderer::{AnimationType, CellAnimation, CellRenderConfig};
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::GameState;
+use crate::ui::toast;
+use crate::tr;
+use crate::CellState;
+
+/// Bloom strengths Shift+Q steps through
+pub const BLOOM_LEVELS: [f32; 4] = [0.15, 0.25, 0.4, 0.6];
+
+/// Extra brightness of a cell at the moment it's born, as a multiple of its colour
+pub const NEWBORN_BOOST: f32 = 2.0;
+
+/// Extra brightness of a WireWorld electron head
+pub const ELECTRON_BOOST: f32 = 1.5;
+
+/// How far past full brightness a cell is pushed while bloom is on: newborns flare and fade
+/// as their birth animation plays out, electron heads burn steadily, and glowing cells pulse
+pub fn emissive_boost(state: CellState, animation: Option<&CellAnimation>) -> f32 {
+    let animated = match animation {
+        Some(anim) => match anim.animation_type {
+            AnimationType::Birth => NEWBORN_BOOST * (1.0 - anim.timer.fraction()),
+            AnimationType::Glow => 1.0,
+            AnimationType::Pulse | AnimationType::Death => 0.0,
+        },
+        None => 0.0,
+    };
+    let steady = if state == CellState::ElectronHead { ELECTRON_BOOST } else { 0.0 };
+    animated.max(steady)
+}
+
+pub struct GlowPlugin;
+
+impl Plugin for GlowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, bloom_controls.run_if(in_state(GameState::Playing)))
+            .add_systems(PostUpdate, apply_bloom);
+    }
+}
+
+fn bloom_controls(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
+    mut config: ResMut<CellRenderConfig>,
+    mut last_intensity: Local<Option<f32>>,
+) {
+    if !keys.just_pressed(&keyboard_input, Action::Bloom) {
+        return;
+    }
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let current = config.bloom_intensity;
+    config.bloom_intensity = if shift {
+        // Next level up from the current strength, wrapping to the weakest
+        BLOOM_LEVELS.into_iter().find(|&level| level > current + 1e-3).unwrap_or(BLOOM_LEVELS[0])
+    } else if current > 0.0 {
+        *last_intensity = Some(current);
+        0.0
+    } else {
+        last_intensity.unwrap_or(BLOOM_LEVELS[0])
+    };
+    match config.bloom_intensity {
+        0.0 => toast(tr!("✨ Bloom off")),
+        intensity => toast(tr!("✨ Bloom {:.2}", intensity)),
+    }
+}
+
+/// Keep the camera's HDR flag and bloom pass in step with the configured intensity
+fn apply_bloom(
+    mut commands: Commands,
+    config: Res<CellRenderConfig>,
+    mut camera_query: Query<(Entity, &mut Camera, Option<&mut Bloom>), With<GameCamera>>,
+) {
+    for (entity, mut camera, bloom) in camera_query.iter_mut() {
+        let enabled = config.bloom_intensity > 0.0;
+        if camera.hdr != enabled {
+            camera.hdr = enabled;
+        }
+        match (bloom, enabled) {
+            (Some(mut bloom), true) => {
+                if bloom.intensity != config.bloom_intensity {
+                    bloom.intensity = config.bloom_intensity;
+                }
+            }
+            (None, true) => {
+                commands.entity(entity).insert(Bloom { intensity: config.bloom_intensity, ..Bloom::NATURAL });
+            }
+            (Some(_), false) => {
+                commands.entity(entity).remove::<Bloom>();
+            }
+            (None, false) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newborns_and_electron_heads_glow() {
+        let birth = CellAnimation {
+            animation_type: AnimationType::Birth,
+            timer: Timer::from_seconds(1.0, TimerMode::Once),
+            progress: 0.0,
+        };
+        assert_eq!(emissive_boost(CellState::Alive, Some(&birth)), NEWBORN_BOOST);
+        assert_eq!(emissive_boost(CellState::Alive, None), 0.0);
+        assert_eq!(emissive_boost(CellState::ElectronHead, None), ELECTRON_BOOST);
+        assert_eq!(emissive_boost(CellState::Wire, None), 0.0);
+    }
+}
@@ -0,0 +1,110 @@
+//! Grid-line overlay.
+//!
+//! Draws the cell boundaries once the camera is zoomed in far enough for them to be useful
+//! (`GridOverlay::min_zoom`), fading them in over the first stretch above that zoom so they
+//! don't pop. Every `MAJOR_EVERY`th line is drawn stronger to make counting cells easy when
+//! laying out WireWorld circuits. X toggles the overlay with the default bindings.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::camera::{CameraState, GameCamera};
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::GameState;
+use crate::theme::Theme;
+use crate::ui::toast;
+use crate::tr;
+
+/// Cells between major lines
+pub const MAJOR_EVERY: i32 = 10;
+
+#[derive(Resource)]
+pub struct GridOverlay {
+    pub enabled: bool,
+    /// Lines are hidden below this camera zoom
+    pub min_zoom: f32,
+}
+
+impl Default for GridOverlay {
+    fn default() -> Self {
+        Self { enabled: false, min_zoom: 0.5 }
+    }
+}
+
+/// World positions of the boundaries between cells `min..=max` along one axis, with
+/// whether each is a major line. Cell `n` is centred on `n * cell_size`.
+pub fn boundaries(min: i32, max: i32, cell_size: f32, offset: f32) -> impl Iterator<Item = (f32, bool)> {
+    (min..=max + 1).map(move |n| ((n as f32 - 0.5) * cell_size + offset, n.rem_euclid(MAJOR_EVERY) == 0))
+}
+
+pub struct GridOverlayPlugin;
+
+impl Plugin for GridOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GridOverlay>()
+            .add_systems(Update, (grid_overlay_controls, draw_grid_lines).run_if(in_state(GameState::Playing)));
+    }
+}
+
+fn grid_overlay_controls(keyboard_input: Res<ButtonInput<KeyCode>>, keys: Res<Keybindings>, mut overlay: ResMut<GridOverlay>) {
+    if keys.just_pressed(&keyboard_input, Action::GridLines) {
+        overlay.enabled = !overlay.enabled;
+        toast(tr!("📏 Grid lines {}", if overlay.enabled { "on" } else { "off" }));
+    }
+}
+
+fn draw_grid_lines(
+    mut gizmos: Gizmos,
+    overlay: Res<GridOverlay>,
+    theme: Res<Theme>,
+    camera_query: Query<(&Transform, &GameCamera)>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_state: Res<CameraState>,
+) {
+    if !overlay.enabled {
+        return;
+    }
+    let (Ok((camera_transform, camera)), Ok(window)) = (camera_query.get_single(), windows.get_single()) else { return };
+    if camera.zoom < overlay.min_zoom {
+        return;
+    }
+    // Fade in over the first 50% of zoom past the threshold
+    let strength = ((camera.zoom / overlay.min_zoom.max(f32::EPSILON) - 1.0) / 0.5).clamp(0.0, 1.0);
+    let colour = theme.grid_lines.0;
+    let minor = colour.with_alpha(colour.alpha() * strength);
+    let major = colour.with_alpha((colour.alpha() * 2.0).min(1.0) * strength);
+
+    let cell_size = camera_state.cell_size;
+    let offset = camera_state.grid_offset;
+    let centre = camera_transform.translation.truncate();
+    let half = Vec2::new(window.width(), window.height()) / camera.zoom / 2.0;
+    let (low, high) = (centre - half, centre + half);
+    let cell_range = |low: f32, high: f32, offset: f32| {
+        (((low - offset) / cell_size).floor() as i32, ((high - offset) / cell_size).ceil() as i32)
+    };
+
+    let (min_x, max_x) = cell_range(low.x, high.x, offset.x);
+    for (x, is_major) in boundaries(min_x, max_x, cell_size, offset.x) {
+        gizmos.line_2d(Vec2::new(x, low.y), Vec2::new(x, high.y), if is_major { major } else { minor });
+    }
+    let (min_y, max_y) = cell_range(low.y, high.y, offset.y);
+    for (y, is_major) in boundaries(min_y, max_y, cell_size, offset.y) {
+        gizmos.line_2d(Vec2::new(low.x, y), Vec2::new(high.x, y), if is_major { major } else { minor });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::world_to_grid;
+
+    #[test]
+    fn boundaries_enclose_the_cells_clicks_land_in() {
+        let lines: Vec<_> = boundaries(-1, 0, 20.0, 0.0).collect();
+        assert_eq!(lines, vec![(-30.0, false), (-10.0, true), (10.0, false)]);
+
+        // A click just inside either side of a boundary lands in the cell on that side
+        let camera_state = CameraState::default();
+        assert_eq!(world_to_grid(Vec2::new(9.9, -9.9), &camera_state), (0, 0));
+        assert_eq!(world_to_grid(Vec2::new(10.1, -10.1), &camera_state), (1, -1));
+    }
+}
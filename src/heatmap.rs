@@ -0,0 +1,225 @@
+//! Density heatmap shown instead of cell sprites when zoomed far out.
+//!
+//! Below `CellRenderConfig::lod_zoom` a cell is only a few pixels across, and a large
+//! pattern would need hundreds of thousands of sprites. The sprite renderer stands down
+//! and this module draws a single texture instead, one texel per block of cells, shaded by
+//! how many of the block's cells are alive. Block size grows as the camera zooms out so a
+//! texel stays roughly `TEXEL_PIXELS` on screen, and the texture is only rebuilt when the
+//! grid or the covered blocks change.
+
+use bevy::color::ColorToPacked;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::window::PrimaryWindow;
+use crate::camera::GameCamera;
+use crate::cell_renderer::CellRenderConfig;
+use crate::infinite_grid::InfiniteGrid;
+use crate::theme::Theme;
+use crate::CellState;
+use crate::start_screen::{GameState, InGame};
+
+/// Screen pixels covered by one heatmap texel, at least
+pub const TEXEL_PIXELS: f32 = 4.0;
+
+/// Longest texture side, in texels
+const MAX_TEXELS: u32 = 1024;
+
+/// Drawn below the cell sprites (which sit at z = 0)
+const HEATMAP_Z: f32 = -1.0;
+
+/// Blocks of cells covered by the heatmap texture
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockRegion {
+    /// Cells per block side
+    pub block: i32,
+    /// Block coordinates of the bottom-left block
+    pub min_bx: i32,
+    pub min_by: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl BlockRegion {
+    /// Blocks covering the cells `min..=max` with blocks of `block` cells
+    pub fn covering(min_x: i32, max_x: i32, min_y: i32, max_y: i32, block: i32) -> Self {
+        let block = block.max(1);
+        let (min_bx, min_by) = (min_x.div_euclid(block), min_y.div_euclid(block));
+        let (max_bx, max_by) = (max_x.div_euclid(block), max_y.div_euclid(block));
+        Self {
+            block,
+            min_bx,
+            min_by,
+            width: (max_bx - min_bx + 1).max(1) as u32,
+            height: (max_by - min_by + 1).max(1) as u32,
+        }
+    }
+
+    /// Live cells per block, row-major from the top-left block (highest y first)
+    pub fn count(&self, grid: &InfiniteGrid) -> Vec<u32> {
+        let mut counts = vec![0; (self.width * self.height) as usize];
+        let min_x = self.min_bx * self.block;
+        let min_y = self.min_by * self.block;
+        let max_x = min_x + self.width as i32 * self.block - 1;
+        let max_y = min_y + self.height as i32 * self.block - 1;
+        for (x, y) in grid.cells_in_region(min_x, max_x, min_y, max_y) {
+            let column = ((x - min_x) / self.block) as u32;
+            let row = self.height - 1 - ((y - min_y) / self.block) as u32;
+            counts[(row * self.width + column) as usize] += 1;
+        }
+        counts
+    }
+
+    /// World-space centre and size of the covered blocks. Cell (x, y) is centred on
+    /// (x, y) * cell_size, so blocks start half a cell early.
+    pub fn world_rect(&self, cell_size: f32) -> (Vec2, Vec2) {
+        let span = Vec2::new(self.width as f32, self.height as f32) * self.block as f32 * cell_size;
+        let origin = Vec2::new(self.min_bx as f32, self.min_by as f32) * self.block as f32 * cell_size - cell_size / 2.0;
+        (origin + span / 2.0, span)
+    }
+}
+
+/// Blocks covering the cells in view with a little margin, matching the sprite renderer's
+/// bounds, with blocks `block_size` cells across or larger to stay within `MAX_TEXELS`
+pub(crate) fn view_region(camera_transform: &Transform, camera: &GameCamera, window: &Window, cell_size: f32) -> BlockRegion {
+    let centre = camera_transform.translation.truncate() / cell_size;
+    let half = Vec2::new(window.width(), window.height()) / (camera.zoom * cell_size) * 0.6;
+    let mut block = block_size(camera.zoom, cell_size);
+    while (2.0 * half.max_element() / block as f32) > MAX_TEXELS as f32 {
+        block *= 2;
+    }
+    BlockRegion::covering(
+        (centre.x - half.x).floor() as i32,
+        (centre.x + half.x).ceil() as i32,
+        (centre.y - half.y).floor() as i32,
+        (centre.y + half.y).ceil() as i32,
+        block,
+    )
+}
+
+/// Cells per block side at `zoom`: a power of two, so blocks stay put while zooming
+pub fn block_size(zoom: f32, cell_size: f32) -> i32 {
+    let cell_pixels = (zoom * cell_size).max(f32::EPSILON);
+    let cells = (TEXEL_PIXELS / cell_pixels).ceil().clamp(1.0, (1 << 20) as f32) as u32;
+    cells.next_power_of_two() as i32
+}
+
+#[derive(Component)]
+pub struct DensityHeatmap;
+
+/// The heatmap texture and what it was last built from
+#[derive(Resource, Default)]
+pub struct HeatmapState {
+    image: Option<Handle<Image>>,
+    region: Option<BlockRegion>,
+    grid_version: u64,
+}
+
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HeatmapState>()
+            .add_systems(Update, update_heatmap.run_if(in_state(GameState::Playing)))
+            .add_systems(OnExit(InGame), despawn_heatmap);
+    }
+}
+
+fn update_heatmap(
+    mut commands: Commands,
+    (grid, config, theme): (Res<InfiniteGrid>, Res<CellRenderConfig>, Res<Theme>),
+    camera_query: Query<(&Transform, &GameCamera), Without<DensityHeatmap>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut heatmap_query: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<DensityHeatmap>>,
+    mut state: ResMut<HeatmapState>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !config.heatmap_active {
+        for (_, _, mut visibility) in heatmap_query.iter_mut() {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    }
+    let (Ok((camera_transform, camera)), Ok(window)) = (camera_query.get_single(), windows.get_single()) else { return };
+    let region = view_region(camera_transform, camera, window, config.cell_size);
+
+    if state.region != Some(region) || state.grid_version != grid.version() || state.image.is_none() {
+        let pixels = density_pixels(&region.count(&grid), region.block, theme.cells.color(CellState::Alive));
+        let size = Extent3d { width: region.width, height: region.height, depth_or_array_layers: 1 };
+        let existing = state.image.as_ref().and_then(|handle| images.get_mut(handle));
+        match existing {
+            Some(image) if image.texture_descriptor.size == size => image.data = pixels,
+            _ => {
+                let mut image = Image::new(size, TextureDimension::D2, pixels, TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::default());
+                image.sampler = ImageSampler::nearest();
+                state.image = Some(images.add(image));
+            }
+        }
+        state.region = Some(region);
+        state.grid_version = grid.version();
+    }
+    let Some(handle) = state.image.clone() else { return };
+    let (centre, span) = region.world_rect(config.cell_size);
+    let translation = centre.extend(HEATMAP_Z);
+
+    if let Ok((mut transform, mut sprite, mut visibility)) = heatmap_query.get_single_mut() {
+        transform.translation = translation;
+        if sprite.image != handle || sprite.custom_size != Some(span) {
+            sprite.image = handle;
+            sprite.custom_size = Some(span);
+        }
+        *visibility = Visibility::Visible;
+    } else {
+        commands.spawn((
+            Sprite { image: handle, custom_size: Some(span), ..default() },
+            Transform::from_translation(translation),
+            DensityHeatmap,
+        ));
+    }
+}
+
+/// Texels in the cell colour, more opaque the fuller the block; empty blocks are transparent
+pub(crate) fn density_pixels(counts: &[u32], block: i32, colour: Color) -> Vec<u8> {
+    let [r, g, b, _] = colour.to_srgba().to_u8_array();
+    let capacity = (block as f32).powi(2);
+    let mut pixels = Vec::with_capacity(counts.len() * 4);
+    for &count in counts {
+        let alpha = if count == 0 {
+            0
+        } else {
+            // Square root so sparse blocks still show up next to solid ones
+            (255.0 * (0.25 + 0.75 * (count as f32 / capacity).min(1.0).sqrt())) as u8
+        };
+        pixels.extend_from_slice(&[r, g, b, alpha]);
+    }
+    pixels
+}
+
+fn despawn_heatmap(mut commands: Commands, heatmap_query: Query<Entity, With<DensityHeatmap>>, mut state: ResMut<HeatmapState>) {
+    for entity in heatmap_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    state.region = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_count_cells_top_row_first() {
+        let mut grid = InfiniteGrid::new();
+        grid.set(0, 0, CellState::Alive);
+        grid.set(1, 1, CellState::Alive);
+        grid.set(-1, 3, CellState::Alive);
+        grid.set(100, 100, CellState::Alive);
+        let region = BlockRegion::covering(-4, 3, 0, 3, 4);
+        assert_eq!((region.min_bx, region.min_by, region.width, region.height), (-1, 0, 2, 1));
+        assert_eq!(region.count(&grid), vec![1, 2]);
+
+        assert_eq!(block_size(1.0, 20.0), 1);
+        assert_eq!(block_size(0.05, 20.0), 4);
+        assert_eq!(block_size(0.01, 20.0), 32);
+    }
+}
@@ -0,0 +1,125 @@
+//! Full keybinding overlay.
+//!
+//! F1 (by default) pauses the game under a list of every key: each keybinding action with
+//! the key it is bound to now, then the Shift variants. The list is built from `Action::ALL`
+//! and the `Keybindings` each time it opens, so rebinding a key or adding an action shows up
+//! without anyone editing it. F1 again or Esc closes it.
+
+use bevy::prelude::*;
+use crate::command_palette::{uses_ctrl, SHIFTED};
+use crate::controls_screen::action_label;
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::GameState;
+use crate::tr;
+
+const KEY_COLOR: Color = Color::srgb(1.0, 0.85, 0.4);
+/// Width of the key column, so the labels line up
+const KEY_WIDTH: f32 = 100.0;
+
+#[derive(Component)]
+struct HelpScreen;
+
+/// Every key with what it does, as (key, description), in `Action::ALL` order then the
+/// Shift variants
+pub fn help_entries(keys: &Keybindings) -> Vec<(String, String)> {
+    let plain = Action::ALL.into_iter().map(|action| {
+        let key = if uses_ctrl(action) { format!("Ctrl+{}", keys.label(action)) } else { keys.label(action) };
+        (key, action_label(action))
+    });
+    let shifted = SHIFTED.into_iter().map(|(action, label)| (format!("Shift+{}", keys.label(action)), label.to_string()));
+    plain.chain(shifted).collect()
+}
+
+pub struct HelpOverlayPlugin;
+
+impl Plugin for HelpOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, open_help.run_if(in_state(GameState::Playing)))
+            .add_systems(OnEnter(GameState::Help), spawn_help)
+            .add_systems(OnExit(GameState::Help), despawn_help)
+            .add_systems(Update, close_help.run_if(in_state(GameState::Help)));
+    }
+}
+
+fn open_help(keyboard_input: Res<ButtonInput<KeyCode>>, keys: Res<Keybindings>, mut next_state: ResMut<NextState<GameState>>) {
+    if keys.just_pressed(&keyboard_input, Action::Help) {
+        next_state.set(GameState::Help);
+    }
+}
+
+fn close_help(keyboard_input: Res<ButtonInput<KeyCode>>, keys: Res<Keybindings>, mut next_state: ResMut<NextState<GameState>>) {
+    if keys.just_pressed(&keyboard_input, Action::Help) || keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Playing);
+    }
+}
+
+fn spawn_help(mut commands: Commands, asset_server: Res<AssetServer>, keys: Res<Keybindings>) {
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                padding: UiRect::all(Val::Px(24.0)),
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.02, 0.02, 0.04, 0.92)),
+            GlobalZIndex(20),
+            HelpScreen,
+        ))
+        .with_children(|screen| {
+            screen.spawn((Text::new(tr!("Keys")), text(32.0), TextColor(Color::WHITE)));
+            screen.spawn((
+                Text::new(tr!("{} or Esc to close; rebind keys in Settings", keys.label(Action::Help))),
+                text(14.0),
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+            // Entries run down a column and wrap into the next one
+            screen
+                .spawn(Node {
+                    flex_direction: FlexDirection::Column,
+                    flex_wrap: FlexWrap::Wrap,
+                    align_content: AlignContent::Center,
+                    flex_grow: 1.0,
+                    width: Val::Percent(100.0),
+                    column_gap: Val::Px(24.0),
+                    margin: UiRect::top(Val::Px(12.0)),
+                    ..default()
+                })
+                .with_children(|columns| {
+                    for (key, label) in help_entries(&keys) {
+                        columns.spawn(Node { flex_direction: FlexDirection::Row, ..default() }).with_children(|row| {
+                            row.spawn((Text::new(key), text(14.0), TextColor(KEY_COLOR), Node { width: Val::Px(KEY_WIDTH), ..default() }));
+                            row.spawn((Text::new(label), text(14.0), TextColor(Color::WHITE)));
+                        });
+                    }
+                });
+        });
+}
+
+fn despawn_help(mut commands: Commands, screens: Query<Entity, With<HelpScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_action_is_listed_with_its_current_key() {
+        let mut keys = Keybindings::default();
+        keys.set(Action::Pause, KeyCode::KeyQ);
+        let entries = help_entries(&keys);
+        assert_eq!(entries.len(), Action::ALL.len() + SHIFTED.len());
+        assert!(entries.contains(&("Q".to_string(), "pause".to_string())));
+        assert!(entries.iter().any(|(key, _)| key.starts_with("Ctrl+")));
+        assert!(entries.iter().any(|(key, label)| key == "F1" && label == "help"));
+    }
+}
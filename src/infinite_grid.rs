@@ -1,71 +1,158 @@
 use std::collections::{HashMap, HashSet};
-use std::cell::RefCell;
 use bevy::prelude::Resource;
 use crate::CellState;
 use crate::start_screen::RuleType;
 
-/// Object pool for commonly used collections to reduce allocations
-#[derive(Debug)]
-pub struct CollectionPool {
-    vec_pools: RefCell<Vec<Vec<(i32, i32)>>>,
-    hashmap_pools: RefCell<Vec<HashMap<(i32, i32), CellState>>>,
-    hashset_pools: RefCell<Vec<HashSet<(i32, i32)>>>,
+pub mod changes;
+pub mod meta;
+pub mod patterns;
+pub mod prune;
+pub mod rewind;
+pub mod run;
+
+pub use meta::CellMeta;
+pub use prune::PrunePolicy;
+pub use rewind::RewindBuffer;
+pub use run::{RunConfig, RunResult, StopReason};
+
+/// Side length of a storage chunk, in cells
+pub const CHUNK_SIZE: i32 = 64;
+const CHUNK_SHIFT: i32 = 6;
+const CHUNK_MASK: i32 = CHUNK_SIZE - 1;
+const CHUNK_AREA: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+/// Chunk plus a one-cell apron on every side, used while stepping
+const PADDED: usize = CHUNK_SIZE as usize + 2;
+
+/// A 64×64 tile of cells.
+///
+/// `occupied` packs one bit per non-dead cell (one `u64` per row) so empty rows and
+/// chunks are skipped cheaply; `states` keeps the full state byte for multi-state rules.
+#[derive(Clone, Debug)]
+struct Chunk {
+    occupied: [u64; CHUNK_SIZE as usize],
+    states: Box<[CellState; CHUNK_AREA]>,
+    population: u32,
 }
 
-impl CollectionPool {
-    pub fn new() -> Self {
+impl Chunk {
+    fn new() -> Self {
         Self {
-            vec_pools: RefCell::new(Vec::with_capacity(8)),
-            hashmap_pools: RefCell::new(Vec::with_capacity(4)),
-            hashset_pools: RefCell::new(Vec::with_capacity(4)),
+            occupied: [0; CHUNK_SIZE as usize],
+            states: Box::new([CellState::Dead; CHUNK_AREA]),
+            population: 0,
         }
     }
 
-    pub fn get_vec(&self) -> Vec<(i32, i32)> {
-        self.vec_pools.borrow_mut().pop().unwrap_or_else(|| Vec::with_capacity(1000))
+    #[inline]
+    fn get(&self, lx: i32, ly: i32) -> CellState {
+        self.states[(ly * CHUNK_SIZE + lx) as usize]
     }
 
-    pub fn return_vec(&self, mut vec: Vec<(i32, i32)>) {
-        vec.clear();
-        if vec.capacity() <= 10000 { // Don't store overly large vectors
-            self.vec_pools.borrow_mut().push(vec);
+    /// Write a state, returning the previous one
+    fn set(&mut self, lx: i32, ly: i32, state: CellState) -> CellState {
+        let idx = (ly * CHUNK_SIZE + lx) as usize;
+        let previous = self.states[idx];
+        self.states[idx] = state;
+        let bit = 1u64 << lx;
+        match (previous == CellState::Dead, state == CellState::Dead) {
+            (true, false) => {
+                self.occupied[ly as usize] |= bit;
+                self.population += 1;
+            }
+            (false, true) => {
+                self.occupied[ly as usize] &= !bit;
+                self.population -= 1;
+            }
+            _ => {}
         }
+        previous
     }
 
-    pub fn get_hashmap(&self) -> HashMap<(i32, i32), CellState> {
-        self.hashmap_pools.borrow_mut().pop().unwrap_or_else(|| HashMap::with_capacity(1000))
+    /// Iterate non-dead cells as chunk-local `(lx, ly, state)`
+    fn iter(&self) -> impl Iterator<Item = (i32, i32, CellState)> + '_ {
+        self.iter_clipped(0, CHUNK_SIZE - 1, 0, CHUNK_SIZE - 1)
     }
 
-    pub fn return_hashmap(&self, mut map: HashMap<(i32, i32), CellState>) {
-        map.clear();
-        if map.capacity() <= 10000 { // Don't store overly large maps
-            self.hashmap_pools.borrow_mut().push(map);
-        }
+    /// Iterate non-dead cells inside the inclusive local rectangle, masking whole rows at once
+    fn iter_clipped(&self, x0: i32, x1: i32, y0: i32, y1: i32) -> impl Iterator<Item = (i32, i32, CellState)> + '_ {
+        let mask = (!0u64 << x0) & (!0u64 >> (CHUNK_SIZE - 1 - x1));
+        (y0..=y1).flat_map(move |ly| {
+            BitIter(self.occupied[ly as usize] & mask).map(move |lx| (lx, ly, self.get(lx, ly)))
+        })
     }
 
-    pub fn get_hashset(&self) -> HashSet<(i32, i32)> {
-        self.hashset_pools.borrow_mut().pop().unwrap_or_else(|| HashSet::with_capacity(2000))
+    /// Local bounding box `(min_x, max_x, min_y, max_y)` of the occupied cells
+    fn local_bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        let min_y = self.occupied.iter().position(|&r| r != 0)? as i32;
+        let max_y = self.occupied.iter().rposition(|&r| r != 0)? as i32;
+        let (mut min_x, mut max_x) = (CHUNK_SIZE, 0);
+        for &row in &self.occupied[min_y as usize..=max_y as usize] {
+            if row != 0 {
+                min_x = min_x.min(row.trailing_zeros() as i32);
+                max_x = max_x.max(63 - row.leading_zeros() as i32);
+            }
+        }
+        Some((min_x, max_x, min_y, max_y))
     }
+}
+
+/// Iterates the set bit indices of a row word
+struct BitIter(u64);
 
-    pub fn return_hashset(&self, mut set: HashSet<(i32, i32)>) {
-        set.clear();
-        if set.capacity() <= 20000 { // Don't store overly large sets
-            self.hashset_pools.borrow_mut().push(set);
+impl Iterator for BitIter {
+    type Item = i32;
+    #[inline]
+    fn next(&mut self) -> Option<i32> {
+        if self.0 == 0 {
+            return None;
         }
+        let bit = self.0.trailing_zeros() as i32;
+        self.0 &= self.0 - 1;
+        Some(bit)
+    }
+}
+
+/// Split world coordinates into chunk coordinate and chunk-local offset
+#[inline]
+fn split(x: i32, y: i32) -> ((i32, i32), (i32, i32)) {
+    ((x >> CHUNK_SHIFT, y >> CHUNK_SHIFT), (x & CHUNK_MASK, y & CHUNK_MASK))
+}
+
+/// The eight neighbour states of a cell, handed to rule closures while stepping
+#[derive(Clone, Copy, Debug)]
+pub struct Neighbourhood {
+    pub states: [CellState; 8],
+}
+
+impl Neighbourhood {
+    /// Number of neighbours that count as alive (`CellState::is_alive`)
+    #[inline]
+    pub fn live(&self) -> u8 {
+        self.states.iter().filter(|s| s.is_alive()).count() as u8
+    }
+
+    /// Number of neighbours in exactly `state`
+    #[inline]
+    pub fn count(&self, state: CellState) -> u8 {
+        self.states.iter().filter(|&&s| s == state).count() as u8
     }
 }
 
-thread_local! {
-    static COLLECTION_POOL: CollectionPool = CollectionPool::new();
+/// Evaluate an outer-totalistic Life-like rule given birth/survival neighbour-count bitmasks
+#[inline]
+fn life_like(alive: bool, neighbours: u8, birth: u16, survive: u16) -> CellState {
+    let mask = if alive { survive } else { birth };
+    if mask & (1 << neighbours) != 0 { CellState::Alive } else { CellState::Dead }
 }
 
-/// Infinite sparse grid using HashMap for storage with optimized collections
-/// Only stores alive cells, treating missing cells as dead
+/// Infinite sparse grid stored as 64×64 chunks keyed by chunk coordinate.
+/// Only chunks containing at least one non-dead cell are kept.
 #[derive(Clone, Debug, Default, Resource)]
 pub struct InfiniteGrid {
-    /// Map from (x, y) coordinates to cell state
-    /// Only alive cells are stored
-    alive_cells: HashMap<(i32, i32), CellState>,
+    /// Populated chunks keyed by `(x >> 6, y >> 6)`
+    chunks: HashMap<(i32, i32), Chunk>,
+    /// Total number of non-dead cells across all chunks
+    population: usize,
     /// Cached alive cells vector to avoid recreating every frame
     cached_alive_positions: Vec<(i32, i32)>,
     /// Flag to track if cached positions are dirty
@@ -74,6 +161,38 @@ pub struct InfiniteGrid {
     bounds: Option<GridBounds>,
     /// Version counter for change detection
     version: u64,
+    /// Generations stepped since creation or the last `clear`
+    generation: u64,
+    /// Side-band metadata for every non-dead cell, when enabled
+    meta: Option<meta::MetaMap>,
+    /// Cells changed since the last `take_changes`, once a consumer has asked for them
+    changes: Option<changes::ChangeLog>,
+    /// Births and deaths in the most recent step
+    last_step: StepCounts,
+    /// How often `get_alive_cells` could hand back the cached list
+    cache_stats: CacheStats,
+}
+
+/// Lookups of a cache that found it fresh, and lookups that had to rebuild it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// Share of lookups that were hits, if there were any
+    pub fn hit_rate(&self) -> Option<f32> {
+        let total = self.hits + self.misses;
+        (total > 0).then(|| self.hits as f32 / total as f32)
+    }
+}
+
+/// Cells born and cells that died in one step
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StepCounts {
+    pub births: usize,
+    pub deaths: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -88,36 +207,59 @@ impl InfiniteGrid {
     /// Create a new empty infinite grid
     pub fn new() -> Self {
         Self {
-            alive_cells: HashMap::new(),
+            chunks: HashMap::new(),
+            population: 0,
             cached_alive_positions: Vec::new(),
             cache_dirty: false,
             bounds: None,
             version: 0,
+            generation: 0,
+            meta: None,
+            changes: None,
+            last_step: StepCounts::default(),
+            cache_stats: CacheStats::default(),
         }
     }
 
     /// Check if a cell is alive at the given coordinates
     pub fn is_alive(&self, x: i32, y: i32) -> bool {
-        if let Some(state) = self.alive_cells.get(&(x, y)) {
-            state.is_alive()
-        } else {
-            false
-        }
+        self.get(x, y).is_alive()
     }
 
     /// Set a cell's state at the given coordinates
     pub fn set(&mut self, x: i32, y: i32, state: CellState) {
+        let (key, (lx, ly)) = split(x, y);
         match state {
             CellState::Dead => {
-                self.alive_cells.remove(&(x, y));
+                if let Some(chunk) = self.chunks.get_mut(&key) {
+                    let previous = chunk.set(lx, ly, state);
+                    if chunk.population == 0 {
+                        self.chunks.remove(&key);
+                    }
+                    if previous != CellState::Dead {
+                        self.population -= 1;
+                        if let Some(meta) = &mut self.meta {
+                            meta.remove(&(x, y));
+                        }
+                        self.log_change(x, y, state);
+                    }
+                }
                 // Note: We don't update bounds when removing cells for performance
                 // Bounds may be larger than actual content, which is fine
             }
-            // All non-dead states are stored in the HashMap
-            CellState::Alive | CellState::Dying | CellState::Wire | 
-            CellState::ElectronHead | CellState::ElectronTail | 
-            CellState::SpeciesA | CellState::SpeciesB => {
-                self.alive_cells.insert((x, y), state);
+            // All non-dead states are stored in the chunk
+            _ => {
+                let chunk = self.chunks.entry(key).or_insert_with(Chunk::new);
+                let previous = chunk.set(lx, ly, state);
+                if previous == CellState::Dead {
+                    self.population += 1;
+                    if let Some(meta) = &mut self.meta {
+                        meta.insert((x, y), CellMeta { born: self.generation, ..Default::default() });
+                    }
+                }
+                if previous != state {
+                    self.log_change(x, y, state);
+                }
                 self.update_bounds(x, y);
             }
         }
@@ -125,6 +267,54 @@ impl InfiniteGrid {
         self.cache_dirty = true; // Mark cache as dirty
     }
 
+    /// Generations stepped since creation or the last `clear`
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Births and deaths in the most recent step (zero before the first)
+    pub fn last_step(&self) -> StepCounts {
+        self.last_step
+    }
+
+    /// Start tracking `CellMeta` for every non-dead cell; existing cells get a fresh record
+    pub fn enable_metadata(&mut self) {
+        if self.meta.is_none() {
+            let born = self.generation;
+            let map = self.alive_cells().map(|pos| (pos, CellMeta { born, ..Default::default() })).collect();
+            self.meta = Some(map);
+        }
+    }
+
+    /// Stop tracking metadata and drop all records
+    pub fn disable_metadata(&mut self) {
+        self.meta = None;
+    }
+
+    pub fn metadata_enabled(&self) -> bool {
+        self.meta.is_some()
+    }
+
+    /// Metadata of a non-dead cell, if tracking is enabled
+    pub fn meta(&self, x: i32, y: i32) -> Option<&CellMeta> {
+        self.meta.as_ref()?.get(&(x, y))
+    }
+
+    /// Mutable metadata of a non-dead cell, if tracking is enabled
+    pub fn meta_mut(&mut self, x: i32, y: i32) -> Option<&mut CellMeta> {
+        self.meta.as_mut()?.get_mut(&(x, y))
+    }
+
+    /// Set a cell's state together with its metadata (enables tracking if needed).
+    /// Metadata is ignored when `state` is `Dead`.
+    pub fn set_with_meta(&mut self, x: i32, y: i32, state: CellState, meta: CellMeta) {
+        self.enable_metadata();
+        self.set(x, y, state);
+        if let (Some(map), true) = (&mut self.meta, state != CellState::Dead) {
+            map.insert((x, y), meta);
+        }
+    }
+
     /// Get the current version (for change detection)
     pub fn version(&self) -> u64 {
         self.version
@@ -132,42 +322,78 @@ impl InfiniteGrid {
 
     /// Count total alive cells
     pub fn live_cell_count(&self) -> usize {
-        self.alive_cells.len()
+        self.population
+    }
+
+    /// Iterate all alive cell positions
+    pub fn alive_cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.cells().map(|(x, y, _)| (x, y))
+    }
+
+    /// Iterate all non-dead cells together with their state
+    pub fn cells(&self) -> impl Iterator<Item = (i32, i32, CellState)> + '_ {
+        self.chunks.iter().flat_map(|(&(cx, cy), chunk)| {
+            let (ox, oy) = (cx * CHUNK_SIZE, cy * CHUNK_SIZE);
+            chunk.iter().map(move |(lx, ly, state)| (ox + lx, oy + ly, state))
+        })
+    }
+
+    /// Number of populated storage chunks
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
     }
 
-    /// Get all alive cell positions (cached version to avoid allocations)
-    pub fn alive_cells(&self) -> impl Iterator<Item = &(i32, i32)> {
-        self.alive_cells.keys()
+    /// Rough heap size in bytes: the chunks, the cell metadata and the cached cell list,
+    /// counting map entries at their payload size
+    pub fn memory_bytes(&self) -> usize {
+        use std::mem::size_of;
+        let chunk = size_of::<((i32, i32), Chunk)>() + CHUNK_AREA * size_of::<CellState>();
+        let meta = self.meta.as_ref().map_or(0, |meta| meta.capacity() * size_of::<((i32, i32), meta::CellMeta)>());
+        self.chunks.capacity() * chunk + meta + self.cached_alive_positions.capacity() * size_of::<(i32, i32)>()
+    }
+
+    /// Hits and misses of the alive cell list cache since the grid was made
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache_stats
     }
 
     /// Get a reference to all alive cell positions (cached for performance)
     pub fn get_alive_cells(&mut self) -> &Vec<(i32, i32)> {
         if self.cache_dirty {
-            self.cached_alive_positions.clear();
-            self.cached_alive_positions.extend(self.alive_cells.keys().copied());
+            self.cache_stats.misses += 1;
+            let mut positions = std::mem::take(&mut self.cached_alive_positions);
+            positions.clear();
+            positions.extend(self.alive_cells());
+            self.cached_alive_positions = positions;
             self.cache_dirty = false;
+        } else {
+            self.cache_stats.hits += 1;
         }
         &self.cached_alive_positions
     }
 
     /// Get alive cells count without requiring mutable access (for read-only operations)
     pub fn alive_cells_count(&self) -> usize {
-        self.alive_cells.len()
+        self.population
     }
 
     /// Get a snapshot of alive cell positions without requiring mutable access (for read-only operations)
     pub fn get_alive_cells_snapshot(&self) -> Vec<(i32, i32)> {
-        self.alive_cells.keys().copied().collect()
+        self.alive_cells().collect()
     }
 
     /// Get cell state at coordinates
     pub fn get(&self, x: i32, y: i32) -> CellState {
-        self.alive_cells.get(&(x, y)).cloned().unwrap_or(CellState::Dead)
+        let (key, (lx, ly)) = split(x, y);
+        self.chunks
+            .get(&key)
+            .map(|chunk| chunk.get(lx, ly))
+            .unwrap_or(CellState::Dead)
     }
 
     /// Get population (number of alive cells)
     pub fn population(&self) -> usize {
-        self.alive_cells.len()
+        self.population
     }
 
     /// Count neighbors for a cell (needed by audio system)
@@ -198,6 +424,7 @@ impl InfiniteGrid {
             RuleType::Coral => self.step_coral(),
             RuleType::Gnarl => self.step_gnarl(),
             RuleType::Replicator => self.step_replicator(),
+            RuleType::Custom { birth, survive } => self.step_life(birth, survive),
         }
     }
 
@@ -206,13 +433,31 @@ impl InfiniteGrid {
         self.bounds.as_ref()
     }
 
+    /// Mean position of the non-dead cells (None if empty)
+    pub fn centroid(&self) -> Option<(f64, f64)> {
+        let (mut sum_x, mut sum_y, mut count) = (0.0, 0.0, 0u64);
+        for (x, y, _) in self.cells() {
+            sum_x += x as f64;
+            sum_y += y as f64;
+            count += 1;
+        }
+        (count > 0).then(|| (sum_x / count as f64, sum_y / count as f64))
+    }
+
     /// Clear all cells
     pub fn clear(&mut self) {
-        self.alive_cells.clear();
+        self.chunks.clear();
+        self.population = 0;
         self.cached_alive_positions.clear();
         self.cache_dirty = false;
         self.bounds = None;
         self.version += 1;
+        self.generation = 0;
+        self.last_step = StepCounts::default();
+        if let Some(meta) = &mut self.meta {
+            meta.clear();
+        }
+        self.invalidate_changes();
     }
 
     /// Update the cached bounds when adding a cell
@@ -251,147 +496,174 @@ impl InfiniteGrid {
         count
     }
 
-    /// Advance the grid by one generation using Conway's rules
-    /// For now, we'll hardcode Conway's rule and extend later
-    pub fn step_conway(&mut self) {
-        COLLECTION_POOL.with(|pool| {
-            let mut new_alive_cells = pool.get_hashmap();
-            let mut candidates = pool.get_hashset();
-
-            // Add all currently alive cells and their neighbors as candidates
-            for &(x, y) in self.alive_cells.keys() {
-                for dy in -1..=1 {
-                    for dx in -1..=1 {
-                        candidates.insert((x + dx, y + dy));
+    /// Copy chunk `key` and a one-cell apron from its eight neighbours into `padded`.
+    /// `row_occupied[r]` is set when padded row `r` holds any non-dead cell.
+    /// Returns `false` when the whole window is empty.
+    fn fill_padded(
+        &self,
+        key: (i32, i32),
+        padded: &mut [CellState],
+        row_occupied: &mut [bool; PADDED],
+    ) -> bool {
+        padded.fill(CellState::Dead);
+        row_occupied.fill(false);
+        let mut any = false;
+        let last = CHUNK_SIZE - 1;
+        for ny in -1..=1 {
+            for nx in -1..=1 {
+                let Some(chunk) = self.chunks.get(&(key.0 + nx, key.1 + ny)) else { continue };
+                // Source ranges inside the neighbour that land in our padded window
+                let xs = match nx { -1 => last..=last, 0 => 0..=last, _ => 0..=0 };
+                let ys = match ny { -1 => last..=last, 0 => 0..=last, _ => 0..=0 };
+                for sy in ys {
+                    let row = chunk.occupied[sy as usize];
+                    if row == 0 {
+                        continue;
+                    }
+                    let py = (sy + 1 + ny * CHUNK_SIZE) as usize;
+                    for sx in xs.clone() {
+                        if row & (1u64 << sx) == 0 {
+                            continue;
+                        }
+                        let px = (sx + 1 + nx * CHUNK_SIZE) as usize;
+                        padded[py * PADDED + px] = chunk.get(sx, sy);
+                        row_occupied[py] = true;
+                        any = true;
                     }
                 }
             }
-
-            // Evaluate each candidate cell using Conway's rules
-            for &(x, y) in &candidates {
-                let alive = self.is_alive(x, y);
-                let neighbors = self.live_neighbors(x, y);
-                
-                let new_state = match (alive, neighbors) {
-                    (true, 2) | (true, 3) => CellState::Alive,
-                    (false, 3) => CellState::Alive,
-                    _ => CellState::Dead,
-                };
-                
-                if new_state == CellState::Alive {
-                    new_alive_cells.insert((x, y), new_state);
-                }
-            }
-
-            // Replace the alive cells with the new generation
-            self.alive_cells = new_alive_cells;
-            self.recalculate_bounds();
-            self.version += 1;
-            self.cache_dirty = true;
-
-            // Return collections to pool
-            pool.return_hashset(candidates);
-            // new_alive_cells is consumed by self.alive_cells, so no need to return it
-        });
+        }
+        any
     }
 
-    /// Advance the grid by one generation using HighLife rules
-    pub fn step_highlife(&mut self) {
-        let mut new_alive_cells = HashMap::new();
-        let mut candidates = std::collections::HashSet::new();
-
-        // Add all currently alive cells and their neighbors as candidates
-        for &(x, y) in self.alive_cells.keys() {
+    /// Advance one generation, evaluating `rule(x, y, state, neighbourhood)` for every cell
+    /// in each populated chunk and the apron of its neighbours. Rows whose 3×N window is
+    /// empty are skipped, so rules must map an empty neighbourhood of a dead cell to `Dead`.
+    pub fn step_with<F>(&mut self, rule: F)
+    where
+        F: Fn(i32, i32, CellState, &Neighbourhood) -> CellState,
+    {
+        let mut candidates = HashSet::with_capacity(self.chunks.len() * 4);
+        for &(cx, cy) in self.chunks.keys() {
             for dy in -1..=1 {
                 for dx in -1..=1 {
-                    candidates.insert((x + dx, y + dy));
+                    candidates.insert((cx + dx, cy + dy));
                 }
             }
         }
 
-        // Evaluate each candidate cell using HighLife rules
-        for &(x, y) in &candidates {
-            let alive = self.is_alive(x, y);
-            let neighbors = self.live_neighbors(x, y);
-            
-            let new_state = match (alive, neighbors) {
-                (true, 2) | (true, 3) => CellState::Alive,
-                (false, 3) | (false, 6) => CellState::Alive,
-                _ => CellState::Dead,
-            };
-            
-            if new_state == CellState::Alive {
-                new_alive_cells.insert((x, y), CellState::Alive);
-            }
-        }
-
-        // Update the grid
-        self.alive_cells = new_alive_cells;
-        self.recalculate_bounds();
-        self.version += 1;
-    }
-
-    /// Advance the grid by one generation using Seeds rules
-    pub fn step_seeds(&mut self) {
-        let mut new_alive_cells = HashMap::new();
-        let mut candidates = std::collections::HashSet::new();
+        let mut next_chunks = HashMap::with_capacity(self.chunks.len());
+        let mut population = 0usize;
+        let mut padded = vec![CellState::Dead; PADDED * PADDED];
+        let mut row_occupied = [false; PADDED];
+        let logging = self.logging_changes();
+        let mut changed = Vec::new();
+        let mut counts = StepCounts::default();
 
-        // Add all currently alive cells and their neighbors as candidates
-        for &(x, y) in self.alive_cells.keys() {
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    candidates.insert((x + dx, y + dy));
+        for key in candidates {
+            if !self.fill_padded(key, &mut padded, &mut row_occupied) {
+                continue;
+            }
+            let (ox, oy) = (key.0 * CHUNK_SIZE, key.1 * CHUNK_SIZE);
+            let mut chunk = Chunk::new();
+            for ly in 0..CHUNK_SIZE as usize {
+                let py = ly + 1;
+                if !(row_occupied[py - 1] || row_occupied[py] || row_occupied[py + 1]) {
+                    continue;
+                }
+                for lx in 0..CHUNK_SIZE as usize {
+                    let px = lx + 1;
+                    let up = (py - 1) * PADDED;
+                    let mid = py * PADDED;
+                    let down = (py + 1) * PADDED;
+                    let neighbourhood = Neighbourhood {
+                        states: [
+                            padded[up + px - 1], padded[up + px], padded[up + px + 1],
+                            padded[mid + px - 1], padded[mid + px + 1],
+                            padded[down + px - 1], padded[down + px], padded[down + px + 1],
+                        ],
+                    };
+                    let (x, y) = (ox + lx as i32, oy + ly as i32);
+                    let previous = padded[mid + px];
+                    let state = rule(x, y, previous, &neighbourhood);
+                    if state != previous {
+                        match (previous, state) {
+                            (CellState::Dead, _) => counts.births += 1,
+                            (_, CellState::Dead) => counts.deaths += 1,
+                            _ => {}
+                        }
+                        if logging {
+                            changed.push((x, y, state));
+                        }
+                    }
+                    if state != CellState::Dead {
+                        chunk.set(lx as i32, ly as i32, state);
+                    }
                 }
             }
-        }
-
-        // Evaluate each candidate cell using Seeds rules (B2/S0)
-        for &(x, y) in &candidates {
-            let alive = self.is_alive(x, y);
-            let neighbors = self.live_neighbors(x, y);
-            
-            let new_state = if !alive && neighbors == 2 {
-                CellState::Alive
-            } else {
-                CellState::Dead
-            };
-            
-            if new_state == CellState::Alive {
-                new_alive_cells.insert((x, y), CellState::Alive);
+            if chunk.population > 0 {
+                population += chunk.population as usize;
+                next_chunks.insert(key, chunk);
             }
         }
 
-        // Update the grid
-        self.alive_cells = new_alive_cells;
+        self.chunks = next_chunks;
+        self.population = population;
+        self.generation += 1;
+        self.last_step = counts;
+        for (x, y, state) in changed {
+            self.log_change(x, y, state);
+        }
+        if let Some(previous) = self.meta.take() {
+            // Survivors keep their record, newborns inherit from last generation's neighbours
+            let generation = self.generation;
+            let next = self
+                .alive_cells()
+                .map(|(x, y)| {
+                    let record = previous.get(&(x, y)).copied().unwrap_or_else(|| meta::inherit(&previous, x, y, generation));
+                    ((x, y), record)
+                })
+                .collect();
+            self.meta = Some(next);
+        }
         self.recalculate_bounds();
         self.version += 1;
+        self.cache_dirty = true;
     }
 
-    /// Recalculate bounds from scratch (used after step)
-    fn recalculate_bounds(&mut self) {
-        if self.alive_cells.is_empty() {
-            self.bounds = None;
-            return;
-        }
+    /// Advance the grid by one generation using Conway's rules (B3/S23)
+    pub fn step_conway(&mut self) {
+        self.step_with(|_, _, s, n| life_like(s.is_alive(), n.live(), 1 << 3, 1 << 2 | 1 << 3));
+    }
 
-        let mut min_x = i32::MAX;
-        let mut max_x = i32::MIN;
-        let mut min_y = i32::MAX;
-        let mut max_y = i32::MIN;
+    /// Advance the grid by one generation using HighLife rules (B36/S23)
+    pub fn step_highlife(&mut self) {
+        self.step_with(|_, _, s, n| life_like(s.is_alive(), n.live(), 1 << 3 | 1 << 6, 1 << 2 | 1 << 3));
+    }
 
-        for &(x, y) in self.alive_cells.keys() {
-            min_x = min_x.min(x);
-            max_x = max_x.max(x);
-            min_y = min_y.min(y);
-            max_y = max_y.max(y);
-        }
+    /// Advance the grid by one generation using Seeds rules (B2/S0)
+    pub fn step_seeds(&mut self) {
+        self.step_with(|_, _, s, n| life_like(s.is_alive(), n.live(), 1 << 2, 0));
+    }
 
-        self.bounds = Some(GridBounds {
-            min_x,
-            max_x,
-            min_y,
-            max_y,
-        });
+    /// Recalculate bounds from scratch (used after step)
+    fn recalculate_bounds(&mut self) {
+        let mut bounds: Option<GridBounds> = None;
+        for (&(cx, cy), chunk) in &self.chunks {
+            let Some((min_x, max_x, min_y, max_y)) = chunk.local_bounds() else { continue };
+            let (ox, oy) = (cx * CHUNK_SIZE, cy * CHUNK_SIZE);
+            let b = bounds.get_or_insert(GridBounds {
+                min_x: i32::MAX,
+                max_x: i32::MIN,
+                min_y: i32::MAX,
+                max_y: i32::MIN,
+            });
+            b.min_x = b.min_x.min(ox + min_x);
+            b.max_x = b.max_x.max(ox + max_x);
+            b.min_y = b.min_y.min(oy + min_y);
+            b.max_y = b.max_y.max(oy + max_y);
+        }
+        self.bounds = bounds;
     }
 
     /// Insert a pattern at the given offset
@@ -404,127 +676,109 @@ impl InfiniteGrid {
         }
     }
 
+    /// Insert cells with explicit states (multi-state patterns) at the given offset
+    pub fn insert_cells<I>(&mut self, cells: I, offset_x: i32, offset_y: i32)
+    where
+        I: Iterator<Item = (i32, i32, CellState)>,
+    {
+        for (x, y, state) in cells {
+            self.set(offset_x + x, offset_y + y, state);
+        }
+    }
+
     /// Get cells in a specific region (for rendering)
-    pub fn cells_in_region(&self, min_x: i32, max_x: i32, min_y: i32, max_y: i32) -> impl Iterator<Item = &(i32, i32)> {
-        self.alive_cells.keys().filter(move |&&(x, y)| {
-            x >= min_x && x <= max_x && y >= min_y && y <= max_y
+    pub fn cells_in_region(&self, min_x: i32, max_x: i32, min_y: i32, max_y: i32) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.cell_states_in_region(min_x, max_x, min_y, max_y).map(|(x, y, _)| (x, y))
+    }
+
+    /// Iterate non-dead cells (with state) inside the inclusive region.
+    /// Whole chunks outside the region are skipped, and rows are clipped with a bit mask,
+    /// so the cost scales with the visible area rather than the total population.
+    pub fn cell_states_in_region(&self, min_x: i32, max_x: i32, min_y: i32, max_y: i32) -> impl Iterator<Item = (i32, i32, CellState)> + '_ {
+        let ((cmin_x, cmin_y), _) = split(min_x, min_y);
+        let ((cmax_x, cmax_y), _) = split(max_x, max_y);
+        let keys: Vec<(i32, i32)> = if min_x > max_x || min_y > max_y {
+            Vec::new()
+        } else {
+            let span = (cmax_x - cmin_x + 1) as i64 * (cmax_y - cmin_y + 1) as i64;
+            if span <= self.chunks.len() as i64 {
+                // Small view: probe each chunk coordinate it covers
+                (cmin_y..=cmax_y)
+                    .flat_map(|cy| (cmin_x..=cmax_x).map(move |cx| (cx, cy)))
+                    .filter(|key| self.chunks.contains_key(key))
+                    .collect()
+            } else {
+                // Large view: scan populated chunks and keep the overlapping ones
+                self.chunks
+                    .keys()
+                    .copied()
+                    .filter(|&(cx, cy)| cx >= cmin_x && cx <= cmax_x && cy >= cmin_y && cy <= cmax_y)
+                    .collect()
+            }
+        };
+
+        keys.into_iter().flat_map(move |key| {
+            let chunk = &self.chunks[&key];
+            let (ox, oy) = (key.0 * CHUNK_SIZE, key.1 * CHUNK_SIZE);
+            chunk
+                .iter_clipped(
+                    (min_x - ox).max(0),
+                    (max_x - ox).min(CHUNK_SIZE - 1),
+                    (min_y - oy).max(0),
+                    (max_y - oy).min(CHUNK_SIZE - 1),
+                )
+                .map(move |(lx, ly, state)| (ox + lx, oy + ly, state))
         })
     }
+
     /// Brian's Brain rule - 3-state automaton
     /// States: Dead, Alive (firing), Dying (refractory)
     pub fn step_brian_brain(&mut self) {
-        let mut new_alive_cells = HashMap::new();
-        let mut candidates = std::collections::HashSet::new();
-
-        // Add all cells and their neighbors as candidates
-        for &(x, y) in self.alive_cells.keys() {
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    candidates.insert((x + dx, y + dy));
-                }
-            }
-        }
-
-        for &(x, y) in &candidates {
-            let current_state = self.get(x, y);
-            let firing_neighbors = self.count_firing_neighbors(x, y);
-            
-            let new_state = match current_state {
-                CellState::Dead => {
-                    if firing_neighbors == 2 {
-                        CellState::Alive // Become firing
-                    } else {
-                        CellState::Dead
-                    }
+        self.step_with(|_, _, current_state, n| match current_state {
+            CellState::Dead => {
+                if n.count(CellState::Alive) == 2 {
+                    CellState::Alive // Become firing
+                } else {
+                    CellState::Dead
                 }
-                CellState::Alive => CellState::Dying, // Firing → Refractory
-                CellState::Dying => CellState::Dead,  // Refractory → Dead
-                _ => CellState::Dead,
-            };
-            
-            if new_state != CellState::Dead {
-                new_alive_cells.insert((x, y), new_state);
             }
-        }
-
-        self.alive_cells = new_alive_cells;
-        self.recalculate_bounds();
-        self.version += 1;
+            CellState::Alive => CellState::Dying, // Firing → Refractory
+            CellState::Dying => CellState::Dead,  // Refractory → Dead
+            _ => CellState::Dead,
+        });
     }
 
     /// WireWorld rule - 4-state digital circuit simulation
     /// States: Empty, Wire, Electron Head, Electron Tail
     pub fn step_wireworld(&mut self) {
-        let mut new_alive_cells = HashMap::new();
-        let mut candidates = std::collections::HashSet::new();
-
-        // Add all cells and their neighbors as candidates
-        for &(x, y) in self.alive_cells.keys() {
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    candidates.insert((x + dx, y + dy));
-                }
-            }
-        }
-
-        for &(x, y) in &candidates {
-            let current_state = self.get(x, y);
-            
-            let new_state = match current_state {
-                CellState::Dead => CellState::Dead,
-                CellState::Wire => {
-                    let electron_heads = self.count_electron_heads(x, y);
-                    if electron_heads == 1 || electron_heads == 2 {
-                        CellState::ElectronHead
-                    } else {
-                        CellState::Wire
-                    }
+        self.step_with(|_, _, current_state, n| match current_state {
+            CellState::Dead => CellState::Dead,
+            CellState::Wire => {
+                let electron_heads = n.count(CellState::ElectronHead);
+                if electron_heads == 1 || electron_heads == 2 {
+                    CellState::ElectronHead
+                } else {
+                    CellState::Wire
                 }
-                CellState::ElectronHead => CellState::ElectronTail,
-                CellState::ElectronTail => CellState::Wire,
-                _ => CellState::Dead,
-            };
-            
-            if new_state != CellState::Dead {
-                new_alive_cells.insert((x, y), new_state);
             }
-        }
-
-        self.alive_cells = new_alive_cells;
-        self.recalculate_bounds();
-        self.version += 1;
+            CellState::ElectronHead => CellState::ElectronTail,
+            CellState::ElectronTail => CellState::Wire,
+            _ => CellState::Dead,
+        });
     }
 
     /// Immigration rule - Conway with 2 competing species
     /// B3/S23 but species can only give birth to their own kind
     pub fn step_immigration(&mut self) {
-        let mut new_alive_cells = HashMap::new();
-        let mut candidates = std::collections::HashSet::new();
-
-        for &(x, y) in self.alive_cells.keys() {
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    candidates.insert((x + dx, y + dy));
-                }
-            }
-        }
-
-        for &(x, y) in &candidates {
-            let current_state = self.get(x, y);
-            let (species_a_neighbors, species_b_neighbors) = self.count_species_neighbors(x, y);
+        self.step_with(|x, y, current_state, n| {
+            let species_a_neighbors = n.count(CellState::SpeciesA);
+            let species_b_neighbors = n.count(CellState::SpeciesB);
             let total_neighbors = species_a_neighbors + species_b_neighbors;
-            
-            let new_state = match current_state {
-                CellState::SpeciesA => {
-                    if total_neighbors == 2 || total_neighbors == 3 {
-                        CellState::SpeciesA
-                    } else {
-                        CellState::Dead
-                    }
-                }
-                CellState::SpeciesB => {
+
+            match current_state {
+                CellState::SpeciesA | CellState::SpeciesB => {
                     if total_neighbors == 2 || total_neighbors == 3 {
-                        CellState::SpeciesB
+                        current_state
                     } else {
                         CellState::Dead
                     }
@@ -545,256 +799,113 @@ impl InfiniteGrid {
                     }
                 }
                 _ => current_state, // Keep other states as-is
-            };
-            
-            if new_state != CellState::Dead {
-                new_alive_cells.insert((x, y), new_state);
             }
-        }
-
-        self.alive_cells = new_alive_cells;
-        self.recalculate_bounds();
-        self.version += 1;
+        });
     }
 
     /// Mazectric rule - B3/S1234 - Creates intricate maze patterns
     pub fn step_mazectric(&mut self) {
-        let mut new_alive_cells = HashMap::new();
-        let mut candidates = std::collections::HashSet::new();
-
-        for &(x, y) in self.alive_cells.keys() {
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    candidates.insert((x + dx, y + dy));
-                }
-            }
-        }
-
-        for &(x, y) in &candidates {
-            let alive = self.is_alive(x, y);
-            let neighbors = self.live_neighbors(x, y);
-            
-            let new_state = match (alive, neighbors) {
-                (true, 1) | (true, 2) | (true, 3) | (true, 4) => CellState::Alive,
-                (false, 3) => CellState::Alive,
-                _ => CellState::Dead,
-            };
-            
-            if new_state == CellState::Alive {
-                new_alive_cells.insert((x, y), CellState::Alive);
-            }
-        }
-
-        self.alive_cells = new_alive_cells;
-        self.recalculate_bounds();
-        self.version += 1;
+        self.step_with(|_, _, s, n| life_like(s.is_alive(), n.live(), 1 << 3, 0b1_1110));
     }
 
     /// Coral rule - B3/S45678 - Coral-like growth structures
     pub fn step_coral(&mut self) {
-        let mut new_alive_cells = HashMap::new();
-        let mut candidates = std::collections::HashSet::new();
-
-        for &(x, y) in self.alive_cells.keys() {
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    candidates.insert((x + dx, y + dy));
-                }
-            }
-        }
-
-        for &(x, y) in &candidates {
-            let alive = self.is_alive(x, y);
-            let neighbors = self.live_neighbors(x, y);
-            
-            let new_state = match (alive, neighbors) {
-                (true, 4) | (true, 5) | (true, 6) | (true, 7) | (true, 8) => CellState::Alive,
-                (false, 3) => CellState::Alive,
-                _ => CellState::Dead,
-            };
-            
-            if new_state == CellState::Alive {
-                new_alive_cells.insert((x, y), CellState::Alive);
-            }
-        }
-
-        self.alive_cells = new_alive_cells;
-        self.recalculate_bounds();
-        self.version += 1;
+        self.step_with(|_, _, s, n| life_like(s.is_alive(), n.live(), 1 << 3, 0b1_1111_0000));
     }
 
     /// Gnarl rule - B1/S1 - Chaotic explosive growth
     pub fn step_gnarl(&mut self) {
-        let mut new_alive_cells = HashMap::new();
-        let mut candidates = std::collections::HashSet::new();
-
-        for &(x, y) in self.alive_cells.keys() {
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    candidates.insert((x + dx, y + dy));
-                }
-            }
-        }
-
-        for &(x, y) in &candidates {
-            let alive = self.is_alive(x, y);
-            let neighbors = self.live_neighbors(x, y);
-            
-            let new_state = match (alive, neighbors) {
-                (true, 1) => CellState::Alive,
-                (false, 1) => CellState::Alive,
-                _ => CellState::Dead,
-            };
-            
-            if new_state == CellState::Alive {
-                new_alive_cells.insert((x, y), CellState::Alive);
-            }
-        }
-
-        self.alive_cells = new_alive_cells;
-        self.recalculate_bounds();
-        self.version += 1;
+        self.step_with(|_, _, s, n| life_like(s.is_alive(), n.live(), 1 << 1, 1 << 1));
     }
 
     /// Replicator rule - B1357/S1357 - Perfect self-replication
     pub fn step_replicator(&mut self) {
-        let mut new_alive_cells = HashMap::new();
-        let mut candidates = std::collections::HashSet::new();
-
-        for &(x, y) in self.alive_cells.keys() {
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    candidates.insert((x + dx, y + dy));
-                }
-            }
-        }
-
-        for &(x, y) in &candidates {
-            let alive = self.is_alive(x, y);
-            let neighbors = self.live_neighbors(x, y);
-            
-            let new_state = match (alive, neighbors) {
-                (true, 1) | (true, 3) | (true, 5) | (true, 7) => CellState::Alive,
-                (false, 1) | (false, 3) | (false, 5) | (false, 7) => CellState::Alive,
-                _ => CellState::Dead,
-            };
-            
-            if new_state == CellState::Alive {
-                new_alive_cells.insert((x, y), CellState::Alive);
-            }
-        }
-
-        self.alive_cells = new_alive_cells;
-        self.recalculate_bounds();
-        self.version += 1;
+        self.step_with(|_, _, s, n| life_like(s.is_alive(), n.live(), 0b1010_1010, 0b1010_1010));
     }
 
-    /// Helper function to count firing neighbors for Brian's Brain
-    fn count_firing_neighbors(&self, x: i32, y: i32) -> u8 {
-        let mut count = 0;
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                if self.get(x + dx, y + dy) == CellState::Alive {
-                    count += 1;
-                }
-            }
-        }
-        count
+    /// Any Life-like rule, given as masks of the neighbour counts that give birth and
+    /// survival. Birth on 0 is ignored: `step_with` never looks at empty neighbourhoods.
+    pub fn step_life(&mut self, birth: u16, survive: u16) {
+        let birth = birth & !1;
+        self.step_with(|_, _, s, n| life_like(s.is_alive(), n.live(), birth, survive));
     }
+}
 
-    /// Helper function to count electron heads for WireWorld
-    fn count_electron_heads(&self, x: i32, y: i32) -> u8 {
-        let mut count = 0;
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                if self.get(x + dx, y + dy) == CellState::ElectronHead {
-                    count += 1;
-                }
-            }
-        }
-        count
-    }
 
-    /// Helper function to count species neighbors for Immigration
-    fn count_species_neighbors(&self, x: i32, y: i32) -> (u8, u8) {
-        let mut species_a = 0;
-        let mut species_b = 0;
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                if dx == 0 && dy == 0 {
-                    continue;
-                }
-                match self.get(x + dx, y + dy) {
-                    CellState::SpeciesA => species_a += 1,
-                    CellState::SpeciesB => species_b += 1,
-                    _ => {}
-                }
-            }
-        }
-        (species_a, species_b)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glider_crosses_chunk_boundaries() {
+        let mut grid = InfiniteGrid::new();
+        // Start just left of the origin so the glider walks down-right from chunk (-1,0) into (0,-1)
+        grid.insert_pattern(patterns::glider(), -3, 0);
+        let before: HashSet<_> = grid.alive_cells().collect();
+
+        for _ in 0..8 {
+            grid.step_conway();
+        }
+
+        let after: HashSet<_> = grid.alive_cells().collect();
+        let shifted: HashSet<_> = before.iter().map(|&(x, y)| (x + 2, y - 2)).collect();
+        assert_eq!(after, shifted);
+        assert_eq!(grid.population(), 5);
+    }
+
+    #[test]
+    fn centroid_is_the_mean_live_cell() {
+        let mut grid = InfiniteGrid::new();
+        assert_eq!(grid.centroid(), None);
+        grid.set(-4, 0, CellState::Alive);
+        grid.set(2, 3, CellState::Alive);
+        grid.set(5, 3, CellState::Wire);
+        assert_eq!(grid.centroid(), Some((1.0, 2.0)));
+    }
+
+    #[test]
+    fn region_iteration_matches_filtering() {
+        let mut grid = InfiniteGrid::new();
+        for i in -200..200 {
+            grid.set(i, (i * 7) % 150, CellState::Alive);
+        }
+        let (min_x, max_x, min_y, max_y) = (-70, 65, -10, 90);
+        let mut expected: Vec<_> = grid
+            .alive_cells()
+            .filter(|&(x, y)| x >= min_x && x <= max_x && y >= min_y && y <= max_y)
+            .collect();
+        let mut actual: Vec<_> = grid.cells_in_region(min_x, max_x, min_y, max_y).collect();
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn set_and_clear_keep_population_in_sync() {
+        let mut grid = InfiniteGrid::new();
+        grid.set(63, 63, CellState::Wire);
+        grid.set(64, 64, CellState::Alive);
+        grid.set(64, 64, CellState::SpeciesA);
+        assert_eq!(grid.population(), 2);
+        assert_eq!(grid.chunk_count(), 2);
+        assert_eq!(grid.get(64, 64), CellState::SpeciesA);
+
+        grid.set(63, 63, CellState::Dead);
+        assert_eq!(grid.population(), 1);
+        assert_eq!(grid.chunk_count(), 1);
+    }
+
+    #[test]
+    fn alive_cell_cache_counts_hits_and_misses() {
+        let mut grid = InfiniteGrid::new();
+        assert_eq!(grid.cache_stats().hit_rate(), None);
+        grid.set(0, 0, CellState::Alive);
+        grid.get_alive_cells();
+        grid.get_alive_cells();
+        grid.get_alive_cells();
+        grid.set(1, 0, CellState::Alive);
+        assert_eq!(grid.get_alive_cells().len(), 2);
+        assert_eq!(grid.cache_stats(), CacheStats { hits: 2, misses: 2 });
+        assert_eq!(grid.cache_stats().hit_rate(), Some(0.5));
     }
 }
-
-// Rules are now implemented directly in the step methods
-
-/// Some common patterns for testing
-pub mod patterns {
-    use crate::CellState;
-
-    /// Create a glider pattern
-    pub fn glider() -> impl Iterator<Item = (i32, i32)> {
-        vec![
-            (1, 0),
-            (2, 1),
-            (0, 2),
-            (1, 2),
-            (2, 2),
-        ].into_iter()
-    }
-
-    /// Create a blinker pattern
-    pub fn blinker() -> impl Iterator<Item = (i32, i32)> {
-        vec![
-            (0, 0),
-            (1, 0),
-            (2, 0),
-        ].into_iter()
-    }
-
-    /// Create a block pattern (still life)
-    pub fn block() -> impl Iterator<Item = (i32, i32)> {
-        vec![
-            (0, 0),
-            (1, 0),
-            (0, 1),
-            (1, 1),
-        ].into_iter()
-    }
-
-    /// HighLife Replicator seed (9-cell)
-    pub fn highlife_replicator() -> impl Iterator<Item = (i32, i32)> {
-        vec![
-            (0,0),(1,0),(2,0),
-            (0,1),(2,1),
-            (0,2),(1,2),(2,2),
-            (1,3),
-        ].into_iter()
-    }
-
-    /// WireWorld simple clock (wire + initial electron head)
-    pub fn wire_clock() -> impl Iterator<Item = (i32, i32, CellState)> {
-        // Returns tuples with state; we convert outside
-        vec![
-            // Wire line
-            (-2,0, CellState::Wire),(-1,0, CellState::Wire),(0,0, CellState::Wire),(1,0,CellState::Wire),(2,0,CellState::Wire),
-            // Electron head at left end
-            (-2,0, CellState::ElectronHead),
-        ].into_iter()
-    }
-} 
\ No newline at end of file
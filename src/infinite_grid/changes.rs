@@ -0,0 +1,103 @@
+//! Birth/death deltas.
+//!
+//! Once a consumer calls `InfiniteGrid::take_changes`, the grid records every cell whose
+//! state changes, whether painted with `set` or produced by a step, until the next call.
+//! Changes the log can't describe cell by cell (`clear`, `prune`, more than
+//! `MAX_LOGGED_CHANGES` cells) invalidate it instead, and the next `take_changes` returns
+//! `None` so the consumer rescans. A grid that replaces another (a loaded session, say)
+//! starts without a log, which has the same effect.
+
+use crate::CellState;
+use super::InfiniteGrid;
+
+/// Changes kept before the log gives up and asks for a rescan
+pub const MAX_LOGGED_CHANGES: usize = 250_000;
+
+/// Cells changed since the last `take_changes`, with their new state
+#[derive(Clone, Debug, Default)]
+pub struct ChangeLog {
+    cells: Vec<(i32, i32, CellState)>,
+    valid: bool,
+}
+
+impl ChangeLog {
+    fn record(&mut self, x: i32, y: i32, state: CellState) {
+        if !self.valid {
+            return;
+        }
+        if self.cells.len() >= MAX_LOGGED_CHANGES {
+            self.invalidate();
+        } else {
+            self.cells.push((x, y, state));
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.valid = false;
+        self.cells = Vec::new();
+    }
+}
+
+impl InfiniteGrid {
+    /// Cells changed since the previous call, with their new state, in the order they
+    /// changed (a cell may appear more than once; the last entry wins). `None` on the first
+    /// call and whenever the changes couldn't be logged: rescan the grid instead.
+    pub fn take_changes(&mut self) -> Option<Vec<(i32, i32, CellState)>> {
+        let previous = self.changes.replace(ChangeLog { cells: Vec::new(), valid: true })?;
+        previous.valid.then_some(previous.cells)
+    }
+
+    pub(super) fn log_change(&mut self, x: i32, y: i32, state: CellState) {
+        if let Some(log) = &mut self.changes {
+            log.record(x, y, state);
+        }
+    }
+
+    pub(super) fn logging_changes(&self) -> bool {
+        self.changes.as_ref().is_some_and(|log| log.valid)
+    }
+
+    /// Force the next `take_changes` to ask for a rescan
+    pub(super) fn invalidate_changes(&mut self) {
+        if let Some(log) = &mut self.changes {
+            log.invalidate();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infinite_grid::{InfiniteGrid, PrunePolicy, StepCounts};
+    use crate::start_screen::RuleType;
+    use crate::CellState;
+
+    #[test]
+    fn steps_and_edits_report_deltas() {
+        let mut grid = InfiniteGrid::new();
+        for x in -1..=1 {
+            grid.set(x, 0, CellState::Alive);
+        }
+        assert_eq!(grid.take_changes(), None);
+
+        // Blinker flips from horizontal to vertical: two deaths, two births
+        grid.update(RuleType::Conway);
+        assert_eq!(grid.last_step(), StepCounts { births: 2, deaths: 2 });
+        let mut changes = grid.take_changes().unwrap();
+        changes.sort_by_key(|&(x, y, _)| (x, y));
+        assert_eq!(changes, vec![
+            (-1, 0, CellState::Dead),
+            (0, -1, CellState::Alive),
+            (0, 1, CellState::Alive),
+            (1, 0, CellState::Dead),
+        ]);
+
+        // Painting over a live cell changes nothing; painting a new one is logged
+        grid.set(0, 0, CellState::Alive);
+        grid.set(5, 5, CellState::Alive);
+        assert_eq!(grid.take_changes(), Some(vec![(5, 5, CellState::Alive)]));
+
+        grid.prune((0, 0), &PrunePolicy { max_distance: None, max_population: Some(0) });
+        assert_eq!(grid.take_changes(), None);
+        assert_eq!(grid.take_changes(), Some(Vec::new()));
+    }
+}
@@ -0,0 +1,92 @@
+//! Optional per-cell metadata.
+//!
+//! When enabled on an `InfiniteGrid`, every non-dead cell carries a small `CellMeta` record.
+//! The grid keeps the map in sync: cells painted with `set` get a fresh record, dying cells
+//! drop theirs, survivors keep theirs across steps, and cells born during a step inherit
+//! from the live neighbours that produced them (majority owner, mean energy).
+
+use std::collections::HashMap;
+
+/// Small per-cell record for ownership/resource style variants
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CellMeta {
+    /// Player or species that owns the cell (0 = nobody)
+    pub owner: u16,
+    /// Free-form resource counter for rules that use one
+    pub energy: u16,
+    /// Generation in which the cell was born or painted
+    pub born: u64,
+}
+
+impl CellMeta {
+    /// Age of the cell at `generation`
+    pub fn age(&self, generation: u64) -> u64 {
+        generation.saturating_sub(self.born)
+    }
+}
+
+pub(super) type MetaMap = HashMap<(i32, i32), CellMeta>;
+
+/// Record for a cell born at `(x, y)` in `generation`, derived from its neighbours' records
+/// in the previous generation
+pub(super) fn inherit(previous: &MetaMap, x: i32, y: i32, generation: u64) -> CellMeta {
+    let mut owners: [(u16, u8); 8] = [(0, 0); 8];
+    let mut distinct = 0;
+    let mut energy = 0u32;
+    let mut parents = 0u32;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let Some(parent) = previous.get(&(x + dx, y + dy)) else { continue };
+            parents += 1;
+            energy += parent.energy as u32;
+            match owners[..distinct].iter_mut().find(|(owner, _)| *owner == parent.owner) {
+                Some((_, votes)) => *votes += 1,
+                None => {
+                    owners[distinct] = (parent.owner, 1);
+                    distinct += 1;
+                }
+            }
+        }
+    }
+    // Ties go to the lowest owner id so the result doesn't depend on iteration order
+    let owner = owners[..distinct]
+        .iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+        .map_or(0, |&(owner, _)| owner);
+    CellMeta {
+        owner,
+        energy: energy.checked_div(parents).unwrap_or(0) as u16,
+        born: generation,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infinite_grid::InfiniteGrid;
+    use crate::start_screen::RuleType;
+    use crate::CellState;
+    use super::*;
+
+    #[test]
+    fn metadata_follows_cells_through_steps() {
+        let mut grid = InfiniteGrid::new();
+        // Vertical blinker owned by player 2 with some energy
+        for y in -1..=1 {
+            grid.set_with_meta(0, y, CellState::Alive, CellMeta { owner: 2, energy: 9, born: 0 });
+        }
+        grid.update(RuleType::Conway);
+        assert_eq!(grid.generation(), 1);
+        // Centre survives untouched, the arms are newborns inheriting owner and energy
+        assert_eq!(grid.meta(0, 0), Some(&CellMeta { owner: 2, energy: 9, born: 0 }));
+        assert_eq!(grid.meta(1, 0), Some(&CellMeta { owner: 2, energy: 9, born: 1 }));
+        assert_eq!(grid.meta(0, 1), None);
+
+        grid.set(0, 0, CellState::Dead);
+        assert_eq!(grid.meta(0, 0), None);
+        grid.set(5, 5, CellState::Alive);
+        assert_eq!(grid.meta(5, 5).map(|m| m.age(4)), Some(3));
+    }
+}
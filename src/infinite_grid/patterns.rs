@@ -0,0 +1,433 @@
+//! Built-in pattern library.
+//!
+//! Patterns are stored as small pictures, one string per row, and tagged with the rules
+//! they are known to work under and a rough category. Look them up by name with [`find`]
+//! (case, spaces and punctuation are ignored) or list them per rule with [`for_rule`].
+//!
+//! Row characters: `.` dead, `o` alive, `d` dying, `w` wire, `h` electron head,
+//! `t` electron tail, `a` species A, `b` species B.
+
+use crate::start_screen::RuleType;
+use crate::CellState;
+
+/// Broad classification used to group patterns in the browser
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    StillLife,
+    Oscillator,
+    Spaceship,
+    Gun,
+    Puffer,
+    Methuselah,
+    Replicator,
+    Circuit,
+}
+
+impl Category {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::StillLife => "Still life",
+            Category::Oscillator => "Oscillator",
+            Category::Spaceship => "Spaceship",
+            Category::Gun => "Gun",
+            Category::Puffer => "Puffer",
+            Category::Methuselah => "Methuselah",
+            Category::Replicator => "Replicator",
+            Category::Circuit => "Circuit",
+        }
+    }
+}
+
+/// A named pattern in the built-in library
+#[derive(Debug, Clone, Copy)]
+pub struct LibraryPattern {
+    pub name: &'static str,
+    pub category: Category,
+    /// Rules the pattern behaves as described under
+    pub rules: &'static [RuleType],
+    pub description: &'static str,
+    pub rows: &'static [&'static str],
+}
+
+impl LibraryPattern {
+    /// Every non-dead cell, relative to the bottom-left corner. Rows are written top-down and
+    /// the grid's y points up the screen, so the first row gets the highest y.
+    pub fn cells(&self) -> impl Iterator<Item = (i32, i32, CellState)> + '_ {
+        let top = self.height() - 1;
+        self.rows.iter().enumerate().flat_map(move |(y, row)| {
+            row.bytes().enumerate().filter_map(move |(x, ch)| {
+                let state = match ch {
+                    b'o' => CellState::Alive,
+                    b'd' => CellState::Dying,
+                    b'w' => CellState::Wire,
+                    b'h' => CellState::ElectronHead,
+                    b't' => CellState::ElectronTail,
+                    b'a' => CellState::SpeciesA,
+                    b'b' => CellState::SpeciesB,
+                    _ => return None,
+                };
+                Some((x as i32, top - y as i32, state))
+            })
+        })
+    }
+
+    /// Positions only, for callers that only deal in live cells
+    pub fn positions(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.cells().map(|(x, y, _)| (x, y))
+    }
+
+    pub fn width(&self) -> i32 {
+        self.rows.iter().map(|r| r.len()).max().unwrap_or(0) as i32
+    }
+
+    pub fn height(&self) -> i32 {
+        self.rows.len() as i32
+    }
+
+    pub fn supports(&self, rule: RuleType) -> bool {
+        self.rules.contains(&rule)
+    }
+}
+
+const LIFE: &[RuleType] = &[RuleType::Conway, RuleType::HighLife];
+const CONWAY: &[RuleType] = &[RuleType::Conway];
+const HIGHLIFE: &[RuleType] = &[RuleType::HighLife];
+const SEEDS: &[RuleType] = &[RuleType::Seeds];
+const BRIAN: &[RuleType] = &[RuleType::Brian];
+const WIREWORLD: &[RuleType] = &[RuleType::WireWorld];
+
+/// Every built-in pattern
+pub const LIBRARY: &[LibraryPattern] = &[
+    // Still lifes
+    LibraryPattern {
+        name: "Block",
+        category: Category::StillLife,
+        rules: LIFE,
+        description: "The most common still life",
+        rows: &["oo", "oo"],
+    },
+    LibraryPattern {
+        name: "Beehive",
+        category: Category::StillLife,
+        rules: LIFE,
+        description: "Six-cell still life",
+        rows: &[".oo.", "o..o", ".oo."],
+    },
+    LibraryPattern {
+        name: "Loaf",
+        category: Category::StillLife,
+        rules: LIFE,
+        description: "Seven-cell still life",
+        rows: &[".oo.", "o..o", ".o.o", "..o."],
+    },
+    LibraryPattern {
+        name: "Boat",
+        category: Category::StillLife,
+        rules: LIFE,
+        description: "Five-cell still life",
+        rows: &["oo.", "o.o", ".o."],
+    },
+    LibraryPattern {
+        name: "Tub",
+        category: Category::StillLife,
+        rules: LIFE,
+        description: "Four-cell still life",
+        rows: &[".o.", "o.o", ".o."],
+    },
+    // Oscillators
+    LibraryPattern {
+        name: "Blinker",
+        category: Category::Oscillator,
+        rules: LIFE,
+        description: "Period 2, the most common oscillator",
+        rows: &["ooo"],
+    },
+    LibraryPattern {
+        name: "Toad",
+        category: Category::Oscillator,
+        rules: LIFE,
+        description: "Period 2",
+        rows: &[".ooo", "ooo."],
+    },
+    LibraryPattern {
+        name: "Beacon",
+        category: Category::Oscillator,
+        rules: LIFE,
+        description: "Period 2, two blocks blinking at the corner",
+        rows: &["oo..", "oo..", "..oo", "..oo"],
+    },
+    LibraryPattern {
+        name: "Pulsar",
+        category: Category::Oscillator,
+        rules: CONWAY,
+        description: "Period 3",
+        rows: &[
+            "..ooo...ooo..",
+            ".............",
+            "o....o.o....o",
+            "o....o.o....o",
+            "o....o.o....o",
+            "..ooo...ooo..",
+            ".............",
+            "..ooo...ooo..",
+            "o....o.o....o",
+            "o....o.o....o",
+            "o....o.o....o",
+            ".............",
+            "..ooo...ooo..",
+        ],
+    },
+    LibraryPattern {
+        name: "Pentadecathlon",
+        category: Category::Oscillator,
+        rules: CONWAY,
+        description: "Period 15",
+        rows: &["..o....o..", "oo.oooo.oo", "..o....o.."],
+    },
+    // Spaceships
+    LibraryPattern {
+        name: "Glider",
+        category: Category::Spaceship,
+        rules: LIFE,
+        description: "c/4 diagonal, heading down-right",
+        rows: &[".o.", "..o", "ooo"],
+    },
+    LibraryPattern {
+        name: "Lightweight spaceship",
+        category: Category::Spaceship,
+        rules: LIFE,
+        description: "c/2 orthogonal (LWSS), heading left",
+        rows: &[".o..o", "o....", "o...o", "oooo."],
+    },
+    LibraryPattern {
+        name: "Middleweight spaceship",
+        category: Category::Spaceship,
+        rules: LIFE,
+        description: "c/2 orthogonal (MWSS), heading left",
+        rows: &["...o..", ".o...o", "o.....", "o....o", "ooooo."],
+    },
+    LibraryPattern {
+        name: "Heavyweight spaceship",
+        category: Category::Spaceship,
+        rules: LIFE,
+        description: "c/2 orthogonal (HWSS), heading left",
+        rows: &["...oo..", ".o....o", "o......", "o.....o", "oooooo."],
+    },
+    // Guns and puffers
+    LibraryPattern {
+        name: "Gosper glider gun",
+        category: Category::Gun,
+        rules: CONWAY,
+        description: "Emits a glider every 30 generations",
+        rows: &[
+            "........................o...........",
+            "......................o.o...........",
+            "............oo......oo............oo",
+            "...........o...o....oo............oo",
+            "oo........o.....o...oo..............",
+            "oo........o...o.oo....o.o...........",
+            "..........o.....o.......o...........",
+            "...........o...o....................",
+            "............oo......................",
+        ],
+    },
+    LibraryPattern {
+        name: "Puffer train",
+        category: Category::Puffer,
+        rules: CONWAY,
+        description: "Two LWSS escorting a tail that leaves growing debris, moving right at c/2",
+        rows: &[
+            "...o.", "....o", "o...o", ".oooo", ".....", ".....", ".....", "o....", ".oo..",
+            "..o..", "..o..", ".o...", ".....", ".....", "...o.", "....o", "o...o", ".oooo",
+        ],
+    },
+    // Methuselahs
+    LibraryPattern {
+        name: "R-pentomino",
+        category: Category::Methuselah,
+        rules: CONWAY,
+        description: "Five cells that take 1103 generations to settle",
+        rows: &[".oo", "oo.", ".o."],
+    },
+    LibraryPattern {
+        name: "Diehard",
+        category: Category::Methuselah,
+        rules: CONWAY,
+        description: "Vanishes completely after 130 generations",
+        rows: &["......o.", "oo......", ".o...ooo"],
+    },
+    LibraryPattern {
+        name: "Acorn",
+        category: Category::Methuselah,
+        rules: CONWAY,
+        description: "Seven cells that run for 5206 generations",
+        rows: &[".o.....", "...o...", "oo..ooo"],
+    },
+    // HighLife
+    LibraryPattern {
+        name: "Replicator",
+        category: Category::Replicator,
+        rules: HIGHLIFE,
+        description: "Copies itself along a diagonal every 12 generations",
+        rows: &["..ooo", ".o..o", "o...o", "o..o.", "ooo.."],
+    },
+    // Seeds
+    LibraryPattern {
+        name: "Seeds flip-flop",
+        category: Category::Oscillator,
+        rules: SEEDS,
+        description: "Diagonal pair that swaps to the other diagonal every generation",
+        rows: &[".o", "o."],
+    },
+    LibraryPattern {
+        name: "Seeds c/1 spaceship",
+        category: Category::Spaceship,
+        rules: SEEDS,
+        description: "Four cells moving down one cell per generation",
+        rows: &["o..o", ".oo."],
+    },
+    // Brian's Brain
+    LibraryPattern {
+        name: "Brian's Brain c/1 spaceship",
+        category: Category::Spaceship,
+        rules: BRIAN,
+        description: "Two firing cells pushed upwards by their own refractory trail",
+        rows: &["oo", "dd"],
+    },
+    LibraryPattern {
+        name: "Brian's Brain pinwheel",
+        category: Category::Oscillator,
+        rules: BRIAN,
+        description: "Eight cells chasing each other round a 4x4 ring, period 3",
+        rows: &[".od.", "d..o", "o..d", ".do."],
+    },
+    // WireWorld
+    LibraryPattern {
+        name: "Clock",
+        category: Category::Circuit,
+        rules: WIREWORLD,
+        description: "Six-cell loop sending an electron down the output wire every 6 generations",
+        rows: &[".tw......", "h..wwwwww", ".ww......"],
+    },
+    LibraryPattern {
+        name: "Diode",
+        category: Category::Circuit,
+        rules: WIREWORLD,
+        description: "Passes electrons left to right and blocks them right to left",
+        rows: &["....ww.....", "wwwww.wwwww", "....ww....."],
+    },
+];
+
+fn normalise(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Look a pattern up by name, ignoring case, spaces and punctuation
+pub fn find(name: &str) -> Option<&'static LibraryPattern> {
+    let key = normalise(name);
+    LIBRARY.iter().find(|p| normalise(p.name) == key)
+}
+
+/// Patterns that work under `rule`, in library order
+pub fn for_rule(rule: RuleType) -> impl Iterator<Item = &'static LibraryPattern> {
+    LIBRARY.iter().filter(move |p| p.supports(rule))
+}
+
+fn positions_of(name: &str) -> impl Iterator<Item = (i32, i32)> {
+    find(name).into_iter().flat_map(|p| p.positions())
+}
+
+/// Create a glider pattern
+pub fn glider() -> impl Iterator<Item = (i32, i32)> {
+    positions_of("glider")
+}
+
+/// Create a blinker pattern
+pub fn blinker() -> impl Iterator<Item = (i32, i32)> {
+    positions_of("blinker")
+}
+
+/// Create a block pattern (still life)
+pub fn block() -> impl Iterator<Item = (i32, i32)> {
+    positions_of("block")
+}
+
+/// HighLife replicator
+pub fn highlife_replicator() -> impl Iterator<Item = (i32, i32)> {
+    positions_of("replicator")
+}
+
+/// WireWorld clock (loop with an output wire)
+pub fn wire_clock() -> impl Iterator<Item = (i32, i32, CellState)> {
+    find("clock").into_iter().flat_map(|p| p.cells())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infinite_grid::InfiniteGrid;
+
+    type Cells = Vec<(i32, i32, CellState)>;
+
+    fn sorted_cells(grid: &InfiniteGrid) -> Cells {
+        let mut cells: Vec<_> = grid.cells().collect();
+        cells.sort_unstable_by_key(|c| (c.0, c.1));
+        cells
+    }
+
+    /// `cells` moved so their bounding box starts at (0, 0), with the offset taken off
+    fn normalised(cells: &[(i32, i32, CellState)]) -> (Cells, (i32, i32)) {
+        let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+        (cells.iter().map(|&(x, y, state)| (x - min_x, y - min_y, state)).collect(), (min_x, min_y))
+    }
+
+    #[test]
+    fn library_patterns_behave_as_categorised() {
+        for pattern in LIBRARY {
+            let rule = pattern.rules[0];
+            let mut grid = InfiniteGrid::new();
+            grid.insert_cells(pattern.cells(), 0, 0);
+            let start = sorted_cells(&grid);
+            let (shape, origin) = normalised(&start);
+            let mut period = None;
+            let mut translated = None;
+            for generation in 1..=30 {
+                grid.update(rule);
+                let cells = sorted_cells(&grid);
+                if cells == start {
+                    period = Some(generation);
+                    break;
+                }
+                let (moved, offset) = normalised(&cells);
+                if translated.is_none() && moved == shape {
+                    translated = Some(offset);
+                }
+            }
+            match pattern.category {
+                Category::StillLife => assert_eq!(period, Some(1), "{}", pattern.name),
+                Category::Oscillator => assert!(period.is_some_and(|p| p > 1), "{}", pattern.name),
+                Category::Spaceship => {
+                    assert!(translated.is_some_and(|offset| offset != origin), "{}", pattern.name)
+                }
+                Category::Gun | Category::Puffer | Category::Replicator => {
+                    assert!(grid.population() > start.len(), "{}", pattern.name)
+                }
+                // Electrons move along the wire but never lay or cut any
+                Category::Circuit => {
+                    let wiring = |cells: &[(i32, i32, CellState)]| cells.iter().map(|c| (c.0, c.1)).collect::<Vec<_>>();
+                    assert_eq!(wiring(&sorted_cells(&grid)), wiring(&start), "{}", pattern.name)
+                }
+                Category::Methuselah => assert!(period.is_none(), "{}", pattern.name),
+            }
+        }
+    }
+
+    #[test]
+    fn lookup_ignores_case_and_punctuation() {
+        assert_eq!(find("gosper-glider-gun").map(|p| p.name), Some("Gosper glider gun"));
+        assert_eq!(find("R PENTOMINO").map(|p| p.height()), Some(3));
+        assert!(for_rule(RuleType::WireWorld).all(|p| p.category == Category::Circuit));
+        assert_eq!(glider().count(), 5);
+    }
+}
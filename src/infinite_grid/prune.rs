@@ -0,0 +1,113 @@
+//! Far-region garbage collection.
+//!
+//! Explosive rules (Gnarl, Seeds, Life without Death) can grow without limit. A `PrunePolicy`
+//! bounds memory by dropping whole chunks: first those further than `max_distance` from a
+//! centre (usually the camera), then, while the population is still above `max_population`,
+//! the remaining chunks furthest from the centre.
+
+use std::collections::HashSet;
+use bevy::prelude::Resource;
+use super::{split, InfiniteGrid, CHUNK_SIZE};
+
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PrunePolicy {
+    /// Drop chunks lying entirely further than this many cells (Chebyshev) from the centre
+    pub max_distance: Option<i32>,
+    /// Keep dropping the furthest chunks until the population is at most this
+    pub max_population: Option<usize>,
+}
+
+impl PrunePolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.max_distance.is_some() || self.max_population.is_some()
+    }
+}
+
+/// Chebyshev distance from `(x, y)` to the nearest cell of chunk `(cx, cy)`
+fn chunk_distance((cx, cy): (i32, i32), (x, y): (i32, i32)) -> i32 {
+    let axis = |c: i32, p: i32| {
+        let (lo, hi) = (c * CHUNK_SIZE, c * CHUNK_SIZE + CHUNK_SIZE - 1);
+        if p < lo { lo - p } else if p > hi { p - hi } else { 0 }
+    };
+    axis(cx, x).max(axis(cy, y))
+}
+
+impl InfiniteGrid {
+    /// Apply `policy` around `centre`, returning the number of cells removed
+    pub fn prune(&mut self, centre: (i32, i32), policy: &PrunePolicy) -> usize {
+        let mut doomed: HashSet<(i32, i32)> = HashSet::new();
+        let mut remaining = self.population;
+
+        if let Some(max_distance) = policy.max_distance {
+            for (&key, chunk) in &self.chunks {
+                if chunk_distance(key, centre) > max_distance {
+                    doomed.insert(key);
+                    remaining -= chunk.population as usize;
+                }
+            }
+        }
+
+        if let Some(max_population) = policy.max_population {
+            if remaining > max_population {
+                let mut by_distance: Vec<(i32, (i32, i32))> = self
+                    .chunks
+                    .keys()
+                    .filter(|key| !doomed.contains(key))
+                    .map(|&key| (chunk_distance(key, centre), key))
+                    .collect();
+                // Furthest first; ties broken by key so the result is deterministic
+                by_distance.sort_unstable_by(|a, b| b.cmp(a));
+                for (_, key) in by_distance {
+                    if remaining <= max_population {
+                        break;
+                    }
+                    remaining -= self.chunks[&key].population as usize;
+                    doomed.insert(key);
+                }
+            }
+        }
+
+        if doomed.is_empty() {
+            return 0;
+        }
+        for key in &doomed {
+            self.chunks.remove(key);
+        }
+        if let Some(meta) = &mut self.meta {
+            meta.retain(|&(x, y), _| !doomed.contains(&split(x, y).0));
+        }
+        let removed = self.population - remaining;
+        self.population = remaining;
+        self.invalidate_changes();
+        self.recalculate_bounds();
+        self.version += 1;
+        self.cache_dirty = true;
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CellState;
+    use super::*;
+
+    #[test]
+    fn prunes_far_chunks_then_furthest_until_under_cap() {
+        let mut grid = InfiniteGrid::new();
+        grid.set(0, 0, CellState::Alive);
+        grid.set(70, 0, CellState::Alive);
+        grid.set(-140, 5, CellState::Alive);
+        grid.set(1000, 1000, CellState::Alive);
+
+        let far = PrunePolicy { max_distance: Some(500), max_population: None };
+        assert_eq!(grid.prune((0, 0), &far), 1);
+        assert!(!grid.is_alive(1000, 1000));
+
+        let capped = PrunePolicy { max_distance: None, max_population: Some(2) };
+        assert_eq!(grid.prune((0, 0), &capped), 1);
+        assert!(!grid.is_alive(-140, 5));
+        assert!(grid.is_alive(0, 0) && grid.is_alive(70, 0));
+        assert_eq!(grid.population(), 2);
+        assert_eq!(grid.bounds().map(|b| b.min_x), Some(0));
+    }
+}
@@ -0,0 +1,135 @@
+//! Rewind buffer.
+//!
+//! The simulation records the grid just before each step, keeping the last `capacity`
+//! generations, so a paused game can step backwards through them. Frames store the cells
+//! and the step's births and deaths; cell ages (`CellMeta`) restart from the restored
+//! generation, and layers aren't rewound. A frame is only restored onto the generation right
+//! after it, so a clear, load or any other jump in the grid's generation ends the history.
+
+use std::collections::VecDeque;
+use bevy::prelude::Resource;
+use crate::CellState;
+use super::{InfiniteGrid, StepCounts};
+
+/// Generations kept by default
+pub const DEFAULT_REWIND: usize = 200;
+
+/// The grid at one generation
+#[derive(Clone, Debug)]
+struct Frame {
+    generation: u64,
+    cells: Vec<(i32, i32, CellState)>,
+    last_step: StepCounts,
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct RewindBuffer {
+    pub capacity: usize,
+    frames: VecDeque<Frame>,
+}
+
+impl Default for RewindBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_REWIND)
+    }
+}
+
+impl RewindBuffer {
+    /// A capacity of 0 keeps nothing, turning rewinding off
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, frames: VecDeque::new() }
+    }
+
+    /// Rough heap size of the recorded frames in bytes
+    pub fn memory_bytes(&self) -> usize {
+        self.frames.iter().map(|frame| frame.cells.capacity() * std::mem::size_of::<(i32, i32, CellState)>()).sum::<usize>()
+            + self.frames.capacity() * std::mem::size_of::<Frame>()
+    }
+
+    /// Record `grid` before it steps
+    pub fn record(&mut self, grid: &InfiniteGrid) {
+        if self.capacity == 0 {
+            return;
+        }
+        // Frames from a later generation belong to a history the grid has left
+        while self.frames.back().is_some_and(|frame| frame.generation >= grid.generation) {
+            self.frames.pop_back();
+        }
+        self.frames.push_back(Frame { generation: grid.generation, cells: grid.cells().collect(), last_step: grid.last_step });
+        while self.frames.len() > self.capacity {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Put `grid` back one generation. False, leaving the grid alone, when there's no frame
+    /// for the generation before it.
+    pub fn step_back(&mut self, grid: &mut InfiniteGrid) -> bool {
+        let Some(frame) = self.frames.pop_back() else { return false };
+        if frame.generation + 1 != grid.generation {
+            self.frames.clear();
+            return false;
+        }
+        // `clear` also invalidates the change log, so renderers rescan the restored grid
+        grid.clear();
+        grid.generation = frame.generation;
+        for (x, y, state) in frame.cells {
+            grid.set(x, y, state);
+        }
+        grid.last_step = frame.last_step;
+        true
+    }
+
+    /// Generations that can be stepped back
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::start_screen::RuleType;
+
+    #[test]
+    fn steps_back_through_recorded_generations() {
+        let mut grid = InfiniteGrid::new();
+        grid.insert_pattern(crate::infinite_grid::patterns::glider(), 0, 0);
+        let start: Vec<_> = grid.alive_cells().collect();
+        let mut rewind = RewindBuffer::new(2);
+        for _ in 0..3 {
+            rewind.record(&grid);
+            grid.update(RuleType::Conway);
+        }
+        let mut third: Vec<_> = grid.alive_cells().collect();
+        third.sort();
+        assert_eq!(rewind.len(), 2);
+
+        assert!(rewind.step_back(&mut grid));
+        assert!(rewind.step_back(&mut grid));
+        assert_eq!(grid.generation(), 1);
+        assert!(!rewind.step_back(&mut grid));
+        assert_eq!(grid.generation(), 1);
+
+        // Stepping forward again replays the same generations
+        grid.update(RuleType::Conway);
+        grid.update(RuleType::Conway);
+        let mut replayed: Vec<_> = grid.alive_cells().collect();
+        replayed.sort();
+        assert_eq!(replayed, third);
+
+        // A cleared grid doesn't go back to the old history
+        rewind.record(&grid);
+        grid.clear();
+        grid.insert_pattern(start.iter().copied(), 0, 0);
+        assert!(!rewind.step_back(&mut grid));
+        assert!(rewind.is_empty());
+    }
+}
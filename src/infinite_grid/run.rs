@@ -0,0 +1,138 @@
+//! Headless runs with stop conditions.
+//!
+//! `InfiniteGrid::run` steps a grid under one rule until the first condition in a `RunConfig`
+//! fires: a generation budget, the population settling into a cycle, the population passing
+//! a limit, or a caller-supplied predicate on the pattern's bounds.
+
+use crate::start_screen::RuleType;
+use crate::stats::StabilityDetector;
+use super::{GridBounds, InfiniteGrid};
+
+/// Generation budget used by `RunConfig::default`
+pub const DEFAULT_MAX_GENERATIONS: u64 = 10_000;
+
+/// Caller-supplied stop condition on the pattern's extent
+pub type BoundsPredicate = Box<dyn Fn(&GridBounds) -> bool>;
+
+/// Stop conditions for `InfiniteGrid::run`; whichever fires first ends the run
+pub struct RunConfig {
+    /// Stop after this many generations. With every condition unset the run never ends.
+    pub max_generations: Option<u64>,
+    /// Stop once the population has repeated with a period up to this long
+    pub stable_period: Option<usize>,
+    /// Stop as soon as the population exceeds this
+    pub max_population: Option<usize>,
+    /// Stop when this returns true for the current bounds (not called while the grid is empty)
+    pub bounds_predicate: Option<BoundsPredicate>,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            max_generations: Some(DEFAULT_MAX_GENERATIONS),
+            stable_period: None,
+            max_population: None,
+            bounds_predicate: None,
+        }
+    }
+}
+
+impl RunConfig {
+    pub fn generations(mut self, generations: u64) -> Self {
+        self.max_generations = Some(generations);
+        self
+    }
+
+    pub fn until_stable(mut self, max_period: usize) -> Self {
+        self.stable_period = Some(max_period);
+        self
+    }
+
+    pub fn population_above(mut self, limit: usize) -> Self {
+        self.max_population = Some(limit);
+        self
+    }
+
+    pub fn until_bounds(mut self, predicate: impl Fn(&GridBounds) -> bool + 'static) -> Self {
+        self.bounds_predicate = Some(Box::new(predicate));
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The generation budget ran out
+    Generations,
+    /// The population has cycled with `period` since generation `since`
+    Stable { period: usize, since: u64 },
+    PopulationLimit,
+    Bounds,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunResult {
+    /// Generations stepped during this run
+    pub generations: u64,
+    pub reason: StopReason,
+}
+
+impl InfiniteGrid {
+    /// Step under `rule` until one of `config`'s stop conditions is met
+    pub fn run(&mut self, rule: RuleType, config: &RunConfig) -> RunResult {
+        let mut detector = config.stable_period.map(StabilityDetector::new);
+        if let Some(detector) = &mut detector {
+            detector.push(self.population());
+        }
+        let mut generations = 0;
+
+        loop {
+            if config.max_generations.is_some_and(|max| generations >= max) {
+                return RunResult { generations, reason: StopReason::Generations };
+            }
+            self.update(rule);
+            generations += 1;
+
+            let reason = if config.max_population.is_some_and(|max| self.population() > max) {
+                Some(StopReason::PopulationLimit)
+            } else if config.bounds_predicate.as_ref().zip(self.bounds()).is_some_and(|(predicate, bounds)| predicate(bounds)) {
+                Some(StopReason::Bounds)
+            } else {
+                detector.as_mut().and_then(|d| d.push(self.population())).map(|period| StopReason::Stable {
+                    period,
+                    // The cycle began three periods before it was confirmed
+                    since: generations.saturating_sub(period as u64 * 3),
+                })
+            };
+            if let Some(reason) = reason {
+                return RunResult { generations, reason };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CellState;
+    use super::*;
+
+    #[test]
+    fn stops_on_first_condition() {
+        let mut blinker = InfiniteGrid::new();
+        for x in -1..=1 {
+            blinker.set(x, 0, CellState::Alive);
+        }
+        let result = blinker.run(RuleType::Conway, &RunConfig::default().until_stable(4));
+        assert!(matches!(result.reason, StopReason::Stable { period: 1, since: 0 }));
+
+        let mut glider = InfiniteGrid::new();
+        for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            glider.set(x, y, CellState::Alive);
+        }
+        let result = glider.run(RuleType::Conway, &RunConfig::default().until_bounds(|b| b.max_x >= 10));
+        assert_eq!(result.reason, StopReason::Bounds);
+        assert!(result.generations > 20 && result.generations < 40);
+
+        let result = glider.run(RuleType::Conway, &RunConfig::default().generations(7));
+        assert_eq!(result, RunResult { generations: 7, reason: StopReason::Generations });
+    }
+}
@@ -0,0 +1,153 @@
+//! Debug inspector (`inspector` feature).
+//!
+//! Built with `--features inspector`, Shift and the performance overlay key (Shift+/ by
+//! default) open an egui window for contributors and tinkerers: the grid's internals
+//! (chunks, bounds, version, the last step, the alive cell cache and how often it is hit),
+//! the features the audio analysis extracted last, and the live values of the main
+//! resources. The simulation speed and pause can be changed from it too. While the pointer
+//! is over the window the grid tools leave clicks alone.
+
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+use crate::audio::{get_hybrid_volume, latest_game_features};
+use crate::camera::{CameraState, GameCamera};
+use crate::infinite_grid::InfiniteGrid;
+use crate::keybindings::{Action, Keybindings};
+use crate::perf_overlay::format_bytes;
+use crate::synth_ui::SynthParameters;
+use crate::tools::{ActiveTool, BrushSettings};
+use crate::ui::PointerCapture;
+use crate::{GameConfig, GameStats};
+
+/// Whether the inspector window is open
+#[derive(Resource, Default)]
+pub struct Inspector {
+    pub open: bool,
+}
+
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(EguiPlugin)
+            .init_resource::<Inspector>()
+            .add_systems(PreUpdate, inspector_capture.after(UiSystem::Focus))
+            .add_systems(Update, (toggle_inspector, draw_inspector).chain());
+    }
+}
+
+fn toggle_inspector(keyboard_input: Res<ButtonInput<KeyCode>>, keys: Res<Keybindings>, mut inspector: ResMut<Inspector>) {
+    if keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) && keys.just_pressed(&keyboard_input, Action::PerfOverlay) {
+        inspector.open = !inspector.open;
+    }
+}
+
+/// Keep clicks on the window from painting the grid underneath
+fn inspector_capture(inspector: Res<Inspector>, mut contexts: EguiContexts, mut capture: ResMut<PointerCapture>) {
+    if !inspector.open {
+        return;
+    }
+    if let Some(ctx) = contexts.try_ctx_mut() {
+        capture.captured |= ctx.is_pointer_over_area() || ctx.wants_pointer_input();
+    }
+}
+
+/// Label and value rows of a two-column grid
+fn rows(ui: &mut egui::Ui, id: &str, rows: &[(&str, String)]) {
+    egui::Grid::new(id).num_columns(2).striped(true).show(ui, |ui| {
+        for (label, value) in rows {
+            ui.label(*label);
+            ui.monospace(value);
+            ui.end_row();
+        }
+    });
+}
+
+fn draw_inspector(
+    mut inspector: ResMut<Inspector>,
+    mut contexts: EguiContexts,
+    grid: Res<InfiniteGrid>,
+    (mut game_stats, game_config): (ResMut<GameStats>, Res<GameConfig>),
+    (camera_state, cameras): (Res<CameraState>, Query<(&Transform, &GameCamera)>),
+    (brush, active, synth): (Res<BrushSettings>, Res<ActiveTool>, Res<SynthParameters>),
+) {
+    if !inspector.open {
+        return;
+    }
+    let Some(ctx) = contexts.try_ctx_mut() else { return };
+    let mut open = inspector.open;
+    egui::Window::new("Inspector").open(&mut open).default_width(320.0).vscroll(true).show(ctx, |ui| {
+        egui::CollapsingHeader::new("Grid").default_open(true).show(ui, |ui| {
+            let bounds = grid.bounds().map_or_else(
+                || "none".to_string(),
+                |b| format!("({}, {}) to ({}, {})", b.min_x, b.min_y, b.max_x, b.max_y),
+            );
+            let step = grid.last_step();
+            let cache = grid.cache_stats();
+            let hit_rate = cache.hit_rate().map_or_else(|| "-".to_string(), |rate| format!("{:.1}%", rate * 100.0));
+            rows(ui, "grid", &[
+                ("Population", grid.population().to_string()),
+                ("Chunks", grid.chunk_count().to_string()),
+                ("Bounds", bounds),
+                ("Generation", grid.generation().to_string()),
+                ("Version", grid.version().to_string()),
+                ("Last step", format!("+{} / -{}", step.births, step.deaths)),
+                ("Memory", format_bytes(grid.memory_bytes())),
+                ("Cell cache", format!("{} hits / {} misses", cache.hits, cache.misses)),
+                ("Cache hit rate", hit_rate),
+            ]);
+        });
+
+        egui::CollapsingHeader::new("Audio features").default_open(true).show(ui, |ui| match latest_game_features() {
+            Some(features) => {
+                let bar = |ui: &mut egui::Ui, label: &str, value: f32| {
+                    ui.label(label);
+                    ui.add(egui::ProgressBar::new(value.clamp(0.0, 1.0)).text(format!("{:.3}", value)));
+                    ui.end_row();
+                };
+                egui::Grid::new("features").num_columns(2).show(ui, |ui| {
+                    bar(ui, "population", features.population);
+                    bar(ui, "density", features.density);
+                    bar(ui, "activity", features.activity);
+                    bar(ui, "clusters", features.cluster_count);
+                    bar(ui, "cluster size", features.avg_cluster_size);
+                    bar(ui, "symmetry", features.symmetry);
+                    bar(ui, "chaos", features.chaos);
+                    bar(ui, "generation", features.generation);
+                });
+                rows(ui, "centroid", &[("Centroid", format!("{:+.2}, {:+.2}", features.centroid_x, features.centroid_y))]);
+            }
+            None => {
+                ui.label("Not analysed yet");
+            }
+        });
+
+        egui::CollapsingHeader::new("Resources").default_open(false).show(ui, |ui| {
+            let mut running = game_stats.is_running;
+            if ui.checkbox(&mut running, "Running").changed() {
+                if running { game_stats.is_running = true } else { game_stats.stop() }
+            }
+            let (min, max) = (game_stats.min_update_interval, game_stats.max_update_interval);
+            ui.add(egui::Slider::new(&mut game_stats.update_interval, min..=max).logarithmic(true).text("interval (s)"));
+            let camera = cameras.get_single().map_or_else(
+                |_| "-".to_string(),
+                |(transform, camera)| format!("({:.0}, {:.0}) x{:.2}", transform.translation.x, transform.translation.y, camera.zoom),
+            );
+            rows(ui, "resources", &[
+                ("GameStats.generation", game_stats.generation.to_string()),
+                ("GameStats.run_remaining", format!("{:?}", game_stats.run_remaining)),
+                ("GameConfig.current_rule", game_config.current_rule.name().to_string()),
+                ("GameConfig.audio_engine", game_config.audio_engine.name().to_string()),
+                ("GameConfig.audio_volume", format!("{:.2}{}", game_config.audio_volume, if game_config.audio_muted { " (muted)" } else { "" })),
+                ("Hybrid volume", format!("{:.2}", get_hybrid_volume())),
+                ("SynthParameters", format!("volume {:.2}, mix {:.2}", synth.volume, synth.mix)),
+                ("CameraState", format!("cell {:.1}, offset ({:.0}, {:.0})", camera_state.cell_size, camera_state.grid_offset.x, camera_state.grid_offset.y)),
+                ("Camera", camera),
+                ("BrushSettings", format!("{} {}, spray {}", brush.size, brush.shape.name(), brush.spray)),
+                ("ActiveTool", active.tool.name().to_string()),
+            ]);
+        });
+    });
+    inspector.open = open;
+}
@@ -2,12 +2,30 @@
 //!
 //! Reference: <https://conwaylife.com/wiki/Run_Length_Encoded>
 //! Only essential tokens are parsed. Comments and header lines are skipped.
+//!
+//! The `rle` module reads and writes full RLE files (header, rule, comments, multi-state
+//! cells) as a [`Pattern`] for the infinite grid. [`load_pattern`] picks the right reader
+//! (RLE, plaintext, Life 1.05, Life 1.06 or macrocell) from the file's header. With the
+//! `net` feature, [`load_pattern_url`] downloads a pattern file over HTTPS.
 
 use core::str::Chars;
 use crate::{CellState, Grid};
 
+pub mod clipboard;
 pub mod lif;
-pub use lif::load_lif_into_grid;
+pub mod macrocell;
+#[cfg(feature = "net")]
+pub mod net;
+pub mod pattern;
+pub mod plaintext;
+pub mod rle;
+pub use lif::{load_lif_into_grid, parse_life105, parse_life106};
+pub use macrocell::{load_macrocell_file, parse_macrocell, save_macrocell_file, write_macrocell, Macrocell};
+#[cfg(feature = "net")]
+pub use net::load_pattern_url;
+pub use pattern::Pattern;
+pub use plaintext::parse_plaintext;
+pub use rle::{load_rle_file, parse_rle, save_rle_file, write_rle};
 
 #[derive(Debug, thiserror::Error)]
 pub enum PatternError {
@@ -17,8 +35,56 @@ pub enum PatternError {
     InvalidRun,
     #[error("pattern exceeds grid bounds")]
     OutOfBounds,
+    #[error("malformed header line: {0}")]
+    BadHeader(String),
+    #[error("malformed coordinates: {0}")]
+    BadCoordinate(String),
+    #[error("malformed macrocell node: {0}")]
+    BadNode(String),
+    #[error("pattern has {0} cells, more than can be expanded")]
+    TooLarge(u64),
+    #[error("download failed: {0}")]
+    Network(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
 }
 
+/// Most cells `load_pattern` will expand a macrocell file into
+pub const MAX_EXPANDED_CELLS: u64 = 2_000_000;
+
+/// Parse pattern text in any supported format, detected from its header
+pub fn load_pattern(text: &str) -> Result<Pattern, PatternError> {
+    let header = text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("");
+    if header.starts_with('!') {
+        parse_plaintext(text)
+    } else if header.starts_with("[M2]") {
+        parse_macrocell(text)?.to_pattern(MAX_EXPANDED_CELLS)
+    } else if header.starts_with("#Life 1.05") || header.starts_with("#Life 1.06") {
+        if lif::is_life105_body(text) {
+            lif::parse_life105(text)
+        } else {
+            lif::parse_life106(text)
+        }
+    } else {
+        parse_rle(text)
+    }
+}
+
+/// Read and parse a pattern file in any supported format. Plaintext files need not start
+/// with a comment, so the `.cells` extension also selects that reader.
+pub fn load_pattern_file(path: impl AsRef<std::path::Path>) -> Result<Pattern, PatternError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)?;
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("cells")) {
+        parse_plaintext(&text)
+    } else {
+        load_pattern(&text)
+    }
+}
+
+/// File extensions `load_pattern_file` understands
+pub const PATTERN_EXTENSIONS: [&str; 4] = ["rle", "cells", "lif", "mc"];
+
 /// Load an RLE string into `grid`, placing top-left corner at `(ox, oy)`.
 /// Returns `PatternError` if the pattern goes out of bounds or the RLE is malformed.
 pub fn load_rle_into_grid(
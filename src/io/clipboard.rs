@@ -0,0 +1,64 @@
+//! Plain-text access to the OS clipboard.
+//!
+//! Goes through the platform's own clipboard commands rather than a windowing-system
+//! binding: `pbcopy`/`pbpaste` on macOS, PowerShell on Windows and `wl-copy`/`wl-paste`,
+//! `xclip` or `xsel` elsewhere, whichever is installed.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClipboardError {
+    #[error("no clipboard command available (install wl-clipboard, xclip or xsel)")]
+    Unavailable,
+    #[error("clipboard command failed: {0}")]
+    Failed(String),
+}
+
+#[cfg(target_os = "macos")]
+const COPY_COMMANDS: &[&[&str]] = &[&["pbcopy"]];
+#[cfg(target_os = "macos")]
+const PASTE_COMMANDS: &[&[&str]] = &[&["pbpaste"]];
+
+#[cfg(target_os = "windows")]
+const COPY_COMMANDS: &[&[&str]] = &[&["powershell", "-NoProfile", "-Command", "$input | Set-Clipboard"]];
+#[cfg(target_os = "windows")]
+const PASTE_COMMANDS: &[&[&str]] = &[&["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"]];
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const COPY_COMMANDS: &[&[&str]] = &[&["wl-copy"], &["xclip", "-selection", "clipboard"], &["xsel", "--clipboard", "--input"]];
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const PASTE_COMMANDS: &[&[&str]] = &[&["wl-paste", "--no-newline"], &["xclip", "-selection", "clipboard", "-o"], &["xsel", "--clipboard", "--output"]];
+
+/// Replace the clipboard contents with `text`
+pub fn copy_text(text: &str) -> Result<(), ClipboardError> {
+    for command in COPY_COMMANDS {
+        let Ok(mut child) = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes()).map_err(|e| ClipboardError::Failed(e.to_string()))?;
+        }
+        let status = child.wait().map_err(|e| ClipboardError::Failed(e.to_string()))?;
+        return if status.success() { Ok(()) } else { Err(ClipboardError::Failed(format!("{} exited with {}", command[0], status))) };
+    }
+    Err(ClipboardError::Unavailable)
+}
+
+/// Current clipboard contents as text
+pub fn paste_text() -> Result<String, ClipboardError> {
+    for command in PASTE_COMMANDS {
+        let Ok(output) = Command::new(command[0]).args(&command[1..]).stderr(Stdio::null()).output() else { continue };
+        if !output.status.success() {
+            return Err(ClipboardError::Failed(format!("{} exited with {}", command[0], output.status)));
+        }
+        return String::from_utf8(output.stdout).map_err(|e| ClipboardError::Failed(e.to_string()));
+    }
+    Err(ClipboardError::Unavailable)
+}
@@ -4,8 +4,13 @@
 //! • Comment lines begin with ‘#’.
 //! • “#P x y” re-positions the cursor.
 //! • Pattern rows use ‘.’ (dead) and ‘*’ (alive).
+//!
+//! `parse_life105` / `parse_life106` read the same files (and Life 1.06 coordinate lists)
+//! into a [`Pattern`] for the infinite grid.
 
 use crate::{CellState, Grid};
+use super::pattern::Pattern;
+use super::PatternError;
 
 #[derive(Debug, thiserror::Error)]
 pub enum LifError {
@@ -60,4 +65,89 @@ pub fn load_lif_into_grid(
         px = 0;
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Parse a Life 1.05 file: `#D` descriptions, `#R` rule, `#N` for normal Conway rules and
+/// `#P x y` blocks of `.`/`*` rows
+pub fn parse_life105(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let (mut px, mut py) = (0i32, 0i32);
+
+    for line in text.lines().map(str::trim_end) {
+        if line.starts_with("#Life") {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("#D") {
+            pattern.comments.push(rest.trim().to_string());
+        } else if line.starts_with("#N") {
+            pattern.rulestring = Some("B3/S23".to_string());
+        } else if let Some(rest) = line.strip_prefix("#R") {
+            pattern.rulestring = Some(rest.trim().to_string());
+        } else if let Some(rest) = line.strip_prefix("#P") {
+            let mut coords = rest.split_whitespace().map(str::parse::<i32>);
+            match (coords.next(), coords.next()) {
+                (Some(Ok(x)), Some(Ok(y))) => (px, py) = (x, y),
+                _ => return Err(PatternError::BadCoordinate(line.to_string())),
+            }
+        } else if !line.starts_with('#') {
+            for (dx, ch) in line.chars().enumerate() {
+                match ch {
+                    '.' => {}
+                    '*' => pattern.cells.push((px + dx as i32, py, CellState::Alive)),
+                    _ => return Err(PatternError::InvalidChar(ch)),
+                }
+            }
+            py += 1;
+        }
+    }
+    pattern.normalise();
+    Ok(pattern)
+}
+
+/// Parse a Life 1.06 file: one `x y` coordinate pair per line
+pub fn parse_life106(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut coords = line.split_whitespace().map(str::parse::<i32>);
+        match (coords.next(), coords.next(), coords.next()) {
+            (Some(Ok(x)), Some(Ok(y)), None) => pattern.cells.push((x, y, CellState::Alive)),
+            _ => return Err(PatternError::BadCoordinate(line.to_string())),
+        }
+    }
+    pattern.normalise();
+    Ok(pattern)
+}
+
+/// Whether a `#Life 1.0x` body is block-based (1.05) rather than a coordinate list (1.06).
+/// Several collections label 1.05 content as 1.06, so the body decides rather than the header.
+pub(super) fn is_life105_body(text: &str) -> bool {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .any(|line| line.chars().all(|c| c == '.' || c == '*'))
+        || text.lines().any(|line| line.starts_with("#P"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::io::load_pattern;
+    use crate::start_screen::RuleType;
+
+    #[test]
+    fn detects_life_formats_by_header() {
+        let coords = load_pattern("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n").unwrap();
+        assert_eq!(coords.cells.len(), 5);
+        assert_eq!((coords.width(), coords.height()), (3, 3));
+
+        let blocks = load_pattern("#Life 1.05\n#D Glider\n#N\n#P -1 -1\n.*\n..*\n***\n").unwrap();
+        assert_eq!(blocks.rule(), Some(RuleType::Conway));
+        assert_eq!(blocks.cells, coords.cells);
+
+        // Shipped sample is labelled 1.06 but written in 1.05 blocks
+        let sample = load_pattern(include_str!("../../patterns/glider_pulsar.lif")).unwrap();
+        assert_eq!(sample.cells.len(), 5 + 48);
+    }
+}
@@ -0,0 +1,325 @@
+//! Golly macrocell (`.mc`) reader and writer.
+//!
+//! A macrocell file is a hash-consed quadtree: each line defines a node from earlier nodes, so
+//! a pattern with billions of cells can be described in a few kilobytes. The file is parsed
+//! into a `Macrocell` node table without expanding it; population and extent come straight
+//! from the tree, and cells are only materialised by `insert_into`, which refuses patterns
+//! above a caller-chosen cap. Writing builds the tree from an `InfiniteGrid`, sharing
+//! identical subtrees so repetitive patterns stay small on disk.
+//!
+//! Two-state rules use 8×8 leaf lines (`.`/`*` rows separated by `$`); multi-state rules use
+//! level-1 nodes whose children are state numbers, numbered as in RLE files.
+
+use std::collections::HashMap;
+use std::path::Path;
+use crate::infinite_grid::InfiniteGrid;
+use crate::start_screen::RuleType;
+use crate::CellState;
+use super::pattern::{numbered_states, Pattern};
+use super::PatternError;
+
+/// Level of an 8×8 leaf line
+const LEAF_LEVEL: u8 = 3;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    /// 8×8 block of a two-state pattern, one bitmask per row (bit `x` = column `x`)
+    Leaf([u8; 8]),
+    /// Quadrant references `[nw, ne, sw, se]`; node 0 is the empty node. At level 1 the
+    /// children are state numbers rather than node references.
+    Quad { level: u8, children: [usize; 4] },
+}
+
+impl Node {
+    fn level(&self) -> u8 {
+        match self {
+            Node::Leaf(_) => LEAF_LEVEL,
+            Node::Quad { level, .. } => *level,
+        }
+    }
+}
+
+/// Parsed macrocell file
+#[derive(Clone, Debug, Default)]
+pub struct Macrocell {
+    pub rulestring: Option<String>,
+    /// Generation recorded with `#G`
+    pub generation: Option<u64>,
+    pub comments: Vec<String>,
+    /// Node table; index 0 stands for the empty node and is never read
+    nodes: Vec<Node>,
+}
+
+impl Macrocell {
+    pub fn rule(&self) -> Option<RuleType> {
+        self.rulestring.as_deref().and_then(RuleType::from_rulestring)
+    }
+
+    fn root(&self) -> usize {
+        self.nodes.len().saturating_sub(1)
+    }
+
+    /// Side length of the root square is `2^level`
+    pub fn level(&self) -> u8 {
+        self.nodes.get(self.root()).map_or(0, Node::level)
+    }
+
+    /// Number of non-dead cells, computed on the tree without expanding it (saturating)
+    pub fn population(&self) -> u64 {
+        let mut memo = vec![0u64; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate().skip(1) {
+            memo[index] = match node {
+                Node::Leaf(rows) => rows.iter().map(|r| r.count_ones() as u64).sum(),
+                Node::Quad { level: 1, children } => children.iter().filter(|&&s| s != 0).count() as u64,
+                Node::Quad { children, .. } => children.iter().fold(0u64, |sum, &c| sum.saturating_add(memo[c])),
+            };
+        }
+        memo.get(self.root()).copied().unwrap_or(0)
+    }
+
+    /// Write every cell into `grid`, with the root square centred on `(ox, oy)` as Golly
+    /// does. Returns the number of cells written, or `TooLarge` without writing anything if
+    /// the pattern has more than `max_cells`.
+    pub fn insert_into(&self, grid: &mut InfiniteGrid, ox: i32, oy: i32, max_cells: u64) -> Result<u64, PatternError> {
+        let population = self.population();
+        if population > max_cells {
+            return Err(PatternError::TooLarge(population));
+        }
+        // Rows count down the file and the grid's y points up
+        let cells = self.cells()?;
+        grid.insert_cells(cells.iter().map(|&(x, y, state)| (x, -y, state)), ox, oy);
+        Ok(population)
+    }
+
+    /// Expand into a `Pattern`, refusing patterns with more than `max_cells`
+    pub fn to_pattern(&self, max_cells: u64) -> Result<Pattern, PatternError> {
+        let population = self.population();
+        if population > max_cells {
+            return Err(PatternError::TooLarge(population));
+        }
+        let mut pattern = Pattern {
+            comments: self.comments.clone(),
+            rulestring: self.rulestring.clone(),
+            cells: self.cells()?,
+            ..Default::default()
+        };
+        pattern.normalise();
+        Ok(pattern)
+    }
+
+    /// Every non-dead cell relative to the centre of the root square
+    fn cells(&self) -> Result<Vec<(i32, i32, CellState)>, PatternError> {
+        let level = self.level();
+        // Coordinates must fit the grid once the root is centred
+        if level > 31 {
+            return Err(PatternError::OutOfBounds);
+        }
+        let half = if level == 0 { 0 } else { 1i64 << (level - 1) };
+        let states = numbered_states(self.rule());
+        let mut cells = Vec::new();
+        let mut stack = vec![(self.root(), -half, -half)];
+        while let Some((index, x, y)) = stack.pop() {
+            if index == 0 {
+                continue;
+            }
+            match &self.nodes[index] {
+                Node::Leaf(rows) => {
+                    for (dy, row) in rows.iter().enumerate() {
+                        for dx in (0..8).filter(|dx| row & (1 << dx) != 0) {
+                            cells.push(((x + dx) as i32, (y + dy as i64) as i32, states[0]));
+                        }
+                    }
+                }
+                Node::Quad { level: 1, children } => {
+                    for (quadrant, &state) in children.iter().enumerate() {
+                        if state != 0 {
+                            let state = *states.get(state - 1).ok_or(PatternError::BadNode(format!("state {}", state)))?;
+                            cells.push(((x + (quadrant & 1) as i64) as i32, (y + (quadrant >> 1) as i64) as i32, state));
+                        }
+                    }
+                }
+                Node::Quad { level, children } => {
+                    let half = 1i64 << (level - 1);
+                    for (quadrant, &child) in children.iter().enumerate() {
+                        stack.push((child, x + half * (quadrant & 1) as i64, y + half * (quadrant >> 1) as i64));
+                    }
+                }
+            }
+        }
+        Ok(cells)
+    }
+}
+
+/// Parse macrocell text into its node table
+pub fn parse_macrocell(text: &str) -> Result<Macrocell, PatternError> {
+    let mut mc = Macrocell { nodes: vec![Node::Quad { level: 0, children: [0; 4] }], ..Default::default() };
+    let mut lines = text.lines().map(str::trim);
+    match lines.next() {
+        Some(header) if header.starts_with("[M2]") => {}
+        other => return Err(PatternError::BadHeader(other.unwrap_or("").to_string())),
+    }
+
+    for line in lines.filter(|l| !l.is_empty()) {
+        if let Some(rest) = line.strip_prefix("#R") {
+            mc.rulestring = Some(rest.trim().to_string());
+        } else if let Some(rest) = line.strip_prefix("#G") {
+            mc.generation = rest.trim().parse().ok();
+        } else if let Some(rest) = line.strip_prefix("#C").or_else(|| line.strip_prefix("#D")) {
+            mc.comments.push(rest.trim().to_string());
+        } else if line.starts_with('#') {
+            continue;
+        } else if line.starts_with(['.', '*', '$']) {
+            let mut rows = [0u8; 8];
+            for (y, row) in line.split('$').take(8).enumerate() {
+                for (x, ch) in row.chars().enumerate() {
+                    match ch {
+                        '.' => {}
+                        '*' if x < 8 => rows[y] |= 1 << x,
+                        _ => return Err(PatternError::BadNode(line.to_string())),
+                    }
+                }
+            }
+            mc.nodes.push(Node::Leaf(rows));
+        } else {
+            let numbers: Vec<usize> = line
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|_| PatternError::BadNode(line.to_string()))?;
+            let [level, nw, ne, sw, se] = numbers[..] else { return Err(PatternError::BadNode(line.to_string())) };
+            let children = [nw, ne, sw, se];
+            let level = u8::try_from(level).ok().filter(|l| (1..64).contains(l)).ok_or(PatternError::BadNode(line.to_string()))?;
+            // Children must already exist and sit exactly one level down
+            if level > 1
+                && children.iter().any(|&c| c != 0 && mc.nodes.get(c).is_none_or(|n| n.level() != level - 1))
+            {
+                return Err(PatternError::BadNode(line.to_string()));
+            }
+            mc.nodes.push(Node::Quad { level, children });
+        }
+    }
+    Ok(mc)
+}
+
+/// Encode the grid as a macrocell file under `rule`
+pub fn write_macrocell(grid: &InfiniteGrid, rule: RuleType, generation: u64) -> String {
+    let states = numbered_states(Some(rule));
+    let multi_state = states.len() > 1;
+    let mut out = format!("[M2] (gameofdeath)\n#R {}\n#G {}\n", rule.rulestring(), generation);
+
+    let Some(bounds) = grid.bounds() else {
+        return out;
+    };
+    let extent = (bounds.max_x - bounds.min_x).max(bounds.max_y - bounds.min_y) as i64 + 1;
+    let mut level = if multi_state { 1 } else { LEAF_LEVEL };
+    while (1i64 << level) < extent {
+        level += 1;
+    }
+
+    let mut writer = TreeWriter { grid, states, lines: Vec::new(), index: HashMap::new() };
+    // The tree is built in file orientation, rows counting down from the grid's top
+    writer.build(level, bounds.min_x as i64, -(bounds.max_y as i64), multi_state);
+    for line in writer.lines {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Builds node lines bottom-up, giving identical subtrees one shared line
+struct TreeWriter<'a> {
+    grid: &'a InfiniteGrid,
+    states: &'static [CellState],
+    lines: Vec<String>,
+    index: HashMap<String, usize>,
+}
+
+impl TreeWriter<'_> {
+    fn build(&mut self, level: u8, x: i64, y: i64, multi_state: bool) -> usize {
+        let size = 1i64 << level;
+        let clamp = |v: i64| v.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        // File row `y` is grid row `-y`
+        let flip = |y: i64| clamp(-y);
+        if self.grid.cells_in_region(clamp(x), clamp(x + size - 1), flip(y + size - 1), flip(y)).next().is_none() {
+            return 0;
+        }
+
+        let line = if !multi_state && level == LEAF_LEVEL {
+            let rows: Vec<String> = (0..8)
+                .map(|dy| {
+                    let row: String = (0..8)
+                        .map(|dx| if self.grid.is_alive(clamp(x + dx), flip(y + dy)) { '*' } else { '.' })
+                        .collect();
+                    row.trim_end_matches('.').to_string()
+                })
+                .collect();
+            let last = rows.iter().rposition(|r| !r.is_empty()).unwrap_or(0);
+            rows[..=last].iter().map(|r| format!("{}$", r)).collect()
+        } else if level == 1 {
+            let state = |dx: i64, dy: i64| {
+                let cell = self.grid.get(clamp(x + dx), flip(y + dy));
+                self.states.iter().position(|&s| s == cell).map_or(0, |i| i + 1)
+            };
+            format!("1 {} {} {} {}", state(0, 0), state(1, 0), state(0, 1), state(1, 1))
+        } else {
+            let half = size / 2;
+            let nw = self.build(level - 1, x, y, multi_state);
+            let ne = self.build(level - 1, x + half, y, multi_state);
+            let sw = self.build(level - 1, x, y + half, multi_state);
+            let se = self.build(level - 1, x + half, y + half, multi_state);
+            format!("{} {} {} {} {}", level, nw, ne, sw, se)
+        };
+
+        if let Some(&existing) = self.index.get(&line) {
+            return existing;
+        }
+        self.lines.push(line.clone());
+        self.index.insert(line, self.lines.len());
+        self.lines.len()
+    }
+}
+
+pub fn load_macrocell_file(path: impl AsRef<Path>) -> Result<Macrocell, PatternError> {
+    parse_macrocell(&std::fs::read_to_string(path)?)
+}
+
+pub fn save_macrocell_file(path: impl AsRef<Path>, grid: &InfiniteGrid, rule: RuleType, generation: u64) -> Result<(), PatternError> {
+    std::fs::write(path, write_macrocell(grid, rule, generation))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_counts_without_expanding() {
+        let mut grid = InfiniteGrid::new();
+        // Two identical gliders far apart share their leaf lines
+        for &(ox, oy) in &[(0, 0), (1000, 0)] {
+            for &(x, y) in &[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+                grid.set(ox + x, oy + y, CellState::Alive);
+            }
+        }
+        let text = write_macrocell(&grid, RuleType::Conway, 42);
+        assert!(text.lines().filter(|l| l.starts_with(['.', '*', '$'])).count() == 1);
+
+        let mc = parse_macrocell(&text).unwrap();
+        assert_eq!(mc.rule(), Some(RuleType::Conway));
+        assert_eq!(mc.generation, Some(42));
+        assert_eq!(mc.population(), 10);
+        assert!(matches!(mc.to_pattern(9), Err(PatternError::TooLarge(10))));
+        let mut original = Pattern::from_grid(&grid, RuleType::Conway);
+        original.rulestring = None;
+        let mut loaded = mc.to_pattern(10).unwrap();
+        loaded.rulestring = None;
+        assert_eq!(loaded, original);
+
+        // A 2^30-wide square of blocks stays a handful of lines
+        let mut huge = String::from("[M2]\n#R B3/S23\n**$**$\n");
+        for level in 4..=30 {
+            huge.push_str(&format!("{} {} {} {} {}\n", level, level - 3, level - 3, level - 3, level - 3));
+        }
+        assert_eq!(parse_macrocell(&huge).unwrap().population(), 4 << 54);
+    }
+}
@@ -0,0 +1,81 @@
+//! Downloading patterns over HTTPS (`net` feature).
+//!
+//! Fetches go through the system `curl`, which ships with macOS, Windows 10+ and nearly every
+//! Linux install, so the feature adds no TLS stack to the build. LifeWiki article links
+//! (`https://conwaylife.com/wiki/Gosper_glider_gun`) are rewritten to the wiki's pattern file
+//! for that article, so a link copied from the browser's address bar works as-is.
+
+use std::process::{Command, Stdio};
+use super::{load_pattern, parse_plaintext, Pattern, PatternError};
+
+/// Seconds a download may take before it's abandoned
+const DOWNLOAD_TIMEOUT_SECS: u32 = 15;
+
+/// Largest file accepted, in bytes
+const MAX_DOWNLOAD_BYTES: u32 = 8 * 1024 * 1024;
+
+const LIFEWIKI_HOSTS: [&str; 2] = ["conwaylife.com", "www.conwaylife.com"];
+
+/// The file to download for `url`: HTTPS only, with LifeWiki article pages mapped to their
+/// RLE file
+pub fn pattern_file_url(url: &str) -> Result<String, PatternError> {
+    let url = url.trim();
+    let Some(rest) = url.strip_prefix("https://") else {
+        return Err(PatternError::Network(format!("only https:// links can be loaded: {}", url)));
+    };
+    let rest = rest.split(['?', '#']).next().unwrap_or(rest);
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    if host.is_empty() {
+        return Err(PatternError::Network(format!("no host in {}", url)));
+    }
+    if let Some(article) = path.strip_prefix("wiki/").filter(|_| LIFEWIKI_HOSTS.contains(&host)) {
+        // LifeWiki names pattern files after the article, lowercased with punctuation removed
+        let file: String = article.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_lowercase()).collect();
+        if !file.is_empty() {
+            return Ok(format!("https://{}/patterns/{}.rle", host, file));
+        }
+    }
+    Ok(format!("https://{}/{}", host, path))
+}
+
+/// Download and parse the pattern at `url`
+pub fn load_pattern_url(url: &str) -> Result<Pattern, PatternError> {
+    let file_url = pattern_file_url(url)?;
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", "--proto", "=https"])
+        .args(["--max-time", &DOWNLOAD_TIMEOUT_SECS.to_string()])
+        .args(["--max-filesize", &MAX_DOWNLOAD_BYTES.to_string()])
+        .arg(&file_url)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| PatternError::Network(format!("could not run curl: {}", e)))?;
+    if !output.status.success() {
+        let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(PatternError::Network(format!("{}: {}", file_url, reason)));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    if file_url.to_ascii_lowercase().ends_with(".cells") {
+        parse_plaintext(&text)
+    } else {
+        load_pattern(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifewiki_articles_map_to_pattern_files() {
+        assert_eq!(
+            pattern_file_url("https://conwaylife.com/wiki/Gosper_glider_gun#Variants").unwrap(),
+            "https://conwaylife.com/patterns/gosperglidergun.rle"
+        );
+        assert_eq!(
+            pattern_file_url(" https://conwaylife.com/patterns/acorn.cells ").unwrap(),
+            "https://conwaylife.com/patterns/acorn.cells"
+        );
+        assert_eq!(pattern_file_url("https://example.org/wiki/Glider").unwrap(), "https://example.org/wiki/Glider");
+        assert!(pattern_file_url("http://conwaylife.com/patterns/acorn.rle").is_err());
+    }
+}
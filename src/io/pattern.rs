@@ -0,0 +1,96 @@
+//! Format-neutral pattern loaded from or written to a file.
+//!
+//! Cells use file orientation: `(0, 0)` is the top-left of the bounding box and rows count
+//! downwards. The grid's y points up the screen, so moving cells between the two flips y:
+//! `from_grid_cells` on the way in, `grid_cells` on the way out.
+
+use crate::infinite_grid::InfiniteGrid;
+use crate::start_screen::RuleType;
+use crate::CellState;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pattern {
+    pub name: Option<String>,
+    /// Free-text comment lines, in file order
+    pub comments: Vec<String>,
+    /// Rule exactly as written in the file, kept so unknown rules survive a round trip
+    pub rulestring: Option<String>,
+    /// Non-dead cells as `(x, y, state)`
+    pub cells: Vec<(i32, i32, CellState)>,
+}
+
+impl Pattern {
+    /// Snapshot every non-dead cell of `grid`, moved so the bounding box starts at `(0, 0)`
+    pub fn from_grid(grid: &InfiniteGrid, rule: RuleType) -> Self {
+        Self::from_grid_cells(grid.cells(), rule)
+    }
+
+    /// A pattern of cells in grid coordinates, flipped into file orientation and moved so the
+    /// bounding box starts at `(0, 0)`
+    pub fn from_grid_cells(cells: impl IntoIterator<Item = (i32, i32, CellState)>, rule: RuleType) -> Self {
+        let mut pattern = Self {
+            rulestring: Some(rule.rulestring()),
+            cells: cells.into_iter().map(|(x, y, state)| (x, -y, state)).collect(),
+            ..Default::default()
+        };
+        pattern.normalise();
+        pattern
+    }
+
+    /// The cells in grid coordinates, the right way up on screen, with the bottom-left of the
+    /// bounding box at `(0, 0)`
+    pub fn grid_cells(&self) -> impl Iterator<Item = (i32, i32, CellState)> + '_ {
+        let (min_x, max_y) = self.bounds().map_or((0, 0), |(x0, _, _, y1)| (x0, y1));
+        self.cells.iter().map(move |&(x, y, state)| (x - min_x, max_y - y, state))
+    }
+
+    /// The file's rule, if it is one this game can run
+    pub fn rule(&self) -> Option<RuleType> {
+        self.rulestring.as_deref().and_then(RuleType::from_rulestring)
+    }
+
+    /// Translate the cells so the bounding box starts at `(0, 0)` and sort them row by row
+    pub fn normalise(&mut self) {
+        let min_x = self.cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let min_y = self.cells.iter().map(|c| c.1).min().unwrap_or(0);
+        for cell in &mut self.cells {
+            cell.0 -= min_x;
+            cell.1 -= min_y;
+        }
+        self.cells.sort_unstable_by_key(|&(x, y, _)| (y, x));
+    }
+
+    /// `(min_x, min_y, max_x, max_y)` of the cells, or `None` when empty
+    pub fn bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        self.cells.iter().fold(None, |acc, &(x, y, _)| {
+            Some(match acc {
+                None => (x, y, x, y),
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            })
+        })
+    }
+
+    pub fn width(&self) -> i32 {
+        self.bounds().map_or(0, |(x0, _, x1, _)| x1 - x0 + 1)
+    }
+
+    pub fn height(&self) -> i32 {
+        self.bounds().map_or(0, |(_, y0, _, y1)| y1 - y0 + 1)
+    }
+
+    /// Write the cells into `grid` the right way up, with the bottom-left of the bounding box
+    /// at `(ox, oy)`
+    pub fn insert_into(&self, grid: &mut InfiniteGrid, ox: i32, oy: i32) {
+        grid.insert_cells(self.grid_cells(), ox, oy);
+    }
+}
+
+/// States a rule uses beyond dead, in the order pattern files number them (Golly convention)
+pub(crate) fn numbered_states(rule: Option<RuleType>) -> &'static [CellState] {
+    match rule {
+        Some(RuleType::Brian) => &[CellState::Alive, CellState::Dying],
+        Some(RuleType::WireWorld) => &[CellState::ElectronHead, CellState::ElectronTail, CellState::Wire],
+        Some(RuleType::Immigration) => &[CellState::SpeciesA, CellState::SpeciesB],
+        _ => &[CellState::Alive],
+    }
+}
@@ -0,0 +1,31 @@
+//! Plaintext (`.cells`) reader.
+//!
+//! `!` lines are comments (`!Name: ...` sets the pattern name); every other line is a row of
+//! `.` (dead) and `O` (alive). Some collections use `*` for alive cells, which is accepted too.
+
+use crate::CellState;
+use super::pattern::Pattern;
+use super::PatternError;
+
+pub fn parse_plaintext(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let mut y = 0;
+    for line in text.lines().map(str::trim_end) {
+        if let Some(comment) = line.strip_prefix('!') {
+            match comment.strip_prefix("Name:") {
+                Some(name) => pattern.name = Some(name.trim().to_string()),
+                None => pattern.comments.push(comment.trim().to_string()),
+            }
+            continue;
+        }
+        for (x, ch) in line.chars().enumerate() {
+            match ch {
+                '.' => {}
+                'O' | '*' => pattern.cells.push((x as i32, y, CellState::Alive)),
+                _ => return Err(PatternError::InvalidChar(ch)),
+            }
+        }
+        y += 1;
+    }
+    Ok(pattern)
+}
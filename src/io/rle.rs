@@ -0,0 +1,215 @@
+//! RLE reader and writer for the infinite grid.
+//!
+//! Handles the `x = .., y = .., rule = ..` header, `#N`/`#C`/`#O`/`#r` comment lines and
+//! multi-state bodies (`.` and `A`..`X`, numbered per Golly for Brian's Brain, WireWorld and
+//! Immigration). Two-state patterns are written with `b`/`o` so any Life program can read them.
+
+use std::path::Path;
+use super::pattern::{numbered_states, Pattern};
+use super::PatternError;
+
+/// Longest body line written, as recommended by the format description
+const LINE_WIDTH: usize = 70;
+
+/// Parse RLE text into a `Pattern`
+pub fn parse_rle(text: &str) -> Result<Pattern, PatternError> {
+    let mut pattern = Pattern::default();
+    let mut body = String::new();
+    let mut seen_header = false;
+
+    for line in text.lines().map(str::trim) {
+        if let Some(comment) = line.strip_prefix('#') {
+            let mut chars = comment.chars();
+            let kind = chars.next();
+            let rest = chars.as_str().trim();
+            match kind {
+                Some('N') => pattern.name = Some(rest.to_string()),
+                Some('C' | 'c' | 'O') => pattern.comments.push(rest.to_string()),
+                Some('r') => pattern.rulestring = Some(rest.to_string()),
+                _ => {}
+            }
+        } else if !seen_header && line.starts_with('x') {
+            seen_header = true;
+            let bad_header = || PatternError::BadHeader(line.to_string());
+            // The rule runs to the end of the line and may hold commas itself (`B3/S23:T100,100`)
+            let (fields, rule) = match line.find("rule") {
+                Some(start) => (&line[..start], Some(&line[start..])),
+                None => (line, None),
+            };
+            for field in fields.split(',').filter(|field| !field.trim().is_empty()) {
+                let (key, value) = field.split_once('=').ok_or_else(bad_header)?;
+                if let "x" | "y" = key.trim() {
+                    value.trim().parse::<u32>().map_err(|_| bad_header())?;
+                }
+            }
+            if let Some(rule) = rule {
+                let (_, value) = rule.split_once('=').ok_or_else(bad_header)?;
+                pattern.rulestring = Some(value.trim().to_string());
+            }
+        } else {
+            body.push_str(line);
+            if line.contains('!') {
+                break;
+            }
+        }
+    }
+
+    let states = numbered_states(pattern.rule());
+    let (mut x, mut y) = (0i32, 0i32);
+    let mut run: Option<i32> = None;
+    for ch in body.chars() {
+        let count = run.unwrap_or(1);
+        match ch {
+            '0'..='9' => {
+                let digit = ch.to_digit(10).unwrap() as i32;
+                run = Some(run.unwrap_or(0).checked_mul(10).and_then(|n| n.checked_add(digit)).ok_or(PatternError::InvalidRun)?);
+                continue;
+            }
+            'b' | '.' => x = x.checked_add(count).ok_or(PatternError::InvalidRun)?,
+            'o' | 'A'..='X' => {
+                let index = if ch == 'o' { 0 } else { ch as usize - 'A' as usize };
+                let state = *states.get(index).ok_or(PatternError::InvalidChar(ch))?;
+                let end = x.checked_add(count).ok_or(PatternError::InvalidRun)?;
+                pattern.cells.extend((x..end).map(|cx| (cx, y, state)));
+                x = end;
+            }
+            '$' => {
+                y = y.checked_add(count).ok_or(PatternError::InvalidRun)?;
+                x = 0;
+            }
+            '!' => break,
+            c if c.is_whitespace() => continue,
+            _ => return Err(PatternError::InvalidChar(ch)),
+        }
+        if run == Some(0) {
+            return Err(PatternError::InvalidRun);
+        }
+        run = None;
+    }
+    Ok(pattern)
+}
+
+/// Encode a pattern as RLE text
+pub fn write_rle(pattern: &Pattern) -> String {
+    let mut pattern = pattern.clone();
+    pattern.normalise();
+    let states = numbered_states(pattern.rule());
+    let letters = states.len() > 1;
+
+    let mut out = String::new();
+    if let Some(name) = &pattern.name {
+        out.push_str(&format!("#N {}\n", name));
+    }
+    for comment in &pattern.comments {
+        out.push_str(&format!("#C {}\n", comment));
+    }
+    out.push_str(&format!("x = {}, y = {}", pattern.width(), pattern.height()));
+    if let Some(rule) = &pattern.rulestring {
+        out.push_str(&format!(", rule = {}", rule));
+    }
+    out.push('\n');
+
+    // Collect (run, tag) tokens; trailing dead cells in a row are never written
+    let mut tokens: Vec<(i32, char)> = Vec::new();
+    let mut push = |run: i32, tag: char| match tokens.last_mut() {
+        Some((n, last)) if *last == tag => *n += run,
+        _ => tokens.push((run, tag)),
+    };
+    let (mut x, mut y) = (0, 0);
+    for &(cx, cy, state) in &pattern.cells {
+        if cy > y {
+            push(cy - y, '$');
+            y = cy;
+            x = 0;
+        }
+        if cx > x {
+            push(cx - x, if letters { '.' } else { 'b' });
+        }
+        let index = states.iter().position(|&s| s == state).unwrap_or(0);
+        push(1, if letters { (b'A' + index as u8) as char } else { 'o' });
+        x = cx + 1;
+    }
+    push(1, '!');
+
+    let mut line = String::new();
+    for (run, tag) in tokens {
+        let token = if run == 1 { tag.to_string() } else { format!("{}{}", run, tag) };
+        if line.len() + token.len() > LINE_WIDTH {
+            out.push_str(&line);
+            out.push('\n');
+            line.clear();
+        }
+        line.push_str(&token);
+    }
+    out.push_str(&line);
+    out.push('\n');
+    out
+}
+
+pub fn load_rle_file(path: impl AsRef<Path>) -> Result<Pattern, PatternError> {
+    parse_rle(&std::fs::read_to_string(path)?)
+}
+
+pub fn save_rle_file(path: impl AsRef<Path>, pattern: &Pattern) -> Result<(), PatternError> {
+    std::fs::write(path, write_rle(pattern))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::infinite_grid::InfiniteGrid;
+    use crate::start_screen::RuleType;
+    use crate::CellState;
+    use super::*;
+
+    const GOSPER_GUN: &str = "#N Gosper glider gun
+#C The first known gun, found by Bill Gosper in 1970.
+x = 36, y = 9, rule = B3/S23
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b
+obo$10bo5bo7bo$11bo3bo$12b2o!
+";
+
+    #[test]
+    fn reads_and_round_trips() {
+        let gun = parse_rle(GOSPER_GUN).unwrap();
+        assert_eq!(gun.name.as_deref(), Some("Gosper glider gun"));
+        assert_eq!(gun.rule(), Some(RuleType::Conway));
+        assert_eq!(gun.cells.len(), 36);
+        assert_eq!((gun.width(), gun.height()), (36, 9));
+        assert_eq!(parse_rle(&write_rle(&gun)).unwrap(), gun);
+
+        let wire = parse_rle("x = 4, y = 2, rule = WireWorld\nAB2C$3.C!").unwrap();
+        assert_eq!(wire.cells[0], (0, 0, CellState::ElectronHead));
+        assert_eq!(wire.cells[4], (3, 1, CellState::Wire));
+        assert!(write_rle(&wire).ends_with("AB2C$3.C!\n"));
+
+        assert!(matches!(parse_rle("x = 2, y = 1\n2q!"), Err(PatternError::InvalidChar('q'))));
+
+        let bounded = parse_rle("x = 3, y = 3, rule = B3/S23:T100,100\nbo$2bo$3o!").unwrap();
+        assert_eq!(bounded.rulestring.as_deref(), Some("B3/S23:T100,100"));
+        assert_eq!(bounded.cells.len(), 5);
+        assert!(matches!(parse_rle("x = 1, y = 1, z\no!"), Err(PatternError::BadHeader(_))));
+        assert!(matches!(parse_rle("2147483647b2147483647b!"), Err(PatternError::InvalidRun)));
+        assert!(matches!(parse_rle("2147483647$2147483647$!"), Err(PatternError::InvalidRun)));
+    }
+
+    /// Rows run down the file and the grid's y up the screen, so a glider drawn heading
+    /// down-right keeps heading down-right once placed
+    #[test]
+    fn placed_glider_heads_down_right_on_screen() {
+        let mut grid = InfiniteGrid::new();
+        parse_rle("x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!").unwrap().insert_into(&mut grid, 0, 0);
+        let sorted = |grid: &InfiniteGrid| {
+            let mut cells: Vec<_> = grid.cells().collect();
+            cells.sort_unstable_by_key(|c| (c.0, c.1));
+            cells
+        };
+        let start = sorted(&grid);
+        for _ in 0..4 {
+            grid.update(RuleType::Conway);
+        }
+        let moved: Vec<_> = start.iter().map(|&(x, y, state)| (x + 1, y - 1, state)).collect();
+        assert_eq!(sorted(&grid), moved);
+        assert_eq!(Pattern::from_grid(&grid, RuleType::Conway).cells, parse_rle("bo$2bo$3o!").unwrap().cells);
+    }
+}
@@ -0,0 +1,236 @@
+//! Isometric cubes.
+//!
+//! With `CellRenderConfig::isometric` on, the cell sprites stand down and every live cell in
+//! view becomes a small cube on a 2:1 isometric plane, the grid's x axis running up and to
+//! the right and its y axis up and to the left. Cubes grow from `MIN_HEIGHT` to
+//! `MAX_HEIGHT` cell sizes tall as they age towards `CellRenderConfig::age_span`, so stable
+//! structures rise out of the churn. All cubes go into one vertex-coloured mesh, painted back
+//! to front, rebuilt when the grid or the view moves on. The plane is shifted so the cell at
+//! the centre of the camera stays there, but the editing tools still aim at the flat grid:
+//! this mode is meant for watching, recording and screenshots.
+
+use std::cmp::Reverse;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::window::PrimaryWindow;
+use crate::camera::GameCamera;
+use crate::cell_renderer::CellRenderConfig;
+use crate::infinite_grid::InfiniteGrid;
+use crate::start_screen::{GameState, InGame};
+use crate::theme::Theme;
+use crate::CellState;
+
+/// Half the width of a cell's diamond, in cell sizes; the height is half of that
+const HALF_WIDTH: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// Cube heights of newborn and fully aged cells, in cell sizes
+const MIN_HEIGHT: f32 = 0.25;
+const MAX_HEIGHT: f32 = 1.5;
+
+/// Fraction of the diamond a cube covers, leaving a seam between neighbours
+const CUBE_FILL: f32 = 0.9;
+
+/// Shading of the left and right faces relative to the top
+const LEFT_SHADE: f32 = 0.7;
+const RIGHT_SHADE: f32 = 0.5;
+
+/// Cubes drawn at most
+const MAX_CUBES: usize = 30_000;
+
+/// The view centre is rounded to chunks this many cells across, so panning rebuilds the
+/// mesh only now and then
+const CHUNK: i32 = 8;
+
+/// Where the cell sprites would be
+const ISO_Z: f32 = 0.0;
+
+/// A cell drawn as a cube
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cube {
+    pub x: i32,
+    pub y: i32,
+    /// In cell sizes
+    pub height: f32,
+    pub color: Color,
+}
+
+/// Centre of the footprint of cell `(x, y)` on the isometric plane
+pub fn iso_position(x: f32, y: f32, cell_size: f32) -> Vec2 {
+    let half_width = HALF_WIDTH * cell_size;
+    Vec2::new((x - y) * half_width, (x + y) * half_width / 2.0)
+}
+
+/// Height of a cube `age` generations old
+pub fn cube_height(age: u64, span: u64) -> f32 {
+    let t = (age as f32 / span.max(1) as f32).min(1.0);
+    MIN_HEIGHT + (MAX_HEIGHT - MIN_HEIGHT) * t
+}
+
+/// Cubes with their top and two visible sides, painted back (high x + y) to front
+pub fn cube_mesh(mut cubes: Vec<Cube>, cell_size: f32) -> Mesh {
+    cubes.sort_by_key(|cube| Reverse(cube.x + cube.y));
+    let half_width = HALF_WIDTH * cell_size * CUBE_FILL;
+    let half_height = half_width / 2.0;
+
+    let mut positions: Vec<[f32; 3]> = Vec::with_capacity(cubes.len() * 12);
+    let mut colors: Vec<[f32; 4]> = Vec::with_capacity(cubes.len() * 12);
+    let mut indices: Vec<u32> = Vec::with_capacity(cubes.len() * 18);
+    let mut quad = |corners: [Vec2; 4], color: LinearRgba| {
+        let first = positions.len() as u32;
+        positions.extend(corners.map(|corner| [corner.x, corner.y, 0.0]));
+        colors.extend([color.to_f32_array(); 4]);
+        indices.extend([first, first + 1, first + 2, first, first + 2, first + 3]);
+    };
+    for cube in cubes {
+        let centre = iso_position(cube.x as f32, cube.y as f32, cell_size);
+        let lift = Vec2::new(0.0, cube.height * cell_size);
+        let (left, right) = (centre - Vec2::new(half_width, 0.0), centre + Vec2::new(half_width, 0.0));
+        let (top, bottom) = (centre + Vec2::new(0.0, half_height), centre - Vec2::new(0.0, half_height));
+        let color = cube.color.to_linear();
+        let shade = |factor: f32| LinearRgba::new(color.red * factor, color.green * factor, color.blue * factor, color.alpha);
+
+        quad([left + lift, bottom + lift, bottom, left], shade(LEFT_SHADE));
+        quad([bottom + lift, right + lift, right, bottom], shade(RIGHT_SHADE));
+        quad([left + lift, top + lift, right + lift, bottom + lift], color);
+    }
+
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+#[derive(Component)]
+pub struct IsoLayer;
+
+/// The cube mesh and what it was last built from
+#[derive(Resource, Default)]
+pub struct IsoState {
+    mesh: Option<Handle<Mesh>>,
+    /// Chunk the view was centred on and the cells reached around it
+    region: Option<((i32, i32), i32)>,
+    grid_version: u64,
+}
+
+/// The game camera, kept apart from the cube layer's transform
+type CameraView<'w, 's> = Query<'w, 's, (&'static Transform, &'static OrthographicProjection), (With<GameCamera>, Without<IsoLayer>)>;
+
+pub struct IsometricPlugin;
+
+impl Plugin for IsometricPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IsoState>()
+            .add_systems(Update, render_cubes.run_if(in_state(GameState::Playing)))
+            .add_systems(OnExit(InGame), despawn_cubes);
+    }
+}
+
+/// Cubes for the live cells within `reach` of `centre` that land on screen
+fn visible_cubes(grid: &InfiniteGrid, config: &CellRenderConfig, theme: &Theme, centre: (i32, i32), reach: (f32, f32)) -> Vec<Cube> {
+    let (across, up) = reach;
+    let square = ((across + up) / 2.0).ceil() as i32;
+    let (cx, cy) = centre;
+    grid.cell_states_in_region(cx - square, cx + square, cy - square, cy + square)
+        .filter(|&(x, y, state)| {
+            let (dx, dy) = ((x - cx) as f32, (y - cy) as f32);
+            state != CellState::Dead && (dx - dy).abs() <= across && (dx + dy).abs() <= up
+        })
+        .take(MAX_CUBES)
+        .map(|(x, y, state)| {
+            let age = grid.meta(x, y).map(|meta| meta.age(grid.generation()));
+            let color = match age {
+                Some(age) if config.generation_colors && state == CellState::Alive => theme.age_color(age, config.age_span),
+                _ => theme.cells.color(state),
+            };
+            Cube { x, y, height: age.map_or(MIN_HEIGHT, |age| cube_height(age, config.age_span)), color }
+        })
+        .collect()
+}
+
+/// Keep the cube mesh covering the view, lined up so the cell at the camera centre stays put
+fn render_cubes(
+    mut commands: Commands,
+    (grid, config, theme): (Res<InfiniteGrid>, Res<CellRenderConfig>, Res<Theme>),
+    camera_query: CameraView,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut layer_query: Query<(&mut Transform, &mut Visibility), With<IsoLayer>>,
+    mut state: ResMut<IsoState>,
+    (mut meshes, mut materials): (ResMut<Assets<Mesh>>, ResMut<Assets<ColorMaterial>>),
+) {
+    if !config.isometric || config.heatmap_active {
+        for (_, mut visibility) in layer_query.iter_mut() {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+        state.region = None;
+        return;
+    }
+    let (Ok((camera_transform, projection)), Ok(window)) = (camera_query.get_single(), windows.get_single()) else { return };
+    let cell_size = config.cell_size;
+    let camera = camera_transform.translation.truncate();
+    let focus = camera / cell_size;
+
+    // Diagonal extents of the view in cells, padded by a chunk and the tallest cube
+    let half_width = HALF_WIDTH * cell_size;
+    let view = Vec2::new(window.width(), window.height()) * projection.scale / 2.0;
+    let padding = CHUNK as f32 + 2.0;
+    let across = view.x / half_width + padding;
+    let up = (view.y + MAX_HEIGHT * cell_size) / (half_width / 2.0) + padding;
+    let centre = ((focus.x.round() as i32).div_euclid(CHUNK) * CHUNK, (focus.y.round() as i32).div_euclid(CHUNK) * CHUNK);
+    let region = (centre, (across + up) as i32);
+
+    let stale = state.region != Some(region) || state.grid_version != grid.version() || state.mesh.is_none();
+    if stale || theme.is_changed() || config.is_changed() {
+        let mesh = cube_mesh(visible_cubes(&grid, &config, &theme, centre, (across, up)), cell_size);
+        match state.mesh.as_ref().and_then(|handle| meshes.get_mut(handle)) {
+            Some(existing) => *existing = mesh,
+            None => state.mesh = Some(meshes.add(mesh)),
+        }
+        state.region = Some(region);
+        state.grid_version = grid.version();
+    }
+    let Some(handle) = state.mesh.clone() else { return };
+    let translation = (camera - iso_position(focus.x, focus.y, cell_size)).extend(ISO_Z);
+
+    if let Ok((mut transform, mut visibility)) = layer_query.get_single_mut() {
+        transform.translation = translation;
+        visibility.set_if_neq(Visibility::Visible);
+    } else {
+        commands.spawn((
+            Mesh2d(handle),
+            MeshMaterial2d(materials.add(ColorMaterial::default())),
+            Transform::from_translation(translation),
+            IsoLayer,
+        ));
+    }
+}
+
+fn despawn_cubes(mut commands: Commands, layer_query: Query<Entity, With<IsoLayer>>, mut state: ResMut<IsoState>) {
+    for entity in layer_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    state.region = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::mesh::VertexAttributeValues;
+
+    #[test]
+    fn cubes_stand_on_the_diamond_grid_back_to_front() {
+        assert_eq!(iso_position(1.0, 1.0, 10.0).x, 0.0);
+        assert!(iso_position(1.0, 0.0, 10.0).x > 0.0 && iso_position(0.0, 1.0, 10.0).x < 0.0);
+        assert_eq!(cube_height(0, 100), MIN_HEIGHT);
+        assert_eq!(cube_height(500, 100), MAX_HEIGHT);
+
+        let cube = |x, y| Cube { x, y, height: 1.0, color: Color::WHITE };
+        let mesh = cube_mesh(vec![cube(0, 0), cube(3, 3)], 10.0);
+        assert_eq!(mesh.count_vertices(), 24);
+        assert_eq!(mesh.indices().map(|indices| indices.len()), Some(36));
+
+        // The cube further back, higher up the screen, comes first
+        let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) else { panic!() };
+        assert!(positions[0][1] > positions[12][1]);
+    }
+}
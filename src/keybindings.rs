@@ -0,0 +1,503 @@
+//! Remappable keyboard bindings.
+//!
+//! Every single-key action in game is looked up through the `Keybindings` resource instead
+//! of a hardcoded `KeyCode`, so players on AZERTY, Dvorak and other layouts can move them.
+//! Bindings are overridden from the `[keybindings]` table of the config file, one
+//! `action = "Key"` line per change:
+//!
+//! ```toml
+//! [keybindings]
+//! pan_up = "Z"
+//! pan_left = "Q"
+//! pause = "Enter"
+//! ```
+//!
+//! Keys are written as a letter, a digit, `F1`-`F12`, a punctuation character or any Bevy
+//! `KeyCode` name (`Space`, `PageUp`, `BracketLeft`, ...). Modifiers (Shift, Alt, Ctrl) keep
+//! their fixed meanings on top of whatever key an action is bound to. Keys can also be
+//! rebound in game on the controls screen (`controls_screen`), which writes the table back.
+
+use std::collections::HashMap;
+use bevy::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    Pause,
+    SpeedUp,
+    SlowDown,
+    Step,
+    ClearGrid,
+    Reset,
+    Menu,
+    AudioToggle,
+    AudioStatus,
+    VolumeUp,
+    VolumeDown,
+    MuteDrone,
+    MuteSamples,
+    MuteGroove,
+    MuteEffects,
+    Sequencer,
+    BrushSmaller,
+    BrushLarger,
+    Spray,
+    BrushShape,
+    CycleTool,
+    Symmetry,
+    Pattern1,
+    Pattern2,
+    Pattern3,
+    StampRotate,
+    StampFlip,
+    StampDrop,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    ResetCamera,
+    ToggleHud,
+    SynthPanel,
+    Browser,
+    AddLayer,
+    QuickSave,
+    QuickLoad,
+    Screenshot,
+    RecordGif,
+    RecordVideo,
+    ExportSvg,
+    AgeColors,
+    Trails,
+    GridLines,
+    NextTheme,
+    Bloom,
+    Minimap,
+    ActivityHeat,
+    RenderPanel,
+    Copy,
+    Paste,
+    PatternMarkers,
+    FitView,
+    PopulationGraph,
+    RuleEditor,
+    RunGenerations,
+    StepBack,
+    Settings,
+    CommandPalette,
+    Console,
+    PerfOverlay,
+    Spectrum,
+    SplitScreen,
+    Help,
+}
+
+impl Action {
+    pub const ALL: [Action; 68] = [
+        Action::Pause, Action::SpeedUp, Action::SlowDown, Action::Step, Action::ClearGrid,
+        Action::Reset, Action::Menu, Action::AudioToggle, Action::AudioStatus, Action::VolumeUp,
+        Action::VolumeDown, Action::MuteDrone, Action::MuteSamples, Action::MuteGroove,
+        Action::MuteEffects, Action::Sequencer, Action::BrushSmaller, Action::BrushLarger, Action::Spray,
+        Action::BrushShape, Action::CycleTool, Action::Symmetry, Action::Pattern1,
+        Action::Pattern2, Action::Pattern3, Action::StampRotate, Action::StampFlip,
+        Action::StampDrop, Action::PanUp, Action::PanDown, Action::PanLeft, Action::PanRight,
+        Action::ZoomIn, Action::ZoomOut, Action::ResetCamera, Action::ToggleHud,
+        Action::SynthPanel, Action::Browser, Action::AddLayer, Action::QuickSave,
+        Action::QuickLoad, Action::Screenshot, Action::RecordGif, Action::RecordVideo,
+        Action::ExportSvg, Action::AgeColors, Action::Trails, Action::GridLines, Action::NextTheme,
+        Action::Bloom, Action::Minimap, Action::ActivityHeat, Action::RenderPanel,
+        Action::Copy, Action::Paste, Action::PatternMarkers, Action::FitView,
+        Action::PopulationGraph, Action::RuleEditor, Action::RunGenerations,
+        Action::StepBack, Action::Settings, Action::CommandPalette,
+        Action::Console, Action::PerfOverlay, Action::Spectrum, Action::SplitScreen,
+        Action::Help,
+    ];
+
+    /// Name used in the config file
+    pub fn config_name(self) -> &'static str {
+        match self {
+            Action::Pause => "pause",
+            Action::SpeedUp => "speed_up",
+            Action::SlowDown => "slow_down",
+            Action::Step => "step",
+            Action::ClearGrid => "clear",
+            Action::Reset => "reset",
+            Action::Menu => "menu",
+            Action::AudioToggle => "audio_toggle",
+            Action::AudioStatus => "audio_status",
+            Action::VolumeUp => "volume_up",
+            Action::VolumeDown => "volume_down",
+            Action::MuteDrone => "mute_drone",
+            Action::MuteSamples => "mute_samples",
+            Action::MuteGroove => "mute_groove",
+            Action::MuteEffects => "mute_effects",
+            Action::Sequencer => "sequencer",
+            Action::BrushSmaller => "brush_smaller",
+            Action::BrushLarger => "brush_larger",
+            Action::Spray => "spray",
+            Action::BrushShape => "brush_shape",
+            Action::CycleTool => "tool",
+            Action::Symmetry => "symmetry",
+            Action::Pattern1 => "pattern_1",
+            Action::Pattern2 => "pattern_2",
+            Action::Pattern3 => "pattern_3",
+            Action::StampRotate => "stamp_rotate",
+            Action::StampFlip => "stamp_flip",
+            Action::StampDrop => "stamp_drop",
+            Action::PanUp => "pan_up",
+            Action::PanDown => "pan_down",
+            Action::PanLeft => "pan_left",
+            Action::PanRight => "pan_right",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::ResetCamera => "reset_camera",
+            Action::ToggleHud => "toggle_hud",
+            Action::SynthPanel => "synth_panel",
+            Action::Browser => "browser",
+            Action::AddLayer => "add_layer",
+            Action::QuickSave => "quick_save",
+            Action::QuickLoad => "quick_load",
+            Action::Screenshot => "screenshot",
+            Action::RecordGif => "record_gif",
+            Action::RecordVideo => "record_video",
+            Action::ExportSvg => "export_svg",
+            Action::AgeColors => "age_colors",
+            Action::Trails => "trails",
+            Action::GridLines => "grid_lines",
+            Action::NextTheme => "next_theme",
+            Action::Bloom => "bloom",
+            Action::Minimap => "minimap",
+            Action::ActivityHeat => "activity",
+            Action::RenderPanel => "render_panel",
+            Action::Copy => "copy",
+            Action::Paste => "paste",
+            Action::PatternMarkers => "pattern_markers",
+            Action::FitView => "fit_view",
+            Action::PopulationGraph => "population_graph",
+            Action::RuleEditor => "rule_editor",
+            Action::RunGenerations => "run",
+            Action::StepBack => "step_back",
+            Action::Settings => "settings",
+            Action::CommandPalette => "command_palette",
+            Action::Console => "console",
+            Action::PerfOverlay => "perf_overlay",
+            Action::Spectrum => "spectrum",
+            Action::SplitScreen => "split_screen",
+            Action::Help => "help",
+        }
+    }
+
+    pub fn from_config_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.config_name() == name)
+    }
+
+    pub fn default_key(self) -> KeyCode {
+        match self {
+            Action::Pause => KeyCode::Space,
+            Action::SpeedUp => KeyCode::Equal,
+            Action::SlowDown => KeyCode::Minus,
+            Action::Step => KeyCode::KeyS,
+            Action::ClearGrid => KeyCode::KeyC,
+            Action::Reset => KeyCode::KeyR,
+            Action::Menu => KeyCode::Escape,
+            Action::AudioToggle => KeyCode::KeyM,
+            Action::AudioStatus => KeyCode::KeyN,
+            Action::VolumeUp => KeyCode::Period,
+            Action::VolumeDown => KeyCode::Comma,
+            Action::MuteDrone => KeyCode::Digit4,
+            Action::MuteSamples => KeyCode::Digit5,
+            Action::MuteGroove => KeyCode::Digit6,
+            Action::MuteEffects => KeyCode::Digit7,
+            Action::Sequencer => KeyCode::Digit8,
+            Action::BrushSmaller => KeyCode::BracketLeft,
+            Action::BrushLarger => KeyCode::BracketRight,
+            Action::Spray => KeyCode::KeyJ,
+            Action::BrushShape => KeyCode::KeyB,
+            Action::CycleTool => KeyCode::KeyT,
+            Action::Symmetry => KeyCode::KeyY,
+            Action::Pattern1 => KeyCode::Digit1,
+            Action::Pattern2 => KeyCode::Digit2,
+            Action::Pattern3 => KeyCode::Digit3,
+            Action::StampRotate => KeyCode::KeyR,
+            Action::StampFlip => KeyCode::KeyF,
+            Action::StampDrop => KeyCode::Escape,
+            Action::PanUp => KeyCode::KeyW,
+            Action::PanDown => KeyCode::KeyS,
+            Action::PanLeft => KeyCode::KeyA,
+            Action::PanRight => KeyCode::KeyD,
+            Action::ZoomIn => KeyCode::PageUp,
+            Action::ZoomOut => KeyCode::PageDown,
+            Action::ResetCamera => KeyCode::Home,
+            Action::ToggleHud => KeyCode::KeyH,
+            Action::SynthPanel => KeyCode::KeyP,
+            Action::Browser => KeyCode::KeyO,
+            Action::AddLayer => KeyCode::KeyL,
+            Action::QuickSave => KeyCode::F5,
+            Action::QuickLoad => KeyCode::F9,
+            Action::Screenshot => KeyCode::F12,
+            Action::RecordGif => KeyCode::F10,
+            Action::RecordVideo => KeyCode::F11,
+            Action::ExportSvg => KeyCode::F8,
+            Action::AgeColors => KeyCode::KeyG,
+            Action::Trails => KeyCode::KeyK,
+            Action::GridLines => KeyCode::KeyX,
+            Action::NextTheme => KeyCode::KeyU,
+            Action::Bloom => KeyCode::KeyQ,
+            Action::Minimap => KeyCode::KeyI,
+            Action::ActivityHeat => KeyCode::KeyE,
+            Action::RenderPanel => KeyCode::F3,
+            Action::Copy => KeyCode::KeyC,
+            Action::Paste => KeyCode::KeyV,
+            Action::PatternMarkers => KeyCode::F4,
+            Action::FitView => KeyCode::KeyF,
+            Action::PopulationGraph => KeyCode::F2,
+            Action::RuleEditor => KeyCode::F6,
+            Action::RunGenerations => KeyCode::Enter,
+            Action::StepBack => KeyCode::Backspace,
+            Action::Settings => KeyCode::F7,
+            Action::CommandPalette => KeyCode::KeyP,
+            Action::Console => KeyCode::Backquote,
+            Action::PerfOverlay => KeyCode::Slash,
+            Action::Spectrum => KeyCode::Quote,
+            Action::SplitScreen => KeyCode::Backslash,
+            Action::Help => KeyCode::F1,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+pub enum KeybindingError {
+    #[error("unknown action '{0}'")]
+    UnknownAction(String),
+    #[error("unknown key '{key}' for '{action}'")]
+    UnknownKey { action: String, key: String },
+}
+
+/// The key bound to each action
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Keybindings {
+    keys: HashMap<Action, KeyCode>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self { keys: Action::ALL.into_iter().map(|action| (action, action.default_key())).collect() }
+    }
+}
+
+impl Keybindings {
+    /// Defaults with the config file's `action = "Key"` overrides applied. Bad entries are
+    /// skipped and reported so one typo doesn't lose the rest of the table.
+    pub fn from_overrides(overrides: &HashMap<String, String>) -> (Self, Vec<KeybindingError>) {
+        let mut bindings = Self::default();
+        let mut errors = Vec::new();
+        let mut entries: Vec<_> = overrides.iter().collect();
+        entries.sort();
+        for (action_name, key_name) in entries {
+            let Some(action) = Action::from_config_name(action_name) else {
+                errors.push(KeybindingError::UnknownAction(action_name.clone()));
+                continue;
+            };
+            match parse_key(key_name) {
+                Some(key) => bindings.set(action, key),
+                None => errors.push(KeybindingError::UnknownKey { action: action_name.clone(), key: key_name.clone() }),
+            }
+        }
+        (bindings, errors)
+    }
+
+    pub fn key(&self, action: Action) -> KeyCode {
+        self.keys.get(&action).copied().unwrap_or_else(|| action.default_key())
+    }
+
+    pub fn set(&mut self, action: Action, key: KeyCode) {
+        self.keys.insert(action, key);
+    }
+
+    pub fn pressed(&self, input: &ButtonInput<KeyCode>, action: Action) -> bool {
+        input.pressed(self.key(action))
+    }
+
+    pub fn just_pressed(&self, input: &ButtonInput<KeyCode>, action: Action) -> bool {
+        input.just_pressed(self.key(action))
+    }
+
+    /// Like `just_pressed`, but consumes the press so later systems don't also see it
+    pub fn clear_just_pressed(&self, input: &mut ButtonInput<KeyCode>, action: Action) -> bool {
+        input.clear_just_pressed(self.key(action))
+    }
+
+    /// Display name of the key bound to `action`, for help text
+    pub fn label(&self, action: Action) -> String {
+        key_name(self.key(action))
+    }
+
+    /// Actions bound to `key`, in `Action::ALL` order
+    pub fn actions_on(&self, key: KeyCode) -> Vec<Action> {
+        Action::ALL.into_iter().filter(|&action| self.key(action) == key).collect()
+    }
+
+    /// The `[keybindings]` table giving these bindings: one entry per action moved off its
+    /// default key
+    pub fn overrides(&self) -> HashMap<String, String> {
+        Action::ALL
+            .into_iter()
+            .filter(|&action| self.key(action) != action.default_key())
+            .map(|action| (action.config_name().to_string(), self.label(action)))
+            .collect()
+    }
+}
+
+const LETTERS: [KeyCode; 26] = [
+    KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF,
+    KeyCode::KeyG, KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL,
+    KeyCode::KeyM, KeyCode::KeyN, KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR,
+    KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU, KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX,
+    KeyCode::KeyY, KeyCode::KeyZ,
+];
+
+const DIGITS: [KeyCode; 10] = [
+    KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+    KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+];
+
+const FUNCTION_KEYS: [KeyCode; 12] = [
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+];
+
+/// Other keys by `KeyCode` name, with the character they type where there is one
+const NAMED_KEYS: [(&str, Option<char>, KeyCode); 30] = [
+    ("Space", Some(' '), KeyCode::Space),
+    ("Enter", None, KeyCode::Enter),
+    ("Escape", None, KeyCode::Escape),
+    ("Tab", None, KeyCode::Tab),
+    ("Backspace", None, KeyCode::Backspace),
+    ("Delete", None, KeyCode::Delete),
+    ("Insert", None, KeyCode::Insert),
+    ("Home", None, KeyCode::Home),
+    ("End", None, KeyCode::End),
+    ("PageUp", None, KeyCode::PageUp),
+    ("PageDown", None, KeyCode::PageDown),
+    ("ArrowUp", None, KeyCode::ArrowUp),
+    ("ArrowDown", None, KeyCode::ArrowDown),
+    ("ArrowLeft", None, KeyCode::ArrowLeft),
+    ("ArrowRight", None, KeyCode::ArrowRight),
+    ("Minus", Some('-'), KeyCode::Minus),
+    ("Equal", Some('='), KeyCode::Equal),
+    ("BracketLeft", Some('['), KeyCode::BracketLeft),
+    ("BracketRight", Some(']'), KeyCode::BracketRight),
+    ("Comma", Some(','), KeyCode::Comma),
+    ("Period", Some('.'), KeyCode::Period),
+    ("Semicolon", Some(';'), KeyCode::Semicolon),
+    ("Quote", Some('\''), KeyCode::Quote),
+    ("Slash", Some('/'), KeyCode::Slash),
+    ("Backslash", Some('\\'), KeyCode::Backslash),
+    ("Backquote", Some('`'), KeyCode::Backquote),
+    ("NumpadAdd", None, KeyCode::NumpadAdd),
+    ("NumpadSubtract", None, KeyCode::NumpadSubtract),
+    ("NumpadEnter", None, KeyCode::NumpadEnter),
+    ("IntlBackslash", None, KeyCode::IntlBackslash),
+];
+
+/// Parse a key name from the config file
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    let name = name.trim();
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphabetic() {
+            return Some(LETTERS[(c.to_ascii_uppercase() as u8 - b'A') as usize]);
+        }
+        if let Some(d) = c.to_digit(10) {
+            return Some(DIGITS[d as usize]);
+        }
+        return NAMED_KEYS.iter().find(|(_, typed, _)| *typed == Some(c)).map(|&(_, _, key)| key);
+    }
+    let lower = name.to_ascii_lowercase();
+    if let Some(letter) = lower.strip_prefix("key").filter(|rest| rest.len() == 1) {
+        return parse_key(letter).filter(|_| letter.chars().all(|c| c.is_ascii_alphabetic()));
+    }
+    if let Some(digit) = lower.strip_prefix("digit").filter(|rest| rest.len() == 1) {
+        return parse_key(digit).filter(|_| digit.chars().all(|c| c.is_ascii_digit()));
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|rest| rest.parse::<usize>().ok()) {
+        return n.checked_sub(1).and_then(|i| FUNCTION_KEYS.get(i)).copied();
+    }
+    NAMED_KEYS.iter().find(|(known, _, _)| known.eq_ignore_ascii_case(name)).map(|&(_, _, key)| key)
+}
+
+/// Whether `key` can be written in the config file, and so bound to an action
+pub fn bindable(key: KeyCode) -> bool {
+    parse_key(&key_name(key)) == Some(key)
+}
+
+/// Short display name for a key (`C`, `1`, `F5`, `[`, `Space`)
+pub fn key_name(key: KeyCode) -> String {
+    if let Some(i) = LETTERS.iter().position(|&k| k == key) {
+        return ((b'A' + i as u8) as char).to_string();
+    }
+    if let Some(i) = DIGITS.iter().position(|&k| k == key) {
+        return i.to_string();
+    }
+    if let Some(i) = FUNCTION_KEYS.iter().position(|&k| k == key) {
+        return format!("F{}", i + 1);
+    }
+    match NAMED_KEYS.iter().find(|&&(_, _, k)| k == key) {
+        Some((_, Some(typed), _)) if *typed != ' ' => typed.to_string(),
+        Some((name, _, _)) => name.to_string(),
+        None => format!("{:?}", key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_apply_and_bad_entries_are_reported() {
+        let overrides: HashMap<String, String> = [
+            ("pan_up", "z"),
+            ("pause", "Enter"),
+            ("brush_larger", "KeyK"),
+            ("quick_save", "f6"),
+            ("zoom_in", "+plus"),
+            ("teleport", "T"),
+        ]
+        .into_iter()
+        .map(|(a, k)| (a.to_string(), k.to_string()))
+        .collect();
+        let (bindings, errors) = Keybindings::from_overrides(&overrides);
+        assert_eq!(bindings.key(Action::PanUp), KeyCode::KeyZ);
+        assert_eq!(bindings.key(Action::Pause), KeyCode::Enter);
+        assert_eq!(bindings.key(Action::BrushLarger), KeyCode::KeyK);
+        assert_eq!(bindings.key(Action::QuickSave), KeyCode::F6);
+        assert_eq!(bindings.key(Action::ZoomIn), KeyCode::PageUp);
+        assert_eq!(bindings.key(Action::PanLeft), KeyCode::KeyA);
+        assert_eq!(errors.len(), 2);
+        assert!(errors.contains(&KeybindingError::UnknownAction("teleport".to_string())));
+
+        // Every display name parses back to its key
+        for action in Action::ALL {
+            let key = action.default_key();
+            assert_eq!(parse_key(&key_name(key)), Some(key), "{:?}", action);
+            assert_eq!(Action::from_config_name(action.config_name()), Some(action));
+        }
+        assert_eq!(key_name(KeyCode::BracketLeft), "[");
+        assert_eq!(key_name(KeyCode::Space), "Space");
+    }
+
+    #[test]
+    fn overrides_round_trip_rebound_keys() {
+        let mut bindings = Keybindings::default();
+        assert!(bindings.overrides().is_empty());
+        bindings.set(Action::PanUp, KeyCode::KeyZ);
+        bindings.set(Action::Pause, KeyCode::BracketLeft);
+        bindings.set(Action::Step, Action::Step.default_key());
+        let overrides = bindings.overrides();
+        assert_eq!(overrides.len(), 2);
+        assert_eq!(overrides["pause"], "[");
+        assert_eq!(Keybindings::from_overrides(&overrides), (bindings.clone(), Vec::new()));
+        assert_eq!(bindings.actions_on(KeyCode::KeyZ), vec![Action::PanUp]);
+        assert!(!bindable(KeyCode::ShiftLeft));
+    }
+}
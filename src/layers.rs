@@ -0,0 +1,223 @@
+//! Extra grid layers stepped alongside the main grid.
+//!
+//! The `InfiniteGrid` resource stays the primary, editable layer that every tool, the
+//! renderer and the audio engines work with. `GridLayers` holds any number of additional
+//! grids, each with its own rule, tint and visibility; they advance whenever the main
+//! simulation does and are drawn underneath it as flat tinted squares.
+//!
+//! L snapshots the main grid into a new layer running the next rule (handy for seeing how
+//! one seed behaves under two rules side by side), Shift+L removes the newest layer and
+//! Alt+1..9 toggles a layer's visibility. The split screen (see `split_screen`) keeps its
+//! comparison grid here too, drawn in the right half of the window instead.
+
+use std::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use bevy::window::PrimaryWindow;
+use crate::camera::{CameraState, GameCamera};
+use crate::infinite_grid::InfiniteGrid;
+use crate::keybindings::{Action, Keybindings};
+use crate::split_screen::SPLIT_RENDER_LAYER;
+use crate::start_screen::{GameState, InGame, RuleType};
+use crate::ui::toast;
+use crate::tr;
+use crate::GameConfig;
+
+/// Colours handed out to new layers in turn
+const LAYER_TINTS: [Color; 4] = [
+    Color::srgba(0.2, 0.9, 1.0, 0.6),
+    Color::srgba(1.0, 0.6, 0.1, 0.6),
+    Color::srgba(1.0, 0.2, 0.8, 0.6),
+    Color::srgba(0.5, 1.0, 0.2, 0.6),
+];
+
+/// Most sprites drawn per layer, so a runaway layer can't stall the frame
+const MAX_LAYER_SPRITES: usize = 20_000;
+
+pub struct GridLayer {
+    pub name: String,
+    pub grid: InfiniteGrid,
+    pub rule: RuleType,
+    pub tint: Color,
+    pub visible: bool,
+    /// Drawn only in the split screen's right half, not under the main grid
+    pub split: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct GridLayers {
+    pub layers: Vec<GridLayer>,
+}
+
+impl GridLayers {
+    /// Add a layer and return its index
+    pub fn add(&mut self, name: impl Into<String>, grid: InfiniteGrid, rule: RuleType) -> usize {
+        let tint = LAYER_TINTS[self.layers.len() % LAYER_TINTS.len()];
+        self.layers.push(GridLayer { name: name.into(), grid, rule, tint, visible: true, split: false });
+        self.layers.len() - 1
+    }
+
+    pub fn remove_last(&mut self) -> Option<GridLayer> {
+        self.layers.pop()
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<GridLayer> {
+        (index < self.layers.len()).then(|| self.layers.remove(index))
+    }
+
+    /// Index of the split screen's layer, if it is on
+    pub fn split_index(&self) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.split)
+    }
+
+    /// Advance every layer by one generation under its own rule
+    pub fn step_all(&mut self) {
+        for layer in &mut self.layers {
+            layer.grid.update(layer.rule);
+        }
+    }
+
+    pub fn toggle_visibility(&mut self, index: usize) -> Option<bool> {
+        let layer = self.layers.get_mut(index)?;
+        layer.visible = !layer.visible;
+        Some(layer.visible)
+    }
+
+    pub fn clear(&mut self) {
+        self.layers.clear();
+    }
+}
+
+/// Sprite for one cell of an extra layer
+#[derive(Component)]
+pub struct LayerSprite {
+    pub layer: usize,
+    /// Whether it was drawn for the split screen, so a layer moving in or out of it is redrawn
+    pub split: bool,
+    pub x: i32,
+    pub y: i32,
+}
+
+pub struct LayersPlugin;
+
+impl Plugin for LayersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GridLayers>()
+            .add_systems(
+                Update,
+                (layer_controls, render_layers.after(layer_controls)).run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnEnter(InGame), |mut layers: ResMut<GridLayers>| layers.clear())
+            .add_systems(OnExit(InGame), despawn_layer_sprites);
+    }
+}
+
+fn layer_controls(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
+    grid: Res<InfiniteGrid>,
+    game_config: Res<GameConfig>,
+    mut layers: ResMut<GridLayers>,
+) {
+    let shift = keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    let alt = keyboard_input.pressed(KeyCode::AltLeft) || keyboard_input.pressed(KeyCode::AltRight);
+
+    if keys.just_pressed(&keyboard_input, Action::AddLayer) {
+        if shift {
+            if let Some(layer) = layers.remove_last() {
+                toast(tr!("🗂️ Removed layer '{}'", layer.name));
+            }
+        } else {
+            // Compare against the next rule in the start-screen order
+            let rules = RuleType::all();
+            let current = rules.iter().position(|&r| r == game_config.current_rule).unwrap_or(0);
+            let rule = rules[(current + 1 + layers.layers.len()) % rules.len()];
+            let index = layers.add(rule.name(), grid.clone(), rule);
+            toast(tr!("🗂️ Layer {} added: {}", index + 1, rule.name()));
+        }
+    }
+
+    if alt {
+        let digits = [
+            KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4, KeyCode::Digit5,
+            KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+        ];
+        for (index, key) in digits.into_iter().enumerate() {
+            if keyboard_input.just_pressed(key) {
+                if let Some(visible) = layers.toggle_visibility(index) {
+                    toast(tr!("🗂️ Layer {} {}", index + 1, if visible { "shown" } else { "hidden" }));
+                }
+            }
+        }
+    }
+}
+
+/// Keep one flat sprite per visible cell of every visible layer in view
+fn render_layers(
+    mut commands: Commands,
+    layers: Res<GridLayers>,
+    camera_query: Query<(&Transform, &GameCamera)>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_state: Res<CameraState>,
+    existing: Query<(Entity, &LayerSprite)>,
+) {
+    let (Ok((camera_transform, camera)), Ok(window)) = (camera_query.get_single(), windows.get_single()) else { return };
+    let cell_size = camera_state.cell_size;
+    let camera_pos = camera_transform.translation.truncate();
+    let half = Vec2::new(window.width(), window.height()) / camera.zoom / 2.0;
+    let min_x = ((camera_pos.x - half.x) / cell_size).floor() as i32 - 1;
+    let max_x = ((camera_pos.x + half.x) / cell_size).ceil() as i32 + 1;
+    let min_y = ((camera_pos.y - half.y) / cell_size).floor() as i32 - 1;
+    let max_y = ((camera_pos.y + half.y) / cell_size).ceil() as i32 + 1;
+
+    let mut shown: HashMap<(usize, i32, i32), Entity> = HashMap::new();
+    for (entity, sprite) in &existing {
+        let keep = layers.layers.get(sprite.layer).is_some_and(|layer| {
+            layer.visible
+                && layer.split == sprite.split
+                && layer.grid.is_alive(sprite.x, sprite.y)
+                && (min_x..=max_x).contains(&sprite.x)
+                && (min_y..=max_y).contains(&sprite.y)
+        });
+        if keep {
+            shown.insert((sprite.layer, sprite.x, sprite.y), entity);
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for (index, layer) in layers.layers.iter().enumerate() {
+        if !layer.visible {
+            continue;
+        }
+        // Later layers sit further back, all behind the main grid's sprites at z = 0
+        let z = -0.1 * (index + 1) as f32;
+        // The split screen's layer is seen only by its own camera, at full strength
+        let (color, render_layers) = if layer.split {
+            (layer.tint.with_alpha(1.0), RenderLayers::layer(SPLIT_RENDER_LAYER))
+        } else {
+            (layer.tint, RenderLayers::default())
+        };
+        for (x, y) in layer.grid.cells_in_region(min_x, max_x, min_y, max_y).take(MAX_LAYER_SPRITES) {
+            if shown.contains_key(&(index, x, y)) {
+                continue;
+            }
+            commands.spawn((
+                Sprite {
+                    color,
+                    custom_size: Some(Vec2::splat(cell_size * 0.8)),
+                    ..default()
+                },
+                Transform::from_xyz(x as f32 * cell_size, y as f32 * cell_size, z),
+                render_layers.clone(),
+                LayerSprite { layer: index, split: layer.split, x, y },
+            ));
+        }
+    }
+}
+
+fn despawn_layer_sprites(mut commands: Commands, sprites: Query<Entity, With<LayerSprite>>) {
+    for entity in &sprites {
+        commands.entity(entity).despawn();
+    }
+}
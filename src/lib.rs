@@ -26,6 +26,7 @@ pub mod start_screen;
 pub mod camera;
 pub mod cell_renderer;
 pub mod ui;
+pub mod locale;
 pub mod hud;
 pub mod settings;
 pub mod config;
@@ -33,6 +34,55 @@ pub mod io;
 pub mod audio;
 pub mod synth_ui;
 pub mod game_config;
+pub mod stats;
+pub mod tools;
+pub mod layers;
+pub mod session;
+pub mod browser;
+pub mod export;
+pub mod keybindings;
+pub mod theme;
+pub mod preferences;
+pub mod heatmap;
+pub mod trails;
+pub mod grid_overlay;
+pub mod glow;
+pub mod cell_material;
+pub mod minimap;
+pub mod activity;
+pub mod render_panel;
+pub mod presets;
+pub mod pixel_mode;
+pub mod isometric;
+pub mod pattern_markers;
+pub mod camera_shake;
+pub mod population_graph;
+pub mod rule_editor;
+pub mod speed_slider;
+pub mod toolbar;
+pub mod perf_overlay;
+pub mod spectrum_meter;
+pub mod pause_overlay;
+pub mod confirm;
+pub mod split_screen;
+pub mod help_overlay;
+pub mod settings_menu;
+pub mod controls_screen;
+pub mod command_palette;
+pub mod console;
+pub mod backdrop;
+pub mod title_soup;
+pub mod rule_preview;
+pub mod start_seed;
+pub mod audio_picker;
+pub mod sessions_list;
+pub mod tutorial;
+pub mod osc;
+pub mod sequencer;
+#[cfg(feature = "inspector")]
+pub mod inspector;
+#[cfg(feature = "midi")]
+pub mod midi;
 
 
 
@@ -50,7 +100,7 @@ pub use cell_renderer::{CellAnimation, AnimationType};
 // GameAudioManager has been removed, using spatial_audio and hybrid_dungeon_synth instead
 
 /// Cell state for multi-state cellular automata
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub enum CellState {
     Dead = 0,
     Alive = 1,
@@ -286,4 +336,4 @@ mod tests {
     }
 }
 
-pub use game_config::GameConfig;
+pub use game_config::{GameConfig, GameStats};
@@ -0,0 +1,178 @@
+//! Interface translations.
+//!
+//! Text shown to the player is written in English in the code and passed through [`tr!`],
+//! which works like `format!` but first looks the English text up in the catalogue of the
+//! `locale` set in the config. Catalogues are `locales/<locale>.toml` files mapping each
+//! English text to its translation:
+//!
+//! ```toml
+//! "🖌️ Brush size: {}" = "🖌️ Taille du pinceau : {}"
+//! ```
+//!
+//! Placeholders are `{}` for the next argument, `{0}`, `{1}`, ... to reorder them, and `:.N`
+//! for decimal places (`{:.1}`, `{2:.0}`). Text missing from the catalogue, and everything
+//! under the built-in `en` locale, is shown in English. The catalogue is global, like the
+//! toast queue, and loaded once before the app is built, so strings made at startup are
+//! translated too.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Write};
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Folder of the catalogue files
+pub const LOCALE_DIR: &str = "locales";
+/// The language the code is written in, which needs no catalogue
+pub const DEFAULT_LOCALE: &str = "en";
+
+static CATALOGUE: RwLock<Option<HashMap<String, String>>> = RwLock::new(None);
+
+#[derive(Debug, thiserror::Error)]
+pub enum LocaleError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Parse(#[from] toml::de::Error),
+}
+
+/// Read the catalogue at `path`
+pub fn load_catalogue(path: impl AsRef<Path>) -> Result<HashMap<String, String>, LocaleError> {
+    Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// Switch to `locale`, reading `LOCALE_DIR/<locale>.toml`. On failure the text stays in
+/// English and the error is returned for the caller to report.
+pub fn set_locale(locale: &str) -> Result<(), LocaleError> {
+    let catalogue = if locale.eq_ignore_ascii_case(DEFAULT_LOCALE) {
+        None
+    } else {
+        Some(load_catalogue(Path::new(LOCALE_DIR).join(format!("{}.toml", locale)))?)
+    };
+    if let Ok(mut current) = CATALOGUE.write() {
+        *current = catalogue;
+    }
+    Ok(())
+}
+
+/// `text` in the current locale
+pub fn translate(text: &str) -> String {
+    CATALOGUE
+        .read()
+        .ok()
+        .and_then(|catalogue| catalogue.as_ref().and_then(|c| c.get(text).cloned()))
+        .unwrap_or_else(|| text.to_string())
+}
+
+/// `text` in the current locale with its placeholders filled from `args`; see [`tr!`]
+pub fn translate_with(text: &str, args: &[&dyn Display]) -> String {
+    interpolate(&translate(text), args)
+}
+
+/// Fill `{}`, `{N}` and `{N:.P}` placeholders from `args`. `{{` and `}}` are literal
+/// braces, and a placeholder with no argument is left as written.
+pub fn interpolate(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let spec: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let (index, precision) = spec.split_once(":.").map_or((spec.as_str(), None), |(i, p)| (i, p.parse::<usize>().ok()));
+                let index = if index.is_empty() {
+                    next += 1;
+                    next - 1
+                } else {
+                    index.parse().unwrap_or(usize::MAX)
+                };
+                match (args.get(index), precision) {
+                    (Some(arg), Some(precision)) => {
+                        let _ = write!(out, "{:.*}", precision, arg);
+                    }
+                    (Some(arg), None) => {
+                        let _ = write!(out, "{}", arg);
+                    }
+                    (None, _) => {
+                        let _ = write!(out, "{{{}}}", spec);
+                    }
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// `format!` through the current locale's catalogue:
+/// `tr!("🖌️ Brush size: {}", brush.size)`. The text must be a literal, so the catalogue
+/// tests can find it.
+#[macro_export]
+macro_rules! tr {
+    ($text:literal) => {
+        $crate::locale::translate($text)
+    };
+    ($text:literal, $($arg:expr),+ $(,)?) => {
+        $crate::locale::translate_with($text, &[$(&$arg as &dyn ::std::fmt::Display),+])
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_fill_in_order_by_index_and_with_precision() {
+        assert_eq!(interpolate("Brush size: {}", &[&3]), "Brush size: 3");
+        assert_eq!(interpolate("{1} before {0}", &[&"a", &"b"]), "b before a");
+        assert_eq!(interpolate("Volume: {:.0}%", &[&72.4]), "Volume: 72%");
+        assert_eq!(interpolate("{{literal}} {} {}", &[&1]), "{literal} 1 {}");
+    }
+
+    /// Every `tr!` text in the source has a French translation with the same placeholders
+    #[test]
+    fn french_catalogue_covers_every_message() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let french = load_catalogue(root.join(LOCALE_DIR).join("fr.toml")).unwrap();
+        let placeholders = |text: &str| interpolate(text, &[&"#", &"#", &"#", &"#", &"#"]).matches('#').count();
+        let mut missing = Vec::new();
+        let mut stack = vec![root.join("src")];
+        while let Some(dir) = stack.pop() {
+            for entry in std::fs::read_dir(dir).unwrap().flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    stack.push(path);
+                    continue;
+                }
+                // This file only mentions the macro
+                if path.ends_with("locale.rs") {
+                    continue;
+                }
+                let source = std::fs::read_to_string(&path).unwrap();
+                let calls = source.match_indices("tr!(").filter(|&(at, _)| !source[..at].ends_with(|c: char| c.is_alphanumeric() || c == '_'));
+                let texts = calls.filter_map(|(at, _)| source[at + 4..].trim_start().strip_prefix('"')?.split_once('"'));
+                for (text, _) in texts {
+                    let text = text.replace("\\\\", "\\");
+                    match french.get(&text) {
+                        Some(translation) => assert_eq!(placeholders(translation), placeholders(&text), "{}", text),
+                        None => missing.push(format!("{}: {}", path.display(), text)),
+                    }
+                }
+            }
+        }
+        // Rule descriptions are translated where the start screen shows them
+        for rule in crate::start_screen::RuleType::all() {
+            if !french.contains_key(rule.description()) {
+                missing.push(format!("rule description: {}", rule.description()));
+            }
+        }
+        assert!(missing.is_empty(), "untranslated:\n{}", missing.join("\n"));
+    }
+}
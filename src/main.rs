@@ -1,12 +1,26 @@
-use bevy::{prelude::*, diagnostic::{FrameTimeDiagnosticsPlugin, DiagnosticsStore}, window::PrimaryWindow};
+use bevy::{prelude::*, diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin, DiagnosticsStore}, window::PrimaryWindow};
 use std::collections::HashSet;
+use std::path::Path;
 
 // Import our modules
 use gameofdeath::*;
-use gameofdeath::camera::{setup_camera, handle_camera_controls, GameCamera, CameraState, world_to_grid, screen_to_world};
-use gameofdeath::start_screen::{GameState, SelectedRule, RuleType, setup_start_screen, handle_start_screen_input, cleanup_start_screen, update_start_screen_ui};
-use gameofdeath::ui::{setup_ui, UiState, RuleControlsContainer, RuleControlText};
-use gameofdeath::cell_renderer::{CellRenderConfig, CellTextureCache, CellTexturePool, render_optimized_cells, update_cell_render_config, animate_cell_textures, CellAnimation, AnimationType};
+use gameofdeath::rule_preview::RulePreviewPlugin;
+use gameofdeath::audio_picker::AudioPickerPlugin;
+use gameofdeath::sessions_list::SessionsListPlugin;
+use gameofdeath::tutorial::TutorialPlugin;
+use gameofdeath::osc::{OscPlugin, OscSettings};
+use gameofdeath::sequencer::{Sequencer, SequencerPlugin};
+#[cfg(feature = "inspector")]
+use gameofdeath::inspector::InspectorPlugin;
+#[cfg(feature = "midi")]
+use gameofdeath::midi::{MidiPlugin, MidiSettings};
+use gameofdeath::start_seed::{apply_start_seed, StartSeed, StartSeedPlugin};
+use gameofdeath::title_soup::{TitleSoup, TitleSoupPlugin, SOUP_HEIGHT, SOUP_WIDTH};
+use gameofdeath::camera::{setup_camera, handle_camera_controls, handle_gestures, fit_view_to_pattern, apply_camera_settings, visible_grid_bounds, GameCamera, CameraState, CameraSettings, cursor_grid_position};
+use gameofdeath::start_screen::{GameState, InGame, SelectedRule, RuleType, setup_start_screen, handle_start_screen_input, cleanup_start_screen, update_start_screen_ui};
+use gameofdeath::ui::{setup_ui, tick_hud_refresh, toast, BrushIndicatorPlugin, HudRefresh, StepCountsPlugin, KeyTextPlugin, FontScale, HudLayoutPlugin, ToastPlugin, UiScalingPlugin, UiState, RuleControlsContainer, RuleControlText};
+use gameofdeath::tr;
+use gameofdeath::cell_renderer::{CellRenderConfig, CellTextureCache, CellTexturePool, GridChanges, collect_grid_changes, render_optimized_cells, update_cell_render_config, animate_cell_textures, toggle_age_colors, CellAnimation, AnimationType};
 use gameofdeath::audio::{
     extract_game_features,
     update_hybrid_dungeon_synth,
@@ -14,12 +28,66 @@ use gameofdeath::audio::{
     set_hybrid_volume,
     get_hybrid_volume,
     init_hybrid_dungeon_synth,
+    init_spatial_audio,
+    update_spatial_audio,
+    toggle_spatial_audio,
+    get_active_voice_count,
+    init_ddsp_audio,
+    update_ddsp_audio,
+    toggle_ddsp_audio,
+    ddsp_model_loaded,
+    load_ddsp_model,
     setup_kira,
+    shutdown_kira,
     IllbientGroove,
+    KiraManager,
+    KeySelection,
+    output::{configure_output, OutputSettings},
+    user_samples::load_user_samples,
 };
 use gameofdeath::config::{Config, AudioEngine};
 use gameofdeath::GameConfig;
-use gameofdeath::synth_ui::SynthControlPanelPlugin;
+use gameofdeath::synth_ui::{SynthControlPanelPlugin, SynthParameters};
+use gameofdeath::session::{PendingSession, SessionError, SessionSnapshot, AUTOSAVE_PATH, SESSION_PATH};
+use gameofdeath::stats::ObjectTracker;
+use gameofdeath::infinite_grid::{GridBounds, PrunePolicy, RewindBuffer};
+use gameofdeath::keybindings::{Action, Keybindings};
+use gameofdeath::theme::{cycle_theme, Theme};
+use gameofdeath::preferences::{Preferences, PreferencesPlugin};
+use gameofdeath::heatmap::HeatmapPlugin;
+use gameofdeath::trails::{DeathTrails, TrailsPlugin};
+use gameofdeath::grid_overlay::{GridOverlay, GridOverlayPlugin};
+use gameofdeath::glow::GlowPlugin;
+use gameofdeath::cell_material::CellMaterialPlugin;
+use gameofdeath::minimap::{Minimap, MinimapPlugin};
+use gameofdeath::activity::{ActivityHeat, ActivityPlugin};
+use gameofdeath::render_panel::RenderPanelPlugin;
+use gameofdeath::presets::{apply_visual_preset, ActivePreset, PresetBaseline};
+use gameofdeath::pixel_mode::PixelModePlugin;
+use gameofdeath::backdrop::BackdropPlugin;
+use gameofdeath::isometric::IsometricPlugin;
+use gameofdeath::pattern_markers::{PatternMarkers, PatternMarkersPlugin};
+use gameofdeath::camera_shake::{CameraShake, CameraShakePlugin};
+use gameofdeath::population_graph::{PopulationGraph, PopulationGraphPlugin};
+use gameofdeath::rule_editor::RuleEditorPlugin;
+use gameofdeath::speed_slider::SpeedSliderPlugin;
+use gameofdeath::toolbar::ToolbarPlugin;
+use gameofdeath::perf_overlay::{PerfOverlayPlugin, STEP_TIME};
+use gameofdeath::spectrum_meter::SpectrumMeterPlugin;
+use gameofdeath::pause_overlay::PauseOverlayPlugin;
+use gameofdeath::confirm::{ConfirmPlugin, Confirmation, Destructive};
+use gameofdeath::split_screen::SplitScreenPlugin;
+use gameofdeath::help_overlay::HelpOverlayPlugin;
+use gameofdeath::ui::scale::{MAX_SCALE, MIN_SCALE};
+use gameofdeath::settings_menu::SettingsMenuPlugin;
+use gameofdeath::controls_screen::ControlsScreenPlugin;
+use gameofdeath::command_palette::CommandPalettePlugin;
+use gameofdeath::console::ConsolePlugin;
+use gameofdeath::stats::PopulationHistory;
+use gameofdeath::browser::{PatternBrowser, PatternBrowserPlugin};
+use gameofdeath::export::{ExportPlugin, ExportSettings};
+use gameofdeath::layers::{GridLayers, LayersPlugin};
+use gameofdeath::tools::{apply_brush, line_cells, state_for_click, BrushSettings, BrushShape, FillSettings, StampState, ActiveTool, Tool, ToolsPlugin, MAX_BRUSH_SIZE, SPRAY_DENSITY_STEP};
 
 /// Custom font resource for the game
 #[derive(Resource)]
@@ -51,22 +119,22 @@ impl Default for AudioEnabled {
 
 /// Setup game audio based on configuration
 fn setup_game_audio(game_config: Res<GameConfig>) {
-    setup_audio(game_config.audio_engine, game_config.audio_volume);
+    setup_audio(game_config.audio_engine, game_config.output_volume());
 }
 
 /// Setup audio system
 fn setup_audio(audio_engine: AudioEngine, volume: f32) {
     match audio_engine {
         AudioEngine::Spatial => {
-            // Spatial audio removed - redirect to hybrid
-            println!("🔊 Spatial audio disabled, using Hybrid instead");
-            init_hybrid_dungeon_synth();
+            // The hybrid engine, with a panned voice for each cluster in view over it
+            init_spatial_audio();
             set_hybrid_volume(volume);
-            println!("🔮 Hybrid Dungeon Synth Engine ready! Volume: {:.0}%", volume * 100.0);
+            println!("🔊 Spatial audio ready! Volume: {:.0}%", volume * 100.0);
         }
         AudioEngine::DDSP => {
-            // DDSP functionality temporarily disabled
-            println!("🎵 DDSP Audio Engine (placeholder)");
+            init_ddsp_audio();
+            set_hybrid_volume(volume);
+            println!("🎵 DDSP Audio Engine ready! Volume: {:.0}%", volume * 100.0);
         }
         AudioEngine::DungeonSynth => {
             // Dungeon synth functionality temporarily disabled
@@ -79,37 +147,6 @@ fn setup_audio(audio_engine: AudioEngine, volume: f32) {
             println!("🔮 Hybrid Dungeon Synth Engine ready! Volume: {:.0}%", volume * 100.0);
         }
     }
-    
-    // Display comprehensive controls
-    println!("\n🎮 GAME CONTROLS:");
-    println!("  SPACE - Pause/Resume simulation");
-    println!("  C - Clear grid");
-    println!("  S - Single step (when paused)");
-    println!("  +/- - Speed up/slow down");
-    match audio_engine {
-        AudioEngine::Spatial | AudioEngine::Hybrid => {
-            println!("  M - Toggle hybrid dungeon synth audio");
-            println!("  N - Show hybrid audio status");
-            println!("  ↑/↓ - Volume up/down");
-        }
-        AudioEngine::DDSP => {
-            println!("  M - Toggle DDSP neural audio");
-            println!("  N - Show DDSP audio status");
-        }
-        AudioEngine::DungeonSynth => {
-            println!("  M - Toggle dungeon synth audio");
-            println!("  N - Show dungeon synth audio status");
-        }
-    }
-    println!("  V - Toggle color variation");
-    println!("  G - Toggle generation colors");
-    println!("  [ ] - Adjust noise density");
-    println!("  Note: Cells animate automatically (living textures!)");
-    println!("  ESC - Return to start screen");
-    println!("  Mouse - Click to add/remove cells");
-    println!("  Arrow keys/WASD - Move camera");
-    println!("  Mouse wheel - Zoom in/out");
-    println!("  H - Toggle UI visibility");
 }
 
 /// Setup custom font system
@@ -121,30 +158,6 @@ fn setup_font(mut commands: Commands, asset_server: Res<AssetServer>) {
     println!("🔤 Loading custom font: Geo-Regular.ttf");
 }
 
-/// Game statistics
-#[derive(Resource)]
-pub struct GameStats {
-    pub is_running: bool,
-    pub generation: u64,
-    pub last_update: f64,
-    pub update_interval: f64,
-    pub min_update_interval: f64,
-    pub max_update_interval: f64,
-}
-
-impl Default for GameStats {
-    fn default() -> Self {
-        Self {
-            is_running: false,
-            generation: 0,
-            last_update: 0.0,
-            update_interval: 0.5,
-            min_update_interval: 0.01,
-            max_update_interval: 2.0,
-        }
-    }
-}
-
 /// Cached audio state to prevent repeated calculations
 #[derive(Resource)]
 pub struct AudioCache {
@@ -169,16 +182,6 @@ impl Default for AudioCache {
     }
 }
 
-/// Current brush parameters for cell placement
-#[derive(Resource)]
-pub struct BrushSettings {
-    pub size: u32, // side length of square brush
-}
-
-impl Default for BrushSettings {
-    fn default() -> Self { Self { size: 1 } }
-}
-
 #[derive(Resource, Default)]
 pub struct OverlayCache {
     version: u64,
@@ -217,122 +220,163 @@ fn handle_game_input(
     mut grid: ResMut<InfiniteGrid>,
     mut game_state: ResMut<NextState<GameState>>,
     mut game_config: ResMut<GameConfig>,
-    mut brush: ResMut<BrushSettings>,
+    (mut brush, mut confirmation): (ResMut<BrushSettings>, ResMut<Confirmation>),
+    (stamp, keys, mut rewind): (Res<StampState>, Res<Keybindings>, ResMut<RewindBuffer>),
 ) {
-    // Pause/Resume
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        game_stats.is_running = !game_stats.is_running;
+    // Pause/Resume (pausing ends a run of N generations early)
+    if keys.just_pressed(&keyboard_input, Action::Pause) {
+        if game_stats.is_running {
+            game_stats.stop();
+        } else {
+            game_stats.is_running = true;
+        }
+    }
+
+    // Run N generations and pause, or end the run under way
+    if keys.just_pressed(&keyboard_input, Action::RunGenerations) {
+        if game_stats.run_remaining.is_some() {
+            game_stats.stop();
+        } else {
+            let generations = game_stats.run_length;
+            game_stats.start_run(generations);
+            toast(tr!("⏱️ Running {} generations", generations));
+        }
     }
 
     // Speed controls
-    if keyboard_input.just_pressed(KeyCode::Equal) {
+    if keys.just_pressed(&keyboard_input, Action::SpeedUp) {
         game_stats.update_interval = (game_stats.update_interval * 0.8).max(game_stats.min_update_interval);
     }
-    if keyboard_input.just_pressed(KeyCode::Minus) {
+    if keys.just_pressed(&keyboard_input, Action::SlowDown) {
         game_stats.update_interval = (game_stats.update_interval * 1.25).min(game_stats.max_update_interval);
     }
 
-    // Clear grid
-    if keyboard_input.just_pressed(KeyCode::KeyC) {
-        grid.clear();
-        game_stats.generation = 0;
-        game_stats.is_running = false;
+    // Clear grid (Ctrl+C copies to the clipboard instead)
+    let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight, KeyCode::SuperLeft, KeyCode::SuperRight]);
+    if keys.just_pressed(&keyboard_input, Action::ClearGrid) && !ctrl {
+        confirmation.request(Destructive::ClearGrid, &mut grid, &mut game_stats, &mut game_state);
     }
 
-    // Reset game and return to start screen (R key)
-    if keyboard_input.just_pressed(KeyCode::KeyR) {
-        // Clear the grid completely
-        grid.clear();
-        // Reset game stats
-        game_stats.generation = 0;
-        game_stats.is_running = false;
-        // Navigate to start screen
-        game_state.set(GameState::StartScreen);
-        println!("🔄 Game reset - returning to start screen");
+    // Reset game and return to start screen (ignored while a stamp is held, whose controls may share the key)
+    if keys.just_pressed(&keyboard_input, Action::Reset) && !stamp.is_armed() {
+        confirmation.request(Destructive::Reset, &mut grid, &mut game_stats, &mut game_state);
     }
     
-    // Just return to start screen without reset (ESC key)
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    // Just return to start screen without reset
+    if keys.just_pressed(&keyboard_input, Action::Menu) && !stamp.is_armed() {
         game_state.set(GameState::StartScreen);
     }
 
-    // Step simulation
-    if keyboard_input.just_pressed(KeyCode::KeyS) && !game_stats.is_running {
-        // Single step
-        game_stats.generation += 1;
+    // Step simulation forwards (through update_simulation) or back through the rewind buffer
+    if keys.just_pressed(&keyboard_input, Action::Step) {
+        game_stats.step();
+    }
+    if keys.just_pressed(&keyboard_input, Action::StepBack) && !game_stats.is_running
+        && !game_stats.step_back(&mut grid, &mut rewind)
+    {
+        toast(tr!("⏪ No earlier generation to step back to"));
     }
 
     // Audio controls
-    if keyboard_input.just_pressed(KeyCode::KeyM) {
+    if keys.just_pressed(&keyboard_input, Action::AudioToggle) {
         // Toggle audio based on current engine
         match game_config.audio_engine {
-            AudioEngine::Spatial | AudioEngine::Hybrid => {
-                // For now, just print status since we removed spatial audio
-                println!("🔮 Hybrid audio is always enabled");
+            AudioEngine::Spatial => {
+                toast(tr!("🔊 Cluster voices {}", if toggle_spatial_audio() { "on" } else { "off" }));
+            }
+            AudioEngine::Hybrid => {
+                toast(tr!("🔮 Hybrid audio is always enabled"));
             }
             AudioEngine::DDSP => {
-                println!("🎵 DDSP audio toggle (placeholder)");
+                toast(tr!("🎵 DDSP audio {}", if toggle_ddsp_audio() { "on" } else { "off" }));
             }
             AudioEngine::DungeonSynth => {
-                println!("🏰 Dungeon synth audio toggle (placeholder)");
+                toast(tr!("🏰 Dungeon synth audio toggle (placeholder)"));
             }
         }
     }
 
-    if keyboard_input.just_pressed(KeyCode::KeyN) {
+    if keys.just_pressed(&keyboard_input, Action::AudioStatus) {
         // Show audio status
         match game_config.audio_engine {
-            AudioEngine::Spatial | AudioEngine::Hybrid => {
-                println!("🔮 Hybrid audio: Volume {:.0}%", get_hybrid_volume() * 100.0);
+            AudioEngine::Spatial => {
+                toast(tr!("🔊 Spatial audio: Volume {:.0}%, {} cluster voices", get_hybrid_volume() * 100.0, get_active_voice_count()));
+            }
+            AudioEngine::Hybrid => {
+                toast(tr!("🔮 Hybrid audio: Volume {:.0}%", get_hybrid_volume() * 100.0));
             }
             AudioEngine::DDSP => {
-                println!("🎵 DDSP audio status (placeholder)");
+                let model = if ddsp_model_loaded() { "pretrained model" } else { "untrained decoders" };
+                toast(tr!("🎵 DDSP audio: Volume {:.0}% ({})", get_hybrid_volume() * 100.0, model));
             }
             AudioEngine::DungeonSynth => {
-                println!("🏰 Dungeon synth audio status (placeholder)");
+                toast(tr!("🏰 Dungeon synth audio status (placeholder)"));
             }
         }
     }
 
-    // Volume controls (< and > keys) - now supports overdrive up to 200%
-    if keyboard_input.just_pressed(KeyCode::Period) { // > key (shift not required)
+    // Volume controls (< and > keys by default) - now supports overdrive up to 200%
+    if keys.just_pressed(&keyboard_input, Action::VolumeUp) {
         game_config.audio_volume = (game_config.audio_volume + 0.1).min(2.0); // Allow up to 200%
+        // Turning the volume up or down unmutes
+        game_config.audio_muted = false;
         match game_config.audio_engine {
-            AudioEngine::Hybrid => {
+            AudioEngine::Spatial | AudioEngine::Hybrid | AudioEngine::DDSP => {
                 set_hybrid_volume(game_config.audio_volume);
                 if game_config.audio_volume > 1.0 {
-                    println!("🔊🔥 OVERDRIVE! Volume: {:.0}%", game_config.audio_volume * 100.0);
+                    toast(tr!("🔊🔥 OVERDRIVE! Volume: {:.0}%", game_config.audio_volume * 100.0));
                 } else {
-                    println!("🔊 Volume: {:.0}%", game_config.audio_volume * 100.0);
+                    toast(tr!("🔊 Volume: {:.0}%", game_config.audio_volume * 100.0));
                 }
             }
             _ => {
-                println!("🔊 Volume: {:.0}% (applies to hybrid engine only)", game_config.audio_volume * 100.0);
+                toast(tr!("🔊 Volume: {:.0}% (applies to hybrid engine only)", game_config.audio_volume * 100.0));
             }
         }
     }
     
-    if keyboard_input.just_pressed(KeyCode::Comma) { // < key (shift not required)
+    if keys.just_pressed(&keyboard_input, Action::VolumeDown) {
         game_config.audio_volume = (game_config.audio_volume - 0.1).max(0.0);
+        game_config.audio_muted = false;
         match game_config.audio_engine {
-            AudioEngine::Hybrid => {
+            AudioEngine::Spatial | AudioEngine::Hybrid | AudioEngine::DDSP => {
                 set_hybrid_volume(game_config.audio_volume);
-                println!("🔊 Volume: {:.0}%", game_config.audio_volume * 100.0);
+                toast(tr!("🔊 Volume: {:.0}%", game_config.audio_volume * 100.0));
             }
             _ => {
-                println!("🔊 Volume: {:.0}% (applies to hybrid engine only)", game_config.audio_volume * 100.0);
+                toast(tr!("🔊 Volume: {:.0}% (applies to hybrid engine only)", game_config.audio_volume * 100.0));
             }
         }
     }
 
-    // Brush size controls with [ and ]
-    if keyboard_input.just_pressed(KeyCode::BracketLeft) {
-        brush.size = brush.size.saturating_sub(1).max(1);
-        println!("🖌️ Brush size: {}", brush.size);
+    // Brush size controls ([ and ] by default); with Shift they adjust the spray density instead
+    let shift = keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    if keys.just_pressed(&keyboard_input, Action::BrushSmaller) {
+        if shift {
+            brush.density = (brush.density - SPRAY_DENSITY_STEP).max(SPRAY_DENSITY_STEP);
+            toast(tr!("🖌️ Spray density: {:.0}%", brush.density * 100.0));
+        } else {
+            brush.size = brush.size.saturating_sub(1).max(1);
+            toast(tr!("🖌️ Brush size: {}", brush.size));
+        }
     }
-    if keyboard_input.just_pressed(KeyCode::BracketRight) {
-        brush.size = (brush.size + 1).min(20);
-        println!("🖌️ Brush size: {}", brush.size);
+    if keys.just_pressed(&keyboard_input, Action::BrushLarger) {
+        if shift {
+            brush.density = (brush.density + SPRAY_DENSITY_STEP).min(1.0);
+            toast(tr!("🖌️ Spray density: {:.0}%", brush.density * 100.0));
+        } else {
+            brush.size = (brush.size + 1).min(MAX_BRUSH_SIZE);
+            toast(tr!("🖌️ Brush size: {}", brush.size));
+        }
+    }
+    if keys.just_pressed(&keyboard_input, Action::Spray) {
+        brush.spray = !brush.spray;
+        toast(tr!("🖌️ Spray: {}", if brush.spray { "on" } else { "off" }));
+    }
+    if keys.just_pressed(&keyboard_input, Action::BrushShape) {
+        brush.shape = brush.shape.next();
+        brush.line_start = None;
+        toast(tr!("🖌️ Brush shape: {}", brush.shape.name()));
     }
 }
 
@@ -344,78 +388,59 @@ fn handle_mouse_input(
     camera_state: Res<CameraState>,
     mut grid: ResMut<InfiniteGrid>,
     game_config: Res<GameConfig>,
-    brush: Res<BrushSettings>,
+    mut brush: ResMut<BrushSettings>,
+    stamp: Res<StampState>,
+    active: Res<ActiveTool>,
+    capture: Res<gameofdeath::ui::PointerCapture>,
 ) {
-    // Use pressed() for continuous placement while holding down mouse button
-    if mouse_button_input.pressed(MouseButton::Left) || mouse_button_input.pressed(MouseButton::Right) {
-        if let (Ok(window), Ok((camera_transform, projection, _game_camera))) = (windows.get_single(), camera_query.get_single()) {
-            if let Some(cursor_position) = window.cursor_position() {
-                // Use the working coordinate conversion
-                let window_size = Vec2::new(window.width(), window.height());
-                
-                let world_pos = screen_to_world(cursor_position, camera_transform, projection, window_size);
-                let (grid_x, grid_y) = world_to_grid(world_pos, &camera_state);
+    if brush.stroke_last.is_some() && !mouse_button_input.any_pressed([MouseButton::Left, MouseButton::Right]) {
+        brush.stroke_last = None;
+    }
+    // Clicks belong to the stamp tool while a pattern is held, and to any panel under the pointer
+    if stamp.is_armed() || !active.tool.paints() || capture.captured {
+        return;
+    }
 
-                let shift = keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
-                let alt = keyboard_input.pressed(KeyCode::AltLeft) || keyboard_input.pressed(KeyCode::AltRight);
+    let (Ok(window), Ok((camera_transform, projection, _game_camera))) = (windows.get_single(), camera_query.get_single()) else { return };
+    let Some((grid_x, grid_y)) = cursor_grid_position(window, camera_transform, projection, &camera_state) else { return };
 
-                if mouse_button_input.pressed(MouseButton::Left) {
-                    let state = state_for_click(game_config.current_rule, MouseButton::Left, shift, alt);
-                    apply_brush(&mut grid, grid_x, grid_y, brush.size, state);
-                }
-                
-                if mouse_button_input.pressed(MouseButton::Right) {
-                    let state = state_for_click(game_config.current_rule, MouseButton::Right, shift, alt);
-                    apply_brush(&mut grid, grid_x, grid_y, brush.size, state);
+    let shift = keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    let alt = keyboard_input.pressed(KeyCode::AltLeft) || keyboard_input.pressed(KeyCode::AltRight);
+
+    for button in [MouseButton::Left, MouseButton::Right] {
+        let state = if active.tool == Tool::Erase { gameofdeath::CellState::Dead } else { state_for_click(game_config.current_rule, button, shift, alt) };
+        if active.tool == Tool::Line || brush.shape == BrushShape::Line {
+            // Lines run from where the button went down to where it is released
+            if mouse_button_input.just_pressed(button) {
+                brush.line_start = Some((grid_x, grid_y));
+            }
+            if mouse_button_input.just_released(button) {
+                if let Some(start) = brush.line_start.take() {
+                    for (x, y) in line_cells(start, (grid_x, grid_y)) {
+                        apply_brush(&mut grid, x, y, &brush, state);
+                    }
                 }
             }
-        }
-    }
-}
-
-fn apply_brush(grid: &mut InfiniteGrid, cx: i32, cy: i32, size: u32, state: CellState) {
-    let half = (size as i32) / 2;
-    for dy in -half..=half {
-        for dx in -half..=half {
-            grid.set(cx + dx, cy + dy, state);
-        }
-    }
-}
-
-/// Return the cell state that should be written for a click under the given rule.
-/// Left click usually creates, right click either deletes or places an alternate species.
-fn state_for_click(rule: RuleType, button: MouseButton, shift: bool, alt: bool) -> CellState {
-    use MouseButton::{Left, Right};
-    match rule {
-        RuleType::WireWorld => {
-            if shift { CellState::ElectronHead }
-            else if alt { CellState::ElectronTail }
-            else if button == Left { CellState::Wire } else { CellState::Dead }
-        }
-        RuleType::Immigration => {
-            match button {
-                Left => CellState::SpeciesA,
-                Right => CellState::SpeciesB,
-                _ => CellState::Dead,
+        } else if mouse_button_input.pressed(button) {
+            // Use pressed() for continuous placement while holding down mouse button,
+            // filling in the cells passed since last frame
+            let from = brush.stroke_last.unwrap_or((grid_x, grid_y));
+            for (x, y) in line_cells(from, (grid_x, grid_y)) {
+                apply_brush(&mut grid, x, y, &brush, state);
             }
-        }
-        // For Brian's Brain a firing cell is represented by Alive
-        RuleType::Brian => {
-            if shift { CellState::Dying }
-            else if button == Left { CellState::Alive } else { CellState::Dead }
-        }
-        _ => {
-            // Default Life-like rules: place Alive, remove on right-click
-            if button == Left { CellState::Alive } else { CellState::Dead }
+            brush.stroke_last = Some((grid_x, grid_y));
         }
     }
 }
 
 fn update_simulation(
-    time: Res<Time>,
+    (time, mut diagnostics): (Res<Time>, Diagnostics),
     mut game_stats: ResMut<GameStats>,
     mut grid: ResMut<InfiniteGrid>,
+    mut layers: ResMut<GridLayers>,
     game_config: Res<GameConfig>,
+    (prune, camera_query, camera_state): (Res<PrunePolicy>, Query<&Transform, With<GameCamera>>, Res<CameraState>),
+    mut rewind: ResMut<RewindBuffer>,
 ) {
     if !game_stats.is_running {
         return;
@@ -423,10 +448,46 @@ fn update_simulation(
 
     let current_time = time.elapsed_secs_f64();
     if current_time - game_stats.last_update >= game_stats.update_interval {
+        let started = std::time::Instant::now();
+        rewind.record(&grid);
         grid.update(game_config.current_rule);
+        layers.step_all();
+        diagnostics.add_measurement(&STEP_TIME, || started.elapsed().as_secs_f64() * 1000.0);
+        if prune.is_enabled() {
+            // Keep whatever the camera is looking at, discard the far reaches
+            let centre = camera_query.get_single().map_or((0, 0), |transform| {
+                let cell = transform.translation.truncate() / camera_state.cell_size;
+                (cell.x.round() as i32, cell.y.round() as i32)
+            });
+            grid.prune(centre, &prune);
+            for layer in &mut layers.layers {
+                layer.grid.prune(centre, &prune);
+            }
+        }
         game_stats.generation += 1;
         game_stats.last_update = current_time;
+        if game_stats.count_generation() {
+            toast(tr!("⏱️ Run finished at generation {}", game_stats.generation));
+        }
+    }
+}
+
+/// Feed each new generation to the object tracker so moving objects (gliders, ships)
+/// are available to camera and audio systems. Skipped for large soups where the
+/// component search would dominate the frame.
+fn track_moving_objects(
+    game_stats: Res<GameStats>,
+    grid: Res<InfiniteGrid>,
+    mut tracker: ResMut<ObjectTracker>,
+) {
+    if tracker.last_generation() == Some(game_stats.generation) {
+        return;
     }
+    if game_stats.generation == 0 || grid.population() > 5000 {
+        tracker.reset();
+        return;
+    }
+    tracker.observe(&grid, game_stats.generation);
 }
 
 fn update_audio_system(
@@ -436,7 +497,8 @@ fn update_audio_system(
     game_stats: Res<GameStats>,
     game_config: Res<GameConfig>,
     mut audio_cache: ResMut<AudioCache>,
-    mut groove: Option<NonSendMut<IllbientGroove>>,
+    mut groove: ResMut<IllbientGroove>,
+    mut kira: Option<ResMut<KiraManager>>,
 ) {
     match game_config.audio_engine {
         AudioEngine::Spatial | AudioEngine::Hybrid => {
@@ -504,9 +566,9 @@ fn update_audio_system(
                 update_hybrid_dungeon_synth(feature_array);
                 
                 // Drive illbient groove
-                if let Some(mut g) = groove {
+                if let Some(kira) = kira.as_deref_mut() {
                     let root = gameofdeath::audio::get_scale_root();
-                    g.update(&features, root);
+                    groove.update(kira, &features, root);
                 }
                 
                 // Determine if we should log this update
@@ -533,8 +595,7 @@ fn update_audio_system(
         AudioEngine::DDSP => {
             // Extract game features for DDSP
             let features = extract_game_features(&grid, &camera_state, game_stats.generation);
-            // DDSP processing placeholder
-            println!("🎵 DDSP features: {:?}", features);
+            update_ddsp_audio(features);
         }
         AudioEngine::DungeonSynth => {
             // Dungeon synth processing placeholder
@@ -543,15 +604,40 @@ fn update_audio_system(
     }
 }
 
+/// Give the clusters in view their panned voices while the spatial engine plays, each time a
+/// generation passes or the view moves
+fn update_spatial_view(
+    grid: Res<InfiniteGrid>,
+    game_config: Res<GameConfig>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    camera_state: Res<CameraState>,
+    mut last: Local<Option<(u64, [i32; 4])>>,
+) {
+    if game_config.audio_engine != AudioEngine::Spatial {
+        return;
+    }
+    let Ok((transform, projection)) = camera_query.get_single() else { return };
+    let view = visible_grid_bounds(transform, projection, &camera_state);
+    let current = (grid.version(), [view.min_x, view.max_x, view.min_y, view.max_y]);
+    if last.replace(current) == Some(current) {
+        return;
+    }
+    update_spatial_audio(&grid, &view);
+}
+
 /// Update game UI state
 fn update_game_ui(
     mut ui_state: ResMut<UiState>,
     game_stats: Res<GameStats>,
-    mut grid: ResMut<InfiniteGrid>,
+    grid: Res<InfiniteGrid>,
     game_config: Res<GameConfig>,
     diagnostics: Res<DiagnosticsStore>,
     camera_query: Query<&GameCamera>,
+    refresh: Res<HudRefresh>,
 ) {
+    if !refresh.due() {
+        return;
+    }
     ui_state.generation = game_stats.generation;
     ui_state.is_running = game_stats.is_running;
     ui_state.update_interval = game_stats.update_interval;
@@ -566,8 +652,9 @@ fn update_game_ui(
         RuleType::Coral => "Coral".to_string(),
         RuleType::Gnarl => "Gnarl".to_string(),
         RuleType::Replicator => "Replicator".to_string(),
+        RuleType::Custom { .. } => game_config.current_rule.rulestring(),
     };
-    ui_state.population = grid.get_alive_cells().len();
+    ui_state.population = grid.population();
     ui_state.audio_volume = game_config.audio_volume;
     
     // Update zoom level from camera
@@ -583,33 +670,30 @@ fn update_game_ui(
     }
 }
 
-/// Show HUD when entering playing state
+/// Show HUD when entering playing state, unless it was toggled off
 fn show_hud(
     mut hud_query: Query<&mut Visibility, With<gameofdeath::ui::HudContainer>>,
-    mut ui_state: ResMut<UiState>,
+    ui_state: Res<UiState>,
 ) {
     for mut visibility in hud_query.iter_mut() {
-        *visibility = Visibility::Visible;
+        *visibility = if ui_state.hud_visible { Visibility::Visible } else { Visibility::Hidden };
     }
-    ui_state.hud_visible = true;
 }
 
-/// Hide HUD when exiting playing state
+/// Hide HUD when exiting playing state; `hud_visible` keeps the in-game setting
 fn hide_hud(
     mut hud_query: Query<&mut Visibility, With<gameofdeath::ui::HudContainer>>,
-    mut ui_state: ResMut<UiState>,
 ) {
     for mut visibility in hud_query.iter_mut() {
         *visibility = Visibility::Hidden;
     }
-    ui_state.hud_visible = false;
 }
 
 /// Handle exiting start screen state
 fn on_exit_start_screen(game_config: Res<GameConfig>) {
     match game_config.audio_engine {
         AudioEngine::DDSP => {
-            println!("🎵 Switching DDSP audio to game mode (placeholder)");
+            println!("🎵 Switching DDSP audio to game mode");
         }
         AudioEngine::DungeonSynth => {
             println!("🏰 Switching dungeon synth to game mode (placeholder)");
@@ -627,10 +711,17 @@ fn on_enter_playing(
     mut grid: ResMut<InfiniteGrid>,
     mut game_stats: ResMut<GameStats>,
     selected_rule: Res<SelectedRule>,
+    (mut theme, mut clear_color): (ResMut<Theme>, ResMut<ClearColor>),
+    (mut render_config, mut trails): (ResMut<CellRenderConfig>, ResMut<DeathTrails>),
+    (baseline, mut active_preset): (Res<PresetBaseline>, ResMut<ActivePreset>),
 ) {
     // Apply the selected rule from start screen to game config
     game_config.current_rule = selected_rule.current;
-    println!("🎯 Applied rule: {} to game", selected_rule.current.name());
+    toast(tr!("🎯 Applied rule: {} to game", selected_rule.current.name()));
+
+    // The rule's own look, if it has one
+    apply_visual_preset(game_config.current_rule, &baseline, &mut active_preset, &mut theme, &mut render_config, &mut trails);
+    clear_color.0 = theme.background.0;
     
     // Ensure no leftover exotic states from a previous game carry over.
     grid.clear();
@@ -645,10 +736,10 @@ fn on_enter_playing(
 
     match game_config.audio_engine {
         AudioEngine::DDSP => {
-            println!("🎮 Game mode: DDSP neural audio active (placeholder)");
+            println!("🎮 Game mode: DDSP neural audio active");
         }
         AudioEngine::Spatial => {
-            println!("🎮 Game mode: Spatial polyphonic audio active");
+            println!("🎮 Game mode: Spatial cluster voices over the hybrid engine active");
         }
         AudioEngine::DungeonSynth => {
             println!("🎮 Game mode: Dungeon synth audio active (placeholder)");
@@ -659,11 +750,116 @@ fn on_enter_playing(
     }
 }
 
+/// Quick save (F5 by default) and quick load (F9) of the whole session
+fn session_hotkeys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut grid: ResMut<InfiniteGrid>,
+    mut game_stats: ResMut<GameStats>,
+    mut game_config: ResMut<GameConfig>,
+    mut camera_query: Query<(&mut Transform, &mut GameCamera)>,
+    mut synth: ResMut<SynthParameters>,
+    keys: Res<Keybindings>,
+) {
+    if keys.just_pressed(&keyboard_input, Action::QuickSave) {
+        let snapshot = capture_session(&grid, &game_stats, &game_config, camera_query.get_single().ok(), &synth);
+        match snapshot.save(SESSION_PATH) {
+            Ok(()) => toast(tr!("💾 Session saved to {} ({} cells)", SESSION_PATH, grid.population())),
+            Err(e) => toast(tr!("💾 Could not save session: {}", e)),
+        }
+    }
+
+    if keys.just_pressed(&keyboard_input, Action::QuickLoad) {
+        let result = SessionSnapshot::load(SESSION_PATH).and_then(|snapshot| {
+            apply_session(&snapshot, &mut grid, &mut game_stats, &mut game_config, camera_query.get_single_mut().ok(), &mut synth)
+        });
+        match result {
+            Ok(()) => toast(tr!("💾 Session loaded from {}", SESSION_PATH)),
+            Err(e) => toast(tr!("💾 Could not load session: {}", e)),
+        }
+    }
+}
+
+/// Snapshot the game as it stands, camera and synth included
+fn capture_session(
+    grid: &InfiniteGrid,
+    game_stats: &GameStats,
+    game_config: &GameConfig,
+    camera: Option<(&Transform, &GameCamera)>,
+    synth: &SynthParameters,
+) -> SessionSnapshot {
+    let mut snapshot = SessionSnapshot::new(grid, game_config.current_rule);
+    snapshot.generation = game_stats.generation;
+    snapshot.update_interval = game_stats.update_interval;
+    snapshot.synth_volume = synth.volume;
+    snapshot.synth_mix = synth.mix;
+    if let Some((transform, camera)) = camera {
+        snapshot.camera = transform.translation.truncate().into();
+        snapshot.zoom = camera.target_zoom;
+    }
+    snapshot
+}
+
+/// Save a game being left for the start screen, so it shows up in the saves listed there
+fn autosave_session(
+    (grid, game_stats, game_config): (Res<InfiniteGrid>, Res<GameStats>, Res<GameConfig>),
+    camera_query: Query<(&Transform, &GameCamera)>,
+    synth: Res<SynthParameters>,
+) {
+    // Reset clears the grid first; nothing worth keeping then
+    if grid.population() == 0 {
+        return;
+    }
+    let snapshot = capture_session(&grid, &game_stats, &game_config, camera_query.get_single().ok(), &synth);
+    if let Err(e) = snapshot.save(AUTOSAVE_PATH) {
+        toast(tr!("💾 Could not autosave: {}", e));
+    }
+}
+
+/// Apply a snapshot chosen with "Continue" on the start screen, after the normal setup
+fn apply_pending_session(
+    mut pending: ResMut<PendingSession>,
+    mut grid: ResMut<InfiniteGrid>,
+    mut game_stats: ResMut<GameStats>,
+    mut game_config: ResMut<GameConfig>,
+    mut camera_query: Query<(&mut Transform, &mut GameCamera)>,
+    mut synth: ResMut<SynthParameters>,
+) {
+    let Some(snapshot) = pending.0.take() else { return };
+    if let Err(e) = apply_session(&snapshot, &mut grid, &mut game_stats, &mut game_config, camera_query.get_single_mut().ok(), &mut synth) {
+        toast(tr!("💾 Could not restore session: {}", e));
+    }
+}
+
+fn apply_session(
+    snapshot: &SessionSnapshot,
+    grid: &mut InfiniteGrid,
+    game_stats: &mut GameStats,
+    game_config: &mut GameConfig,
+    camera: Option<(Mut<Transform>, Mut<GameCamera>)>,
+    synth: &mut SynthParameters,
+) -> Result<(), SessionError> {
+    let rule = snapshot.rule()?;
+    *grid = snapshot.grid()?;
+    game_config.current_rule = rule;
+    game_stats.generation = snapshot.generation;
+    game_stats.update_interval = snapshot.update_interval.clamp(game_stats.min_update_interval, game_stats.max_update_interval);
+    game_stats.stop();
+    synth.volume = snapshot.synth_volume;
+    synth.mix = snapshot.synth_mix;
+    if let Some((mut transform, mut camera)) = camera {
+        transform.translation.x = snapshot.camera[0];
+        transform.translation.y = snapshot.camera[1];
+        camera.target_zoom = snapshot.zoom.clamp(camera.min_zoom, camera.max_zoom);
+        camera.zoom = camera.target_zoom;
+    }
+    Ok(())
+}
+
 /// Handle exiting playing state  
 fn on_exit_playing(game_config: Res<GameConfig>) {
     match game_config.audio_engine {
         AudioEngine::DDSP => {
-            println!("🎵 Switching DDSP audio to start screen mode (placeholder)");
+            println!("🎵 Switching DDSP audio to start screen mode");
         }
         AudioEngine::DungeonSynth => {
             println!("🏰 Switching dungeon synth to start screen mode (placeholder)");
@@ -691,20 +887,21 @@ fn cleanup_game_entities(
 fn setup_start_screen_audio(config: Res<GameConfig>) {
     match config.audio_engine {
         AudioEngine::Spatial => {
-            // Spatial audio removed - use hybrid instead
-            init_hybrid_dungeon_synth();
-            set_hybrid_volume(config.audio_volume);
-            println!("🔊 Start screen: Using Hybrid audio instead of Spatial");
+            init_spatial_audio();
+            set_hybrid_volume(config.output_volume());
+            println!("🔊 Start screen: Spatial audio initialized! Volume: {:.0}%", config.audio_volume * 100.0);
         },
         AudioEngine::DDSP => {
-            println!("🎵 Start screen: DDSP audio system (placeholder)");
+            init_ddsp_audio();
+            set_hybrid_volume(config.output_volume());
+            println!("🎵 Start screen: DDSP audio initialized! Volume: {:.0}%", config.audio_volume * 100.0);
         },
         AudioEngine::DungeonSynth => {
             println!("🏰 Start screen: Dungeon synth audio system (placeholder)");
         },
         AudioEngine::Hybrid => {
             init_hybrid_dungeon_synth();
-            set_hybrid_volume(config.audio_volume);
+            set_hybrid_volume(config.output_volume());
             println!("🔮 Start screen: Hybrid dungeon synth initialized! Volume: {:.0}%", config.audio_volume * 100.0);
         }
     }
@@ -712,23 +909,40 @@ fn setup_start_screen_audio(config: Res<GameConfig>) {
 
 fn update_start_screen_audio(
     config: Res<GameConfig>,
+    camera_state: Res<CameraState>,
+    mut soup: ResMut<TitleSoup>,
 ) {
-    // The hybrid audio engine automatically handles start screen mode
-    // by processing empty game features (all zeros) when no real game is running
+    // The start screen soup plays the part of the game; only a new generation is worth analysing
+    let stepped = soup.is_changed();
     match config.audio_engine {
-        AudioEngine::Spatial => {
-            // Spatial audio doesn't need continuous updates on start screen
-        },
-        AudioEngine::DDSP => {
-            // DDSP would update here if implemented
+        AudioEngine::DDSP if stepped => {
+            update_ddsp_audio(extract_game_features(&soup.grid, &camera_state, soup.generation));
         },
         AudioEngine::DungeonSynth => {
             // Dungeon synth would update here if implemented
         },
-                 AudioEngine::Hybrid => {
-             // Update hybrid audio with empty game features for ambient start screen audio
-             update_hybrid_dungeon_synth([0.0; 8]); // All zeros = ambient mode
-         }
+        AudioEngine::Spatial | AudioEngine::Hybrid if stepped => {
+            let features = extract_game_features(&soup.grid, &camera_state, soup.generation);
+            let centre = (SOUP_WIDTH as f32 / 2.0, SOUP_HEIGHT as f32 / 2.0);
+            // Refreshing the cached cell list is not a change to the soup
+            let cells = soup.bypass_change_detection().grid.get_alive_cells();
+            update_hybrid_cell_data(cells, centre.0, centre.1, SOUP_WIDTH as f32);
+            update_hybrid_dungeon_synth([
+                features.population,
+                features.density,
+                features.activity,
+                features.cluster_count,
+                features.avg_cluster_size,
+                features.symmetry,
+                features.chaos,
+                features.generation,
+            ]);
+            if config.audio_engine == AudioEngine::Spatial {
+                // The whole soup is in view
+                update_spatial_audio(&soup.grid, &GridBounds { min_x: 0, max_x: SOUP_WIDTH - 1, min_y: 0, max_y: SOUP_HEIGHT - 1 });
+            }
+        },
+        AudioEngine::Spatial | AudioEngine::Hybrid | AudioEngine::DDSP => {}
     }
 }
 
@@ -853,37 +1067,40 @@ fn adjust_cell_scale_and_overlay(
     }
 }
 
-/// Allow quick insertion of demo patterns with number keys 1-5 depending on active rule.
+/// Pick up a demo pattern with number keys 1-3 depending on active rule; it is placed with the stamp tool.
 fn pattern_hotkeys(
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    camera_query: Query<&Transform, With<GameCamera>>,
-    camera_state: Res<CameraState>,
-    mut grid: ResMut<InfiniteGrid>,
     game_config: Res<GameConfig>,
+    mut stamp: ResMut<StampState>,
+    keys: Res<Keybindings>,
 ) {
-    let pos = if let Ok(t) = camera_query.get_single() { t.translation } else { return; };
-    let (cx, cy) = world_to_grid(pos.truncate(), &camera_state);
-
-    if keyboard_input.just_pressed(KeyCode::Digit1) {
-        insert_rule_pattern(1, &game_config, &mut grid, cx, cy);
-    }
-    if keyboard_input.just_pressed(KeyCode::Digit2) {
-        insert_rule_pattern(2, &game_config, &mut grid, cx, cy);
+    // Alt+digit belongs to the layer visibility toggles
+    if keyboard_input.pressed(KeyCode::AltLeft) || keyboard_input.pressed(KeyCode::AltRight) {
+        return;
     }
-    if keyboard_input.just_pressed(KeyCode::Digit3) {
-        insert_rule_pattern(3, &game_config, &mut grid, cx, cy);
+    let slots = [(Action::Pattern1, 1), (Action::Pattern2, 2), (Action::Pattern3, 3)];
+    for (action, slot) in slots {
+        if keys.just_pressed(&keyboard_input, action) {
+            if let Some(pattern) = rule_pattern(slot, game_config.current_rule) {
+                stamp.arm(pattern);
+            }
+        }
     }
 }
 
-fn insert_rule_pattern(slot: u8, config: &GameConfig, grid: &mut InfiniteGrid, ox: i32, oy: i32) {
-    use gameofdeath::infinite_grid::patterns as pat;
-    match (config.current_rule, slot) {
-        (RuleType::HighLife, 1) => grid.insert_pattern(pat::highlife_replicator(), ox, oy),
-        (RuleType::Conway, 1) => grid.insert_pattern(pat::glider(), ox, oy),
-        (RuleType::Conway, 2) => grid.insert_pattern(pat::blinker(), ox, oy),
-        (RuleType::Conway, 3) => grid.insert_pattern(pat::block(), ox, oy),
-        _ => {}
-    }
+fn rule_pattern(slot: u8, rule: RuleType) -> Option<&'static gameofdeath::infinite_grid::patterns::LibraryPattern> {
+    let name = match (rule, slot) {
+        (RuleType::HighLife, 1) => "replicator",
+        (RuleType::Conway, 1) => "glider",
+        (RuleType::Conway, 2) => "blinker",
+        (RuleType::Conway, 3) => "block",
+        (RuleType::WireWorld, 1) => "clock",
+        (RuleType::WireWorld, 2) => "diode",
+        (RuleType::Seeds, 1) => "seeds c/1 spaceship",
+        (RuleType::Brian, 1) => "brian's brain c/1 spaceship",
+        _ => return None,
+    };
+    gameofdeath::infinite_grid::patterns::find(name)
 }
 
 /// Dynamically populate the HUD panel with rule-specific controls when the rule changes.
@@ -914,7 +1131,7 @@ fn update_rule_controls(
 
     commands.entity(entity).with_children(|parent| {
         parent.spawn((
-            Text::new("Rule Controls:"),
+            Text::new(tr!("Rule Controls:")),
             TextFont { font: font.clone(), font_size: 14.0, ..default() },
             TextColor(Color::rgb(1.0, 0.85, 0.3)),
             RuleControlText,
@@ -930,23 +1147,26 @@ fn update_rule_controls(
     });
 }
 
-fn rule_specific_controls(rule: RuleType) -> Vec<&'static str> {
+fn rule_specific_controls(rule: RuleType) -> Vec<String> {
     match rule {
         RuleType::WireWorld => vec![
-            "LMB: Wire",
-            "Shift+Click: Electron Head",
-            "Alt+Click: Electron Tail",
-            "1: Clock pattern",
+            tr!("LMB: Wire"),
+            tr!("Shift+Click: Electron Head"),
+            tr!("Alt+Click: Electron Tail"),
+            tr!("1: Clock pattern"),
+            tr!("2: Diode"),
         ],
         RuleType::Brian => vec![
-            "LMB: Firing cell",
-            "Shift+Click: Dying cell",
+            tr!("LMB: Firing cell"),
+            tr!("Shift+Click: Dying cell"),
+            tr!("1: Spaceship"),
         ],
         RuleType::Immigration => vec![
-            "LMB: Species A",
-            "RMB: Species B",
+            tr!("LMB: Species A"),
+            tr!("RMB: Species B"),
         ],
-        RuleType::HighLife => vec!["1: Replicator seed"],
+        RuleType::HighLife => vec![tr!("1: Replicator")],
+        RuleType::Seeds => vec![tr!("1: Spaceship")],
         _ => Vec::new(),
     }
 }
@@ -956,19 +1176,54 @@ fn main() {
     
     // Load configuration from file
     let config = Config::load(None);
+    if let Err(error) = gameofdeath::locale::set_locale(&config.locale) {
+        toast(format!("🌐 Can't load locale '{}': {}", config.locale, error));
+    }
+    let theme = Theme::named(&config.theme).with_color_vision(config.color_vision);
+    let (keybindings, keybinding_errors) = Keybindings::from_overrides(&config.keybindings);
+    #[cfg(feature = "midi")]
+    let midi_settings = MidiSettings { port: config.midi_port.clone(), columns: config.midi_columns };
+    for error in &keybinding_errors {
+        toast(tr!("⌨️ Ignoring keybinding: {}", error));
+    }
+    // All before the audio engine is first created, which opens the output and copies the
+    // samples into its sample bank (or the model into its decoders)
+    configure_output(OutputSettings { sample_rate: config.audio_sample_rate, buffer_size: config.audio_buffer_size });
+    if let Some(model) = &config.ddsp_model {
+        match load_ddsp_model(Path::new(model)) {
+            Ok(()) => toast(tr!("🧠 Loaded DDSP model {}", model)),
+            Err(error) => toast(tr!("🧠 Can't load DDSP model {}: {}", model, error)),
+        }
+    }
+    let (sample_count, sample_errors) = load_user_samples(Path::new(&config.sample_dir), config.sample_mode);
+    for (path, error) in &sample_errors {
+        toast(tr!("🎻 Can't load sample {}: {}", path.display(), error));
+    }
+    if sample_count > 0 {
+        toast(tr!("🎻 Loaded {} samples from {}", sample_count, config.sample_dir));
+    }
+    // Preferences saved by the previous run
+    let last_rule = config.last_rule.as_deref().and_then(RuleType::from_rulestring).unwrap_or(RuleType::Conway);
+    let window_position = match config.window_position {
+        Some([x, y]) => WindowPosition::At(IVec2::new(x, y)),
+        None => WindowPosition::Automatic,
+    };
     
-    App::default()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
+    let mut app = App::default();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Game of Death".into(),
-                resolution: (1200.0, 800.0).into(),
+                resolution: (config.window_width.max(320.0), config.window_height.max(240.0)).into(),
+                position: window_position,
                 ..default()
             }),
             ..default()
         }))
         .add_plugins(FrameTimeDiagnosticsPlugin::default())
         .init_state::<GameState>()
-        .init_resource::<GameStats>()
+        .add_computed_state::<InGame>()
+        .insert_resource(GameStats { run_length: config.run_generations, ..default() })
+        .insert_resource(RewindBuffer::new(config.rewind_generations))
         .init_resource::<AudioEnabled>()
         .init_resource::<AudioCache>()
         .init_resource::<OverlayCache>()
@@ -977,17 +1232,131 @@ fn main() {
             current_rule: RuleType::Conway,
             audio_engine: config.audio_engine,
             audio_volume: config.audio_volume,
+            audio_muted: config.audio_muted,
         })
         .init_resource::<InfiniteGrid>()
-        .init_resource::<SelectedRule>()
-        .init_resource::<UiState>()
+        .insert_resource(SelectedRule {
+            current: last_rule,
+            index: RuleType::all().iter().position(|&r| r == last_rule).unwrap_or(0),
+        })
+        .insert_resource(UiState { hud_visible: config.hud_visible, ..default() })
+        .insert_resource(HudRefresh::new(config.hud_refresh))
+        .add_systems(First, tick_hud_refresh)
         .init_resource::<CameraState>()
-        .init_resource::<CellRenderConfig>()
+        .insert_resource(CameraSettings::new(config.min_zoom, config.max_zoom, config.zoom_speed, config.pan_speed))
+        .insert_resource(CellRenderConfig {
+            themes: Theme::available(&theme.name),
+            color_vision: config.color_vision,
+            lod_zoom: config.heatmap_zoom,
+            lod_enabled: config.heatmap_zoom > 0.0,
+            generation_colors: config.age_colors,
+            age_span: config.age_span,
+            bloom_intensity: config.bloom_intensity.max(0.0),
+            shader_cells: config.cell_shader,
+            reduced_motion: config.reduced_motion,
+            pixel_mode: config.pixel_mode,
+            isometric: config.isometric,
+            ..default()
+        })
         .init_resource::<CellTextureCache>()
-        .init_resource::<BrushSettings>()
-        .insert_non_send_resource(IllbientGroove::new(100.0))
+        .init_resource::<GridChanges>()
+        .init_resource::<ObjectTracker>()
+        .init_resource::<PendingSession>()
+        .insert_resource(IllbientGroove::new(100.0))
         .add_plugins(SynthControlPanelPlugin)
-        .add_systems(Startup, (setup_kira, setup_camera, setup_ui, setup_font, setup_start_screen_audio))
+        .insert_resource(SynthParameters { key: KeySelection::from_config(config.key_root.as_deref(), config.key_mode), ..default() })
+        .add_plugins(KeyTextPlugin)
+        .add_plugins(ToolsPlugin)
+        .insert_resource(BrushSettings { size: config.brush_size.clamp(1, MAX_BRUSH_SIZE), ..default() })
+        .add_plugins(LayersPlugin)
+        .add_plugins(PatternBrowserPlugin)
+        .insert_resource(PatternBrowser::new(&config.pattern_dir))
+        .insert_resource(StartSeed::new(&config.pattern_dir))
+        .add_plugins(ExportPlugin)
+        .insert_resource(ExportSettings {
+            cell_pixels: config.export_cell_pixels,
+            dir: config.export_dir.clone().into(),
+            svg_merge_runs: config.svg_merge_runs,
+            record_format: config.record_format,
+            record_fps: config.record_fps,
+            record_generations: config.record_generations,
+            video_format: config.video_format,
+            video_fps: config.video_fps,
+            ffmpeg_path: config.ffmpeg_path.clone(),
+        })
+        .insert_resource(FillSettings { max_cells: config.fill_max_cells })
+        .insert_resource(keybindings)
+        .insert_resource(ClearColor(theme.background.0))
+        .insert_resource(theme)
+        .insert_resource(config.hud_layout)
+        .insert_resource(PrunePolicy { max_distance: config.prune_distance, max_population: config.prune_max_population })
+        .add_plugins(HeatmapPlugin)
+        .add_plugins(PixelModePlugin)
+        .add_plugins(IsometricPlugin)
+        .add_plugins(BackdropPlugin)
+        .add_plugins(TrailsPlugin)
+        .insert_resource(DeathTrails::new(config.trails, config.trail_length))
+        .insert_resource(PresetBaseline {
+            enabled: config.rule_presets,
+            animation_speed: CellRenderConfig::default().animation_speed,
+            bloom_intensity: config.bloom_intensity.max(0.0),
+            trails: config.trails,
+            trail_length: config.trail_length,
+        })
+        .init_resource::<ActivePreset>()
+        .add_plugins(GridOverlayPlugin)
+        .insert_resource(GridOverlay { enabled: config.grid_lines, min_zoom: config.grid_lines_zoom })
+        .add_plugins(GlowPlugin)
+        .add_plugins(CellMaterialPlugin)
+        .init_resource::<gameofdeath::ui::PointerCapture>()
+        .add_systems(PreUpdate, gameofdeath::ui::release_pointer_capture.before(bevy::ui::UiSystem::Focus))
+        .add_plugins(MinimapPlugin)
+        .insert_resource(Minimap::new(config.minimap))
+        .insert_resource(Confirmation::new(config.confirm_destructive))
+        .insert_resource(UiScale(config.ui_scale.clamp(MIN_SCALE, MAX_SCALE)))
+        .insert_resource(FontScale(config.font_scale.clamp(MIN_SCALE, MAX_SCALE)))
+        .add_plugins(PatternMarkersPlugin)
+        .insert_resource(PatternMarkers::new(config.pattern_markers))
+        .add_plugins(PopulationGraphPlugin)
+        .insert_resource(PopulationGraph::new(config.population_graph))
+        .insert_resource(PopulationHistory::new(config.graph_generations))
+        .add_plugins(CameraShakePlugin)
+        .insert_resource(CameraShake::new(config.camera_shake))
+        .add_plugins(ActivityPlugin)
+        .insert_resource(ActivityHeat::new(config.activity_heat, config.activity_half_life))
+        .add_plugins(RenderPanelPlugin)
+        .add_plugins(RuleEditorPlugin)
+        .add_plugins(SpeedSliderPlugin)
+        .add_plugins(ToolbarPlugin)
+        .add_plugins(PerfOverlayPlugin)
+        .add_plugins(SpectrumMeterPlugin)
+        .add_plugins(PauseOverlayPlugin)
+        .add_plugins(ConfirmPlugin)
+        .add_plugins(SplitScreenPlugin)
+        .add_plugins(HelpOverlayPlugin)
+        .add_plugins(SettingsMenuPlugin)
+        .add_plugins(ControlsScreenPlugin)
+        .add_plugins(ToastPlugin)
+        .add_plugins(BrushIndicatorPlugin)
+        .add_plugins(StepCountsPlugin)
+        .add_plugins(TitleSoupPlugin)
+        .add_plugins(RulePreviewPlugin)
+        .add_plugins(StartSeedPlugin)
+        .add_plugins(AudioPickerPlugin)
+        .add_plugins(SessionsListPlugin)
+        .add_plugins(TutorialPlugin)
+        .add_plugins(OscPlugin)
+        .add_plugins(SequencerPlugin)
+        .insert_resource(Sequencer::new(config.sequencer_bpm))
+        .insert_resource(OscSettings { target: config.osc_target.clone(), rate: config.osc_rate, regions: config.osc_regions })
+        .add_plugins(UiScalingPlugin)
+        .add_plugins(HudLayoutPlugin)
+        .add_plugins(CommandPalettePlugin)
+        .add_plugins(ConsolePlugin)
+        .add_plugins(PreferencesPlugin)
+        .insert_resource(Preferences::new(config, None))
+        .add_systems(Startup, (setup_kira, setup_camera, setup_ui, setup_font, setup_start_screen_audio.after(setup_kira)))
+        .add_systems(Last, shutdown_kira)
         .add_systems(
             Update,
             (
@@ -999,18 +1368,23 @@ fn main() {
         )
         .add_systems(OnEnter(GameState::StartScreen), setup_start_screen)
         .add_systems(OnExit(GameState::StartScreen), (cleanup_start_screen, on_exit_start_screen))
-        .add_systems(OnEnter(GameState::Playing), (show_hud, setup_game_audio, on_enter_playing))
-        .add_systems(OnExit(GameState::Playing), (hide_hud, on_exit_playing, cleanup_game_entities))
+        .add_systems(OnEnter(InGame), (show_hud, setup_game_audio, on_enter_playing, apply_start_seed.after(on_enter_playing), apply_pending_session.after(apply_start_seed)))
+        .add_systems(OnExit(InGame), (autosave_session, hide_hud, on_exit_playing, cleanup_game_entities))
         .add_systems(
             Update,
             (
+                fit_view_to_pattern.before(handle_camera_controls),
                 handle_camera_controls,
+                handle_gestures.after(handle_camera_controls),
                 handle_game_input,
                 update_simulation,
+                track_moving_objects.after(update_simulation),
                 handle_mouse_input,
                 // Ensure cell animations/despawns happen after rendering logic to avoid race conditions.
+                collect_grid_changes.before(render_optimized_cells),
                 render_optimized_cells,
                 animate_cell_textures.after(render_optimized_cells),
+                toggle_age_colors,
                 update_cell_render_config.after(render_optimized_cells),
                 animate_cells.after(render_optimized_cells),
                 update_game_ui,
@@ -1023,5 +1397,13 @@ fn main() {
             )
                 .run_if(in_state(GameState::Playing))
         )
-        .run();
+        .add_systems(Update, (session_hotkeys, update_spatial_view).run_if(in_state(GameState::Playing)))
+        .add_systems(Update, gameofdeath::ui::refresh_help_text)
+        .add_systems(Update, apply_camera_settings.before(fit_view_to_pattern))
+        .add_systems(Update, (cycle_theme, gameofdeath::ui::apply_hud_theme.after(cycle_theme)).run_if(in_state(GameState::Playing)));
+    #[cfg(feature = "inspector")]
+    app.add_plugins(InspectorPlugin);
+    #[cfg(feature = "midi")]
+    app.add_plugins(MidiPlugin).insert_resource(midi_settings);
+    app.run();
 } 
\ No newline at end of file
@@ -0,0 +1,170 @@
+//! MIDI input (`midi` feature).
+//!
+//! Notes from a MIDI keyboard or sequencer place cells as they are played. The 128 note
+//! numbers are laid out `MidiSettings::columns` to a row, an octave per row by default, with
+//! middle C at the centre of the view and higher notes further right and up. Velocity sets
+//! the brush size, from a single cell for the softest notes to `MAX_BRUSH_SIZE`; the current
+//! brush shape, spray and symmetry apply as they do to the mouse. The first input port whose
+//! name contains `MidiSettings::port` (or simply the first one) is opened at startup.
+
+use bevy::prelude::*;
+use crossbeam_channel::{unbounded, Receiver};
+use midir::{MidiInput, MidiInputConnection};
+use std::sync::Mutex;
+use crate::camera::{world_to_grid, CameraState, GameCamera};
+use crate::infinite_grid::InfiniteGrid;
+use crate::start_screen::GameState;
+use crate::tools::brush::{apply_brush, BrushSettings, MAX_BRUSH_SIZE};
+use crate::tools::state_for_click;
+use crate::ui::toast;
+use crate::{tr, GameConfig};
+
+/// Note placed at the centre of the view
+pub const MIDDLE_C: u8 = 60;
+pub const DEFAULT_MIDI_COLUMNS: u32 = 12;
+
+/// Which port to open and how notes map onto the grid
+#[derive(Resource, Debug, Clone)]
+pub struct MidiSettings {
+    /// Part of the input port's name to look for; the first port if unset
+    pub port: Option<String>,
+    /// Notes per grid row
+    pub columns: u32,
+}
+
+impl Default for MidiSettings {
+    fn default() -> Self {
+        Self { port: None, columns: DEFAULT_MIDI_COLUMNS }
+    }
+}
+
+/// A note-on message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MidiNote {
+    pub note: u8,
+    pub velocity: u8,
+}
+
+/// The note-on in a raw MIDI message, on any channel; note-ons with velocity 0 are note-offs
+pub fn parse_note_on(message: &[u8]) -> Option<MidiNote> {
+    match *message {
+        [status, note, velocity, ..] if status & 0xF0 == 0x90 && velocity > 0 => {
+            Some(MidiNote { note: note & 0x7F, velocity: velocity & 0x7F })
+        }
+        _ => None,
+    }
+}
+
+/// Cell a note lands on, relative to the centre of the view
+pub fn note_offset(note: u8, columns: u32) -> (i32, i32) {
+    let columns = columns.max(1) as i32;
+    let (centre, note) = (MIDDLE_C as i32, note as i32);
+    (note.rem_euclid(columns) - centre.rem_euclid(columns), note.div_euclid(columns) - centre.div_euclid(columns))
+}
+
+/// Brush diameter for a velocity of 1..=127
+pub fn velocity_brush_size(velocity: u8) -> u32 {
+    1 + (velocity.saturating_sub(1) as u32 * (MAX_BRUSH_SIZE - 1)) / 126
+}
+
+/// The open port, and the notes its callback has received
+#[derive(Resource)]
+struct MidiConnection {
+    _connection: Mutex<MidiInputConnection<()>>,
+    notes: Receiver<MidiNote>,
+}
+
+pub struct MidiPlugin;
+
+impl Plugin for MidiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MidiSettings>()
+            .add_systems(Startup, connect_midi)
+            .add_systems(Update, play_midi_notes);
+    }
+}
+
+fn connect_midi(mut commands: Commands, settings: Res<MidiSettings>) {
+    let input = match MidiInput::new("gameofdeath") {
+        Ok(input) => input,
+        Err(e) => return warn!("MIDI input unavailable: {}", e),
+    };
+    let ports = input.ports();
+    let port = ports.iter().find(|port| {
+        let name = input.port_name(port).unwrap_or_default();
+        settings.port.as_ref().is_none_or(|wanted| name.to_lowercase().contains(&wanted.to_lowercase()))
+    });
+    let Some(port) = port else {
+        if let Some(wanted) = &settings.port {
+            warn!("No MIDI input port matching {:?}", wanted);
+        }
+        return;
+    };
+    let name = input.port_name(port).unwrap_or_default();
+    let (sender, notes) = unbounded();
+    let connected = input.connect(port, "gameofdeath-notes", move |_, message, _| {
+        if let Some(note) = parse_note_on(message) {
+            let _ = sender.send(note);
+        }
+    }, ());
+    match connected {
+        Ok(connection) => {
+            toast(tr!("🎹 MIDI input: {}", name));
+            commands.insert_resource(MidiConnection { _connection: Mutex::new(connection), notes });
+        }
+        Err(e) => warn!("Could not open MIDI port {}: {}", name, e),
+    }
+}
+
+/// Place the cells of the notes played since the last frame; notes played outside the game
+/// are dropped
+fn play_midi_notes(
+    midi: Option<Res<MidiConnection>>,
+    (settings, state, game_config): (Res<MidiSettings>, Res<State<GameState>>, Res<GameConfig>),
+    mut grid: ResMut<InfiniteGrid>,
+    brush: Res<BrushSettings>,
+    cameras: Query<&Transform, With<GameCamera>>,
+    camera_state: Res<CameraState>,
+) {
+    let Some(midi) = midi else { return };
+    let notes: Vec<MidiNote> = midi.notes.try_iter().collect();
+    if notes.is_empty() || *state.get() != GameState::Playing {
+        return;
+    }
+    let Ok(camera) = cameras.get_single() else { return };
+    let (cx, cy) = world_to_grid(camera.translation.truncate(), &camera_state);
+    let cell = state_for_click(game_config.current_rule, MouseButton::Left, false, false);
+    for MidiNote { note, velocity } in notes {
+        let (dx, dy) = note_offset(note, settings.columns);
+        let played = BrushSettings {
+            size: velocity_brush_size(velocity),
+            shape: brush.shape,
+            symmetry: brush.symmetry,
+            spray: brush.spray,
+            density: brush.density,
+            ..Default::default()
+        };
+        apply_brush(&mut grid, cx + dx, cy + dy, &played, cell);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_map_to_cells_and_velocity_to_brush_size() {
+        assert_eq!(parse_note_on(&[0x93, 64, 100]), Some(MidiNote { note: 64, velocity: 100 }));
+        assert_eq!(parse_note_on(&[0x90, 64, 0]), None);
+        assert_eq!(parse_note_on(&[0x80, 64, 100]), None);
+
+        assert_eq!(note_offset(MIDDLE_C, 12), (0, 0));
+        assert_eq!(note_offset(MIDDLE_C + 1, 12), (1, 0));
+        assert_eq!(note_offset(MIDDLE_C + 12, 12), (0, 1));
+        assert_eq!(note_offset(MIDDLE_C - 1, 12), (11, -1));
+        assert_eq!(note_offset(MIDDLE_C + 3, 8), (3, 0));
+
+        assert_eq!(velocity_brush_size(1), 1);
+        assert_eq!(velocity_brush_size(127), MAX_BRUSH_SIZE);
+    }
+}
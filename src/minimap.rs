@@ -0,0 +1,293 @@
+//! Corner minimap.
+//!
+//! A small panel in the bottom-left corner showing the whole pattern: the area covered by
+//! the grid's bounds and the current view, squared up, drawn as a density texture like the
+//! zoomed-out heatmap, with the camera's viewport outlined on top. Clicking or dragging on
+//! the minimap moves the camera there. The texture is rebuilt at most every
+//! `REFRESH_SECONDS`, since counting a very large pattern takes a while. I toggles the
+//! minimap with the default bindings; it hides along with the HUD.
+
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::ui::{RelativeCursorPosition, UiSystem};
+use crate::camera::{CameraState, GameCamera};
+use crate::heatmap::{density_pixels, BlockRegion};
+use crate::infinite_grid::{GridBounds, InfiniteGrid};
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::{GameState, InGame};
+use crate::theme::Theme;
+use crate::ui::{toast, HudColor, PointerCapture, UiState};
+use crate::tr;
+use crate::CellState;
+
+/// Side of the minimap panel, in logical pixels
+const PANEL_SIZE: f32 = 160.0;
+
+/// Longest texture side, in texels
+const MINIMAP_TEXELS: f32 = 128.0;
+
+const REFRESH_SECONDS: f32 = 0.25;
+
+/// Cells the minimap shows, in continuous cell coordinates (cell `n` spans `n ± 0.5`)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MinimapFrame {
+    pub min: Vec2,
+    pub size: Vec2,
+}
+
+impl MinimapFrame {
+    /// Square frame around the pattern `bounds` and the view `view_min..view_max`, with a
+    /// margin so neither touches the edge
+    pub fn around(bounds: Option<&GridBounds>, view_min: Vec2, view_max: Vec2) -> Self {
+        let (mut min, mut max) = (view_min, view_max);
+        if let Some(bounds) = bounds {
+            min = min.min(Vec2::new(bounds.min_x as f32, bounds.min_y as f32) - 0.5);
+            max = max.max(Vec2::new(bounds.max_x as f32, bounds.max_y as f32) + 0.5);
+        }
+        let side = (max - min).max_element().max(1.0) * 1.1;
+        let centre = (min + max) / 2.0;
+        Self { min: centre - side / 2.0, size: Vec2::splat(side) }
+    }
+
+    /// The frame covered exactly by the blocks of `region`
+    pub fn of_region(region: &BlockRegion) -> Self {
+        let block = region.block as f32;
+        Self {
+            min: Vec2::new(region.min_bx as f32, region.min_by as f32) * block - 0.5,
+            size: Vec2::new(region.width as f32, region.height as f32) * block,
+        }
+    }
+
+    /// Position of `cell` on the panel, from (0, 0) at the top-left to (1, 1) at the bottom-right
+    pub fn to_panel(&self, cell: Vec2) -> Vec2 {
+        let t = (cell - self.min) / self.size;
+        Vec2::new(t.x, 1.0 - t.y)
+    }
+
+    /// Inverse of `to_panel`
+    pub fn from_panel(&self, panel: Vec2) -> Vec2 {
+        self.min + Vec2::new(panel.x, 1.0 - panel.y) * self.size
+    }
+}
+
+#[derive(Resource)]
+pub struct Minimap {
+    pub enabled: bool,
+    image: Option<Handle<Image>>,
+    frame: Option<MinimapFrame>,
+    refresh: Timer,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+impl Minimap {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled, image: None, frame: None, refresh: Timer::from_seconds(REFRESH_SECONDS, TimerMode::Repeating) }
+    }
+}
+
+#[derive(Component)]
+pub struct MinimapPanel;
+
+/// Outline of the camera's view on the minimap
+#[derive(Component)]
+pub struct MinimapViewport;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Minimap>()
+            .add_systems(OnEnter(InGame), spawn_minimap)
+            .add_systems(OnExit(InGame), despawn_minimap)
+            // Before Update, so a click on the minimap never reaches the painting tools
+            .add_systems(PreUpdate, minimap_clicks.after(UiSystem::Focus).run_if(in_state(GameState::Playing)))
+            .add_systems(
+                Update,
+                (minimap_controls, update_minimap.after(minimap_controls), recolour_minimap).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+fn spawn_minimap(mut commands: Commands, theme: Res<Theme>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                width: Val::Px(PANEL_SIZE),
+                height: Val::Px(PANEL_SIZE),
+                border: UiRect::all(Val::Px(1.0)),
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            ImageNode::default(),
+            BackgroundColor(theme.hud.panel.0),
+            BorderColor(theme.hud.text.0.with_alpha(0.4)),
+            HudColor::Panel,
+            RelativeCursorPosition::default(),
+            Visibility::Hidden,
+            MinimapPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Node { position_type: PositionType::Absolute, border: UiRect::all(Val::Px(1.0)), ..default() },
+                BorderColor(theme.hud.zoom.0),
+                MinimapViewport,
+            ));
+        });
+}
+
+fn despawn_minimap(mut commands: Commands, panels: Query<Entity, With<MinimapPanel>>, mut minimap: ResMut<Minimap>) {
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+    minimap.image = None;
+    minimap.frame = None;
+}
+
+fn minimap_controls(keyboard_input: Res<ButtonInput<KeyCode>>, keys: Res<Keybindings>, mut minimap: ResMut<Minimap>) {
+    if keys.just_pressed(&keyboard_input, Action::Minimap) {
+        minimap.enabled = !minimap.enabled;
+        toast(tr!("🗺️ Minimap {}", if minimap.enabled { "on" } else { "off" }));
+    }
+}
+
+/// Centre the camera on the point pressed, following the pointer while the button is held
+/// (even off the panel), and keep those clicks from painting under the panel
+fn minimap_clicks(
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    minimap: Res<Minimap>,
+    panels: Query<(&RelativeCursorPosition, &Visibility), With<MinimapPanel>>,
+    mut camera_query: Query<&mut Transform, With<GameCamera>>,
+    camera_state: Res<CameraState>,
+    mut capture: ResMut<PointerCapture>,
+    mut dragging: Local<bool>,
+) {
+    let Ok((cursor, visibility)) = panels.get_single() else { return };
+    let over = *visibility != Visibility::Hidden && cursor.mouse_over();
+    if mouse_button_input.just_pressed(MouseButton::Left) {
+        *dragging = over;
+    }
+    if !mouse_button_input.pressed(MouseButton::Left) {
+        *dragging = false;
+    }
+    if *dragging {
+        if let (Some(frame), Some(position), Ok(mut transform)) = (minimap.frame, cursor.normalized, camera_query.get_single_mut()) {
+            let world = frame.from_panel(position.clamp(Vec2::ZERO, Vec2::ONE)) * camera_state.cell_size + camera_state.grid_offset;
+            transform.translation.x = world.x;
+            transform.translation.y = world.y;
+        }
+    }
+    capture.captured |= over || *dragging;
+}
+
+fn update_minimap(
+    (grid, theme): (Res<InfiniteGrid>, Res<Theme>),
+    (ui_state, camera_state, time): (Res<UiState>, Res<CameraState>, Res<Time>),
+    mut minimap: ResMut<Minimap>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    mut panels: Query<(&mut ImageNode, &mut Visibility), With<MinimapPanel>>,
+    mut viewports: Query<&mut Node, With<MinimapViewport>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Ok((mut image_node, mut visibility)) = panels.get_single_mut() else { return };
+    let shown = minimap.enabled && ui_state.hud_visible;
+    visibility.set_if_neq(if shown { Visibility::Visible } else { Visibility::Hidden });
+    let Ok((camera_transform, projection)) = camera_query.get_single() else { return };
+    if !shown {
+        return;
+    }
+
+    let centre = camera_transform.translation.truncate();
+    let to_cells = |world: Vec2| (world - camera_state.grid_offset) / camera_state.cell_size;
+    let view_min = to_cells(centre + projection.area.min);
+    let view_max = to_cells(centre + projection.area.max);
+
+    if minimap.refresh.tick(time.delta()).just_finished() || minimap.frame.is_none() {
+        let frame = MinimapFrame::around(grid.bounds(), view_min, view_max);
+        let block = (frame.size.max_element() / MINIMAP_TEXELS).ceil().max(1.0) as i32;
+        let max = frame.min + frame.size;
+        let region = BlockRegion::covering(
+            (frame.min.x + 0.5).floor() as i32,
+            (max.x - 0.5).ceil() as i32,
+            (frame.min.y + 0.5).floor() as i32,
+            (max.y - 0.5).ceil() as i32,
+            block,
+        );
+        let pixels = density_pixels(&region.count(&grid), region.block, theme.cells.color(CellState::Alive));
+        let size = Extent3d { width: region.width, height: region.height, depth_or_array_layers: 1 };
+        match minimap.image.as_ref().and_then(|handle| images.get_mut(handle)) {
+            Some(image) if image.texture_descriptor.size == size => image.data = pixels,
+            _ => {
+                let mut image = Image::new(size, TextureDimension::D2, pixels, TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::default());
+                image.sampler = ImageSampler::nearest();
+                minimap.image = Some(images.add(image));
+            }
+        }
+        minimap.frame = Some(MinimapFrame::of_region(&region));
+    }
+    if let Some(handle) = &minimap.image {
+        if image_node.image != *handle {
+            image_node.image = handle.clone();
+        }
+    }
+
+    // Outline the view, clipped by the panel when it runs off the frame
+    let Some(frame) = minimap.frame else { return };
+    let top_left = frame.to_panel(Vec2::new(view_min.x, view_max.y));
+    let bottom_right = frame.to_panel(Vec2::new(view_max.x, view_min.y));
+    let extent = (bottom_right - top_left).max(Vec2::splat(0.02));
+    if let Ok(mut node) = viewports.get_single_mut() {
+        node.left = Val::Percent(top_left.x * 100.0);
+        node.top = Val::Percent(top_left.y * 100.0);
+        node.width = Val::Percent(extent.x * 100.0);
+        node.height = Val::Percent(extent.y * 100.0);
+    }
+}
+
+/// Follow theme changes for the outlines (the panel background is a `HudColor`)
+fn recolour_minimap(
+    theme: Res<Theme>,
+    mut panels: Query<&mut BorderColor, With<MinimapPanel>>,
+    mut viewports: Query<&mut BorderColor, (With<MinimapViewport>, Without<MinimapPanel>)>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+    for mut border in panels.iter_mut() {
+        border.0 = theme.hud.text.0.with_alpha(0.4);
+    }
+    for mut border in viewports.iter_mut() {
+        border.0 = theme.hud.zoom.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_covers_pattern_and_view_and_maps_both_ways() {
+        let bounds = GridBounds { min_x: -100, max_x: 50, min_y: 0, max_y: 10 };
+        let frame = MinimapFrame::around(Some(&bounds), Vec2::new(-5.0, -5.0), Vec2::new(5.0, 5.0));
+        assert_eq!(frame.size.x, frame.size.y);
+        for corner in [Vec2::new(-100.5, -5.0), Vec2::new(50.5, 10.5)] {
+            let panel = frame.to_panel(corner);
+            assert!(panel.cmpge(Vec2::ZERO).all() && panel.cmple(Vec2::ONE).all(), "{:?}", panel);
+            assert!((frame.from_panel(panel) - corner).length() < 1e-3);
+        }
+        // Higher y is nearer the top of the panel
+        assert!(frame.to_panel(Vec2::new(0.0, 10.0)).y < frame.to_panel(Vec2::new(0.0, 0.0)).y);
+
+        let region = BlockRegion::covering(-4, 3, 0, 3, 4);
+        assert_eq!(MinimapFrame::of_region(&region), MinimapFrame { min: Vec2::new(-4.5, -0.5), size: Vec2::new(8.0, 4.0) });
+    }
+}
@@ -0,0 +1,163 @@
+//! OSC output of the simulation.
+//!
+//! With `osc_target` set (`host:port`), the game sends UDP Open Sound Control messages at
+//! `osc_rate` per second while playing, for TouchDesigner, Max or anything else that speaks
+//! OSC to build visuals and sound from:
+//!
+//! * `/gameofdeath/features` — the eight features the audio analysis extracts: population,
+//!   density, activity, cluster count, average cluster size, symmetry, chaos and generation.
+//! * `/gameofdeath/regions` — the density (0..1) of each of `osc_regions` × `osc_regions`
+//!   equal regions of the view, row by row from the top left.
+//!
+//! Every argument is a 32-bit float. Messages are sent one per datagram, without bundles.
+
+use std::net::UdpSocket;
+use bevy::prelude::*;
+use crate::audio::latest_game_features;
+use crate::camera::{visible_grid_bounds, CameraState, GameCamera};
+use crate::infinite_grid::{GridBounds, InfiniteGrid};
+use crate::start_screen::GameState;
+use crate::ui::toast;
+use crate::tr;
+
+pub const FEATURES_ADDRESS: &str = "/gameofdeath/features";
+pub const REGIONS_ADDRESS: &str = "/gameofdeath/regions";
+pub const DEFAULT_OSC_RATE: f32 = 10.0;
+pub const DEFAULT_OSC_REGIONS: u32 = 4;
+
+/// Where and how often the simulation is sent
+#[derive(Resource, Debug, Clone)]
+pub struct OscSettings {
+    /// `host:port` to send to; nothing is sent if unset
+    pub target: Option<String>,
+    /// Messages per second of each kind
+    pub rate: f32,
+    /// Regions per side of the view's density grid
+    pub regions: u32,
+}
+
+impl Default for OscSettings {
+    fn default() -> Self {
+        Self { target: None, rate: DEFAULT_OSC_RATE, regions: DEFAULT_OSC_REGIONS }
+    }
+}
+
+/// The socket connected to `OscSettings::target`
+#[derive(Resource)]
+struct OscOutput {
+    socket: UdpSocket,
+    /// Seconds since the last send
+    elapsed: f32,
+}
+
+/// Append an OSC string: the bytes, a terminating NUL and padding to a multiple of four
+fn push_string(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend_from_slice(value.as_bytes());
+    buffer.resize((buffer.len() / 4 + 1) * 4, 0);
+}
+
+/// An OSC message with float arguments
+pub fn encode_message(address: &str, args: &[f32]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(address.len() + args.len() * 5 + 8);
+    push_string(&mut buffer, address);
+    push_string(&mut buffer, &format!(",{}", "f".repeat(args.len())));
+    for arg in args {
+        buffer.extend_from_slice(&arg.to_be_bytes());
+    }
+    buffer
+}
+
+/// Share of live cells in each of `divisions` × `divisions` regions of `bounds`, row by row
+/// from the top left
+pub fn region_densities(grid: &InfiniteGrid, bounds: GridBounds, divisions: u32) -> Vec<f32> {
+    let n = divisions.max(1) as usize;
+    let width = (bounds.max_x - bounds.min_x + 1).max(1) as usize;
+    let height = (bounds.max_y - bounds.min_y + 1).max(1) as usize;
+    let mut counts = vec![0usize; n * n];
+    for (x, y) in grid.cells_in_region(bounds.min_x, bounds.max_x, bounds.min_y, bounds.max_y) {
+        let column = (x - bounds.min_x) as usize * n / width;
+        // Grid y grows upwards, regions are listed from the top
+        let row = (bounds.max_y - y) as usize * n / height;
+        counts[row * n + column] += 1;
+    }
+    (0..n * n)
+        .map(|i| {
+            let (row, column) = (i / n, i % n);
+            let region_width = (column + 1) * width / n - column * width / n;
+            let region_height = (row + 1) * height / n - row * height / n;
+            let area = (region_width * region_height).max(1);
+            counts[i] as f32 / area as f32
+        })
+        .collect()
+}
+
+pub struct OscPlugin;
+
+impl Plugin for OscPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OscSettings>()
+            .add_systems(Startup, connect_osc)
+            .add_systems(Update, send_osc.run_if(in_state(GameState::Playing)));
+    }
+}
+
+fn connect_osc(mut commands: Commands, settings: Res<OscSettings>) {
+    let Some(target) = &settings.target else { return };
+    let connected = UdpSocket::bind("0.0.0.0:0").and_then(|socket| socket.connect(target).map(|_| socket));
+    match connected {
+        Ok(socket) => {
+            toast(tr!("📡 Sending OSC to {}", target));
+            commands.insert_resource(OscOutput { socket, elapsed: 0.0 });
+        }
+        Err(e) => toast(tr!("📡 Can't send OSC to {}: {}", target, e)),
+    }
+}
+
+fn send_osc(
+    output: Option<ResMut<OscOutput>>,
+    settings: Res<OscSettings>,
+    time: Res<Time>,
+    grid: Res<InfiniteGrid>,
+    cameras: Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    camera_state: Res<CameraState>,
+) {
+    let Some(mut output) = output else { return };
+    output.elapsed += time.delta_secs();
+    if output.elapsed < 1.0 / settings.rate.max(0.1) {
+        return;
+    }
+    output.elapsed = 0.0;
+    let features = latest_game_features().unwrap_or_default().to_vector();
+    let mut messages = vec![encode_message(FEATURES_ADDRESS, &features[..8])];
+    if let Ok((transform, projection)) = cameras.get_single() {
+        let bounds = visible_grid_bounds(transform, projection, &camera_state);
+        messages.push(encode_message(REGIONS_ADDRESS, &region_densities(&grid, bounds, settings.regions)));
+    }
+    for message in messages {
+        // Nobody listening yet is normal; the receiver can start at any time
+        if let Err(e) = output.socket.send(&message) {
+            debug!("OSC send failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CellState;
+
+    #[test]
+    fn messages_are_padded_osc_and_regions_count_from_the_top_left() {
+        let message = encode_message("/ab", &[1.0, -2.0]);
+        assert_eq!(&message[..8], b"/ab\0,ff\0");
+        assert_eq!(&message[8..], &[0x3F, 0x80, 0, 0, 0xC0, 0, 0, 0]);
+        assert_eq!(encode_message("/abcd", &[]), b"/abcd\0\0\0,\0\0\0");
+
+        let mut grid = InfiniteGrid::new();
+        grid.set(0, 3, CellState::Alive);
+        grid.set(1, 3, CellState::Alive);
+        grid.set(3, 0, CellState::Alive);
+        let bounds = GridBounds { min_x: 0, max_x: 3, min_y: 0, max_y: 3 };
+        assert_eq!(region_densities(&grid, bounds, 2), vec![0.5, 0.0, 0.0, 0.25]);
+    }
+}
@@ -0,0 +1,190 @@
+//! Pattern bounds and centroid markers.
+//!
+//! Debugging overlays for where the pattern is: a box round the grid's bounds
+//! (`InfiniteGrid::bounds`) and a crosshair on the centroid of the live cells, with a short
+//! trail of where the centroid was over the last `TRAIL_LENGTH` generations so the drift of
+//! a spaceship or an expanding soup is easy to read. F4 cycles through `MarkerMode`s with the
+//! default bindings. `PatternMarkers::centroid` is kept up to date while the centroid is
+//! shown, for anything else that wants to follow the pattern.
+
+use std::collections::VecDeque;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::camera::{CameraState, GameCamera};
+use crate::infinite_grid::InfiniteGrid;
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::GameState;
+use crate::theme::Theme;
+use crate::ui::toast;
+use crate::tr;
+
+/// Generations of centroid positions kept for the drift trail
+pub const TRAIL_LENGTH: usize = 64;
+
+/// Crosshair arm length, in screen pixels
+const CROSSHAIR_PX: f32 = 10.0;
+
+/// Which markers are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkerMode {
+    #[default]
+    Off,
+    Bounds,
+    Centroid,
+    Both,
+}
+
+impl MarkerMode {
+    pub fn next(self) -> Self {
+        match self {
+            MarkerMode::Off => MarkerMode::Bounds,
+            MarkerMode::Bounds => MarkerMode::Centroid,
+            MarkerMode::Centroid => MarkerMode::Both,
+            MarkerMode::Both => MarkerMode::Off,
+        }
+    }
+
+    pub fn shows_bounds(self) -> bool {
+        matches!(self, MarkerMode::Bounds | MarkerMode::Both)
+    }
+
+    pub fn shows_centroid(self) -> bool {
+        matches!(self, MarkerMode::Centroid | MarkerMode::Both)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct PatternMarkers {
+    pub mode: MarkerMode,
+    /// Centroid of the live cells in grid coordinates, as of `version`
+    pub centroid: Option<Vec2>,
+    version: Option<u64>,
+    /// Centroid after each of the recent generations, oldest first
+    trail: VecDeque<(u64, Vec2)>,
+}
+
+impl PatternMarkers {
+    pub fn new(mode: MarkerMode) -> Self {
+        Self { mode, ..default() }
+    }
+
+    /// Recompute the centroid if the grid changed, extending the trail on a new generation
+    pub fn track(&mut self, grid: &InfiniteGrid) {
+        if self.version == Some(grid.version()) {
+            return;
+        }
+        self.version = Some(grid.version());
+        self.centroid = grid.centroid().map(|(x, y)| Vec2::new(x as f32, y as f32));
+
+        let generation = grid.generation();
+        if self.trail.back().is_some_and(|&(last, _)| generation < last) {
+            // Cleared or loaded: the old path no longer means anything
+            self.trail.clear();
+        }
+        match (self.centroid, self.trail.back_mut()) {
+            (None, _) => self.trail.clear(),
+            // Edits within a generation move the latest point rather than adding one
+            (Some(centroid), Some(last)) if last.0 == generation => last.1 = centroid,
+            (Some(centroid), _) => {
+                self.trail.push_back((generation, centroid));
+                while self.trail.len() > TRAIL_LENGTH {
+                    self.trail.pop_front();
+                }
+            }
+        }
+    }
+
+    pub fn trail(&self) -> impl Iterator<Item = Vec2> + '_ {
+        self.trail.iter().map(|&(_, point)| point)
+    }
+}
+
+pub struct PatternMarkersPlugin;
+
+impl Plugin for PatternMarkersPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PatternMarkers>()
+            .add_systems(Update, (marker_controls, track_centroid, draw_markers).chain().run_if(in_state(GameState::Playing)));
+    }
+}
+
+fn marker_controls(keyboard_input: Res<ButtonInput<KeyCode>>, keys: Res<Keybindings>, mut markers: ResMut<PatternMarkers>) {
+    if keys.just_pressed(&keyboard_input, Action::PatternMarkers) {
+        markers.mode = markers.mode.next();
+        toast(tr!("📐 Pattern markers: {}", format!("{:?}", markers.mode)));
+    }
+}
+
+fn track_centroid(grid: Res<InfiniteGrid>, mut markers: ResMut<PatternMarkers>) {
+    if markers.mode.shows_centroid() {
+        markers.track(&grid);
+    } else if markers.version.is_some() {
+        // Start a fresh trail next time it's shown
+        *markers = PatternMarkers::new(markers.mode);
+    }
+}
+
+fn draw_markers(
+    mut gizmos: Gizmos,
+    (grid, markers, theme): (Res<InfiniteGrid>, Res<PatternMarkers>, Res<Theme>),
+    camera_query: Query<&OrthographicProjection, With<GameCamera>>,
+    camera_state: Res<CameraState>,
+) {
+    let Ok(projection) = camera_query.get_single() else { return };
+    let to_world = |point: Vec2| point * camera_state.cell_size + camera_state.grid_offset;
+
+    if markers.mode.shows_bounds() {
+        if let Some(bounds) = grid.bounds() {
+            // Round the outer edges of the corner cells
+            let min = to_world(Vec2::new(bounds.min_x as f32, bounds.min_y as f32) - 0.5);
+            let max = to_world(Vec2::new(bounds.max_x as f32, bounds.max_y as f32) + 0.5);
+            gizmos.rect_2d(Isometry2d::from_translation((min + max) / 2.0), max - min, theme.hud.zoom.0);
+        }
+    }
+    if markers.mode.shows_centroid() {
+        let colour = theme.hud.volume.0;
+        let points: Vec<Vec2> = markers.trail().map(to_world).collect();
+        for (i, pair) in points.windows(2).enumerate() {
+            // Older stretches of the trail fade out
+            let age = (i + 1) as f32 / points.len() as f32;
+            gizmos.line_2d(pair[0], pair[1], colour.with_alpha(colour.alpha() * age));
+        }
+        if let Some(centroid) = markers.centroid.map(to_world) {
+            let arm = CROSSHAIR_PX * projection.scale;
+            gizmos.line_2d(centroid - Vec2::X * arm, centroid + Vec2::X * arm, colour);
+            gizmos.line_2d(centroid - Vec2::Y * arm, centroid + Vec2::Y * arm, colour);
+            gizmos.circle_2d(Isometry2d::from_translation(centroid), arm * 0.6, colour);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CellState;
+
+    #[test]
+    fn centroid_trail_follows_generations() {
+        let mut grid = InfiniteGrid::new();
+        grid.set(0, 0, CellState::Alive);
+        let mut markers = PatternMarkers::new(MarkerMode::Centroid);
+        markers.track(&grid);
+        assert_eq!(markers.centroid, Some(Vec2::ZERO));
+
+        // An edit in the same generation moves the point instead of adding one
+        grid.set(2, 0, CellState::Alive);
+        markers.track(&grid);
+        assert_eq!(markers.centroid, Some(Vec2::new(1.0, 0.0)));
+        assert_eq!(markers.trail().count(), 1);
+
+        grid.update(crate::start_screen::RuleType::Seeds);
+        markers.track(&grid);
+        assert_eq!(markers.trail().count(), 2);
+
+        grid.clear();
+        markers.track(&grid);
+        assert_eq!((markers.centroid, markers.trail().count()), (None, 0));
+        assert_eq!(MarkerMode::Both.next(), MarkerMode::Off);
+    }
+}
@@ -0,0 +1,193 @@
+//! Pause overlay.
+//!
+//! While the simulation is paused the playfield is dimmed a little and a "PAUSED" banner
+//! sits near the top of the window, with buttons to step, run ten generations, clear the
+//! grid (asking first, see `confirm`) and go back to the menu. A single step keeps it up, so stepping through a pattern
+//! doesn't make it flicker. The dimming stays beneath the HUD panels and lets clicks
+//! through to the grid.
+
+use bevy::prelude::*;
+use bevy::ui::{FocusPolicy, RelativeCursorPosition, UiSystem};
+use crate::confirm::{Confirmation, Destructive};
+use crate::infinite_grid::InfiniteGrid;
+use crate::keybindings::{key_name, Action, Keybindings};
+use crate::settings_menu::button_color;
+use crate::start_screen::{GameState, InGame};
+use crate::theme::Theme;
+use crate::ui::{toast, HudColor, PointerCapture};
+use crate::GameStats;
+use crate::tr;
+
+/// Generations the run button goes for
+const QUICK_RUN: u64 = 10;
+/// Opacity of the dimming over the playfield
+const DIM_ALPHA: f32 = 0.25;
+
+/// The dimming and the banner, shown together
+#[derive(Component)]
+struct PauseOverlay;
+
+#[derive(Component)]
+struct PausePanel;
+
+#[derive(Component)]
+struct PauseHint;
+
+#[derive(Component, Clone, Copy)]
+enum PauseButton {
+    Step,
+    Run,
+    Clear,
+    Menu,
+}
+
+impl PauseButton {
+    const ALL: [PauseButton; 4] = [PauseButton::Step, PauseButton::Run, PauseButton::Clear, PauseButton::Menu];
+
+    fn label(self) -> String {
+        match self {
+            PauseButton::Step => tr!("Step"),
+            PauseButton::Run => tr!("Run {}", QUICK_RUN),
+            PauseButton::Clear => tr!("Clear"),
+            PauseButton::Menu => tr!("Menu"),
+        }
+    }
+}
+
+fn resume_hint(keys: &Keybindings) -> String {
+    tr!("{} to resume", key_name(keys.key(Action::Pause)))
+}
+
+pub struct PauseOverlayPlugin;
+
+impl Plugin for PauseOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(InGame), spawn_pause_overlay)
+            .add_systems(OnExit(InGame), despawn_pause_overlay)
+            // Before Update, so a click on the banner never reaches the painting tools
+            .add_systems(PreUpdate, pause_banner_capture.after(UiSystem::Focus).run_if(in_state(GameState::Playing)))
+            .add_systems(Update, (pause_buttons, update_pause_overlay).chain().run_if(in_state(GameState::Playing)));
+    }
+}
+
+fn spawn_pause_overlay(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>, keys: Res<Keybindings>) {
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    let hud = &theme.hud;
+    commands.spawn((
+        Node { position_type: PositionType::Absolute, width: Val::Percent(100.0), height: Val::Percent(100.0), ..default() },
+        BackgroundColor(Color::BLACK.with_alpha(DIM_ALPHA)),
+        FocusPolicy::Pass,
+        // Under the HUD panels, over the playfield
+        GlobalZIndex(-1),
+        Visibility::Hidden,
+        PauseOverlay,
+    ));
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Percent(18.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            FocusPolicy::Pass,
+            Visibility::Hidden,
+            PauseOverlay,
+        ))
+        .with_children(|row| {
+            row.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(8.0),
+                    padding: UiRect::axes(Val::Px(24.0), Val::Px(12.0)),
+                    ..default()
+                },
+                BackgroundColor(hud.panel.0),
+                HudColor::Panel,
+                RelativeCursorPosition::default(),
+                PausePanel,
+            ))
+            .with_children(|banner| {
+                banner.spawn((
+                    Text::new(tr!("PAUSED")),
+                    TextFont { font: font.clone(), font_size: 44.0, ..default() },
+                    TextColor(hud.text.0),
+                    HudColor::Text,
+                ));
+                banner.spawn((
+                    Text::new(resume_hint(&keys)),
+                    TextFont { font: font.clone(), font_size: 14.0, ..default() },
+                    TextColor(hud.text.0.with_alpha(0.7)),
+                    PauseHint,
+                ));
+                banner.spawn(Node { flex_direction: FlexDirection::Row, column_gap: Val::Px(6.0), ..default() }).with_children(|row| {
+                    for button in PauseButton::ALL {
+                        row.spawn((
+                            Button,
+                            Node { padding: UiRect::axes(Val::Px(12.0), Val::Px(4.0)), ..default() },
+                            BackgroundColor(button_color(Interaction::None)),
+                            button,
+                        ))
+                        .with_child((
+                            Text::new(button.label()),
+                            TextFont { font: font.clone(), font_size: 16.0, ..default() },
+                            TextColor(hud.text.0),
+                            HudColor::Text,
+                        ));
+                    }
+                });
+            });
+        });
+}
+
+fn despawn_pause_overlay(mut commands: Commands, overlay: Query<Entity, With<PauseOverlay>>) {
+    for entity in &overlay {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Keep clicks on the banner from painting the grid underneath
+fn pause_banner_capture(game_stats: Res<GameStats>, panels: Query<&RelativeCursorPosition, With<PausePanel>>, mut capture: ResMut<PointerCapture>) {
+    capture.captured |= game_stats.is_paused() && panels.iter().any(RelativeCursorPosition::mouse_over);
+}
+
+fn pause_buttons(
+    mut buttons: Query<(&Interaction, &PauseButton, &mut BackgroundColor), Changed<Interaction>>,
+    (mut game_stats, mut grid): (ResMut<GameStats>, ResMut<InfiniteGrid>),
+    mut confirmation: ResMut<Confirmation>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button, mut colour) in &mut buttons {
+        colour.0 = button_color(*interaction);
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *button {
+            PauseButton::Step => game_stats.step(),
+            PauseButton::Run => {
+                game_stats.start_run(QUICK_RUN);
+                toast(tr!("⏱️ Running {} generations", QUICK_RUN));
+            }
+            PauseButton::Clear => confirmation.request(Destructive::ClearGrid, &mut grid, &mut game_stats, &mut game_state),
+            PauseButton::Menu => game_state.set(GameState::StartScreen),
+        }
+    }
+}
+
+fn update_pause_overlay(
+    (game_stats, keys): (Res<GameStats>, Res<Keybindings>),
+    mut overlay: Query<&mut Visibility, With<PauseOverlay>>,
+    mut hints: Query<&mut Text, With<PauseHint>>,
+) {
+    let shown = if game_stats.is_paused() { Visibility::Visible } else { Visibility::Hidden };
+    for mut visibility in &mut overlay {
+        visibility.set_if_neq(shown);
+    }
+    if keys.is_changed() {
+        for mut text in &mut hints {
+            text.0 = resume_hint(&keys);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Performance overlay.
+//!
+//! / (by default) shows a HUD panel with the numbers that matter when tuning a large run,
+//! beyond the FPS in the stats: frame time, how long the last generation took to compute
+//! (`STEP_TIME`, measured by the simulation), cell sprites drawn against those spawned, the
+//! entity count, how much of real time the audio thread spends making samples, and an
+//! estimate of the memory held by the grid, its layers and the rewind history. The panel
+//! moves and folds like the other HUD panels. Audio load is only measured while it is shown.
+
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin, RegisterDiagnostic};
+use bevy::prelude::*;
+use crate::audio::{set_audio_load_metering, take_audio_load};
+use crate::cell_renderer::CellSprite;
+use crate::infinite_grid::{InfiniteGrid, RewindBuffer};
+use crate::keybindings::{Action, Keybindings};
+use crate::layers::GridLayers;
+use crate::start_screen::GameState;
+use crate::theme::Theme;
+use crate::ui::layout::{spawn_panel_title, HudPanelBody};
+use crate::ui::{toast, HudColor, HudPanel};
+use crate::tr;
+
+/// Milliseconds the last simulation step took, layers and rewind recording included
+pub const STEP_TIME: DiagnosticPath = DiagnosticPath::const_new("simulation/step_time");
+
+/// Seconds between refreshes of the figures
+const REFRESH_SECONDS: f32 = 0.5;
+
+/// Whether the overlay is shown
+#[derive(Resource, Default)]
+pub struct PerfOverlay {
+    pub enabled: bool,
+}
+
+#[derive(Component)]
+struct PerfPanel;
+
+#[derive(Component)]
+struct PerfText;
+
+/// `bytes` in B, KB, MB or GB, with one decimal above bytes
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+pub struct PerfOverlayPlugin;
+
+impl Plugin for PerfOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PerfOverlay>()
+            .register_diagnostic(Diagnostic::new(STEP_TIME).with_suffix("ms"))
+            .add_plugins(EntityCountDiagnosticsPlugin)
+            .add_systems(Startup, spawn_perf_panel)
+            .add_systems(Update, (toggle_perf_overlay, update_perf_overlay).chain().run_if(in_state(GameState::Playing)));
+    }
+}
+
+fn spawn_perf_panel(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    let hud = &theme.hud;
+    commands
+        .spawn((
+            Node { flex_direction: FlexDirection::Column, padding: UiRect::all(Val::Px(8.0)), display: Display::None, ..default() },
+            BackgroundColor(hud.panel.0),
+            HudColor::Panel,
+            HudPanel::Perf,
+            PerfPanel,
+        ))
+        .with_children(|panel| {
+            spawn_panel_title(panel, &font, HudPanel::Perf, hud);
+            panel.spawn((Node { flex_direction: FlexDirection::Column, ..default() }, HudPanelBody(HudPanel::Perf))).with_child((
+                Text::new(""),
+                TextFont { font: font.clone(), font_size: 14.0, ..default() },
+                TextColor(hud.text.0),
+                HudColor::Text,
+                PerfText,
+            ));
+        });
+}
+
+fn toggle_perf_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
+    mut overlay: ResMut<PerfOverlay>,
+    mut panels: Query<&mut Node, With<PerfPanel>>,
+) {
+    if !keys.just_pressed(&keyboard_input, Action::PerfOverlay) {
+        return;
+    }
+    // With Shift it opens the inspector instead
+    if cfg!(feature = "inspector") && keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        return;
+    }
+    overlay.enabled = !overlay.enabled;
+    set_audio_load_metering(overlay.enabled);
+    // Drop whatever was measured before, so the first reading covers the overlay's own span
+    take_audio_load();
+    for mut node in &mut panels {
+        node.display = if overlay.enabled { Display::Flex } else { Display::None };
+    }
+    toast(tr!("📊 Performance overlay {}", if overlay.enabled { "on" } else { "off" }));
+}
+
+fn update_perf_overlay(
+    (time, mut since_refresh): (Res<Time>, Local<f32>),
+    overlay: Res<PerfOverlay>,
+    diagnostics: Res<DiagnosticsStore>,
+    (grid, layers, rewind): (Res<InfiniteGrid>, Res<GridLayers>, Res<RewindBuffer>),
+    sprites: Query<&ViewVisibility, With<CellSprite>>,
+    mut texts: Query<&mut Text, With<PerfText>>,
+) {
+    *since_refresh += time.delta_secs();
+    if !overlay.enabled || *since_refresh < REFRESH_SECONDS {
+        return;
+    }
+    *since_refresh = 0.0;
+
+    let smoothed = |path: &DiagnosticPath| diagnostics.get(path).and_then(Diagnostic::smoothed);
+    let frame = match (smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME), smoothed(&FrameTimeDiagnosticsPlugin::FPS)) {
+        (Some(ms), Some(fps)) => tr!("Frame: {:.1} ms ({:.0} FPS)", ms, fps),
+        _ => tr!("Frame: measuring"),
+    };
+    let step = match smoothed(&STEP_TIME) {
+        Some(ms) => tr!("Step: {:.2} ms", ms),
+        None => tr!("Step: not run yet"),
+    };
+    let drawn = sprites.iter().filter(|visibility| visibility.get()).count();
+    let entities = diagnostics.get(&EntityCountDiagnosticsPlugin::ENTITY_COUNT).and_then(Diagnostic::value).unwrap_or(0.0);
+    let audio = match take_audio_load() {
+        Some(load) => tr!("Audio load: {:.0}%", load * 100.0),
+        None => tr!("Audio load: silent"),
+    };
+    let layer_bytes: usize = layers.layers.iter().map(|layer| layer.grid.memory_bytes()).sum();
+    let (grid_bytes, rewind_bytes) = (grid.memory_bytes(), rewind.memory_bytes());
+    let lines = [
+        frame,
+        step,
+        tr!("Cell sprites: {} drawn of {}", drawn, sprites.iter().count()),
+        tr!("Entities: {:.0}", entities),
+        audio,
+        tr!(
+            "Memory: {} (grid {}, layers {}, rewind {})",
+            format_bytes(grid_bytes + layer_bytes + rewind_bytes),
+            format_bytes(grid_bytes),
+            format_bytes(layer_bytes),
+            format_bytes(rewind_bytes)
+        ),
+    ];
+    for mut text in &mut texts {
+        text.0 = lines.join("\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_are_shown_in_readable_units() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MB");
+        assert_eq!(format_bytes(5 * 1024 * 1024 * 1024), "5.0 GB");
+    }
+}
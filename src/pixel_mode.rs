@@ -0,0 +1,165 @@
+//! Crisp pixel rendering.
+//!
+//! With `CellRenderConfig::pixel_mode` on, the textured and animated cell sprites stand
+//! down and the view is drawn as one texture holding a texel per cell, sampled nearest-
+//! neighbour so every cell is an exact, flat square in its state (or age) colour. There is
+//! no birth animation, no texture cycling and no neighbour stretching, and a single sprite
+//! is far cheaper than thousands. When zoomed out past the point where a cell covers a few
+//! screen pixels the density heatmap takes over as usual.
+
+use bevy::color::ColorToPacked;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::window::PrimaryWindow;
+use crate::camera::GameCamera;
+use crate::cell_renderer::CellRenderConfig;
+use crate::heatmap::{view_region, BlockRegion};
+use crate::infinite_grid::InfiniteGrid;
+use crate::start_screen::{GameState, InGame};
+use crate::theme::Theme;
+use crate::CellState;
+
+/// Where the cell sprites would be
+const PIXEL_Z: f32 = 0.0;
+
+#[derive(Component)]
+pub struct PixelLayer;
+
+/// The pixel texture and what it was last built from
+#[derive(Resource, Default)]
+pub struct PixelState {
+    image: Option<Handle<Image>>,
+    region: Option<BlockRegion>,
+    grid_version: u64,
+}
+
+/// Whether the pixel texture is drawn instead of cell sprites; isometric cubes win over it
+pub fn pixel_layer_active(config: &CellRenderConfig) -> bool {
+    config.pixel_mode && !config.isometric && !config.heatmap_active
+}
+
+/// Opaque texels in each live cell's colour, row-major from the top-left like
+/// `BlockRegion::count`; dead cells are transparent. Where a block holds several cells the
+/// last one found colours it.
+pub fn cell_pixels(grid: &InfiniteGrid, region: &BlockRegion, theme: &Theme, config: &CellRenderConfig) -> Vec<u8> {
+    let mut pixels = vec![0; (region.width * region.height * 4) as usize];
+    let min_x = region.min_bx * region.block;
+    let min_y = region.min_by * region.block;
+    let max_x = min_x + region.width as i32 * region.block - 1;
+    let max_y = min_y + region.height as i32 * region.block - 1;
+    for (x, y, state) in grid.cell_states_in_region(min_x, max_x, min_y, max_y) {
+        if state == CellState::Dead {
+            continue;
+        }
+        let age = grid.meta(x, y).filter(|_| config.generation_colors && state == CellState::Alive);
+        let colour = match age {
+            Some(meta) => theme.age_color(meta.age(grid.generation()), config.age_span),
+            None => theme.cells.color(state),
+        };
+        let [r, g, b, _] = colour.to_srgba().to_u8_array();
+        let column = ((x - min_x) / region.block) as u32;
+        let row = region.height - 1 - ((y - min_y) / region.block) as u32;
+        let at = ((row * region.width + column) * 4) as usize;
+        pixels[at..at + 4].copy_from_slice(&[r, g, b, 255]);
+    }
+    pixels
+}
+
+pub struct PixelModePlugin;
+
+impl Plugin for PixelModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PixelState>()
+            .add_systems(Update, render_pixels.run_if(in_state(GameState::Playing)))
+            .add_systems(OnExit(InGame), despawn_pixels);
+    }
+}
+
+/// Keep the pixel texture covering the view, rebuilt when the grid or the view changes
+fn render_pixels(
+    mut commands: Commands,
+    (grid, config, theme): (Res<InfiniteGrid>, Res<CellRenderConfig>, Res<Theme>),
+    camera_query: Query<(&Transform, &GameCamera), Without<PixelLayer>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut layer_query: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<PixelLayer>>,
+    mut state: ResMut<PixelState>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if !pixel_layer_active(&config) {
+        for (_, _, mut visibility) in layer_query.iter_mut() {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+        state.region = None;
+        return;
+    }
+    let (Ok((camera_transform, camera)), Ok(window)) = (camera_query.get_single(), windows.get_single()) else { return };
+    let region = view_region(camera_transform, camera, window, config.cell_size);
+
+    let stale = state.region != Some(region) || state.grid_version != grid.version() || state.image.is_none();
+    if stale || theme.is_changed() || config.is_changed() {
+        let pixels = cell_pixels(&grid, &region, &theme, &config);
+        let size = Extent3d { width: region.width, height: region.height, depth_or_array_layers: 1 };
+        let existing = state.image.as_ref().and_then(|handle| images.get_mut(handle));
+        match existing {
+            Some(image) if image.texture_descriptor.size == size => image.data = pixels,
+            _ => {
+                let mut image = Image::new(size, TextureDimension::D2, pixels, TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::default());
+                image.sampler = ImageSampler::nearest();
+                state.image = Some(images.add(image));
+            }
+        }
+        state.region = Some(region);
+        state.grid_version = grid.version();
+    }
+    let Some(handle) = state.image.clone() else { return };
+    let (centre, span) = region.world_rect(config.cell_size);
+    let translation = centre.extend(PIXEL_Z);
+
+    if let Ok((mut transform, mut sprite, mut visibility)) = layer_query.get_single_mut() {
+        transform.translation = translation;
+        if sprite.image != handle || sprite.custom_size != Some(span) {
+            sprite.image = handle;
+            sprite.custom_size = Some(span);
+        }
+        visibility.set_if_neq(Visibility::Visible);
+    } else {
+        commands.spawn((
+            Sprite { image: handle, custom_size: Some(span), ..default() },
+            Transform::from_translation(translation),
+            PixelLayer,
+        ));
+    }
+}
+
+fn despawn_pixels(mut commands: Commands, layer_query: Query<Entity, With<PixelLayer>>, mut state: ResMut<PixelState>) {
+    for entity in layer_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    state.region = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_opaque_texel_per_live_cell() {
+        let mut grid = InfiniteGrid::new();
+        grid.set(0, 0, CellState::Alive);
+        grid.set(2, 1, CellState::Wire);
+        let region = BlockRegion::covering(0, 2, 0, 1, 1);
+        let theme = Theme::default();
+        let pixels = cell_pixels(&grid, &region, &theme, &CellRenderConfig::default());
+        assert_eq!(pixels.len(), 3 * 2 * 4);
+
+        // Top row holds y = 1, bottom row y = 0
+        let texel = |column: usize, row: usize| &pixels[(row * 3 + column) * 4..(row * 3 + column) * 4 + 4];
+        let [r, g, b, _] = theme.cells.color(CellState::Wire).to_srgba().to_u8_array();
+        assert_eq!(texel(2, 0), &[r, g, b, 255]);
+        assert_eq!(texel(0, 1)[3], 255);
+        assert_eq!(texel(1, 1)[3], 0);
+        assert_eq!(texel(0, 0)[3], 0);
+    }
+}
@@ -0,0 +1,264 @@
+//! Population graph.
+//!
+//! A HUD panel above the minimap plotting the population over the generations kept by
+//! `PopulationHistory`, scaled to the peak in view, with the current and peak counts
+//! written across the top. In `GraphMode::Full` the births and deaths of each step are
+//! drawn too, on their own scale since they are usually a small fraction of the
+//! population. F2 cycles off → population → full with the default bindings; the panel
+//! hides along with the HUD.
+
+use bevy::color::ColorToPacked;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use serde::{Deserialize, Serialize};
+use crate::infinite_grid::InfiniteGrid;
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::{GameState, InGame};
+use crate::stats::{PopulationHistory, Sample};
+use crate::theme::Theme;
+use crate::tools::line_cells;
+use crate::ui::{toast, HudColor, UiState};
+use crate::tr;
+use crate::CellState;
+
+/// Plot size in texels, drawn one texel per logical pixel
+const GRAPH_WIDTH: u32 = 240;
+const GRAPH_HEIGHT: u32 = 72;
+
+/// Room left for the counts written across the top of the panel
+const LABEL_HEIGHT: f32 = 18.0;
+
+/// What the graph shows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphMode {
+    #[default]
+    Off,
+    Population,
+    /// Population plus births and deaths
+    Full,
+}
+
+impl GraphMode {
+    pub fn next(self) -> Self {
+        match self {
+            GraphMode::Off => GraphMode::Population,
+            GraphMode::Population => GraphMode::Full,
+            GraphMode::Full => GraphMode::Off,
+        }
+    }
+}
+
+/// Line colours of the population, births and deaths
+pub struct GraphColors {
+    pub population: Color,
+    pub births: Color,
+    pub deaths: Color,
+}
+
+impl GraphColors {
+    pub fn from_theme(theme: &Theme) -> Self {
+        Self { population: theme.cells.color(CellState::Alive), births: theme.hud.zoom.0, deaths: theme.hud.volume.0 }
+    }
+}
+
+/// RGBA texels of the graph, transparent where nothing is drawn. The newest sample is at the
+/// right edge and a full history spans the width; a shorter one fills in from the right.
+pub fn graph_pixels(samples: &[Sample], capacity: usize, mode: GraphMode, colors: &GraphColors) -> Vec<u8> {
+    let (width, height) = (GRAPH_WIDTH as i32, GRAPH_HEIGHT as i32);
+    let mut pixels = vec![0; (GRAPH_WIDTH * GRAPH_HEIGHT * 4) as usize];
+    let mut plot = |values: &[usize], peak: usize, color: Color| {
+        let [r, g, b, _] = color.to_srgba().to_u8_array();
+        let span = capacity.max(2) as f32 - 1.0;
+        let point = |index: usize, value: usize| {
+            let age = (values.len() - 1 - index) as f32;
+            let x = (width - 1) - (age / span * (width - 1) as f32).round() as i32;
+            let y = (height - 1) - (value as f32 / peak.max(1) as f32 * (height - 1) as f32).round() as i32;
+            (x, y)
+        };
+        let points: Vec<_> = values.iter().enumerate().map(|(index, &value)| point(index, value)).collect();
+        let segments = points.windows(2).map(|pair| (pair[0], pair[1])).chain(points.first().map(|&p| (p, p)));
+        for (from, to) in segments {
+            for (x, y) in line_cells(from, to) {
+                if (0..width).contains(&x) && (0..height).contains(&y) {
+                    let at = ((y * width + x) * 4) as usize;
+                    pixels[at..at + 4].copy_from_slice(&[r, g, b, 255]);
+                }
+            }
+        }
+    };
+
+    if mode == GraphMode::Full {
+        let births: Vec<usize> = samples.iter().map(|sample| sample.births).collect();
+        let deaths: Vec<usize> = samples.iter().map(|sample| sample.deaths).collect();
+        let peak = births.iter().chain(&deaths).copied().max().unwrap_or(0);
+        plot(&deaths, peak, colors.deaths);
+        plot(&births, peak, colors.births);
+    }
+    let population: Vec<usize> = samples.iter().map(|sample| sample.population).collect();
+    let peak = population.iter().copied().max().unwrap_or(0);
+    plot(&population, peak, colors.population);
+    pixels
+}
+
+#[derive(Resource, Default)]
+pub struct PopulationGraph {
+    pub mode: GraphMode,
+    image: Option<Handle<Image>>,
+    /// Mode the texture was last drawn in
+    drawn: Option<GraphMode>,
+}
+
+impl PopulationGraph {
+    pub fn new(mode: GraphMode) -> Self {
+        Self { mode, ..default() }
+    }
+}
+
+#[derive(Component)]
+pub struct GraphPanel;
+
+/// The counts across the top of the panel
+#[derive(Component)]
+pub struct GraphLabel;
+
+pub struct PopulationGraphPlugin;
+
+impl Plugin for PopulationGraphPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PopulationGraph>()
+            .init_resource::<PopulationHistory>()
+            .add_systems(OnEnter(InGame), spawn_graph)
+            .add_systems(OnExit(InGame), despawn_graph)
+            .add_systems(
+                Update,
+                (graph_controls, record_history, update_graph).chain().run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+fn spawn_graph(mut commands: Commands, theme: Res<Theme>, asset_server: Res<AssetServer>, mut history: ResMut<PopulationHistory>) {
+    history.clear();
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                // Above the minimap
+                bottom: Val::Px(180.0),
+                width: Val::Px(GRAPH_WIDTH as f32),
+                height: Val::Px(GRAPH_HEIGHT as f32 + LABEL_HEIGHT),
+                padding: UiRect::top(Val::Px(LABEL_HEIGHT)),
+                ..default()
+            },
+            BackgroundColor(theme.hud.panel.0),
+            HudColor::Panel,
+            Visibility::Hidden,
+            GraphPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                TextFont { font: asset_server.load("fonts/Geo-Regular.ttf"), font_size: 13.0, ..default() },
+                TextColor(theme.hud.text.0),
+                HudColor::Text,
+                Node { position_type: PositionType::Absolute, left: Val::Px(4.0), top: Val::Px(1.0), ..default() },
+                GraphLabel,
+            ));
+            parent.spawn((ImageNode::default(), Node { width: Val::Percent(100.0), height: Val::Percent(100.0), ..default() }));
+        });
+}
+
+fn despawn_graph(mut commands: Commands, panels: Query<Entity, With<GraphPanel>>, mut graph: ResMut<PopulationGraph>) {
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+    graph.image = None;
+    graph.drawn = None;
+}
+
+fn graph_controls(keyboard_input: Res<ButtonInput<KeyCode>>, keys: Res<Keybindings>, mut graph: ResMut<PopulationGraph>) {
+    if keys.just_pressed(&keyboard_input, Action::PopulationGraph) {
+        graph.mode = graph.mode.next();
+        toast(tr!("📈 Population graph: {}", format!("{:?}", graph.mode)));
+    }
+}
+
+/// Sampled whether or not the graph is shown, so it has a history as soon as it opens
+fn record_history(grid: Res<InfiniteGrid>, mut history: ResMut<PopulationHistory>) {
+    let latest = history.latest().map(|sample| (sample.generation, sample.population));
+    if latest != Some((grid.generation(), grid.population())) {
+        history.record(&grid);
+    }
+}
+
+fn update_graph(
+    (history, theme, ui_state): (Res<PopulationHistory>, Res<Theme>, Res<UiState>),
+    mut graph: ResMut<PopulationGraph>,
+    mut panels: Query<(&mut Visibility, &Children), With<GraphPanel>>,
+    mut image_nodes: Query<&mut ImageNode>,
+    mut labels: Query<&mut Text, With<GraphLabel>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Ok((mut visibility, children)) = panels.get_single_mut() else { return };
+    let shown = graph.mode != GraphMode::Off && ui_state.hud_visible;
+    visibility.set_if_neq(if shown { Visibility::Visible } else { Visibility::Hidden });
+    let Some(latest) = history.latest().copied() else { return };
+    if !shown || (graph.drawn == Some(graph.mode) && !history.is_changed() && !theme.is_changed()) {
+        return;
+    }
+
+    let samples: Vec<Sample> = history.samples().copied().collect();
+    let pixels = graph_pixels(&samples, history.capacity, graph.mode, &GraphColors::from_theme(&theme));
+    match graph.image.as_ref().and_then(|handle| images.get_mut(handle)) {
+        Some(image) => image.data = pixels,
+        None => {
+            let size = Extent3d { width: GRAPH_WIDTH, height: GRAPH_HEIGHT, depth_or_array_layers: 1 };
+            let mut image = Image::new(size, TextureDimension::D2, pixels, TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::default());
+            image.sampler = ImageSampler::nearest();
+            graph.image = Some(images.add(image));
+        }
+    }
+    graph.drawn = Some(graph.mode);
+    if let Some(handle) = graph.image.clone() {
+        let mut nodes = image_nodes.iter_many_mut(children);
+        while let Some(mut node) = nodes.fetch_next() {
+            if node.image != handle {
+                node.image = handle.clone();
+            }
+        }
+    }
+
+    let mut text = format!("Population {}  peak {}", latest.population, history.peak_population());
+    if graph.mode == GraphMode::Full {
+        text.push_str(&format!("  +{} −{}", latest.births, latest.deaths));
+    }
+    for mut label in labels.iter_mut() {
+        if label.0 != text {
+            label.0 = text.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newest_sample_at_the_right_edge_scaled_to_the_peak() {
+        let colors = GraphColors { population: Color::WHITE, births: Color::BLACK, deaths: Color::BLACK };
+        let sample = |generation, population| Sample { generation, population, births: 0, deaths: 0 };
+        let pixels = graph_pixels(&[sample(0, 5), sample(1, 10)], 100, GraphMode::Population, &colors);
+        let alpha = |x: u32, y: u32| pixels[((y * GRAPH_WIDTH + x) * 4 + 3) as usize];
+
+        // The peak touches the top at the right edge; the older, lower sample sits left of it
+        assert_eq!(alpha(GRAPH_WIDTH - 1, 0), 255);
+        assert_eq!(alpha(0, GRAPH_HEIGHT - 1), 0);
+        let column = GRAPH_WIDTH - 1 - ((GRAPH_WIDTH - 1) as f32 / 99.0).round() as u32;
+        let row = GRAPH_HEIGHT - 1 - ((GRAPH_HEIGHT - 1) as f32 / 2.0).round() as u32;
+        assert_eq!(alpha(column, row), 255);
+        assert_eq!(GraphMode::Full.next(), GraphMode::Off);
+    }
+}
@@ -0,0 +1,117 @@
+//! Window and session preferences.
+//!
+//! Keeps the loaded `Config` up to date with the window geometry, the rule being played, the
+//! camera zoom, brush size, HUD visibility and layout and volume as they change (and the audio engine,
+//! theme and render options chosen on the settings screen), and writes it back with
+//! `Config::save` when the app exits so the next launch picks up where this one left off.
+//! Restoring happens in `main`, which builds the window and resources from the config, except
+//! for the camera zoom, which is applied here once the camera exists.
+
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowMoved, WindowResized};
+use crate::camera::{CameraSettings, GameCamera};
+use crate::cell_renderer::CellRenderConfig;
+use crate::config::Config;
+use crate::tools::BrushSettings;
+use crate::ui::{HudLayout, UiState};
+use crate::{GameConfig, GameState};
+
+/// The config as it will be saved on exit
+#[derive(Resource)]
+pub struct Preferences {
+    pub config: Config,
+    /// File to save to (`None` = the default config path)
+    pub path: Option<String>,
+}
+
+impl Preferences {
+    pub fn new(config: Config, path: Option<String>) -> Self {
+        Self { config, path }
+    }
+}
+
+pub struct PreferencesPlugin;
+
+impl Plugin for PreferencesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostStartup, restore_camera_zoom)
+            .add_systems(Update, (track_window, track_session.run_if(in_state(GameState::Playing))))
+            .add_systems(Last, save_on_exit);
+    }
+}
+
+fn restore_camera_zoom(preferences: Res<Preferences>, mut camera_query: Query<&mut GameCamera>) {
+    for mut camera in camera_query.iter_mut() {
+        let zoom = preferences.config.camera_zoom.clamp(camera.min_zoom, camera.max_zoom);
+        camera.zoom = zoom;
+        camera.target_zoom = zoom;
+    }
+}
+
+fn track_window(
+    mut resized: EventReader<WindowResized>,
+    mut moved: EventReader<WindowMoved>,
+    primary: Query<Entity, With<PrimaryWindow>>,
+    mut preferences: ResMut<Preferences>,
+) {
+    let Ok(primary) = primary.get_single() else {
+        resized.clear();
+        moved.clear();
+        return;
+    };
+    for event in resized.read().filter(|e| e.window == primary) {
+        preferences.config.window_width = event.width;
+        preferences.config.window_height = event.height;
+    }
+    for event in moved.read().filter(|e| e.window == primary) {
+        preferences.config.window_position = Some(event.position.to_array());
+    }
+}
+
+fn track_session(
+    game_config: Res<GameConfig>,
+    camera_query: Query<&GameCamera>,
+    brush: Res<BrushSettings>,
+    (ui_state, hud_layout): (Res<UiState>, Res<HudLayout>),
+    mut preferences: ResMut<Preferences>,
+) {
+    let config = &mut preferences.config;
+    let rule = game_config.current_rule.rulestring();
+    if config.last_rule.as_ref() != Some(&rule) {
+        config.last_rule = Some(rule);
+    }
+    if let Ok(camera) = camera_query.get_single() {
+        config.camera_zoom = camera.target_zoom;
+    }
+    config.brush_size = brush.size;
+    config.hud_visible = ui_state.hud_visible;
+    config.hud_layout = *hud_layout;
+    config.audio_volume = game_config.audio_volume;
+    config.audio_muted = game_config.audio_muted;
+}
+
+/// Copy the render and camera options that have a config entry into `config`, so changes
+/// made in game (the settings screen, F3) are kept
+pub fn store_render_settings(config: &mut Config, render: &CellRenderConfig, camera: &CameraSettings) {
+    config.pixel_mode = render.pixel_mode;
+    config.isometric = render.isometric;
+    config.reduced_motion = render.reduced_motion;
+    config.heatmap_zoom = if render.lod_enabled { render.lod_zoom } else { 0.0 };
+    config.age_colors = render.generation_colors;
+    config.bloom_intensity = render.bloom_intensity;
+    config.color_vision = render.color_vision;
+    config.min_zoom = camera.min_zoom;
+    config.max_zoom = camera.max_zoom;
+    config.zoom_speed = camera.zoom_speed;
+    config.pan_speed = camera.pan_speed;
+}
+
+fn save_on_exit(mut exits: EventReader<AppExit>, preferences: Res<Preferences>) {
+    if exits.read().next().is_none() {
+        return;
+    }
+    match preferences.config.save(preferences.path.as_deref()) {
+        Ok(()) => println!("💾 Saved preferences"),
+        Err(e) => println!("💾 Could not save preferences: {}", e),
+    }
+}
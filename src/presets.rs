@@ -0,0 +1,193 @@
+//! Per-rule visual presets.
+//!
+//! Each `RuleType` can declare a `VisualPreset` that is laid over the configured look when a
+//! game starts: WireWorld is drawn as a circuit board with copper wires and glowing
+//! electrons, Seeds as fireworks bursting out of a night sky with short trails. Presets
+//! start from `PresetBaseline`, the settings from the config file, so switching back to a
+//! rule without a preset restores them. A colour vision palette still takes precedence over
+//! a preset's cell colours. Set `rule_presets = false` to keep one look for every rule.
+
+use bevy::prelude::*;
+use crate::cell_renderer::CellRenderConfig;
+use crate::start_screen::RuleType;
+use crate::theme::{ColorVision, Theme, ThemeColor};
+use crate::trails::DeathTrails;
+use crate::CellState;
+
+/// How a rule looks out of the box; `None` fields leave the configured value alone
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VisualPreset {
+    pub background: Option<Color>,
+    /// Replacement colours for these cell states
+    pub cells: Vec<(CellState, Color)>,
+    /// Newborn flash and bloom tint
+    pub glow: Option<Color>,
+    pub trail: Option<Color>,
+    pub grid_lines: Option<Color>,
+    /// Multiplies the configured animation speed
+    pub animation_speed: f32,
+    /// Bloom intensity to use at least
+    pub bloom: Option<f32>,
+    /// Death trails switched on with this length
+    pub trails: Option<u32>,
+}
+
+impl VisualPreset {
+    /// The configured look, unchanged
+    pub fn none() -> Self {
+        Self { animation_speed: 1.0, ..default() }
+    }
+
+    /// Lay the preset's colours over `theme`
+    pub fn apply_colors(&self, theme: &mut Theme) {
+        let set = |slot: &mut ThemeColor, colour: Option<Color>| {
+            if let Some(colour) = colour {
+                *slot = ThemeColor(colour);
+            }
+        };
+        set(&mut theme.background, self.background);
+        set(&mut theme.glow, self.glow);
+        set(&mut theme.trail, self.trail);
+        set(&mut theme.grid_lines, self.grid_lines);
+        for &(state, colour) in &self.cells {
+            let slot = match state {
+                CellState::Alive => &mut theme.cells.alive,
+                CellState::Dying => &mut theme.cells.dying,
+                CellState::Wire => &mut theme.cells.wire,
+                CellState::ElectronHead => &mut theme.cells.electron_head,
+                CellState::ElectronTail => &mut theme.cells.electron_tail,
+                CellState::SpeciesA => &mut theme.cells.species_a,
+                CellState::SpeciesB => &mut theme.cells.species_b,
+                CellState::Dead => continue,
+            };
+            *slot = ThemeColor(colour);
+        }
+        // Live cells of a recoloured rule start their age gradient from the new colour
+        if let Some(&(_, alive)) = self.cells.iter().find(|(state, _)| *state == CellState::Alive) {
+            if let Some(first) = theme.age_gradient.first_mut() {
+                *first = ThemeColor(alive);
+            }
+        }
+    }
+}
+
+impl RuleType {
+    pub fn visual_preset(&self) -> VisualPreset {
+        let hex = |rgb: u32| Color::srgb_u8((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+        match self {
+            // A circuit board: copper traces on green fibreglass, electrons that glow
+            RuleType::WireWorld => VisualPreset {
+                background: Some(hex(0x0b1a12)),
+                cells: vec![
+                    (CellState::Wire, hex(0xc8873a)),
+                    (CellState::ElectronHead, hex(0x7df9ff)),
+                    (CellState::ElectronTail, hex(0x2f6fd0)),
+                ],
+                glow: Some(hex(0xb8fcff)),
+                grid_lines: Some(Color::srgba(0.3, 0.8, 0.5, 0.1)),
+                animation_speed: 0.5,
+                bloom: Some(0.25),
+                ..VisualPreset::none()
+            },
+            // Fireworks: golden sparks against the night that leave short embers behind
+            RuleType::Seeds => VisualPreset {
+                background: Some(hex(0x05030a)),
+                cells: vec![(CellState::Alive, hex(0xffcf40))],
+                glow: Some(Color::WHITE),
+                trail: Some(Color::srgba(1.0, 0.45, 0.1, 0.6)),
+                animation_speed: 2.0,
+                bloom: Some(0.4),
+                trails: Some(8),
+                ..VisualPreset::none()
+            },
+            // Firing neurons with a cooling afterglow
+            RuleType::Brian => VisualPreset {
+                background: Some(hex(0x04060f)),
+                cells: vec![(CellState::Alive, hex(0x9fe8ff)), (CellState::Dying, hex(0x3b4fd8))],
+                glow: Some(hex(0xe0f8ff)),
+                bloom: Some(0.2),
+                ..VisualPreset::none()
+            },
+            _ => VisualPreset::none(),
+        }
+    }
+}
+
+/// Settings presets are applied on top of, as read from the config file
+#[derive(Resource, Clone, Debug)]
+pub struct PresetBaseline {
+    pub enabled: bool,
+    pub animation_speed: f32,
+    pub bloom_intensity: f32,
+    pub trails: bool,
+    pub trail_length: u32,
+}
+
+/// The preset of the rule being played, kept so a theme reloaded mid-game (a theme switch,
+/// a new colour vision palette) gets the preset's colours again
+#[derive(Resource, Clone, Debug)]
+pub struct ActivePreset(pub VisualPreset);
+
+impl Default for ActivePreset {
+    fn default() -> Self {
+        Self(VisualPreset::none())
+    }
+}
+
+impl ActivePreset {
+    /// Theme `name` with the preset's colours, then the `vision` palette, laid over it
+    pub fn theme(&self, name: &str, vision: ColorVision) -> Theme {
+        let mut theme = Theme::named(name);
+        self.0.apply_colors(&mut theme);
+        let mut theme = theme.with_color_vision(vision);
+        theme.name = name.to_string();
+        theme
+    }
+}
+
+/// Restore the baseline look and lay `rule`'s preset over it
+pub fn apply_visual_preset(
+    rule: RuleType,
+    baseline: &PresetBaseline,
+    active: &mut ActivePreset,
+    theme: &mut Theme,
+    render_config: &mut CellRenderConfig,
+    trails: &mut DeathTrails,
+) {
+    if !baseline.enabled {
+        return;
+    }
+    let preset = rule.visual_preset();
+    active.0 = preset.clone();
+    *theme = active.theme(&theme.name, render_config.color_vision);
+
+    render_config.animation_speed = baseline.animation_speed * preset.animation_speed;
+    render_config.bloom_intensity = baseline.bloom_intensity.max(preset.bloom.unwrap_or(0.0));
+    trails.enabled = baseline.trails || preset.trails.is_some();
+    trails.length = preset.trails.unwrap_or(baseline.trail_length);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_recolour_their_rule_only() {
+        assert_eq!(RuleType::Conway.visual_preset(), VisualPreset::none());
+
+        let mut theme = Theme::default();
+        RuleType::Conway.visual_preset().apply_colors(&mut theme);
+        assert_eq!(theme, Theme::default());
+
+        RuleType::WireWorld.visual_preset().apply_colors(&mut theme);
+        assert_ne!(theme.cells.wire, Theme::default().cells.wire);
+        assert_ne!(theme.background, Theme::default().background);
+        assert_eq!(theme.cells.alive, Theme::default().cells.alive);
+
+        let seeds = RuleType::Seeds.visual_preset();
+        let mut theme = Theme::default();
+        seeds.apply_colors(&mut theme);
+        assert_eq!(theme.age_gradient[0], theme.cells.alive);
+        assert!(seeds.trails.is_some() && seeds.bloom.is_some());
+    }
+}
@@ -0,0 +1,429 @@
+//! In-game render settings.
+//!
+//! F3 (by default) opens a panel listing the `CellRenderConfig` values that used to need a
+//! code change: pixel and isometric modes, cell size and scaling, animation and texture rates, reduced motion, the zoomed-out heatmap,
+//! age colours, bloom and the colour vision palette, followed by the camera's zoom limits and
+//! speeds (`CameraSettings`). Each row has − and + buttons, and the mouse wheel over a row
+//! nudges it like the synth panel's knobs. Changes apply on the spot.
+
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::prelude::*;
+use bevy::ui::{RelativeCursorPosition, UiSystem};
+use crate::camera::{CameraSettings, CameraState, GameCamera};
+use crate::cell_renderer::{CellRenderConfig, CellSprite};
+use crate::keybindings::{Action, Keybindings};
+use crate::layers::LayerSprite;
+use crate::presets::ActivePreset;
+use crate::start_screen::{GameState, InGame};
+use crate::theme::{ColorVision, Theme};
+use crate::tr;
+use crate::trails::TrailGhost;
+use crate::ui::PointerCapture;
+
+/// A row of the panel
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderSetting {
+    PixelMode,
+    Isometric,
+    CellSize,
+    BaseScale,
+    OverlayScale,
+    AnimationSpeed,
+    ReducedMotion,
+    TextureFps,
+    Heatmap,
+    HeatmapZoom,
+    AgeColors,
+    Bloom,
+    ColorVision,
+    MinZoom,
+    MaxZoom,
+    ZoomSpeed,
+    PanSpeed,
+}
+
+impl RenderSetting {
+    pub const ALL: [RenderSetting; 17] = [
+        RenderSetting::PixelMode, RenderSetting::Isometric, RenderSetting::CellSize,
+        RenderSetting::BaseScale, RenderSetting::OverlayScale, RenderSetting::AnimationSpeed,
+        RenderSetting::ReducedMotion, RenderSetting::TextureFps, RenderSetting::Heatmap,
+        RenderSetting::HeatmapZoom, RenderSetting::AgeColors, RenderSetting::Bloom,
+        RenderSetting::ColorVision, RenderSetting::MinZoom, RenderSetting::MaxZoom,
+        RenderSetting::ZoomSpeed, RenderSetting::PanSpeed,
+    ];
+
+    /// Whether the row sets `CameraSettings` rather than `CellRenderConfig`
+    pub fn is_camera(self) -> bool {
+        matches!(self, RenderSetting::MinZoom | RenderSetting::MaxZoom | RenderSetting::ZoomSpeed | RenderSetting::PanSpeed)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RenderSetting::PixelMode => "Pixel mode",
+            RenderSetting::Isometric => "Isometric cubes",
+            RenderSetting::CellSize => "Cell size",
+            RenderSetting::BaseScale => "Cell scale",
+            RenderSetting::OverlayScale => "Neighbour stretch",
+            RenderSetting::AnimationSpeed => "Animation speed",
+            RenderSetting::ReducedMotion => "Reduced motion",
+            RenderSetting::TextureFps => "Texture FPS",
+            RenderSetting::Heatmap => "Zoomed-out heatmap",
+            RenderSetting::HeatmapZoom => "Heatmap below zoom",
+            RenderSetting::AgeColors => "Age colours",
+            RenderSetting::Bloom => "Bloom",
+            RenderSetting::ColorVision => "Colour vision",
+            RenderSetting::MinZoom => "Zoom out to",
+            RenderSetting::MaxZoom => "Zoom in to",
+            RenderSetting::ZoomSpeed => "Zoom speed",
+            RenderSetting::PanSpeed => "Pan speed",
+        }
+    }
+
+    /// Current value as shown in the panel
+    pub fn value(self, config: &CellRenderConfig, camera: &CameraSettings) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" }.to_string();
+        match self {
+            RenderSetting::PixelMode => on_off(config.pixel_mode),
+            RenderSetting::Isometric => on_off(config.isometric),
+            RenderSetting::CellSize => format!("{:.0}", config.cell_size),
+            RenderSetting::BaseScale => format!("{:.2}", config.base_scale),
+            RenderSetting::OverlayScale => format!("{:.2}", config.overlay_scale),
+            RenderSetting::AnimationSpeed => format!("{:.2}×", config.animation_speed),
+            RenderSetting::ReducedMotion => on_off(config.reduced_motion),
+            RenderSetting::TextureFps => format!("{:.0}", config.texture_fps),
+            RenderSetting::Heatmap => on_off(config.lod_enabled),
+            RenderSetting::HeatmapZoom => format!("{:.2}", config.lod_zoom),
+            RenderSetting::AgeColors => on_off(config.generation_colors),
+            RenderSetting::Bloom => if config.bloom_intensity > 0.0 { format!("{:.2}", config.bloom_intensity) } else { "Off".to_string() },
+            RenderSetting::ColorVision => config.color_vision.name().to_string(),
+            RenderSetting::MinZoom => format!("{:.3}", camera.min_zoom),
+            RenderSetting::MaxZoom => format!("{:.1}", camera.max_zoom),
+            RenderSetting::ZoomSpeed => format!("{:.2}×", camera.zoom_speed),
+            RenderSetting::PanSpeed => format!("{:.0}", camera.pan_speed),
+        }
+    }
+
+    /// Move the setting `steps` notches up (or down, if negative), within its range.
+    /// Switches flip on any step; the colour vision palette cycles. Camera rows are left to
+    /// `adjust_camera`.
+    pub fn adjust(self, config: &mut CellRenderConfig, steps: i32) {
+        let nudge = |value: &mut f32, step: f32, min: f32, max: f32| nudge_value(value, steps, step, min, max);
+        match self {
+            RenderSetting::PixelMode => config.pixel_mode = !config.pixel_mode,
+            RenderSetting::Isometric => config.isometric = !config.isometric,
+            RenderSetting::CellSize => nudge(&mut config.cell_size, 2.0, 8.0, 64.0),
+            RenderSetting::BaseScale => nudge(&mut config.base_scale, 0.05, 0.2, 1.0),
+            RenderSetting::OverlayScale => nudge(&mut config.overlay_scale, 0.02, 0.0, 0.5),
+            RenderSetting::AnimationSpeed => nudge(&mut config.animation_speed, 0.25, 0.0, 5.0),
+            RenderSetting::ReducedMotion => config.reduced_motion = !config.reduced_motion,
+            RenderSetting::TextureFps => nudge(&mut config.texture_fps, 2.0, 2.0, 60.0),
+            RenderSetting::Heatmap => config.lod_enabled = !config.lod_enabled,
+            RenderSetting::HeatmapZoom => nudge(&mut config.lod_zoom, 0.05, 0.05, 1.0),
+            RenderSetting::AgeColors => config.generation_colors = !config.generation_colors,
+            RenderSetting::Bloom => nudge(&mut config.bloom_intensity, 0.05, 0.0, 1.0),
+            RenderSetting::ColorVision => {
+                let count = ColorVision::ALL.len() as i32;
+                let index = ColorVision::ALL.iter().position(|&vision| vision == config.color_vision).unwrap_or(0) as i32;
+                config.color_vision = ColorVision::ALL[(index + steps).rem_euclid(count) as usize];
+            }
+            RenderSetting::MinZoom | RenderSetting::MaxZoom | RenderSetting::ZoomSpeed | RenderSetting::PanSpeed => {}
+        }
+    }
+
+    /// `adjust` for the camera rows; the zoom limits double or halve each notch
+    pub fn adjust_camera(self, camera: &mut CameraSettings, steps: i32) {
+        let factor = 2f32.powi(steps);
+        match self {
+            RenderSetting::MinZoom => camera.min_zoom = (camera.min_zoom * factor).clamp(0.001, 1.0),
+            RenderSetting::MaxZoom => camera.max_zoom = (camera.max_zoom * factor).clamp(1.0, 100.0),
+            RenderSetting::ZoomSpeed => nudge_value(&mut camera.zoom_speed, steps, 0.25, 0.25, 5.0),
+            RenderSetting::PanSpeed => nudge_value(&mut camera.pan_speed, steps, 100.0, 100.0, 5000.0),
+            _ => {}
+        }
+    }
+}
+
+/// Round `value` to a multiple of `step` and move it `steps` of them, within `min..=max`
+fn nudge_value(value: &mut f32, steps: i32, step: f32, min: f32, max: f32) {
+    *value = ((*value / step).round() * step + steps as f32 * step).clamp(min, max);
+}
+
+/// Step a row, touching only the resource it belongs to so the other doesn't register a change
+pub fn step_setting(setting: RenderSetting, steps: i32, config: &mut ResMut<CellRenderConfig>, camera: &mut ResMut<CameraSettings>) {
+    if setting.is_camera() {
+        setting.adjust_camera(camera, steps);
+    } else {
+        setting.adjust(config, steps);
+    }
+}
+
+#[derive(Component)]
+struct RenderPanel;
+
+/// − or + button of a row
+#[derive(Component, Clone, Copy)]
+struct SettingButton {
+    setting: RenderSetting,
+    steps: i32,
+}
+
+/// Value text of a row
+#[derive(Component)]
+struct SettingValue(RenderSetting);
+
+pub struct RenderPanelPlugin;
+
+impl Plugin for RenderPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreUpdate, render_panel_capture.after(UiSystem::Focus).run_if(in_state(GameState::Playing)))
+            .add_systems(
+                Update,
+                (
+                    toggle_render_panel,
+                    (render_panel_buttons, scroll_render_panel),
+                    (refresh_setting_values, apply_cell_size, apply_color_vision),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(InGame), close_render_panel);
+    }
+}
+
+fn toggle_render_panel(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
+    asset_server: Res<AssetServer>,
+    (config, camera): (Res<CellRenderConfig>, Res<CameraSettings>),
+    panels: Query<Entity, With<RenderPanel>>,
+) {
+    if !keys.just_pressed(&keyboard_input, Action::RenderPanel) {
+        return;
+    }
+    if !panels.is_empty() {
+        for entity in &panels {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    spawn_panel(&mut commands, &asset_server.load("fonts/Geo-Regular.ttf"), (&config, &camera), &keys);
+}
+
+fn spawn_panel(commands: &mut Commands, font: &Handle<Font>, (config, camera): (&CellRenderConfig, &CameraSettings), keys: &Keybindings) {
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                top: Val::Px(60.0),
+                width: Val::Px(300.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.92)),
+            GlobalZIndex(10),
+            RelativeCursorPosition::default(),
+            RenderPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new(tr!("Render Settings")), text(20.0), TextColor(Color::srgb(1.0, 0.9, 0.3))));
+            parent.spawn((
+                Text::new(tr!("Click −/+ or scroll over a row, {} to close", keys.label(Action::RenderPanel))),
+                text(13.0),
+                TextColor(Color::srgb(0.6, 0.6, 0.6)),
+                Node { margin: UiRect::bottom(Val::Px(6.0)), ..default() },
+            ));
+            for setting in RenderSetting::ALL {
+                parent
+                    .spawn((
+                        Node {
+                            width: Val::Percent(100.0),
+                            height: Val::Px(28.0),
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(6.0),
+                            margin: UiRect::bottom(Val::Px(2.0)),
+                            padding: UiRect::horizontal(Val::Px(6.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgba(0.15, 0.15, 0.2, 0.9)),
+                        Interaction::default(),
+                        setting,
+                    ))
+                    .with_children(|row| {
+                        row.spawn((Text::new(setting.label()), text(15.0), TextColor(Color::WHITE), Node { flex_grow: 1.0, ..default() }));
+                        spawn_button(row, text(16.0), "−", SettingButton { setting, steps: -1 });
+                        row.spawn((
+                            Text::new(setting.value(config, camera)),
+                            text(14.0),
+                            TextColor(Color::srgb(0.9, 0.9, 0.4)),
+                            TextLayout::new_with_justify(JustifyText::Center),
+                            Node { width: Val::Px(84.0), ..default() },
+                            SettingValue(setting),
+                        ));
+                        spawn_button(row, text(16.0), "+", SettingButton { setting, steps: 1 });
+                    });
+            }
+        });
+}
+
+fn spawn_button(row: &mut ChildBuilder, font: TextFont, label: &str, button: SettingButton) {
+    row.spawn((
+        Button,
+        Node {
+            width: Val::Px(22.0),
+            height: Val::Px(22.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.25, 0.25, 0.3, 0.95)),
+        button,
+    ))
+    .with_child((Text::new(label), font, TextColor(Color::WHITE)));
+}
+
+/// Keep clicks and the wheel on the panel from reaching the grid tools and the camera
+fn render_panel_capture(panels: Query<&RelativeCursorPosition, With<RenderPanel>>, mut capture: ResMut<PointerCapture>) {
+    capture.captured |= panels.iter().any(RelativeCursorPosition::mouse_over);
+}
+
+fn render_panel_buttons(
+    mut buttons: Query<(&Interaction, &SettingButton, &mut BackgroundColor), Changed<Interaction>>,
+    (mut config, mut camera): (ResMut<CellRenderConfig>, ResMut<CameraSettings>),
+) {
+    for (interaction, button, mut colour) in &mut buttons {
+        match interaction {
+            Interaction::Pressed => {
+                step_setting(button.setting, button.steps, &mut config, &mut camera);
+                *colour = BackgroundColor(Color::srgba(0.45, 0.4, 0.2, 0.95));
+            }
+            Interaction::Hovered => *colour = BackgroundColor(Color::srgba(0.35, 0.35, 0.45, 0.95)),
+            Interaction::None => *colour = BackgroundColor(Color::srgba(0.25, 0.25, 0.3, 0.95)),
+        }
+    }
+}
+
+fn scroll_render_panel(
+    mut wheel_events: EventReader<MouseWheel>,
+    rows: Query<(&Interaction, &RenderSetting)>,
+    (mut config, mut camera): (ResMut<CellRenderConfig>, ResMut<CameraSettings>),
+) {
+    let Some(setting) = rows.iter().find(|(interaction, _)| **interaction != Interaction::None).map(|(_, &setting)| setting) else {
+        wheel_events.clear();
+        return;
+    };
+    for event in wheel_events.read() {
+        let notches = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 40.0,
+        };
+        if notches.abs() >= 0.5 {
+            step_setting(setting, notches.round() as i32, &mut config, &mut camera);
+        }
+    }
+}
+
+fn refresh_setting_values(
+    (config, camera): (Res<CellRenderConfig>, Res<CameraSettings>),
+    mut values: Query<(&SettingValue, &mut Text)>,
+) {
+    if !config.is_changed() && !camera.is_changed() {
+        return;
+    }
+    for (value, mut text) in &mut values {
+        let shown = value.0.value(&config, &camera);
+        if text.0 != shown {
+            text.0 = shown;
+        }
+    }
+}
+
+/// Sprites drawn at a grid position: cells, death-trail ghosts and extra-layer cells
+type GridSprites<'w, 's> = Query<'w, 's, (&'static mut Transform, AnyOf<(&'static CellSprite, &'static TrailGhost, &'static LayerSprite)>), Without<GameCamera>>;
+
+/// Follow a new cell size: the camera and every per-cell sprite move so the same cells stay
+/// in view at their new spacing
+fn apply_cell_size(
+    config: Res<CellRenderConfig>,
+    mut camera_state: ResMut<CameraState>,
+    mut camera_query: Query<&mut Transform, With<GameCamera>>,
+    mut sprites: GridSprites,
+) {
+    let (old, new) = (camera_state.cell_size, config.cell_size);
+    if old == new {
+        return;
+    }
+    camera_state.cell_size = new;
+    if let Ok(mut transform) = camera_query.get_single_mut() {
+        let ratio = new / old.max(f32::EPSILON);
+        transform.translation.x *= ratio;
+        transform.translation.y *= ratio;
+    }
+    for (mut transform, (cell, ghost, layer)) in &mut sprites {
+        let position = cell.map(|c| (c.x, c.y)).or(ghost.map(|g| (g.x, g.y))).or(layer.map(|l| (l.x, l.y)));
+        if let Some((x, y)) = position {
+            transform.translation.x = x as f32 * new;
+            transform.translation.y = y as f32 * new;
+        }
+    }
+}
+
+/// Recolour for a new colour vision palette by reloading the theme with it applied
+fn apply_color_vision(
+    (config, preset): (Res<CellRenderConfig>, Res<ActivePreset>),
+    mut theme: ResMut<Theme>,
+    mut applied: Local<Option<ColorVision>>,
+) {
+    let previous = applied.replace(config.color_vision);
+    if previous.is_none_or(|vision| vision == config.color_vision) {
+        return;
+    }
+    *theme = preset.theme(&theme.name, config.color_vision);
+}
+
+fn close_render_panel(mut commands: Commands, panels: Query<Entity, With<RenderPanel>>) {
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_step_within_their_ranges() {
+        let mut config = CellRenderConfig::default();
+        RenderSetting::CellSize.adjust(&mut config, 3);
+        assert_eq!(config.cell_size, 26.0);
+        RenderSetting::CellSize.adjust(&mut config, -100);
+        assert_eq!(config.cell_size, 8.0);
+
+        let camera = CameraSettings::default();
+        RenderSetting::Bloom.adjust(&mut config, 2);
+        assert_eq!(RenderSetting::Bloom.value(&config, &camera), "0.10");
+        RenderSetting::Bloom.adjust(&mut config, -5);
+        assert_eq!(RenderSetting::Bloom.value(&config, &camera), "Off");
+
+        let heatmap = config.lod_enabled;
+        RenderSetting::Heatmap.adjust(&mut config, -1);
+        assert_eq!(config.lod_enabled, !heatmap);
+        RenderSetting::PixelMode.adjust(&mut config, 1);
+        assert_eq!(RenderSetting::PixelMode.value(&config, &camera), "On");
+        RenderSetting::ReducedMotion.adjust(&mut config, -1);
+        assert_eq!(config.motion_speed(), 0.0);
+
+        RenderSetting::ColorVision.adjust(&mut config, -1);
+        assert_eq!(config.color_vision, ColorVision::Tritanopia);
+
+        let mut camera = CameraSettings::default();
+        RenderSetting::MinZoom.adjust_camera(&mut camera, -2);
+        assert_eq!(RenderSetting::MinZoom.value(&config, &camera), "0.013");
+        RenderSetting::PanSpeed.adjust_camera(&mut camera, 3);
+        assert_eq!(camera.pan_speed, 800.0);
+    }
+}
@@ -0,0 +1,218 @@
+//! In-game rule editor.
+//!
+//! F6 (by default) opens a panel with a button for each neighbour count, B0–B8 for birth and
+//! S0–S8 for survival, showing which the current rule uses. Clicking one switches the count
+//! in or out and puts the resulting Life-like rule into effect at once: a named rule when the
+//! counts match one (`RuleType::life`), `RuleType::Custom` otherwise. The rulestring shown is
+//! the one saved with sessions and pattern files. Starting from a multi-state rule such as
+//! WireWorld, the first click builds on B3/S23. B0 can't be switched on, since a rule with
+//! birth on no neighbours would fill the infinite plane.
+
+use bevy::prelude::*;
+use bevy::ui::{RelativeCursorPosition, UiSystem};
+use crate::game_config::GameConfig;
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::{GameState, InGame, RuleType};
+use crate::ui::{toast, PointerCapture};
+use crate::tr;
+
+/// Rule the counts are toggled on when the current rule isn't Life-like
+const FALLBACK: RuleType = RuleType::Conway;
+
+const BUTTON_OFF: Color = Color::srgba(0.25, 0.25, 0.3, 0.95);
+const BUTTON_ON: Color = Color::srgba(0.75, 0.6, 0.2, 0.95);
+const BUTTON_HOVER: Color = Color::srgba(0.35, 0.35, 0.45, 0.95);
+
+/// Rule with neighbour count `count` switched for birth (or survival), or `None` for B0
+pub fn toggle_count(rule: RuleType, survive: bool, count: u32) -> Option<RuleType> {
+    if !survive && count == 0 {
+        return None;
+    }
+    let (birth, survival) = rule.life_masks().or_else(|| FALLBACK.life_masks()).unwrap_or_default();
+    Some(if survive { RuleType::life(birth, survival ^ 1 << count) } else { RuleType::life(birth ^ 1 << count, survival) })
+}
+
+#[derive(Component)]
+struct RuleEditorPanel;
+
+/// A neighbour count's button
+#[derive(Component, Clone, Copy)]
+struct CountButton {
+    survive: bool,
+    count: u32,
+}
+
+/// Rulestring and name of the rule, or why a click did nothing
+#[derive(Component)]
+struct RuleSummary;
+
+pub struct RuleEditorPlugin;
+
+impl Plugin for RuleEditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreUpdate, rule_editor_capture.after(UiSystem::Focus).run_if(in_state(GameState::Playing)))
+            .add_systems(
+                Update,
+                (toggle_rule_editor, count_buttons, refresh_rule_editor).chain().run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(InGame), close_rule_editor);
+    }
+}
+
+fn toggle_rule_editor(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
+    asset_server: Res<AssetServer>,
+    panels: Query<Entity, With<RuleEditorPanel>>,
+) {
+    if !keys.just_pressed(&keyboard_input, Action::RuleEditor) {
+        return;
+    }
+    if !panels.is_empty() {
+        for entity in &panels {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(320.0),
+                top: Val::Px(60.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.92)),
+            GlobalZIndex(10),
+            RelativeCursorPosition::default(),
+            RuleEditorPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new(tr!("Rule Editor")), text(20.0), TextColor(Color::srgb(1.0, 0.9, 0.3))));
+            parent.spawn((
+                Text::new(tr!("Click a neighbour count to switch it, {} to close", keys.label(Action::RuleEditor))),
+                text(13.0),
+                TextColor(Color::srgb(0.6, 0.6, 0.6)),
+            ));
+            for (survive, label) in [(false, "Born"), (true, "Survive")] {
+                parent
+                    .spawn(Node { flex_direction: FlexDirection::Row, align_items: AlignItems::Center, column_gap: Val::Px(4.0), ..default() })
+                    .with_children(|row| {
+                        row.spawn((Text::new(label), text(15.0), TextColor(Color::WHITE), Node { width: Val::Px(64.0), ..default() }));
+                        for count in 0..=8 {
+                            row.spawn((
+                                Button,
+                                Node {
+                                    width: Val::Px(24.0),
+                                    height: Val::Px(24.0),
+                                    align_items: AlignItems::Center,
+                                    justify_content: JustifyContent::Center,
+                                    ..default()
+                                },
+                                BackgroundColor(BUTTON_OFF),
+                                CountButton { survive, count },
+                            ))
+                            .with_child((Text::new(count.to_string()), text(15.0), TextColor(Color::WHITE)));
+                        }
+                    });
+            }
+            parent.spawn((Text::new(""), text(15.0), TextColor(Color::srgb(0.9, 0.9, 0.4)), RuleSummary));
+        });
+}
+
+/// Keep clicks on the panel from painting the grid underneath
+fn rule_editor_capture(panels: Query<&RelativeCursorPosition, With<RuleEditorPanel>>, mut capture: ResMut<PointerCapture>) {
+    capture.captured |= panels.iter().any(RelativeCursorPosition::mouse_over);
+}
+
+fn count_buttons(
+    buttons: Query<(&Interaction, &CountButton), Changed<Interaction>>,
+    mut game_config: ResMut<GameConfig>,
+    mut summaries: Query<&mut Text, With<RuleSummary>>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match toggle_count(game_config.current_rule, button.survive, button.count) {
+            Some(rule) => {
+                game_config.current_rule = rule;
+                toast(tr!("🧬 Rule: {} ({})", rule.rulestring(), rule.name()));
+            }
+            None => {
+                for mut text in summaries.iter_mut() {
+                    text.0 = "B0 would fill the infinite plane".to_string();
+                }
+            }
+        }
+    }
+}
+
+type HoveredCounts<'w, 's> = Query<'w, 's, (), (Changed<Interaction>, With<CountButton>)>;
+
+/// Light the counts the rule uses and show its rulestring, following rule changes from
+/// anywhere (a loaded session, say)
+fn refresh_rule_editor(
+    game_config: Res<GameConfig>,
+    mut buttons: Query<(&Interaction, &CountButton, &mut BackgroundColor)>,
+    mut summaries: Query<&mut Text, With<RuleSummary>>,
+    (added, hovered): (Query<(), Added<RuleEditorPanel>>, HoveredCounts),
+) {
+    if !game_config.is_changed() && added.is_empty() && hovered.is_empty() {
+        return;
+    }
+    let rule = game_config.current_rule;
+    let (birth, survive) = rule.life_masks().unwrap_or_default();
+    for (interaction, button, mut colour) in &mut buttons {
+        let mask = if button.survive { survive } else { birth };
+        let shown = match interaction {
+            _ if mask & (1 << button.count) != 0 => BUTTON_ON,
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVER,
+            Interaction::None => BUTTON_OFF,
+        };
+        colour.set_if_neq(BackgroundColor(shown));
+    }
+    if game_config.is_changed() || !added.is_empty() {
+        let summary = match rule {
+            RuleType::Custom { .. } => rule.rulestring(),
+            _ if rule.life_masks().is_none() => format!("{} isn't Life-like", rule.name()),
+            _ => format!("{} · {}", rule.rulestring(), rule.name()),
+        };
+        for mut text in summaries.iter_mut() {
+            text.0.clone_from(&summary);
+        }
+    }
+}
+
+fn close_rule_editor(mut commands: Commands, panels: Query<Entity, With<RuleEditorPanel>>) {
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_toggle_between_named_and_custom_rules() {
+        let highlife = toggle_count(RuleType::Conway, false, 6);
+        assert_eq!(highlife, Some(RuleType::HighLife));
+        let custom = toggle_count(RuleType::HighLife, true, 5).unwrap();
+        assert_eq!(custom, RuleType::Custom { birth: 1 << 3 | 1 << 6, survive: 1 << 2 | 1 << 3 | 1 << 5 });
+        assert_eq!(custom.rulestring(), "B36/S235");
+        assert_eq!(RuleType::from_rulestring("b36/s532"), Some(custom));
+        assert_eq!(toggle_count(custom, true, 5), Some(RuleType::HighLife));
+
+        // Multi-state rules start from B3/S23; B0 is refused everywhere
+        assert_eq!(toggle_count(RuleType::WireWorld, true, 2), Some(RuleType::Custom { birth: 1 << 3, survive: 1 << 3 }));
+        assert_eq!(toggle_count(RuleType::Conway, false, 0), None);
+        assert_eq!(RuleType::from_rulestring("B03/S23"), None);
+    }
+}
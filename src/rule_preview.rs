@@ -0,0 +1,172 @@
+//! Rule preview thumbnail on the start screen.
+//!
+//! Under the selected rule's name, a small grid runs that rule from a fixed seed: a patch of
+//! random soup in the middle for the automata that grow from noise, and a clock feeding a
+//! long wire for WireWorld. Every `LOOP_GENERATIONS` (or once it dies out) it restarts from
+//! the same seed, so each rule has its own short loop to recognise it by. Cells are drawn in
+//! the theme's cell colours. In reduced-motion mode the preview shows its seed and stays still.
+
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::cell_renderer::CellRenderConfig;
+use crate::infinite_grid::{patterns, InfiniteGrid};
+use crate::start_screen::{GameState, RuleType, SelectedRule};
+use crate::theme::Theme;
+use crate::title_soup::clip_to_box;
+use crate::CellState;
+
+/// Size of the preview grid, in cells (and texels of its image)
+pub const PREVIEW_WIDTH: i32 = 64;
+pub const PREVIEW_HEIGHT: i32 = 40;
+/// Side of the square of soup the preview starts from
+const PATCH: i32 = 14;
+const PATCH_DENSITY: f64 = 0.45;
+/// Every rule starts from the same soup, so the differences are the rules'
+const PREVIEW_SEED: u64 = 0x60D;
+const STEP_SECONDS: f32 = 0.1;
+/// Generations before the preview starts over
+const LOOP_GENERATIONS: u64 = 150;
+
+/// The preview of the selected rule, and the image it is drawn into
+#[derive(Resource)]
+pub struct RulePreview {
+    pub rule: RuleType,
+    pub grid: InfiniteGrid,
+    pub generation: u64,
+    pub image: Handle<Image>,
+}
+
+impl RulePreview {
+    fn restart(&mut self, rule: RuleType) {
+        self.rule = rule;
+        self.grid = seed_preview(rule);
+        self.generation = 0;
+    }
+}
+
+/// The grid `rule`'s preview starts from
+pub fn seed_preview(rule: RuleType) -> InfiniteGrid {
+    let mut grid = InfiniteGrid::new();
+    if rule == RuleType::WireWorld {
+        // A clock on the left, its output wire run out to the far side
+        let (x, y) = (2, PREVIEW_HEIGHT / 2 - 1);
+        grid.insert_cells(patterns::wire_clock(), x, y);
+        for wire_x in x + 9..PREVIEW_WIDTH - 2 {
+            grid.set(wire_x, y + 1, CellState::Wire);
+        }
+        return grid;
+    }
+    let mut rng = StdRng::seed_from_u64(PREVIEW_SEED);
+    let (left, top) = ((PREVIEW_WIDTH - PATCH) / 2, (PREVIEW_HEIGHT - PATCH) / 2);
+    for y in top..top + PATCH {
+        for x in left..left + PATCH {
+            if !rng.gen_bool(PATCH_DENSITY) {
+                continue;
+            }
+            let state = match rule {
+                RuleType::Immigration if rng.gen_bool(0.5) => CellState::SpeciesB,
+                RuleType::Immigration => CellState::SpeciesA,
+                _ => CellState::Alive,
+            };
+            grid.set(x, y, state);
+        }
+    }
+    grid
+}
+
+/// RGBA texels of the preview grid, dead cells in `background`
+fn preview_pixels(grid: &InfiniteGrid, theme: &Theme) -> Vec<u8> {
+    let background = theme.start_screen.background.0.to_srgba().to_u8_array();
+    let mut pixels = background.repeat((PREVIEW_WIDTH * PREVIEW_HEIGHT) as usize);
+    for (x, y, state) in grid.cells() {
+        let index = ((y * PREVIEW_WIDTH + x) * 4) as usize;
+        if let Some(pixel) = pixels.get_mut(index..index + 4) {
+            pixel.copy_from_slice(&theme.cells.color(state).to_srgba().to_u8_array());
+        }
+    }
+    pixels
+}
+
+pub struct RulePreviewPlugin;
+
+impl Plugin for RulePreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, create_rule_preview)
+            .add_systems(Update, (step_rule_preview, draw_rule_preview).chain().run_if(in_state(GameState::StartScreen)));
+    }
+}
+
+fn create_rule_preview(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let size = Extent3d { width: PREVIEW_WIDTH as u32, height: PREVIEW_HEIGHT as u32, depth_or_array_layers: 1 };
+    let pixels = vec![0; (PREVIEW_WIDTH * PREVIEW_HEIGHT * 4) as usize];
+    let mut image = Image::new(size, TextureDimension::D2, pixels, TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::default());
+    image.sampler = ImageSampler::nearest();
+    let rule = RuleType::Conway;
+    commands.insert_resource(RulePreview { rule, grid: seed_preview(rule), generation: 0, image: images.add(image) });
+}
+
+fn step_rule_preview(
+    (time, render, selected): (Res<Time>, Res<CellRenderConfig>, Res<SelectedRule>),
+    mut preview: ResMut<RulePreview>,
+    mut since_step: Local<f32>,
+) {
+    if selected.current != preview.rule {
+        preview.restart(selected.current);
+        *since_step = 0.0;
+        return;
+    }
+    if render.reduced_motion {
+        return;
+    }
+    *since_step += time.delta_secs();
+    if *since_step < STEP_SECONDS {
+        return;
+    }
+    *since_step = 0.0;
+    if preview.generation >= LOOP_GENERATIONS || preview.grid.population() == 0 {
+        let rule = preview.rule;
+        preview.restart(rule);
+        return;
+    }
+    let rule = preview.rule;
+    preview.grid.update(rule);
+    clip_to_box(&mut preview.grid, PREVIEW_WIDTH, PREVIEW_HEIGHT);
+    preview.generation += 1;
+}
+
+fn draw_rule_preview(preview: Res<RulePreview>, theme: Res<Theme>, mut images: ResMut<Assets<Image>>) {
+    if !preview.is_changed() && !theme.is_changed() {
+        return;
+    }
+    if let Some(image) = images.get_mut(&preview.image) {
+        image.data = preview_pixels(&preview.grid, &theme);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_rule_has_a_live_preview_inside_the_box() {
+        for rule in RuleType::all() {
+            let mut grid = seed_preview(rule);
+            assert!(grid.population() > 0, "{} starts empty", rule.name());
+            for _ in 0..20 {
+                grid.update(rule);
+                clip_to_box(&mut grid, PREVIEW_WIDTH, PREVIEW_HEIGHT);
+            }
+            assert!(grid.cells().all(|(x, y, _)| (0..PREVIEW_WIDTH).contains(&x) && (0..PREVIEW_HEIGHT).contains(&y)));
+        }
+        // Electrons leave the clock along the wire
+        let mut grid = seed_preview(RuleType::WireWorld);
+        for _ in 0..12 {
+            grid.update(RuleType::WireWorld);
+        }
+        assert!(grid.cells().any(|(x, _, state)| state == CellState::ElectronHead && x > 11));
+    }
+}
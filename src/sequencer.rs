@@ -0,0 +1,181 @@
+//! Step-sequencer sonification.
+//!
+//! With the sequencer on (8 with the default bindings), a playhead sweeps across the columns
+//! of the view from left to right, one column per sixteenth note at `Sequencer::bpm`, and
+//! wraps around. Every live cell in the column under it plucks a lute note whose pitch rises
+//! with the cell's row, in the current scale of the hybrid engine, on top of the ambient
+//! sound. The view's height is spread over `DEGREES` scale degrees (three octaves) so any zoom
+//! stays playable, and a crowded column plays at most `MAX_NOTES` of its rows. The playhead
+//! keeps going while the simulation is paused, so a still pattern can be edited as a loop.
+
+use bevy::prelude::*;
+use crate::audio::play_hybrid_note;
+use crate::camera::{visible_grid_bounds, CameraState, GameCamera};
+use crate::infinite_grid::InfiniteGrid;
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::{GameState, InGame};
+use crate::ui::toast;
+use crate::tr;
+
+pub const DEFAULT_BPM: f32 = 120.0;
+/// Columns played per beat
+pub const STEPS_PER_BEAT: f32 = 4.0;
+/// Scale degrees the view's rows are spread over
+pub const DEGREES: i32 = 21;
+/// Most notes one column plays at once
+pub const MAX_NOTES: usize = 4;
+
+#[derive(Resource, Debug)]
+pub struct Sequencer {
+    pub enabled: bool,
+    pub bpm: f32,
+    /// Steps played since the sequencer was turned on
+    step: u64,
+    /// Seconds into the current step
+    elapsed: f32,
+    /// Column the playhead is on
+    column: Option<i32>,
+}
+
+impl Default for Sequencer {
+    fn default() -> Self {
+        Self::new(DEFAULT_BPM)
+    }
+}
+
+impl Sequencer {
+    pub fn new(bpm: f32) -> Self {
+        Self { enabled: false, bpm: bpm.clamp(20.0, 400.0), step: 0, elapsed: 0.0, column: None }
+    }
+
+    pub fn toggle(&mut self) -> bool {
+        self.enabled = !self.enabled;
+        self.step = 0;
+        self.elapsed = 0.0;
+        self.column = None;
+        self.enabled
+    }
+
+    fn step_seconds(&self) -> f32 {
+        60.0 / self.bpm / STEPS_PER_BEAT
+    }
+}
+
+/// Scale degrees for the live cells of column `x` between rows `min_y` and `max_y`, lowest
+/// first, thinned evenly to `MAX_NOTES`
+pub fn column_degrees(grid: &InfiniteGrid, x: i32, min_y: i32, max_y: i32) -> Vec<i32> {
+    let height = (max_y - min_y + 1).max(1);
+    let mut degrees: Vec<i32> = grid
+        .cells_in_region(x, x, min_y, max_y)
+        .map(|(_, y)| if height <= DEGREES { y - min_y } else { (y - min_y) * DEGREES / height })
+        .collect();
+    degrees.sort_unstable();
+    degrees.dedup();
+    if degrees.len() > MAX_NOTES {
+        let count = degrees.len();
+        degrees = (0..MAX_NOTES).map(|i| degrees[i * (count - 1) / (MAX_NOTES - 1)]).collect();
+    }
+    degrees
+}
+
+pub struct SequencerPlugin;
+
+impl Plugin for SequencerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Sequencer>()
+            .add_systems(
+                Update,
+                (sequencer_hotkey, advance_sequencer, draw_playhead).chain().run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(InGame), stop_sequencer);
+    }
+}
+
+fn sequencer_hotkey(keyboard_input: Res<ButtonInput<KeyCode>>, keys: Res<Keybindings>, mut sequencer: ResMut<Sequencer>) {
+    // Alt+digits belong to the grid layers
+    if !keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]) && keys.just_pressed(&keyboard_input, Action::Sequencer) {
+        if sequencer.toggle() {
+            toast(tr!("🎹 Step sequencer on at {:.0} BPM", sequencer.bpm));
+        } else {
+            toast(tr!("🎹 Step sequencer off"));
+        }
+    }
+}
+
+/// Move the playhead on by the steps due this frame and play the column it lands on
+fn advance_sequencer(
+    mut sequencer: ResMut<Sequencer>,
+    time: Res<Time>,
+    grid: Res<InfiniteGrid>,
+    cameras: Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    camera_state: Res<CameraState>,
+) {
+    if !sequencer.enabled {
+        return;
+    }
+    let Ok((transform, projection)) = cameras.get_single() else { return };
+    let bounds = visible_grid_bounds(transform, projection, &camera_state);
+    let first_step = sequencer.column.is_none();
+    sequencer.elapsed += time.delta_secs();
+    let step_seconds = sequencer.step_seconds();
+    if !first_step && sequencer.elapsed < step_seconds {
+        return;
+    }
+    // After a hitch, skip the missed steps rather than playing them all at once
+    let steps = if first_step { 0 } else { (sequencer.elapsed / step_seconds) as u64 };
+    sequencer.elapsed -= steps as f32 * step_seconds;
+    sequencer.step += steps;
+    let width = (bounds.max_x - bounds.min_x + 1).max(1) as u64;
+    let x = bounds.min_x + (sequencer.step % width) as i32;
+    sequencer.column = Some(x);
+
+    let degrees = column_degrees(&grid, x, bounds.min_y, bounds.max_y);
+    let velocity = 0.8 / (degrees.len() as f32).sqrt().max(1.0);
+    for degree in degrees {
+        play_hybrid_note(degree, velocity);
+    }
+}
+
+fn draw_playhead(
+    mut gizmos: Gizmos,
+    sequencer: Res<Sequencer>,
+    cameras: Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    camera_state: Res<CameraState>,
+) {
+    let (true, Some(column), Ok((transform, projection))) = (sequencer.enabled, sequencer.column, cameras.get_single()) else { return };
+    let x = column as f32 * camera_state.cell_size + camera_state.grid_offset.x;
+    let centre = transform.translation.truncate();
+    let size = Vec2::new(camera_state.cell_size, projection.area.height());
+    gizmos.rect_2d(Isometry2d::from_translation(Vec2::new(x, centre.y)), size, Color::srgba(1.0, 0.9, 0.4, 0.6));
+}
+
+fn stop_sequencer(mut sequencer: ResMut<Sequencer>) {
+    if sequencer.enabled {
+        sequencer.toggle();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CellState;
+
+    #[test]
+    fn rows_become_degrees_thinned_to_the_note_limit() {
+        let mut grid = InfiniteGrid::new();
+        for y in [2, 5, 5, 7] {
+            grid.set(3, y, CellState::Alive);
+        }
+        grid.set(4, 0, CellState::Alive);
+        assert_eq!(column_degrees(&grid, 3, 0, 9), vec![2, 5, 7]);
+
+        // A tall view squeezes its rows into the degree range
+        assert_eq!(column_degrees(&grid, 3, 0, 41), vec![1, 2, 3]);
+
+        for y in 0..10 {
+            grid.set(6, y, CellState::Alive);
+        }
+        assert_eq!(column_degrees(&grid, 6, 0, 9), vec![0, 3, 6, 9]);
+        assert!(column_degrees(&grid, 8, 0, 9).is_empty());
+    }
+}
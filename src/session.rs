@@ -0,0 +1,215 @@
+//! Whole-session save and load.
+//!
+//! A `SessionSnapshot` captures everything needed to resume a game: the cells (stored as an
+//! RLE body plus the origin it was normalised from, so every state survives), the rule,
+//! generation count, camera, simulation speed and synth parameters. Snapshots are written
+//! as TOML with a format version so older saves can be rejected cleanly.
+//!
+//! F5 saves to `SESSION_PATH`, F9 reloads it, and the start screen offers "Continue" when a
+//! save exists. Leaving a game for the start screen writes `AUTOSAVE_PATH`, and the start
+//! screen lists the most recent saves in `SAVES_DIR` (see `recent_sessions`). The start
+//! screen hands the loaded snapshot over through `PendingSession`, which is applied once the
+//! playing state has finished its own setup.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+use crate::infinite_grid::InfiniteGrid;
+use crate::io::{parse_rle, write_rle, Pattern, PatternError};
+use crate::start_screen::RuleType;
+use crate::tr;
+
+/// Current save format; bump when fields change meaning
+pub const SESSION_VERSION: u32 = 2;
+
+/// Where the quick save lives, relative to the working directory
+pub const SESSION_PATH: &str = "saves/session.toml";
+
+/// Where leaving a game for the start screen saves it
+pub const AUTOSAVE_PATH: &str = "saves/autosave.toml";
+
+/// Folder the start screen lists saves from
+pub const SAVES_DIR: &str = "saves";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("could not read save: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("could not write save: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    #[error("save format {0} is not supported (expected {SESSION_VERSION})")]
+    UnsupportedVersion(u32),
+    #[error("unknown rule '{0}'")]
+    UnknownRule(String),
+    #[error(transparent)]
+    Pattern(#[from] PatternError),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub version: u32,
+    /// Rule in pattern-file notation (`RuleType::rulestring`)
+    pub rule: String,
+    pub generation: u64,
+    /// Camera centre in world units
+    pub camera: [f32; 2],
+    pub zoom: f32,
+    /// Seconds between generations
+    pub update_interval: f64,
+    pub synth_volume: f32,
+    pub synth_mix: f32,
+    /// Grid position of the bottom-left of `cells` (its smallest x and y)
+    pub origin: [i32; 2],
+    /// RLE of every non-dead cell
+    pub cells: String,
+}
+
+impl SessionSnapshot {
+    /// Snapshot the grid and rule; the remaining fields start at neutral values for the
+    /// caller to fill in
+    pub fn new(grid: &InfiniteGrid, rule: RuleType) -> Self {
+        let origin = grid.bounds().map_or([0, 0], |b| [b.min_x, b.min_y]);
+        Self {
+            version: SESSION_VERSION,
+            rule: rule.rulestring(),
+            generation: 0,
+            camera: [0.0, 0.0],
+            zoom: 1.0,
+            update_interval: 0.2,
+            synth_volume: 0.7,
+            synth_mix: 0.7,
+            origin,
+            cells: write_rle(&Pattern::from_grid(grid, rule)),
+        }
+    }
+
+    pub fn rule(&self) -> Result<RuleType, SessionError> {
+        RuleType::from_rulestring(&self.rule).ok_or_else(|| SessionError::UnknownRule(self.rule.clone()))
+    }
+
+    /// Rebuild the saved grid
+    pub fn grid(&self) -> Result<InfiniteGrid, SessionError> {
+        let mut grid = InfiniteGrid::new();
+        parse_rle(&self.cells)?.insert_into(&mut grid, self.origin[0], self.origin[1]);
+        Ok(grid)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SessionError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SessionError> {
+        let snapshot: Self = toml::from_str(&std::fs::read_to_string(path)?)?;
+        if snapshot.version != SESSION_VERSION {
+            return Err(SessionError::UnsupportedVersion(snapshot.version));
+        }
+        snapshot.rule()?;
+        Ok(snapshot)
+    }
+}
+
+/// A readable save found on disk, with what the start screen shows about it
+pub struct SavedSession {
+    pub path: PathBuf,
+    pub snapshot: SessionSnapshot,
+    pub grid: InfiniteGrid,
+    pub modified: SystemTime,
+}
+
+impl SavedSession {
+    /// "Quick save", "Autosave", or the file name for saves made some other way
+    pub fn title(&self) -> String {
+        if self.path.ends_with(Path::new(SESSION_PATH).file_name().unwrap_or_default()) {
+            tr!("Quick save")
+        } else if self.path.ends_with(Path::new(AUTOSAVE_PATH).file_name().unwrap_or_default()) {
+            tr!("Autosave")
+        } else {
+            self.path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default()
+        }
+    }
+}
+
+/// Up to `limit` loadable saves in `dir`, newest first; files that fail to load are skipped
+pub fn recent_sessions(dir: impl AsRef<Path>, limit: usize) -> Vec<SavedSession> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut saves: Vec<SavedSession> = read_dir
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|entry| {
+            let path = entry.path();
+            let modified = entry.metadata().and_then(|meta| meta.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
+            let snapshot = SessionSnapshot::load(&path).ok()?;
+            let grid = snapshot.grid().ok()?;
+            Some(SavedSession { path, snapshot, grid, modified })
+        })
+        .collect();
+    saves.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.path.cmp(&b.path)));
+    saves.truncate(limit);
+    saves
+}
+
+/// Whether a quick save exists to continue from
+pub fn has_saved_session() -> bool {
+    Path::new(SESSION_PATH).is_file()
+}
+
+/// Snapshot waiting to be applied when the playing state starts
+#[derive(Resource, Default)]
+pub struct PendingSession(pub Option<SessionSnapshot>);
+
+#[cfg(test)]
+mod tests {
+    use crate::CellState;
+    use super::*;
+
+    #[test]
+    fn snapshot_round_trips_through_toml() {
+        let mut grid = InfiniteGrid::new();
+        grid.set(-5, 3, CellState::ElectronHead);
+        grid.set(-4, 3, CellState::ElectronTail);
+        grid.set(2, 9, CellState::Wire);
+        let mut snapshot = SessionSnapshot::new(&grid, RuleType::WireWorld);
+        snapshot.generation = 17;
+
+        let path = std::env::temp_dir().join(format!("gameofdeath-session-{}.toml", std::process::id()));
+        snapshot.save(&path).unwrap();
+        let loaded = SessionSnapshot::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded, snapshot);
+        assert_eq!(loaded.rule().unwrap(), RuleType::WireWorld);
+        let restored = loaded.grid().unwrap();
+        assert_eq!(restored.get(-5, 3), CellState::ElectronHead);
+        assert_eq!(restored.get(2, 9), CellState::Wire);
+        assert_eq!(restored.population(), 3);
+    }
+
+    #[test]
+    fn recent_sessions_lists_loadable_saves() {
+        let dir = std::env::temp_dir().join(format!("gameofdeath-saves-{}", std::process::id()));
+        let mut grid = InfiniteGrid::new();
+        grid.set(1, 1, CellState::Alive);
+        SessionSnapshot::new(&grid, RuleType::Conway).save(dir.join("autosave.toml")).unwrap();
+        SessionSnapshot::new(&grid, RuleType::Seeds).save(dir.join("session.toml")).unwrap();
+        std::fs::write(dir.join("broken.toml"), "not a save").unwrap();
+
+        let saves = recent_sessions(&dir, 5);
+        let one = recent_sessions(&dir, 1);
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(saves.len(), 2);
+        assert_eq!(one.len(), 1);
+        let mut titles: Vec<String> = saves.iter().map(SavedSession::title).collect();
+        titles.sort();
+        assert_eq!(titles, ["Autosave", "Quick save"]);
+        assert!(saves.iter().all(|save| save.grid.population() == 1));
+    }
+}
@@ -0,0 +1,180 @@
+//! Saved games on the start screen.
+//!
+//! The start screen lists the most recent saves in `SAVES_DIR` (the quick save, the autosave
+//! written on leaving a game, and any other session files put there) down its left side,
+//! newest first. Each shows a thumbnail of its cells with the rule, generation, population
+//! and age of the save; clicking one goes straight into the game it holds, camera included.
+
+use std::time::SystemTime;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use crate::infinite_grid::InfiniteGrid;
+use crate::session::{recent_sessions, PendingSession, SavedSession, SessionSnapshot, SAVES_DIR};
+use crate::start_screen::{GameState, RuleType, SelectedRule, StartScreenEntity};
+use crate::theme::Theme;
+use crate::tr;
+
+/// Saves listed at most
+const MAX_LISTED: usize = 5;
+/// Side of a thumbnail, in texels
+const THUMB_TEXELS: u32 = 48;
+/// Side of a thumbnail on screen
+const THUMB_SIZE: f32 = 56.0;
+
+/// Button loading one of the listed saves
+#[derive(Component)]
+struct SessionEntry(SessionSnapshot);
+
+/// RGBA texels of `grid` shrunk to fit a `size`-square thumbnail, top row first
+pub fn thumbnail_pixels(grid: &InfiniteGrid, size: u32, theme: &Theme) -> Vec<u8> {
+    let mut pixels = theme.start_screen.background.0.to_srgba().to_u8_array().repeat((size * size) as usize);
+    let Some(bounds) = grid.bounds() else { return pixels };
+    let span = (bounds.max_x - bounds.min_x).max(bounds.max_y - bounds.min_y) + 1;
+    // Cells per texel, so the whole pattern fits; centred in the square
+    let scale = (span as f32 / size as f32).max(1.0);
+    let used = |extent: i32| ((extent + 1) as f32 / scale).ceil() as i32;
+    let pad_x = (size as i32 - used(bounds.max_x - bounds.min_x)) / 2;
+    let pad_y = (size as i32 - used(bounds.max_y - bounds.min_y)) / 2;
+    for (x, y, state) in grid.cells() {
+        let column = pad_x + ((x - bounds.min_x) as f32 / scale) as i32;
+        // Grid y points up, image rows go down
+        let row = pad_y + ((bounds.max_y - y) as f32 / scale) as i32;
+        if !(0..size as i32).contains(&column) || !(0..size as i32).contains(&row) {
+            continue;
+        }
+        let index = ((row as u32 * size + column as u32) * 4) as usize;
+        pixels[index..index + 4].copy_from_slice(&theme.cells.color(state).to_srgba().to_u8_array());
+    }
+    pixels
+}
+
+/// How long ago `time` was, roughly
+fn age(time: SystemTime) -> String {
+    let seconds = SystemTime::now().duration_since(time).map_or(0, |age| age.as_secs());
+    match seconds {
+        0..60 => tr!("just now"),
+        60..3600 => tr!("{} min ago", seconds / 60),
+        3600..86400 => tr!("{} h ago", seconds / 3600),
+        _ => tr!("{} days ago", seconds / 86400),
+    }
+}
+
+pub struct SessionsListPlugin;
+
+impl Plugin for SessionsListPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::StartScreen), spawn_sessions_list)
+            .add_systems(Update, load_session_entry.run_if(in_state(GameState::StartScreen)));
+    }
+}
+
+fn spawn_sessions_list(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let saves = recent_sessions(SAVES_DIR, MAX_LISTED);
+    if saves.is_empty() {
+        return;
+    }
+    let palette = &theme.start_screen;
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+    let mut thumbnail = |save: &SavedSession| {
+        let size = Extent3d { width: THUMB_TEXELS, height: THUMB_TEXELS, depth_or_array_layers: 1 };
+        let pixels = thumbnail_pixels(&save.grid, THUMB_TEXELS, &theme);
+        let mut image = Image::new(size, TextureDimension::D2, pixels, TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::default());
+        image.sampler = ImageSampler::nearest();
+        images.add(image)
+    };
+    let entries: Vec<_> = saves.iter().map(|save| (save, thumbnail(save))).collect();
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                left: Val::Px(20.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            BackgroundColor(palette.panel.0),
+            BorderColor(palette.panel_border.0),
+            // Over the start screen's full-window background
+            GlobalZIndex(1),
+            StartScreenEntity,
+        ))
+        .with_children(|panel| {
+            panel.spawn((Text::new(tr!("SAVED GAMES")), text(18.0), TextColor(palette.heading.0)));
+            for (save, image) in entries {
+                let rule = save.snapshot.rule().map_or_else(|_| save.snapshot.rule.clone(), |rule| rule.name().to_string());
+                let details = tr!("{} · gen {} · {} cells", rule, save.snapshot.generation, save.grid.population());
+                panel
+                    .spawn((
+                        Button,
+                        Node {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(10.0),
+                            padding: UiRect::all(Val::Px(4.0)),
+                            border: UiRect::all(Val::Px(1.0)),
+                            ..default()
+                        },
+                        BackgroundColor(palette.button.0),
+                        BorderColor(palette.button_border.0),
+                        SessionEntry(save.snapshot.clone()),
+                    ))
+                    .with_children(|entry| {
+                        entry.spawn((ImageNode::new(image), Node { width: Val::Px(THUMB_SIZE), height: Val::Px(THUMB_SIZE), ..default() }));
+                        entry.spawn(Node { flex_direction: FlexDirection::Column, ..default() }).with_children(|lines| {
+                            lines.spawn((Text::new(format!("{} · {}", save.title(), age(save.modified))), text(16.0), TextColor(palette.accent.0)));
+                            lines.spawn((Text::new(details), text(13.0), TextColor(palette.description.0)));
+                        });
+                    });
+            }
+        });
+}
+
+fn load_session_entry(
+    entries: Query<(&Interaction, &SessionEntry), Changed<Interaction>>,
+    mut selected_rule: ResMut<SelectedRule>,
+    mut pending_session: ResMut<PendingSession>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, entry) in &entries {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Ok(rule) = entry.0.rule() {
+            selected_rule.current = rule;
+            selected_rule.index = RuleType::all().iter().position(|&r| r == rule).unwrap_or(0);
+        }
+        pending_session.0 = Some(entry.0.clone());
+        next_state.set(GameState::Playing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CellState;
+
+    #[test]
+    fn thumbnail_fits_the_whole_pattern() {
+        let theme = Theme::default();
+        let background = theme.start_screen.background.0.to_srgba().to_u8_array();
+        let mut grid = InfiniteGrid::new();
+        grid.set(-100, 50, CellState::Alive);
+        grid.set(100, -50, CellState::Alive);
+        let pixels = thumbnail_pixels(&grid, 16, &theme);
+        assert_eq!(pixels.len(), 16 * 16 * 4);
+        let lit = pixels.chunks(4).filter(|texel| *texel != background).count();
+        assert_eq!(lit, 2);
+        assert_eq!(thumbnail_pixels(&InfiniteGrid::new(), 16, &theme).chunks(4).filter(|texel| *texel != background).count(), 0);
+    }
+}
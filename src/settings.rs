@@ -104,6 +104,7 @@ impl GameSettings {
             seed: None,
             audio_engine: crate::config::AudioEngine::Spatial, // Default
             audio_volume: self.audio_volume,
+            ..Default::default()
         }
     }
 }
@@ -0,0 +1,403 @@
+//! In-game settings screen.
+//!
+//! F7 (by default) pauses the game into `GameState::Settings`, a screen over the dimmed
+//! playfield where the audio engine, volume, theme and interface size can be changed, keys rebound on the
+//! controls screen (`controls_screen`) or the `[keybindings]` table reloaded from the config
+//! file after editing it, and every render and camera option of the F3 panel set. Changes
+//! apply at once and are kept in `Preferences`, so they are saved on exit like the window
+//! size. F7 or Esc resumes; the game is left exactly as it was, since only leaving `InGame`
+//! tears it down.
+
+use bevy::prelude::*;
+use crate::audio::{ddsp_audio_running, hybrid_dungeon_synth_running, init_ddsp_audio, init_hybrid_dungeon_synth, set_hybrid_volume, set_spatial_audio};
+use crate::camera::CameraSettings;
+use crate::cell_renderer::CellRenderConfig;
+use crate::config::{AudioEngine, Config};
+use crate::game_config::GameConfig;
+use crate::keybindings::{Action, Keybindings};
+use crate::preferences::{store_render_settings, Preferences};
+use crate::presets::ActivePreset;
+use crate::render_panel::{step_setting, RenderSetting};
+use crate::start_screen::GameState;
+use crate::theme::{step_theme, Theme};
+use crate::ui::{step_scale, toast, FontScale};
+use crate::tr;
+
+/// Loudest volume, as with the volume keys
+const MAX_VOLUME: f32 = 2.0;
+
+const ROW_COLOR: Color = Color::srgba(0.15, 0.15, 0.2, 0.9);
+pub(crate) const BUTTON_IDLE: Color = Color::srgba(0.25, 0.25, 0.3, 0.95);
+const BUTTON_HOVER: Color = Color::srgba(0.35, 0.35, 0.45, 0.95);
+const BUTTON_PRESSED: Color = Color::srgba(0.45, 0.4, 0.2, 0.95);
+
+/// A row of the screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingsRow {
+    AudioEngine,
+    Volume,
+    Theme,
+    UiScale,
+    FontScale,
+    Render(RenderSetting),
+}
+
+impl SettingsRow {
+    pub fn label(self) -> &'static str {
+        match self {
+            SettingsRow::AudioEngine => "Audio engine",
+            SettingsRow::Volume => "Volume",
+            SettingsRow::Theme => "Theme",
+            SettingsRow::UiScale => "Interface scale",
+            SettingsRow::FontScale => "Text size",
+            SettingsRow::Render(setting) => setting.label(),
+        }
+    }
+}
+
+/// Audio engine `steps` places along `AudioEngine::ALL` from `engine`, wrapping around
+pub fn step_engine(engine: AudioEngine, steps: i32) -> AudioEngine {
+    let count = AudioEngine::ALL.len() as i32;
+    let index = AudioEngine::ALL.iter().position(|&e| e == engine).unwrap_or(0) as i32;
+    AudioEngine::ALL[(index + steps).rem_euclid(count) as usize]
+}
+
+/// Volume `steps` tenths up or down, within 0..=`MAX_VOLUME`
+pub fn step_volume(volume: f32, steps: i32) -> f32 {
+    ((volume * 10.0).round() + steps as f32).clamp(0.0, MAX_VOLUME * 10.0) / 10.0
+}
+
+/// Start the hybrid synth (with the cluster voices over it for the spatial engine) or the
+/// DDSP engine for `engine`, or silence them for the dungeon synth, which is a placeholder
+pub fn apply_audio_engine(engine: AudioEngine, volume: f32) {
+    set_spatial_audio(engine == AudioEngine::Spatial);
+    match engine {
+        AudioEngine::Spatial | AudioEngine::Hybrid => {
+            if !hybrid_dungeon_synth_running() {
+                init_hybrid_dungeon_synth();
+            }
+            set_hybrid_volume(volume);
+        }
+        AudioEngine::DDSP => {
+            if !ddsp_audio_running() {
+                init_ddsp_audio();
+            }
+            set_hybrid_volume(volume);
+        }
+        AudioEngine::DungeonSynth => set_hybrid_volume(0.0),
+    }
+}
+
+#[derive(Component)]
+struct SettingsScreen;
+
+/// − or + button of a row
+#[derive(Component, Clone, Copy)]
+struct RowButton {
+    row: SettingsRow,
+    steps: i32,
+}
+
+/// Value text of a row
+#[derive(Component)]
+struct RowValue(SettingsRow);
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum ScreenButton {
+    Controls,
+    ReloadKeys,
+    Resume,
+    Menu,
+}
+
+/// Outcome of the last keybinding reload
+#[derive(Component)]
+struct ReloadStatus;
+
+pub struct SettingsMenuPlugin;
+
+impl Plugin for SettingsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, open_settings.run_if(in_state(GameState::Playing)))
+            .add_systems(OnEnter(GameState::Settings), spawn_settings_screen)
+            .add_systems(OnExit(GameState::Settings), (despawn_settings_screen, keep_render_settings))
+            .add_systems(
+                Update,
+                (close_settings, row_buttons, screen_buttons, refresh_values).chain().run_if(in_state(GameState::Settings)),
+            );
+    }
+}
+
+fn open_settings(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keys.just_pressed(&keyboard_input, Action::Settings) {
+        next_state.set(GameState::Settings);
+    }
+}
+
+fn close_settings(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keys.just_pressed(&keyboard_input, Action::Settings) || keyboard_input.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Playing);
+    }
+}
+
+fn spawn_settings_screen(mut commands: Commands, asset_server: Res<AssetServer>, keys: Res<Keybindings>) {
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+    let heading = |title: String| (Text::new(title), text(17.0), TextColor(Color::srgb(1.0, 0.9, 0.3)), Node { margin: UiRect::vertical(Val::Px(4.0)), ..default() });
+    let column = || Node { width: Val::Px(330.0), flex_direction: FlexDirection::Column, row_gap: Val::Px(2.0), ..default() };
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            GlobalZIndex(20),
+            SettingsScreen,
+        ))
+        .with_children(|screen| {
+            screen
+                .spawn((
+                    Node { flex_direction: FlexDirection::Column, padding: UiRect::all(Val::Px(12.0)), row_gap: Val::Px(6.0), ..default() },
+                    BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.95)),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((Text::new(tr!("Settings (paused)")), text(24.0), TextColor(Color::srgb(1.0, 0.9, 0.3))));
+                    panel.spawn((
+                        Text::new(tr!("Changes apply at once and are saved on exit. {} or Esc to resume", keys.label(Action::Settings))),
+                        text(13.0),
+                        TextColor(Color::srgb(0.6, 0.6, 0.6)),
+                    ));
+                    panel.spawn(Node { flex_direction: FlexDirection::Row, column_gap: Val::Px(16.0), ..default() }).with_children(|columns| {
+                        columns.spawn(column()).with_children(|left| {
+                            left.spawn(heading(tr!("Game")));
+                            for row in [SettingsRow::AudioEngine, SettingsRow::Volume, SettingsRow::Theme] {
+                                spawn_row(left, &text, row);
+                            }
+                            left.spawn(heading(tr!("Interface")));
+                            for row in [SettingsRow::UiScale, SettingsRow::FontScale] {
+                                spawn_row(left, &text, row);
+                            }
+                            left.spawn(heading(tr!("Keybindings")));
+                            left.spawn((
+                                Text::new(tr!("Rebind keys, or edit the [keybindings] table of the config file and reload it")),
+                                text(13.0),
+                                TextColor(Color::srgb(0.6, 0.6, 0.6)),
+                            ));
+                            left.spawn(Node { flex_direction: FlexDirection::Row, column_gap: Val::Px(6.0), ..default() }).with_children(|buttons| {
+                                spawn_screen_button(buttons, text(15.0), "Rebind keys", ScreenButton::Controls);
+                                spawn_screen_button(buttons, text(15.0), "Reload from file", ScreenButton::ReloadKeys);
+                            });
+                            left.spawn((Text::new(""), text(13.0), TextColor(Color::srgb(0.9, 0.9, 0.4)), ReloadStatus));
+                        });
+                        columns.spawn(column()).with_children(|right| {
+                            right.spawn(heading(tr!("Render")));
+                            for setting in RenderSetting::ALL {
+                                spawn_row(right, &text, SettingsRow::Render(setting));
+                            }
+                        });
+                    });
+                    panel.spawn(Node { flex_direction: FlexDirection::Row, column_gap: Val::Px(8.0), margin: UiRect::top(Val::Px(6.0)), ..default() }).with_children(|footer| {
+                        spawn_screen_button(footer, text(16.0), "Resume", ScreenButton::Resume);
+                        spawn_screen_button(footer, text(16.0), "Quit to menu", ScreenButton::Menu);
+                    });
+                });
+        });
+}
+
+fn spawn_row(parent: &mut ChildBuilder, text: &impl Fn(f32) -> TextFont, row: SettingsRow) {
+    parent
+        .spawn((
+            Node {
+                height: Val::Px(26.0),
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.0),
+                padding: UiRect::horizontal(Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(ROW_COLOR),
+        ))
+        .with_children(|parent| {
+            parent.spawn((Text::new(row.label()), text(15.0), TextColor(Color::WHITE), Node { flex_grow: 1.0, ..default() }));
+            spawn_row_button(parent, text(16.0), "−", RowButton { row, steps: -1 });
+            parent.spawn((
+                Text::new(""),
+                text(14.0),
+                TextColor(Color::srgb(0.9, 0.9, 0.4)),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Node { width: Val::Px(96.0), ..default() },
+                RowValue(row),
+            ));
+            spawn_row_button(parent, text(16.0), "+", RowButton { row, steps: 1 });
+        });
+}
+
+fn spawn_row_button(parent: &mut ChildBuilder, font: TextFont, label: &str, button: RowButton) {
+    parent
+        .spawn((
+            Button,
+            Node { width: Val::Px(22.0), height: Val::Px(22.0), align_items: AlignItems::Center, justify_content: JustifyContent::Center, ..default() },
+            BackgroundColor(BUTTON_IDLE),
+            button,
+        ))
+        .with_child((Text::new(label), font, TextColor(Color::WHITE)));
+}
+
+fn spawn_screen_button(parent: &mut ChildBuilder, font: TextFont, label: &str, button: ScreenButton) {
+    parent
+        .spawn((
+            Button,
+            Node { padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)), justify_content: JustifyContent::Center, ..default() },
+            BackgroundColor(BUTTON_IDLE),
+            button,
+        ))
+        .with_child((Text::new(label), font, TextColor(Color::WHITE)));
+}
+
+fn despawn_settings_screen(mut commands: Commands, screens: Query<Entity, With<SettingsScreen>>) {
+    for entity in &screens {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Render options have no `Preferences` tracking of their own, so they are stored on the way
+/// out (F3 changes made before opening the screen included)
+fn keep_render_settings((render, camera): (Res<CellRenderConfig>, Res<CameraSettings>), mut preferences: ResMut<Preferences>) {
+    store_render_settings(&mut preferences.config, &render, &camera);
+}
+
+pub(crate) fn button_color(interaction: Interaction) -> Color {
+    match interaction {
+        Interaction::Pressed => BUTTON_PRESSED,
+        Interaction::Hovered => BUTTON_HOVER,
+        Interaction::None => BUTTON_IDLE,
+    }
+}
+
+fn row_buttons(
+    mut buttons: Query<(&Interaction, &RowButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut game_config: ResMut<GameConfig>,
+    (mut render, mut camera): (ResMut<CellRenderConfig>, ResMut<CameraSettings>),
+    (mut theme, mut clear_color, preset): (ResMut<Theme>, ResMut<ClearColor>, Res<ActivePreset>),
+    (mut ui_scale, mut font_scale, mut preferences): (ResMut<UiScale>, ResMut<FontScale>, ResMut<Preferences>),
+) {
+    for (interaction, button, mut colour) in &mut buttons {
+        colour.0 = button_color(*interaction);
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button.row {
+            SettingsRow::AudioEngine => {
+                let engine = step_engine(game_config.audio_engine, button.steps);
+                game_config.audio_engine = engine;
+                preferences.config.audio_engine = engine;
+                apply_audio_engine(engine, game_config.output_volume());
+                toast(tr!("🔊 Audio engine: {}", engine.name()));
+            }
+            SettingsRow::Volume => {
+                game_config.audio_volume = step_volume(game_config.audio_volume, button.steps);
+                game_config.audio_muted = false;
+                if matches!(game_config.audio_engine, AudioEngine::Spatial | AudioEngine::Hybrid | AudioEngine::DDSP) {
+                    set_hybrid_volume(game_config.audio_volume);
+                }
+            }
+            SettingsRow::Theme => {
+                if let Some(name) = step_theme(button.steps < 0, &render, &mut theme, &mut clear_color, Some(&preset)) {
+                    preferences.config.theme = name;
+                }
+            }
+            SettingsRow::UiScale => {
+                ui_scale.0 = step_scale(ui_scale.0, button.steps);
+                preferences.config.ui_scale = ui_scale.0;
+            }
+            SettingsRow::FontScale => {
+                font_scale.0 = step_scale(font_scale.0, button.steps);
+                preferences.config.font_scale = font_scale.0;
+            }
+            SettingsRow::Render(setting) => step_setting(setting, button.steps, &mut render, &mut camera),
+        }
+    }
+}
+
+fn screen_buttons(
+    mut buttons: Query<(&Interaction, &ScreenButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut keys: ResMut<Keybindings>,
+    mut preferences: ResMut<Preferences>,
+    mut statuses: Query<&mut Text, With<ReloadStatus>>,
+) {
+    for (interaction, button, mut colour) in &mut buttons {
+        colour.0 = button_color(*interaction);
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            ScreenButton::Resume => next_state.set(GameState::Playing),
+            ScreenButton::Menu => next_state.set(GameState::StartScreen),
+            ScreenButton::Controls => next_state.set(GameState::Controls),
+            ScreenButton::ReloadKeys => {
+                let table = Config::load(preferences.path.as_deref()).keybindings;
+                let (bindings, errors) = Keybindings::from_overrides(&table);
+                let status = match errors.first() {
+                    None => format!("Reloaded {} custom binding(s)", table.len()),
+                    Some(error) => format!("Reloaded; {} skipped ({})", errors.len(), error),
+                };
+                *keys = bindings;
+                preferences.config.keybindings = table;
+                for mut text in &mut statuses {
+                    text.0.clone_from(&status);
+                }
+            }
+        }
+    }
+}
+
+fn refresh_values(
+    game_config: Res<GameConfig>,
+    (render, camera, theme): (Res<CellRenderConfig>, Res<CameraSettings>, Res<Theme>),
+    (ui_scale, font_scale): (Res<UiScale>, Res<FontScale>),
+    mut values: Query<(&RowValue, &mut Text)>,
+) {
+    for (value, mut text) in &mut values {
+        let shown = match value.0 {
+            SettingsRow::AudioEngine => game_config.audio_engine.name().to_string(),
+            SettingsRow::Volume => format!("{:.0}%", game_config.audio_volume * 100.0),
+            SettingsRow::Theme => theme.name.clone(),
+            SettingsRow::UiScale => format!("{:.0}%", ui_scale.0 * 100.0),
+            SettingsRow::FontScale => format!("{:.0}%", font_scale.0 * 100.0),
+            SettingsRow::Render(setting) => setting.value(&render, &camera),
+        };
+        if text.0 != shown {
+            text.0 = shown;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn engine_and_volume_steps_wrap_and_clamp() {
+        assert_eq!(step_engine(AudioEngine::Spatial, 1), AudioEngine::Hybrid);
+        assert_eq!(step_engine(AudioEngine::Spatial, -1), AudioEngine::DungeonSynth);
+        assert_eq!(step_engine(AudioEngine::DungeonSynth, 1), AudioEngine::Spatial);
+        assert_eq!(step_volume(0.7, 1), 0.8);
+        assert_eq!(step_volume(0.05, -1), 0.0);
+        assert_eq!(step_volume(1.95, 3), MAX_VOLUME);
+    }
+}
@@ -0,0 +1,190 @@
+//! Spectrum meter.
+//!
+//! ' (by default) shows a HUD panel with the spectrum of everything the game plays, as bars
+//! from 40 Hz to 16 kHz, over a meter of its RMS and peak level, so the generative audio can
+//! be seen answering the grid. The analysis runs on a thread of its own (see
+//! `audio::spectrum`), and only while the panel is shown. Bars jump up at once and fall back
+//! slowly, like a hardware meter. The panel moves and folds like the other HUD panels.
+
+use bevy::prelude::*;
+use crate::audio::spectrum::{BANDS, FLOOR_DB};
+use crate::audio::{latest_spectrum, set_spectrum_analysis};
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::GameState;
+use crate::theme::Theme;
+use crate::ui::layout::{spawn_panel_title, HudPanelBody};
+use crate::ui::{toast, HudColor, HudPanel};
+use crate::tr;
+
+/// Decibels a bar falls per second once the sound drops
+const FALL_DB_PER_SECOND: f32 = 40.0;
+/// Size of the bars
+const BAR_WIDTH: f32 = 6.0;
+const BAR_GAP: f32 = 2.0;
+const BARS_HEIGHT: f32 = 60.0;
+
+/// Whether the meter is shown, and the levels on show
+#[derive(Resource)]
+pub struct SpectrumMeter {
+    pub enabled: bool,
+    bands: [f32; BANDS],
+    rms_db: f32,
+    peak_db: f32,
+}
+
+impl Default for SpectrumMeter {
+    fn default() -> Self {
+        Self { enabled: false, bands: [FLOOR_DB; BANDS], rms_db: FLOOR_DB, peak_db: FLOOR_DB }
+    }
+}
+
+#[derive(Component)]
+struct SpectrumPanel;
+
+/// The bar of one band
+#[derive(Component)]
+struct SpectrumBar(usize);
+
+#[derive(Component)]
+struct LevelFill;
+
+#[derive(Component)]
+struct LevelText;
+
+/// Height of a bar for `db`, in percent of the full height
+fn bar_percent(db: f32) -> f32 {
+    ((db - FLOOR_DB) / -FLOOR_DB * 100.0).clamp(0.0, 100.0)
+}
+
+/// Follow `target` up at once and down at `FALL_DB_PER_SECOND`
+fn fall_towards(shown: f32, target: f32, seconds: f32) -> f32 {
+    target.max(shown - FALL_DB_PER_SECOND * seconds).max(FLOOR_DB)
+}
+
+pub struct SpectrumMeterPlugin;
+
+impl Plugin for SpectrumMeterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpectrumMeter>()
+            .add_systems(Startup, spawn_spectrum_panel)
+            .add_systems(Update, (toggle_spectrum_meter, update_spectrum_meter).chain().run_if(in_state(GameState::Playing)));
+    }
+}
+
+fn spawn_spectrum_panel(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    let hud = &theme.hud;
+    let bars_width = BANDS as f32 * (BAR_WIDTH + BAR_GAP) - BAR_GAP;
+    commands
+        .spawn((
+            Node { flex_direction: FlexDirection::Column, padding: UiRect::all(Val::Px(8.0)), display: Display::None, ..default() },
+            BackgroundColor(hud.panel.0),
+            HudColor::Panel,
+            HudPanel::Spectrum,
+            SpectrumPanel,
+        ))
+        .with_children(|panel| {
+            spawn_panel_title(panel, &font, HudPanel::Spectrum, hud);
+            panel
+                .spawn((Node { flex_direction: FlexDirection::Column, row_gap: Val::Px(4.0), ..default() }, HudPanelBody(HudPanel::Spectrum)))
+                .with_children(|body| {
+                    body.spawn(Node {
+                        width: Val::Px(bars_width),
+                        height: Val::Px(BARS_HEIGHT),
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::FlexEnd,
+                        column_gap: Val::Px(BAR_GAP),
+                        ..default()
+                    })
+                    .with_children(|bars| {
+                        for band in 0..BANDS {
+                            bars.spawn((
+                                Node { width: Val::Px(BAR_WIDTH), height: Val::Percent(0.0), ..default() },
+                                BackgroundColor(hud.zoom.0),
+                                HudColor::Zoom,
+                                SpectrumBar(band),
+                            ));
+                        }
+                    });
+                    body.spawn((Node { width: Val::Px(bars_width), height: Val::Px(6.0), ..default() }, BackgroundColor(hud.text.0.with_alpha(0.15))))
+                        .with_child((
+                            Node { width: Val::Percent(0.0), height: Val::Percent(100.0), ..default() },
+                            BackgroundColor(hud.volume.0),
+                            HudColor::Volume,
+                            LevelFill,
+                        ));
+                    body.spawn((
+                        Text::new(""),
+                        TextFont { font: font.clone(), font_size: 12.0, ..default() },
+                        TextColor(hud.text.0),
+                        HudColor::Text,
+                        LevelText,
+                    ));
+                });
+        });
+}
+
+fn toggle_spectrum_meter(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
+    mut meter: ResMut<SpectrumMeter>,
+    mut panels: Query<&mut Node, With<SpectrumPanel>>,
+) {
+    if !keys.just_pressed(&keyboard_input, Action::Spectrum) {
+        return;
+    }
+    meter.enabled = !meter.enabled;
+    set_spectrum_analysis(meter.enabled);
+    for mut node in &mut panels {
+        node.display = if meter.enabled { Display::Flex } else { Display::None };
+    }
+    toast(tr!("🎚️ Spectrum meter {}", if meter.enabled { "on" } else { "off" }));
+}
+
+fn update_spectrum_meter(
+    time: Res<Time>,
+    mut meter: ResMut<SpectrumMeter>,
+    mut bars: Query<(&SpectrumBar, &mut Node), Without<LevelFill>>,
+    mut fills: Query<&mut Node, With<LevelFill>>,
+    mut texts: Query<&mut Text, With<LevelText>>,
+) {
+    if !meter.enabled {
+        return;
+    }
+    let seconds = time.delta_secs();
+    let spectrum = latest_spectrum();
+    for (shown, target) in meter.bands.iter_mut().zip(spectrum.bands) {
+        *shown = fall_towards(*shown, target, seconds);
+    }
+    meter.rms_db = fall_towards(meter.rms_db, spectrum.rms_db, seconds);
+    meter.peak_db = fall_towards(meter.peak_db, spectrum.peak_db, seconds);
+
+    for (bar, mut node) in &mut bars {
+        node.height = Val::Percent(bar_percent(meter.bands[bar.0]));
+    }
+    for mut node in &mut fills {
+        node.width = Val::Percent(bar_percent(meter.rms_db));
+    }
+    for mut text in &mut texts {
+        text.0 = if spectrum.peak_db <= FLOOR_DB {
+            tr!("Level: silent")
+        } else {
+            tr!("Level: {:.0} dB RMS, {:.0} dB peak", meter.rms_db, meter.peak_db)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bars_rise_at_once_and_fall_slowly() {
+        assert_eq!(bar_percent(FLOOR_DB), 0.0);
+        assert_eq!(bar_percent(0.0), 100.0);
+        assert_eq!(bar_percent(FLOOR_DB / 2.0), 50.0);
+        assert_eq!(fall_towards(-60.0, -10.0, 0.1), -10.0);
+        assert_eq!(fall_towards(-10.0, -60.0, 0.5), -30.0);
+        assert_eq!(fall_towards(-79.0, FLOOR_DB, 1.0), FLOOR_DB);
+    }
+}
@@ -0,0 +1,332 @@
+//! Speed slider.
+//!
+//! A HUD slider at the bottom centre of the screen for the simulation speed, bound to
+//! `GameStats::update_interval` and labelled in generations per second. The scale is
+//! logarithmic between `max_update_interval` (left) and `min_update_interval` (right), so
+//! each stretch of the track multiplies the speed by the same factor, as the +/- keys do.
+//! Clicking the track jumps the knob there and dragging moves it, even past the ends of the
+//! track; the knob follows speed changes from the keys, rule presets and loaded sessions.
+//!
+//! Below the slider sits the run control: Run advances exactly `GameStats::run_length`
+//! generations and pauses (as does Enter by default), and the buttons either side change the
+//! count. While a run is under way the button shows what's left and clicking it stops early.
+//! At either end of the row, Back and Step move a paused game one generation backwards
+//! (through the `RewindBuffer`) or forwards, like Backspace and S.
+
+use bevy::prelude::*;
+use bevy::ui::{RelativeCursorPosition, UiSystem};
+use crate::game_config::GameStats;
+use crate::infinite_grid::{InfiniteGrid, RewindBuffer};
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::{GameState, InGame};
+use crate::theme::Theme;
+use crate::ui::{toast, HudColor, PointerCapture, UiState};
+use crate::tr;
+
+const TRACK_WIDTH: f32 = 200.0;
+const KNOB_WIDTH: f32 = 10.0;
+/// Wide enough for the run buttons with a long run under way
+const PANEL_WIDTH: f32 = 340.0;
+
+/// Longest run the buttons set
+pub const MAX_RUN_LENGTH: u64 = 1_000_000;
+
+/// Steps of the buttons either side of Run
+const RUN_STEPS: [i64; 4] = [-10, -1, 1, 10];
+
+const BUTTON_IDLE: Color = Color::srgba(0.25, 0.25, 0.3, 0.9);
+const BUTTON_HOVER: Color = Color::srgba(0.35, 0.35, 0.45, 0.9);
+
+/// Position along the track, 0 at the slow end and 1 at the fast end, of `interval`
+pub fn slider_position(interval: f64, min: f64, max: f64) -> f32 {
+    if max <= min {
+        return 1.0;
+    }
+    let interval = interval.clamp(min, max);
+    ((max / interval).ln() / (max / min).ln()) as f32
+}
+
+/// Update interval at `position` along the track; the inverse of `slider_position`
+pub fn slider_interval(position: f32, min: f64, max: f64) -> f64 {
+    if max <= min {
+        return min;
+    }
+    (max * (min / max).powf(position.clamp(0.0, 1.0) as f64)).clamp(min, max)
+}
+
+/// Generations per second, with more decimals the slower it is
+pub fn speed_label(interval: f64) -> String {
+    let rate = 1.0 / interval.max(f64::EPSILON);
+    if rate < 10.0 {
+        format!("Speed: {:.1} gen/s", rate)
+    } else {
+        format!("Speed: {:.0} gen/s", rate)
+    }
+}
+
+/// Run length after pressing the button for `step`, kept to 1..=`MAX_RUN_LENGTH`
+pub fn step_run_length(length: u64, step: i64) -> u64 {
+    length.saturating_add_signed(step).clamp(1, MAX_RUN_LENGTH)
+}
+
+/// Whether a drag that started on the track is under way
+#[derive(Resource, Default)]
+pub struct SpeedSlider {
+    pub dragging: bool,
+}
+
+#[derive(Component)]
+pub struct SpeedPanel;
+
+#[derive(Component)]
+struct SpeedTrack;
+
+#[derive(Component)]
+struct SpeedKnob;
+
+#[derive(Component)]
+struct SpeedLabel;
+
+/// A button of the run control: changes the run length by its step, starts and stops a run,
+/// or steps a generation back or forward
+#[derive(Component, Clone, Copy)]
+enum RunButton {
+    Length(i64),
+    Run,
+    Back,
+    Forward,
+}
+
+pub struct SpeedSliderPlugin;
+
+impl Plugin for SpeedSliderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpeedSlider>()
+            .add_systems(OnEnter(InGame), spawn_speed_slider)
+            .add_systems(OnExit(InGame), despawn_speed_slider)
+            .add_systems(PreUpdate, speed_slider_capture.after(UiSystem::Focus).run_if(in_state(GameState::Playing)))
+            .add_systems(
+                Update,
+                (drag_speed_slider, run_buttons, update_speed_slider).chain().run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+fn spawn_speed_slider(mut commands: Commands, theme: Res<Theme>, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                left: Val::Percent(50.0),
+                margin: UiRect::left(Val::Px(-PANEL_WIDTH / 2.0)),
+                width: Val::Px(PANEL_WIDTH),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(theme.hud.panel.0),
+            HudColor::Panel,
+            Visibility::Hidden,
+            RelativeCursorPosition::default(),
+            SpeedPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                text(14.0),
+                TextColor(theme.hud.text.0),
+                HudColor::Text,
+                SpeedLabel,
+            ));
+            parent
+                .spawn((
+                    Node { width: Val::Px(TRACK_WIDTH), height: Val::Px(14.0), justify_content: JustifyContent::Center, ..default() },
+                    Interaction::default(),
+                    RelativeCursorPosition::default(),
+                    SpeedTrack,
+                ))
+                .with_children(|track| {
+                    // The groove, and the knob riding on it
+                    track.spawn((
+                        Node { width: Val::Percent(100.0), height: Val::Px(4.0), margin: UiRect::vertical(Val::Auto), ..default() },
+                        BackgroundColor(Color::srgba(0.5, 0.5, 0.55, 0.6)),
+                    ));
+                    track.spawn((
+                        Node { position_type: PositionType::Absolute, width: Val::Px(KNOB_WIDTH), height: Val::Percent(100.0), ..default() },
+                        BackgroundColor(theme.hud.zoom.0),
+                        HudColor::Zoom,
+                        SpeedKnob,
+                    ));
+                });
+            parent
+                .spawn(Node { flex_direction: FlexDirection::Row, column_gap: Val::Px(4.0), ..default() })
+                .with_children(|row| {
+                    let (before, after) = RUN_STEPS.split_at(2);
+                    let buttons = [RunButton::Back].into_iter()
+                        .chain(before.iter().map(|&step| RunButton::Length(step)))
+                        .chain([RunButton::Run])
+                        .chain(after.iter().map(|&step| RunButton::Length(step)))
+                        .chain([RunButton::Forward]);
+                    for button in buttons {
+                        let label = match button {
+                            RunButton::Length(step) => format!("{:+}", step),
+                            RunButton::Run => String::new(),
+                            RunButton::Back => "< Back".to_string(),
+                            RunButton::Forward => "Step >".to_string(),
+                        };
+                        row.spawn((
+                            Button,
+                            Node { padding: UiRect::axes(Val::Px(5.0), Val::Px(1.0)), ..default() },
+                            BackgroundColor(BUTTON_IDLE),
+                            button,
+                        ))
+                        .with_child((Text::new(label), text(13.0), TextColor(Color::WHITE)));
+                    }
+                });
+        });
+}
+
+fn despawn_speed_slider(mut commands: Commands, panels: Query<Entity, With<SpeedPanel>>, mut slider: ResMut<SpeedSlider>) {
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+    slider.dragging = false;
+}
+
+/// Keep clicks and drags on the slider from painting the grid underneath
+fn speed_slider_capture(
+    panels: Query<(&RelativeCursorPosition, &Visibility), With<SpeedPanel>>,
+    slider: Res<SpeedSlider>,
+    mut capture: ResMut<PointerCapture>,
+) {
+    let over = panels.iter().any(|(cursor, visibility)| *visibility != Visibility::Hidden && cursor.mouse_over());
+    capture.captured |= over || slider.dragging;
+}
+
+fn drag_speed_slider(
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    tracks: Query<(&Interaction, &RelativeCursorPosition), With<SpeedTrack>>,
+    mut slider: ResMut<SpeedSlider>,
+    mut game_stats: ResMut<GameStats>,
+) {
+    let Ok((interaction, cursor)) = tracks.get_single() else { return };
+    if *interaction == Interaction::Pressed && mouse_button_input.just_pressed(MouseButton::Left) {
+        slider.dragging = true;
+    }
+    if !mouse_button_input.pressed(MouseButton::Left) {
+        slider.dragging = false;
+    }
+    if !slider.dragging {
+        return;
+    }
+    // Measured from the knob's centre, so the knob stays under the pointer
+    let Some(normalized) = cursor.normalized else { return };
+    let span = TRACK_WIDTH - KNOB_WIDTH;
+    let position = ((normalized.x * TRACK_WIDTH - KNOB_WIDTH / 2.0) / span).clamp(0.0, 1.0);
+    let interval = slider_interval(position, game_stats.min_update_interval, game_stats.max_update_interval);
+    if interval != game_stats.update_interval {
+        game_stats.update_interval = interval;
+    }
+}
+
+fn run_buttons(
+    mut buttons: Query<(&Interaction, &RunButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut game_stats: ResMut<GameStats>,
+    mut grid: ResMut<InfiniteGrid>,
+    mut rewind: ResMut<RewindBuffer>,
+) {
+    for (interaction, button, mut colour) in &mut buttons {
+        colour.0 = match interaction {
+            Interaction::None => BUTTON_IDLE,
+            Interaction::Hovered | Interaction::Pressed => BUTTON_HOVER,
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *button {
+            RunButton::Length(step) => game_stats.run_length = step_run_length(game_stats.run_length, step),
+            RunButton::Run if game_stats.run_remaining.is_some() => game_stats.stop(),
+            RunButton::Run => {
+                let generations = game_stats.run_length;
+                game_stats.start_run(generations);
+                toast(tr!("⏱️ Running {} generations", generations));
+            }
+            RunButton::Back => {
+                if !game_stats.step_back(&mut grid, &mut rewind) {
+                    toast(tr!("⏪ No earlier generation to step back to"));
+                }
+            }
+            RunButton::Forward => game_stats.step(),
+        }
+    }
+}
+
+fn update_speed_slider(
+    (game_stats, ui_state, keys): (Res<GameStats>, Res<UiState>, Res<Keybindings>),
+    mut panels: Query<&mut Visibility, With<SpeedPanel>>,
+    mut knobs: Query<&mut Node, With<SpeedKnob>>,
+    mut labels: Query<&mut Text, With<SpeedLabel>>,
+    run_buttons: Query<(&RunButton, &Children)>,
+    mut texts: Query<&mut Text, Without<SpeedLabel>>,
+) {
+    for mut visibility in panels.iter_mut() {
+        visibility.set_if_neq(if ui_state.hud_visible { Visibility::Visible } else { Visibility::Hidden });
+    }
+    let position = slider_position(game_stats.update_interval, game_stats.min_update_interval, game_stats.max_update_interval);
+    let left = Val::Px(position * (TRACK_WIDTH - KNOB_WIDTH));
+    for mut knob in knobs.iter_mut() {
+        if knob.left != left {
+            knob.left = left;
+        }
+    }
+    let label = speed_label(game_stats.update_interval);
+    for mut text in labels.iter_mut() {
+        if text.0 != label {
+            text.0.clone_from(&label);
+        }
+    }
+    let run_label = match game_stats.run_remaining {
+        Some(left) => format!("Stop ({} left)", left),
+        None => format!("Run {} ({})", game_stats.run_length, keys.label(Action::RunGenerations)),
+    };
+    for (button, children) in &run_buttons {
+        if !matches!(button, RunButton::Run) {
+            continue;
+        }
+        let mut labels = texts.iter_many_mut(children);
+        while let Some(mut text) = labels.fetch_next() {
+            if text.0 != run_label {
+                text.0.clone_from(&run_label);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn track_is_logarithmic_in_the_interval() {
+        let (min, max) = (0.01, 2.0);
+        assert_eq!(slider_position(max, min, max), 0.0);
+        assert_eq!(slider_position(min, min, max), 1.0);
+        // Halfway along is the geometric mean of the limits
+        assert!((slider_interval(0.5, min, max) - (min * max).sqrt()).abs() < 1e-9);
+        for interval in [0.013, 0.2, 0.5, 1.7] {
+            let position = slider_position(interval, min, max);
+            assert!((slider_interval(position, min, max) - interval).abs() < 1e-6);
+        }
+        assert_eq!(slider_interval(3.0, min, max), min);
+        assert_eq!(speed_label(0.5), "Speed: 2.0 gen/s");
+        assert_eq!(speed_label(0.04), "Speed: 25 gen/s");
+        assert_eq!(step_run_length(5, -10), 1);
+        assert_eq!(step_run_length(100, 10), 110);
+        assert_eq!(step_run_length(MAX_RUN_LENGTH, 1), MAX_RUN_LENGTH);
+    }
+}
@@ -0,0 +1,242 @@
+//! Split-screen rule comparison.
+//!
+//! \ (by default) copies the main grid into a comparison grid running the next rule in the
+//! start-screen order and splits the window down the middle: the left half is the game as
+//! usual, the right half shows the comparison grid over the same cells, so both follow the
+//! one camera. Shift+\ moves the comparison on to the following rule, reseeding it from the
+//! main grid, and \ again closes it.
+//!
+//! The comparison grid is a `GridLayers` layer marked `split`, so it steps with the main
+//! grid. Its sprites are on their own render layer, seen only by a second camera covering
+//! the right half; the main camera still covers the whole window (and the HUD), and the
+//! pointer is kept off the right half so nothing is drawn on the hidden part of the main grid.
+
+use bevy::prelude::*;
+use bevy::render::camera::Viewport;
+use bevy::render::view::RenderLayers;
+use bevy::ui::{FocusPolicy, UiSystem};
+use bevy::window::PrimaryWindow;
+use crate::camera::GameCamera;
+use crate::infinite_grid::InfiniteGrid;
+use crate::keybindings::{Action, Keybindings};
+use crate::layers::{GridLayers, LayerSprite};
+use crate::start_screen::{GameState, InGame, RuleType};
+use crate::theme::Theme;
+use crate::ui::{toast, HudColor, PointerCapture};
+use crate::tr;
+use crate::GameConfig;
+
+/// Render layer of the comparison grid's sprites
+pub const SPLIT_RENDER_LAYER: usize = 1;
+
+#[derive(Component)]
+struct SplitCamera;
+
+/// The camera, the divider and the rule labels, gone when the split screen closes
+#[derive(Component)]
+struct SplitScreenPart;
+
+#[derive(Component)]
+struct SplitLabel {
+    right: bool,
+}
+
+/// The rule after `rule` in the start-screen order, skipping `skip`
+pub fn next_rule(rule: RuleType, skip: RuleType) -> RuleType {
+    let rules = RuleType::all();
+    let start = rules.iter().position(|&r| r == rule).unwrap_or(0);
+    (1..=rules.len()).map(|offset| rules[(start + offset) % rules.len()]).find(|&r| r != skip).unwrap_or(rule)
+}
+
+/// The right half of a window `width` physical pixels wide, as a viewport
+fn right_half(width: u32, height: u32) -> Viewport {
+    let left = width / 2;
+    Viewport {
+        physical_position: UVec2::new(left, 0),
+        physical_size: UVec2::new((width - left).max(1), height.max(1)),
+        ..default()
+    }
+}
+
+pub struct SplitScreenPlugin;
+
+impl Plugin for SplitScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(InGame), close_split_screen)
+            // Before Update, so a click on the right half never reaches the painting tools
+            .add_systems(PreUpdate, split_capture.after(UiSystem::Focus).run_if(in_state(GameState::Playing)))
+            .add_systems(Update, (split_controls, follow_main_camera, update_split_labels).chain().run_if(in_state(GameState::Playing)));
+    }
+}
+
+fn split_controls(
+    (keyboard_input, keys): (Res<ButtonInput<KeyCode>>, Res<Keybindings>),
+    (grid, game_config): (Res<InfiniteGrid>, Res<GameConfig>),
+    mut layers: ResMut<GridLayers>,
+    mut commands: Commands,
+    (asset_server, theme): (Res<AssetServer>, Res<Theme>),
+    split_entities: Query<Entity, With<SplitScreenPart>>,
+    layer_sprites: Query<Entity, With<LayerSprite>>,
+) {
+    if !keys.just_pressed(&keyboard_input, Action::SplitScreen) {
+        return;
+    }
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let current = game_config.current_rule;
+    match layers.split_index() {
+        Some(index) if shift => {
+            // Onto the next rule, from the main grid as it is now
+            let layer = &mut layers.layers[index];
+            layer.rule = next_rule(layer.rule, current);
+            layer.grid = grid.clone();
+            layer.name = layer.rule.name().to_string();
+            toast(tr!("🪟 Split screen: {} vs {}", current.name(), layer.rule.name()));
+        }
+        Some(index) => {
+            layers.remove(index);
+            // Later layers' sprites are indexed one too far now; they are redrawn next frame
+            for entity in split_entities.iter().chain(&layer_sprites) {
+                commands.entity(entity).despawn_recursive();
+            }
+            toast(tr!("🪟 Split screen off"));
+        }
+        None => {
+            let rule = next_rule(current, current);
+            let index = layers.add(rule.name(), grid.clone(), rule);
+            layers.layers[index].split = true;
+            spawn_split_screen(&mut commands, &asset_server, &theme);
+            toast(tr!("🪟 Split screen: {} vs {}", current.name(), rule.name()));
+        }
+    }
+}
+
+fn spawn_split_screen(commands: &mut Commands, asset_server: &AssetServer, theme: &Theme) {
+    commands.spawn((
+        Camera2d,
+        // Drawn over the main camera's output, which a clear would wipe along with the HUD
+        Camera { order: 1, clear_color: ClearColorConfig::None, ..default() },
+        RenderLayers::layer(SPLIT_RENDER_LAYER),
+        SplitCamera,
+        SplitScreenPart,
+    ));
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(50.0),
+            width: Val::Px(2.0),
+            height: Val::Percent(100.0),
+            margin: UiRect::left(Val::Px(-1.0)),
+            ..default()
+        },
+        BackgroundColor(theme.hud.text.0.with_alpha(0.5)),
+        FocusPolicy::Pass,
+        SplitScreenPart,
+    ));
+    for right in [false, true] {
+        commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(if right { 50.0 } else { 0.0 }),
+                    width: Val::Percent(50.0),
+                    bottom: Val::Px(160.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                FocusPolicy::Pass,
+                SplitScreenPart,
+            ))
+            .with_children(|row| {
+                row.spawn((
+                    Node { padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)), ..default() },
+                    BackgroundColor(theme.hud.panel.0),
+                    HudColor::Panel,
+                ))
+                .with_child((
+                    Text::new(""),
+                    TextFont { font: font.clone(), font_size: 16.0, ..default() },
+                    TextColor(theme.hud.text.0),
+                    HudColor::Text,
+                    SplitLabel { right },
+                ));
+            });
+    }
+}
+
+fn close_split_screen(mut commands: Commands, split_entities: Query<Entity, With<SplitScreenPart>>) {
+    for entity in &split_entities {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Keep clicks on the right half from drawing on the main grid hidden behind it
+fn split_capture(
+    cameras: Query<(), With<SplitCamera>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut capture: ResMut<PointerCapture>,
+) {
+    let Ok(window) = windows.get_single() else { return };
+    if !cameras.is_empty() && window.cursor_position().is_some_and(|cursor| cursor.x > window.width() / 2.0) {
+        capture.captured = true;
+    }
+}
+
+type MainCameraQuery<'w, 's> = Query<'w, 's, (&'static Transform, &'static OrthographicProjection, &'static Camera), (With<GameCamera>, Without<SplitCamera>)>;
+
+/// Show the right half the same cells the left half shows, through the same kind of target
+fn follow_main_camera(
+    main: MainCameraQuery,
+    mut split: Query<(&mut Transform, &mut OrthographicProjection, &mut Camera), With<SplitCamera>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let (Ok((main_transform, main_projection, main_camera)), Ok(window)) = (main.get_single(), windows.get_single()) else { return };
+    for (mut transform, mut projection, mut camera) in &mut split {
+        // The left half is centred a quarter of the window left of the main camera
+        let offset = Vec3::new(-window.width() / 4.0 * main_projection.scale, 0.0, 0.0);
+        *transform = main_transform.with_translation(main_transform.translation + offset);
+        projection.scale = main_projection.scale;
+        // Cameras sharing a window have to agree on HDR, which bloom switches on the main one
+        if camera.hdr != main_camera.hdr {
+            camera.hdr = main_camera.hdr;
+        }
+        let viewport = right_half(window.physical_width(), window.physical_height());
+        if camera.viewport.as_ref().is_none_or(|current| current.physical_position != viewport.physical_position || current.physical_size != viewport.physical_size) {
+            camera.viewport = Some(viewport);
+        }
+    }
+}
+
+fn update_split_labels(
+    (grid, layers, game_config): (Res<InfiniteGrid>, Res<GridLayers>, Res<GameConfig>),
+    mut labels: Query<(&SplitLabel, &mut Text)>,
+) {
+    let Some(layer) = layers.split_index().map(|index| &layers.layers[index]) else { return };
+    for (label, mut text) in &mut labels {
+        let shown = if label.right {
+            tr!("{}: {} cells", layer.rule.name(), layer.grid.population())
+        } else {
+            tr!("{}: {} cells", game_config.current_rule.name(), grid.population())
+        };
+        if text.0 != shown {
+            text.0 = shown;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comparison_rule_differs_and_viewport_covers_the_right_half() {
+        for rule in RuleType::all() {
+            assert_ne!(next_rule(rule, rule), rule);
+            let following = next_rule(rule, RuleType::all()[0]);
+            assert_ne!(following, RuleType::all()[0]);
+        }
+        let viewport = right_half(1201, 800);
+        assert_eq!(viewport.physical_position, UVec2::new(600, 0));
+        assert_eq!(viewport.physical_size, UVec2::new(601, 800));
+    }
+}
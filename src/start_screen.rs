@@ -1,4 +1,14 @@
 use bevy::prelude::*;
+use crate::session::{has_saved_session, PendingSession, SessionSnapshot, SESSION_PATH};
+use crate::theme::Theme;
+use crate::title_soup::TitleSoup;
+use crate::start_seed::spawn_seed_picker;
+use crate::audio_picker::spawn_audio_picker;
+use crate::tutorial::spawn_tutorial_button;
+use crate::rule_preview::{RulePreview, PREVIEW_HEIGHT, PREVIEW_WIDTH};
+use crate::ui::toast;
+use crate::locale::translate;
+use crate::tr;
 
 /// Marker component for start screen entities
 #[derive(Component)]
@@ -24,6 +34,9 @@ pub enum RuleType {
     Coral,        // Coral - Growth pattern automaton
     Gnarl,        // Gnarl - Chaotic growth
     Replicator,   // Replicator - Self-replicating patterns
+    /// Any other Life-like rule, as bitmasks of the neighbour counts that give birth and
+    /// survival (bit n set = n neighbours); built in the rule editor or read from a pattern
+    Custom { birth: u16, survive: u16 },
 }
 
 impl RuleType {
@@ -54,6 +67,7 @@ impl RuleType {
             RuleType::Coral => "Coral",
             RuleType::Gnarl => "Gnarl",
             RuleType::Replicator => "Replicator",
+            RuleType::Custom { .. } => "Custom rule",
         }
     }
 
@@ -69,8 +83,71 @@ impl RuleType {
             RuleType::Coral => "B3/S45678 - Coral-like growth structures",
             RuleType::Gnarl => "B1/S1 - Chaotic explosive growth",
             RuleType::Replicator => "B1357/S1357 - Perfect self-replication",
+            RuleType::Custom { .. } => "Life-like rule of your own",
         }
     }
+
+    /// Rule as written in pattern file headers (Golly/LifeWiki notation)
+    pub fn rulestring(&self) -> String {
+        match self {
+            RuleType::Brian => "B2/S/C3".to_string(),
+            RuleType::WireWorld => "WireWorld".to_string(),
+            RuleType::Immigration => "Immigration".to_string(),
+            _ => {
+                let (birth, survive) = self.life_masks().unwrap_or_default();
+                let digits = |mask: u16| (0..=8).filter(|n| mask & (1 << n) != 0).map(|n: u32| char::from_digit(n, 10).unwrap_or('?')).collect::<String>();
+                format!("B{}/S{}", digits(birth), digits(survive))
+            }
+        }
+    }
+
+    /// Birth and survival masks of the two-state Life-like rules, `None` for the multi-state ones
+    pub fn life_masks(&self) -> Option<(u16, u16)> {
+        match *self {
+            RuleType::Conway => Some((1 << 3, 1 << 2 | 1 << 3)),
+            RuleType::HighLife => Some((1 << 3 | 1 << 6, 1 << 2 | 1 << 3)),
+            RuleType::Seeds => Some((1 << 2, 0)),
+            RuleType::Mazectric => Some((1 << 3, 0b1_1110)),
+            RuleType::Coral => Some((1 << 3, 0b1_1111_0000)),
+            RuleType::Gnarl => Some((1 << 1, 1 << 1)),
+            RuleType::Replicator => Some((0b1010_1010, 0b1010_1010)),
+            RuleType::Custom { birth, survive } => Some((birth, survive)),
+            RuleType::Brian | RuleType::WireWorld | RuleType::Immigration => None,
+        }
+    }
+
+    /// The Life-like rule with these masks: one of the named rules if it matches, otherwise
+    /// `Custom`. Counts above 8 are dropped.
+    pub fn life(birth: u16, survive: u16) -> RuleType {
+        let (birth, survive) = (birth & 0x1FF, survive & 0x1FF);
+        Self::all().into_iter().find(|rule| rule.life_masks() == Some((birth, survive))).unwrap_or(RuleType::Custom { birth, survive })
+    }
+
+    /// Recognise a rulestring from a pattern file. Accepts B/S notation in either order, the
+    /// older S/B form (`23/3`), Generations notation for Brian's Brain and Golly's names for
+    /// the multi-state rules.
+    pub fn from_rulestring(rule: &str) -> Option<RuleType> {
+        let rule: String = rule.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase();
+        match rule.as_str() {
+            "WIREWORLD" => return Some(RuleType::WireWorld),
+            "IMMIGRATION" => return Some(RuleType::Immigration),
+            "BRIANSBRAIN" | "B2/S/C3" | "/2/3" | "B2/S/3" => return Some(RuleType::Brian),
+            _ => {}
+        }
+
+        let parts: Vec<&str> = rule.split('/').collect();
+        let [first, second] = parts.as_slice() else { return None };
+        let mask = |s: &str| -> Option<u16> {
+            s.chars().try_fold(0u16, |mask, c| c.to_digit(10).filter(|&n| n <= 8).map(|n| mask | 1 << n))
+        };
+        let (birth, survival) = match (first.strip_prefix('B'), second.strip_prefix('S'), first.strip_prefix('S'), second.strip_prefix('B')) {
+            (Some(b), Some(s), _, _) | (_, _, Some(s), Some(b)) => (mask(b)?, mask(s)?),
+            // Legacy survival/birth order without letters
+            _ => (mask(second)?, mask(first)?),
+        };
+        // B0 rules would fill the infinite plane, which the sparse grid can't step
+        (birth & 1 == 0).then(|| Self::life(birth, survival))
+    }
 }
 
 impl Default for SelectedRule {
@@ -88,6 +165,34 @@ pub enum GameState {
     #[default]
     StartScreen,
     Playing,
+    /// The settings screen, over a paused game
+    Settings,
+    /// The key rebinding screen, opened from the settings screen
+    Controls,
+    /// The command palette, over a paused game
+    Palette,
+    /// The console, over a paused game
+    Console,
+    /// Asking whether to go ahead with clearing or resetting, over a paused game
+    Confirm,
+    /// The full list of keys, over a paused game
+    Help,
+}
+
+/// Set while a game is under way, whether playing or paused in a menu. Game entities and
+/// resources are set up on entering it and torn down on leaving it, so a trip to a menu keeps
+/// the game as it was; systems that only make sense while playing run in `GameState::Playing`
+/// instead.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct InGame;
+
+impl ComputedStates for InGame {
+    type SourceStates = GameState;
+
+    fn compute(state: GameState) -> Option<Self> {
+        matches!(state, GameState::Playing | GameState::Settings | GameState::Controls | GameState::Palette | GameState::Console | GameState::Confirm | GameState::Help)
+            .then_some(InGame)
+    }
 }
 
 /// Marker components for UI elements
@@ -107,7 +212,13 @@ pub struct RightArrowButton;
 pub struct StartGameButton;
 
 /// Setup the start screen UI
-pub fn setup_start_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn setup_start_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    theme: Res<Theme>,
+    (soup, preview): (Option<Res<TitleSoup>>, Option<Res<RulePreview>>),
+) {
+    let palette = &theme.start_screen;
     println!("Setting up start screen...");
     
     // Load the custom font
@@ -123,18 +234,34 @@ pub fn setup_start_screen(mut commands: Commands, asset_server: Res<AssetServer>
             flex_direction: FlexDirection::Column,
             ..default()
         },
-        BackgroundColor(Color::srgb(0.05, 0.0, 0.02)), // Very dark red tint
+        BackgroundColor(palette.background.0),
         StartScreenEntity,
     )).with_children(|parent| {
+        // The ambient soup, stretched behind everything else
+        if let Some(soup) = &soup {
+            parent.spawn((
+                ImageNode::new(soup.image.clone()),
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+            ));
+        }
+
+        // Engine, volume and mute, in the corner
+        spawn_audio_picker(parent, &custom_font, palette);
+
         // Game title
         parent.spawn((
-            Text::new("GAME OF DEATH"),
+            Text::new(tr!("GAME OF DEATH")),
             TextFont {
                 font: custom_font.clone(),
                 font_size: 72.0,
                 ..default()
             },
-            TextColor(Color::srgb(0.8, 0.1, 0.1)), // Blood red
+            TextColor(palette.title.0),
             Node {
                 margin: UiRect::bottom(Val::Px(10.0)),
                 ..default()
@@ -143,13 +270,13 @@ pub fn setup_start_screen(mut commands: Commands, asset_server: Res<AssetServer>
         
         // Subtitle
         parent.spawn((
-            Text::new("a game of death"),
+            Text::new(tr!("a game of death")),
             TextFont {
                 font: custom_font.clone(),
                 font_size: 24.0,
                 ..default()
             },
-            TextColor(Color::srgb(0.4, 0.0, 0.0)), // Dark red
+            TextColor(palette.subtitle.0),
             Node {
                 margin: UiRect::bottom(Val::Px(50.0)),
                 ..default()
@@ -165,18 +292,18 @@ pub fn setup_start_screen(mut commands: Commands, asset_server: Res<AssetServer>
                 border: UiRect::all(Val::Px(2.0)),
                 ..default()
             },
-            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
-            BorderColor(Color::srgb(0.3, 0.0, 0.0)),
+            BackgroundColor(palette.panel.0),
+            BorderColor(palette.panel_border.0),
         )).with_children(|parent| {
             // Rule selection title
             parent.spawn((
-                Text::new("SELECT GAME MODE"),
+                Text::new(tr!("SELECT GAME MODE")),
                 TextFont {
                     font: custom_font.clone(),
                     font_size: 28.0,
                     ..default()
                 },
-                TextColor(Color::srgb(0.7, 0.0, 0.0)),
+                TextColor(palette.heading.0),
                 Node {
                     margin: UiRect::bottom(Val::Px(30.0)),
                     ..default()
@@ -206,8 +333,8 @@ pub fn setup_start_screen(mut commands: Commands, asset_server: Res<AssetServer>
                         margin: UiRect::right(Val::Px(20.0)),
                         ..default()
                     },
-                    BackgroundColor(Color::srgba(0.1, 0.0, 0.0, 0.9)),
-                    BorderColor(Color::srgb(0.2, 0.0, 0.0)),
+                    BackgroundColor(palette.button.0),
+                    BorderColor(palette.button_border.0),
                     LeftArrowButton,
                 )).with_children(|parent| {
                     parent.spawn((
@@ -217,7 +344,7 @@ pub fn setup_start_screen(mut commands: Commands, asset_server: Res<AssetServer>
                             font_size: 24.0,
                             ..default()
                         },
-                        TextColor(Color::srgb(0.9, 0.2, 0.2)),
+                        TextColor(palette.accent.0),
                     ));
                 });
 
@@ -239,7 +366,7 @@ pub fn setup_start_screen(mut commands: Commands, asset_server: Res<AssetServer>
                             font_size: 24.0,
                             ..default()
                         },
-                        TextColor(Color::srgb(0.9, 0.2, 0.2)),
+                        TextColor(palette.accent.0),
                         Node {
                             margin: UiRect::bottom(Val::Px(8.0)),
                             ..default()
@@ -249,15 +376,30 @@ pub fn setup_start_screen(mut commands: Commands, asset_server: Res<AssetServer>
                     
                     // Rule description
                     parent.spawn((
-                        Text::new("Classic B3/S23 - Born with 3, survives with 2-3"),
+                        Text::new(translate(RuleType::Conway.description())),
                         TextFont {
                             font: custom_font.clone(),
                             font_size: 16.0,
                             ..default()
                         },
-                        TextColor(Color::srgb(0.5, 0.1, 0.1)),
+                        TextColor(palette.description.0),
                         RuleDescriptionText,
                     ));
+
+                    // The rule running in miniature
+                    if let Some(preview) = &preview {
+                        parent.spawn((
+                            ImageNode::new(preview.image.clone()),
+                            Node {
+                                width: Val::Px(PREVIEW_WIDTH as f32 * 3.0),
+                                height: Val::Px(PREVIEW_HEIGHT as f32 * 3.0),
+                                margin: UiRect::top(Val::Px(12.0)),
+                                border: UiRect::all(Val::Px(1.0)),
+                                ..default()
+                            },
+                            BorderColor(palette.panel_border.0),
+                        ));
+                    }
                 });
 
                 // Right arrow button
@@ -272,8 +414,8 @@ pub fn setup_start_screen(mut commands: Commands, asset_server: Res<AssetServer>
                         margin: UiRect::left(Val::Px(20.0)),
                         ..default()
                     },
-                    BackgroundColor(Color::srgba(0.1, 0.0, 0.0, 0.9)),
-                    BorderColor(Color::srgb(0.2, 0.0, 0.0)),
+                    BackgroundColor(palette.button.0),
+                    BorderColor(palette.button_border.0),
                     RightArrowButton,
                 )).with_children(|parent| {
                     parent.spawn((
@@ -283,11 +425,14 @@ pub fn setup_start_screen(mut commands: Commands, asset_server: Res<AssetServer>
                             font_size: 24.0,
                             ..default()
                         },
-                        TextColor(Color::srgb(0.9, 0.2, 0.2)),
+                        TextColor(palette.accent.0),
                     ));
                 });
             });
 
+            // What the game starts from
+            spawn_seed_picker(parent, &custom_font, palette);
+
             // Start game button
             parent.spawn((
                 Button,
@@ -300,20 +445,23 @@ pub fn setup_start_screen(mut commands: Commands, asset_server: Res<AssetServer>
                     border: UiRect::all(Val::Px(2.0)),
                     ..default()
                 },
-                BackgroundColor(Color::srgba(0.2, 0.0, 0.0, 0.9)),
-                BorderColor(Color::srgb(0.4, 0.0, 0.0)),
+                BackgroundColor(palette.start_button.0),
+                BorderColor(palette.start_button_border.0),
                 StartGameButton,
             )).with_children(|parent| {
                 parent.spawn((
-                    Text::new("START GAME"),
+                    Text::new(tr!("START GAME")),
                     TextFont {
                         font: custom_font.clone(),
                         font_size: 22.0,
                         ..default()
                     },
-                    TextColor(Color::srgb(1.0, 0.3, 0.3)),
+                    TextColor(palette.start_text.0),
                 ));
             });
+
+            // Guided first game
+            spawn_tutorial_button(parent, &custom_font, palette);
         });
 
         // Instructions at bottom
@@ -329,39 +477,80 @@ pub fn setup_start_screen(mut commands: Commands, asset_server: Res<AssetServer>
             },
         )).with_children(|parent| {
             parent.spawn((
-                Text::new("Arrow keys or buttons to change game mode"),
+                Text::new(tr!("Arrow keys or buttons to change game mode")),
                 TextFont {
                     font: custom_font.clone(),
                     font_size: 16.0,
                     ..default()
                 },
-                TextColor(Color::srgb(0.4, 0.1, 0.1)),
+                TextColor(palette.hint.0),
                 Node {
                     margin: UiRect::bottom(Val::Px(5.0)),
                     ..default()
                 },
             ));
             parent.spawn((
-                Text::new("ENTER or START GAME button to begin"),
+                Text::new(tr!("Up/Down and -/+ to choose the starting grid")),
                 TextFont {
                     font: custom_font.clone(),
                     font_size: 14.0,
                     ..default()
                 },
-                TextColor(Color::srgb(0.3, 0.05, 0.05)),
+                TextColor(palette.hint_faint.0),
                 Node {
                     margin: UiRect::bottom(Val::Px(5.0)),
                     ..default()
                 },
             ));
             parent.spawn((
-                Text::new("ESC to quit"),
+                Text::new(tr!("ENTER or START GAME button to begin")),
+                TextFont {
+                    font: custom_font.clone(),
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(palette.hint_faint.0),
+                Node {
+                    margin: UiRect::bottom(Val::Px(5.0)),
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                Text::new(tr!("T for a guided tutorial")),
+                TextFont {
+                    font: custom_font.clone(),
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(palette.hint_faint.0),
+                Node {
+                    margin: UiRect::bottom(Val::Px(5.0)),
+                    ..default()
+                },
+            ));
+            if has_saved_session() {
+                parent.spawn((
+                    Text::new(tr!("C to continue your saved session")),
+                    TextFont {
+                        font: custom_font.clone(),
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(palette.hint_faint.0),
+                    Node {
+                        margin: UiRect::bottom(Val::Px(5.0)),
+                        ..default()
+                    },
+                ));
+            }
+            parent.spawn((
+                Text::new(tr!("ESC to quit")),
                 TextFont {
                     font: custom_font,
                     font_size: 14.0,
                     ..default()
                 },
-                TextColor(Color::srgb(0.3, 0.05, 0.05)),
+                TextColor(palette.hint_faint.0),
             ));
         });
     });
@@ -373,6 +562,8 @@ pub fn handle_start_screen_input(
     mut selected_rule: ResMut<SelectedRule>,
     mut next_state: ResMut<NextState<GameState>>,
     mut app_exit_events: EventWriter<AppExit>,
+    mut pending_session: ResMut<PendingSession>,
+    theme: Res<Theme>,
     mut button_interaction_query: Query<(
         &Interaction,
         &mut BackgroundColor,
@@ -391,7 +582,7 @@ pub fn handle_start_screen_input(
     for (interaction, mut color, left_btn, right_btn, start_btn) in &mut button_interaction_query {
         match *interaction {
             Interaction::Pressed => {
-                *color = BackgroundColor(Color::srgb(0.4, 0.05, 0.05));
+                *color = BackgroundColor(theme.start_screen.pressed.0);
                 
                 if left_btn.is_some() {
                     left_clicked = true;
@@ -403,16 +594,16 @@ pub fn handle_start_screen_input(
             }
             Interaction::Hovered => {
                 if start_btn.is_some() {
-                    *color = BackgroundColor(Color::srgb(0.3, 0.03, 0.03));
+                    *color = BackgroundColor(theme.start_screen.start_button_hover.0);
                 } else {
-                    *color = BackgroundColor(Color::srgb(0.2, 0.02, 0.02));
+                    *color = BackgroundColor(theme.start_screen.button_hover.0);
                 }
             }
             Interaction::None => {
                 if start_btn.is_some() {
-                    *color = BackgroundColor(Color::srgba(0.2, 0.0, 0.0, 0.9));
+                    *color = BackgroundColor(theme.start_screen.start_button.0);
                 } else {
-                    *color = BackgroundColor(Color::srgba(0.1, 0.0, 0.0, 0.9));
+                    *color = BackgroundColor(theme.start_screen.button.0);
                 }
             }
         }
@@ -441,6 +632,22 @@ pub fn handle_start_screen_input(
         next_state.set(GameState::Playing);
     }
 
+    // Continue the quick-saved session
+    if keyboard_input.just_pressed(KeyCode::KeyC) && has_saved_session() {
+        match SessionSnapshot::load(SESSION_PATH) {
+            Ok(snapshot) => {
+                if let Ok(rule) = snapshot.rule() {
+                    selected_rule.current = rule;
+                    selected_rule.index = rules.iter().position(|&r| r == rule).unwrap_or(0);
+                }
+                toast(tr!("Continuing saved session ({})", snapshot.rule));
+                pending_session.0 = Some(snapshot);
+                next_state.set(GameState::Playing);
+            }
+            Err(e) => toast(tr!("Could not load saved session: {}", e)),
+        }
+    }
+
     // Quit game
     if keyboard_input.just_pressed(KeyCode::Escape) {
         println!("Quitting game...");
@@ -462,7 +669,7 @@ pub fn update_start_screen_ui(
         
         // Update rule description
         if let Ok(mut text) = rule_desc_query.get_single_mut() {
-            **text = selected_rule.current.description().to_string();
+            **text = translate(selected_rule.current.description());
         }
     }
 }
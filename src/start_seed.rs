@@ -0,0 +1,378 @@
+//! Starting grid chosen on the start screen.
+//!
+//! Under the rule selector the player picks what the game starts from: an empty grid, a
+//! random soup (with a slider for how many cells start alive), one of the library patterns
+//! for the selected rule, or a pattern file from the pattern folder (the same files the
+//! in-game browser lists). Up and Down or the arrow buttons change the kind; - and + or the
+//! buttons either side move the density or step through the patterns and files. The choice
+//! is written into the cleared grid when the game starts, centred on the origin with the
+//! view fitted to it, unless a saved session is being continued.
+
+use std::path::PathBuf;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use bevy::window::PrimaryWindow;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::browser::{scan_patterns, BrowserEntry};
+use crate::camera::{fit_view, CameraState, GameCamera};
+use crate::infinite_grid::{patterns, InfiniteGrid};
+use crate::session::PendingSession;
+use crate::start_screen::{GameState, RuleType, SelectedRule};
+use crate::theme::StartScreenColors;
+use crate::tools::state_for_click;
+use crate::tr;
+use crate::{CellState, GameConfig};
+
+/// Side of the random soup's square
+pub const SOUP_SIZE: i32 = 64;
+/// Density change per press of - or +
+const DENSITY_STEP: f64 = 0.05;
+const MIN_DENSITY: f64 = 0.05;
+const MAX_DENSITY: f64 = 0.95;
+const TRACK_WIDTH: f32 = 160.0;
+const KNOB_WIDTH: f32 = 8.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeedKind {
+    Empty,
+    Soup,
+    Pattern,
+    File,
+}
+
+impl SeedKind {
+    pub const ALL: [SeedKind; 4] = [SeedKind::Empty, SeedKind::Soup, SeedKind::Pattern, SeedKind::File];
+
+    /// The kind `steps` places along `ALL`, wrapping round
+    pub fn cycle(self, steps: i32) -> SeedKind {
+        let index = Self::ALL.iter().position(|&kind| kind == self).unwrap_or(0) as i32;
+        Self::ALL[(index + steps).rem_euclid(Self::ALL.len() as i32) as usize]
+    }
+}
+
+/// What the next game starts from
+#[derive(Resource)]
+pub struct StartSeed {
+    pub kind: SeedKind,
+    /// Probability that a cell of the soup starts alive
+    pub density: f64,
+    /// Position among the library patterns for the selected rule
+    pub pattern: usize,
+    /// Folder the pattern files come from
+    pub dir: PathBuf,
+    /// Readable pattern files in `dir`, rescanned whenever the start screen opens
+    pub files: Vec<BrowserEntry>,
+    pub file: usize,
+}
+
+impl StartSeed {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { kind: SeedKind::Empty, density: 0.35, pattern: 0, dir: dir.into(), files: Vec::new(), file: 0 }
+    }
+
+    /// The library pattern chosen for `rule`, if it has any
+    pub fn library_pattern(&self, rule: RuleType) -> Option<&'static patterns::LibraryPattern> {
+        let available: Vec<_> = patterns::for_rule(rule).collect();
+        (!available.is_empty()).then(|| available[self.pattern % available.len()])
+    }
+
+    pub fn chosen_file(&self) -> Option<&BrowserEntry> {
+        (!self.files.is_empty()).then(|| &self.files[self.file % self.files.len()])
+    }
+
+    /// Move the density, pattern or file `steps` along
+    pub fn adjust(&mut self, steps: i32, rule: RuleType) {
+        match self.kind {
+            SeedKind::Empty => {}
+            SeedKind::Soup => {
+                let density = self.density + steps as f64 * DENSITY_STEP;
+                self.density = ((density / DENSITY_STEP).round() * DENSITY_STEP).clamp(MIN_DENSITY, MAX_DENSITY);
+            }
+            SeedKind::Pattern => self.pattern = step_index(self.pattern, steps, patterns::for_rule(rule).count()),
+            SeedKind::File => self.file = step_index(self.file, steps, self.files.len()),
+        }
+    }
+
+    /// What the start screen says the game starts from
+    pub fn label(&self, rule: RuleType) -> String {
+        match self.kind {
+            SeedKind::Empty => tr!("Empty grid"),
+            SeedKind::Soup => tr!("Random soup, {:.0}% alive", self.density * 100.0),
+            SeedKind::Pattern => match self.library_pattern(rule) {
+                Some(pattern) => tr!("Pattern: {}", pattern.name),
+                None => tr!("No patterns for {}", rule.name()),
+            },
+            SeedKind::File => match self.chosen_file() {
+                Some(entry) => tr!("File: {}", entry.title()),
+                None => tr!("No pattern files in {}", self.dir.display()),
+            },
+        }
+    }
+
+    /// The starting cells under `rule`, centred on the origin; `seed` picks the soup
+    pub fn cells(&self, rule: RuleType, seed: u64) -> Vec<(i32, i32, CellState)> {
+        let mut cells: Vec<(i32, i32, CellState)> = match self.kind {
+            SeedKind::Empty => Vec::new(),
+            SeedKind::Soup => {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut cells = Vec::new();
+                for y in 0..SOUP_SIZE {
+                    for x in 0..SOUP_SIZE {
+                        if rng.gen_bool(self.density) {
+                            // Whatever a click draws under the rule; Immigration gets both species
+                            let button = if rng.gen_bool(0.5) { MouseButton::Right } else { MouseButton::Left };
+                            let button = if rule == RuleType::Immigration { button } else { MouseButton::Left };
+                            cells.push((x, y, state_for_click(rule, button, false, false)));
+                        }
+                    }
+                }
+                cells
+            }
+            SeedKind::Pattern => self.library_pattern(rule).map(|pattern| pattern.cells().collect()).unwrap_or_default(),
+            SeedKind::File => self.chosen_file().map(|entry| entry.pattern.grid_cells().collect()).unwrap_or_default(),
+        };
+        let width = cells.iter().map(|c| c.0).max().map_or(0, |max| max + 1);
+        let height = cells.iter().map(|c| c.1).max().map_or(0, |max| max + 1);
+        for cell in &mut cells {
+            cell.0 -= width / 2;
+            cell.1 -= height / 2;
+        }
+        cells
+    }
+}
+
+/// `index` moved `steps` along a list of `len`, wrapping round
+fn step_index(index: usize, steps: i32, len: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    (index as i64 + steps as i64).rem_euclid(len as i64) as usize
+}
+
+#[derive(Component)]
+struct SeedLabel;
+
+/// The - / slider / + row, hidden for an empty grid
+#[derive(Component)]
+struct SeedAdjustRow;
+
+#[derive(Component)]
+struct DensityTrack;
+
+#[derive(Component)]
+struct DensityKnob;
+
+#[derive(Component, Clone, Copy)]
+enum SeedButton {
+    Kind(i32),
+    Adjust(i32),
+}
+
+/// The starting-grid controls, as children of the start screen's rule panel
+pub fn spawn_seed_picker(parent: &mut ChildBuilder, font: &Handle<Font>, palette: &StartScreenColors) {
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+    let button = |parent: &mut ChildBuilder, label: &str, action: SeedButton| {
+        parent
+            .spawn((
+                Button,
+                Node {
+                    width: Val::Px(30.0),
+                    height: Val::Px(30.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..default()
+                },
+                BackgroundColor(palette.button.0),
+                BorderColor(palette.button_border.0),
+                action,
+            ))
+            .with_child((Text::new(label), text(18.0), TextColor(palette.accent.0)));
+    };
+    let row = || Node {
+        flex_direction: FlexDirection::Row,
+        align_items: AlignItems::Center,
+        column_gap: Val::Px(12.0),
+        margin: UiRect::bottom(Val::Px(8.0)),
+        ..default()
+    };
+    parent.spawn(row()).with_children(|row| {
+        button(row, "<", SeedButton::Kind(-1));
+        row.spawn((
+            Text::new(""),
+            text(18.0),
+            TextColor(palette.description.0),
+            Node { width: Val::Px(320.0), justify_content: JustifyContent::Center, ..default() },
+            TextLayout::new_with_justify(JustifyText::Center),
+            SeedLabel,
+        ));
+        button(row, ">", SeedButton::Kind(1));
+    });
+    parent.spawn((row(), SeedAdjustRow)).with_children(|row| {
+        button(row, "-", SeedButton::Adjust(-1));
+        row.spawn((
+            Node { width: Val::Px(TRACK_WIDTH), height: Val::Px(14.0), ..default() },
+            Interaction::default(),
+            RelativeCursorPosition::default(),
+            DensityTrack,
+        ))
+        .with_children(|track| {
+            track.spawn((
+                Node { width: Val::Percent(100.0), height: Val::Px(4.0), margin: UiRect::vertical(Val::Auto), ..default() },
+                BackgroundColor(palette.button_border.0),
+            ));
+            track.spawn((
+                Node { position_type: PositionType::Absolute, width: Val::Px(KNOB_WIDTH), height: Val::Percent(100.0), ..default() },
+                BackgroundColor(palette.accent.0),
+                DensityKnob,
+            ));
+        });
+        button(row, "+", SeedButton::Adjust(1));
+    });
+}
+
+pub struct StartSeedPlugin;
+
+impl Plugin for StartSeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::StartScreen), rescan_seed_files)
+            .add_systems(Update, (seed_picker_input, update_seed_picker).chain().run_if(in_state(GameState::StartScreen)));
+    }
+}
+
+fn rescan_seed_files(mut seed: ResMut<StartSeed>) {
+    seed.files = scan_patterns(&seed.dir);
+}
+
+fn seed_picker_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    selected: Res<SelectedRule>,
+    mut seed: ResMut<StartSeed>,
+    buttons: Query<(&Interaction, &SeedButton), Changed<Interaction>>,
+    tracks: Query<(&Interaction, &RelativeCursorPosition), With<DensityTrack>>,
+) {
+    let mut kind_steps = 0;
+    let mut adjust_steps = 0;
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        kind_steps -= 1;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        kind_steps += 1;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        adjust_steps -= 1;
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        adjust_steps += 1;
+    }
+    for (interaction, button) in &buttons {
+        match (*interaction, *button) {
+            (Interaction::Pressed, SeedButton::Kind(steps)) => kind_steps += steps,
+            (Interaction::Pressed, SeedButton::Adjust(steps)) => adjust_steps += steps,
+            _ => {}
+        }
+    }
+    if kind_steps != 0 {
+        seed.kind = seed.kind.cycle(kind_steps);
+    }
+    if adjust_steps != 0 {
+        seed.adjust(adjust_steps, selected.current);
+    }
+    // Held down on the track, the density follows the pointer
+    if seed.kind == SeedKind::Soup {
+        for (interaction, cursor) in &tracks {
+            if let (Interaction::Pressed, Some(at)) = (interaction, cursor.normalized) {
+                let density = MIN_DENSITY + (at.x as f64 + 0.5).clamp(0.0, 1.0) * (MAX_DENSITY - MIN_DENSITY);
+                let density = (density / DENSITY_STEP).round() * DENSITY_STEP;
+                if density != seed.density {
+                    seed.density = density.clamp(MIN_DENSITY, MAX_DENSITY);
+                }
+            }
+        }
+    }
+}
+
+fn update_seed_picker(
+    seed: Res<StartSeed>,
+    selected: Res<SelectedRule>,
+    mut labels: Query<&mut Text, With<SeedLabel>>,
+    mut rows: Query<&mut Visibility, With<SeedAdjustRow>>,
+    mut tracks: Query<&mut Node, (With<DensityTrack>, Without<DensityKnob>)>,
+    mut knobs: Query<&mut Node, With<DensityKnob>>,
+) {
+    // The labels are spawned with the start screen, after this resource last changed
+    let fresh = labels.iter().any(|text| text.0.is_empty());
+    if !seed.is_changed() && !selected.is_changed() && !fresh {
+        return;
+    }
+    for mut text in &mut labels {
+        text.0 = seed.label(selected.current);
+    }
+    for mut visibility in &mut rows {
+        *visibility = if seed.kind == SeedKind::Empty { Visibility::Hidden } else { Visibility::Inherited };
+    }
+    for mut node in &mut tracks {
+        node.display = if seed.kind == SeedKind::Soup { Display::Flex } else { Display::None };
+    }
+    let position = ((seed.density - MIN_DENSITY) / (MAX_DENSITY - MIN_DENSITY)) as f32;
+    for mut node in &mut knobs {
+        node.left = Val::Px(position * (TRACK_WIDTH - KNOB_WIDTH));
+    }
+}
+
+/// Write the chosen starting grid into the cleared grid and frame it; runs as the game starts
+pub fn apply_start_seed(
+    (seed, game_config, camera_state): (Res<StartSeed>, Res<GameConfig>, Res<CameraState>),
+    pending: Res<PendingSession>,
+    mut grid: ResMut<InfiniteGrid>,
+    mut cameras: Query<&mut GameCamera>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    // A continued session brings its own grid and view
+    if pending.0.is_some() {
+        return;
+    }
+    let cells = seed.cells(game_config.current_rule, rand::random());
+    if cells.is_empty() {
+        return;
+    }
+    grid.insert_cells(cells.into_iter(), 0, 0);
+    let (Some(bounds), Ok(mut camera), Ok(window)) = (grid.bounds(), cameras.get_single_mut(), windows.get_single()) else { return };
+    let (centre, zoom) = fit_view(bounds, &camera_state, Vec2::new(window.width(), window.height()));
+    camera.target_zoom = zoom.clamp(camera.min_zoom, camera.max_zoom);
+    camera.pan_target = Some(centre);
+    camera.zoom_anchor = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_kind_gives_its_starting_cells() {
+        let mut seed = StartSeed::new("no such folder");
+        assert!(seed.cells(RuleType::Conway, 1).is_empty());
+
+        seed.kind = seed.kind.cycle(1);
+        assert_eq!(seed.kind, SeedKind::Soup);
+        seed.adjust(-100, RuleType::Conway);
+        assert_eq!(seed.density, MIN_DENSITY);
+        seed.adjust(4, RuleType::Conway);
+        assert!((seed.density - 0.25).abs() < 1e-9);
+        let soup = seed.cells(RuleType::Conway, 7);
+        assert_eq!(soup, seed.cells(RuleType::Conway, 7));
+        assert!(soup.iter().all(|&(x, y, _)| (-SOUP_SIZE / 2..SOUP_SIZE / 2).contains(&x) && (-SOUP_SIZE / 2..SOUP_SIZE / 2).contains(&y)));
+        assert!(seed.cells(RuleType::WireWorld, 7).iter().all(|c| c.2 == CellState::Wire));
+
+        seed.kind = SeedKind::Pattern;
+        let first = seed.label(RuleType::Conway);
+        seed.adjust(1, RuleType::Conway);
+        assert_ne!(seed.label(RuleType::Conway), first);
+        assert!(!seed.cells(RuleType::Conway, 0).is_empty());
+
+        seed.kind = SeedKind::File;
+        assert!(seed.cells(RuleType::Conway, 0).is_empty());
+        assert_eq!(SeedKind::Empty.cycle(-1), SeedKind::File);
+    }
+}
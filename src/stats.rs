@@ -0,0 +1,16 @@
+//! Simulation statistics derived from the grid.
+//!
+//! * `objects` — connected components and moving-object (spaceship) tracking.
+//! * `census` — apgcode classification and object counts of a settled grid.
+//! * `soup` — headless batch runner for seeded random soups.
+//! * `history` — population, births and deaths over recent generations.
+
+pub mod objects;
+pub mod census;
+pub mod soup;
+pub mod history;
+
+pub use census::{Census, classify, common_name};
+pub use objects::{components_of, connected_components, Component, MovingObject, ObjectTracker};
+pub use history::{PopulationHistory, Sample};
+pub use soup::{run_batch, run_soup, SoupConfig, SoupReport, SoupResult, StabilityDetector};
@@ -0,0 +1,235 @@
+//! Catagolue-style census of a settled grid.
+//!
+//! Every connected component is evolved in isolation until it repeats, which tells us
+//! whether it is a still life (`xs`), oscillator (`xp`) or spaceship (`xq`). The object is
+//! then named by its apgcode: the extended Wechsler encoding of the smallest
+//! representation over all phases and the eight rotations/reflections, so the same
+//! object always hashes to the same key regardless of where or how it was found.
+//!
+//! Reference: <https://conwaylife.com/wiki/Apgcode>
+
+use std::collections::BTreeMap;
+use crate::infinite_grid::InfiniteGrid;
+use crate::start_screen::RuleType;
+use crate::CellState;
+use super::objects::connected_components;
+
+/// Longest period searched for when classifying an object
+pub const MAX_CENSUS_PERIOD: u64 = 64;
+
+/// Key used for components that did not repeat within `MAX_CENSUS_PERIOD`
+pub const UNCLASSIFIED: &str = "zz_UNCLASSIFIED";
+
+/// Object counts keyed by apgcode
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Census {
+    pub counts: BTreeMap<String, usize>,
+}
+
+impl Census {
+    /// Classify every connected component of `grid` under `rule`
+    pub fn take(grid: &InfiniteGrid, rule: RuleType) -> Self {
+        let mut census = Census::default();
+        for component in connected_components(grid) {
+            let cells: Vec<_> = component.cells.iter().map(|&(x, y)| (x, y, grid.get(x, y))).collect();
+            let code = classify(&cells, rule).unwrap_or_else(|| UNCLASSIFIED.to_string());
+            *census.counts.entry(code).or_default() += 1;
+        }
+        census
+    }
+
+    /// Total number of objects counted
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Fold another census into this one
+    pub fn merge(&mut self, other: &Census) {
+        for (code, count) in &other.counts {
+            *self.counts.entry(code.clone()).or_default() += count;
+        }
+    }
+
+    /// `(code, common name, count)` sorted by descending count
+    pub fn ranked(&self) -> Vec<(&str, Option<&'static str>, usize)> {
+        let mut ranked: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(code, &count)| (code.as_str(), common_name(code), count))
+            .collect();
+        ranked.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(b.0)));
+        ranked
+    }
+}
+
+/// Well-known B3/S23 objects
+pub fn common_name(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "xs4_33" => "block",
+        "xs6_696" => "beehive",
+        "xs7_2596" => "loaf",
+        "xs5_253" => "boat",
+        "xs6_356" => "ship",
+        "xs4_252" => "tub",
+        "xs8_6996" => "pond",
+        "xp2_7" => "blinker",
+        "xp2_7e" => "toad",
+        "xp2_318c" => "beacon",
+        "xq4_153" => "glider",
+        "xq4_6frc" => "lightweight spaceship",
+        _ => return None,
+    })
+}
+
+/// Evolve an isolated object and return its apgcode, or `None` if it does not repeat
+pub fn classify(cells: &[(i32, i32, CellState)], rule: RuleType) -> Option<String> {
+    let mut grid = InfiniteGrid::new();
+    for &(x, y, state) in cells {
+        grid.set(x, y, state);
+    }
+    let start = normalised_state(&grid);
+    let start_origin = origin(&grid)?;
+    let population = grid.population();
+    let mut best = wechsler_canonical(&grid);
+
+    for period in 1..=MAX_CENSUS_PERIOD {
+        grid.update(rule);
+        if grid.population() == 0 {
+            return None;
+        }
+        if normalised_state(&grid) == start {
+            let prefix = if origin(&grid)? == start_origin { if period == 1 { "xs" } else { "xp" } } else { "xq" };
+            let size = if prefix == "xs" { population as u64 } else { period };
+            return Some(format!("{prefix}{size}_{best}"));
+        }
+        let phase = wechsler_canonical(&grid);
+        if better(&phase, &best) {
+            best = phase;
+        }
+    }
+    None
+}
+
+fn origin(grid: &InfiniteGrid) -> Option<(i32, i32)> {
+    grid.bounds().map(|b| (b.min_x, b.min_y))
+}
+
+/// Sorted cells (with state) translated to a (0, 0) bounding-box origin
+fn normalised_state(grid: &InfiniteGrid) -> Vec<(i32, i32, CellState)> {
+    let Some((ox, oy)) = origin(grid) else { return Vec::new() };
+    let mut cells: Vec<_> = grid.cells().map(|(x, y, s)| (x - ox, y - oy, s)).collect();
+    cells.sort_unstable_by_key(|&(x, y, _)| (y, x));
+    cells
+}
+
+/// apgcode ordering: shorter wins, ties broken lexicographically
+fn better(a: &str, b: &str) -> bool {
+    (a.len(), a) < (b.len(), b)
+}
+
+/// Smallest Wechsler encoding over the eight symmetries of the current phase
+fn wechsler_canonical(grid: &InfiniteGrid) -> String {
+    let cells: Vec<(i32, i32)> = grid.alive_cells().collect();
+    let mut best: Option<String> = None;
+    for transform in 0..8 {
+        let mapped: Vec<(i32, i32)> = cells
+            .iter()
+            .map(|&(x, y)| {
+                let (x, y) = if transform & 4 != 0 { (y, x) } else { (x, y) };
+                let x = if transform & 1 != 0 { -x } else { x };
+                let y = if transform & 2 != 0 { -y } else { y };
+                (x, y)
+            })
+            .collect();
+        let code = wechsler(&mapped);
+        if best.as_ref().is_none_or(|b| better(&code, b)) {
+            best = Some(code);
+        }
+    }
+    best.unwrap_or_default()
+}
+
+/// Extended Wechsler format: 5-row strips, one base-32 digit per column, `z` between strips,
+/// and runs of blank columns compressed to `w`/`x`/`y?`.
+pub fn wechsler(cells: &[(i32, i32)]) -> String {
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuv";
+    const RUNS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    if cells.is_empty() {
+        return String::new();
+    }
+    let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+    let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+    let width = (cells.iter().map(|c| c.0).max().unwrap_or(0) - min_x + 1) as usize;
+    let height = (cells.iter().map(|c| c.1).max().unwrap_or(0) - min_y + 1) as usize;
+    let strips = height.div_ceil(5);
+
+    let mut columns = vec![0u8; width * strips];
+    for &(x, y) in cells {
+        let (x, y) = ((x - min_x) as usize, (y - min_y) as usize);
+        columns[(y / 5) * width + x] |= 1 << (y % 5);
+    }
+
+    let mut out = String::new();
+    for strip in 0..strips {
+        if strip > 0 {
+            out.push('z');
+        }
+        let row = &columns[strip * width..(strip + 1) * width];
+        let used = row.iter().rposition(|&c| c != 0).map_or(0, |i| i + 1);
+        let mut zeros = 0usize;
+        let flush = |zeros: &mut usize, out: &mut String| {
+            while *zeros > 0 {
+                match *zeros {
+                    1 => { out.push('0'); *zeros = 0; }
+                    2 => { out.push('w'); *zeros = 0; }
+                    3 => { out.push('x'); *zeros = 0; }
+                    n => {
+                        let take = n.min(4 + RUNS.len() - 1);
+                        out.push('y');
+                        out.push(RUNS[take - 4] as char);
+                        *zeros -= take;
+                    }
+                }
+            }
+        };
+        for &column in &row[..used] {
+            if column == 0 {
+                zeros += 1;
+            } else {
+                flush(&mut zeros, &mut out);
+                out.push(DIGITS[column as usize] as char);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(cells: &[(i32, i32)]) -> Option<String> {
+        let cells: Vec<_> = cells.iter().map(|&(x, y)| (x, y, CellState::Alive)).collect();
+        classify(&cells, RuleType::Conway)
+    }
+
+    #[test]
+    fn common_objects_get_their_apgcodes() {
+        assert_eq!(code(&[(0, 0), (1, 0), (0, 1), (1, 1)]).as_deref(), Some("xs4_33"));
+        assert_eq!(code(&[(1, 0), (2, 0), (0, 1), (3, 1), (1, 2), (2, 2)]).as_deref(), Some("xs6_696"));
+        assert_eq!(code(&[(0, 0), (1, 0), (2, 0)]).as_deref(), Some("xp2_7"));
+        assert_eq!(code(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]).as_deref(), Some("xq4_153"));
+    }
+
+    #[test]
+    fn census_counts_separate_objects() {
+        let mut grid = InfiniteGrid::new();
+        grid.insert_pattern([(0, 0), (1, 0), (0, 1), (1, 1)].into_iter(), 0, 0);
+        grid.insert_pattern([(0, 0), (1, 0), (0, 1), (1, 1)].into_iter(), 10, 10);
+        grid.insert_pattern([(0, 0), (1, 0), (2, 0)].into_iter(), -10, 0);
+        let census = Census::take(&grid, RuleType::Conway);
+        assert_eq!(census.counts.get("xs4_33"), Some(&2));
+        assert_eq!(census.counts.get("xp2_7"), Some(&1));
+        assert_eq!(census.ranked()[0].1, Some("block"));
+    }
+}
@@ -0,0 +1,110 @@
+//! Population history.
+//!
+//! One sample per generation seen: the population and the births and deaths of the step
+//! that produced it (`InfiniteGrid::last_step`), keeping the most recent `capacity`. When the
+//! simulation runs several generations between samples only the last step's births and
+//! deaths are known, so those samples undercount them. Going back a generation (a clear or a
+//! load) starts the history afresh.
+
+use std::collections::VecDeque;
+use bevy::prelude::Resource;
+use crate::infinite_grid::InfiniteGrid;
+
+/// Generations kept by default
+pub const DEFAULT_HISTORY: usize = 500;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Sample {
+    pub generation: u64,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct PopulationHistory {
+    pub capacity: usize,
+    samples: VecDeque<Sample>,
+}
+
+impl Default for PopulationHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY)
+    }
+}
+
+impl PopulationHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(2), samples: VecDeque::new() }
+    }
+
+    /// Take a sample if the grid has moved on a generation since the last one; edits within
+    /// a generation update the latest sample's population instead
+    pub fn record(&mut self, grid: &InfiniteGrid) {
+        let generation = grid.generation();
+        match self.samples.back_mut() {
+            Some(last) if last.generation == generation => {
+                last.population = grid.population();
+                return;
+            }
+            Some(last) if last.generation > generation => self.samples.clear(),
+            _ => {}
+        }
+        let step = grid.last_step();
+        self.samples.push_back(Sample { generation, population: grid.population(), births: step.births, deaths: step.deaths });
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Samples oldest first
+    pub fn samples(&self) -> impl ExactSizeIterator<Item = &Sample> + '_ {
+        self.samples.iter()
+    }
+
+    pub fn latest(&self) -> Option<&Sample> {
+        self.samples.back()
+    }
+
+    pub fn peak_population(&self) -> usize {
+        self.samples.iter().map(|sample| sample.population).max().unwrap_or(0)
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::start_screen::RuleType;
+    use crate::CellState;
+
+    #[test]
+    fn samples_each_generation_up_to_capacity() {
+        let mut grid = InfiniteGrid::new();
+        for x in -1..=1 {
+            grid.set(x, 0, CellState::Alive);
+        }
+        let mut history = PopulationHistory::new(3);
+        history.record(&grid);
+        grid.set(10, 10, CellState::Alive);
+        history.record(&grid);
+        assert_eq!(history.samples().len(), 1);
+        assert_eq!(history.latest().map(|sample| sample.population), Some(4));
+
+        for _ in 0..4 {
+            grid.update(RuleType::Conway);
+            history.record(&grid);
+        }
+        assert_eq!(history.samples().len(), 3);
+        let latest = history.latest().copied().unwrap_or_default();
+        assert_eq!((latest.generation, latest.population, latest.births, latest.deaths), (4, 3, 2, 2));
+        assert_eq!(history.peak_population(), 3);
+
+        grid.clear();
+        history.record(&grid);
+        assert_eq!(history.samples().len(), 1);
+    }
+}
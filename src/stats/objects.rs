@@ -0,0 +1,258 @@
+//! Connected-component extraction and spaceship tracking.
+//!
+//! Objects are 8-connected groups of non-dead cells. A component is considered to be
+//! moving when the exact same shape appeared `p` generations earlier at a different
+//! offset no further than `p` cells away (the speed of light), which catches gliders,
+//! *WSS and most small ships without knowing them in advance.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use bevy::prelude::Resource;
+use crate::infinite_grid::InfiniteGrid;
+
+/// An 8-connected group of live cells
+#[derive(Clone, Debug)]
+pub struct Component {
+    /// Absolute cell positions
+    pub cells: Vec<(i32, i32)>,
+    pub min_x: i32,
+    pub min_y: i32,
+}
+
+impl Component {
+    fn from_cells(mut cells: Vec<(i32, i32)>) -> Self {
+        cells.sort_unstable();
+        let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+        Self { cells, min_x, min_y }
+    }
+
+    /// Cells translated so the bounding box starts at (0, 0), in sorted order
+    pub fn shape(&self) -> Vec<(i32, i32)> {
+        let mut shape: Vec<_> = self.cells.iter().map(|&(x, y)| (x - self.min_x, y - self.min_y)).collect();
+        shape.sort_unstable();
+        shape
+    }
+
+    /// Mean cell position
+    pub fn centroid(&self) -> (f32, f32) {
+        let n = self.cells.len().max(1) as f32;
+        let (sx, sy) = self.cells.iter().fold((0i64, 0i64), |(sx, sy), &(x, y)| (sx + x as i64, sy + y as i64));
+        (sx as f32 / n, sy as f32 / n)
+    }
+}
+
+/// Split every non-dead cell of `grid` into 8-connected components
+pub fn connected_components(grid: &InfiniteGrid) -> Vec<Component> {
+    components_of(grid.alive_cells().collect())
+}
+
+/// Split `cells` into 8-connected components
+pub fn components_of(cells: HashSet<(i32, i32)>) -> Vec<Component> {
+    let mut visited = HashSet::with_capacity(cells.len());
+    let mut components = Vec::new();
+
+    for &start in &cells {
+        if !visited.insert(start) {
+            continue;
+        }
+        let mut members = Vec::new();
+        let mut stack = vec![start];
+        while let Some((x, y)) = stack.pop() {
+            members.push((x, y));
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let n = (x + dx, y + dy);
+                    if cells.contains(&n) && visited.insert(n) {
+                        stack.push(n);
+                    }
+                }
+            }
+        }
+        components.push(Component::from_cells(members));
+    }
+    components
+}
+
+/// A detected spaceship (or any translating object)
+#[derive(Clone, Debug, PartialEq)]
+pub struct MovingObject {
+    /// Centroid in grid coordinates
+    pub position: (f32, f32),
+    /// Displacement in cells per generation
+    pub velocity: (f32, f32),
+    /// Generations between repeated appearances of the same shape
+    pub period: u64,
+    /// Number of cells in the current phase
+    pub population: usize,
+}
+
+/// Normalised shape → bounding-box origins where it occurs
+type ShapeIndex = HashMap<Vec<(i32, i32)>, Vec<(i32, i32)>>;
+
+/// Components of one observed generation, indexed by normalised shape
+struct Snapshot {
+    generation: u64,
+    shapes: ShapeIndex,
+}
+
+/// Tracks connected components across generations and reports the ones that move
+#[derive(Resource)]
+pub struct ObjectTracker {
+    /// Largest period searched for (gliders and *WSS are period 4)
+    pub max_period: u64,
+    /// Components larger than this are ignored (ships are small; big blobs are soup)
+    pub max_object_cells: usize,
+    history: VecDeque<Snapshot>,
+    moving: Vec<MovingObject>,
+    last_generation: Option<u64>,
+}
+
+impl Default for ObjectTracker {
+    fn default() -> Self {
+        Self {
+            max_period: 4,
+            max_object_cells: 64,
+            history: VecDeque::new(),
+            moving: Vec::new(),
+            last_generation: None,
+        }
+    }
+}
+
+impl ObjectTracker {
+    pub fn new(max_period: u64) -> Self {
+        Self { max_period, ..Default::default() }
+    }
+
+    /// Objects found moving at the most recent observation
+    pub fn moving_objects(&self) -> &[MovingObject] {
+        &self.moving
+    }
+
+    /// Generation of the last observation, if any
+    pub fn last_generation(&self) -> Option<u64> {
+        self.last_generation
+    }
+
+    /// Forget all history (e.g. after the grid was cleared or edited heavily)
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.moving.clear();
+        self.last_generation = None;
+    }
+
+    /// Record the grid state at `generation` and refresh the list of moving objects.
+    /// Observations are expected once per generation; a gap resets the history.
+    pub fn observe(&mut self, grid: &InfiniteGrid, generation: u64) {
+        if let Some(last) = self.last_generation {
+            if generation != last + 1 {
+                self.history.clear();
+            }
+        }
+        self.last_generation = Some(generation);
+
+        let mut shapes = ShapeIndex::new();
+        // Earlier appearances already paired with a component, so each pairs with one at most
+        let mut claimed = HashSet::new();
+        self.moving.clear();
+
+        for component in connected_components(grid) {
+            if component.cells.len() > self.max_object_cells {
+                continue;
+            }
+            let shape = component.shape();
+            let origin = (component.min_x, component.min_y);
+
+            if let Some((period, dx, dy)) = self.find_match(&shape, origin, generation, &mut claimed) {
+                let centroid = component.centroid();
+                self.moving.push(MovingObject {
+                    position: centroid,
+                    velocity: (dx as f32 / period as f32, dy as f32 / period as f32),
+                    period,
+                    population: component.cells.len(),
+                });
+            }
+            shapes.entry(shape).or_default().push(origin);
+        }
+
+        self.history.push_back(Snapshot { generation, shapes });
+        while self.history.len() as u64 > self.max_period {
+            self.history.pop_front();
+        }
+    }
+
+    /// Smallest period `p` for which the same shape sat at a displaced origin `p` generations
+    /// ago, pairing the component with that earlier appearance in `claimed`. A component whose
+    /// shape sat at its own origin at some period is a still life or oscillator, not a ship,
+    /// however many identical neighbours it has.
+    fn find_match(
+        &self,
+        shape: &[(i32, i32)],
+        origin: (i32, i32),
+        generation: u64,
+        claimed: &mut HashSet<(u64, (i32, i32))>,
+    ) -> Option<(u64, i32, i32)> {
+        for snapshot in self.history.iter().rev() {
+            let period = generation.checked_sub(snapshot.generation)?;
+            if period == 0 || period > self.max_period {
+                continue;
+            }
+            let Some(origins) = snapshot.shapes.get(shape) else { continue };
+            if origins.contains(&origin) {
+                claimed.insert((snapshot.generation, origin));
+                return None;
+            }
+            let reach = period as i32;
+            let best = origins
+                .iter()
+                .filter(|&&earlier| !claimed.contains(&(snapshot.generation, earlier)))
+                .map(|&(ox, oy)| (origin.0 - ox, origin.1 - oy))
+                .filter(|&(dx, dy)| dx.abs() <= reach && dy.abs() <= reach)
+                .min_by_key(|&(dx, dy)| dx.abs() + dy.abs());
+            if let Some((dx, dy)) = best {
+                claimed.insert((snapshot.generation, (origin.0 - dx, origin.1 - dy)));
+                return Some((period, dx, dy));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infinite_grid::patterns;
+
+    #[test]
+    fn glider_is_reported_with_diagonal_c4_velocity() {
+        let mut grid = InfiniteGrid::new();
+        grid.insert_pattern(patterns::glider(), 0, 0);
+        grid.insert_pattern(patterns::block(), 40, 40);
+
+        let mut tracker = ObjectTracker::default();
+        for generation in 0..8 {
+            tracker.observe(&grid, generation);
+            grid.step_conway();
+        }
+
+        let moving = tracker.moving_objects();
+        assert_eq!(moving.len(), 1);
+        assert_eq!(moving[0].period, 4);
+        assert_eq!(moving[0].velocity.0.abs(), 0.25);
+        assert_eq!(moving[0].velocity.1.abs(), 0.25);
+    }
+
+    #[test]
+    fn identical_still_lifes_close_together_are_not_ships() {
+        let mut grid = InfiniteGrid::new();
+        grid.insert_pattern(patterns::block(), 0, 0);
+        grid.insert_pattern(patterns::block(), 3, 0);
+
+        let mut tracker = ObjectTracker::default();
+        for generation in 0..8 {
+            tracker.observe(&grid, generation);
+            grid.step_conway();
+        }
+        assert!(tracker.moving_objects().is_empty(), "{:?}", tracker.moving_objects());
+    }
+}
@@ -0,0 +1,186 @@
+//! Headless soup search.
+//!
+//! A soup is a random square of cells seeded from a `u64`, so any interesting result can be
+//! reproduced from its seed alone. Each soup is run on an `InfiniteGrid` until its population
+//! settles into a short cycle (or a generation limit is hit) and the remaining ash is
+//! classified with [`Census`]. Nothing here touches Bevy, so the runner can be driven from
+//! tests, scripts or a separate binary.
+
+use std::collections::VecDeque;
+use std::ops::Range;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::infinite_grid::{InfiniteGrid, RunConfig, StopReason};
+use crate::start_screen::RuleType;
+use crate::CellState;
+use super::census::Census;
+
+/// Parameters shared by every soup in a search
+#[derive(Clone, Debug)]
+pub struct SoupConfig {
+    pub rule: RuleType,
+    /// Side length of the random square
+    pub size: i32,
+    /// Probability that a cell in the square starts alive
+    pub density: f64,
+    /// Give up on soups that have not settled by this generation
+    pub max_generations: u64,
+    /// Longest population cycle accepted as "settled"
+    pub max_period: usize,
+}
+
+impl Default for SoupConfig {
+    fn default() -> Self {
+        Self {
+            rule: RuleType::Conway,
+            size: 16,
+            density: 0.5,
+            max_generations: 10_000,
+            max_period: 30,
+        }
+    }
+}
+
+/// Outcome of a single soup
+#[derive(Clone, Debug)]
+pub struct SoupResult {
+    pub seed: u64,
+    pub initial_population: usize,
+    pub final_population: usize,
+    /// Generation at which the population cycle was first detected, `None` if it never settled
+    pub stabilised_at: Option<u64>,
+    pub generations: u64,
+    pub census: Census,
+}
+
+/// Aggregate of a batch of soups
+#[derive(Clone, Debug, Default)]
+pub struct SoupReport {
+    pub results: Vec<SoupResult>,
+    /// Census summed over every soup
+    pub census: Census,
+}
+
+impl SoupReport {
+    /// Soups that hit the generation limit without settling
+    pub fn unsettled(&self) -> impl Iterator<Item = &SoupResult> {
+        self.results.iter().filter(|r| r.stabilised_at.is_none())
+    }
+
+    /// Mean stabilisation time over the soups that settled
+    pub fn mean_stabilisation(&self) -> Option<f64> {
+        let settled: Vec<u64> = self.results.iter().filter_map(|r| r.stabilised_at).collect();
+        (!settled.is_empty()).then(|| settled.iter().sum::<u64>() as f64 / settled.len() as f64)
+    }
+
+    /// Seeds whose census contains `code`, handy for finding a rare object again
+    pub fn seeds_with(&self, code: &str) -> Vec<u64> {
+        self.results.iter().filter(|r| r.census.counts.contains_key(code)).map(|r| r.seed).collect()
+    }
+}
+
+/// Detects when a population sequence has become periodic.
+///
+/// Population alone can cycle by coincidence, so a period only counts once it has repeated
+/// three times in a row.
+#[derive(Clone, Debug)]
+pub struct StabilityDetector {
+    max_period: usize,
+    history: VecDeque<usize>,
+}
+
+impl StabilityDetector {
+    pub fn new(max_period: usize) -> Self {
+        let max_period = max_period.max(1);
+        Self { max_period, history: VecDeque::with_capacity(max_period * 3 + 1) }
+    }
+
+    /// Record the next population and return the detected period, if any
+    pub fn push(&mut self, population: usize) -> Option<usize> {
+        if self.history.len() == self.max_period * 3 {
+            self.history.pop_front();
+        }
+        self.history.push_back(population);
+        let len = self.history.len();
+        (1..=self.max_period).find(|&p| {
+            len >= p * 3 && (0..p * 2).all(|i| self.history[len - 1 - i] == self.history[len - 1 - i - p])
+        })
+    }
+
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+}
+
+/// Fill a `size`×`size` square at the origin from `seed`
+pub fn seed_soup(seed: u64, size: i32, density: f64) -> InfiniteGrid {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut grid = InfiniteGrid::new();
+    for y in 0..size {
+        for x in 0..size {
+            if rng.gen_bool(density.clamp(0.0, 1.0)) {
+                grid.set(x, y, CellState::Alive);
+            }
+        }
+    }
+    grid
+}
+
+/// Run one soup to stability and take its census
+pub fn run_soup(seed: u64, config: &SoupConfig) -> SoupResult {
+    let mut grid = seed_soup(seed, config.size, config.density);
+    let initial_population = grid.population();
+    let run = grid.run(config.rule, &RunConfig::default().generations(config.max_generations).until_stable(config.max_period));
+    let stabilised_at = match run.reason {
+        StopReason::Stable { since, .. } => Some(since),
+        _ => None,
+    };
+    let generations = run.generations;
+
+    SoupResult {
+        seed,
+        initial_population,
+        final_population: grid.population(),
+        stabilised_at,
+        generations,
+        census: Census::take(&grid, config.rule),
+    }
+}
+
+/// Run every seed in `seeds` and merge their censuses
+pub fn run_batch(seeds: Range<u64>, config: &SoupConfig) -> SoupReport {
+    let mut report = SoupReport::default();
+    for seed in seeds {
+        let result = run_soup(seed, config);
+        report.census.merge(&result.census);
+        report.results.push(result);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soups_are_reproducible_and_settle() {
+        let config = SoupConfig { size: 8, max_generations: 2_000, ..Default::default() };
+        let report = run_batch(0..4, &config);
+        assert_eq!(report.results.len(), 4);
+        assert!(report.mean_stabilisation().is_some());
+        assert_eq!(report.census.total(), report.results.iter().map(|r| r.census.total()).sum::<usize>());
+
+        let again = run_soup(2, &config);
+        assert_eq!(again.final_population, report.results[2].final_population);
+        assert_eq!(again.census, report.results[2].census);
+    }
+
+    #[test]
+    fn detector_needs_three_repeats() {
+        let mut detector = StabilityDetector::new(4);
+        let seq = [5, 3, 4, 3, 4, 3, 4];
+        let found: Vec<_> = seq.iter().map(|&p| detector.push(p)).collect();
+        assert_eq!(found[5], None);
+        assert_eq!(found[6], Some(2));
+    }
+}
@@ -1,6 +1,13 @@
 use bevy::prelude::*;
 use bevy::prelude::{UiRect, Val};
-use crate::audio::{set_hybrid_volume, set_hybrid_synthesis_mix};
+use crate::audio::dataset::{exported_frames, is_exporting_dataset, start_dataset, stop_dataset};
+use crate::audio::recorder::{is_recording, recorded_seconds, start_recording, stop_recording};
+use crate::audio::{set_hybrid_key, set_hybrid_layer_mix, set_hybrid_volume, set_hybrid_synthesis_mix, AudioLayer, KeySelection, KiraManager, LayerMix, MixTrack};
+use crate::keybindings::{Action, Keybindings};
+use crate::export::ExportSettings;
+use crate::start_screen::{GameState, InGame};
+use crate::ui::toast;
+use crate::tr;
 use bevy::input::mouse::{MouseWheel, MouseScrollUnit};
 
 // Resource holding current values for user-tweakable audio parameters.
@@ -8,14 +15,28 @@ use bevy::input::mouse::{MouseWheel, MouseScrollUnit};
 pub struct SynthParameters {
     pub volume: f32, // 0.0 .. 2.0 (overdrive possible)
     pub mix: f32,    // 0.0 .. 1.0
+    pub layers: LayerMix, // Gain, mute and solo of the drone, samples, groove and effects
+    pub key: KeySelection, // Root and mode fixed by the player (automatic when unset)
 }
 
 impl Default for SynthParameters {
     fn default() -> Self {
-        Self { volume: 0.7, mix: 0.7 }
+        Self { volume: 0.7, mix: 0.7, layers: LayerMix::default(), key: KeySelection::default() }
     }
 }
 
+/// Keys muting each layer (Shift solos)
+const LAYER_KEYS: [(Action, AudioLayer); 4] = [
+    (Action::MuteDrone, AudioLayer::Drone),
+    (Action::MuteSamples, AudioLayer::Samples),
+    (Action::MuteGroove, AudioLayer::Groove),
+    (Action::MuteEffects, AudioLayer::Effects),
+];
+
+const LAYER_BUTTON_OFF: Color = Color::srgb(0.2, 0.2, 0.22);
+const LAYER_MUTED: Color = Color::srgb(0.6, 0.15, 0.15);
+const LAYER_SOLOED: Color = Color::srgb(0.7, 0.6, 0.1);
+
 // Marker for the whole panel root node.
 #[derive(Component)]
 struct SynthPanel;
@@ -25,6 +46,7 @@ struct SynthPanel;
 enum ParamLabel {
     Volume,
     Mix,
+    Layer(AudioLayer),
 }
 
 /// Graphical knob widget bound to a parameter.
@@ -41,8 +63,38 @@ enum SynthButtonAction {
     VolumeDown,
     MixUp,
     MixDown,
+    Mute(AudioLayer),
+    Solo(AudioLayer),
+    Record,
+    Dataset,
+    CycleRoot,
+    CycleMode,
+}
+
+/// Text of the key buttons
+#[derive(Component, Clone, Copy)]
+enum KeyLabel {
+    Root,
+    Mode,
 }
 
+impl KeyLabel {
+    fn text(self, key: &KeySelection) -> String {
+        match self {
+            KeyLabel::Root => format!("Root: {}", key.root_label()),
+            KeyLabel::Mode => format!("Mode: {}", key.mode_label()),
+        }
+    }
+}
+
+/// Text of the audio recording button
+#[derive(Component)]
+struct RecordLabel;
+
+/// Text of the DDSP dataset export button
+#[derive(Component)]
+struct DatasetLabel;
+
 pub struct SynthControlPanelPlugin;
 
 impl Plugin for SynthControlPanelPlugin {
@@ -54,18 +106,19 @@ impl Plugin for SynthControlPanelPlugin {
                 (
                     toggle_panel_visibility,
                     button_interaction_system,
+                    layer_hotkeys.run_if(in_state(GameState::Playing)),
                     refresh_param_labels,
+                    refresh_layer_buttons,
+                    refresh_record_button,
                     knob_scroll_system,
                     knob_visual_system,
                     push_params_to_engine,
                 ),
-            );
+            )
+            .add_systems(OnExit(InGame), (finish_audio_recording, finish_dataset_export));
     }
 }
 
-/// Key used to open / close the panel.
-const TOGGLE_KEY: KeyCode = KeyCode::KeyP;
-
 fn setup_synth_panel(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
@@ -120,6 +173,13 @@ fn setup_synth_panel(
             ParamLabel::Mix,
             params.mix,
         );
+        for layer in AudioLayer::ALL {
+            spawn_layer_row(parent, &font_handle, layer, params.layers.level(layer));
+        }
+
+        spawn_key_row(parent, &font_handle, &params.key);
+        spawn_capture_button(parent, &font_handle, SynthButtonAction::Record, "● REC", RecordLabel);
+        spawn_capture_button(parent, &font_handle, SynthButtonAction::Dataset, "◆ DATASET", DatasetLabel);
 
         // Spacer to make panel nicer
         parent.spawn((Node { flex_grow: 1.0, ..Default::default() },));
@@ -171,6 +231,106 @@ fn spawn_param_row(
     });
 }
 
+/// A layer's knob and value with its mute and (for sound layers) solo buttons
+fn spawn_layer_row(parent: &mut ChildBuilder, font: &Handle<Font>, layer: AudioLayer, initial_value: f32) {
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..Default::default() };
+    parent
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(36.0),
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::SpaceBetween,
+                ..Default::default()
+            },
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.17)),
+        ))
+        .with_children(|row| {
+            row.spawn((Text::new(layer.name()), text(15.0), TextColor(Color::WHITE), Node { width: Val::Px(64.0), ..Default::default() }));
+            spawn_knob(row, font, ParamLabel::Layer(layer), initial_value);
+            row.spawn((Text::new(format!("{:.2}", initial_value)), text(14.0), TextColor(Color::srgb(0.9, 0.9, 0.4)), ParamLabel::Layer(layer)));
+            let mut buttons = vec![(SynthButtonAction::Mute(layer), "M")];
+            if layer.is_source() {
+                buttons.push((SynthButtonAction::Solo(layer), "S"));
+            }
+            row.spawn(Node { width: Val::Px(52.0), column_gap: Val::Px(4.0), ..Default::default() }).with_children(|cell| {
+                for (action, label) in buttons {
+                    cell.spawn((
+                        Button,
+                        Node {
+                            width: Val::Px(24.0),
+                            height: Val::Px(22.0),
+                            align_items: AlignItems::Center,
+                            justify_content: JustifyContent::Center,
+                            ..Default::default()
+                        },
+                        BackgroundColor(LAYER_BUTTON_OFF),
+                        action,
+                    ))
+                    .with_child((Text::new(label), text(14.0), TextColor(Color::WHITE)));
+                }
+            });
+        });
+}
+
+/// Buttons cycling the scale's root and mode through automatic and each fixed choice
+fn spawn_key_row(parent: &mut ChildBuilder, font: &Handle<Font>, key: &KeySelection) {
+    parent
+        .spawn(Node {
+            width: Val::Percent(100.0),
+            margin: UiRect::top(Val::Px(8.0)),
+            column_gap: Val::Px(6.0),
+            ..Default::default()
+        })
+        .with_children(|row| {
+            for (action, label) in [(SynthButtonAction::CycleRoot, KeyLabel::Root), (SynthButtonAction::CycleMode, KeyLabel::Mode)] {
+                row.spawn((
+                    Button,
+                    Node {
+                        flex_grow: 1.0,
+                        height: Val::Px(26.0),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..Default::default()
+                    },
+                    BackgroundColor(LAYER_BUTTON_OFF),
+                    action,
+                ))
+                .with_child((
+                    Text::new(label.text(key)),
+                    TextFont { font: font.clone(), font_size: 14.0, ..Default::default() },
+                    TextColor(Color::WHITE),
+                    label,
+                ));
+            }
+        });
+}
+
+/// Button starting and stopping a WAV recording of the audio output, or a DDSP dataset export
+fn spawn_capture_button(parent: &mut ChildBuilder, font: &Handle<Font>, action: SynthButtonAction, text: &str, label: impl Component) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(30.0),
+                margin: UiRect::top(Val::Px(8.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            BackgroundColor(LAYER_BUTTON_OFF),
+            action,
+        ))
+        .with_child((
+            Text::new(text),
+            TextFont { font: font.clone(), font_size: 16.0, ..Default::default() },
+            TextColor(Color::WHITE),
+            label,
+        ));
+}
+
 fn spawn_knob(
     parent: &mut ChildBuilder,
     font: &Handle<Font>,
@@ -209,8 +369,11 @@ fn spawn_knob(
 fn toggle_panel_visibility(
     mut panel_query: Query<&mut Visibility, With<SynthPanel>>,
     keyboard: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
 ) {
-    if keyboard.just_pressed(TOGGLE_KEY) {
+    // Ctrl+P opens the command palette
+    let ctrl = keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight, KeyCode::SuperLeft, KeyCode::SuperRight]);
+    if keys.just_pressed(&keyboard, Action::SynthPanel) && !ctrl {
         if let Ok(mut vis) = panel_query.get_single_mut() {
             *vis = match *vis {
                 Visibility::Hidden => Visibility::Visible,
@@ -224,6 +387,7 @@ fn toggle_panel_visibility(
 fn button_interaction_system(
     mut interaction_query: Query<(&Interaction, &SynthButtonAction), (Changed<Interaction>, With<Button>)>,
     mut params: ResMut<SynthParameters>,
+    settings: Res<ExportSettings>,
 ) {
     for (interaction, action) in &mut interaction_query {
         if *interaction == Interaction::Pressed {
@@ -244,6 +408,16 @@ fn button_interaction_system(
                     params.mix = (params.mix - 0.05).max(0.0);
                     set_hybrid_synthesis_mix(params.mix);
                 }
+                SynthButtonAction::Mute(layer) => {
+                    params.layers.toggle_mute(*layer);
+                }
+                SynthButtonAction::Solo(layer) => {
+                    params.layers.toggle_solo(*layer);
+                }
+                SynthButtonAction::Record => toggle_audio_recording(&settings),
+                SynthButtonAction::Dataset => toggle_dataset_export(&settings),
+                SynthButtonAction::CycleRoot => params.key.cycle_root(),
+                SynthButtonAction::CycleMode => params.key.cycle_mode(),
             }
         }
     }
@@ -253,6 +427,7 @@ fn button_interaction_system(
 fn refresh_param_labels(
     params: Res<SynthParameters>,
     mut query: Query<(&ParamLabel, &mut Text)>,
+    mut key_labels: Query<(&KeyLabel, &mut Text), Without<ParamLabel>>,
 ) {
     if !params.is_changed() {
         return;
@@ -265,6 +440,139 @@ fn refresh_param_labels(
             ParamLabel::Mix => {
                 *text = Text::new(format!("{:.2}", params.mix));
             }
+            ParamLabel::Layer(layer) => {
+                *text = Text::new(format!("{:.2}", params.layers.level(*layer)));
+            }
+        }
+    }
+    for (label, mut text) in &mut key_labels {
+        text.0 = label.text(&params.key);
+    }
+}
+
+// System: light up the mute and solo buttons of muted and soloed layers.
+fn refresh_layer_buttons(params: Res<SynthParameters>, mut buttons: Query<(&SynthButtonAction, &mut BackgroundColor)>) {
+    if !params.is_changed() {
+        return;
+    }
+    for (action, mut colour) in &mut buttons {
+        colour.0 = match *action {
+            SynthButtonAction::Mute(layer) if params.layers.is_muted(layer) => LAYER_MUTED,
+            SynthButtonAction::Solo(layer) if params.layers.solo == Some(layer) => LAYER_SOLOED,
+            SynthButtonAction::Mute(_) | SynthButtonAction::Solo(_) => LAYER_BUTTON_OFF,
+            _ => continue,
+        };
+    }
+}
+
+// System: show whether the audio output is being recorded or exported as a dataset, and how
+// much has been so far.
+fn refresh_record_button(
+    mut buttons: Query<(&SynthButtonAction, &mut BackgroundColor)>,
+    mut record_labels: Query<&mut Text, (With<RecordLabel>, Without<DatasetLabel>)>,
+    mut dataset_labels: Query<&mut Text, With<DatasetLabel>>,
+) {
+    let (record_colour, record_label) = if is_recording() {
+        (LAYER_MUTED, format!("■ STOP {:.0}s", recorded_seconds()))
+    } else {
+        (LAYER_BUTTON_OFF, "● REC".to_string())
+    };
+    let (dataset_colour, dataset_label) = if is_exporting_dataset() {
+        (LAYER_MUTED, format!("■ STOP {} frames", exported_frames()))
+    } else {
+        (LAYER_BUTTON_OFF, "◆ DATASET".to_string())
+    };
+    for (action, mut background) in &mut buttons {
+        let colour = match action {
+            SynthButtonAction::Record => record_colour,
+            SynthButtonAction::Dataset => dataset_colour,
+            _ => continue,
+        };
+        if background.0 != colour {
+            background.0 = colour;
+        }
+    }
+    for mut text in &mut record_labels {
+        if text.0 != record_label {
+            text.0 = record_label.clone();
+        }
+    }
+    for mut text in &mut dataset_labels {
+        if text.0 != dataset_label {
+            text.0 = dataset_label.clone();
+        }
+    }
+}
+
+fn toggle_audio_recording(settings: &ExportSettings) {
+    if is_recording() {
+        finish_audio_recording();
+        return;
+    }
+    let started = settings.next_path("audio", "wav").map_err(|e| e.to_string()).and_then(|path| {
+        start_recording(&path).map_err(|e| e.to_string())?;
+        Ok(path)
+    });
+    match started {
+        Ok(path) => toast(tr!("🎙 Recording audio to {}", path.display())),
+        Err(e) => toast(tr!("🎙 Audio recording failed: {}", e)),
+    }
+}
+
+/// Stop recording the audio output and finish the file off the main thread
+fn finish_audio_recording() {
+    let Some(writer) = stop_recording() else { return };
+    std::thread::spawn(move || match writer.join() {
+        Ok(Ok(recording)) => toast(tr!("🎙 Saved {:.1}s of audio to {}", recording.seconds, recording.path.display())),
+        Ok(Err(e)) => toast(tr!("🎙 Saving audio failed: {}", e)),
+        Err(_) => toast(tr!("🎙 Saving audio failed: {}", "writer thread panicked")),
+    });
+}
+
+fn toggle_dataset_export(settings: &ExportSettings) {
+    if is_exporting_dataset() {
+        finish_dataset_export();
+        return;
+    }
+    let started = settings.next_path("ddsp-dataset", "safetensors").map_err(|e| e.to_string()).and_then(|path| {
+        start_dataset(&path).map_err(|e| e.to_string())?;
+        Ok(path)
+    });
+    match started {
+        Ok(path) => toast(tr!("🧠 Collecting DDSP training data in {}", path.display())),
+        Err(e) => toast(tr!("🧠 Dataset export failed: {}", e)),
+    }
+}
+
+/// Stop the dataset export and save the file off the main thread
+fn finish_dataset_export() {
+    let Some(writer) = stop_dataset() else { return };
+    std::thread::spawn(move || match writer.join() {
+        Ok(Ok(dataset)) => toast(tr!("🧠 Saved {} frames of DDSP training data to {}", dataset.frames, dataset.path.display())),
+        Ok(Err(e)) => toast(tr!("🧠 Dataset export failed: {}", e)),
+        Err(_) => toast(tr!("🧠 Dataset export failed: {}", "writer thread panicked")),
+    });
+}
+
+// System: mute keys for each layer, with Shift to solo (Alt+digits belong to the grid layers).
+fn layer_hotkeys(keyboard: Res<ButtonInput<KeyCode>>, keys: Res<Keybindings>, mut params: ResMut<SynthParameters>) {
+    if keyboard.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]) {
+        return;
+    }
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    for (action, layer) in LAYER_KEYS {
+        if !keys.just_pressed(&keyboard, action) {
+            continue;
+        }
+        if !shift {
+            let muted = params.layers.toggle_mute(layer);
+            toast(if muted { tr!("🔇 {} muted", layer.name()) } else { tr!("🔊 {} unmuted", layer.name()) });
+        } else if !layer.is_source() {
+            toast(tr!("🎧 {} can't be soloed", layer.name()));
+        } else if params.layers.toggle_solo(layer) {
+            toast(tr!("🎧 {} solo", layer.name()));
+        } else {
+            toast(tr!("🎧 Solo off"));
         }
     }
 }
@@ -294,6 +602,10 @@ fn knob_scroll_system(
                 ParamLabel::Mix => {
                     params.mix = (params.mix + delta).clamp(0.0, 1.0);
                 }
+                ParamLabel::Layer(layer) => {
+                    let level = params.layers.level(layer) + delta;
+                    params.layers.set_level(layer, level);
+                }
             }
         }
     }
@@ -309,6 +621,7 @@ fn knob_visual_system(
         let val = match knob.param {
             ParamLabel::Volume => params.volume / 2.0, // 0..1
             ParamLabel::Mix => params.mix,             // 0..1
+            ParamLabel::Layer(layer) => params.layers.level(layer),
         };
         // Map value to angle (-135° .. +135°)
         let angle = (-135.0_f32).to_radians() + val * 270.0_f32.to_radians();
@@ -321,9 +634,22 @@ fn knob_visual_system(
 }
 
 // Parameter → engine sync every frame if changed.
-fn push_params_to_engine(params: Res<SynthParameters>) {
+fn push_params_to_engine(
+    params: Res<SynthParameters>,
+    in_game: Option<Res<State<InGame>>>,
+    kira: Option<Res<KiraManager>>,
+) {
     if params.is_changed() {
         set_hybrid_volume(params.volume);
         set_hybrid_synthesis_mix(params.mix);
     }
+    // The engine is created afresh on entering a game, without the layer levels or key
+    let engine_started = in_game.is_some_and(|state| state.is_added());
+    if params.is_changed() || engine_started {
+        set_hybrid_layer_mix(params.layers);
+        set_hybrid_key(params.key);
+        if let Some(kira) = kira {
+            kira.set_volume(MixTrack::Groove, params.layers.gain(AudioLayer::Groove));
+        }
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,464 @@
+//! Colour themes.
+//!
+//! A `Theme` holds every colour the game draws with: one per cell state, the window
+//! background, the in-game HUD and the start screen. Themes live as TOML files in
+//! `THEME_DIR` and are picked with `theme = "<name>"` in the config file, so the blood-red
+//! look can be swapped without recompiling. Colours are written as `#rrggbb` or `#rrggbbaa`
+//! hex strings, and any key a theme file leaves out keeps its built-in value.
+//!
+//! `age_gradient` lists the colours a cell moves through from newborn to ancient when
+//! age colouring is switched on (`CellRenderConfig::generation_colors`).
+//!
+//! In game, U steps through the themes in `CellRenderConfig::themes` (Shift+U backwards).
+//! Cells, background and HUD recolour on the spot, and the choice is written back to the
+//! config on exit.
+//!
+//! An optional `[backdrop]` section puts a parallax starfield or fog behind the grid (see
+//! `backdrop`).
+//!
+//! `color_vision` in the config swaps the colours of the multi-state rules and the HUD
+//! accents for a palette that stays distinguishable with a colour vision deficiency (see
+//! `ColorVision`), on top of whichever theme is loaded.
+
+use std::path::Path;
+use bevy::color::ColorToPacked;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::backdrop::Backdrop;
+use crate::cell_renderer::{base_color_for_state, CellRenderConfig};
+use crate::keybindings::{Action, Keybindings};
+use crate::preferences::Preferences;
+use crate::ui::toast;
+use crate::tr;
+use crate::CellState;
+
+/// Folder theme files are read from
+pub const THEME_DIR: &str = "themes";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ThemeError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("could not read theme: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+/// A colour that reads and writes as a hex string
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ThemeColor(pub Color);
+
+impl ThemeColor {
+    pub fn srgb(r: f32, g: f32, b: f32) -> Self {
+        Self(Color::srgb(r, g, b))
+    }
+
+    pub fn srgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self(Color::srgba(r, g, b, a))
+    }
+}
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Srgba::hex(text.trim()).map(|c| Self(c.into())).map_err(|e| format!("bad colour '{}': {}", text, e))
+    }
+}
+
+impl From<ThemeColor> for String {
+    fn from(colour: ThemeColor) -> Self {
+        let [r, g, b, a] = colour.0.to_srgba().to_u8_array();
+        if a == 255 {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CellColors {
+    pub alive: ThemeColor,
+    pub dying: ThemeColor,
+    pub wire: ThemeColor,
+    pub electron_head: ThemeColor,
+    pub electron_tail: ThemeColor,
+    pub species_a: ThemeColor,
+    pub species_b: ThemeColor,
+}
+
+impl Default for CellColors {
+    fn default() -> Self {
+        let base = |state| ThemeColor(base_color_for_state(state));
+        Self {
+            alive: base(CellState::Alive),
+            dying: base(CellState::Dying),
+            wire: base(CellState::Wire),
+            electron_head: base(CellState::ElectronHead),
+            electron_tail: base(CellState::ElectronTail),
+            species_a: base(CellState::SpeciesA),
+            species_b: base(CellState::SpeciesB),
+        }
+    }
+}
+
+impl CellColors {
+    pub fn color(&self, state: CellState) -> Color {
+        match state {
+            CellState::Alive => self.alive.0,
+            CellState::Dying => self.dying.0,
+            CellState::Wire => self.wire.0,
+            CellState::ElectronHead => self.electron_head.0,
+            CellState::ElectronTail => self.electron_tail.0,
+            CellState::SpeciesA => self.species_a.0,
+            CellState::SpeciesB => self.species_b.0,
+            CellState::Dead => Color::BLACK,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HudColors {
+    /// Stats panel background
+    pub panel: ThemeColor,
+    pub text: ThemeColor,
+    /// Volume readout
+    pub volume: ThemeColor,
+    /// Zoom readout
+    pub zoom: ThemeColor,
+    /// Controls panel background
+    pub help_panel: ThemeColor,
+    pub help_text: ThemeColor,
+}
+
+impl Default for HudColors {
+    fn default() -> Self {
+        Self {
+            panel: ThemeColor::srgba(0.0, 0.0, 0.0, 0.7),
+            text: ThemeColor(Color::WHITE),
+            volume: ThemeColor::srgb(1.0, 0.8, 0.2),
+            zoom: ThemeColor::srgb(0.7, 0.9, 1.0),
+            help_panel: ThemeColor::srgba(0.0, 0.0, 0.0, 0.5),
+            help_text: ThemeColor::srgba(0.8, 0.8, 0.8, 0.9),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StartScreenColors {
+    pub background: ThemeColor,
+    pub title: ThemeColor,
+    pub subtitle: ThemeColor,
+    /// Mode selector box
+    pub panel: ThemeColor,
+    pub panel_border: ThemeColor,
+    pub heading: ThemeColor,
+    /// Rule name and arrow glyphs
+    pub accent: ThemeColor,
+    pub description: ThemeColor,
+    /// Arrow buttons
+    pub button: ThemeColor,
+    pub button_border: ThemeColor,
+    pub button_hover: ThemeColor,
+    pub start_button: ThemeColor,
+    pub start_button_border: ThemeColor,
+    pub start_button_hover: ThemeColor,
+    pub start_text: ThemeColor,
+    /// Any button while held down
+    pub pressed: ThemeColor,
+    /// Instructions along the bottom
+    pub hint: ThemeColor,
+    pub hint_faint: ThemeColor,
+}
+
+impl Default for StartScreenColors {
+    fn default() -> Self {
+        Self {
+            background: ThemeColor::srgb(0.05, 0.0, 0.02),
+            title: ThemeColor::srgb(0.8, 0.1, 0.1),
+            subtitle: ThemeColor::srgb(0.4, 0.0, 0.0),
+            panel: ThemeColor::srgba(0.0, 0.0, 0.0, 0.8),
+            panel_border: ThemeColor::srgb(0.3, 0.0, 0.0),
+            heading: ThemeColor::srgb(0.7, 0.0, 0.0),
+            accent: ThemeColor::srgb(0.9, 0.2, 0.2),
+            description: ThemeColor::srgb(0.5, 0.1, 0.1),
+            button: ThemeColor::srgba(0.1, 0.0, 0.0, 0.9),
+            button_border: ThemeColor::srgb(0.2, 0.0, 0.0),
+            button_hover: ThemeColor::srgb(0.2, 0.02, 0.02),
+            start_button: ThemeColor::srgba(0.2, 0.0, 0.0, 0.9),
+            start_button_border: ThemeColor::srgb(0.4, 0.0, 0.0),
+            start_button_hover: ThemeColor::srgb(0.3, 0.03, 0.03),
+            start_text: ThemeColor::srgb(1.0, 0.3, 0.3),
+            pressed: ThemeColor::srgb(0.4, 0.05, 0.05),
+            hint: ThemeColor::srgb(0.4, 0.1, 0.1),
+            hint_faint: ThemeColor::srgb(0.3, 0.05, 0.05),
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub name: String,
+    /// Window clear colour behind the grid
+    pub background: ThemeColor,
+    /// Live cell colours by age, newborn first
+    pub age_gradient: Vec<ThemeColor>,
+    /// Flash tint of newborn cells, and what glowing cells bloom towards
+    pub glow: ThemeColor,
+    /// Ghosts left by dead cells while death trails are on, at full strength
+    pub trail: ThemeColor,
+    /// Cell boundaries of the grid-line overlay (major lines are drawn twice as opaque)
+    pub grid_lines: ThemeColor,
+    pub cells: CellColors,
+    pub hud: HudColors,
+    pub start_screen: StartScreenColors,
+    pub backdrop: Backdrop,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: "blood".to_string(),
+            background: ThemeColor(ClearColor::default().0),
+            age_gradient: vec![
+                ThemeColor::srgb(1.0, 1.0, 1.0),
+                ThemeColor::srgb(1.0, 0.42, 0.42),
+                ThemeColor::srgb(0.76, 0.09, 0.09),
+                ThemeColor::srgb(0.3, 0.02, 0.02),
+            ],
+            glow: ThemeColor::srgb(1.0, 0.85, 0.85),
+            trail: ThemeColor::srgba(0.8, 0.1, 0.1, 0.5),
+            grid_lines: ThemeColor::srgba(1.0, 1.0, 1.0, 0.08),
+            cells: CellColors::default(),
+            hud: HudColors::default(),
+            start_screen: StartScreenColors::default(),
+            backdrop: Backdrop::default(),
+        }
+    }
+}
+
+impl Theme {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ThemeError> {
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Load `THEME_DIR/<name>.toml`, falling back to the built-in theme if it can't be read
+    pub fn named(name: &str) -> Self {
+        let path = Path::new(THEME_DIR).join(format!("{}.toml", name));
+        match Self::load(&path) {
+            Ok(mut theme) => {
+                if theme.name.is_empty() || theme.name == Theme::default().name {
+                    theme.name = name.to_string();
+                }
+                theme
+            }
+            Err(e) => {
+                toast(tr!("🎨 Could not load theme {}: {}; using the built-in theme", path.display(), e));
+                Self::default()
+            }
+        }
+    }
+
+    /// Names of the theme files in `THEME_DIR`, sorted, always including `current`
+    pub fn available(current: &str) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(THEME_DIR)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok()?.path().file_name()?.to_str()?.strip_suffix(".toml").map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !names.iter().any(|name| name == current) {
+            names.push(current.to_string());
+        }
+        names.sort();
+        names
+    }
+
+    /// Colour of a live cell `age` generations old. The gradient is walked on a log scale
+    /// so the first few generations stay distinguishable, reaching its end at `span`.
+    pub fn age_color(&self, age: u64, span: u64) -> Color {
+        let stops = &self.age_gradient;
+        let (Some(first), Some(last)) = (stops.first(), stops.last()) else { return self.cells.alive.0 };
+        if stops.len() == 1 || age == 0 {
+            return first.0;
+        }
+        let t = ((age as f32).ln_1p() / (span.max(1) as f32).ln_1p()).min(1.0);
+        if t >= 1.0 {
+            return last.0;
+        }
+        let position = t * (stops.len() - 1) as f32;
+        let index = position.floor() as usize;
+        stops[index].0.mix(&stops[index + 1].0, position - index as f32)
+    }
+}
+
+/// Colour vision the cell and accent colours are chosen for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorVision {
+    /// The theme's own colours
+    #[default]
+    Normal,
+    /// Red-green, weak green cones (the most common)
+    Deuteranopia,
+    /// Red-green, weak red cones: reds look dark, so none are relied on
+    Protanopia,
+    /// Blue-yellow: pairs are told apart by red against cyan instead
+    Tritanopia,
+}
+
+impl ColorVision {
+    pub const ALL: [ColorVision; 4] = [ColorVision::Normal, ColorVision::Deuteranopia, ColorVision::Protanopia, ColorVision::Tritanopia];
+
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&vision| vision == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorVision::Normal => "Normal",
+            ColorVision::Deuteranopia => "Deuteranopia",
+            ColorVision::Protanopia => "Protanopia",
+            ColorVision::Tritanopia => "Tritanopia",
+        }
+    }
+
+    /// Colours for dying, wire, electron head, electron tail, species A, species B, and the
+    /// volume and zoom readouts. Built from the Okabe-Ito palette, with the states that sit
+    /// next to each other on the grid (the two species, head and tail) far apart in lightness.
+    fn palette(self) -> Option<[u32; 8]> {
+        match self {
+            ColorVision::Normal => None,
+            ColorVision::Deuteranopia => Some([0xcc79a7, 0xf0e442, 0x0072b2, 0xd55e00, 0xe69f00, 0x56b4e9, 0xf0e442, 0x56b4e9]),
+            ColorVision::Protanopia => Some([0xcc79a7, 0xe69f00, 0x56b4e9, 0x004d80, 0xf0e442, 0x0072b2, 0xf0e442, 0x56b4e9]),
+            ColorVision::Tritanopia => Some([0x8c8c8c, 0xf5a0b5, 0x00c8c8, 0xb3261e, 0xe8384f, 0x00a3a3, 0xff8c8c, 0x66d9d9]),
+        }
+    }
+}
+
+impl Theme {
+    /// This theme with its multi-state cell colours and HUD accents replaced for `vision`
+    pub fn with_color_vision(mut self, vision: ColorVision) -> Self {
+        let Some(palette) = vision.palette() else { return self };
+        let [dying, wire, head, tail, species_a, species_b, volume, zoom] =
+            palette.map(|rgb| ThemeColor(Color::srgb_u8((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)));
+        self.cells.dying = dying;
+        self.cells.wire = wire;
+        self.cells.electron_head = head;
+        self.cells.electron_tail = tail;
+        self.cells.species_a = species_a;
+        self.cells.species_b = species_b;
+        self.hud.volume = volume;
+        self.hud.zoom = zoom;
+        self
+    }
+}
+
+/// Switch to the next (or with Shift, previous) theme in `CellRenderConfig::themes`
+pub fn cycle_theme(
+    (keyboard_input, keys): (Res<ButtonInput<KeyCode>>, Res<Keybindings>),
+    config: Res<CellRenderConfig>,
+    mut theme: ResMut<Theme>,
+    mut clear_color: ResMut<ClearColor>,
+    (preferences, preset): (Option<ResMut<Preferences>>, Option<Res<crate::presets::ActivePreset>>),
+) {
+    if !keys.just_pressed(&keyboard_input, Action::NextTheme) {
+        return;
+    }
+    let backwards = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if let Some(name) = step_theme(backwards, &config, &mut theme, &mut clear_color, preset.as_deref()) {
+        if let Some(mut preferences) = preferences {
+            preferences.config.theme = name;
+        }
+    }
+}
+
+/// Switch to the next of `config.themes` (the previous one if `backwards`), keeping the rule
+/// preset's look and the colour vision palette. Returns the new theme's name.
+pub fn step_theme(
+    backwards: bool,
+    config: &CellRenderConfig,
+    theme: &mut Theme,
+    clear_color: &mut ClearColor,
+    preset: Option<&crate::presets::ActivePreset>,
+) -> Option<String> {
+    if config.themes.is_empty() {
+        return None;
+    }
+    let count = config.themes.len();
+    let current = config.themes.iter().position(|name| *name == theme.name).unwrap_or(0);
+    let next = if backwards { (current + count - 1) % count } else { (current + 1) % count };
+    let name = &config.themes[next];
+
+    *theme = match preset {
+        Some(preset) => preset.theme(name, config.color_vision),
+        None => Theme::named(name).with_color_vision(config.color_vision),
+    };
+    theme.name = name.clone();
+    clear_color.0 = theme.background.0;
+    toast(tr!("🎨 Theme: {}", name));
+    Some(name.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_theme_files_keep_the_defaults() {
+        let theme: Theme = toml::from_str(
+            r##"
+            name = "test"
+            background = "#102030"
+            [cells]
+            alive = "#ff000080"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(theme.background, ThemeColor(Color::srgb_u8(0x10, 0x20, 0x30)));
+        assert_eq!(theme.cells.color(CellState::Alive), Color::srgba_u8(255, 0, 0, 128));
+        assert_eq!(theme.cells.wire, CellColors::default().wire);
+        assert_eq!(theme.start_screen, StartScreenColors::default());
+
+        // Age gradient runs from the first colour to the last
+        let mut aged = Theme::default();
+        let (white, black) = (ThemeColor::srgb(1.0, 1.0, 1.0), ThemeColor::srgb(0.0, 0.0, 0.0));
+        aged.age_gradient = vec![white, black];
+        assert_eq!(aged.age_color(0, 100), white.0);
+        assert_eq!(aged.age_color(100, 100), black.0);
+        assert_eq!(aged.age_color(5_000, 100), black.0);
+        let middle = aged.age_color(9, 99).to_srgba().red;
+        assert!((middle - 0.5).abs() < 0.01, "{}", middle);
+
+        // Colours survive a round trip through their hex form
+        assert_eq!(String::from(theme.cells.alive), "#ff000080");
+        assert!(toml::from_str::<Theme>("background = \"red\"").is_err());
+
+        // Shipped theme files parse
+        for entry in std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/themes")).unwrap() {
+            let path = entry.unwrap().path();
+            Theme::load(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        }
+    }
+
+    #[test]
+    fn color_vision_palettes_replace_state_colours() {
+        assert_eq!(Theme::default().with_color_vision(ColorVision::Normal), Theme::default());
+        for vision in &ColorVision::ALL[1..] {
+            let theme = Theme::default().with_color_vision(*vision);
+            assert_ne!(theme.cells.species_a, theme.cells.species_b);
+            assert_ne!(theme.cells.electron_head, theme.cells.electron_tail);
+            // Live cells and the background keep the theme's colours
+            assert_eq!(theme.cells.alive, Theme::default().cells.alive);
+            assert_eq!(theme.background, Theme::default().background);
+        }
+        assert_eq!(ColorVision::Tritanopia.next(), ColorVision::Normal);
+    }
+}
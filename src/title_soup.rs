@@ -0,0 +1,169 @@
+//! Ambient soup behind the start screen.
+//!
+//! While the start screen is up, a random Conway soup runs in a small box behind the menu,
+//! drawn faintly in the title colour one texel per cell, so the title screen shows the game
+//! rather than describing it. Cells leaving the box are dropped; once the soup has burnt down
+//! to ash, or after `RESEED_GENERATIONS`, a fresh one is sown. The hybrid synth is fed this
+//! soup's features (see `update_start_screen_audio` in `main`), so the menu music follows
+//! real activity instead of silence. In reduced-motion mode the soup stays still.
+
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::cell_renderer::CellRenderConfig;
+use crate::infinite_grid::InfiniteGrid;
+use crate::start_screen::GameState;
+use crate::theme::Theme;
+use crate::CellState;
+
+/// Size of the soup's box, in cells (and texels of its image)
+pub const SOUP_WIDTH: i32 = 160;
+pub const SOUP_HEIGHT: i32 = 90;
+/// Probability that a cell starts alive
+const SOUP_DENSITY: f64 = 0.3;
+const STEP_SECONDS: f32 = 0.12;
+/// Generations before a fresh soup is sown regardless
+const RESEED_GENERATIONS: u64 = 600;
+/// A soup thinner than this fraction of the box is considered burnt out
+const ASH_FRACTION: f32 = 0.02;
+/// Opacity of live cells over the start screen background
+const SOUP_ALPHA: f32 = 0.18;
+
+/// The soup, and the image it is drawn into
+#[derive(Resource)]
+pub struct TitleSoup {
+    pub grid: InfiniteGrid,
+    /// Generations since it was sown
+    pub generation: u64,
+    pub image: Handle<Image>,
+    rng: StdRng,
+}
+
+impl TitleSoup {
+    /// Replace the soup with a fresh random one
+    fn sow(&mut self) {
+        self.grid = sow_soup(self.rng.gen());
+        self.generation = 0;
+    }
+
+    fn is_spent(&self) -> bool {
+        self.generation >= RESEED_GENERATIONS
+            || (self.grid.population() as f32) < (SOUP_WIDTH * SOUP_HEIGHT) as f32 * ASH_FRACTION
+    }
+}
+
+/// A random soup filling the box, from `seed`
+pub fn sow_soup(seed: u64) -> InfiniteGrid {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut grid = InfiniteGrid::new();
+    for y in 0..SOUP_HEIGHT {
+        for x in 0..SOUP_WIDTH {
+            if rng.gen_bool(SOUP_DENSITY) {
+                grid.set_alive(x, y);
+            }
+        }
+    }
+    grid
+}
+
+/// Drop the cells, of any state, that have left the `width` by `height` box at the origin
+pub fn clip_to_box(grid: &mut InfiniteGrid, width: i32, height: i32) {
+    let outside: Vec<(i32, i32)> = grid
+        .cells()
+        .filter(|&(x, y, _)| !(0..width).contains(&x) || !(0..height).contains(&y))
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    for (x, y) in outside {
+        grid.set(x, y, CellState::Dead);
+    }
+}
+
+/// RGBA texels of `grid`'s box in `color`, top row first
+fn soup_pixels(grid: &InfiniteGrid, color: Color) -> Vec<u8> {
+    let texel = color.with_alpha(SOUP_ALPHA).to_srgba().to_u8_array();
+    let mut pixels = vec![0; (SOUP_WIDTH * SOUP_HEIGHT * 4) as usize];
+    for (x, y) in grid.alive_cells() {
+        // Grid y points up, image rows go down
+        let index = (((SOUP_HEIGHT - 1 - y) * SOUP_WIDTH + x) * 4) as usize;
+        if let Some(pixel) = pixels.get_mut(index..index + 4) {
+            pixel.copy_from_slice(&texel);
+        }
+    }
+    pixels
+}
+
+pub struct TitleSoupPlugin;
+
+impl Plugin for TitleSoupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, create_title_soup)
+            .add_systems(OnEnter(GameState::StartScreen), sow_title_soup)
+            .add_systems(Update, (step_title_soup, draw_title_soup).chain().run_if(in_state(GameState::StartScreen)));
+    }
+}
+
+fn create_title_soup(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let size = Extent3d { width: SOUP_WIDTH as u32, height: SOUP_HEIGHT as u32, depth_or_array_layers: 1 };
+    let pixels = vec![0; (SOUP_WIDTH * SOUP_HEIGHT * 4) as usize];
+    let mut image = Image::new(size, TextureDimension::D2, pixels, TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::default());
+    image.sampler = ImageSampler::nearest();
+    let mut rng = StdRng::from_entropy();
+    commands.insert_resource(TitleSoup { grid: sow_soup(rng.gen()), generation: 0, image: images.add(image), rng });
+}
+
+fn sow_title_soup(mut soup: ResMut<TitleSoup>) {
+    soup.sow();
+}
+
+fn step_title_soup(time: Res<Time>, render: Res<CellRenderConfig>, mut soup: ResMut<TitleSoup>, mut since_step: Local<f32>) {
+    if render.reduced_motion {
+        return;
+    }
+    // The soup only counts as changed when it steps, so it is redrawn (and heard) once per step
+    *since_step += time.delta_secs();
+    if *since_step < STEP_SECONDS {
+        return;
+    }
+    *since_step = 0.0;
+    if soup.is_spent() {
+        soup.sow();
+        return;
+    }
+    soup.grid.step_conway();
+    clip_to_box(&mut soup.grid, SOUP_WIDTH, SOUP_HEIGHT);
+    soup.generation += 1;
+}
+
+fn draw_title_soup(soup: Res<TitleSoup>, theme: Res<Theme>, mut images: ResMut<Assets<Image>>) {
+    if !soup.is_changed() && !theme.is_changed() {
+        return;
+    }
+    if let Some(image) = images.get_mut(&soup.image) {
+        image.data = soup_pixels(&soup.grid, theme.start_screen.title.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soups_are_reproducible_and_stay_in_their_box() {
+        let mut grid = sow_soup(7);
+        let mut cells = grid.get_alive_cells_snapshot();
+        let mut again = sow_soup(7).get_alive_cells_snapshot();
+        cells.sort_unstable();
+        again.sort_unstable();
+        assert_eq!(cells, again);
+        assert!(!cells.is_empty());
+        for _ in 0..50 {
+            grid.step_conway();
+            clip_to_box(&mut grid, SOUP_WIDTH, SOUP_HEIGHT);
+        }
+        assert!(grid.alive_cells().all(|(x, y)| (0..SOUP_WIDTH).contains(&x) && (0..SOUP_HEIGHT).contains(&y)));
+        assert_eq!(soup_pixels(&grid, Color::WHITE).len(), (SOUP_WIDTH * SOUP_HEIGHT * 4) as usize);
+    }
+}
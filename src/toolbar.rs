@@ -0,0 +1,253 @@
+//! Tool selection toolbar.
+//!
+//! A row of buttons above the speed slider, one per `Tool`, each with a small pixel icon
+//! drawn at startup. Clicking a button makes its tool the `ActiveTool`, the same as cycling
+//! with T; the active tool's button is highlighted, including the stamp while a pattern is
+//! held. The stamp button picks the last pattern put away back up, or puts the held one
+//! away. The toolbar hides along with the HUD.
+
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::ui::{RelativeCursorPosition, UiSystem};
+use crate::keybindings::{key_name, Action, Keybindings};
+use crate::settings_menu::button_color;
+use crate::start_screen::{GameState, InGame};
+use crate::theme::Theme;
+use crate::tools::{ActiveTool, StampState, Tool};
+use crate::ui::{toast, HudColor, PointerCapture, UiState};
+use crate::tr;
+
+/// Side of an icon, in texels
+pub const ICON_SIZE: usize = 9;
+/// Logical pixels per icon texel
+const ICON_SCALE: f32 = 3.0;
+const BUTTON_SIZE: f32 = 34.0;
+/// Clear of the speed slider below
+const TOOLBAR_BOTTOM: f32 = 110.0;
+
+/// The icon of `tool`, a row per line from the top, `#` for a lit texel
+pub fn icon(tool: Tool) -> [&'static str; ICON_SIZE] {
+    match tool {
+        Tool::Draw => [
+            ".........",
+            "...###...",
+            "..#####..",
+            ".#######.",
+            ".#######.",
+            ".#######.",
+            "..#####..",
+            "...###...",
+            ".........",
+        ],
+        Tool::Erase => [
+            "#.......#",
+            ".#.....#.",
+            "..#...#..",
+            "...#.#...",
+            "....#....",
+            "...#.#...",
+            "..#...#..",
+            ".#.....#.",
+            "#.......#",
+        ],
+        Tool::Line => [
+            ".......##",
+            ".......##",
+            "......#..",
+            ".....#...",
+            "....#....",
+            "...#.....",
+            "..#......",
+            "##.......",
+            "##.......",
+        ],
+        Tool::Fill => [
+            ".........",
+            ".#######.",
+            ".#######.",
+            ".#######.",
+            ".#######.",
+            ".#######.",
+            ".#######.",
+            ".#######.",
+            ".........",
+        ],
+        Tool::Select => [
+            "#.#.#.#.#",
+            ".........",
+            "#.......#",
+            ".........",
+            "#.......#",
+            ".........",
+            "#.......#",
+            ".........",
+            "#.#.#.#.#",
+        ],
+        // A glider
+        Tool::Stamp => [
+            "...##....",
+            "...##....",
+            ".........",
+            "......##.",
+            "......##.",
+            ".........",
+            "##.##.##.",
+            "##.##.##.",
+            ".........",
+        ],
+    }
+}
+
+/// White RGBA texels of `tool`'s icon, to be tinted by the `ImageNode`
+fn icon_image(tool: Tool) -> Image {
+    let pixels = icon(tool)
+        .iter()
+        .flat_map(|row| row.bytes())
+        .flat_map(|texel| if texel == b'#' { [255; 4] } else { [0; 4] })
+        .collect();
+    let size = Extent3d { width: ICON_SIZE as u32, height: ICON_SIZE as u32, depth_or_array_layers: 1 };
+    let mut image = Image::new(size, TextureDimension::D2, pixels, TextureFormat::Rgba8UnormSrgb, RenderAssetUsages::default());
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+#[derive(Component)]
+struct ToolbarPanel;
+
+#[derive(Component)]
+struct ToolButton(Tool);
+
+#[derive(Component)]
+struct ToolIcon;
+
+pub struct ToolbarPlugin;
+
+impl Plugin for ToolbarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(InGame), spawn_toolbar)
+            .add_systems(OnExit(InGame), despawn_toolbar)
+            // Before Update, so a click on the toolbar never reaches the painting tools
+            .add_systems(PreUpdate, toolbar_capture.after(UiSystem::Focus).run_if(in_state(GameState::Playing)))
+            .add_systems(Update, (tool_buttons, update_toolbar).chain().run_if(in_state(GameState::Playing)));
+    }
+}
+
+fn spawn_toolbar(mut commands: Commands, theme: Res<Theme>, mut images: ResMut<Assets<Image>>) {
+    let width = Tool::ALL.len() as f32 * (BUTTON_SIZE + 4.0) + 8.0;
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(TOOLBAR_BOTTOM),
+                left: Val::Percent(50.0),
+                margin: UiRect::left(Val::Px(-width / 2.0)),
+                width: Val::Px(width),
+                flex_direction: FlexDirection::Row,
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+            BackgroundColor(theme.hud.panel.0),
+            HudColor::Panel,
+            Visibility::Hidden,
+            RelativeCursorPosition::default(),
+            ToolbarPanel,
+        ))
+        .with_children(|bar| {
+            for tool in Tool::ALL {
+                bar.spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(BUTTON_SIZE),
+                        height: Val::Px(BUTTON_SIZE),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BackgroundColor(button_color(Interaction::None)),
+                    ToolButton(tool),
+                ))
+                .with_child((
+                    ImageNode { image: images.add(icon_image(tool)), color: theme.hud.text.0, ..default() },
+                    Node { width: Val::Px(ICON_SIZE as f32 * ICON_SCALE), height: Val::Px(ICON_SIZE as f32 * ICON_SCALE), ..default() },
+                    ToolIcon,
+                ));
+            }
+        });
+}
+
+fn despawn_toolbar(mut commands: Commands, panels: Query<Entity, With<ToolbarPanel>>) {
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Keep clicks on the toolbar from painting the grid underneath
+fn toolbar_capture(panels: Query<(&RelativeCursorPosition, &Visibility), With<ToolbarPanel>>, mut capture: ResMut<PointerCapture>) {
+    capture.captured |= panels.iter().any(|(cursor, visibility)| *visibility != Visibility::Hidden && cursor.mouse_over());
+}
+
+fn tool_buttons(
+    buttons: Query<(&Interaction, &ToolButton), Changed<Interaction>>,
+    mut active: ResMut<ActiveTool>,
+    mut stamp: ResMut<StampState>,
+    keys: Res<Keybindings>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        // The stamp button puts a held pattern away again
+        let tool = if button.0 == Tool::Stamp && active.tool == Tool::Stamp { Tool::Draw } else { button.0 };
+        if active.select(tool, &mut stamp) {
+            toast(tr!("🧰 Tool: {}", active.tool.name()));
+        } else {
+            toast(tr!("🧰 No pattern to stamp: pick one with {} or the number keys", key_name(keys.key(Action::Browser))));
+        }
+    }
+}
+
+fn update_toolbar(
+    (active, ui_state, theme): (Res<ActiveTool>, Res<UiState>, Res<Theme>),
+    mut panels: Query<&mut Visibility, With<ToolbarPanel>>,
+    mut buttons: Query<(&Interaction, &ToolButton, &mut BackgroundColor)>,
+    mut icons: Query<&mut ImageNode, With<ToolIcon>>,
+) {
+    for mut visibility in panels.iter_mut() {
+        visibility.set_if_neq(if ui_state.hud_visible { Visibility::Visible } else { Visibility::Hidden });
+    }
+    for (interaction, button, mut colour) in &mut buttons {
+        let shown = if button.0 == active.tool { theme.hud.zoom.0.with_alpha(0.6) } else { button_color(*interaction) };
+        if colour.0 != shown {
+            colour.0 = shown;
+        }
+    }
+    if theme.is_changed() {
+        for mut image in &mut icons {
+            image.color = theme.hud.text.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icons_are_square_and_cycle_skips_the_stamp() {
+        for tool in Tool::ALL {
+            assert!(icon(tool).iter().all(|row| row.len() == ICON_SIZE), "{:?}", tool);
+        }
+        let mut tool = Tool::Draw;
+        let mut seen = Vec::new();
+        for _ in 0..Tool::ALL.len() - 1 {
+            seen.push(tool);
+            tool = tool.next();
+        }
+        assert_eq!(tool, Tool::Draw);
+        assert_eq!(seen, Tool::ALL[..Tool::ALL.len() - 1]);
+    }
+}
@@ -0,0 +1,192 @@
+//! Editing tools for placing cells on the grid.
+//!
+//! * `brush` — freehand brush shapes and straight-line strokes.
+//! * `clipboard` — copy/paste patterns as RLE through the OS clipboard.
+//! * `drop` — pick up pattern files dropped onto the window.
+//! * `edge_pan` — scroll the view while a stroke is dragged to the window edge.
+//! * `fill` — capped flood fill of a connected region.
+//! * `ghost` — translucent preview of what the next click would paint.
+//! * `select` — drag out a rectangle of cells for copying.
+//! * `stamp` — place library patterns with rotation/mirroring.
+//! * `symmetry` — mirror brush strokes across axes through a chosen origin.
+
+use bevy::prelude::*;
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::{GameState, InGame, RuleType};
+use crate::ui::toast;
+use crate::tr;
+use crate::CellState;
+
+pub mod brush;
+pub mod clipboard;
+pub mod drop;
+pub mod edge_pan;
+pub mod fill;
+pub mod ghost;
+pub mod select;
+pub mod stamp;
+pub mod symmetry;
+
+pub use brush::{apply_brush, line_cells, BrushSettings, BrushShape, MAX_BRUSH_SIZE, SPRAY_DENSITY_STEP};
+pub use fill::{flood_fill, FillSettings};
+pub use select::Selection;
+pub use stamp::{Orientation, StampPlugin, StampState};
+pub use symmetry::{Symmetry, SymmetryMode};
+
+/// What a mouse click on the grid does
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tool {
+    /// Paint with the brush; right click erases or places the alternate state
+    #[default]
+    Draw,
+    /// Clear cells with the brush, whichever button
+    Erase,
+    /// Paint a straight line from where the button went down to where it is released
+    Line,
+    Fill,
+    Select,
+    /// Place the held pattern
+    Stamp,
+}
+
+impl Tool {
+    /// Every tool, in toolbar order
+    pub const ALL: [Tool; 6] = [Tool::Draw, Tool::Erase, Tool::Line, Tool::Fill, Tool::Select, Tool::Stamp];
+
+    /// The tool after this one for the cycle key, which skips the stamp as it needs a pattern
+    pub fn next(self) -> Self {
+        match self {
+            Tool::Draw => Tool::Erase,
+            Tool::Erase => Tool::Line,
+            Tool::Line => Tool::Fill,
+            Tool::Fill => Tool::Select,
+            Tool::Select | Tool::Stamp => Tool::Draw,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Tool::Draw => tr!("Draw"),
+            Tool::Erase => tr!("Erase"),
+            Tool::Line => tr!("Line"),
+            Tool::Fill => tr!("Fill"),
+            Tool::Select => tr!("Select"),
+            Tool::Stamp => tr!("Stamp"),
+        }
+    }
+
+    /// Whether the tool paints with the brush (see `handle_mouse_input`)
+    pub fn paints(self) -> bool {
+        matches!(self, Tool::Draw | Tool::Erase | Tool::Line)
+    }
+}
+
+/// The tool chosen on the toolbar or with the cycle key. It reads `Stamp` while a pattern
+/// is held, however it was picked up, and goes back to the tool before once it is put away.
+#[derive(Resource, Default, Debug)]
+pub struct ActiveTool {
+    pub tool: Tool,
+    before_stamp: Tool,
+}
+
+impl ActiveTool {
+    /// Switch to `tool`, putting away a held pattern when leaving the stamp. Choosing the
+    /// stamp picks up the last pattern put away; false if there is none.
+    pub fn select(&mut self, tool: Tool, stamp: &mut StampState) -> bool {
+        if tool == Tool::Stamp {
+            if !stamp.is_armed() && !stamp.rearm() {
+                return false;
+            }
+            self.follow(stamp);
+        } else {
+            stamp.cancel();
+            self.tool = tool;
+        }
+        true
+    }
+
+    /// Switch to the stamp while a pattern is held, and back when it is put away
+    pub fn follow(&mut self, stamp: &StampState) {
+        if stamp.is_armed() && self.tool != Tool::Stamp {
+            self.before_stamp = self.tool;
+            self.tool = Tool::Stamp;
+        } else if !stamp.is_armed() && self.tool == Tool::Stamp {
+            self.tool = self.before_stamp;
+        }
+    }
+}
+
+pub struct ToolsPlugin;
+
+impl Plugin for ToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveTool>()
+            .init_resource::<BrushSettings>()
+            .init_resource::<FillSettings>()
+            .init_resource::<Selection>()
+            .init_resource::<clipboard::PendingDownload>()
+            .add_plugins(StampPlugin)
+            .add_systems(
+                Update,
+                (
+                    cycle_tool,
+                    follow_stamp,
+                    clipboard::clipboard_hotkeys,
+                    clipboard::finish_download,
+                    drop::load_dropped_files,
+                    edge_pan::edge_pan_while_drawing,
+                    fill::fill_on_click,
+                    symmetry::symmetry_controls,
+                    symmetry::draw_symmetry_axes,
+                    ghost::update_click_ghost,
+                    select::selection_controls,
+                    (select::draw_selection, select::update_selection_label).after(select::selection_controls),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(InGame), (ghost::despawn_click_ghost, select::reset_selection));
+    }
+}
+
+fn cycle_tool(keyboard_input: Res<ButtonInput<KeyCode>>, keys: Res<Keybindings>, mut active: ResMut<ActiveTool>, mut stamp: ResMut<StampState>) {
+    if keys.just_pressed(&keyboard_input, Action::CycleTool) {
+        let next = active.tool.next();
+        active.select(next, &mut stamp);
+        toast(tr!("🧰 Tool: {}", next.name()));
+    }
+}
+
+fn follow_stamp(stamp: Res<StampState>, mut active: ResMut<ActiveTool>) {
+    if stamp.is_changed() {
+        active.follow(&stamp);
+    }
+}
+
+/// Return the cell state that should be written for a click under the given rule.
+/// Left click usually creates, right click either deletes or places an alternate species.
+pub fn state_for_click(rule: RuleType, button: MouseButton, shift: bool, alt: bool) -> CellState {
+    use MouseButton::{Left, Right};
+    match rule {
+        RuleType::WireWorld => {
+            if shift { CellState::ElectronHead }
+            else if alt { CellState::ElectronTail }
+            else if button == Left { CellState::Wire } else { CellState::Dead }
+        }
+        RuleType::Immigration => {
+            match button {
+                Left => CellState::SpeciesA,
+                Right => CellState::SpeciesB,
+                _ => CellState::Dead,
+            }
+        }
+        // For Brian's Brain a firing cell is represented by Alive
+        RuleType::Brian => {
+            if shift { CellState::Dying }
+            else if button == Left { CellState::Alive } else { CellState::Dead }
+        }
+        _ => {
+            // Default Life-like rules: place Alive, remove on right-click
+            if button == Left { CellState::Alive } else { CellState::Dead }
+        }
+    }
+}
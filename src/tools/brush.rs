@@ -0,0 +1,169 @@
+//! Freehand brush.
+//!
+//! Every shape except `Line` paints its footprint under the cursor while a mouse button is
+//! held, along the whole path the cursor took since the last frame so quick strokes leave no
+//! gaps. `Line` instead remembers where the button went down and draws a straight run of
+//! cells to where it is released, which is what laying out WireWorld wires needs.
+//! With `spray` on, each cell of the footprint is only painted with probability `density`,
+//! which seeds random soup instead of a solid blob.
+
+use bevy::prelude::Resource;
+use rand::Rng;
+use crate::infinite_grid::InfiniteGrid;
+use crate::CellState;
+use super::symmetry::Symmetry;
+
+/// Largest brush size reachable with the size hotkeys
+pub const MAX_BRUSH_SIZE: u32 = 20;
+
+/// Step used by the spray density hotkeys
+pub const SPRAY_DENSITY_STEP: f32 = 0.05;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BrushShape {
+    #[default]
+    Square,
+    Circle,
+    Diamond,
+    Ring,
+    Line,
+}
+
+impl BrushShape {
+    /// Next shape in the hotkey cycle
+    pub fn next(self) -> Self {
+        match self {
+            BrushShape::Square => BrushShape::Circle,
+            BrushShape::Circle => BrushShape::Diamond,
+            BrushShape::Diamond => BrushShape::Ring,
+            BrushShape::Ring => BrushShape::Line,
+            BrushShape::Line => BrushShape::Square,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BrushShape::Square => "Square",
+            BrushShape::Circle => "Circle",
+            BrushShape::Diamond => "Diamond",
+            BrushShape::Ring => "Ring",
+            BrushShape::Line => "Line",
+        }
+    }
+
+    /// Cell offsets covered by a brush of this shape centred on (0, 0).
+    ///
+    /// `size` is the diameter; `Line` uses it as the stroke thickness (a square footprint).
+    pub fn offsets(&self, size: u32) -> Vec<(i32, i32)> {
+        let radius = (size as i32) / 2;
+        let r2 = (radius * radius) as f32;
+        let mut offsets = Vec::new();
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let d2 = (dx * dx + dy * dy) as f32;
+                let inside = match self {
+                    BrushShape::Square | BrushShape::Line => true,
+                    // The +r term rounds the disc out so small circles aren't just a plus sign
+                    BrushShape::Circle => d2 <= r2 + radius as f32,
+                    BrushShape::Diamond => dx.abs() + dy.abs() <= radius,
+                    BrushShape::Ring => {
+                        let d = d2.sqrt();
+                        (d - radius as f32).abs() < 0.5 || radius == 0
+                    }
+                };
+                if inside {
+                    offsets.push((dx, dy));
+                }
+            }
+        }
+        offsets
+    }
+}
+
+/// Current brush parameters for cell placement
+#[derive(Resource)]
+pub struct BrushSettings {
+    pub size: u32, // diameter of the brush footprint
+    pub shape: BrushShape,
+    /// Where the current `Line` stroke started, while the button is held
+    pub line_start: Option<(i32, i32)>,
+    /// Cell painted last in the current freehand stroke, while the button is held
+    pub stroke_last: Option<(i32, i32)>,
+    /// Mirror axes applied to every painted cell
+    pub symmetry: Symmetry,
+    /// Paint a random subset of the footprint instead of all of it
+    pub spray: bool,
+    /// Fraction of footprint cells painted per application while spraying, 0..=1
+    pub density: f32,
+}
+
+impl Default for BrushSettings {
+    fn default() -> Self {
+        Self {
+            size: 1,
+            shape: BrushShape::Square,
+            line_start: None,
+            stroke_last: None,
+            symmetry: Symmetry::default(),
+            spray: false,
+            density: 0.3,
+        }
+    }
+}
+
+/// Paint the brush footprint centred on `(cx, cy)`, plus its mirror images
+pub fn apply_brush(grid: &mut InfiniteGrid, cx: i32, cy: i32, brush: &BrushSettings, state: CellState) {
+    let mut rng = rand::thread_rng();
+    for (dx, dy) in brush.shape.offsets(brush.size) {
+        if brush.spray && !rng.gen_bool(brush.density.clamp(0.0, 1.0) as f64) {
+            continue;
+        }
+        for (x, y) in brush.symmetry.images(cx + dx, cy + dy) {
+            grid.set(x, y, state);
+        }
+    }
+}
+
+/// Cells on the straight line between two points (Bresenham), both ends included
+pub fn line_cells(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let sx = if x < to.0 { 1 } else { -1 };
+    let sy = if y < to.1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let mut cells = Vec::with_capacity((dx - dy + 1) as usize);
+    loop {
+        cells.push((x, y));
+        if (x, y) == to {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_and_lines_cover_expected_cells() {
+        assert_eq!(BrushShape::Square.offsets(5).len(), 25);
+        assert_eq!(BrushShape::Diamond.offsets(5).len(), 13);
+        assert!(!BrushShape::Ring.offsets(7).contains(&(0, 0)));
+        assert!(BrushShape::Circle.offsets(7).contains(&(3, 0)));
+        assert_eq!(BrushShape::Circle.offsets(1), vec![(0, 0)]);
+
+        let line = line_cells((0, 0), (5, -2));
+        assert_eq!((line[0], line[line.len() - 1], line.len()), ((0, 0), (5, -2), 6));
+    }
+}
@@ -0,0 +1,117 @@
+//! Copy and paste patterns through the OS clipboard.
+//!
+//! Ctrl+C copies the pattern held by the stamp tool, else the cells in the selection, else
+//! the whole grid, as RLE with the current rule in the header. Ctrl+V reads the clipboard (RLE or any other
+//! format `io::load_pattern` understands) and picks it up with the stamp, so it follows the
+//! cursor and is placed with a left click like any other stamped pattern. With the `net`
+//! feature, a pasted `https://` link (such as a LifeWiki article) is downloaded instead, on a
+//! thread of its own so the game carries on meanwhile, and picked up once it arrives.
+
+use bevy::prelude::*;
+use crossbeam_channel::{Receiver, TryRecvError};
+use crate::infinite_grid::InfiniteGrid;
+use crate::io::clipboard::{copy_text, paste_text};
+use crate::io::{load_pattern, write_rle, Pattern, PatternError};
+use crate::keybindings::{Action, Keybindings};
+use crate::ui::toast;
+use crate::tr;
+use crate::GameConfig;
+use super::{Selection, StampState};
+
+/// The pasted link being downloaded, if any
+#[derive(Resource, Default)]
+pub(super) struct PendingDownload(Option<Receiver<Result<Pattern, PatternError>>>);
+
+pub(super) fn clipboard_hotkeys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    grid: Res<InfiniteGrid>,
+    game_config: Res<GameConfig>,
+    mut stamp: ResMut<StampState>,
+    (keys, selection, mut download): (Res<Keybindings>, Res<Selection>, ResMut<PendingDownload>),
+) {
+    let ctrl = keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight, KeyCode::SuperLeft, KeyCode::SuperRight]);
+    if !ctrl {
+        return;
+    }
+
+    if keys.just_pressed(&keyboard_input, Action::Copy) {
+        let pattern = if stamp.is_armed() {
+            let cells = stamp.orientation.transform(stamp.cells.iter().copied());
+            Pattern { name: Some(stamp.name.clone()), ..Pattern::from_grid_cells(cells, game_config.current_rule) }
+        } else if selection.bounds.is_some() {
+            Pattern::from_grid_cells(selection.cells(&grid), game_config.current_rule)
+        } else {
+            Pattern::from_grid(&grid, game_config.current_rule)
+        };
+        match copy_text(&write_rle(&pattern)) {
+            Ok(()) => toast(tr!("📋 Copied {} cells as RLE", pattern.cells.len())),
+            Err(e) => toast(tr!("📋 Copy failed: {}", e)),
+        }
+    }
+
+    if keys.just_pressed(&keyboard_input, Action::Paste) {
+        match paste_text() {
+            Ok(text) => {
+                let trimmed = text.trim();
+                if trimmed.starts_with("https://") && !trimmed.contains(char::is_whitespace) {
+                    download_pattern(trimmed, &mut download);
+                } else {
+                    hold_pasted(load_pattern(&text), &mut stamp, &game_config);
+                }
+            }
+            Err(e) => toast(tr!("📋 Paste failed: {}", e)),
+        }
+    }
+}
+
+/// System: pick up the pasted link's pattern once its download has finished
+pub(super) fn finish_download(mut download: ResMut<PendingDownload>, mut stamp: ResMut<StampState>, game_config: Res<GameConfig>) {
+    let Some(received) = download.0.as_ref().map(Receiver::try_recv) else { return };
+    match received {
+        Ok(pasted) => {
+            download.0 = None;
+            hold_pasted(pasted, &mut stamp, &game_config);
+        }
+        Err(TryRecvError::Empty) => {}
+        Err(TryRecvError::Disconnected) => {
+            download.0 = None;
+            toast(tr!("📋 Paste failed: {}", "download thread panicked"));
+        }
+    }
+}
+
+/// Start downloading the pattern a pasted link points to
+fn download_pattern(url: &str, download: &mut PendingDownload) {
+    #[cfg(feature = "net")]
+    {
+        toast(tr!("📋 Downloading {}", url));
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        let url = url.to_string();
+        std::thread::spawn(move || {
+            let _ = sender.send(crate::io::load_pattern_url(&url));
+        });
+        download.0 = Some(receiver);
+    }
+    #[cfg(not(feature = "net"))]
+    {
+        let _ = (url, download);
+        let e = PatternError::Network("this build can't load links; rebuild with --features net".to_string());
+        toast(tr!("📋 Paste failed: {}", e));
+    }
+}
+
+/// Pick up a pasted pattern with the stamp
+fn hold_pasted(pasted: Result<Pattern, PatternError>, stamp: &mut StampState, game_config: &GameConfig) {
+    match pasted {
+        Ok(pattern) if !pattern.cells.is_empty() => {
+            let name = pattern.name.clone().unwrap_or_else(|| "Clipboard".to_string());
+            if let Some(rule) = pattern.rule().filter(|&r| r != game_config.current_rule) {
+                toast(tr!("📋 Pattern was written for {}, placing it under {}", rule.name(), game_config.current_rule.name()));
+            }
+            toast(tr!("📋 Pasted {} ({} cells) - click to place", name, pattern.cells.len()));
+            stamp.hold(name, pattern.grid_cells());
+        }
+        Ok(_) => toast(tr!("📋 Clipboard pattern is empty")),
+        Err(e) => toast(tr!("📋 Paste failed: {}", e)),
+    }
+}
@@ -0,0 +1,36 @@
+//! Loading patterns by dropping files on the window.
+//!
+//! Dropping an `.rle`, `.cells`, `.lif` or `.mc` file picks it up with the stamp tool. The
+//! ghost is centred on the cursor, which is where the file was let go, so a click places it
+//! right there. When several files are dropped at once the last readable one is kept.
+
+use bevy::prelude::*;
+use crate::io::load_pattern_file;
+use crate::ui::toast;
+use crate::tr;
+use crate::GameConfig;
+use super::StampState;
+
+pub(super) fn load_dropped_files(
+    mut drop_events: EventReader<FileDragAndDrop>,
+    game_config: Res<GameConfig>,
+    mut stamp: ResMut<StampState>,
+) {
+    for event in drop_events.read() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = event else { continue };
+        match load_pattern_file(path_buf) {
+            Ok(pattern) if !pattern.cells.is_empty() => {
+                let name = pattern.name.clone().unwrap_or_else(|| {
+                    path_buf.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()
+                });
+                if let Some(rule) = pattern.rule().filter(|&r| r != game_config.current_rule) {
+                    toast(tr!("📂 Pattern was written for {}, placing it under {}", rule.name(), game_config.current_rule.name()));
+                }
+                toast(tr!("📂 Dropped {} ({} cells) - click to place", name, pattern.cells.len()));
+                stamp.hold(name, pattern.grid_cells());
+            }
+            Ok(_) => toast(tr!("📂 {} has no live cells", path_buf.display())),
+            Err(e) => toast(tr!("📂 Could not load {}: {}", path_buf.display(), e)),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Auto-pan while drawing.
+//!
+//! While a brush stroke is held and the cursor comes within `EDGE_PX` of the window edge,
+//! the view scrolls that way, faster the closer the cursor gets, up to the pan keys' speed
+//! (`GameCamera::pan_speed`). Long lines and WireWorld wires can then be drawn past the edge
+//! of the screen without letting go of the button.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::camera::GameCamera;
+use crate::ui::PointerCapture;
+use super::{ActiveTool, StampState};
+
+/// Width of the band along each window edge that scrolls the view, in screen pixels
+pub const EDGE_PX: f32 = 40.0;
+
+/// Direction and strength of the scroll for a cursor at `cursor` in a window of `size`, each
+/// axis from -1 to 1 (y up). Zero away from the edges.
+pub fn edge_pan_direction(cursor: Vec2, size: Vec2) -> Vec2 {
+    let axis = |position: f32, length: f32| {
+        let band = EDGE_PX.min(length / 4.0);
+        if position < band {
+            -(1.0 - position / band)
+        } else if position > length - band {
+            1.0 - (length - position) / band
+        } else {
+            0.0
+        }
+    };
+    // Window y runs downwards
+    Vec2::new(axis(cursor.x, size.x), -axis(cursor.y, size.y)).clamp(Vec2::NEG_ONE, Vec2::ONE)
+}
+
+pub fn edge_pan_while_drawing(
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    (tool, stamp, capture): (Res<ActiveTool>, Res<StampState>, Res<PointerCapture>),
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection, &mut GameCamera)>,
+    time: Res<Time>,
+) {
+    if !tool.tool.paints() || stamp.is_armed() || capture.captured || !mouse_button_input.any_pressed([MouseButton::Left, MouseButton::Right]) {
+        return;
+    }
+    let (Ok(window), Ok((mut transform, projection, mut camera))) = (windows.get_single(), camera_query.get_single_mut()) else { return };
+    let Some(cursor) = window.cursor_position() else { return };
+    let direction = edge_pan_direction(cursor, Vec2::new(window.width(), window.height()));
+    if direction == Vec2::ZERO {
+        return;
+    }
+    camera.zoom_anchor = None;
+    camera.pan_target = None;
+    transform.translation += (direction * camera.pan_speed * time.delta_secs() * projection.scale).extend(0.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrolls_towards_the_nearest_edges() {
+        let size = Vec2::new(800.0, 600.0);
+        assert_eq!(edge_pan_direction(Vec2::new(400.0, 300.0), size), Vec2::ZERO);
+        assert_eq!(edge_pan_direction(Vec2::new(0.0, 300.0), size), Vec2::new(-1.0, 0.0));
+        // Top right corner, halfway into both bands: up is +y on screen
+        assert_eq!(edge_pan_direction(Vec2::new(780.0, 20.0), size), Vec2::new(0.5, 0.5));
+    }
+}
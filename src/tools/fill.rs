@@ -0,0 +1,108 @@
+//! Flood fill.
+//!
+//! Replaces the 4-connected region of cells sharing the clicked cell's state. On the infinite
+//! grid an open dead region never ends, so the region is measured first and nothing is written
+//! if it is larger than the configured cap.
+
+use std::collections::{HashSet, VecDeque};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::camera::{cursor_grid_position, CameraState, GameCamera};
+use crate::infinite_grid::InfiniteGrid;
+use crate::ui::{toast, PointerCapture};
+use crate::tr;
+use crate::GameConfig;
+use super::{state_for_click, ActiveTool, StampState, Tool};
+
+/// Default for `FillSettings::max_cells`
+pub const DEFAULT_FILL_CAP: usize = 10_000;
+
+#[derive(Resource, Debug, Clone)]
+pub struct FillSettings {
+    /// Regions larger than this are left untouched
+    pub max_cells: usize,
+}
+
+impl Default for FillSettings {
+    fn default() -> Self {
+        Self { max_cells: DEFAULT_FILL_CAP }
+    }
+}
+
+/// Fill the region containing `(x, y)` with `state`.
+///
+/// Returns the number of cells written, or `None` if the region exceeded `max_cells`.
+pub fn flood_fill(grid: &mut InfiniteGrid, x: i32, y: i32, state: crate::CellState, max_cells: usize) -> Option<usize> {
+    let target = grid.get(x, y);
+    if target == state {
+        return Some(0);
+    }
+
+    let mut region = HashSet::new();
+    let mut queue = VecDeque::from([(x, y)]);
+    region.insert((x, y));
+    while let Some((cx, cy)) = queue.pop_front() {
+        for next in [(cx + 1, cy), (cx - 1, cy), (cx, cy + 1), (cx, cy - 1)] {
+            if grid.get(next.0, next.1) == target && region.insert(next) {
+                if region.len() > max_cells {
+                    return None;
+                }
+                queue.push_back(next);
+            }
+        }
+    }
+
+    for &(cx, cy) in &region {
+        grid.set(cx, cy, state);
+    }
+    Some(region.len())
+}
+
+pub(super) fn fill_on_click(
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    camera_state: Res<CameraState>,
+    (tool, stamp, settings, game_config): (Res<ActiveTool>, Res<StampState>, Res<FillSettings>, Res<GameConfig>),
+    (mut grid, capture): (ResMut<InfiniteGrid>, Res<PointerCapture>),
+) {
+    if tool.tool != Tool::Fill || stamp.is_armed() || capture.captured {
+        return;
+    }
+    let Some(button) = [MouseButton::Left, MouseButton::Right].into_iter().find(|&b| mouse_button_input.just_pressed(b)) else { return };
+    let (Ok(window), Ok((transform, projection))) = (windows.get_single(), camera_query.get_single()) else { return };
+    let Some((x, y)) = cursor_grid_position(window, transform, projection, &camera_state) else { return };
+
+    let shift = keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    let alt = keyboard_input.pressed(KeyCode::AltLeft) || keyboard_input.pressed(KeyCode::AltRight);
+    let state = state_for_click(game_config.current_rule, button, shift, alt);
+    match flood_fill(&mut grid, x, y, state, settings.max_cells) {
+        Some(count) => toast(tr!("🪣 Filled {} cells", count)),
+        None => toast(tr!("🪣 Fill region is larger than {} cells - enclose it first", settings.max_cells)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CellState;
+
+    #[test]
+    fn fills_enclosed_region_and_refuses_open_space() {
+        let mut grid = InfiniteGrid::new();
+        // 5x5 hollow box of wire, 3x3 interior
+        for i in 0..5 {
+            for (x, y) in [(i, 0), (i, 4), (0, i), (4, i)] {
+                grid.set(x, y, CellState::Wire);
+            }
+        }
+        assert_eq!(flood_fill(&mut grid, 2, 2, CellState::Alive, 100), Some(9));
+        assert_eq!(grid.get(2, 2), CellState::Alive);
+        assert_eq!(grid.get(5, 5), CellState::Dead);
+
+        let before = grid.population();
+        assert_eq!(flood_fill(&mut grid, 10, 10, CellState::Alive, 100), None);
+        assert_eq!(grid.population(), before);
+    }
+}
@@ -0,0 +1,142 @@
+//! Ghost preview of the next click.
+//!
+//! While the pointer is over the grid, translucent cells show what a left click would
+//! place there: the brush footprint with its mirror images, the pending stroke of a `Line`
+//! brush, or the armed stamp pattern. Cells are tinted in the theme colour of the state
+//! they would get under the current rule and modifiers; erasing shows the background
+//! colour. Ghost sprites are pooled and hidden rather than respawned every frame.
+
+use std::collections::HashSet;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::camera::{cursor_grid_position, grid_to_world, CameraState, GameCamera};
+use crate::theme::Theme;
+use crate::ui::PointerCapture;
+use crate::{CellState, GameConfig};
+use super::{line_cells, state_for_click, ActiveTool, BrushSettings, StampState, Tool};
+
+/// Opacity of ghost cells
+pub const GHOST_ALPHA: f32 = 0.4;
+
+/// Ghost cells drawn at most, so a long line of a wide mirrored brush stays cheap
+pub const MAX_GHOST_CELLS: usize = 4096;
+
+/// Above the cells, below the HUD
+const GHOST_Z: f32 = 5.0;
+
+#[derive(Component)]
+pub struct GhostCell;
+
+/// The game camera, kept apart from the ghost sprites' transforms
+type CameraView<'w, 's> = Query<'w, 's, (&'static Transform, &'static OrthographicProjection), (With<GameCamera>, Without<GhostCell>)>;
+
+/// Cells a click at `(cx, cy)` would paint: the footprint and its mirror images, or for a
+/// line stroke in progress (the `Line` shape or tool) every footprint along the line from
+/// where it started
+pub fn brush_footprint(brush: &BrushSettings, cx: i32, cy: i32) -> Vec<(i32, i32)> {
+    let centres = match brush.line_start {
+        Some(start) => line_cells(start, (cx, cy)),
+        None => vec![(cx, cy)],
+    };
+    let offsets = brush.shape.offsets(brush.size);
+    let mut seen = HashSet::new();
+    let mut cells = Vec::new();
+    for (x, y) in centres {
+        for &(dx, dy) in &offsets {
+            for cell in brush.symmetry.images(x + dx, y + dy) {
+                if seen.insert(cell) {
+                    cells.push(cell);
+                }
+            }
+        }
+    }
+    cells
+}
+
+/// Move the pooled ghost sprites onto the cells the next click would change
+pub fn update_click_ghost(
+    mut commands: Commands,
+    mut ghosts: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<GhostCell>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: CameraView,
+    (camera_state, theme, game_config): (Res<CameraState>, Res<Theme>, Res<GameConfig>),
+    (brush, stamp, tool, capture): (Res<BrushSettings>, Res<StampState>, Res<ActiveTool>, Res<PointerCapture>),
+    (mouse_buttons, keyboard_input): (Res<ButtonInput<MouseButton>>, Res<ButtonInput<KeyCode>>),
+) {
+    let cursor = match (windows.get_single(), camera_query.get_single()) {
+        (Ok(window), Ok((transform, projection))) if !capture.captured => {
+            cursor_grid_position(window, transform, projection, &camera_state)
+        }
+        _ => None,
+    };
+
+    let cells: Vec<(i32, i32, CellState)> = match cursor {
+        Some((cx, cy)) if stamp.is_armed() => stamp.cells_at(cx, cy),
+        Some((cx, cy)) if tool.tool.paints() => {
+            // Preview the button being held, if any, so erasing strokes show as erasing
+            let button = if mouse_buttons.pressed(MouseButton::Right) { MouseButton::Right } else { MouseButton::Left };
+            let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+            let alt = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+            let state = if tool.tool == Tool::Erase { CellState::Dead } else { state_for_click(game_config.current_rule, button, shift, alt) };
+            brush_footprint(&brush, cx, cy).into_iter().map(|(x, y)| (x, y, state)).collect()
+        }
+        _ => Vec::new(),
+    };
+
+    let color_of = |state: CellState| {
+        let color = if state == CellState::Dead { theme.background.0 } else { theme.cells.color(state) };
+        color.with_alpha(GHOST_ALPHA)
+    };
+    let size = Vec2::splat(camera_state.cell_size * 0.9);
+    let mut cells = cells.into_iter().take(MAX_GHOST_CELLS);
+
+    for (mut transform, mut sprite, mut visibility) in ghosts.iter_mut() {
+        match cells.next() {
+            Some((x, y, state)) => {
+                transform.translation = grid_to_world(x, y, &camera_state).extend(GHOST_Z);
+                sprite.color = color_of(state);
+                sprite.custom_size = Some(size);
+                visibility.set_if_neq(Visibility::Inherited);
+            }
+            None => {
+                visibility.set_if_neq(Visibility::Hidden);
+            }
+        }
+    }
+    for (x, y, state) in cells {
+        commands.spawn((
+            Sprite { color: color_of(state), custom_size: Some(size), ..default() },
+            Transform::from_translation(grid_to_world(x, y, &camera_state).extend(GHOST_Z)),
+            GhostCell,
+        ));
+    }
+}
+
+pub fn despawn_click_ghost(mut commands: Commands, ghosts: Query<Entity, With<GhostCell>>) {
+    for entity in &ghosts {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::{BrushShape, Symmetry, SymmetryMode};
+
+    #[test]
+    fn footprint_covers_mirrors_and_pending_lines() {
+        let mut brush = BrushSettings { size: 3, ..default() };
+        assert_eq!(brush_footprint(&brush, 10, 10).len(), 9);
+
+        brush.symmetry = Symmetry { mode: SymmetryMode::Vertical, ..default() };
+        let mirrored = brush_footprint(&brush, 10, 0);
+        assert_eq!(mirrored.len(), 18);
+        assert!(mirrored.contains(&(-10, 0)));
+
+        brush.symmetry = Symmetry::default();
+        brush.size = 1;
+        brush.shape = BrushShape::Line;
+        brush.line_start = Some((0, 0));
+        assert_eq!(brush_footprint(&brush, 4, 0).len(), 5);
+    }
+}
@@ -0,0 +1,231 @@
+//! Rectangular selection.
+//!
+//! With the Select tool, dragging marks out a rectangle of cells, drawn as marching ants
+//! with a handle on each corner. Dragging a handle resizes the rectangle from the opposite
+//! corner and right click drops it. While a selection exists, Ctrl+C copies the cells inside
+//! it instead of the whole grid. During a drag the ants run faster, the moving corner is
+//! highlighted and the rectangle's size is shown beside it.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::camera::{cursor_grid_position, grid_to_world, screen_to_world, CameraState, GameCamera};
+use crate::infinite_grid::{GridBounds, InfiniteGrid};
+use crate::ui::PointerCapture;
+use crate::CellState;
+use super::{ActiveTool, StampState, Tool};
+
+/// Handle size and grab radius, in screen pixels
+const HANDLE_PX: f32 = 8.0;
+
+/// Length of one marching-ants dash, in screen pixels
+const DASH_PX: f32 = 6.0;
+
+/// Dashes drawn per frame at most; bigger rectangles get longer dashes
+const MAX_DASHES: usize = 4000;
+
+/// Screen pixels per second the ants march, and how much faster they go while dragging
+const MARCH_SPEED: f32 = 12.0;
+const DRAG_MARCH_FACTOR: f32 = 3.0;
+
+/// Above the cells and the click ghost
+const LABEL_Z: f32 = 6.0;
+
+/// The selected rectangle of cells, and the drag editing it
+#[derive(Resource, Default)]
+pub struct Selection {
+    pub bounds: Option<GridBounds>,
+    /// Fixed corner of the rectangle while the button is held; the cursor is the other one
+    anchor: Option<(i32, i32)>,
+}
+
+impl Selection {
+    pub fn clear(&mut self) {
+        self.bounds = None;
+        self.anchor = None;
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.anchor.is_some()
+    }
+
+    /// Live cells inside the selection, in grid coordinates
+    pub fn cells(&self, grid: &InfiniteGrid) -> Vec<(i32, i32, CellState)> {
+        let Some(b) = &self.bounds else { return Vec::new() };
+        grid.cells_in_region(b.min_x, b.max_x, b.min_y, b.max_y)
+            .map(|(x, y)| (x, y, grid.get(x, y)))
+            .filter(|&(_, _, state)| state != CellState::Dead)
+            .collect()
+    }
+}
+
+/// Smallest rectangle holding both cells
+pub fn bounds_between(a: (i32, i32), b: (i32, i32)) -> GridBounds {
+    GridBounds { min_x: a.0.min(b.0), max_x: a.0.max(b.0), min_y: a.1.min(b.1), max_y: a.1.max(b.1) }
+}
+
+/// Corner cells, going round the rectangle so the opposite of corner `i` is `(i + 2) % 4`
+pub fn corners(bounds: &GridBounds) -> [(i32, i32); 4] {
+    [
+        (bounds.min_x, bounds.min_y),
+        (bounds.max_x, bounds.min_y),
+        (bounds.max_x, bounds.max_y),
+        (bounds.min_x, bounds.max_y),
+    ]
+}
+
+/// World-space outline of the rectangle: the outer edges of its corner cells, in `corners` order
+fn outline(bounds: &GridBounds, camera_state: &CameraState) -> [Vec2; 4] {
+    let half = Vec2::splat(camera_state.cell_size / 2.0);
+    let min = grid_to_world(bounds.min_x, bounds.min_y, camera_state) - half;
+    let max = grid_to_world(bounds.max_x, bounds.max_y, camera_state) + half;
+    [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)]
+}
+
+#[derive(Component)]
+pub(super) struct SelectionLabel;
+
+/// Start, resize and drop the selection with the mouse while the Select tool is active
+pub(super) fn selection_controls(
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    camera_state: Res<CameraState>,
+    (tool, stamp, capture): (Res<ActiveTool>, Res<StampState>, Res<PointerCapture>),
+    mut selection: ResMut<Selection>,
+) {
+    if tool.tool != Tool::Select || stamp.is_armed() {
+        selection.anchor = None;
+        return;
+    }
+    let (Ok(window), Ok((transform, projection))) = (windows.get_single(), camera_query.get_single()) else { return };
+    let Some(cell) = cursor_grid_position(window, transform, projection, &camera_state) else { return };
+
+    if !capture.captured && mouse_button_input.just_pressed(MouseButton::Right) {
+        selection.clear();
+        return;
+    }
+    if !capture.captured && mouse_button_input.just_pressed(MouseButton::Left) {
+        // Grabbing a handle keeps the opposite corner; anywhere else starts a new rectangle
+        let cursor = window.cursor_position().map(|position| {
+            screen_to_world(position, transform, projection, Vec2::new(window.width(), window.height()))
+        });
+        let grabbed = selection.bounds.as_ref().zip(cursor).and_then(|(bounds, cursor)| {
+            let points = outline(bounds, &camera_state);
+            let reach = HANDLE_PX * projection.scale;
+            (0..4).find(|&i| points[i].distance(cursor) <= reach).map(|i| corners(bounds)[(i + 2) % 4])
+        });
+        selection.anchor = Some(grabbed.unwrap_or(cell));
+    }
+    if let Some(anchor) = selection.anchor {
+        let bounds = bounds_between(anchor, cell);
+        if selection.bounds.as_ref().map(corners) != Some(corners(&bounds)) {
+            selection.bounds = Some(bounds);
+        }
+        if !mouse_button_input.pressed(MouseButton::Left) {
+            selection.anchor = None;
+        }
+    }
+}
+
+/// Marching ants round the selection, with corner handles
+pub(super) fn draw_selection(
+    mut gizmos: Gizmos,
+    selection: Res<Selection>,
+    camera_query: Query<&OrthographicProjection, With<GameCamera>>,
+    camera_state: Res<CameraState>,
+    time: Res<Time>,
+) {
+    let (Some(bounds), Ok(projection)) = (&selection.bounds, camera_query.get_single()) else { return };
+    let points = outline(bounds, &camera_state);
+    let scale = projection.scale;
+
+    let perimeter: f32 = (0..4).map(|i| points[i].distance(points[(i + 1) % 4])).sum();
+    let dash = (DASH_PX * scale).max(perimeter / MAX_DASHES as f32);
+    let speed = if selection.is_dragging() { MARCH_SPEED * DRAG_MARCH_FACTOR } else { MARCH_SPEED };
+    let phase = (time.elapsed_secs() * speed * scale).rem_euclid(dash * 2.0);
+
+    // Walk the outline as one path, alternating light and dark dashes offset by the phase
+    let mut travelled = -phase;
+    for i in 0..4 {
+        let (start, end) = (points[i], points[(i + 1) % 4]);
+        let length = start.distance(end);
+        let direction = (end - start) / length.max(f32::EPSILON);
+        let mut along = 0.0;
+        while along < length {
+            let index = ((travelled + along) / dash).floor();
+            let next = ((index + 1.0) * dash - travelled).min(length);
+            let color = if index.rem_euclid(2.0) < 1.0 { Color::WHITE } else { Color::BLACK };
+            gizmos.line_2d(start + direction * along, start + direction * next, color);
+            along = next;
+        }
+        travelled += length;
+    }
+
+    // While dragging, the corner under the cursor is the one opposite the anchor
+    let moving = selection.anchor.and_then(|anchor| corners(bounds).iter().position(|&c| c == anchor)).map(|i| (i + 2) % 4);
+    for (i, point) in points.into_iter().enumerate() {
+        let size = Vec2::splat(HANDLE_PX * scale);
+        let color = if Some(i) == moving { Color::srgb(1.0, 0.8, 0.2) } else { Color::WHITE };
+        gizmos.rect_2d(Isometry2d::from_translation(point), size, color);
+        gizmos.rect_2d(Isometry2d::from_translation(point), size * 0.5, Color::BLACK);
+    }
+}
+
+/// Width × height readout above the rectangle while a selection is being dragged
+pub(super) fn update_selection_label(
+    mut commands: Commands,
+    selection: Res<Selection>,
+    mut labels: Query<(&mut Text2d, &mut Transform, &mut Visibility), With<SelectionLabel>>,
+    camera_query: Query<&OrthographicProjection, With<GameCamera>>,
+    camera_state: Res<CameraState>,
+) {
+    let shown = selection.bounds.as_ref().filter(|_| selection.is_dragging());
+    let Ok((mut text, mut transform, mut visibility)) = labels.get_single_mut() else {
+        if shown.is_some() {
+            commands.spawn((Text2d::default(), TextFont { font_size: 14.0, ..default() }, Transform::default(), Visibility::Hidden, SelectionLabel));
+        }
+        return;
+    };
+    let (Some(bounds), Ok(projection)) = (shown, camera_query.get_single()) else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+    let label = format!("{} × {}", bounds.max_x - bounds.min_x + 1, bounds.max_y - bounds.min_y + 1);
+    if text.0 != label {
+        text.0 = label;
+    }
+    // Sit just above the rectangle at a constant size on screen
+    let points = outline(bounds, &camera_state);
+    let top_centre = (points[2] + points[3]) / 2.0;
+    transform.translation = (top_centre + Vec2::new(0.0, 12.0) * projection.scale).extend(LABEL_Z);
+    transform.scale = Vec3::splat(projection.scale);
+    visibility.set_if_neq(Visibility::Inherited);
+}
+
+pub(super) fn reset_selection(mut commands: Commands, mut selection: ResMut<Selection>, labels: Query<Entity, With<SelectionLabel>>) {
+    selection.clear();
+    for entity in &labels {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_spans_both_corners_and_copies_live_cells() {
+        let bounds = bounds_between((4, -1), (1, 2));
+        assert_eq!((bounds.min_x, bounds.max_x, bounds.min_y, bounds.max_y), (1, 4, -1, 2));
+        let corners = corners(&bounds);
+        assert_eq!(corners[0], (1, -1));
+        assert_eq!(corners[2], (4, 2));
+
+        let mut grid = InfiniteGrid::new();
+        grid.set(2, 0, CellState::Alive);
+        grid.set(9, 0, CellState::Alive);
+        let selection = Selection { bounds: Some(bounds), anchor: None };
+        assert_eq!(selection.cells(&grid), vec![(2, 0, CellState::Alive)]);
+        assert!(Selection::default().cells(&grid).is_empty());
+    }
+}
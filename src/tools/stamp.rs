@@ -0,0 +1,200 @@
+//! Pattern stamping.
+//!
+//! Picking a pattern (e.g. with the number-key hotkeys) arms the stamp: a ghost of the
+//! pattern follows the cursor (drawn by `ghost`), R rotates it a quarter turn, F mirrors
+//! it, left click places it and Escape or right click puts it away. Choosing the stamp on
+//! the toolbar picks the last pattern put away back up.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::camera::{cursor_grid_position, CameraState, GameCamera};
+use crate::infinite_grid::patterns::LibraryPattern;
+use crate::infinite_grid::InfiniteGrid;
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::{GameState, InGame};
+use crate::ui::PointerCapture;
+use crate::CellState;
+
+/// One of the eight rotations/reflections of a pattern
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Orientation {
+    /// Clockwise quarter turns, 0..4
+    pub quarter_turns: u8,
+    /// Mirror left-right before rotating
+    pub flipped: bool,
+}
+
+impl Orientation {
+    pub fn rotate(&mut self) {
+        self.quarter_turns = (self.quarter_turns + 1) % 4;
+    }
+
+    pub fn flip(&mut self) {
+        self.flipped = !self.flipped;
+    }
+
+    /// Map a pattern-local offset through this orientation
+    pub fn apply(&self, x: i32, y: i32) -> (i32, i32) {
+        let (mut x, mut y) = if self.flipped { (-x, y) } else { (x, y) };
+        for _ in 0..self.quarter_turns {
+            (x, y) = (-y, x);
+        }
+        (x, y)
+    }
+
+    /// Transform `cells` and shift them back so the bounding box starts at (0, 0)
+    pub fn transform<I>(&self, cells: I) -> Vec<(i32, i32, CellState)>
+    where
+        I: Iterator<Item = (i32, i32, CellState)>,
+    {
+        let mut out: Vec<_> = cells
+            .map(|(x, y, state)| {
+                let (x, y) = self.apply(x, y);
+                (x, y, state)
+            })
+            .collect();
+        let min_x = out.iter().map(|c| c.0).min().unwrap_or(0);
+        let min_y = out.iter().map(|c| c.1).min().unwrap_or(0);
+        for cell in &mut out {
+            cell.0 -= min_x;
+            cell.1 -= min_y;
+        }
+        out
+    }
+}
+
+/// The pattern currently held by the stamp tool, if any
+#[derive(Resource, Default)]
+pub struct StampState {
+    pub name: String,
+    /// Cells of the held pattern in its own coordinates; empty when nothing is held
+    pub cells: Vec<(i32, i32, CellState)>,
+    pub orientation: Orientation,
+    /// The last pattern put away, for `rearm`
+    put_away_name: String,
+    put_away: Vec<(i32, i32, CellState)>,
+}
+
+impl StampState {
+    /// Pick up a library pattern in its default orientation
+    pub fn arm(&mut self, pattern: &LibraryPattern) {
+        self.hold(pattern.name, pattern.cells());
+    }
+
+    /// Pick up arbitrary cells (e.g. a loaded file) in their default orientation
+    pub fn hold(&mut self, name: impl Into<String>, cells: impl IntoIterator<Item = (i32, i32, CellState)>) {
+        self.name = name.into();
+        self.cells = cells.into_iter().collect();
+        self.orientation = Orientation::default();
+    }
+
+    pub fn cancel(&mut self) {
+        if self.is_armed() {
+            self.put_away_name = std::mem::take(&mut self.name);
+            self.put_away = std::mem::take(&mut self.cells);
+        }
+    }
+
+    /// Pick the last pattern put away back up; false if there is none
+    pub fn rearm(&mut self) -> bool {
+        if self.put_away.is_empty() {
+            return false;
+        }
+        let (name, cells) = (std::mem::take(&mut self.put_away_name), std::mem::take(&mut self.put_away));
+        self.hold(name, cells);
+        true
+    }
+
+    pub fn is_armed(&self) -> bool {
+        !self.cells.is_empty()
+    }
+
+    /// Absolute cells the stamp would write with its centre on `(cx, cy)`
+    pub fn cells_at(&self, cx: i32, cy: i32) -> Vec<(i32, i32, CellState)> {
+        let mut cells = self.orientation.transform(self.cells.iter().copied());
+        let width = cells.iter().map(|c| c.0).max().unwrap_or(0) + 1;
+        let height = cells.iter().map(|c| c.1).max().unwrap_or(0) + 1;
+        for cell in &mut cells {
+            cell.0 += cx - width / 2;
+            cell.1 += cy - height / 2;
+        }
+        cells
+    }
+}
+
+pub struct StampPlugin;
+
+impl Plugin for StampPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StampState>()
+            .add_systems(
+                Update,
+                stamp_controls
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(InGame), |mut stamp: ResMut<StampState>| *stamp = StampState::default());
+    }
+}
+
+fn stamp_controls(
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut mouse_button_input: ResMut<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    camera_state: Res<CameraState>,
+    mut stamp: ResMut<StampState>,
+    (mut grid, keys, capture): (ResMut<InfiniteGrid>, Res<Keybindings>, Res<PointerCapture>),
+) {
+    if !stamp.is_armed() {
+        return;
+    }
+
+    // Consume the keys so the game-wide bindings (R = reset, Esc = menu) don't also fire
+    if keys.clear_just_pressed(&mut keyboard_input, Action::StampRotate) {
+        stamp.orientation.rotate();
+    }
+    if keys.clear_just_pressed(&mut keyboard_input, Action::StampFlip) {
+        stamp.orientation.flip();
+    }
+    if keys.clear_just_pressed(&mut keyboard_input, Action::StampDrop) || mouse_button_input.just_pressed(MouseButton::Right) {
+        mouse_button_input.reset(MouseButton::Right);
+        stamp.cancel();
+        return;
+    }
+
+    if mouse_button_input.just_pressed(MouseButton::Left) && !capture.captured {
+        let (Ok(window), Ok((transform, projection))) = (windows.get_single(), camera_query.get_single()) else { return };
+        if let Some((cx, cy)) = cursor_grid_position(window, transform, projection, &camera_state) {
+            for (x, y, state) in stamp.cells_at(cx, cy) {
+                grid.set(x, y, state);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn four_rotations_and_two_flips_return_to_start() {
+        let glider = [(1, 0, CellState::Alive), (2, 1, CellState::Alive), (0, 2, CellState::Alive), (1, 2, CellState::Alive), (2, 2, CellState::Alive)];
+        let mut orientation = Orientation::default();
+        orientation.rotate();
+        let turned = orientation.transform(glider.into_iter());
+        assert_ne!(sorted(turned.clone()), sorted(glider.to_vec()));
+        assert!(turned.iter().all(|&(x, y, _)| (0..3).contains(&x) && (0..3).contains(&y)));
+
+        for _ in 0..3 {
+            orientation.rotate();
+        }
+        orientation.flip();
+        orientation.flip();
+        assert_eq!(sorted(orientation.transform(glider.into_iter())), sorted(glider.to_vec()));
+    }
+
+    fn sorted(mut cells: Vec<(i32, i32, CellState)>) -> Vec<(i32, i32, CellState)> {
+        cells.sort_unstable_by_key(|c| (c.0, c.1));
+        cells
+    }
+}
@@ -0,0 +1,153 @@
+//! Symmetric painting.
+//!
+//! Mirrors every painted cell across one or more axes through a chosen origin cell, so a
+//! single stroke seeds a pattern with bilateral, 4-fold or full 8-fold (dihedral) symmetry.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::camera::{cursor_grid_position, grid_to_world, CameraState, GameCamera};
+use crate::keybindings::{Action, Keybindings};
+use crate::ui::toast;
+use crate::tr;
+use super::BrushSettings;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SymmetryMode {
+    #[default]
+    Off,
+    /// Mirror across the horizontal axis (top/bottom)
+    Horizontal,
+    /// Mirror across the vertical axis (left/right)
+    Vertical,
+    /// Both axes
+    FourFold,
+    /// Both axes and both diagonals
+    EightFold,
+}
+
+impl SymmetryMode {
+    pub fn next(self) -> Self {
+        match self {
+            SymmetryMode::Off => SymmetryMode::Horizontal,
+            SymmetryMode::Horizontal => SymmetryMode::Vertical,
+            SymmetryMode::Vertical => SymmetryMode::FourFold,
+            SymmetryMode::FourFold => SymmetryMode::EightFold,
+            SymmetryMode::EightFold => SymmetryMode::Off,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            SymmetryMode::Off => tr!("Off"),
+            SymmetryMode::Horizontal => tr!("Horizontal"),
+            SymmetryMode::Vertical => tr!("Vertical"),
+            SymmetryMode::FourFold => tr!("4-fold"),
+            SymmetryMode::EightFold => tr!("8-fold"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Symmetry {
+    pub mode: SymmetryMode,
+    /// Cell the mirror axes pass through
+    pub origin: (i32, i32),
+}
+
+impl Symmetry {
+    /// `(x, y)` and all of its mirror images, without duplicates
+    pub fn images(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
+        let (ox, oy) = self.origin;
+        let (dx, dy) = (x - ox, y - oy);
+        let offsets: &[(i32, i32)] = match self.mode {
+            SymmetryMode::Off => &[(dx, dy)],
+            SymmetryMode::Horizontal => &[(dx, dy), (dx, -dy)],
+            SymmetryMode::Vertical => &[(dx, dy), (-dx, dy)],
+            SymmetryMode::FourFold => &[(dx, dy), (-dx, dy), (dx, -dy), (-dx, -dy)],
+            SymmetryMode::EightFold => &[
+                (dx, dy), (-dx, dy), (dx, -dy), (-dx, -dy),
+                (dy, dx), (-dy, dx), (dy, -dx), (-dy, -dx),
+            ],
+        };
+        let mut images = Vec::with_capacity(offsets.len());
+        for &(ix, iy) in offsets {
+            let image = (ox + ix, oy + iy);
+            if !images.contains(&image) {
+                images.push(image);
+            }
+        }
+        images
+    }
+}
+
+pub(super) fn symmetry_controls(
+    (keyboard_input, keys): (Res<ButtonInput<KeyCode>>, Res<Keybindings>),
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<GameCamera>>,
+    camera_state: Res<CameraState>,
+    mut brush: ResMut<BrushSettings>,
+) {
+    if !keys.just_pressed(&keyboard_input, Action::Symmetry) {
+        return;
+    }
+    let shift = keyboard_input.pressed(KeyCode::ShiftLeft) || keyboard_input.pressed(KeyCode::ShiftRight);
+    if shift {
+        let (Ok(window), Ok((transform, projection))) = (windows.get_single(), camera_query.get_single()) else { return };
+        if let Some(origin) = cursor_grid_position(window, transform, projection, &camera_state) {
+            brush.symmetry.origin = origin;
+            toast(tr!("🪞 Symmetry origin: {}", format!("{:?}", origin)));
+        }
+    } else {
+        brush.symmetry.mode = brush.symmetry.mode.next();
+        toast(tr!("🪞 Symmetry: {}", brush.symmetry.mode.name()));
+    }
+}
+
+/// Draw the active mirror axes through the origin
+pub(super) fn draw_symmetry_axes(
+    mut gizmos: Gizmos,
+    camera_query: Query<&OrthographicProjection, With<GameCamera>>,
+    camera_state: Res<CameraState>,
+    brush: Res<BrushSettings>,
+) {
+    let mode = brush.symmetry.mode;
+    if mode == SymmetryMode::Off {
+        return;
+    }
+    let Ok(projection) = camera_query.get_single() else { return };
+    let centre = grid_to_world(brush.symmetry.origin.0, brush.symmetry.origin.1, &camera_state);
+    // Long enough to cross the visible area at the current zoom
+    let reach = projection.area.size().max_element().max(1.0) * 2.0;
+    let colour = Color::srgba(1.0, 0.4, 0.8, 0.5);
+
+    let mut axes = Vec::new();
+    if matches!(mode, SymmetryMode::Horizontal | SymmetryMode::FourFold | SymmetryMode::EightFold) {
+        axes.push(Vec2::X);
+    }
+    if matches!(mode, SymmetryMode::Vertical | SymmetryMode::FourFold | SymmetryMode::EightFold) {
+        axes.push(Vec2::Y);
+    }
+    if mode == SymmetryMode::EightFold {
+        axes.push(Vec2::ONE.normalize());
+        axes.push(Vec2::new(1.0, -1.0).normalize());
+    }
+    for axis in axes {
+        gizmos.line_2d(centre - axis * reach, centre + axis * reach, colour);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eight_fold_images_are_unique() {
+        let symmetry = Symmetry { mode: SymmetryMode::EightFold, origin: (10, -5) };
+        assert_eq!(symmetry.images(12, -4).len(), 8);
+        // On a diagonal, images coincide pairwise
+        assert_eq!(symmetry.images(12, -3).len(), 4);
+        assert_eq!(symmetry.images(10, -5), vec![(10, -5)]);
+        let vertical = Symmetry { mode: SymmetryMode::Vertical, origin: (0, 0) };
+        assert_eq!(vertical.images(3, 7), vec![(3, 7), (-3, 7)]);
+    }
+}
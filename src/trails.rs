@@ -0,0 +1,212 @@
+//! Death trails.
+//!
+//! When enabled, every cell that dies leaves a faint ghost that fades out over
+//! `DeathTrails::length` generations, so the path of gliders, spaceships and puffers stays
+//! visible behind them. Ghosts are tracked per generation by comparing the live cells with
+//! the previous generation's, and drawn as flat squares underneath the live cells in the
+//! theme's `trail` colour. K toggles trails with the default bindings.
+
+use std::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::camera::{CameraState, GameCamera};
+use crate::cell_renderer::CellRenderConfig;
+use crate::infinite_grid::InfiniteGrid;
+use crate::keybindings::{Action, Keybindings};
+use crate::start_screen::{GameState, InGame};
+use crate::theme::Theme;
+use crate::ui::toast;
+use crate::tr;
+
+/// Most ghost sprites drawn at once
+const MAX_GHOST_SPRITES: usize = 20_000;
+
+/// Ghosts sit between extra layers (z < 0) and live cells (z = 0)
+const GHOST_Z: f32 = -0.05;
+
+#[derive(Resource)]
+pub struct DeathTrails {
+    pub enabled: bool,
+    /// Generations a ghost takes to fade out completely
+    pub length: u32,
+    /// Generations since each ghost's cell died
+    ghosts: HashMap<(i32, i32), u32>,
+    /// Live cells of the last generation seen
+    previous: HashSet<(i32, i32)>,
+    generation: u64,
+}
+
+impl Default for DeathTrails {
+    fn default() -> Self {
+        Self::new(false, 20)
+    }
+}
+
+impl DeathTrails {
+    pub fn new(enabled: bool, length: u32) -> Self {
+        Self { enabled, length, ghosts: HashMap::new(), previous: HashSet::new(), generation: 0 }
+    }
+
+    /// Age existing ghosts by `generations`, then add one for every cell alive before and
+    /// not now. Cells that came back to life lose their ghost.
+    pub fn advance(&mut self, alive: HashSet<(i32, i32)>, generations: u32) {
+        let length = self.length;
+        self.ghosts.retain(|pos, age| {
+            *age = age.saturating_add(generations);
+            *age < length && !alive.contains(pos)
+        });
+        if length > 0 {
+            for &pos in self.previous.difference(&alive) {
+                self.ghosts.insert(pos, 0);
+            }
+        }
+        self.previous = alive;
+    }
+
+    /// Forget all ghosts and start over from `alive`
+    pub fn reset(&mut self, alive: HashSet<(i32, i32)>) {
+        self.ghosts.clear();
+        self.previous = alive;
+    }
+
+    /// Opacity of the ghost at `pos` from 1 (just died) towards 0, if there is one
+    pub fn fade(&self, pos: (i32, i32)) -> Option<f32> {
+        let age = *self.ghosts.get(&pos)?;
+        Some(1.0 - age as f32 / self.length.max(1) as f32)
+    }
+
+    pub fn ghost_count(&self) -> usize {
+        self.ghosts.len()
+    }
+}
+
+/// Sprite for one ghost
+#[derive(Component)]
+pub struct TrailGhost {
+    pub x: i32,
+    pub y: i32,
+}
+
+pub struct TrailsPlugin;
+
+impl Plugin for TrailsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DeathTrails>()
+            .add_systems(
+                Update,
+                (trail_controls, track_deaths.after(trail_controls), render_trails.after(track_deaths))
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(InGame), despawn_ghosts);
+    }
+}
+
+fn trail_controls(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
+    grid: Res<InfiniteGrid>,
+    mut trails: ResMut<DeathTrails>,
+) {
+    if keys.just_pressed(&keyboard_input, Action::Trails) {
+        trails.enabled = !trails.enabled;
+        trails.reset(grid.alive_cells().collect());
+        trails.generation = grid.generation();
+        toast(tr!("👻 Death trails {}", if trails.enabled { "on" } else { "off" }));
+    }
+}
+
+fn track_deaths(grid: Res<InfiniteGrid>, mut trails: ResMut<DeathTrails>) {
+    if !trails.enabled || grid.generation() == trails.generation {
+        return;
+    }
+    let alive = grid.alive_cells().collect();
+    if grid.generation() < trails.generation {
+        // Cleared or a session was loaded: the old trails no longer mean anything
+        trails.reset(alive);
+    } else {
+        let steps = (grid.generation() - trails.generation).min(u32::MAX as u64) as u32;
+        trails.advance(alive, steps);
+    }
+    trails.generation = grid.generation();
+}
+
+/// Keep one fading sprite per ghost in view
+fn render_trails(
+    mut commands: Commands,
+    (trails, theme, render_config): (Res<DeathTrails>, Res<Theme>, Res<CellRenderConfig>),
+    camera_query: Query<(&Transform, &GameCamera)>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    camera_state: Res<CameraState>,
+    mut existing: Query<(Entity, &TrailGhost, &mut Sprite)>,
+) {
+    let (Ok((camera_transform, camera)), Ok(window)) = (camera_query.get_single(), windows.get_single()) else { return };
+    let cell_size = camera_state.cell_size;
+    let camera_pos = camera_transform.translation.truncate();
+    let half = Vec2::new(window.width(), window.height()) / camera.zoom / 2.0;
+    let min_x = ((camera_pos.x - half.x) / cell_size).floor() as i32 - 1;
+    let max_x = ((camera_pos.x + half.x) / cell_size).ceil() as i32 + 1;
+    let min_y = ((camera_pos.y - half.y) / cell_size).floor() as i32 - 1;
+    let max_y = ((camera_pos.y + half.y) / cell_size).ceil() as i32 + 1;
+    let in_view = |(x, y): (i32, i32)| (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y);
+    let show = trails.enabled && !render_config.heatmap_active;
+    let base = theme.trail.0;
+    let base_alpha = base.alpha();
+
+    let mut shown = HashSet::new();
+    for (entity, ghost, mut sprite) in existing.iter_mut() {
+        let fade = trails.fade((ghost.x, ghost.y)).filter(|_| show && in_view((ghost.x, ghost.y)));
+        match fade {
+            Some(fade) => {
+                sprite.color = base.with_alpha(base_alpha * fade);
+                shown.insert((ghost.x, ghost.y));
+            }
+            None => commands.entity(entity).despawn(),
+        }
+    }
+    if !show {
+        return;
+    }
+
+    let new_ghosts = trails.ghosts.keys().filter(|&&pos| in_view(pos) && !shown.contains(&pos));
+    for &(x, y) in new_ghosts.take(MAX_GHOST_SPRITES.saturating_sub(shown.len())) {
+        let fade = trails.fade((x, y)).unwrap_or(0.0);
+        commands.spawn((
+            Sprite {
+                color: base.with_alpha(base_alpha * fade),
+                custom_size: Some(Vec2::splat(cell_size * 0.8)),
+                ..default()
+            },
+            Transform::from_xyz(x as f32 * cell_size, y as f32 * cell_size, GHOST_Z),
+            TrailGhost { x, y },
+        ));
+    }
+}
+
+fn despawn_ghosts(mut commands: Commands, ghosts: Query<Entity, With<TrailGhost>>, mut trails: ResMut<DeathTrails>) {
+    for entity in &ghosts {
+        commands.entity(entity).despawn();
+    }
+    trails.reset(HashSet::new());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ghosts_fade_and_clear_on_rebirth() {
+        let mut trails = DeathTrails::new(true, 3);
+        trails.reset(HashSet::from([(0, 0), (1, 0)]));
+        trails.advance(HashSet::from([(1, 0)]), 1);
+        assert_eq!(trails.fade((0, 0)), Some(1.0));
+        assert_eq!(trails.fade((1, 0)), None);
+
+        trails.advance(HashSet::new(), 1);
+        assert_eq!(trails.ghost_count(), 2);
+        assert!((trails.fade((0, 0)).unwrap() - 2.0 / 3.0).abs() < 1e-6);
+
+        // Reborn cells drop their ghost; old ones expire after `length` generations
+        trails.advance(HashSet::from([(1, 0)]), 2);
+        assert_eq!(trails.ghost_count(), 0);
+    }
+}
@@ -0,0 +1,427 @@
+//! Guided tutorial.
+//!
+//! The Tutorial button on the start screen (or T there) starts an empty game with a panel at
+//! the top of the screen walking through the basics one step at a time: placing cells,
+//! running, the speed, the camera, rules and brushes. Each step names the keys as they are
+//! currently bound, outlines the part of the HUD it is about, and moves on by itself once
+//! the player has done what it asks (or when they press Skip). Leaving the game ends it.
+
+use bevy::prelude::*;
+use bevy::ui::{RelativeCursorPosition, UiSystem};
+use crate::camera::GameCamera;
+use crate::infinite_grid::InfiniteGrid;
+use crate::keybindings::{Action, Keybindings};
+use crate::settings_menu::button_color;
+use crate::speed_slider::SpeedPanel;
+use crate::start_screen::{GameState, InGame, RuleType};
+use crate::start_seed::{SeedKind, StartSeed};
+use crate::theme::{StartScreenColors, Theme};
+use crate::tools::{ActiveTool, BrushSettings, Tool};
+use crate::ui::{toast, BrushText, HudColor, PointerCapture, RuleText, StatusText, ZoomText};
+use crate::{GameConfig, GameStats};
+use crate::tr;
+
+/// Cells to place before the first step counts as done
+const CELLS_TO_PLACE: usize = 5;
+/// World units the camera has to move, or zoom factor change, for the camera step
+const CAMERA_MOVE: f32 = 50.0;
+const CAMERA_ZOOM: f32 = 0.1;
+const HIGHLIGHT: Color = Color::srgb(1.0, 0.85, 0.3);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TutorialStep {
+    PlaceCells,
+    Run,
+    Speed,
+    Camera,
+    Rules,
+    Brushes,
+}
+
+impl TutorialStep {
+    pub const ALL: [TutorialStep; 6] = [
+        TutorialStep::PlaceCells,
+        TutorialStep::Run,
+        TutorialStep::Speed,
+        TutorialStep::Camera,
+        TutorialStep::Rules,
+        TutorialStep::Brushes,
+    ];
+
+    /// What to do, with the keys as bound now
+    pub fn instruction(self, keys: &Keybindings) -> String {
+        match self {
+            TutorialStep::PlaceCells => tr!("Click and drag on the grid to bring at least {} cells to life", CELLS_TO_PLACE),
+            TutorialStep::Run => tr!("Press {} to set them running", keys.label(Action::Pause)),
+            TutorialStep::Speed => tr!(
+                "Press {} or {}, or drag the speed slider, to change the speed",
+                keys.label(Action::SpeedUp),
+                keys.label(Action::SlowDown)
+            ),
+            TutorialStep::Camera => tr!(
+                "Move the view with {}{}{}{} and zoom with the mouse wheel",
+                keys.label(Action::PanUp),
+                keys.label(Action::PanLeft),
+                keys.label(Action::PanDown),
+                keys.label(Action::PanRight)
+            ),
+            TutorialStep::Rules => tr!("Press {} to open the rule editor and change the rule", keys.label(Action::RuleEditor)),
+            TutorialStep::Brushes => tr!(
+                "Press {} or {} to resize the brush, or {} to change tools",
+                keys.label(Action::BrushSmaller),
+                keys.label(Action::BrushLarger),
+                keys.label(Action::CycleTool)
+            ),
+        }
+    }
+
+    /// Whether the player has done what the step asks, since `start`
+    pub fn is_done(self, start: &Observed, now: &Observed) -> bool {
+        match self {
+            TutorialStep::PlaceCells => now.population >= CELLS_TO_PLACE,
+            TutorialStep::Run => now.running,
+            TutorialStep::Speed => now.interval != start.interval,
+            TutorialStep::Camera => {
+                now.camera.distance(start.camera) > CAMERA_MOVE || (now.zoom / start.zoom.max(f32::EPSILON) - 1.0).abs() > CAMERA_ZOOM
+            }
+            TutorialStep::Rules => now.rule != start.rule,
+            TutorialStep::Brushes => now.brush_size != start.brush_size || now.tool != start.tool,
+        }
+    }
+}
+
+/// What the steps look at, taken each frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Observed {
+    pub population: usize,
+    pub running: bool,
+    pub interval: f64,
+    pub camera: Vec2,
+    pub zoom: f32,
+    pub rule: RuleType,
+    pub brush_size: u32,
+    pub tool: Tool,
+}
+
+/// The step under way, if a tutorial is running, and the state it started from
+#[derive(Resource, Default)]
+pub struct Tutorial {
+    pub step: Option<usize>,
+    /// Set once the last step is done, while the closing message is up
+    pub finished: bool,
+    start: Option<Observed>,
+}
+
+impl Tutorial {
+    pub fn begin(&mut self) {
+        *self = Tutorial { step: Some(0), ..default() };
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.step.is_some()
+    }
+
+    pub fn current(&self) -> Option<TutorialStep> {
+        self.step.and_then(|index| TutorialStep::ALL.get(index).copied())
+    }
+
+    /// On to the next step, or to the closing message after the last
+    pub fn advance(&mut self) {
+        let Some(index) = self.step else { return };
+        self.start = None;
+        if index + 1 < TutorialStep::ALL.len() {
+            self.step = Some(index + 1);
+        } else {
+            self.finished = true;
+        }
+    }
+
+    pub fn end(&mut self) {
+        *self = Tutorial::default();
+    }
+}
+
+#[derive(Component)]
+struct TutorialPanel;
+
+#[derive(Component)]
+struct TutorialTitle;
+
+#[derive(Component)]
+struct TutorialText;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum TutorialButton {
+    Skip,
+    End,
+}
+
+#[derive(Component)]
+struct Highlighted;
+
+/// Start screen button starting the tutorial
+#[derive(Component)]
+struct TutorialStartButton;
+
+/// The start screen's Tutorial button, under Start Game
+pub fn spawn_tutorial_button(parent: &mut ChildBuilder, font: &Handle<Font>, palette: &StartScreenColors) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(200.0),
+                height: Val::Px(36.0),
+                margin: UiRect::top(Val::Px(10.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            BackgroundColor(palette.button.0),
+            BorderColor(palette.button_border.0),
+            TutorialStartButton,
+        ))
+        .with_child((Text::new(tr!("TUTORIAL")), TextFont { font: font.clone(), font_size: 18.0, ..default() }, TextColor(palette.accent.0)));
+}
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tutorial>()
+            .add_systems(Update, start_tutorial.run_if(in_state(GameState::StartScreen)))
+            .add_systems(OnEnter(InGame), spawn_tutorial_panel)
+            .add_systems(OnExit(InGame), end_tutorial)
+            .add_systems(PreUpdate, tutorial_capture.after(UiSystem::Focus).run_if(in_state(GameState::Playing)))
+            .add_systems(
+                Update,
+                (tutorial_buttons, follow_tutorial, update_tutorial_panel, highlight_step).chain().run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// T or the Tutorial button: an empty grid of the chosen rule, with the tutorial running
+fn start_tutorial(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<TutorialStartButton>)>,
+    mut tutorial: ResMut<Tutorial>,
+    mut start_seed: ResMut<StartSeed>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyT) && !buttons.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        return;
+    }
+    tutorial.begin();
+    start_seed.kind = SeedKind::Empty;
+    next_state.set(GameState::Playing);
+}
+
+fn spawn_tutorial_panel(mut commands: Commands, tutorial: Res<Tutorial>, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    if !tutorial.is_active() {
+        return;
+    }
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    let text = |size: f32| TextFont { font: font.clone(), font_size: size, ..default() };
+    let hud = &theme.hud;
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Percent(50.0),
+                width: Val::Px(420.0),
+                margin: UiRect::left(Val::Px(-210.0)),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(12.0)),
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            BackgroundColor(hud.panel.0),
+            BorderColor(HIGHLIGHT),
+            HudColor::Panel,
+            // Over the HUD panels, under the toasts
+            GlobalZIndex(10),
+            RelativeCursorPosition::default(),
+            TutorialPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((Text::new(""), text(16.0), TextColor(HIGHLIGHT), TutorialTitle));
+            panel.spawn((
+                Text::new(""),
+                text(18.0),
+                TextColor(hud.text.0),
+                HudColor::Text,
+                TextLayout::new_with_justify(JustifyText::Center),
+                TutorialText,
+            ));
+            panel.spawn(Node { flex_direction: FlexDirection::Row, column_gap: Val::Px(8.0), ..default() }).with_children(|row| {
+                for (button, label) in [(TutorialButton::Skip, tr!("Skip")), (TutorialButton::End, tr!("End tutorial"))] {
+                    row.spawn((Button, Node { padding: UiRect::axes(Val::Px(10.0), Val::Px(3.0)), ..default() }, BackgroundColor(button_color(Interaction::None)), button))
+                        .with_child((Text::new(label), text(14.0), TextColor(Color::WHITE)));
+                }
+            });
+        });
+}
+
+fn end_tutorial(mut commands: Commands, mut tutorial: ResMut<Tutorial>, panels: Query<Entity, With<TutorialPanel>>, highlighted: Query<Entity, With<Highlighted>>) {
+    tutorial.end();
+    for entity in &panels {
+        commands.entity(entity).despawn_recursive();
+    }
+    for entity in &highlighted {
+        commands.entity(entity).remove::<(Outline, Highlighted)>();
+    }
+}
+
+/// Keep clicks on the panel from painting the grid underneath
+fn tutorial_capture(panels: Query<&RelativeCursorPosition, With<TutorialPanel>>, mut capture: ResMut<PointerCapture>) {
+    capture.captured |= panels.iter().any(RelativeCursorPosition::mouse_over);
+}
+
+fn tutorial_buttons(
+    mut commands: Commands,
+    mut buttons: Query<(&Interaction, &TutorialButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut tutorial: ResMut<Tutorial>,
+    panels: Query<Entity, With<TutorialPanel>>,
+) {
+    for (interaction, button, mut colour) in &mut buttons {
+        colour.0 = button_color(*interaction);
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            TutorialButton::Skip if !tutorial.finished => tutorial.advance(),
+            _ => {
+                tutorial.end();
+                for entity in &panels {
+                    commands.entity(entity).despawn_recursive();
+                }
+            }
+        }
+    }
+}
+
+/// Watch for the current step being done
+fn follow_tutorial(
+    mut tutorial: ResMut<Tutorial>,
+    (grid, game_stats, game_config): (Res<InfiniteGrid>, Res<GameStats>, Res<GameConfig>),
+    (brush, active): (Res<BrushSettings>, Res<ActiveTool>),
+    cameras: Query<(&Transform, &GameCamera)>,
+) {
+    let Some(step) = tutorial.current().filter(|_| !tutorial.finished) else { return };
+    let Ok((transform, camera)) = cameras.get_single() else { return };
+    let now = Observed {
+        population: grid.population(),
+        running: !game_stats.is_paused(),
+        interval: game_stats.update_interval,
+        camera: transform.translation.truncate(),
+        zoom: camera.target_zoom,
+        rule: game_config.current_rule,
+        brush_size: brush.size,
+        tool: active.tool,
+    };
+    let Some(start) = tutorial.start else {
+        tutorial.start = Some(now);
+        return;
+    };
+    if step.is_done(&start, &now) {
+        toast(tr!("🎓 Done!"));
+        tutorial.advance();
+    }
+}
+
+fn update_tutorial_panel(
+    tutorial: Res<Tutorial>,
+    keys: Res<Keybindings>,
+    mut titles: Query<&mut Text, (With<TutorialTitle>, Without<TutorialText>)>,
+    mut texts: Query<&mut Text, (With<TutorialText>, Without<TutorialTitle>)>,
+    mut buttons: Query<(&TutorialButton, &mut Visibility)>,
+) {
+    if !tutorial.is_changed() && !keys.is_changed() {
+        return;
+    }
+    let Some(step) = tutorial.current() else { return };
+    let (title, text) = if tutorial.finished {
+        (tr!("Tutorial complete"), tr!("That's the basics! Press {} any time for every key", keys.label(Action::Help)))
+    } else {
+        let index = tutorial.step.unwrap_or(0);
+        (tr!("Tutorial {}/{}", index + 1, TutorialStep::ALL.len()), step.instruction(&keys))
+    };
+    for mut line in &mut titles {
+        line.0 = title.clone();
+    }
+    for mut line in &mut texts {
+        line.0 = text.clone();
+    }
+    // Only End is left once it is all done
+    for (button, mut visibility) in &mut buttons {
+        if *button == TutorialButton::Skip {
+            *visibility = if tutorial.finished { Visibility::Hidden } else { Visibility::Inherited };
+        }
+    }
+}
+
+/// Outline the part of the HUD the current step is about
+fn highlight_step(
+    mut commands: Commands,
+    tutorial: Res<Tutorial>,
+    highlighted: Query<Entity, With<Highlighted>>,
+    (status, speed): (Query<Entity, With<StatusText>>, Query<Entity, With<SpeedPanel>>),
+    (zoom, rule): (Query<Entity, With<ZoomText>>, Query<Entity, With<RuleText>>),
+    brush: Query<Entity, With<BrushText>>,
+) {
+    if !tutorial.is_changed() {
+        return;
+    }
+    for entity in &highlighted {
+        commands.entity(entity).remove::<(Outline, Highlighted)>();
+    }
+    let targets: Vec<Entity> = match tutorial.current().filter(|_| !tutorial.finished) {
+        Some(TutorialStep::Run) => status.iter().collect(),
+        Some(TutorialStep::Speed) => speed.iter().collect(),
+        Some(TutorialStep::Camera) => zoom.iter().collect(),
+        Some(TutorialStep::Rules) => rule.iter().collect(),
+        Some(TutorialStep::Brushes) => brush.iter().collect(),
+        Some(TutorialStep::PlaceCells) | None => Vec::new(),
+    };
+    for entity in targets {
+        commands.entity(entity).insert((Outline::new(Val::Px(2.0), Val::Px(3.0), HIGHLIGHT), Highlighted));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_finish_when_their_task_is_done_and_run_in_order() {
+        let start = Observed {
+            population: 0,
+            running: false,
+            interval: 0.2,
+            camera: Vec2::ZERO,
+            zoom: 1.0,
+            rule: RuleType::Conway,
+            brush_size: 1,
+            tool: Tool::default(),
+        };
+        for step in TutorialStep::ALL {
+            assert!(!step.is_done(&start, &start), "{:?} done before anything happened", step);
+        }
+        let now = Observed { population: 5, running: true, interval: 0.1, camera: Vec2::new(80.0, 0.0), rule: RuleType::Seeds, brush_size: 3, ..start };
+        assert!(TutorialStep::ALL.iter().all(|step| step.is_done(&start, &now)));
+
+        let mut tutorial = Tutorial::default();
+        tutorial.begin();
+        assert_eq!(tutorial.current(), Some(TutorialStep::PlaceCells));
+        for _ in 1..TutorialStep::ALL.len() {
+            tutorial.advance();
+        }
+        assert_eq!(tutorial.current(), Some(TutorialStep::Brushes));
+        assert!(!tutorial.finished);
+        tutorial.advance();
+        assert!(tutorial.finished && tutorial.is_active());
+    }
+}
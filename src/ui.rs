@@ -1,4 +1,22 @@
 use bevy::prelude::*;
+use crate::keybindings::{Action, Keybindings};
+use crate::theme::{HudColors, Theme};
+use crate::tr;
+
+pub mod brush_indicator;
+pub mod key_text;
+pub mod layout;
+pub mod scale;
+pub mod step_counts;
+pub mod toast;
+
+pub use brush_indicator::{BrushIndicatorPlugin, BrushText};
+pub use key_text::{KeyTextPlugin, KeyText};
+pub use scale::{step_scale, FontScale, UiScalingPlugin};
+pub use step_counts::{StepCountsPlugin, StepCountsText};
+pub use layout::{Corner, HudLayout, HudLayoutPlugin, HudPanel, PanelLayout};
+use layout::{spawn_hud_corners, spawn_panel_title, HudPanelBody};
+pub use toast::{toast, ToastPlugin, Toasts};
 // Removed unused import
 
 // UI Components
@@ -26,10 +44,94 @@ pub struct ZoomText;
 #[derive(Component)]
 pub struct HudContainer;
 
+/// Which theme colour a HUD element is drawn in, so it can be recoloured when the theme
+/// changes at runtime
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HudColor {
+    Panel,
+    Text,
+    Volume,
+    Zoom,
+    HelpPanel,
+    HelpText,
+}
+
+impl HudColor {
+    pub fn color(self, hud: &HudColors) -> Color {
+        match self {
+            HudColor::Panel => hud.panel.0,
+            HudColor::Text => hud.text.0,
+            HudColor::Volume => hud.volume.0,
+            HudColor::Zoom => hud.zoom.0,
+            HudColor::HelpPanel => hud.help_panel.0,
+            HudColor::HelpText => hud.help_text.0,
+        }
+    }
+}
+
 // UI marker component
 #[derive(Component)]
 pub struct UiRoot;
 
+/// A line of the help panel, by index into `help_lines`
+#[derive(Component)]
+pub struct HelpLine(pub usize);
+
+/// Set while the pointer is on a panel that handles its own clicks (such as the minimap), so
+/// the grid tools leave those clicks alone. Cleared every frame before UI focus runs.
+#[derive(Resource, Default)]
+pub struct PointerCapture {
+    pub captured: bool,
+}
+
+pub fn release_pointer_capture(mut capture: ResMut<PointerCapture>) {
+    capture.captured = false;
+}
+
+/// Seconds between HUD text refreshes unless configured otherwise
+pub const DEFAULT_HUD_REFRESH: f32 = 0.25;
+
+/// How often the HUD text (cell count, generation, births and deaths and the like) is
+/// gathered and rewritten, so the figures aren't formatted again every frame
+#[derive(Resource)]
+pub struct HudRefresh {
+    /// Seconds between refreshes; 0 refreshes every frame
+    pub interval: f32,
+    elapsed: f32,
+    due: bool,
+}
+
+impl HudRefresh {
+    pub fn new(interval: f32) -> Self {
+        // Due on the first frame, so the HUD never starts out blank
+        Self { interval: interval.max(0.0), elapsed: 0.0, due: true }
+    }
+
+    /// Whether the HUD text is refreshed this frame
+    pub fn due(&self) -> bool {
+        self.due
+    }
+
+    /// Move on by `delta` seconds
+    pub fn tick(&mut self, delta: f32) {
+        self.elapsed += delta;
+        self.due = self.elapsed >= self.interval;
+        if self.due {
+            self.elapsed = 0.0;
+        }
+    }
+}
+
+impl Default for HudRefresh {
+    fn default() -> Self {
+        Self::new(DEFAULT_HUD_REFRESH)
+    }
+}
+
+pub fn tick_hud_refresh(time: Res<Time>, mut refresh: ResMut<HudRefresh>) {
+    refresh.tick(time.delta_secs());
+}
+
 // Components for dynamic rule controls (defined in main crate)
 #[derive(Component)]
 pub struct RuleControlsContainer;
@@ -73,7 +175,87 @@ impl Default for UiState {
     }
 }
 
-pub fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// Lines of the help panel, naming the keys currently bound
+pub fn help_lines(keys: &Keybindings) -> Vec<String> {
+    let key = |action: Action| keys.label(action);
+    vec![
+        tr!("{}: All Keys", key(Action::Help)),
+        tr!("{}: Pause/Resume", key(Action::Pause)),
+        tr!("{}: Back to Menu", key(Action::Reset)),
+        tr!("{}: Clear Grid", key(Action::ClearGrid)),
+        tr!("{}/{}: Speed Control (or drag the speed slider)", key(Action::SpeedUp), key(Action::SlowDown)),
+        tr!("{}: Run N Generations and Pause (set N on the speed panel)", key(Action::RunGenerations)),
+        tr!("{}: Single Step, {}: Step Back (when paused)", key(Action::Step), key(Action::StepBack)),
+        tr!("{}: Settings (audio, theme, render, keys)", key(Action::Settings)),
+        tr!("{}/{}: Volume Control", key(Action::VolumeDown), key(Action::VolumeUp)),
+        tr!(
+            "{}-{}: Mute Drone/Samples/Groove/Effects, Shift: Solo",
+            key(Action::MuteDrone), key(Action::MuteEffects)
+        ),
+        tr!("{}: Step Sequencer (plays the view column by column)", key(Action::Sequencer)),
+        tr!(
+            "{}{}{}{}: Pan Camera",
+            key(Action::PanUp), key(Action::PanLeft), key(Action::PanDown), key(Action::PanRight)
+        ),
+        tr!("Mouse Wheel / Pinch: Zoom, Two Fingers: Pan"),
+        tr!("LMB: Toggle Cells"),
+        tr!("{}/{}: Brush Size, {}: Brush Shape", key(Action::BrushSmaller), key(Action::BrushLarger), key(Action::BrushShape)),
+        tr!("{}: Next Tool (Draw/Erase/Line/Fill/Select), or click the toolbar", key(Action::CycleTool)),
+        tr!("{0}: Symmetry Mode, Shift+{0}: Set Origin", key(Action::Symmetry)),
+        tr!("{}: Spray Brush, Shift+{}/{}: Spray Density", key(Action::Spray), key(Action::BrushSmaller), key(Action::BrushLarger)),
+        tr!("{0}: Add Layer, Shift+{0}: Remove, Alt+1-9: Show/Hide", key(Action::AddLayer)),
+        tr!("{}: Toggle HUD", key(Action::ToggleHud)),
+        tr!("{}: Quick Save, {}: Quick Load", key(Action::QuickSave), key(Action::QuickLoad)),
+        tr!("{}: Save PNG (Shift: Whole Pattern)", key(Action::Screenshot)),
+        tr!("{}: Save SVG (Shift: Whole Pattern)", key(Action::ExportSvg)),
+        tr!("{}: Record GIF (Shift: Next N Generations)", key(Action::RecordGif)),
+        tr!("{}: Record Video (needs ffmpeg)", key(Action::RecordVideo)),
+        tr!("{}: Reset Camera", key(Action::ResetCamera)),
+        tr!("{}: Fit View to Pattern", key(Action::FitView)),
+        tr!(
+            "{}-{}: Pick Pattern ({}/{}: Rotate/Flip, {}: Drop)",
+            key(Action::Pattern1), key(Action::Pattern3), key(Action::StampRotate), key(Action::StampFlip), key(Action::StampDrop)
+        ),
+        tr!("{}: Pattern Browser (or drop a file on the window)", key(Action::Browser)),
+        tr!("Ctrl+{}/Ctrl+{}: Copy/Paste RLE", key(Action::Copy), key(Action::Paste)),
+        tr!("Ctrl+{}: Command Palette (every action, pattern and rule)", key(Action::CommandPalette)),
+        tr!("{}: Console (type help for commands)", key(Action::Console)),
+        tr!("{}: Performance Overlay (step time, entities, audio load, memory)", key(Action::PerfOverlay)),
+        tr!("{}: Spectrum Meter (the audio's bands and level)", key(Action::Spectrum)),
+        tr!("{}: Split Screen (same seed, next rule; Shift: change rule)", key(Action::SplitScreen)),
+        String::new(),
+        tr!("🎨 Visual Controls:"),
+        tr!("V: Toggle Color Variation"),
+        tr!("{}: Toggle Age Colors", key(Action::AgeColors)),
+        tr!("{}: Toggle Death Trails", key(Action::Trails)),
+        tr!("{}: Toggle Grid Lines (when zoomed in)", key(Action::GridLines)),
+        tr!("{0}: Next Theme, Shift+{0}: Previous", key(Action::NextTheme)),
+        tr!("{0}: Toggle Bloom, Shift+{0}: Stronger", key(Action::Bloom)),
+        tr!("{}: Toggle Minimap (click it to jump)", key(Action::Minimap)),
+        tr!("{}: Toggle Activity Heat", key(Action::ActivityHeat)),
+        tr!("{}: Render Settings", key(Action::RenderPanel)),
+        tr!("{}: Rule Editor (B/S neighbour counts)", key(Action::RuleEditor)),
+        tr!("{}: Pattern Bounds / Centroid", key(Action::PatternMarkers)),
+        tr!("{}: Population Graph (again for births/deaths)", key(Action::PopulationGraph)),
+        tr!("[/]: Noise Density"),
+    ]
+}
+
+/// Rewrite the help panel after keys are rebound
+pub fn refresh_help_text(keys: Res<Keybindings>, mut lines: Query<(&HelpLine, &mut Text)>) {
+    if !keys.is_changed() {
+        return;
+    }
+    let help = help_lines(&keys);
+    for (line, mut text) in &mut lines {
+        if let Some(control) = help.get(line.0) {
+            text.0.clone_from(control);
+        }
+    }
+}
+
+pub fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>, keys: Res<Keybindings>, theme: Res<Theme>) {
+    let hud = &theme.hud;
     // Load the custom font
     let custom_font = asset_server.load("fonts/Geo-Regular.ttf");
     
@@ -91,146 +273,180 @@ pub fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
         UiRoot,
     ))
     .with_children(|parent| {
-        // Top-left HUD panel
+        spawn_hud_corners(parent);
+
+        // Stats panel, put in its corner by `apply_hud_layout`
         parent.spawn((
             Node {
-                position_type: PositionType::Absolute,
-                left: Val::Px(10.0),
-                top: Val::Px(10.0),
                 flex_direction: FlexDirection::Column,
                 padding: UiRect::all(Val::Px(8.0)),
                 ..default()
             },
-            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            BackgroundColor(hud.panel.0),
+            HudColor::Panel,
+            HudPanel::Stats,
         ))
         .with_children(|parent| {
-            // FPS
-            parent.spawn((
-                Text::new("FPS: 60"),
-                TextFont {
-                    font: custom_font.clone(),
-                    font_size: 16.0,
-                    ..default()
-                },
-                TextColor(Color::WHITE),
-                FpsText,
-            ));
-
-            // Cell count
-            parent.spawn((
-                Text::new("Cells: 0"),
-                TextFont {
-                    font: custom_font.clone(),
-                    font_size: 16.0,
-                    ..default()
-                },
-                TextColor(Color::WHITE),
-                CellCountText,
-            ));
-
-            // Rule
-            parent.spawn((
-                Text::new("Rule: Conway"),
-                TextFont {
-                    font: custom_font.clone(),
-                    font_size: 16.0,
-                    ..default()
-                },
-                TextColor(Color::WHITE),
-                RuleText,
-            ));
-
-            // Status
-            parent.spawn((
-                Text::new("Status: Paused"),
-                TextFont {
-                    font: custom_font.clone(),
-                    font_size: 16.0,
-                    ..default()
-                },
-                TextColor(Color::WHITE),
-                StatusText,
-            ));
-
-            // Volume
-            parent.spawn((
-                Text::new("Volume: 70%"),
-                TextFont {
-                    font: custom_font.clone(),
-                    font_size: 16.0,
-                    ..default()
-                },
-                TextColor(Color::srgb(1.0, 0.8, 0.2)), // Gold color for volume
-                VolumeText,
-            ));
-
-            // Zoom level
-            parent.spawn((
-                Text::new("Zoom: 1.0x"),
-                TextFont {
-                    font: custom_font.clone(),
-                    font_size: 16.0,
-                    ..default()
-                },
-                TextColor(Color::srgb(0.7, 0.9, 1.0)), // Light blue color for zoom
-                ZoomText,
-            ));
+            spawn_panel_title(parent, &custom_font, HudPanel::Stats, hud);
+            parent.spawn((Node { flex_direction: FlexDirection::Column, ..default() }, HudPanelBody(HudPanel::Stats))).with_children(|parent| {
+                // FPS
+                parent.spawn((
+                    Text::new(tr!("FPS: {:.0}", 60)),
+                    TextFont {
+                        font: custom_font.clone(),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(hud.text.0),
+                    HudColor::Text,
+                    FpsText,
+                ));
+
+                // Cell count
+                parent.spawn((
+                    Text::new(tr!("Cells: {}", 0)),
+                    TextFont {
+                        font: custom_font.clone(),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(hud.text.0),
+                    HudColor::Text,
+                    CellCountText,
+                ));
+
+                // Births and deaths, filled in by `step_counts`
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font: custom_font.clone(),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(hud.text.0),
+                    HudColor::Text,
+                    StepCountsText,
+                ));
+
+                // Rule
+                parent.spawn((
+                    Text::new(tr!("Rule: {}", "Conway")),
+                    TextFont {
+                        font: custom_font.clone(),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(hud.text.0),
+                    HudColor::Text,
+                    RuleText,
+                ));
+
+                // Status
+                parent.spawn((
+                    Text::new(tr!("Paused")),
+                    TextFont {
+                        font: custom_font.clone(),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(hud.text.0),
+                    HudColor::Text,
+                    StatusText,
+                ));
+
+                // Volume
+                parent.spawn((
+                    Text::new(tr!("🔊 Volume: {}%", 70)),
+                    TextFont {
+                        font: custom_font.clone(),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(hud.volume.0),
+                    HudColor::Volume,
+                    VolumeText,
+                ));
+
+                // Musical key, filled in by `key_text`
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font: custom_font.clone(),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(hud.text.0),
+                    HudColor::Text,
+                    KeyText,
+                ));
+
+                // Zoom level
+                parent.spawn((
+                    Text::new(tr!("🔍 Zoom: {:.1}x", 1.0)),
+                    TextFont {
+                        font: custom_font.clone(),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(hud.zoom.0),
+                    HudColor::Zoom,
+                    ZoomText,
+                ));
+
+                // Brush, filled in by `brush_indicator`
+                parent.spawn((
+                    Text::new(""),
+                    TextFont {
+                        font: custom_font.clone(),
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(hud.text.0),
+                    HudColor::Text,
+                    BrushText,
+                ));
+            });
         });
 
-        // Bottom-right help panel
+        // Controls help panel
         parent.spawn((
             Node {
-                position_type: PositionType::Absolute,
-                right: Val::Px(10.0),
-                bottom: Val::Px(10.0),
                 flex_direction: FlexDirection::Column,
                 padding: UiRect::all(Val::Px(8.0)),
-                align_items: AlignItems::FlexEnd,
                 ..default()
             },
-            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+            BackgroundColor(hud.help_panel.0),
+            HudColor::HelpPanel,
+            HudPanel::Help,
         ))
         .with_children(|parent| {
-            let controls = [
-                "Space: Pause/Resume",
-                "R: Back to Menu",
-                "C: Clear Grid",
-                "+/-: Speed Control",
-                "</>/: Volume Control",
-                "WASD: Pan Camera",
-                "Mouse Wheel: Zoom",
-                "LMB: Toggle Cells",
-                "H: Toggle HUD",
-                "Home: Reset Camera",
-                "",
-                "🎨 Visual Controls:",
-                "V: Toggle Color Variation",
-                "G: Toggle Generation Colors", 
-                "[/]: Noise Density",
-            ];
-
-            for control in controls.iter() {
+            spawn_panel_title(parent, &custom_font, HudPanel::Help, hud);
+            parent.spawn((Node { flex_direction: FlexDirection::Column, ..default() }, HudPanelBody(HudPanel::Help))).with_children(|parent| {
+                for (index, control) in help_lines(&keys).into_iter().enumerate() {
+                    parent.spawn((
+                        Text::new(control),
+                        TextFont {
+                            font: custom_font.clone(),
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(hud.help_text.0),
+                        HudColor::HelpText,
+                        HelpLine(index),
+                    ));
+                }
+
+                // Empty container for dynamic rule-specific controls
                 parent.spawn((
-                    Text::new(*control),
-                    TextFont {
-                        font: custom_font.clone(),
-                        font_size: 14.0,
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        margin: UiRect::top(Val::Px(8.0)),
+                        align_items: AlignItems::FlexEnd,
                         ..default()
                     },
-                    TextColor(Color::srgba(0.8, 0.8, 0.8, 0.9)),
+                    RuleControlsContainer,
                 ));
-            }
-
-            // Empty container for dynamic rule-specific controls
-            parent.spawn((
-                Node {
-                    flex_direction: FlexDirection::Column,
-                    margin: UiRect::top(Val::Px(8.0)),
-                    align_items: AlignItems::FlexEnd,
-                    ..default()
-                },
-                RuleControlsContainer,
-            ));
+            });
         });
     });
 }
@@ -243,58 +459,63 @@ pub fn update_ui(
     mut volume_query: Query<&mut Text, (With<VolumeText>, Without<FpsText>, Without<CellCountText>, Without<RuleText>, Without<StatusText>, Without<ZoomText>)>,
     mut zoom_query: Query<&mut Text, (With<ZoomText>, Without<FpsText>, Without<CellCountText>, Without<RuleText>, Without<StatusText>, Without<VolumeText>)>,
     mut ui_state: ResMut<UiState>,
-    time: Res<Time>,
+    (time, refresh): (Res<Time>, Res<HudRefresh>),
 ) {
     // Update FPS timer
     ui_state.fps_update_timer += time.delta_secs_f64();
     
-    // Update FPS only every 2 seconds
-    if ui_state.fps_update_timer >= 2.0 {
+    // Update FPS only every 2 seconds, or the HUD refresh interval if that is longer
+    if ui_state.fps_update_timer >= f64::from(refresh.interval).max(2.0) {
         if let Ok(mut text) = fps_query.get_single_mut() {
-            **text = format!("FPS: {:.0}", ui_state.fps);
+            **text = tr!("FPS: {:.0}", ui_state.fps);
         }
         ui_state.fps_update_timer = 0.0; // Reset timer
         ui_state.last_fps_update = time.elapsed_secs_f64();
     }
 
+    if !refresh.due() {
+        return;
+    }
+
     // Update cell count
     if let Ok(mut text) = cell_count_query.get_single_mut() {
-        **text = format!("Cells: {}", ui_state.population);
+        **text = tr!("Cells: {}", ui_state.population);
     }
 
     // Update rule
     if let Ok(mut text) = rule_query.get_single_mut() {
-        **text = format!("Rule: {}", ui_state.current_rule);
+        **text = tr!("Rule: {}", ui_state.current_rule);
     }
 
     // Update status
     if let Ok(mut text) = status_query.get_single_mut() {
-        let status = if ui_state.is_running { "Running" } else { "Paused" };
-        **text = format!("Gen: {} | {} ({:.2}s)", ui_state.generation, status, ui_state.update_interval);
+        let status = if ui_state.is_running { tr!("Running") } else { tr!("Paused") };
+        **text = tr!("Gen: {} | {} ({:.2}s)", ui_state.generation, status, ui_state.update_interval);
     }
 
     // Update volume
     if let Ok(mut text) = volume_query.get_single_mut() {
         let volume_percent = (ui_state.audio_volume * 100.0) as u32;
         if ui_state.audio_volume > 1.0 {
-            **text = format!("🔊🔥 Volume: {}% OVERDRIVE!", volume_percent);
+            **text = tr!("🔊🔥 Volume: {}% OVERDRIVE!", volume_percent);
         } else {
-            **text = format!("🔊 Volume: {}%", volume_percent);
+            **text = tr!("🔊 Volume: {}%", volume_percent);
         }
     }
 
     // Update zoom level
     if let Ok(mut text) = zoom_query.get_single_mut() {
-        **text = format!("🔍 Zoom: {:.1}x", ui_state.zoom_level);
+        **text = tr!("🔍 Zoom: {:.1}x", ui_state.zoom_level);
     }
 }
 
 pub fn toggle_hud_visibility(
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    keys: Res<Keybindings>,
     mut ui_state: ResMut<UiState>,
     mut hud_query: Query<&mut Visibility, With<HudContainer>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::KeyH) {
+    if keys.just_pressed(&keyboard_input, Action::ToggleHud) {
         ui_state.hud_visible = !ui_state.hud_visible;
         
         for mut visibility in hud_query.iter_mut() {
@@ -305,4 +526,42 @@ pub fn toggle_hud_visibility(
             };
         }
     }
-} 
\ No newline at end of file
+} 
+
+/// Recolour the HUD after the theme changes at runtime
+pub fn apply_hud_theme(
+    theme: Res<Theme>,
+    mut backgrounds: Query<(&HudColor, &mut BackgroundColor)>,
+    mut texts: Query<(&HudColor, &mut TextColor)>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+    for (role, mut background) in backgrounds.iter_mut() {
+        background.0 = role.color(&theme.hud);
+    }
+    for (role, mut colour) in texts.iter_mut() {
+        colour.0 = role.color(&theme.hud);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hud_refresh_is_due_once_per_interval() {
+        let mut refresh = HudRefresh::new(0.25);
+        assert!(refresh.due());
+        let due: Vec<bool> = (0..6)
+            .map(|_| {
+                refresh.tick(0.1);
+                refresh.due()
+            })
+            .collect();
+        assert_eq!(due, [false, false, true, false, false, true]);
+        let mut every_frame = HudRefresh::new(0.0);
+        every_frame.tick(0.016);
+        assert!(every_frame.due());
+    }
+}
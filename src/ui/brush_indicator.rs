@@ -0,0 +1,132 @@
+//! Brush indicator.
+//!
+//! The stats panel has a line with the brush size and shape and the active tool. For a
+//! moment after the size, shape or spray changes, a small tag next to the cursor says what
+//! the brush is now, so resizing with [ and ] shows up where the eye already is.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use crate::start_screen::GameState;
+use crate::theme::Theme;
+use crate::tools::{ActiveTool, BrushSettings};
+use crate::tr;
+use super::{HudColor, PointerCapture};
+
+/// Seconds the cursor tag stays up after a change
+const TAG_SECONDS: f32 = 1.5;
+/// Logical pixels between the cursor and the tag
+const TAG_OFFSET: f32 = 18.0;
+
+/// The brush line of the stats panel
+#[derive(Component)]
+pub struct BrushText;
+
+#[derive(Component)]
+struct BrushTag;
+
+/// Size and shape of `brush`, with the spray density when it is on
+pub fn brush_summary(brush: &BrushSettings) -> String {
+    if brush.spray {
+        tr!("{} {}, spray {:.0}%", brush.size, brush.shape.name(), brush.density * 100.0)
+    } else {
+        format!("{} {}", brush.size, brush.shape.name())
+    }
+}
+
+pub struct BrushIndicatorPlugin;
+
+impl Plugin for BrushIndicatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_brush_tag)
+            .add_systems(Update, update_brush_indicator.run_if(in_state(GameState::Playing)))
+            .add_systems(OnExit(GameState::Playing), hide_brush_tag);
+    }
+}
+
+fn spawn_brush_tag(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    commands
+        .spawn((
+            Node { position_type: PositionType::Absolute, padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)), ..default() },
+            BackgroundColor(theme.hud.panel.0),
+            HudColor::Panel,
+            // Over the HUD panels, under the toasts
+            GlobalZIndex(10),
+            Visibility::Hidden,
+            BrushTag,
+        ))
+        .with_child((
+            Text::new(""),
+            TextFont { font: asset_server.load("fonts/Geo-Regular.ttf"), font_size: 14.0, ..default() },
+            TextColor(theme.hud.text.0),
+            HudColor::Text,
+        ));
+}
+
+fn update_brush_indicator(
+    (brush, active, capture): (Res<BrushSettings>, Res<ActiveTool>, Res<PointerCapture>),
+    (time, ui_scale, mut shown_for, mut last): (Res<Time>, Res<UiScale>, Local<f32>, Local<String>),
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut lines: Query<&mut Text, With<BrushText>>,
+    mut tags: Query<(&mut Node, &mut Visibility, &Children), With<BrushTag>>,
+    mut tag_texts: Query<&mut Text, Without<BrushText>>,
+) {
+    let summary = brush_summary(&brush);
+    // Painting moves the stroke fields too; only a different summary brings the tag up
+    if summary != *last {
+        // Not on the first frame of a game, when nothing was pressed
+        if !last.is_empty() {
+            *shown_for = TAG_SECONDS;
+        }
+        let line = tr!("🖌️ Brush: {} ({})", summary, active.tool.name());
+        for mut text in &mut lines {
+            text.0 = line.clone();
+        }
+        *last = summary;
+    } else if active.is_changed() {
+        for mut text in &mut lines {
+            text.0 = tr!("🖌️ Brush: {} ({})", last.as_str(), active.tool.name());
+        }
+    }
+    *shown_for = (*shown_for - time.delta_secs()).max(0.0);
+
+    let cursor = windows.get_single().ok().and_then(Window::cursor_position);
+    for (mut node, mut visibility, children) in &mut tags {
+        let Some(cursor) = cursor.filter(|_| *shown_for > 0.0 && !capture.captured) else {
+            visibility.set_if_neq(Visibility::Hidden);
+            continue;
+        };
+        // UI positions are in logical pixels divided by the interface scale
+        let at = cursor / ui_scale.0 + Vec2::splat(TAG_OFFSET);
+        node.left = Val::Px(at.x);
+        node.top = Val::Px(at.y);
+        visibility.set_if_neq(Visibility::Visible);
+        for &child in children {
+            if let Ok(mut text) = tag_texts.get_mut(child) {
+                if text.0 != *last {
+                    text.0 = last.clone();
+                }
+            }
+        }
+    }
+}
+
+fn hide_brush_tag(mut tags: Query<&mut Visibility, With<BrushTag>>) {
+    for mut visibility in &mut tags {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::BrushShape;
+
+    #[test]
+    fn summary_gives_size_shape_and_spray_density() {
+        let mut brush = BrushSettings { size: 5, shape: BrushShape::Circle, ..default() };
+        assert_eq!(brush_summary(&brush), "5 Circle");
+        brush.spray = true;
+        brush.density = 0.25;
+        assert_eq!(brush_summary(&brush), "5 Circle, spray 25%");
+    }
+}
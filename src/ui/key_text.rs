@@ -0,0 +1,65 @@
+//! Musical key line.
+//!
+//! Under the volume, the stats panel names the key the hybrid engine is playing in, marked
+//! "auto" while the pattern chooses it and "fixed" once the player has pinned the root and
+//! mode from the synth panel or the config.
+
+use bevy::prelude::*;
+use crate::audio::{get_scale_key, key_name, KeySelection};
+use crate::start_screen::GameState;
+use crate::synth_ui::SynthParameters;
+use crate::tr;
+use super::HudRefresh;
+
+/// The key line of the stats panel
+#[derive(Component)]
+pub struct KeyText;
+
+/// The key line for a scale root and mode, or a dash while the engine is silent
+pub fn key_summary(key: Option<(f32, crate::audio::Mode)>, selection: &KeySelection) -> String {
+    let Some((root_hz, mode)) = key else { return tr!("🎼 Key: {}", "-") };
+    let how = match (selection.root, selection.mode) {
+        (None, None) => tr!("auto"),
+        (Some(_), Some(_)) => tr!("fixed"),
+        _ => tr!("partly fixed"),
+    };
+    tr!("🎼 Key: {} ({})", key_name(root_hz, mode), how)
+}
+
+pub struct KeyTextPlugin;
+
+impl Plugin for KeyTextPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_key_text.run_if(in_state(GameState::Playing)));
+    }
+}
+
+fn update_key_text(
+    (refresh, synth): (Res<HudRefresh>, Res<SynthParameters>),
+    mut lines: Query<&mut Text, With<KeyText>>,
+) {
+    if !refresh.due() {
+        return;
+    }
+    let line = key_summary(get_scale_key(), &synth.key);
+    for mut text in &mut lines {
+        if text.0 != line {
+            text.0 = line.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::Mode;
+
+    #[test]
+    fn key_line_says_whether_the_key_is_chosen_by_the_pattern() {
+        let auto = KeySelection::default();
+        assert_eq!(key_summary(None, &auto), "🎼 Key: -");
+        assert_eq!(key_summary(Some((55.0, Mode::Aeolian)), &auto), "🎼 Key: A Aeolian (auto)");
+        let fixed = KeySelection { root: Some(2), mode: Some(Mode::Dorian) };
+        assert_eq!(key_summary(Some((73.42, Mode::Dorian)), &fixed), "🎼 Key: D Dorian (fixed)");
+    }
+}
@@ -0,0 +1,275 @@
+//! HUD panel placement.
+//!
+//! Each HUD panel (the stats, the controls help, the performance overlay and the spectrum meter) has a title bar with two buttons: one
+//! moves the panel round the corners of the window, the other folds it down to its title bar.
+//! Panels sharing a corner stack up in it. The `HudLayout` is stored in the config under
+//! `[hud_layout]`, so the panels come back where they were left.
+
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+use serde::{Deserialize, Serialize};
+use crate::theme::HudColors;
+use crate::tr;
+use super::{HudColor, PointerCapture};
+
+/// Gap between a corner stack and the edges of the window, and between its panels
+const CORNER_MARGIN: f32 = 10.0;
+
+const BUTTON_IDLE: Color = Color::srgba(1.0, 1.0, 1.0, 0.08);
+const BUTTON_HOVER: Color = Color::srgba(1.0, 1.0, 1.0, 0.2);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Corner {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomRight,
+    BottomLeft,
+}
+
+impl Corner {
+    /// Clockwise from the top left
+    pub const ALL: [Corner; 4] = [Corner::TopLeft, Corner::TopRight, Corner::BottomRight, Corner::BottomLeft];
+
+    /// The next corner clockwise
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&corner| corner == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    fn is_left(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::BottomLeft)
+    }
+
+    fn is_top(self) -> bool {
+        matches!(self, Corner::TopLeft | Corner::TopRight)
+    }
+}
+
+/// Where one panel sits and whether it is folded
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PanelLayout {
+    pub corner: Corner,
+    #[serde(default)]
+    pub collapsed: bool,
+}
+
+/// A HUD panel that can be moved and folded
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HudPanel {
+    Stats,
+    Help,
+    Perf,
+    Spectrum,
+}
+
+impl HudPanel {
+    /// Stacking order within a corner
+    pub const ALL: [HudPanel; 4] = [HudPanel::Stats, HudPanel::Perf, HudPanel::Spectrum, HudPanel::Help];
+
+    pub fn title(self) -> String {
+        match self {
+            HudPanel::Stats => tr!("Stats"),
+            HudPanel::Help => tr!("Controls"),
+            HudPanel::Perf => tr!("Performance"),
+            HudPanel::Spectrum => tr!("Spectrum"),
+        }
+    }
+}
+
+/// Placement of every HUD panel
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HudLayout {
+    #[serde(default)]
+    pub stats: PanelLayout,
+    #[serde(default = "default_help_layout")]
+    pub help: PanelLayout,
+    #[serde(default)]
+    pub perf: PanelLayout,
+    #[serde(default)]
+    pub spectrum: PanelLayout,
+}
+
+fn default_help_layout() -> PanelLayout {
+    PanelLayout { corner: Corner::BottomRight, collapsed: false }
+}
+
+impl Default for HudLayout {
+    fn default() -> Self {
+        Self {
+            stats: PanelLayout::default(),
+            help: default_help_layout(),
+            perf: PanelLayout::default(),
+            spectrum: PanelLayout::default(),
+        }
+    }
+}
+
+impl HudLayout {
+    pub fn panel(&self, panel: HudPanel) -> PanelLayout {
+        match panel {
+            HudPanel::Stats => self.stats,
+            HudPanel::Help => self.help,
+            HudPanel::Perf => self.perf,
+            HudPanel::Spectrum => self.spectrum,
+        }
+    }
+
+    pub fn panel_mut(&mut self, panel: HudPanel) -> &mut PanelLayout {
+        match panel {
+            HudPanel::Stats => &mut self.stats,
+            HudPanel::Help => &mut self.help,
+            HudPanel::Perf => &mut self.perf,
+            HudPanel::Spectrum => &mut self.spectrum,
+        }
+    }
+}
+
+/// The stack of panels in one corner of the window
+#[derive(Component)]
+pub struct HudCorner(Corner);
+
+/// The part of a panel hidden when it is folded
+#[derive(Component)]
+pub struct HudPanelBody(pub HudPanel);
+
+#[derive(Component, Clone, Copy)]
+enum PanelButton {
+    Move(HudPanel),
+    Collapse(HudPanel),
+}
+
+pub struct HudLayoutPlugin;
+
+impl Plugin for HudLayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HudLayout>()
+            // Before Update, so a click on a title bar never reaches the painting tools
+            .add_systems(PreUpdate, panel_button_capture.after(UiSystem::Focus))
+            .add_systems(Update, (panel_buttons, apply_hud_layout).chain());
+    }
+}
+
+/// Spawn the four corner stacks the panels are moved between
+pub fn spawn_hud_corners(parent: &mut ChildBuilder) {
+    for corner in Corner::ALL {
+        let edge = |near: bool| if near { Val::Px(CORNER_MARGIN) } else { Val::Auto };
+        parent.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: edge(corner.is_left()),
+                right: edge(!corner.is_left()),
+                top: edge(corner.is_top()),
+                bottom: edge(!corner.is_top()),
+                flex_direction: FlexDirection::Column,
+                align_items: if corner.is_left() { AlignItems::FlexStart } else { AlignItems::FlexEnd },
+                row_gap: Val::Px(CORNER_MARGIN),
+                ..default()
+            },
+            HudCorner(corner),
+        ));
+    }
+}
+
+/// Spawn `panel`'s title bar with its move and fold buttons
+pub fn spawn_panel_title(parent: &mut ChildBuilder, font: &Handle<Font>, panel: HudPanel, hud: &HudColors) {
+    let text = TextFont { font: font.clone(), font_size: 12.0, ..default() };
+    parent
+        .spawn(Node { flex_direction: FlexDirection::Row, column_gap: Val::Px(6.0), margin: UiRect::bottom(Val::Px(4.0)), ..default() })
+        .with_children(|bar| {
+            bar.spawn((Text::new(panel.title()), text.clone(), TextColor(hud.text.0.with_alpha(0.7)), Node { flex_grow: 1.0, ..default() }));
+            for (button, label) in [(PanelButton::Move(panel), tr!("move")), (PanelButton::Collapse(panel), "-".to_string())] {
+                bar.spawn((
+                    Button,
+                    Node { padding: UiRect::axes(Val::Px(5.0), Val::Px(0.0)), ..default() },
+                    BackgroundColor(BUTTON_IDLE),
+                    button,
+                ))
+                .with_child((Text::new(label), text.clone(), TextColor(hud.text.0), HudColor::Text));
+            }
+        });
+}
+
+fn panel_button_capture(buttons: Query<&Interaction, With<PanelButton>>, mut capture: ResMut<PointerCapture>) {
+    capture.captured |= buttons.iter().any(|interaction| *interaction != Interaction::None);
+}
+
+fn panel_buttons(
+    mut buttons: Query<(&Interaction, &PanelButton, &mut BackgroundColor), Changed<Interaction>>,
+    mut layout: ResMut<HudLayout>,
+) {
+    for (interaction, button, mut colour) in &mut buttons {
+        colour.0 = if *interaction == Interaction::None { BUTTON_IDLE } else { BUTTON_HOVER };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *button {
+            PanelButton::Move(panel) => {
+                let placement = layout.panel_mut(panel);
+                placement.corner = placement.corner.next();
+            }
+            PanelButton::Collapse(panel) => {
+                let placement = layout.panel_mut(panel);
+                placement.collapsed = !placement.collapsed;
+            }
+        }
+    }
+}
+
+/// Put each panel in its corner, folded or not, when the layout changes or the HUD is spawned
+fn apply_hud_layout(
+    mut commands: Commands,
+    (layout, new_panels): (Res<HudLayout>, Query<(), Added<HudPanel>>),
+    panels: Query<(Entity, &HudPanel)>,
+    corners: Query<(Entity, &HudCorner)>,
+    mut bodies: Query<(&HudPanelBody, &mut Node)>,
+    labels: Query<(&PanelButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    if !layout.is_changed() && new_panels.is_empty() {
+        return;
+    }
+    for (corner_entity, corner) in &corners {
+        let mut stacked: Vec<_> = panels.iter().filter(|(_, panel)| layout.panel(**panel).corner == corner.0).collect();
+        stacked.sort_by_key(|(_, panel)| HudPanel::ALL.iter().position(|p| p == *panel));
+        let stacked: Vec<Entity> = stacked.into_iter().map(|(entity, _)| entity).collect();
+        commands.entity(corner_entity).add_children(&stacked);
+    }
+    for (body, mut node) in &mut bodies {
+        let placement = layout.panel(body.0);
+        node.display = if placement.collapsed { Display::None } else { Display::Flex };
+        node.align_items = if placement.corner.is_left() { AlignItems::FlexStart } else { AlignItems::FlexEnd };
+    }
+    for (button, children) in &labels {
+        let PanelButton::Collapse(panel) = *button else { continue };
+        let label = if layout.panel(panel).collapsed { "+" } else { "-" };
+        for &child in children {
+            if let Ok(mut text) = texts.get_mut(child) {
+                text.0 = label.to_string();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corners_cycle_and_layout_round_trips_through_toml() {
+        let mut corner = Corner::TopLeft;
+        for _ in 0..Corner::ALL.len() {
+            corner = corner.next();
+        }
+        assert_eq!(corner, Corner::TopLeft);
+
+        let mut layout = HudLayout::default();
+        layout.help.collapsed = true;
+        layout.stats.corner = Corner::BottomLeft;
+        let text = toml::to_string(&layout).unwrap();
+        assert!(text.contains("corner = \"bottom_left\""), "{}", text);
+        assert_eq!(toml::from_str::<HudLayout>(&text).unwrap(), layout);
+        assert_eq!(toml::from_str::<HudLayout>("").unwrap(), HudLayout::default());
+    }
+}
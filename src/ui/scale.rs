@@ -0,0 +1,73 @@
+//! Interface and text scaling.
+//!
+//! `ui_scale` in the config sets Bevy's `UiScale`, which enlarges every panel, button and
+//! line of text alike: the HUD, the start screen, the synth panel and the menus. `font_scale`
+//! enlarges only the text, on top of that, for players who want bigger letters without
+//! bigger panels. Each text entity's size as spawned is kept in `BaseFontSize` so the scale
+//! can change at runtime, from the settings screen, without drifting.
+
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+
+/// Smallest and largest scale the settings screen steps to
+pub const MIN_SCALE: f32 = 0.5;
+pub const MAX_SCALE: f32 = 3.0;
+
+/// Multiplier on every UI font size
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct FontScale(pub f32);
+
+impl Default for FontScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Font size a text entity was spawned with, before `FontScale`
+#[derive(Component)]
+pub struct BaseFontSize(f32);
+
+/// `scale` moved `steps` tenths up or down, within `MIN_SCALE..=MAX_SCALE`
+pub fn step_scale(scale: f32, steps: i32) -> f32 {
+    ((scale * 10.0).round() + steps as f32).clamp(MIN_SCALE * 10.0, MAX_SCALE * 10.0) / 10.0
+}
+
+pub struct UiScalingPlugin;
+
+impl Plugin for UiScalingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FontScale>().add_systems(PostUpdate, scale_fonts.before(UiSystem::Layout));
+    }
+}
+
+fn scale_fonts(
+    mut commands: Commands,
+    scale: Res<FontScale>,
+    mut texts: Query<(Entity, &mut TextFont, Option<&BaseFontSize>), With<Text>>,
+) {
+    for (entity, mut font, base) in &mut texts {
+        match base {
+            None => {
+                commands.entity(entity).insert(BaseFontSize(font.font_size));
+                if scale.0 != 1.0 {
+                    font.font_size *= scale.0;
+                }
+            }
+            Some(base) if scale.is_changed() => font.font_size = base.0 * scale.0,
+            Some(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_steps_by_tenths_within_limits() {
+        assert_eq!(step_scale(1.0, 1), 1.1);
+        assert_eq!(step_scale(1.25, -1), 1.2);
+        assert_eq!(step_scale(0.5, -1), MIN_SCALE);
+        assert_eq!(step_scale(2.9, 5), MAX_SCALE);
+    }
+}
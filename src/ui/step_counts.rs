@@ -0,0 +1,65 @@
+//! Births and deaths line.
+//!
+//! Under the cell count, the stats panel shows how many cells were born and how many died
+//! in the latest generation, and the net change. The counts come from the grid's step, so
+//! an oscillator that keeps its population still shows how busy it is.
+
+use bevy::prelude::*;
+use crate::infinite_grid::{InfiniteGrid, StepCounts};
+use crate::start_screen::GameState;
+use crate::tr;
+use super::HudRefresh;
+
+/// The births and deaths line of the stats panel
+#[derive(Component)]
+pub struct StepCountsText;
+
+/// Births, deaths and net change of one step
+pub fn step_summary(step: StepCounts) -> String {
+    let net = step.births as i64 - step.deaths as i64;
+    tr!("Births: +{} | Deaths: −{} | Net: {}", step.births, step.deaths, format!("{:+}", net))
+}
+
+pub struct StepCountsPlugin;
+
+impl Plugin for StepCountsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_step_counts.run_if(in_state(GameState::Playing)));
+    }
+}
+
+fn update_step_counts(
+    (grid, refresh): (Res<InfiniteGrid>, Res<HudRefresh>),
+    mut lines: Query<&mut Text, With<StepCountsText>>,
+    mut shown: Local<Option<StepCounts>>,
+) {
+    let step = grid.last_step();
+    if !refresh.due() || *shown == Some(step) {
+        return;
+    }
+    let line = step_summary(step);
+    for mut text in &mut lines {
+        text.0 = line.clone();
+    }
+    *shown = Some(step);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CellState;
+
+    #[test]
+    fn blinker_shows_its_turnover_with_no_net_change() {
+        let mut grid = InfiniteGrid::new();
+        for x in -1..=1 {
+            grid.set(x, 0, CellState::Alive);
+        }
+        assert_eq!(step_summary(grid.last_step()), "Births: +0 | Deaths: −0 | Net: +0");
+        grid.step_conway();
+        assert_eq!(step_summary(grid.last_step()), "Births: +2 | Deaths: −2 | Net: +0");
+        grid.set(5, 5, CellState::Alive);
+        grid.step_conway();
+        assert_eq!(step_summary(grid.last_step()), "Births: +2 | Deaths: −3 | Net: -1");
+    }
+}
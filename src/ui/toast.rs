@@ -0,0 +1,198 @@
+//! On-screen notifications.
+//!
+//! `toast` queues a short message (brush size, volume, rule applied, file saved, ...) to be
+//! shown at the top of the window, where it stays for a few seconds and then fades out. At
+//! most `MAX_TOASTS` are shown, oldest first out, and a message on the same topic as the
+//! newest one (the text before its `:`) replaces it, so holding a key down updates one toast
+//! rather than stacking a column of them. The queue is global, like the audio engines, so any
+//! code can post to it without the system carrying another parameter, and messages sent
+//! before the app starts (config errors) show on the first frame.
+
+use std::sync::Mutex;
+use bevy::prelude::*;
+use crate::theme::Theme;
+
+/// Seconds a toast stays up, fade included
+pub const TOAST_SECONDS: f32 = 3.0;
+/// Seconds of fading at the end
+const FADE_SECONDS: f32 = 0.8;
+/// Toasts on screen at once
+pub const MAX_TOASTS: usize = 5;
+
+static PENDING: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Show `message` on screen for a few seconds
+pub fn toast(message: impl Into<String>) {
+    if let Ok(mut pending) = PENDING.lock() {
+        pending.push(message.into());
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Toast {
+    text: String,
+    age: f32,
+}
+
+/// Toasts being shown, oldest first
+#[derive(Resource, Default, Debug)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+/// The part of a message naming what it is about ("🔊 Volume" for "🔊 Volume: 80%")
+fn topic(text: &str) -> Option<&str> {
+    text.split_once(':').map(|(topic, _)| topic)
+}
+
+impl Toasts {
+    pub fn push(&mut self, text: String) {
+        if let Some(newest) = self.toasts.last_mut() {
+            if topic(&text).is_some() && topic(&newest.text) == topic(&text) {
+                *newest = Toast { text, age: 0.0 };
+                return;
+            }
+        }
+        self.toasts.push(Toast { text, age: 0.0 });
+        if self.toasts.len() > MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+    }
+
+    /// Age the toasts by `seconds`, dropping those that have run their time
+    pub fn tick(&mut self, seconds: f32) {
+        for toast in &mut self.toasts {
+            toast.age += seconds;
+        }
+        self.toasts.retain(|toast| toast.age < TOAST_SECONDS);
+    }
+
+    /// Text and opacity of each toast, oldest first
+    pub fn shown(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.toasts.iter().map(|toast| (toast.text.as_str(), ((TOAST_SECONDS - toast.age) / FADE_SECONDS).clamp(0.0, 1.0)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.toasts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+}
+
+/// One of the `MAX_TOASTS` toast slots, top to bottom
+#[derive(Component)]
+struct ToastSlot(usize);
+
+#[derive(Component)]
+struct ToastText;
+
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Toasts>()
+            .add_systems(Startup, spawn_toast_slots)
+            .add_systems(Update, (collect_toasts, show_toasts).chain());
+    }
+}
+
+fn spawn_toast_slots(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load("fonts/Geo-Regular.ttf");
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                top: Val::Px(12.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            // Above the settings screens
+            GlobalZIndex(30),
+        ))
+        .with_children(|column| {
+            for slot in 0..MAX_TOASTS {
+                column
+                    .spawn((
+                        Node { padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)), ..default() },
+                        BackgroundColor(Color::NONE),
+                        Visibility::Hidden,
+                        ToastSlot(slot),
+                    ))
+                    .with_child((
+                        Text::new(""),
+                        TextFont { font: font.clone(), font_size: 16.0, ..default() },
+                        TextColor(Color::NONE),
+                        ToastText,
+                    ));
+            }
+        });
+}
+
+fn collect_toasts(time: Res<Time>, mut toasts: ResMut<Toasts>) {
+    let pending = PENDING.lock().map(|mut pending| std::mem::take(&mut *pending)).unwrap_or_default();
+    if pending.is_empty() && toasts.is_empty() {
+        return;
+    }
+    toasts.tick(time.delta_secs());
+    for text in pending {
+        toasts.push(text);
+    }
+}
+
+fn show_toasts(
+    toasts: Res<Toasts>,
+    theme: Res<Theme>,
+    mut slots: Query<(&ToastSlot, &Children, &mut Visibility, &mut BackgroundColor)>,
+    mut texts: Query<(&mut Text, &mut TextColor), With<ToastText>>,
+) {
+    if !toasts.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let shown: Vec<_> = toasts.shown().collect();
+    for (slot, children, mut visibility, mut background) in &mut slots {
+        let Some(&(message, opacity)) = shown.get(slot.0) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        let panel = theme.hud.panel.0;
+        background.0 = panel.with_alpha(panel.alpha() * opacity);
+        for &child in children {
+            if let Ok((mut text, mut colour)) = texts.get_mut(child) {
+                if text.0 != message {
+                    text.0 = message.to_string();
+                }
+                colour.0 = theme.hud.text.0.with_alpha(opacity);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toasts_merge_by_topic_cap_and_fade() {
+        let mut toasts = Toasts::default();
+        toasts.push("🔊 Volume: 70%".to_string());
+        toasts.push("🔊 Volume: 80%".to_string());
+        assert_eq!(toasts.shown().collect::<Vec<_>>(), vec![("🔊 Volume: 80%", 1.0)]);
+
+        for i in 0..MAX_TOASTS + 2 {
+            toasts.push(format!("Saved {}", i));
+        }
+        assert_eq!(toasts.len(), MAX_TOASTS);
+        assert_eq!(toasts.shown().next().unwrap().0, "Saved 2");
+
+        toasts.tick(TOAST_SECONDS - FADE_SECONDS / 2.0);
+        assert!(toasts.shown().all(|(_, opacity)| (opacity - 0.5).abs() < 1e-4));
+        toasts.tick(FADE_SECONDS);
+        assert!(toasts.is_empty());
+    }
+}